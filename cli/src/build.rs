@@ -1,10 +1,18 @@
-use std::path::PathBuf;
+use std::{
+    io::BufWriter,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{bail, Result};
 use clap::Args;
 use ds_rom::{
     crypto::blowfish::BlowfishKey,
-    rom::{Rom, RomLoadOptions, RomSaveError},
+    io::{is_previous_build, write_build_fingerprint, write_file_atomic_with},
+    rom::{
+        raw, BuildCache, BuildMetadata, BuildSummary, OverlayTable, ProjectManifest, Rom, RomHashReport, RomLoadOptions,
+        RomSaveError,
+    },
 };
 
 /// Builds a ROM from a path generated by `extract`
@@ -14,27 +22,215 @@ pub struct Build {
     #[arg(long, short = 'c')]
     config: PathBuf,
 
-    /// Nintendo DS ARM7 BIOS file
+    /// Nintendo DS ARM7 BIOS file, can be repeated to try the Blowfish key of each BIOS (e.g. for iQue DS titles)
     #[arg(long, short = '7')]
-    arm7_bios: Option<PathBuf>,
+    arm7_bios: Vec<PathBuf>,
 
     /// Output ROM
-    #[arg(long, short = 'o')]
-    rom: PathBuf,
+    #[arg(long, short = 'o', required_unless_present = "dry_run")]
+    rom: Option<PathBuf>,
+
+    /// Original ROM, required if files were skipped during extraction
+    #[arg(long)]
+    original_rom: Option<PathBuf>,
+
+    /// ARM9 overlay table binary to use as-is, instead of generating it from the overlay configs
+    #[arg(long)]
+    ovt_bin: Option<PathBuf>,
+
+    /// Prints the planned section offsets and ROM size without building. Programs and overlays are not compressed, so
+    /// their sizes are estimated
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Fails if the output ROM doesn't match the hashes of the original ROM, which are saved when extracting
+    #[arg(long)]
+    require_match: bool,
+
+    /// Development build: pads the ROM to the next 0x200 bytes instead of the next power of two, and keeps the original
+    /// capacity. The output won't match the original ROM, so it can't be verified
+    #[arg(long, conflicts_with = "require_match")]
+    dev_pad: bool,
+
+    /// Writes the sizes of each compressed program and overlay to a YAML file
+    #[arg(long)]
+    compression_report: Option<PathBuf>,
+
+    /// Overwrites the output ROM even if it wasn't written by a previous build, e.g. the original ROM
+    #[arg(long)]
+    output_overwrite: bool,
+
+    /// Fails if the header's version or unit code is inconsistent with the ROM contents, instead of only warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Reads asset files while writing the ROM instead of loading them all up front, which lowers memory use
+    #[arg(long)]
+    lazy_files: bool,
+
+    /// Overrides a header field without editing the header YAML, e.g. `secure_area_delay=0x51e` or `autostart=1`. Can be
+    /// repeated
+    #[arg(long, value_name = "FIELD=VALUE", value_parser = parse_header_override)]
+    set_header: Vec<(String, String)>,
+
+    /// Writes the input files and output sections of each component (ARM9, each overlay, FNT, FAT, banner and each file) to
+    /// a JSON file, so that build systems can tell which components changed since the last build
+    #[arg(long, value_name = "PATH", conflicts_with = "dry_run")]
+    emit_metadata: Option<PathBuf>,
+
+    /// Reuses the compressed ARM9 program and overlays of the previous build of the output ROM, except for the given
+    /// component (e.g. `arm9` or `overlay:12`), which is built again. Reads the metadata of the previous build from `--emit-metadata`, and builds
+    /// everything if the previous build is missing or out of date. Can be repeated
+    #[arg(long, value_name = "COMPONENT", requires = "emit_metadata", conflicts_with = "dry_run")]
+    only_refresh: Vec<String>,
+}
+
+fn parse_header_override(s: &str) -> Result<(String, String), String> {
+    let (field, value) = s.split_once('=').ok_or_else(|| format!("expected FIELD=VALUE, got '{s}'"))?;
+    Ok((field.trim().to_string(), value.trim().to_string()))
 }
 
 impl Build {
-    pub fn run(&self) -> Result<()> {
-        let key =
-            if let Some(arm7_bios) = &self.arm7_bios { Some(BlowfishKey::from_arm7_bios_path(arm7_bios)?) } else { None };
-        let rom = match Rom::load(&self.config, RomLoadOptions { key: key.as_ref(), ..Default::default() }) {
+    pub fn run(&self, threads: Option<NonZeroUsize>) -> Result<()> {
+        let keys = self.arm7_bios.iter().map(BlowfishKey::from_arm7_bios_path).collect::<Result<Vec<_>, _>>()?;
+        let original_rom = self.original_rom.as_ref().map(raw::Rom::from_file).transpose()?;
+        let build_cache = self.load_build_cache();
+        let options = RomLoadOptions {
+            keys: &keys,
+            compress: !self.dry_run,
+            encrypt: !self.dry_run,
+            original_rom: original_rom.as_ref(),
+            concurrency: threads,
+            dev_padding: self.dev_pad,
+            strict_header: self.strict,
+            lazy_files: self.lazy_files,
+            build_cache: build_cache.as_ref(),
+            ..Default::default()
+        };
+        let mut rom = match Rom::load(&self.config, options.clone()) {
             Err(RomSaveError::BlowfishKeyNeeded) => {
                 bail!("The ROM is encrypted, please provide ARM7 BIOS");
             }
             result => result?,
         };
-        let raw_rom = rom.build(key.as_ref())?;
-        raw_rom.save(&self.rom)?;
+        rom.header_mut().apply_overrides(&self.set_header)?;
+        let manifest_path = self.config.parent().unwrap_or(Path::new("")).join(ProjectManifest::FILE_NAME);
+        if !self.dry_run && manifest_path.exists() {
+            for warning in ProjectManifest::load(&manifest_path)?.check(&rom, &options) {
+                log::warn!("{warning}");
+            }
+        }
+        if let Some(compression_report) = &self.compression_report {
+            std::fs::write(compression_report, serde_yml::to_string(rom.compression_report())?)?;
+        }
+        if let Some(ovt_bin) = &self.ovt_bin {
+            let arm9_overlay_table = OverlayTable::from_raw_bytes(&std::fs::read(ovt_bin)?)?;
+            rom.set_arm9_overlay_table_override(Some(arm9_overlay_table))?;
+        }
+        if self.dry_run {
+            let layout = rom.plan_layout()?;
+            println!("{layout}");
+            return Ok(());
+        }
+        let key_refs = keys.iter().collect::<Vec<_>>();
+        let key = BlowfishKey::select(&key_refs, rom.blowfish_key_sha1());
+        let rom_path = self.rom.as_ref().unwrap();
+        let summary = self.write_rom(rom, key, rom_path)?;
+        log::info!("{summary}");
+
+        if let Some(metadata_path) = &self.emit_metadata {
+            BuildMetadata::compute(&self.config, &raw::Rom::from_file(rom_path)?)?.save(metadata_path)?;
+        }
+        if self.dev_pad {
+            println!("Skipped comparison to the original ROM, as this is a development build");
+            return Ok(());
+        }
+        let report_path = self.config.parent().unwrap_or(Path::new("")).join(RomHashReport::FILE_NAME);
+        if report_path.exists() {
+            let raw_rom = raw::Rom::from_file(rom_path)?;
+            self.compare_to_original(&raw_rom, &report_path)?;
+        } else if self.require_match {
+            bail!("Can't verify the output ROM, {} not found", report_path.display());
+        }
+        Ok(())
+    }
+
+    /// Loads the compressed ARM9 program and overlays of the previous build for `--only-refresh`. Returns `None` if there's nothing to refresh,
+    /// or with a warning if the previous build can't be reused.
+    fn load_build_cache(&self) -> Option<BuildCache> {
+        if self.only_refresh.is_empty() {
+            return None;
+        }
+        let (metadata_path, rom_path) = (self.emit_metadata.as_ref()?, self.rom.as_ref()?);
+        if !metadata_path.exists() || !rom_path.exists() {
+            log::warn!("No previous build found, building all components");
+            return None;
+        }
+        let result = BuildMetadata::load(metadata_path).map_err(anyhow::Error::from).and_then(|metadata| {
+            for name in self.only_refresh.iter().filter(|name| metadata.component(name).is_none()) {
+                log::warn!("Component '{name}' is not in the previous build");
+            }
+            let previous_rom = raw::Rom::from_file(rom_path)?;
+            Ok(BuildCache::load(&metadata, &previous_rom, &self.only_refresh)?)
+        });
+        match result {
+            Ok(cache) => {
+                let arm9 = if cache.has_arm9() { "the ARM9 program and " } else { "" };
+                log::info!("Reusing {arm9}{} compressed overlays from the previous build", cache.num_overlays());
+                Some(cache)
+            }
+            Err(err) => {
+                log::warn!("Can't reuse the previous build, building all components: {err}");
+                None
+            }
+        }
+    }
+
+    /// Streams the built ROM to a temporary file next to `path`, and replaces `path` with it if [`Self::check_overwrite`]
+    /// allows it. The ROM is synced to disk before replacing `path`, see [`write_file_atomic_with`].
+    fn write_rom(&self, rom: Rom, key: Option<&BlowfishKey>, path: &Path) -> Result<BuildSummary> {
+        let summary = write_file_atomic_with(path, |file| -> Result<_> {
+            let summary = rom.build_to_writer(BufWriter::new(file), key)?;
+            self.check_overwrite(path)?;
+            Ok(summary)
+        })?;
+        write_build_fingerprint(path)?;
+        Ok(summary)
+    }
+
+    /// Refuses to overwrite an existing file, unless `--output-overwrite` is passed or the file is unchanged since a previous
+    /// build wrote it, according to its fingerprint file.
+    fn check_overwrite(&self, path: &Path) -> Result<()> {
+        if self.output_overwrite || !path.exists() || is_previous_build(path)? {
+            return Ok(());
+        }
+        bail!(
+            "{} already exists and was not written by a previous build, pass --output-overwrite to replace it",
+            path.display()
+        );
+    }
+
+    fn compare_to_original(&self, raw_rom: &raw::Rom, report_path: &Path) -> Result<()> {
+        let original = RomHashReport::load(report_path)?;
+        let comparisons = original.compare(&RomHashReport::compute(raw_rom)?);
+
+        let mut num_mismatches = 0;
+        println!("Comparison to {}:", report_path.display());
+        for comparison in &comparisons {
+            let actual = comparison.actual.as_deref().unwrap_or("missing");
+            if comparison.matches() {
+                println!("  {:<20} : {actual}", comparison.field);
+            } else {
+                println!("  {:<20} : {actual} (expected {})", comparison.field, comparison.expected);
+                num_mismatches += 1;
+            }
+        }
+
+        let sha1_matches = comparisons.iter().find(|c| c.field == "SHA-1").is_some_and(|c| c.matches());
+        println!("Output SHA-1 matches the original: {}", if sha1_matches { "yes" } else { "no" });
+        if self.require_match && num_mismatches > 0 {
+            bail!("{num_mismatches} values differ from the original ROM");
+        }
         Ok(())
     }
 }
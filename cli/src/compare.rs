@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use ds_rom::rom::{raw, RomComparison, DEFAULT_MAX_MISMATCHES};
+
+/// Verifies a built ROM against the original, listing differences by header field, section and file
+#[derive(Args)]
+pub struct Compare {
+    /// Original ROM.
+    #[arg(long, short = 'r')]
+    original: PathBuf,
+
+    /// ROM to verify, such as one built from an extracted project.
+    #[arg(long, short = 'o')]
+    other: PathBuf,
+
+    /// Only summarizes which sections match, and lists the files which differ.
+    #[arg(long, short = 'b')]
+    brief: bool,
+
+    /// Output format.
+    #[arg(long, short = 'f', value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Maximum number of differing byte ranges to list.
+    #[arg(long, short = 'm', default_value_t = DEFAULT_MAX_MISMATCHES)]
+    max_mismatches: usize,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Human-readable text
+    Text,
+    /// JSON, for other tools
+    Json,
+}
+
+impl Compare {
+    pub fn run(&self) -> Result<()> {
+        let original = raw::Rom::from_file(&self.original)?;
+        let other = raw::Rom::from_file(&self.other)?;
+        let comparison = RomComparison::compute(&original, &other, self.max_mismatches)?;
+
+        match self.format {
+            Format::Text if self.brief => println!("{}", comparison.display_brief()),
+            Format::Text => println!("{comparison}"),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&comparison)?),
+        }
+        if !comparison.identical() {
+            bail!("The ROMs differ");
+        }
+        Ok(())
+    }
+}
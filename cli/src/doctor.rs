@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use ds_rom::rom::project;
+
+/// Checks an extracted ROM for inconsistencies between its configs and files
+#[derive(Args)]
+pub struct Doctor {
+    /// Path to config YAML
+    #[arg(long, short = 'c')]
+    config: PathBuf,
+
+    /// Repairs the issues which can be repaired safely
+    #[arg(long)]
+    fix: bool,
+}
+
+impl Doctor {
+    pub fn run(&self) -> Result<()> {
+        let issues = project::check(&self.config);
+        if issues.is_empty() {
+            println!("No problems found");
+            return Ok(());
+        }
+        for issue in &issues {
+            let fixable = if issue.is_fixable() { " (fixable)" } else { "" };
+            println!("{issue}{fixable}");
+        }
+
+        if self.fix {
+            let num_fixed = project::fix(&self.config, &issues)?;
+            println!("Fixed {num_fixed} of {} issues", issues.len());
+        }
+        Ok(())
+    }
+}
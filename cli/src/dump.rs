@@ -1,14 +1,20 @@
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{bail, Result};
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use ds_rom::{
     compress::lz77::Lz77,
-    crypto::blowfish::BlowfishKey,
-    rom::{self, raw, Arm9, Logo, Overlay},
+    crypto::{blowfish::BlowfishKey, hmac_sha1::HmacSha1},
+    rom::{
+        self, raw, Arm9, CompressionFormat, CtorLayout, DataWindow, FileSource, Logo, Overlay, OverlayTable,
+        OverlayTableError, Processor, Rom, RomDiff, RomLoadOptions,
+    },
 };
 
-use crate::print_hex;
+use crate::{extract::parse_compression_format, hex_string, parse_hex_bytes, parse_signature, print_hex, print_hex_annotated};
 
 /// Prints information about a ROM
 #[derive(Args)]
@@ -17,9 +23,9 @@ pub struct Dump {
     #[arg(long, short = 'r')]
     rom: PathBuf,
 
-    /// Nintendo DS ARM7 BIOS file
+    /// Nintendo DS ARM7 BIOS file, can be repeated to try the Blowfish key of each BIOS (e.g. for iQue DS titles)
     #[arg(long, short = '7')]
-    arm7_bios: Option<PathBuf>,
+    arm7_bios: Vec<PathBuf>,
 
     /// Encrypts the secure area.
     #[arg(long, short = 'e')]
@@ -39,19 +45,22 @@ pub struct Dump {
 
 impl Dump {
     pub fn run(&self) -> Result<()> {
-        let key =
-            if let Some(arm7_bios) = &self.arm7_bios { Some(BlowfishKey::from_arm7_bios_path(arm7_bios)?) } else { None };
+        let keys = self.arm7_bios.iter().map(BlowfishKey::from_arm7_bios_path).collect::<Result<Vec<_>, _>>()?;
+        let keys = keys.iter().collect::<Vec<_>>();
 
         let rom = raw::Rom::from_file(self.rom.clone())?;
         let header = rom.header()?;
         let mut arm9 = rom.arm9()?;
-        if arm9.is_encrypted() && key.is_some() {
-            let Some(key) = &key else { unreachable!() };
-            arm9.decrypt(&key, header.gamecode.to_le_u32())?;
+        let mut key = keys.first().copied();
+        if arm9.is_encrypted() && !keys.is_empty() {
+            if let Some(index) = arm9.decrypt_with_any_key(&keys, header.gamecode.to_le_u32())? {
+                key = Some(keys[index]);
+            }
         }
-        if self.encrypt && !arm9.is_encrypted() && key.is_some() {
-            let Some(key) = &key else { unreachable!() };
-            arm9.encrypt(&key, header.gamecode.to_le_u32())?;
+        if self.encrypt && !arm9.is_encrypted() {
+            if let Some(key) = key {
+                arm9.encrypt(key, header.gamecode.to_le_u32())?;
+            }
         }
         if self.decompress && arm9.build_info()?.is_compressed() {
             arm9.decompress()?;
@@ -64,8 +73,10 @@ impl Dump {
             DumpCommand::Header(dump_header) => dump_header.run(&rom),
             DumpCommand::Arm9(dump_arm9) => dump_arm9.run(&arm9),
             DumpCommand::BuildInfo(dump_build_info) => dump_build_info.run(&arm9),
+            DumpCommand::Arm9Footer(dump_arm9_footer) => dump_arm9_footer.run(&rom),
             DumpCommand::Arm7(dump_arm7) => dump_arm7.run(&rom),
             DumpCommand::Arm9OverlayTable(dump_arm9_overlay_table) => dump_arm9_overlay_table.run(&rom),
+            DumpCommand::Arm9OverlaySignatures(dump_arm9_overlay_signatures) => dump_arm9_overlay_signatures.run(&rom),
             DumpCommand::Arm7OverlayTable(dump_arm7_overlay_table) => dump_arm7_overlay_table.run(&rom),
             DumpCommand::AutoloadInfo(dump_autoload_info) => dump_autoload_info.run(&mut arm9),
             DumpCommand::Autoload(dump_autoload) => dump_autoload.run(&mut arm9),
@@ -73,6 +84,12 @@ impl Dump {
             DumpCommand::Banner(dump_banner) => dump_banner.run(&rom),
             DumpCommand::Arm9Overlay(dump_arm9_overlay) => dump_arm9_overlay.run(&rom, self.decompress, self.compress),
             DumpCommand::Arm7Overlay(dump_arm7_overlay) => dump_arm7_overlay.run(&rom),
+            DumpCommand::File(dump_file) => dump_file.run(&rom),
+            DumpCommand::Layout(dump_layout) => dump_layout.run(&rom),
+            DumpCommand::Locate(dump_locate) => dump_locate.run(&rom),
+            DumpCommand::Diff(dump_diff) => dump_diff.run(&rom),
+            DumpCommand::MemoryUsage(dump_memory_usage) => dump_memory_usage.run(&rom),
+            DumpCommand::Duplicates(dump_duplicates) => dump_duplicates.run(&rom),
         }
     }
 }
@@ -83,9 +100,13 @@ enum DumpCommand {
     Arm9(DumpArm9),
     #[command(name = "build-info")]
     BuildInfo(DumpBuildInfo),
+    #[command(name = "arm9-footer")]
+    Arm9Footer(DumpArm9Footer),
     Arm7(DumpArm7),
     #[command(name = "arm9-ovt")]
     Arm9OverlayTable(DumpArm9OverlayTable),
+    #[command(name = "arm9-ov-sigs")]
+    Arm9OverlaySignatures(DumpArm9OverlaySignatures),
     #[command(name = "arm7-ovt")]
     Arm7OverlayTable(DumpArm7OverlayTable),
     #[command(name = "autoload-info")]
@@ -97,6 +118,13 @@ enum DumpCommand {
     Arm9Overlay(DumpArm9Overlay),
     #[command(name = "arm7-ov")]
     Arm7Overlay(DumpArm7Overlay),
+    File(DumpFile),
+    Layout(DumpLayout),
+    Locate(DumpLocate),
+    Diff(DumpDiff),
+    #[command(name = "memory-usage")]
+    MemoryUsage(DumpMemoryUsage),
+    Duplicates(DumpDuplicates),
 }
 
 /// Shows the contents of the ROM header.
@@ -105,10 +133,18 @@ struct DumpHeader {
     /// Changes the header logo to this PNG.
     #[arg(long, short = 'l')]
     header_logo: Option<PathBuf>,
+
+    /// Compares the header to this extracted header YAML instead of showing its contents.
+    #[arg(long, visible_alias = "config")]
+    compare_to_config: Option<PathBuf>,
 }
 
 impl DumpHeader {
     pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        if let Some(config) = &self.compare_to_config {
+            return self.compare(rom, config);
+        }
+
         let mut header = rom.header()?.clone();
 
         if let Some(header_logo) = &self.header_logo {
@@ -120,6 +156,34 @@ impl DumpHeader {
 
         Ok(())
     }
+
+    fn compare(&self, rom: &raw::Rom, config: &Path) -> Result<()> {
+        let header: rom::Header = serde_yml::from_reader(File::open(config)?)?;
+        let comparisons = header.compare_to_raw(rom.header()?);
+
+        let mut num_mismatches = 0;
+        println!("Fields from {}:", config.display());
+        for comparison in comparisons.iter().filter(|c| !c.is_derived()) {
+            let expected = comparison.expected.as_deref().unwrap_or_default();
+            if comparison.matches() {
+                println!("  {:<24} : {}", comparison.field, comparison.actual);
+            } else {
+                println!("  {:<24} : {} (expected {expected})", comparison.field, comparison.actual);
+                num_mismatches += 1;
+            }
+        }
+        println!("Fields derived at build time:");
+        for comparison in comparisons.iter().filter(|c| c.is_derived()) {
+            println!("  {:<24} : {}", comparison.field, comparison.actual);
+        }
+
+        if num_mismatches == 0 {
+            println!("All fields from the config match");
+        } else {
+            println!("{num_mismatches} fields from the config differ");
+        }
+        Ok(())
+    }
 }
 
 /// Prints the contents of the ARM9 program.
@@ -136,6 +200,13 @@ struct DumpArm9 {
     /// Prints contents as raw bytes.
     #[arg(long, short = 'R')]
     raw: bool,
+
+    /// Interleaves markers into the hexdump at the boundaries of the code, autoload blocks, autoload infos and build info.
+    #[arg(long, short = 'a', conflicts_with = "raw")]
+    annotate: bool,
+
+    #[command(flatten)]
+    window: HexWindow,
 }
 
 impl DumpArm9 {
@@ -154,7 +225,17 @@ impl DumpArm9 {
         }
 
         if !self.compare_lz77 && !self.show_lz77_tokens {
-            print_hex(arm9.as_ref(), self.raw, arm9.base_address())?;
+            if self.annotate && arm9.is_compressed()? {
+                println!("Note: ARM9 program is compressed, use -d to decompress it before annotating");
+                self.window.print(arm9.as_ref(), self.raw, arm9.base_address())?;
+            } else if self.annotate {
+                let (data, address) = self.window.slice(arm9.as_ref(), arm9.base_address())?;
+                let end = address + data.len() as u32;
+                let markers = arm9.markers()?.into_iter().filter(|marker| (address..=end).contains(&marker.address));
+                print_hex_annotated(data, address, &markers.collect::<Vec<_>>());
+            } else {
+                self.window.print(arm9.as_ref(), self.raw, arm9.base_address())?;
+            }
         }
 
         Ok(())
@@ -180,12 +261,15 @@ struct DumpArm7 {
     /// Prints contents as raw bytes.
     #[arg(long, short = 'R')]
     raw: bool,
+
+    #[command(flatten)]
+    window: HexWindow,
 }
 
 impl DumpArm7 {
     pub fn run(&self, rom: &raw::Rom) -> Result<()> {
         let arm7 = rom.arm7()?;
-        print_hex(arm7.full_data(), self.raw, arm7.base_address())?;
+        self.window.print(arm7.full_data(), self.raw, arm7.base_address())?;
 
         Ok(())
     }
@@ -193,18 +277,82 @@ impl DumpArm7 {
 
 /// Prints the contents of the ARM9 overlay table.
 #[derive(Args)]
-struct DumpArm9OverlayTable {}
+struct DumpArm9OverlayTable {
+    /// Prints the table as raw bytes.
+    #[arg(long, short = 'R')]
+    raw: bool,
+
+    /// Writes the raw table to this file instead of printing it.
+    #[arg(long, short = 'o', requires = "raw")]
+    out: Option<PathBuf>,
+}
 
 impl DumpArm9OverlayTable {
     pub fn run(&self, rom: &raw::Rom) -> Result<()> {
         let arm9_ovt = rom.arm9_overlay_table()?;
+        if self.raw {
+            let table = rom.header()?.arm9_overlays;
+            let start = table.offset as usize;
+            let data = &rom.data()[start..start + table.size as usize];
+            if let Some(out) = &self.out {
+                std::fs::write(out, data)?;
+            } else {
+                print_hex(data, true, 0)?;
+            }
+            return Ok(());
+        }
         if arm9_ovt.is_empty() {
             println!("The ROM has no ARM9 overlays");
+            return Ok(());
         }
-        for overlay in arm9_ovt {
-            println!("ARM9 Overlay:\n{}", overlay.display(2));
+        let table = OverlayTable::new(arm9_ovt.to_vec());
+        println!("ARM9 overlay table:\n{}", table.display_with_fat(rom.fat()?, 2));
+
+        Ok(())
+    }
+}
+
+/// Computes the HMAC-SHA1 signatures of the signed ARM9 overlays and of the ARM9 overlay table.
+#[derive(Args)]
+struct DumpArm9OverlaySignatures {
+    /// HMAC-SHA1 key in hexadecimal.
+    #[arg(long, short = 'k')]
+    key: String,
+
+    /// Verifies this signature of the overlay table, in hexadecimal.
+    #[arg(long, short = 'v')]
+    verify: Option<String>,
+}
+
+impl DumpArm9OverlaySignatures {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let hmac = HmacSha1::new(&parse_hex_bytes(&self.key)?);
+        let arm9_ovt = rom.arm9_overlay_table()?;
+        if arm9_ovt.is_empty() {
+            println!("The ROM has no ARM9 overlays");
+            return Ok(());
         }
 
+        let fat = rom.fat()?;
+        for overlay in arm9_ovt.iter().filter(|overlay| overlay.flags.is_signed()) {
+            let Some(alloc) = fat.get(overlay.file_id as usize) else {
+                bail!("ARM9 overlay {} has file ID {} outside the FAT", overlay.id, overlay.file_id);
+            };
+            let signature = hmac.compute(&rom.data()[alloc.range()]);
+            println!("Overlay {:<4} : {}", overlay.id, hex_string(&signature));
+        }
+
+        let table = OverlayTable::new(arm9_ovt.to_vec());
+        println!("Overlay table : {}", hex_string(&table.compute_signature(&hmac)));
+        if let Some(signature) = &self.verify {
+            match table.check_signature(&hmac, &parse_signature(signature)?) {
+                Ok(()) => println!("The overlay table signature matches"),
+                Err(OverlayTableError::SignatureMismatch { actual, .. }) => {
+                    println!("(!) The overlay table signature {actual} does not match")
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
         Ok(())
     }
 }
@@ -218,10 +366,10 @@ impl DumpArm7OverlayTable {
         let arm7_ovt = rom.arm7_overlay_table()?;
         if arm7_ovt.is_empty() {
             println!("The ROM has no ARM7 overlays");
+            return Ok(());
         }
-        for overlay in arm7_ovt {
-            println!("ARM7 Overlay:\n{}", overlay.display(2));
-        }
+        let table = OverlayTable::new(arm7_ovt.to_vec());
+        println!("ARM7 overlay table:\n{}", table.display_with_fat(rom.fat()?, 2));
 
         Ok(())
     }
@@ -252,6 +400,9 @@ struct DumpAutoload {
     /// Prints contents as raw bytes.
     #[arg(long, short = 'R')]
     raw: bool,
+
+    #[command(flatten)]
+    window: HexWindow,
 }
 
 impl DumpAutoload {
@@ -262,7 +413,7 @@ impl DumpAutoload {
             bail!("Cannot print autoload at index {}, max index is {}", self.index, autoloads.len() - 1);
         }
         let autoload = &autoloads[self.index];
-        print_hex(autoload.full_data(), self.raw, autoload.base_address())?;
+        self.window.print(autoload.full_data(), self.raw, autoload.base_address())?;
 
         Ok(())
     }
@@ -270,14 +421,45 @@ impl DumpAutoload {
 
 /// Shows the contents of the file name table.
 #[derive(Args)]
-struct DumpFnt {}
+struct DumpFnt {
+    /// Instead of listing files, reports directories which are not in canonical FNT order and whether files are stored out
+    /// of file ID order.
+    #[arg(long)]
+    verify_order: bool,
+
+    /// Instead of listing files, shows the number of files and directories, the total size and statistics about names
+    #[arg(long, conflicts_with = "verify_order")]
+    stats: bool,
+}
 
 impl DumpFnt {
     pub fn run(&self, rom: &raw::Rom) -> Result<()> {
         let fnt = rom.fnt()?;
         let fat = rom.fat()?;
         let root = rom::FileSystem::parse(&fnt, fat, &rom)?;
-        println!("Files:\n{}", root.display(2));
+        if self.stats {
+            println!("{}", root.stats());
+            return Ok(());
+        }
+        if !self.verify_order {
+            println!("Files:\n{}", root.display(2));
+            return Ok(());
+        }
+
+        let report = root.analyze_ordering();
+        if report.is_fnt_sorted() {
+            println!("FNT is sorted");
+        } else {
+            println!("FNT is not sorted in {} directories:", report.unsorted_dirs.len());
+            for dir in &report.unsorted_dirs {
+                println!("  {}: '{}' comes before '{}'", dir.path, dir.first, dir.second);
+            }
+        }
+        if report.file_image_differs {
+            println!("Files are not stored in file ID order");
+        } else {
+            println!("Files are stored in file ID order");
+        }
 
         Ok(())
     }
@@ -289,8 +471,10 @@ struct DumpBanner {}
 
 impl DumpBanner {
     pub fn run(&self, rom: &raw::Rom) -> Result<()> {
-        let banner = rom.banner()?;
-        println!("ROM banner:\n{}", banner.display(2));
+        match rom.banner()? {
+            Some(banner) => println!("ROM banner:\n{}", banner.display(2)),
+            None => println!("ROM has no banner"),
+        }
 
         Ok(())
     }
@@ -313,6 +497,34 @@ struct DumpArm9Overlay {
     /// Prints contents as raw bytes.
     #[arg(long, short = 'R')]
     raw: bool,
+
+    /// Lists the static initializers in the .ctor section, in the order they are called.
+    #[arg(long)]
+    ctors: bool,
+
+    /// Layout of the static initializer list, detected if not set.
+    #[arg(long, value_enum, requires = "ctors")]
+    ctor_layout: Option<CtorLayoutArg>,
+
+    #[command(flatten)]
+    window: HexWindow,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CtorLayoutArg {
+    /// Function pointers until the first null pointer
+    Terminated,
+    /// A count followed by that many function pointers
+    CountPrefixed,
+}
+
+impl From<CtorLayoutArg> for CtorLayout {
+    fn from(layout: CtorLayoutArg) -> Self {
+        match layout {
+            CtorLayoutArg::Terminated => Self::Terminated,
+            CtorLayoutArg::CountPrefixed => Self::CountPrefixed,
+        }
+    }
 }
 
 impl DumpArm9Overlay {
@@ -341,8 +553,22 @@ impl DumpArm9Overlay {
             println!("{tokens}");
         }
 
-        if !self.compare_lz77 && !self.show_lz77_tokens {
-            print_hex(overlay.full_data(), self.raw, overlay.base_address())?;
+        if self.ctors {
+            let mut decompressed = overlay.clone();
+            decompressed.decompress()?;
+            let layout = match self.ctor_layout {
+                Some(layout) => layout.into(),
+                None => decompressed.ctor_layout()?,
+            };
+            let ctors = decompressed.ctor_functions(Some(layout))?;
+            println!("Static initializers ({layout}, {:#x}..{:#x}):", decompressed.ctor_start(), decompressed.ctor_end());
+            for ctor in ctors {
+                println!("  {ctor}");
+            }
+        }
+
+        if !self.compare_lz77 && !self.show_lz77_tokens && !self.ctors {
+            self.window.print(overlay.full_data(), self.raw, overlay.base_address())?;
         }
 
         Ok(())
@@ -371,6 +597,238 @@ impl DumpArm7Overlay {
     }
 }
 
+/// Prints the contents of a file in the file system.
+#[derive(Args)]
+struct DumpFile {
+    /// Path to the file.
+    #[arg(long, short = 'p')]
+    path: String,
+
+    /// Parses the file as an embedded ROM, such as a DS Download Play child.
+    #[arg(long, short = 'a')]
+    as_rom: bool,
+
+    /// Shows the header of the embedded ROM.
+    #[arg(long, short = 's', requires = "as_rom")]
+    show_header: bool,
+
+    /// Decompresses the file in this format first: `lz10`, `rle`, `huffman4` or `huffman8`.
+    #[arg(long, value_parser = parse_compression_format, conflicts_with = "as_rom")]
+    decompress_as: Option<CompressionFormat>,
+
+    /// Prints contents as raw bytes.
+    #[arg(long, short = 'R')]
+    raw: bool,
+}
+
+impl DumpFile {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let fnt = rom.fnt()?;
+        let fat = rom.fat()?;
+        let files = rom::FileSystem::parse(&fnt, fat, rom)?;
+        if self.as_rom {
+            let embedded_rom = files.open_embedded_rom(&self.path)?;
+            if self.show_header {
+                let header = embedded_rom.header()?;
+                println!("Embedded ROM header:\n{}", header.display(2));
+            } else {
+                print_hex(embedded_rom.data(), self.raw, 0)?;
+            }
+            return Ok(());
+        }
+
+        let path = self.path.strip_prefix("/").unwrap_or(&self.path);
+        let id = files.resolve_path(path)?;
+        if rom::FileSystem::is_dir(id) {
+            bail!("'{}' is a directory", self.path);
+        }
+        let contents = files.file(id).contents();
+        match self.decompress_as {
+            Some(format) => print_hex(&format.decompress(contents)?, self.raw, 0)?,
+            None => print_hex(contents, self.raw, 0)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Shows the contents of the ARM9 footer.
+#[derive(Args)]
+struct DumpArm9Footer {}
+
+impl DumpArm9Footer {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let footer = rom.arm9_footer()?;
+        println!("ARM9 footer:\n{}", footer.display(2));
+        Ok(())
+    }
+}
+
+/// Shows where each section is located in the ROM.
+#[derive(Args)]
+struct DumpLayout {}
+
+impl DumpLayout {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let header = rom.header()?;
+        let fat = rom.fat()?;
+
+        let mut regions = vec![];
+        let header_end = header.version().used_size() as u32;
+        regions.push((0, header_end, "Header".to_string()));
+        if let Some(pre_arm9) = rom.pre_arm9()? {
+            regions.push((header_end, header_end + pre_arm9.len() as u32, "Pre-ARM9 data".to_string()));
+        }
+        regions.push((header.arm9.offset, header.arm9.offset + header.arm9.size, "ARM9 program".to_string()));
+        regions.push((header.arm7.offset, header.arm7.offset + header.arm7.size, "ARM7 program".to_string()));
+        for (table, name) in [
+            (header.arm9_overlays, "ARM9 overlay table"),
+            (header.arm7_overlays, "ARM7 overlay table"),
+            (header.file_names, "File name table"),
+            (header.file_allocs, "File allocation table"),
+        ] {
+            if table.size > 0 {
+                regions.push((table.offset, table.offset + table.size, name.to_string()));
+            }
+        }
+        if let Some(banner) = rom.banner()? {
+            let banner_size = banner.version().banner_size() as u32;
+            regions.push((header.banner_offset, header.banner_offset + banner_size, "Banner".to_string()));
+        }
+
+        for (processor, table) in [(Processor::Arm9, rom.arm9_overlay_table()?), (Processor::Arm7, rom.arm7_overlay_table()?)]
+        {
+            for overlay in table.iter().filter(|overlay| !overlay.is_placeholder()) {
+                let alloc = fat[overlay.file_id as usize];
+                regions.push((alloc.start, alloc.end, format!("{processor} overlay {}", overlay.id)));
+            }
+        }
+
+        let num_overlays = rom.num_arm9_overlays()? + rom.num_arm7_overlays()?;
+        let files = &fat[num_overlays.min(fat.len())..];
+        if let (Some(start), Some(end)) = (files.iter().map(|f| f.start).min(), files.iter().map(|f| f.end).max()) {
+            regions.push((start, end, format!("Files ({})", files.len())));
+        }
+        if let Some(trailing_data) = rom.trailing_data()? {
+            let end = rom.data().len() as u32;
+            let size = trailing_data.len() as u32;
+            regions.push((end - size, end, format!("Trailing data ({size:#x} bytes)")));
+        }
+
+        regions.sort_by_key(|(start, end, _)| (*start, *end));
+        println!("ROM layout:");
+        for (start, end, name) in regions {
+            println!("  {start:#010x}..{end:#010x} : {name}");
+        }
+        println!("ROM size: {:#x}", rom.data().len());
+
+        Ok(())
+    }
+}
+
+/// Shows which section or file contains a ROM offset, or where an ARM9 virtual address is stored in the ROM.
+#[derive(Args)]
+struct DumpLocate {
+    /// ROM offset, in hexadecimal if prefixed with `0x`.
+    #[arg(long, short = 'o', value_parser = parse_offset, required_unless_present = "vaddr", conflicts_with = "vaddr")]
+    offset: Option<u32>,
+
+    /// Virtual address in the ARM9 address space, such as one from a crash log, in hexadecimal if prefixed with `0x`.
+    #[arg(long, value_parser = parse_offset)]
+    vaddr: Option<u32>,
+}
+
+impl DumpLocate {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        if let Some(vaddr) = self.vaddr {
+            let locations = rom.vaddr_to_rom_offset(vaddr)?;
+            if locations.is_empty() {
+                println!("{vaddr:#x}: no ARM9 module is loaded to this address");
+            }
+            for location in locations {
+                println!("{location}");
+            }
+            return Ok(());
+        }
+
+        let offset = self.offset.unwrap();
+        println!("{offset:#x}: {}", rom.locate(offset)?);
+        if let Some(location) = rom.rom_offset_to_vaddr(offset).ok().flatten() {
+            println!("Loaded to {location}");
+        }
+        Ok(())
+    }
+}
+
+/// Compares the ROM to another ROM, section by section and file by file.
+#[derive(Args)]
+struct DumpDiff {
+    /// ROM to compare to.
+    #[arg(long, short = 'o')]
+    other: PathBuf,
+
+    /// Maximum number of differing byte ranges to list.
+    #[arg(long, short = 'm', default_value_t = 64)]
+    max_mismatches: usize,
+
+    /// Only lists sections and files which differ.
+    #[arg(long, short = 'D')]
+    differing_only: bool,
+}
+
+impl DumpDiff {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let other = raw::Rom::from_file(&self.other)?;
+        let diff = RomDiff::compute(rom, &other, self.max_mismatches)?;
+        if !self.differing_only {
+            println!("{diff}");
+            return Ok(());
+        }
+        if diff.identical {
+            println!("ROMs are identical");
+        }
+        for section in diff.differing() {
+            println!("{section}");
+        }
+        if diff.truncated {
+            println!("More mismatches were found but not listed");
+        }
+        Ok(())
+    }
+}
+
+/// Limits a hexdump to part of the module.
+#[derive(Args)]
+struct HexWindow {
+    /// Start of the hexdump, in hexadecimal if prefixed with `0x`. Either a virtual address or an offset from the start
+    /// of the module.
+    #[arg(long, value_parser = parse_offset)]
+    offset: Option<u32>,
+
+    /// Number of bytes to print, in hexadecimal if prefixed with `0x`. Defaults to the rest of the module.
+    #[arg(long, value_parser = parse_offset)]
+    length: Option<u32>,
+}
+
+impl HexWindow {
+    fn slice<'a>(&self, data: &'a [u8], base_address: u32) -> Result<(&'a [u8], u32)> {
+        Ok(DataWindow { offset: self.offset, length: self.length }.slice(data, base_address)?)
+    }
+
+    fn print(&self, data: &[u8], raw: bool, base_address: u32) -> Result<()> {
+        let (data, address) = self.slice(data, base_address)?;
+        print_hex(data, raw, address)
+    }
+}
+
+fn parse_offset(value: &str) -> Result<u32, String> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|error| format!("invalid offset '{value}': {error}"))
+}
+
 fn compare_lz77(data_before: &[u8], data_after: &[u8], start: usize, base_address: usize) {
     let before = data_before.len();
     let after = data_after.len();
@@ -397,3 +855,69 @@ fn compare_lz77(data_before: &[u8], data_after: &[u8], start: usize, base_addres
         println!("Compression matched");
     }
 }
+
+/// Shows how much memory the plain ROM uses, and how much of it is owned instead of borrowed from the raw ROM.
+#[derive(Args)]
+struct DumpMemoryUsage {
+    /// Loads the plain ROM from this extracted config YAML instead of extracting it from the ROM.
+    #[arg(long, short = 'c')]
+    config: Option<PathBuf>,
+}
+
+impl DumpMemoryUsage {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let rom = match &self.config {
+            Some(config) => Rom::load(config, RomLoadOptions { compress: false, encrypt: false, ..Default::default() })?,
+            None => Rom::extract(rom)?,
+        };
+
+        let usage = |sources: &[(FileSource, usize)]| {
+            let total = sources.iter().map(|(_, size)| size).sum::<usize>();
+            let owned = sources.iter().filter(|(source, _)| *source == FileSource::Owned).map(|(_, size)| size).sum::<usize>();
+            (total, owned)
+        };
+        let overlays = |overlays: &[Overlay]| {
+            usage(&overlays.iter().map(|overlay| (overlay.source(), overlay.full_data().len())).collect::<Vec<_>>())
+        };
+        let sections = [
+            ("ARM9 program", usage(&[(rom.arm9().source(), rom.arm9().full_data().len())])),
+            ("ARM9 overlays", overlays(rom.arm9_overlays())),
+            ("ARM7 program", usage(&[(rom.arm7().source(), rom.arm7().full_data().len())])),
+            ("ARM7 overlays", overlays(rom.arm7_overlays())),
+            ("Files", (rom.files().total_content_size(), rom.files().owned_bytes())),
+        ];
+
+        println!("Section          Total        Owned");
+        for (name, (total, owned)) in sections {
+            println!("{name:<15}  {total:<11}  {owned}");
+        }
+        let total = sections.iter().map(|(_, (total, _))| total).sum::<usize>();
+        let owned = sections.iter().map(|(_, (_, owned))| owned).sum::<usize>();
+        println!("{:<15}  {total:<11}  {owned}", "Total");
+        Ok(())
+    }
+}
+
+/// Lists files with identical contents, sorted by how many bytes are wasted on the copies.
+#[derive(Args)]
+struct DumpDuplicates {}
+
+impl DumpDuplicates {
+    pub fn run(&self, rom: &raw::Rom) -> Result<()> {
+        let fnt = rom.fnt()?;
+        let fat = rom.fat()?;
+        let files = rom::FileSystem::parse(&fnt, fat, rom)?;
+        let groups = files.duplicate_report()?;
+        if groups.is_empty() {
+            println!("No duplicate files found");
+            return Ok(());
+        }
+        for group in &groups {
+            println!("{group}");
+        }
+        let wasted_bytes = groups.iter().map(|group| group.wasted_bytes()).sum::<usize>();
+        let copies = groups.iter().map(|group| group.paths.len() - 1).sum::<usize>();
+        println!("Total: {copies} duplicate files in {} groups, {wasted_bytes:#x} bytes wasted", groups.len());
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
-use std::path::PathBuf;
+use std::{num::NonZeroUsize, path::PathBuf};
 
 use anyhow::{bail, Result};
-use clap::Args;
+use clap::{Args, ValueEnum};
 use ds_rom::{
     crypto::blowfish::BlowfishKey,
-    rom::{raw, Rom, RomSaveError},
+    rom::{
+        raw, CompressionFormat, DedupMode, DedupStore, PartialSaveError, PreservationLevel, ProjectManifest, Rom, RomConfig,
+        RomExtractOptions, RomHashReport, RomSaveError, RomSaveOptions, TransparentCompression,
+    },
 };
 
 /// Extracts a ROM to a given path
@@ -14,27 +17,139 @@ pub struct Extract {
     #[arg(long, short = 'r')]
     rom: PathBuf,
 
-    /// Nintendo DS ARM7 BIOS file
+    /// Nintendo DS ARM7 BIOS file, can be repeated to try the Blowfish key of each BIOS (e.g. for iQue DS titles)
     #[arg(long, short = '7')]
-    arm7_bios: Option<PathBuf>,
+    arm7_bios: Vec<PathBuf>,
 
     /// Output path
     #[arg(long, short = 'o')]
     path: PathBuf,
+
+    /// Skips extracting files larger than this many bytes, they will be copied from the original ROM when building
+    #[arg(long)]
+    skip_larger_than: Option<usize>,
+
+    /// Skips extracting files matching this glob pattern, they will be copied from the original ROM when building
+    #[arg(long)]
+    skip: Vec<String>,
+
+    /// Saves the exact order of every file in the ROM to file_order.txt, for games where path_order.txt can't reproduce it
+    #[arg(long)]
+    full_file_order: bool,
+
+    /// How much of the original ROM's layout to preserve
+    #[arg(long, value_enum, default_value_t = Preservation::Standard)]
+    preservation: Preservation,
+
+    /// Preserves everything needed for a byte-exact rebuild, same as `--preservation exact`
+    #[arg(long, conflicts_with = "preservation")]
+    exact: bool,
+
+    /// Saves file contents into this content-addressed store, which can be shared between extracted ROMs. The files
+    /// directory will contain references to the store
+    #[arg(long)]
+    dedup_store: Option<PathBuf>,
+
+    /// Hard links files to the dedup store instead of writing reference files
+    #[arg(long, requires = "dedup_store")]
+    hardlink: bool,
+
+    /// Saves files matching a glob pattern decompressed, and compresses them again when building. Written as
+    /// `<glob>=<format>`, where the format is `lz10`, `rle`, `huffman4` or `huffman8`
+    #[arg(long, value_parser = parse_transparent_compression)]
+    decompress: Vec<TransparentCompression>,
+
+    /// Saves each banner title to its own text file, such as banner/title_en.txt, which overrides the title in banner.yaml
+    #[arg(long)]
+    split_banner_titles: bool,
+
+    /// Leaves files which were already extracted with the same contents untouched, such as when resuming an extraction
+    /// which failed partway
+    #[arg(long)]
+    skip_unchanged: bool,
+
+    /// Saves PROJECT.yaml, which describes the purpose of each project file, the original ROM and the options in effect
+    #[arg(long)]
+    manifest: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Preservation {
+    /// Only what the game needs to run, sorting the FNT and packing files and sections
+    Minimal,
+    /// Everything needed to rebuild a matching ROM in the common case
+    Standard,
+    /// Also the order of every file and the original capacity, and building fails on header problems
+    Exact,
+}
+
+impl From<Preservation> for PreservationLevel {
+    fn from(value: Preservation) -> Self {
+        match value {
+            Preservation::Minimal => PreservationLevel::Minimal,
+            Preservation::Standard => PreservationLevel::Standard,
+            Preservation::Exact => PreservationLevel::Exact,
+        }
+    }
+}
+
+fn parse_transparent_compression(value: &str) -> Result<TransparentCompression, String> {
+    let (glob, format) = value.rsplit_once('=').ok_or_else(|| format!("expected <glob>=<format>, got '{value}'"))?;
+    Ok(TransparentCompression { glob: glob.to_string(), format: parse_compression_format(format)? })
+}
+
+pub fn parse_compression_format(format: &str) -> Result<CompressionFormat, String> {
+    match format {
+        "lz10" => Ok(CompressionFormat::Lz10),
+        "rle" => Ok(CompressionFormat::Rle),
+        "huffman4" => Ok(CompressionFormat::Huffman4),
+        "huffman8" => Ok(CompressionFormat::Huffman8),
+        _ => Err(format!("unknown compression format '{format}', expected lz10, rle, huffman4 or huffman8")),
+    }
 }
 
 impl Extract {
-    pub fn run(&self) -> Result<()> {
+    pub fn run(&self, threads: Option<NonZeroUsize>) -> Result<()> {
         let raw_rom = raw::Rom::from_file(&self.rom)?;
-        let key =
-            if let Some(arm7_bios) = &self.arm7_bios { Some(BlowfishKey::from_arm7_bios_path(arm7_bios)?) } else { None };
-        let rom = Rom::extract(&raw_rom)?;
+        let keys = self.arm7_bios.iter().map(BlowfishKey::from_arm7_bios_path).collect::<Result<Vec<_>, _>>()?;
+        let level = if self.exact { PreservationLevel::Exact } else { self.preservation.into() };
+        let mut extract_options = RomExtractOptions::preservation(level);
+        extract_options.full_file_order |= self.full_file_order;
+        let rom = Rom::extract_with_options(&raw_rom, extract_options)?;
 
-        match rom.save(&self.path, key.as_ref()) {
-            Err(RomSaveError::BlowfishKeyNeeded) => {
+        let dedup_store = match &self.dedup_store {
+            Some(path) => {
+                let mode = if self.hardlink { DedupMode::Hardlink } else { DedupMode::Reference };
+                Some(DedupStore { path: std::path::absolute(path)?, mode })
+            }
+            None => None,
+        };
+        let options = RomSaveOptions {
+            key: None,
+            keys: &keys,
+            skip_files_larger_than: self.skip_larger_than,
+            skip_files_matching: self.skip.clone(),
+            concurrency: threads,
+            dedup_store,
+            transparent_compression: self.decompress.clone(),
+            split_banner_titles: self.split_banner_titles,
+            skip_unchanged_files: self.skip_unchanged,
+        };
+        match rom.save_with_summary(&self.path, options) {
+            Ok(summary) => log::info!("Extracted {summary}"),
+            Err(PartialSaveError { source, .. }) if matches!(*source, RomSaveError::BlowfishKeyNeeded) => {
                 bail!("The ROM is encrypted, please provide ARM7 BIOS");
             }
-            result => Ok(result?),
+            Err(PartialSaveError { source, summary }) => {
+                bail!("Extraction failed after {summary}: {source}\nRerun with --skip-unchanged to only extract the remaining files");
+            }
+        }
+
+        RomHashReport::compute(&raw_rom)?.save(self.path.join(RomHashReport::FILE_NAME))?;
+        if self.manifest {
+            let config: RomConfig = serde_yml::from_reader(std::fs::File::open(self.path.join("config.yaml"))?)?;
+            ProjectManifest::new(&raw_rom, &rom, &config).save(self.path.join(ProjectManifest::FILE_NAME))?;
         }
+        Ok(())
     }
 }
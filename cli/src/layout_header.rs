@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use ds_rom::{
+    io::write_file_and_dirs,
+    rom::{LayoutHeader, LayoutHeaderFormat, Rom, RomLoadOptions},
+};
+
+/// Generates an include file with the addresses and sizes of the ARM9 program, autoloads and overlays
+#[derive(Args)]
+pub struct GenerateLayoutHeader {
+    /// Path to config YAML
+    #[arg(long, short = 'c')]
+    config: PathBuf,
+
+    /// Format of the include file.
+    #[arg(long, short = 'f', value_enum, default_value_t = Format::C)]
+    format: Format,
+
+    /// Output path, prints to stdout if not set.
+    #[arg(long, short = 'o')]
+    out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// C header with `#define` lines
+    C,
+    /// GNU assembler include file with `.set` lines
+    Asm,
+    /// Linker script fragment with a `MEMORY` command and `PROVIDE` lines
+    Ld,
+}
+
+impl From<Format> for LayoutHeaderFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::C => Self::C,
+            Format::Asm => Self::Asm,
+            Format::Ld => Self::Ld,
+        }
+    }
+}
+
+impl GenerateLayoutHeader {
+    pub fn run(&self) -> Result<()> {
+        let rom = Rom::load(&self.config, RomLoadOptions {
+            compress: false,
+            encrypt: false,
+            load_files: false,
+            ..Default::default()
+        })?;
+
+        let header = LayoutHeader::new(&rom)?;
+        let contents = header.display(self.format.into()).to_string();
+        match &self.out {
+            Some(out) => write_file_and_dirs(out, contents)?,
+            None => print!("{contents}"),
+        }
+        Ok(())
+    }
+}
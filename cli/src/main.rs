@@ -1,21 +1,38 @@
 mod build;
+mod compare;
+mod doctor;
 mod dump;
 mod extract;
+mod layout_header;
+mod resign;
+mod schema;
+mod validate;
 
-use std::io::Write;
+use std::{io::Write, num::NonZeroUsize};
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{anyhow, bail, Result};
 use build::Build;
+use clap::{Parser, Subcommand};
+use compare::Compare;
+use doctor::Doctor;
+use ds_rom::rom::Arm9Marker;
 use dump::Dump;
 use extract::Extract;
+use layout_header::GenerateLayoutHeader;
 use log::LevelFilter;
+use resign::Resign;
+use schema::Schema;
+use validate::Validate;
 
 /// Command-line interface for extracting/building Nintendo DS ROMs.
 #[derive(Parser)]
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// Max number of threads to use, defaults to the number of available CPU cores
+    #[arg(long, global = true)]
+    threads: Option<NonZeroUsize>,
 }
 
 #[derive(Subcommand)]
@@ -23,23 +40,60 @@ enum Command {
     Dump(Dump),
     Extract(Extract),
     Build(Build),
+    Validate(Validate),
+    Doctor(Doctor),
+    GenerateLayoutHeader(GenerateLayoutHeader),
+    Schema(Schema),
+    #[command(alias = "verify")]
+    Compare(Compare),
+    Resign(Resign),
 }
 
 impl Command {
-    fn run(&self) -> Result<()> {
+    fn run(&self, threads: Option<NonZeroUsize>) -> Result<()> {
         match self {
             Command::Dump(dump) => dump.run(),
-            Command::Extract(extract) => extract.run(),
-            Command::Build(build) => build.run(),
+            Command::Extract(extract) => extract.run(threads),
+            Command::Build(build) => build.run(threads),
+            Command::Validate(validate) => validate.run(),
+            Command::Doctor(doctor) => doctor.run(),
+            Command::GenerateLayoutHeader(generate_layout_header) => generate_layout_header.run(),
+            Command::Schema(schema) => schema.run(),
+            Command::Compare(compare) => compare.run(),
+            Command::Resign(resign) => resign.run(),
         }
     }
 }
 
+/// Returns `bytes` as a lowercase hexadecimal string, the format read by [`parse_hex_bytes`].
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Parses a hexadecimal string such as a key or signature, e.g. `0a1b2c`.
+pub fn parse_hex_bytes(value: &str) -> Result<Vec<u8>> {
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        bail!("'{value}' is not an even number of hexadecimal digits");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|error| anyhow!("invalid hexadecimal '{value}': {error}")))
+        .collect()
+}
+
+/// Parses a hexadecimal HMAC-SHA1 signature, see [`parse_hex_bytes`].
+pub fn parse_signature(value: &str) -> Result<[u8; 20]> {
+    let bytes = parse_hex_bytes(value)?;
+    let length = bytes.len();
+    bytes.try_into().map_err(|_| anyhow!("signature '{value}' is {length} bytes long but must be 20 bytes"))
+}
+
 fn main() -> Result<()> {
     env_logger::builder().filter_level(LevelFilter::Info).init();
 
     let args: Cli = Cli::parse();
-    args.command.run()
+    args.command.run(args.threads)
 }
 
 pub fn print_hex(data: &[u8], raw: bool, base: u32) -> Result<()> {
@@ -56,3 +110,21 @@ pub fn print_hex(data: &[u8], raw: bool, base: u32) -> Result<()> {
     }
     Ok(())
 }
+
+pub fn print_hex_annotated(data: &[u8], base: u32, markers: &[Arm9Marker]) {
+    let mut markers = markers.iter().peekable();
+    for (offset, chunk) in data.chunks(16).enumerate() {
+        let row_start = base + offset as u32 * 16;
+        while let Some(marker) = markers.next_if(|marker| marker.address < row_start + 16) {
+            println!("-------- {:#010x}: {}", marker.address, marker.label);
+        }
+        print!("{row_start:08x} ");
+        for byte in chunk {
+            print!(" {byte:02x}");
+        }
+        println!();
+    }
+    for marker in markers {
+        println!("-------- {:#010x}: {}", marker.address, marker.label);
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use ds_rom::{
+    crypto::{blowfish::BlowfishKey, hmac_sha1::HmacSha1},
+    io::read_file,
+    rom::{raw, OverlayTable, Rom},
+};
+
+use crate::hex_string;
+
+/// Recomputes the signatures of ARM9 overlays and the overlay table in a built ROM, e.g. after editing an overlay in place
+#[derive(Args)]
+pub struct Resign {
+    /// Nintendo DS game ROM
+    #[arg(long, short = 'r')]
+    rom: PathBuf,
+
+    /// Output ROM
+    #[arg(long, short = 'o')]
+    out: PathBuf,
+
+    /// ID of an ARM9 overlay to sign, can be repeated. Defaults to every overlay flagged as signed
+    #[arg(long)]
+    overlay: Vec<u16>,
+
+    /// File containing the HMAC-SHA1 key. By default, the key is searched for in the ARM9 program
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+
+    /// Nintendo DS ARM7 BIOS file, needed if the ARM9 program is encrypted. Can be repeated to try the Blowfish key of each
+    /// BIOS
+    #[arg(long, short = '7')]
+    arm7_bios: Vec<PathBuf>,
+}
+
+impl Resign {
+    pub fn run(&self) -> Result<()> {
+        let keys = self.arm7_bios.iter().map(BlowfishKey::from_arm7_bios_path).collect::<Result<Vec<_>, _>>()?;
+        let key_refs = keys.iter().collect::<Vec<_>>();
+        let raw_rom = raw::Rom::from_file(&self.rom)?;
+        let mut rom = Rom::extract(&raw_rom)?;
+
+        let hmac_key = match &self.key_file {
+            Some(key_file) => read_file(key_file)?,
+            None => rom
+                .arm9_overlay_hmac_sha1_key(&key_refs)?
+                .ok_or_else(|| {
+                    anyhow!("no key in the ARM9 program matches the overlay table signature, pass the key with --key-file")
+                })?
+                .to_vec(),
+        };
+        let hmac = HmacSha1::new(&hmac_key);
+        let signed_ids = rom.resign_arm9_overlays(&hmac, &self.overlay, &key_refs)?;
+        for &id in &signed_ids {
+            let overlay = rom.arm9_overlays().iter().find(|overlay| overlay.id() == id).unwrap();
+            println!("Overlay {id:<4} : {}", hex_string(&overlay.compute_signature(&hmac)));
+        }
+        let table = match rom.arm9_overlay_table_override() {
+            Some(table) => table.clone(),
+            None => OverlayTable::from_overlays(rom.arm9_overlays()),
+        };
+        println!("Overlay table : {}", hex_string(&table.compute_signature(&hmac)));
+
+        let key = BlowfishKey::select(&key_refs, rom.blowfish_key_sha1());
+        rom.build(key)?.save(&self.out)?;
+        Ok(())
+    }
+}
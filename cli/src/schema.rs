@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use ds_rom::{io::write_file_and_dirs, rom::SchemaKind};
+
+/// Prints a JSON Schema of a project file format, for editors and external tools which generate or validate projects
+#[derive(Args)]
+pub struct Schema {
+    /// Project file to describe.
+    #[arg(value_enum, default_value_t = Kind::Config)]
+    kind: Kind,
+
+    /// Format of the schema.
+    #[arg(long, short = 'f', value_enum, default_value_t = Format::JsonSchema)]
+    format: Format,
+
+    /// Output path, prints to stdout if not set.
+    #[arg(long, short = 'o')]
+    out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Kind {
+    /// ROM config, normally `config.yaml`
+    Config,
+    /// ARM9 config, normally `arm9/arm9.yaml`
+    Arm9,
+    /// Overlay list, normally `arm9_overlays/overlays.yaml`
+    Overlays,
+    /// Banner, normally `banner/banner.yaml`
+    Banner,
+    /// Header, normally `header.yaml`
+    Header,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// JSON Schema, draft 2020-12
+    JsonSchema,
+}
+
+impl From<Kind> for SchemaKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Config => Self::Config,
+            Kind::Arm9 => Self::Arm9,
+            Kind::Overlays => Self::Overlays,
+            Kind::Banner => Self::Banner,
+            Kind::Header => Self::Header,
+        }
+    }
+}
+
+impl Schema {
+    pub fn run(&self) -> Result<()> {
+        let schema = SchemaKind::from(self.kind).schema();
+        let contents = match self.format {
+            Format::JsonSchema => schema.to_json(),
+        };
+        match &self.out {
+            Some(out) => write_file_and_dirs(out, contents)?,
+            None => print!("{contents}"),
+        }
+        Ok(())
+    }
+}
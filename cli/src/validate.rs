@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use ds_rom::{
+    crypto::hmac_sha1::HmacSha1,
+    rom::{OverlayTable, OverlayTableError, Rom, RomLoadOptions},
+};
+
+use crate::{parse_hex_bytes, parse_signature};
+
+/// Checks an extracted ROM for problems that would cause it to fail at boot, and for changed banner CRCs
+#[derive(Args)]
+pub struct Validate {
+    /// Path to config YAML
+    #[arg(long, short = 'c')]
+    config: PathBuf,
+
+    /// HMAC-SHA1 key in hexadecimal, to verify the signature of the ARM9 overlay table
+    #[arg(long, requires = "ovt_signature")]
+    ovt_key: Option<String>,
+
+    /// Expected signature of the ARM9 overlay table in hexadecimal, as it will be built
+    #[arg(long, requires = "ovt_key")]
+    ovt_signature: Option<String>,
+}
+
+impl Validate {
+    pub fn run(&self) -> Result<()> {
+        let rom = Rom::load(&self.config, RomLoadOptions {
+            // Compressed overlays are built with their compressed size in the overlay table, which is signed
+            compress: self.ovt_signature.is_some(),
+            encrypt: false,
+            load_files: false,
+            ..Default::default()
+        })?;
+
+        let findings = rom.config().memory_limits.unwrap_or_default().check(&rom);
+        let banner_crc_mismatches = match rom.banner() {
+            Some(banner) => banner.crc_mismatches()?,
+            None => vec![],
+        };
+        let ovt_signature_mismatch = self.check_ovt_signature(&rom)?;
+        if findings.is_empty() && banner_crc_mismatches.is_empty() && ovt_signature_mismatch.is_none() {
+            println!("No problems found");
+        }
+        if let Some(mismatch) = ovt_signature_mismatch {
+            println!("{mismatch}");
+        }
+        for finding in findings {
+            println!("{finding}");
+        }
+        for mismatch in banner_crc_mismatches {
+            println!("{mismatch}");
+        }
+        Ok(())
+    }
+
+    /// Returns a description of the mismatch if the ARM9 overlay table is not signed by the given signature.
+    fn check_ovt_signature(&self, rom: &Rom) -> Result<Option<String>> {
+        let (Some(key), Some(signature)) = (&self.ovt_key, &self.ovt_signature) else { return Ok(None) };
+        let hmac = HmacSha1::new(&parse_hex_bytes(key)?);
+        let table = match rom.arm9_overlay_table_override() {
+            Some(table) => table.clone(),
+            None => OverlayTable::from_overlays(rom.arm9_overlays()),
+        };
+        match table.check_signature(&hmac, &parse_signature(signature)?) {
+            Ok(()) => Ok(None),
+            Err(OverlayTableError::SignatureMismatch { expected, actual, .. }) => {
+                Ok(Some(format!("ARM9 overlay table signature {actual} does not match, the table is signed as {expected}")))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Compares the LZ77 match finders on code-like data. Run with `cargo bench -p ds-rom --bench lz77`.
+
+use std::time::{Duration, Instant};
+
+use ds_rom::compress::lz77::{Lz77, Lz77MatchFinder};
+
+/// Returns code-like data, with many short repeats at varying distances like in ARM programs.
+fn code_like_data(size: usize) -> Vec<u8> {
+    let mut seed = 0x9abcdef0u32;
+    let mut data = Vec::with_capacity(size);
+    while data.len() < size {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let instruction = match seed >> 30 {
+            0 => 0xe1a00000,
+            1 => 0xe5900000 | (seed >> 8) & 0xfff,
+            2 => 0xeb000000 | (seed >> 4) & 0xffff,
+            _ => seed,
+        };
+        data.extend(instruction.to_le_bytes());
+    }
+    data.truncate(size);
+    data
+}
+
+/// Returns the fastest of a few runs of compressing `data` with `match_finder`.
+fn bench(data: &[u8], match_finder: Lz77MatchFinder) -> Duration {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(Lz77 {}.compress_with(data, 0x4000, match_finder).unwrap());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    for size in [0x10000, 0x40000] {
+        let data = code_like_data(size);
+        let naive = bench(&data, Lz77MatchFinder::Naive);
+        let hash_chain = bench(&data, Lz77MatchFinder::HashChain);
+        println!(
+            "{size:#x} bytes: naive {naive:?}, hash chain {hash_chain:?} ({:.1}x)",
+            naive.as_secs_f64() / hash_chain.as_secs_f64()
+        );
+    }
+}
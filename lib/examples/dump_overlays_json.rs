@@ -0,0 +1,42 @@
+//! Prints the metadata of every overlay in a ROM as JSON.
+//!
+//! ```sh
+//! cargo run --example dump_overlays_json -- mygame.nds
+//! ```
+
+use std::{env, process::ExitCode};
+
+use anyhow::Result;
+use ds_rom::rom::{raw, Overlay, Rom};
+use serde_json::{json, Value};
+
+fn overlays_json(overlays: &[Overlay]) -> Vec<Value> {
+    overlays
+        .iter()
+        .map(|overlay| {
+            json!({
+                "info": overlay.info(),
+                "originally_compressed": overlay.originally_compressed(),
+                "stored_size": overlay.full_data().len(),
+                "end_address": overlay.end_address(),
+            })
+        })
+        .collect()
+}
+
+fn main() -> Result<ExitCode> {
+    let args = env::args().collect::<Vec<_>>();
+    let [_, rom_path] = args.as_slice() else {
+        eprintln!("Usage: dump_overlays_json <ROM>");
+        return Ok(ExitCode::FAILURE);
+    };
+
+    let raw_rom = raw::Rom::from_file(rom_path)?;
+    let rom = Rom::extract(&raw_rom)?;
+    let json = json!({
+        "arm9": overlays_json(rom.arm9_overlays()),
+        "arm7": overlays_json(rom.arm7_overlays()),
+    });
+    println!("{}", serde_json::to_string_pretty(&json)?);
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,56 @@
+//! Modifies a ROM entirely in memory, without extracting it to files on disk.
+//!
+//! ```sh
+//! cargo run --example modify_in_memory -- mygame.nds /path/to/file.bin replacement.bin mygame_modified.nds
+//! ```
+
+use std::{env, fs, process::ExitCode};
+
+use anyhow::{bail, Context, Result};
+use ds_rom::rom::{
+    raw::{self, Language},
+    Rom,
+};
+
+/// Extracts a ROM which owns its data, so that it can outlive the raw ROM it was extracted from.
+fn extract(path: &str) -> Result<Rom<'static>> {
+    let raw_rom = raw::Rom::from_file(path)?;
+    let rom = Rom::extract(&raw_rom)?;
+    Ok(rom.into_owned())
+}
+
+fn run(rom_path: &str, file_path: &str, replacement_path: &str, output_path: &str) -> Result<()> {
+    let mut rom = extract(rom_path)?;
+
+    if let Some(banner) = rom.banner_mut() {
+        let title = banner.title.get(Language::English).unwrap_or_default();
+        // Titles have up to three lines, the first one being the game name
+        let title = match title.split_once('\n') {
+            Some((name, rest)) => format!("{name} (modified)\n{rest}"),
+            None => format!("{title} (modified)"),
+        };
+        banner.title.set(Language::English, title);
+    }
+
+    let Some(id) = rom.files().find_path(file_path) else {
+        bail!("file {file_path} not found in {rom_path}");
+    };
+    let contents = fs::read(replacement_path).with_context(|| format!("failed to read {replacement_path}"))?;
+    println!("Replacing {file_path} ({:#x} bytes) with {:#x} bytes", rom.files().file(id).size(), contents.len());
+    rom.files_mut().set_file_contents(id, contents);
+
+    let raw_rom = rom.build(None)?;
+    raw_rom.save(output_path)?;
+    println!("Saved modified ROM to {output_path}");
+    Ok(())
+}
+
+fn main() -> Result<ExitCode> {
+    let args = env::args().collect::<Vec<_>>();
+    let [_, rom_path, file_path, replacement_path, output_path] = args.as_slice() else {
+        eprintln!("Usage: modify_in_memory <ROM> <FILE PATH IN ROM> <REPLACEMENT FILE> <OUTPUT ROM>");
+        return Ok(ExitCode::FAILURE);
+    };
+    run(rom_path, file_path, replacement_path, output_path)?;
+    Ok(ExitCode::SUCCESS)
+}
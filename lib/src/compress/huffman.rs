@@ -1,5 +1,10 @@
+use std::{backtrace::Backtrace, cmp::Reverse, collections::BinaryHeap};
+
 use bitreader::BitReader;
 use rust_bitwriter::BitWriter;
+use snafu::{OptionExt, Snafu};
+
+use super::{read_header, write_header};
 
 /// De/compresses data with [Huffman coding](https://en.wikipedia.org/wiki/Huffman_coding), one nibble at a time. This struct
 /// is not represented as a tree (like it is formally) but instead the Huffman codes are found in an array of length 16, one
@@ -72,41 +77,316 @@ impl NibbleHuffman {
         let len = out.len().min(data.len());
         out[..len].copy_from_slice(&data[..len]);
     }
+}
 
-    /// Does the opposite of [Self::data_to_diff16]. If `data` consists of 16-bit integers that look like A, B-A, C-B and so
-    /// on, this function will recover the original data A, B, C.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `data.len()` is not a multiple of 2.
-    pub fn diff16_to_data(&self, data: &mut [u8]) {
-        assert!(data.len() % 2 == 0);
-        let mut prev = 0;
-        for i in (0..data.len()).step_by(2) {
-            let curr = u16::from_le_bytes([data[i], data[i + 1]]);
-            let value = curr.wrapping_add(prev);
-            data[i..i + 2].copy_from_slice(&value.to_le_bytes());
-            prev = value;
+/// Errors related to [`diff16_to_data`] and [`data_to_diff16`].
+#[derive(Debug, Snafu)]
+pub enum Diff16Error {
+    /// Occurs when the data is not a whole number of 16-bit integers.
+    #[snafu(display("16-bit differential data must have an even length, but it has {length} bytes:\n{backtrace}"))]
+    OddLength {
+        /// Length of the data.
+        length: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Does the opposite of [`data_to_diff16`]. If `data` consists of 16-bit integers that look like A, B-A, C-B and so on,
+/// this function will recover the original data A, B, C. Each sum wraps around at 16 bits.
+///
+/// The integers are little-endian, so bytes `2*i` and `2*i+1` hold the low and high byte of the `i`th integer.
+///
+/// # Errors
+///
+/// This function will return an error if `data.len()` is not a multiple of 2.
+pub fn diff16_to_data(data: &mut [u8]) -> Result<(), Diff16Error> {
+    if !data.len().is_multiple_of(2) {
+        return OddLengthSnafu { length: data.len() }.fail();
+    }
+    let mut prev = 0u16;
+    for pair in data.chunks_exact_mut(2) {
+        let value = u16::from_le_bytes([pair[0], pair[1]]).wrapping_add(prev);
+        pair.copy_from_slice(&value.to_le_bytes());
+        prev = value;
+    }
+    Ok(())
+}
+
+/// Differentiates every 16-bit integer in `data`. For example, if the 16-bit integers are called A, B, C and so on, then
+/// they will be differentiated to A, B-A, C-B and so on. Each difference wraps around at 16 bits.
+///
+/// The integers are little-endian, so bytes `2*i` and `2*i+1` hold the low and high byte of the `i`th integer.
+///
+/// If `data` has a lot of repeating values, this will result in plenty of zeros. This benefits Huffman compression, as it
+/// compresses better if some values occur more often than others.
+///
+/// # Errors
+///
+/// This function will return an error if `data.len()` is not a multiple of 2.
+pub fn data_to_diff16(data: &mut [u8]) -> Result<(), Diff16Error> {
+    if !data.len().is_multiple_of(2) {
+        return OddLengthSnafu { length: data.len() }.fail();
+    }
+    let mut prev = 0u16;
+    for pair in data.chunks_exact_mut(2) {
+        let curr = u16::from_le_bytes([pair[0], pair[1]]);
+        pair.copy_from_slice(&curr.wrapping_sub(prev).to_le_bytes());
+        prev = curr;
+    }
+    Ok(())
+}
+
+/// De/compresses data using the Huffman format of the DS BIOS, which has the type 0x24 or 0x28 in its header depending on
+/// whether the data is encoded 4 or 8 bits at a time.
+pub struct Huffman {}
+
+/// Number of bits which [`Huffman`] encodes at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HuffmanBitWidth {
+    /// Encodes nibbles, low nibble first. Has the type 0x24 in the header.
+    Four,
+    /// Encodes bytes. Has the type 0x28 in the header.
+    Eight,
+}
+
+impl HuffmanBitWidth {
+    fn bits(self) -> u8 {
+        match self {
+            Self::Four => 4,
+            Self::Eight => 8,
         }
     }
 
-    /// Differentiates every 16-bit integer in `data`. For example, if the 16-bit integers are called A, B, C and so on, then
-    /// they will be differentiated to A, B-A, C-B and so on.
-    ///
-    /// If `data` has a lot of repeating values, this will result in plenty of zeros. This benefits Huffman compression, as it
-    /// compresses better if some values occur more often than others.
+    fn header_type(self) -> u8 {
+        0x20 | self.bits()
+    }
+}
+
+/// Child nodes of a tree node can be at most this many pairs after the node.
+const MAX_CHILD_OFFSET: usize = 0x3f;
+const LEAF_FLAG_0: u8 = 0x80;
+const LEAF_FLAG_1: u8 = 0x40;
+
+/// Errors related to [`Huffman::decompress`].
+#[derive(Debug, Snafu)]
+pub enum HuffmanDecompressError {
+    /// Occurs when the data doesn't start with a Huffman header.
+    #[snafu(display("missing Huffman header:\n{backtrace}"))]
+    InvalidHeader {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the compressed data ends before the decompressed size is reached.
+    #[snafu(display(
+        "Huffman data ended at offset {offset:#x} before the decompressed size {size:#x} was reached:\n{backtrace}"
+    ))]
+    Truncated {
+        /// Offset where the data ended.
+        offset: usize,
+        /// Decompressed size in the header.
+        size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a tree node points past the end of the tree table.
+    #[snafu(display("Huffman tree node at offset {offset:#x} points past the end of the tree at {end:#x}:\n{backtrace}"))]
+    OutOfBounds {
+        /// Offset of the tree node.
+        offset: usize,
+        /// Offset where the tree table ends.
+        end: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+enum HuffmanNode {
+    Leaf(u8),
+    Internal([usize; 2]),
+}
+
+/// A Huffman tree, where the root is the last node.
+struct HuffmanTree {
+    nodes: Vec<HuffmanNode>,
+    /// Number of internal nodes in the subtree of each node.
+    internal_counts: Vec<usize>,
+}
+
+impl HuffmanTree {
+    fn new(frequencies: &[usize]) -> Self {
+        let mut tree = Self { nodes: vec![], internal_counts: vec![] };
+        let mut heap = BinaryHeap::new();
+        for (value, &frequency) in frequencies.iter().enumerate() {
+            if frequency > 0 {
+                heap.push(Reverse((frequency, tree.push(HuffmanNode::Leaf(value as u8), 0))));
+            }
+        }
+        // The root must have two children, so add unused values until there are at least two leaves
+        for value in 0..2 {
+            if heap.len() < 2 && frequencies.get(value).is_none_or(|&frequency| frequency == 0) {
+                heap.push(Reverse((0, tree.push(HuffmanNode::Leaf(value as u8), 0))));
+            }
+        }
+        while let (Some(Reverse((frequency0, node0))), Some(Reverse((frequency1, node1)))) = (heap.pop(), heap.pop()) {
+            let internal_count = tree.internal_counts[node0] + tree.internal_counts[node1] + 1;
+            let node = tree.push(HuffmanNode::Internal([node0, node1]), internal_count);
+            heap.push(Reverse((frequency0 + frequency1, node)));
+        }
+        tree
+    }
+
+    fn push(&mut self, node: HuffmanNode, internal_count: usize) -> usize {
+        self.nodes.push(node);
+        self.internal_counts.push(internal_count);
+        self.nodes.len() - 1
+    }
+
+    fn root(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// Returns the code of each leaf value as a tuple of its bits and length. The codes fit in 64 bits, as a code of length
+    /// `n` requires more values than the `n`th Fibonacci number.
+    fn codes(&self) -> [(u64, u8); 256] {
+        let mut codes = [(0, 0); 256];
+        let mut stack = vec![(self.root(), 0, 0)];
+        while let Some((node, bits, length)) = stack.pop() {
+            match self.nodes[node] {
+                HuffmanNode::Leaf(value) => codes[value as usize] = (bits, length),
+                HuffmanNode::Internal(children) => {
+                    for (bit, child) in children.into_iter().enumerate() {
+                        stack.push((child, bits << 1 | bit as u64, length + 1));
+                    }
+                }
+            }
+        }
+        codes
+    }
+
+    /// Lays out the tree table read by the BIOS, starting with the size byte. Each internal node is a byte which points to
+    /// a pair of child nodes at most [`MAX_CHILD_OFFSET`] pairs later, so the pairs are placed one by one in an order that
+    /// never lets a node wait too long for its children. Nodes with small subtrees are placed first to keep the number of
+    /// waiting nodes low, unless that would make a waiting node miss its deadline.
+    fn table(&self) -> Vec<u8> {
+        let mut table = vec![0u8; (self.internal_counts[self.root()] + 1) * 2];
+        // Internal nodes waiting for their children, as tuples of node, address and last possible pair index
+        let mut waiting = vec![(self.root(), 1, MAX_CHILD_OFFSET + 1)];
+        for pair in 1..table.len() / 2 {
+            waiting.sort_by_key(|&(node, _, deadline)| (deadline, node));
+            let smallest = (0..waiting.len()).min_by_key(|&index| (self.internal_counts[waiting[index].0], index)).unwrap();
+            let can_wait = |skipped: usize| {
+                let remaining = waiting.iter().enumerate().filter(|&(index, _)| index != skipped);
+                remaining.enumerate().all(|(slot, (_, &(_, _, deadline)))| deadline > pair + slot)
+            };
+            let (node, address, deadline) = waiting.remove(if can_wait(smallest) { smallest } else { 0 });
+            assert!(pair <= deadline, "Huffman tree table could not be laid out");
+
+            let HuffmanNode::Internal(children) = self.nodes[node] else { unreachable!() };
+            table[address] = (pair - address / 2 - 1) as u8;
+            for (index, child) in children.into_iter().enumerate() {
+                let child_address = pair * 2 + index;
+                match self.nodes[child] {
+                    HuffmanNode::Leaf(value) => {
+                        table[address] |= [LEAF_FLAG_0, LEAF_FLAG_1][index];
+                        table[child_address] = value;
+                    }
+                    HuffmanNode::Internal(_) => waiting.push((child, child_address, pair + MAX_CHILD_OFFSET + 1)),
+                }
+            }
+        }
+        // The bitstream after the table must be word aligned
+        table.resize(table.len().next_multiple_of(4), 0);
+        table[0] = (table.len() / 2 - 1) as u8;
+        table
+    }
+}
+
+impl Huffman {
+    /// Compresses `bytes` `bit_width` bits at a time and returns the result, padded to a multiple of 4 bytes.
+    pub fn compress(&self, bytes: &[u8], bit_width: HuffmanBitWidth) -> Box<[u8]> {
+        let values: Vec<u8> = match bit_width {
+            HuffmanBitWidth::Four => bytes.iter().flat_map(|byte| [byte & 0xf, byte >> 4]).collect(),
+            HuffmanBitWidth::Eight => bytes.to_vec(),
+        };
+        let mut frequencies = vec![0; 1 << bit_width.bits()];
+        for &value in &values {
+            frequencies[value as usize] += 1;
+        }
+        let tree = HuffmanTree::new(&frequencies);
+
+        let mut compressed = Vec::with_capacity(bytes.len() + 0x208);
+        write_header(&mut compressed, bit_width.header_type(), bytes.len());
+        compressed.extend(tree.table());
+
+        // The bitstream is read one little-endian word at a time, starting with the most significant bit
+        let codes = tree.codes();
+        let mut word = 0u32;
+        let mut word_bits = 0;
+        for value in values {
+            let (bits, length) = codes[value as usize];
+            for shift in (0..length).rev() {
+                word = word << 1 | (bits >> shift) as u32 & 1;
+                word_bits += 1;
+                if word_bits == 32 {
+                    compressed.extend_from_slice(&word.to_le_bytes());
+                    (word, word_bits) = (0, 0);
+                }
+            }
+        }
+        if word_bits > 0 {
+            compressed.extend_from_slice(&(word << (32 - word_bits)).to_le_bytes());
+        }
+        compressed.into_boxed_slice()
+    }
+
+    /// Decompresses `bytes` and returns the result. The bit width is read from the header. Trailing bytes after the
+    /// compressed data are ignored.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if `data.len()` is not a multiple of 2.
-    pub fn data_to_diff16(&self, data: &mut [u8]) {
-        assert!(data.len() % 2 == 0);
-        let mut prev = 0;
-        for i in (0..data.len()).step_by(2) {
-            let curr = u16::from_le_bytes([data[i], data[i + 1]]);
-            let value = curr.wrapping_sub(prev);
-            data[i..i + 2].copy_from_slice(&value.to_le_bytes());
-            prev = curr;
+    /// This function will return an error if the header is missing, the data is truncated or the tree table is invalid.
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Box<[u8]>, HuffmanDecompressError> {
+        let bit_width = match bytes.first() {
+            Some(0x24) => HuffmanBitWidth::Four,
+            Some(0x28) => HuffmanBitWidth::Eight,
+            _ => return InvalidHeaderSnafu.fail(),
+        };
+        let (size, tree_start) = read_header(bytes, bit_width.header_type()).context(InvalidHeaderSnafu)?;
+        let tree_size = *bytes.get(tree_start).context(TruncatedSnafu { offset: tree_start, size })? as usize * 2 + 2;
+        let tree_end = tree_start + tree_size;
+        let tree = bytes.get(..tree_end).context(TruncatedSnafu { offset: tree_start, size })?;
+        let root = tree_start + 1;
+
+        let mut decompressed = Vec::with_capacity(size);
+        let mut offset = tree_end;
+        let mut node = root;
+        let mut nibble = None;
+        'words: while decompressed.len() < size {
+            let word = bytes.get(offset..offset + 4).context(TruncatedSnafu { offset, size })?;
+            let word = u32::from_le_bytes(word.try_into().unwrap());
+            for shift in (0..32).rev() {
+                let bit = (word >> shift) as usize & 1;
+                let child = (node & !1) + (tree[node] as usize & MAX_CHILD_OFFSET) * 2 + 2 + bit;
+                if child >= tree_end {
+                    return OutOfBoundsSnafu { offset: node, end: tree_end }.fail();
+                }
+                if tree[node] & [LEAF_FLAG_0, LEAF_FLAG_1][bit] == 0 {
+                    node = child;
+                    continue;
+                }
+                node = root;
+                match (bit_width, nibble.take()) {
+                    (HuffmanBitWidth::Four, None) => nibble = Some(tree[child] & 0xf),
+                    (HuffmanBitWidth::Four, Some(low)) => decompressed.push(tree[child] << 4 | low),
+                    (HuffmanBitWidth::Eight, _) => decompressed.push(tree[child]),
+                }
+                if decompressed.len() >= size {
+                    break 'words;
+                }
+            }
+            offset += 4;
         }
+        Ok(decompressed.into_boxed_slice())
     }
 }
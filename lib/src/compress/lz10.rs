@@ -0,0 +1,191 @@
+use std::backtrace::Backtrace;
+
+use snafu::{OptionExt, Snafu};
+
+use super::{pad_to_word, read_header, write_header};
+
+/// De/compresses data using the LZ77 format of the DS BIOS, which has the type 0x10 in its header. Unlike
+/// [`super::lz77::Lz77`], it de/compresses forwards and is mostly used for files rather than programs.
+pub struct Lz10 {}
+
+const TYPE: u8 = 0x10;
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = MIN_LENGTH + 0xf;
+/// The BIOS functions which decompress to VRAM write two bytes at a time, so a distance of 1 would read a byte which has
+/// not been written yet.
+const MIN_VRAM_DISTANCE: usize = 2;
+const MAX_DISTANCE: usize = 0x1000;
+
+const HASH_BITS: usize = 15;
+const NO_POSITION: usize = usize::MAX;
+
+/// Errors related to [`Lz10::decompress`].
+#[derive(Debug, Snafu)]
+pub enum Lz10DecompressError {
+    /// Occurs when the data doesn't start with an LZ10 header.
+    #[snafu(display("missing LZ10 header:\n{backtrace}"))]
+    InvalidHeader {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the compressed data ends before the decompressed size is reached.
+    #[snafu(display(
+        "LZ10 data ended at offset {offset:#x} before the decompressed size {size:#x} was reached:\n{backtrace}"
+    ))]
+    Truncated {
+        /// Offset where the data ended.
+        offset: usize,
+        /// Decompressed size in the header.
+        size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a length-distance pair points before the start of the decompressed data.
+    #[snafu(display("LZ10 pair at offset {offset:#x} has distance {distance:#x} but only {available:#x} bytes are decompressed:\n{backtrace}"))]
+    OutOfBounds {
+        /// Offset of the length-distance pair.
+        offset: usize,
+        /// Distance of the pair.
+        distance: usize,
+        /// Number of bytes decompressed before the pair.
+        available: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+impl Lz10 {
+    /// Compresses `bytes` and returns the result, padded to a multiple of 4 bytes. The result can be decompressed to VRAM,
+    /// see [`Self::compress_with`].
+    pub fn compress(&self, bytes: &[u8]) -> Box<[u8]> {
+        self.compress_with(bytes, true)
+    }
+
+    /// Compresses `bytes` and returns the result, padded to a multiple of 4 bytes. If `vram_safe` is false, the result may
+    /// repeat the previous byte with a distance of 1, which compresses runs slightly better but can't be decompressed to
+    /// VRAM by the BIOS.
+    pub fn compress_with(&self, bytes: &[u8], vram_safe: bool) -> Box<[u8]> {
+        let mut compressed = Vec::with_capacity(bytes.len() + bytes.len() / 8 + 8);
+        write_header(&mut compressed, TYPE, bytes.len());
+
+        let min_distance = if vram_safe { MIN_VRAM_DISTANCE } else { 1 };
+        let mut matcher = MatchFinder::new(bytes, min_distance);
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let flags_index = compressed.len();
+            compressed.push(0);
+            for bit in 0..8 {
+                if pos >= bytes.len() {
+                    break;
+                }
+                match matcher.find(pos) {
+                    Some((length, distance)) => {
+                        compressed[flags_index] |= 0x80 >> bit;
+                        let distance = distance - 1;
+                        compressed.push((((length - MIN_LENGTH) << 4) | (distance >> 8)) as u8);
+                        compressed.push(distance as u8);
+                        for _ in 0..length {
+                            matcher.insert(pos);
+                            pos += 1;
+                        }
+                    }
+                    None => {
+                        compressed.push(bytes[pos]);
+                        matcher.insert(pos);
+                        pos += 1;
+                    }
+                }
+            }
+        }
+
+        pad_to_word(&mut compressed);
+        compressed.into_boxed_slice()
+    }
+
+    /// Decompresses `bytes` and returns the result. Trailing bytes after the compressed data are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header is missing, the data is truncated or a length-distance pair points
+    /// outside of the decompressed data.
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Box<[u8]>, Lz10DecompressError> {
+        let (size, mut offset) = read_header(bytes, TYPE).context(InvalidHeaderSnafu)?;
+        let mut decompressed = Vec::with_capacity(size);
+        while decompressed.len() < size {
+            let flags = *bytes.get(offset).context(TruncatedSnafu { offset, size })?;
+            offset += 1;
+            for bit in 0..8 {
+                if decompressed.len() >= size {
+                    break;
+                }
+                if flags & (0x80 >> bit) == 0 {
+                    decompressed.push(*bytes.get(offset).context(TruncatedSnafu { offset, size })?);
+                    offset += 1;
+                    continue;
+                }
+
+                let pair = bytes.get(offset..offset + 2).context(TruncatedSnafu { offset, size })?;
+                let length = (pair[0] >> 4) as usize + MIN_LENGTH;
+                let distance = (((pair[0] & 0xf) as usize) << 8 | pair[1] as usize) + 1;
+                let available = decompressed.len();
+                if distance > available {
+                    return OutOfBoundsSnafu { offset, distance, available }.fail();
+                }
+                for _ in 0..length {
+                    decompressed.push(decompressed[decompressed.len() - distance]);
+                }
+                offset += 2;
+            }
+        }
+        decompressed.truncate(size);
+        Ok(decompressed.into_boxed_slice())
+    }
+}
+
+/// Finds the longest previous match at each position using hash chains of 3-byte prefixes.
+struct MatchFinder<'a> {
+    bytes: &'a [u8],
+    min_distance: usize,
+    heads: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl<'a> MatchFinder<'a> {
+    fn new(bytes: &'a [u8], min_distance: usize) -> Self {
+        Self { bytes, min_distance, heads: vec![NO_POSITION; 1 << HASH_BITS], previous: vec![NO_POSITION; bytes.len()] }
+    }
+
+    fn hash(&self, pos: usize) -> Option<usize> {
+        let prefix = self.bytes.get(pos..pos + MIN_LENGTH)?;
+        let value = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], 0]);
+        Some((value.wrapping_mul(0x9e3779b1) >> (32 - HASH_BITS)) as usize)
+    }
+
+    fn insert(&mut self, pos: usize) {
+        let Some(hash) = self.hash(pos) else { return };
+        self.previous[pos] = self.heads[hash];
+        self.heads[hash] = pos;
+    }
+
+    /// Returns the length and distance of the longest match at `pos`, preferring the closest one.
+    fn find(&self, pos: usize) -> Option<(usize, usize)> {
+        let hash = self.hash(pos)?;
+        let max_length = MAX_LENGTH.min(self.bytes.len() - pos);
+        let mut best: Option<(usize, usize)> = None;
+        let mut candidate = self.heads[hash];
+        while candidate != NO_POSITION && pos - candidate <= MAX_DISTANCE {
+            let distance = pos - candidate;
+            if distance >= self.min_distance {
+                let length = (0..max_length).take_while(|&i| self.bytes[candidate + i] == self.bytes[pos + i]).count();
+                if length >= MIN_LENGTH && length > best.map_or(0, |(best_length, _)| best_length) {
+                    best = Some((length, distance));
+                    if length == max_length {
+                        break;
+                    }
+                }
+            }
+            candidate = self.previous[candidate];
+        }
+        best
+    }
+}
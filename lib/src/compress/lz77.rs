@@ -22,6 +22,35 @@ const MAX_SUBSEQUENCE: usize = MIN_SUBSEQUENCE + LENGTH_MASK;
 const LOOKAHEAD: usize = 1 << DISTANCE_BITS;
 const MAX_DISTANCE: usize = DISTANCE_MASK + MIN_SUBSEQUENCE;
 
+/// Number of bits in the hashes of [`HashChain`].
+const HASH_BITS: usize = 15;
+
+/// Algorithm used by [`Lz77::compress_with`] to find repeated sequences. Both algorithms find the same matches, so the
+/// compressed output is identical.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lz77MatchFinder {
+    /// Compares against every position in the window. Slow, but kept as a reference for [`Self::HashChain`].
+    Naive,
+    /// Indexes every 3-byte sequence in hash chains, so that only positions which may match are compared.
+    #[default]
+    HashChain,
+}
+
+/// Footer at the end of compressed data, see [`Lz77::footer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lz77Footer {
+    /// Size of the compressed part at the end of the data, including padding and this footer. Bytes before it are
+    /// stored uncompressed.
+    pub total_size: usize,
+    /// Size of the padding and this footer, which are skipped before reading compressed data.
+    pub read_offset: usize,
+    /// Number of bytes that the data grows by when decompressed.
+    pub write_offset: usize,
+}
+
+/// Size of [`Lz77Footer`] in bytes.
+const FOOTER_SIZE: usize = 8;
+
 /// Length-distance pair
 #[derive(Clone, Copy, Debug)]
 pub struct Pair {
@@ -84,8 +113,13 @@ pub enum Lz77DecompressError {
 }
 
 impl Lz77 {
-    fn compress_bytes(&self, bytes: &[u8], compressed: &mut Vec<u8>) -> Result<usize, io::Error> {
-        let mut tokens = Tokens::compress(bytes);
+    fn compress_bytes(
+        &self,
+        bytes: &[u8],
+        compressed: &mut Vec<u8>,
+        match_finder: Lz77MatchFinder,
+    ) -> Result<usize, io::Error> {
+        let mut tokens = Tokens::compress(bytes, match_finder);
         tokens.drop_wasteful_tokens()?;
         tokens.write(compressed)
     }
@@ -119,8 +153,17 @@ impl Lz77 {
     ///
     /// This function will return an error if an I/O operation fails.
     pub fn compress(&self, bytes: &[u8], start: usize) -> Result<Box<[u8]>, io::Error> {
+        self.compress_with(bytes, start, Lz77MatchFinder::default())
+    }
+
+    /// Compresses `bytes[start..]` like [`Self::compress`], using the given algorithm to find matches.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails.
+    pub fn compress_with(&self, bytes: &[u8], start: usize, match_finder: Lz77MatchFinder) -> Result<Box<[u8]>, io::Error> {
         let mut compressed = Vec::with_capacity(bytes.len());
-        let num_identical = self.compress_bytes(&bytes[start..], &mut compressed)?;
+        let num_identical = self.compress_bytes(&bytes[start..], &mut compressed, match_finder)?;
         for i in (0..start).rev() {
             compressed.push(bytes[i]);
         }
@@ -147,6 +190,22 @@ impl Lz77 {
         (total_size, read_offset, write_offset)
     }
 
+    /// Returns the footer at the end of `bytes` if it's plausible: the padding and footer fit in the compressed part, the
+    /// compressed part fits in `bytes`, the padding consists of 0xff bytes and the decompressed data is at most 8 times
+    /// larger. Returns `None` otherwise, which most likely means that `bytes` isn't compressed.
+    pub fn footer(&self, bytes: &[u8]) -> Option<Lz77Footer> {
+        if bytes.len() < FOOTER_SIZE {
+            return None;
+        }
+        let (total_size, read_offset, write_offset) = self.read_footer(bytes);
+        let length = bytes.len();
+        let plausible = (FOOTER_SIZE..FOOTER_SIZE + 4).contains(&read_offset)
+            && (read_offset..=length).contains(&total_size)
+            && bytes[length - read_offset..length - FOOTER_SIZE].iter().all(|&b| b == 0xff)
+            && write_offset <= total_size * 8;
+        plausible.then_some(Lz77Footer { total_size, read_offset, write_offset })
+    }
+
     /// Parses the LZ77 tokens in the `bytes` slice.
     pub fn parse_tokens<'a>(&self, bytes: &'a [u8]) -> Result<Tokens<'a>, Lz77ParseError> {
         let (total_size, read_offset, write_offset) = self.read_footer(bytes);
@@ -197,6 +256,38 @@ impl<'a> Display for Token<'a> {
     }
 }
 
+/// Links each position to the next position after it where the 3 bytes ending there have the same hash. Since the data
+/// is compressed backwards, these are the closest earlier positions in compression order which may match.
+struct HashChain {
+    next: Vec<u32>,
+}
+
+impl HashChain {
+    const NONE: u32 = u32::MAX;
+
+    fn new(bytes: &[u8]) -> Self {
+        let mut heads = vec![Self::NONE; 1 << HASH_BITS];
+        let mut next = vec![Self::NONE; bytes.len()];
+        for pos in (MIN_SUBSEQUENCE - 1..bytes.len()).rev() {
+            let hash = Self::hash(&bytes[pos + 1 - MIN_SUBSEQUENCE..=pos]);
+            next[pos] = heads[hash];
+            heads[hash] = pos as u32;
+        }
+        Self { next }
+    }
+
+    fn hash(sequence: &[u8]) -> usize {
+        let value = u32::from_le_bytes([sequence[0], sequence[1], sequence[2], 0]);
+        (value.wrapping_mul(0x9e3779b1) >> (32 - HASH_BITS)) as usize
+    }
+
+    /// Returns the positions after `pos` which may match it, closest first.
+    fn candidates(&self, pos: usize) -> impl Iterator<Item = usize> + '_ {
+        let link = |pos: usize| Some(self.next[pos]).filter(|&next| next != Self::NONE).map(|next| next as usize);
+        std::iter::successors(link(pos), move |&next| link(next))
+    }
+}
+
 /// Represents LZ77 tokens of a compressed stream.
 pub struct Tokens<'a> {
     tokens: Vec<Token<'a>>,
@@ -250,7 +341,7 @@ pub enum Lz77ParseError {
 }
 
 impl<'a> Tokens<'a> {
-    fn find_match(bytes: &[u8], pos: usize) -> Option<Pair> {
+    fn find_match_naive(bytes: &[u8], pos: usize) -> Option<Pair> {
         let max_lookahead = (LOOKAHEAD + MAX_SUBSEQUENCE).min(bytes.len() - pos - 1);
         (MIN_SUBSEQUENCE - 1..max_lookahead)
             .fold(None, |best_pair, i| {
@@ -277,8 +368,37 @@ impl<'a> Tokens<'a> {
             .and_then(|p| (p.length >= MIN_SUBSEQUENCE).then_some(p))
     }
 
-    fn compress(bytes: &'a [u8]) -> Self {
+    /// Returns the same match as [`Self::find_match_naive`], the longest one at the smallest distance, but only compares
+    /// against positions whose preceding 3 bytes have the same hash, as no other position can match at least 3 bytes.
+    fn find_match_hash_chain(bytes: &[u8], pos: usize, chain: &HashChain) -> Option<Pair> {
+        if pos < MIN_SUBSEQUENCE - 1 {
+            return None;
+        }
+        let max_distance = MAX_DISTANCE.min(bytes.len() - pos - 1);
+        let max_length = MAX_SUBSEQUENCE.min(pos + 1);
+        let mut best_pair: Option<Pair> = None;
+        for haystack in chain.candidates(pos) {
+            let distance = haystack - pos;
+            if distance > max_distance {
+                break;
+            }
+            if distance < MIN_SUBSEQUENCE {
+                continue;
+            }
+            let length = (0..max_length.min(distance)).take_while(|&i| bytes[pos - i] == bytes[haystack - i]).count();
+            if length > best_pair.map_or(MIN_SUBSEQUENCE - 1, |pair| pair.length) {
+                best_pair = Some(Pair { length, distance });
+                if length == max_length {
+                    break;
+                }
+            }
+        }
+        best_pair
+    }
+
+    fn compress(bytes: &'a [u8], match_finder: Lz77MatchFinder) -> Self {
         let mut tokens = vec![];
+        let chain = (match_finder == Lz77MatchFinder::HashChain).then(|| HashChain::new(bytes));
 
         let mut read = bytes.len();
         let mut bytes_saved = 0;
@@ -286,7 +406,11 @@ impl<'a> Tokens<'a> {
             if (tokens.len() % 8) == 0 {
                 bytes_saved -= 1;
             }
-            if let Some(pair) = Self::find_match(bytes, read - 1) {
+            let pair = match &chain {
+                Some(chain) => Self::find_match_hash_chain(bytes, read - 1, chain),
+                None => Self::find_match_naive(bytes, read - 1),
+            };
+            if let Some(pair) = pair {
                 read -= pair.length;
                 bytes_saved += pair.bytes_saved() as isize;
                 tokens.push(Token::Pair((pair, Cow::Borrowed(&bytes[read..read + pair.length]))));
@@ -1,4 +1,36 @@
 /// De/compression using Huffman coding.
 pub mod huffman;
+/// De/compression using the BIOS LZ77 format, type 0x10.
+pub mod lz10;
 /// De/compression using backwards LZ77.
 pub mod lz77;
+/// De/compression using the BIOS run-length encoding format, type 0x30.
+pub mod rle;
+
+/// Writes the header of a BIOS compression format. Sizes which are zero or don't fit in 24 bits are written after the header.
+fn write_header(compressed: &mut Vec<u8>, kind: u8, size: usize) {
+    if (1..=0xffffff).contains(&size) {
+        compressed.extend_from_slice(&(kind as u32 | (size as u32) << 8).to_le_bytes());
+    } else {
+        compressed.extend_from_slice(&(kind as u32).to_le_bytes());
+        compressed.extend_from_slice(&(size as u32).to_le_bytes());
+    }
+}
+
+/// Reads the header of a BIOS compression format and returns the decompressed size and the header size, or `None` if the
+/// header is missing or has a different type.
+fn read_header(bytes: &[u8], kind: u8) -> Option<(usize, usize)> {
+    let header = u32::from_le_bytes(bytes.get(0..4)?.try_into().unwrap());
+    if header as u8 != kind {
+        return None;
+    }
+    match (header >> 8) as usize {
+        0 => Some((u32::from_le_bytes(bytes.get(4..8)?.try_into().unwrap()) as usize, 8)),
+        size => Some((size, 4)),
+    }
+}
+
+/// Pads compressed data to a multiple of 4 bytes, as the BIOS functions read whole words.
+fn pad_to_word(compressed: &mut Vec<u8>) {
+    compressed.resize(compressed.len().next_multiple_of(4), 0);
+}
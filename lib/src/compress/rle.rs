@@ -0,0 +1,97 @@
+use std::backtrace::Backtrace;
+
+use snafu::{OptionExt, Snafu};
+
+use super::{pad_to_word, read_header, write_header};
+
+/// De/compresses data using the run-length encoding format of the DS BIOS, which has the type 0x30 in its header.
+pub struct Rle {}
+
+const TYPE: u8 = 0x30;
+const MIN_RUN: usize = 3;
+const MAX_RUN: usize = MIN_RUN + 0x7f;
+const MAX_LITERALS: usize = 0x80;
+
+/// Errors related to [`Rle::decompress`].
+#[derive(Debug, Snafu)]
+pub enum RleDecompressError {
+    /// Occurs when the data doesn't start with an RLE header.
+    #[snafu(display("missing RLE header:\n{backtrace}"))]
+    InvalidHeader {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the compressed data ends before the decompressed size is reached.
+    #[snafu(display(
+        "RLE data ended at offset {offset:#x} before the decompressed size {size:#x} was reached:\n{backtrace}"
+    ))]
+    Truncated {
+        /// Offset where the data ended.
+        offset: usize,
+        /// Decompressed size in the header.
+        size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+impl Rle {
+    /// Compresses `bytes` and returns the result, padded to a multiple of 4 bytes.
+    pub fn compress(&self, bytes: &[u8]) -> Box<[u8]> {
+        let mut compressed = Vec::with_capacity(bytes.len() + bytes.len() / MAX_LITERALS + 8);
+        write_header(&mut compressed, TYPE, bytes.len());
+
+        let mut literals_start = 0;
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let run = bytes[pos..].iter().take(MAX_RUN).take_while(|&&byte| byte == bytes[pos]).count();
+            if run < MIN_RUN {
+                pos += 1;
+                continue;
+            }
+            Self::write_literals(&mut compressed, &bytes[literals_start..pos]);
+            compressed.push(0x80 | (run - MIN_RUN) as u8);
+            compressed.push(bytes[pos]);
+            pos += run;
+            literals_start = pos;
+        }
+        Self::write_literals(&mut compressed, &bytes[literals_start..]);
+
+        pad_to_word(&mut compressed);
+        compressed.into_boxed_slice()
+    }
+
+    fn write_literals(compressed: &mut Vec<u8>, literals: &[u8]) {
+        for chunk in literals.chunks(MAX_LITERALS) {
+            compressed.push((chunk.len() - 1) as u8);
+            compressed.extend_from_slice(chunk);
+        }
+    }
+
+    /// Decompresses `bytes` and returns the result. Trailing bytes after the compressed data are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header is missing or the data is truncated.
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Box<[u8]>, RleDecompressError> {
+        let (size, mut offset) = read_header(bytes, TYPE).context(InvalidHeaderSnafu)?;
+        let mut decompressed = Vec::with_capacity(size);
+        while decompressed.len() < size {
+            let flag = *bytes.get(offset).context(TruncatedSnafu { offset, size })?;
+            offset += 1;
+            if flag & 0x80 != 0 {
+                let byte = *bytes.get(offset).context(TruncatedSnafu { offset, size })?;
+                let run = (flag & 0x7f) as usize + MIN_RUN;
+                decompressed.resize(decompressed.len() + run, byte);
+                offset += 1;
+            } else {
+                let length = (flag & 0x7f) as usize + 1;
+                let literals = bytes.get(offset..offset + length).context(TruncatedSnafu { offset, size })?;
+                decompressed.extend_from_slice(literals);
+                offset += length;
+            }
+        }
+        decompressed.truncate(size);
+        Ok(decompressed.into_boxed_slice())
+    }
+}
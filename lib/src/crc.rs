@@ -1,2 +1,4 @@
 /// CRC algorithm used for checksums in a ROM.
 pub const CRC_16_MODBUS: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_MODBUS);
+/// CRC algorithm used for checksums of whole ROM files, see [`crate::rom::RomHashReport`].
+pub const CRC_32_ISO_HDLC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
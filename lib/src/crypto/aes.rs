@@ -0,0 +1,20 @@
+use aes::cipher::{BlockEncrypt, KeyInit};
+
+/// Encrypts blocks with [AES-128](https://en.wikipedia.org/wiki/Advanced_Encryption_Standard). Only encryption is
+/// exposed, which is all that counter mode needs.
+#[derive(Clone)]
+pub struct Aes128 {
+    cipher: aes::Aes128,
+}
+
+impl Aes128 {
+    /// Creates a new [`Aes128`] which encrypts with `key`.
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self { cipher: aes::Aes128::new(key.into()) }
+    }
+
+    /// Encrypts a block in place.
+    pub fn encrypt_block(&self, block: &mut [u8; 16]) {
+        self.cipher.encrypt_block(block.into());
+    }
+}
@@ -7,6 +7,7 @@ use std::{
 use bytemuck::{Pod, Zeroable};
 use snafu::{Backtrace, Snafu};
 
+use super::sha1::Sha1;
 use crate::io::{open_file, FileError};
 
 /// De/encrypts data using the [Blowfish](https://en.wikipedia.org/wiki/Blowfish_(cipher)) block cipher.
@@ -212,4 +213,17 @@ impl BlowfishKey {
 
         Ok(Self(key))
     }
+
+    /// Returns the SHA-1 hash of this key, which identifies it without storing the key itself.
+    pub fn sha1(&self) -> [u8; 0x14] {
+        Sha1::digest(&self.0)
+    }
+
+    /// Returns the key in `keys` with the given SHA-1 hash, or the first key if `sha1` is `None`.
+    pub fn select<'k>(keys: &[&'k BlowfishKey], sha1: Option<&[u8; 0x14]>) -> Option<&'k BlowfishKey> {
+        match sha1 {
+            Some(sha1) => keys.iter().find(|key| key.sha1() == *sha1).copied(),
+            None => keys.first().copied(),
+        }
+    }
 }
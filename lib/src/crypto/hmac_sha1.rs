@@ -0,0 +1,29 @@
+use hmac::{Hmac, Mac};
+
+/// Computes [HMAC-SHA1](https://en.wikipedia.org/wiki/HMAC) signatures with a given key.
+#[derive(Clone)]
+pub struct HmacSha1 {
+    mac: Hmac<sha1::Sha1>,
+}
+
+impl HmacSha1 {
+    /// Creates a new [`HmacSha1`] which signs with `key`. Keys longer than 64 bytes are hashed first.
+    pub fn new(key: &[u8]) -> Self {
+        let mac = Hmac::new_from_slice(key).expect("HMAC key of any length is valid");
+        Self { mac }
+    }
+
+    /// Returns the signature of `data`.
+    pub fn compute(&self, data: &[u8]) -> [u8; 20] {
+        let mut mac = self.mac.clone();
+        mac.update(data);
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Returns whether `signature` is the signature of `data`.
+    pub fn verify(&self, data: &[u8], signature: &[u8; 20]) -> bool {
+        let mut mac = self.mac.clone();
+        mac.update(data);
+        mac.verify_slice(signature).is_ok()
+    }
+}
@@ -1,2 +1,10 @@
+/// Block encryption using AES-128.
+pub mod aes;
 /// De/encryption using Blowfish.
 pub mod blowfish;
+/// Signing using HMAC-SHA1.
+pub mod hmac_sha1;
+/// De/encryption of DSi modcrypt areas.
+pub mod modcrypt;
+/// Hashing using SHA-1.
+pub mod sha1;
@@ -0,0 +1,62 @@
+use super::aes::Aes128;
+
+/// Constant added when scrambling a key pair, see [`Modcrypt::scramble_key`].
+const KEY_SCRAMBLER: u128 = 0xfffefb4e295902582a680f5f1a4f3e79;
+
+/// De/encrypts the modcrypt areas of DSi ROMs, using AES-128 in counter mode.
+///
+/// The DSi AES engine treats keys and counters as little-endian 128-bit numbers and outputs each keystream block in
+/// reverse byte order, so the keys and counters here are given as they are stored in the ROM header. Counter mode is
+/// symmetric, so [`Self::apply`] both decrypts and encrypts.
+#[derive(Clone)]
+pub struct Modcrypt {
+    aes: Aes128,
+    counter: u128,
+}
+
+impl Modcrypt {
+    /// Creates a new [`Modcrypt`] with a normal key and the initial counter of a modcrypt area.
+    pub fn new(key: &[u8; 16], counter: &[u8; 16]) -> Self {
+        let aes = Aes128::new(&u128::from_le_bytes(*key).to_be_bytes());
+        Self { aes, counter: u128::from_le_bytes(*counter) }
+    }
+
+    /// Derives a normal key from a key pair using the DSi key scrambler.
+    pub fn scramble_key(key_x: &[u8; 16], key_y: &[u8; 16]) -> [u8; 16] {
+        let key_x = u128::from_le_bytes(*key_x);
+        let key_y = u128::from_le_bytes(*key_y);
+        (key_x ^ key_y).wrapping_add(KEY_SCRAMBLER).rotate_left(42).to_le_bytes()
+    }
+
+    /// Returns the normal key of a retail modcrypted ROM. Key X is "Nintendo" followed by the gamecode and the reversed
+    /// gamecode, and key Y is the first 16 bytes of the ARM9i SHA1-HMAC.
+    pub fn retail_key(gamecode: &[u8; 4], sha1_hmac_arm9i: &[u8; 0x14]) -> [u8; 16] {
+        let mut key_x = [0u8; 16];
+        key_x[..8].copy_from_slice(b"Nintendo");
+        key_x[8..12].copy_from_slice(gamecode);
+        key_x[12..].copy_from_slice(gamecode);
+        key_x[12..].reverse();
+        let key_y = sha1_hmac_arm9i[..16].try_into().unwrap();
+        Self::scramble_key(&key_x, &key_y)
+    }
+
+    /// XORs `data` with the keystream, starting `offset` bytes into the modcrypt area.
+    pub fn apply(&self, data: &mut [u8], offset: usize) {
+        let mut block_index = offset / 16;
+        let mut skip = offset % 16;
+        let mut data = data;
+        while !data.is_empty() {
+            let mut stream = self.counter.wrapping_add(block_index as u128).to_be_bytes();
+            self.aes.encrypt_block(&mut stream);
+            stream.reverse();
+
+            let len = (16 - skip).min(data.len());
+            for (byte, key) in data[..len].iter_mut().zip(&stream[skip..]) {
+                *byte ^= key;
+            }
+            data = &mut data[len..];
+            block_index += 1;
+            skip = 0;
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use ::sha1::Digest;
+
+/// Computes [SHA-1](https://en.wikipedia.org/wiki/SHA-1) hashes.
+#[derive(Clone, Default)]
+pub struct Sha1(::sha1::Sha1);
+
+impl Sha1 {
+    /// Creates a new [`Sha1`] hasher.
+    pub fn new() -> Self {
+        Self(::sha1::Sha1::new())
+    }
+
+    /// Returns the SHA-1 hash of `data`.
+    pub fn digest(data: &[u8]) -> [u8; 20] {
+        ::sha1::Sha1::digest(data).into()
+    }
+
+    /// Adds `data` to the hash.
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Finishes and returns the hash.
+    pub fn finish(self) -> [u8; 20] {
+        self.0.finalize().into()
+    }
+}
@@ -1,28 +1,90 @@
 use std::{
     backtrace::Backtrace,
     fs::{self, File, ReadDir},
-    io,
-    path::Path,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
 };
 
-use snafu::Snafu;
+use snafu::{ResultExt, Snafu};
 
+use crate::{crypto::sha1::Sha1, str::hex_string};
+
+/// Errors related to file system operations. Every variant names the file or directory which caused it.
 #[derive(Debug, Snafu)]
 pub enum FileError {
-    #[snafu(transparent)]
-    Io { source: io::Error },
+    /// Occurs for I/O errors which have no more specific variant.
+    #[snafu(visibility(pub(crate)), display("I/O error for '{path}': {source}:\n{backtrace}"))]
+    Io {
+        /// Path to the file or directory.
+        path: String,
+        /// Source error.
+        source: io::Error,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a file doesn't exist.
     #[snafu(display("the file '{path}' was not found:\n{backtrace}"))]
-    FileNotFound { path: String, backtrace: Backtrace },
+    FileNotFound {
+        /// Path to the file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when creating a file in a directory which doesn't exist.
     #[snafu(display("parent directory does not exist for file '{path}':\n{backtrace}"))]
-    FileParentNotFound { path: String, backtrace: Backtrace },
+    FileParentNotFound {
+        /// Path to the file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a directory doesn't exist.
     #[snafu(display("the directory '{path}' was not found:\n{backtrace}"))]
-    DirNotFound { path: String, backtrace: Backtrace },
-    #[snafu(display("failed to read file '{path}', ran out of memory:\n{backtrace}"))]
-    FileOutOfMemory { path: String, backtrace: Backtrace },
+    DirNotFound {
+        /// Path to the directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when running out of memory while reading a file.
     #[snafu(display("failed to read file '{path}', ran out of memory:\n{backtrace}"))]
-    DirOutOfMemory { path: String, backtrace: Backtrace },
+    FileOutOfMemory {
+        /// Path to the file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when running out of memory while reading a directory.
+    #[snafu(display("failed to read directory '{path}', ran out of memory:\n{backtrace}"))]
+    DirOutOfMemory {
+        /// Path to the directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a file which was loaded lazily has a different size when its contents are read.
+    #[snafu(
+        visibility(pub(crate)),
+        display("the file '{path}' changed size from {expected:#x} to {actual:#x} bytes since it was loaded:\n{backtrace}")
+    )]
+    FileSizeChanged {
+        /// Path to the file.
+        path: String,
+        /// Size when the file was loaded.
+        expected: usize,
+        /// Size when the file was read.
+        actual: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a file already exists.
     #[snafu(display("the file '{path}' already exists:\n{backtrace}"))]
-    AlreadyExists { path: String, backtrace: Backtrace },
+    AlreadyExists {
+        /// Path to the file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 /// Wrapper for [`File::open`] with clearer errors.
@@ -34,7 +96,7 @@ pub fn open_file<P: AsRef<Path>>(path: P) -> Result<File, FileError> {
             let path = path.to_string_lossy();
             match err.kind() {
                 io::ErrorKind::NotFound => return FileNotFoundSnafu { path }.fail(),
-                _ => Err(err)?,
+                _ => return Err(err).context(IoSnafu { path }),
             }
         }
     };
@@ -51,7 +113,7 @@ pub fn create_file<P: AsRef<Path>>(path: P) -> Result<File, FileError> {
             match err.kind() {
                 io::ErrorKind::AlreadyExists => return AlreadyExistsSnafu { path }.fail(),
                 io::ErrorKind::NotFound => return FileParentNotFoundSnafu { path }.fail(),
-                _ => Err(err)?,
+                _ => return Err(err).context(IoSnafu { path }),
             }
         }
     };
@@ -75,7 +137,7 @@ pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, FileError> {
             match err.kind() {
                 io::ErrorKind::NotFound => return FileNotFoundSnafu { path }.fail(),
                 io::ErrorKind::OutOfMemory => return FileOutOfMemorySnafu { path }.fail(),
-                _ => todo!(),
+                _ => return Err(err).context(IoSnafu { path }),
             }
         }
     };
@@ -92,13 +154,125 @@ pub fn write_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Resul
             let path = path.to_string_lossy();
             match err.kind() {
                 io::ErrorKind::AlreadyExists => return AlreadyExistsSnafu { path }.fail(),
-                _ => Err(err)?,
+                _ => return Err(err).context(IoSnafu { path }),
             }
         }
     };
     Ok(bytes)
 }
 
+/// Writes a file atomically: `write` is called with a temporary file in the same directory, which is synced to disk and
+/// renamed to `path` on success. On failure the temporary file is removed, so an existing file at `path` is left
+/// untouched and a partially written file is never left behind.
+///
+/// # Errors
+///
+/// This function will return an error if the temporary file can't be created, `write` fails, or the rename fails.
+pub fn write_file_atomic<P, F>(path: P, write: F) -> Result<(), FileError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let path = path.as_ref();
+    write_file_atomic_with(path, |file| write(file).context(IoSnafu { path: path.to_string_lossy() }))
+}
+
+/// Like [`write_file_atomic`], but `write` gets the temporary file itself so that it can seek, and may return a value or
+/// any error which a [`FileError`] converts into. Errors from `write` are returned as-is.
+///
+/// # Errors
+///
+/// This function will return an error if the temporary file can't be created, `write` fails, or syncing or renaming the
+/// file fails.
+pub fn write_file_atomic_with<P, T, E, F>(path: P, write: F) -> Result<T, E>
+where
+    P: AsRef<Path>,
+    E: From<FileError>,
+    F: FnOnce(&mut File) -> Result<T, E>,
+{
+    let path = path.as_ref();
+    let temp_path = atomic_temp_path(path);
+    let mut file = create_file(&temp_path)?;
+    let result = write(&mut file).and_then(|value| {
+        file.sync_all().context(IoSnafu { path: temp_path.to_string_lossy() })?;
+        Ok(value)
+    });
+    drop(file);
+    let result = result.and_then(|value| {
+        fs::rename(&temp_path, path).context(IoSnafu { path: path.to_string_lossy() })?;
+        Ok(value)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Returns the temporary path which [`write_file_atomic`] writes to before renaming it to `path`.
+pub fn atomic_temp_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()))
+}
+
+/// Returns the path of the fingerprint file next to `path`, which holds the SHA-1 hash of the file as it was written by a
+/// build. See [`write_build_fingerprint`].
+pub fn build_fingerprint_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{file_name}.sha1"))
+}
+
+/// Records the SHA-1 hash of the file at `path` in its fingerprint file, so that [`is_previous_build`] can later tell that
+/// the file was produced by a build and is safe to overwrite.
+///
+/// # Errors
+///
+/// This function will return an error if the file can't be read or the fingerprint file can't be written.
+pub fn write_build_fingerprint<P: AsRef<Path>>(path: P) -> Result<(), FileError> {
+    let path = path.as_ref();
+    let sha1 = file_sha1(path)?;
+    write_file(build_fingerprint_path(path), hex_string(&sha1))
+}
+
+/// Returns whether the file at `path` is unchanged since [`write_build_fingerprint`] was called for it. Files without a
+/// fingerprint, such as an original ROM, are never considered previous builds.
+///
+/// # Errors
+///
+/// This function will return an error if the file or its fingerprint file exist but can't be read.
+pub fn is_previous_build<P: AsRef<Path>>(path: P) -> Result<bool, FileError> {
+    let path = path.as_ref();
+    let fingerprint_path = build_fingerprint_path(path);
+    if !path.exists() || !fingerprint_path.exists() {
+        return Ok(false);
+    }
+    let fingerprint = read_to_string(&fingerprint_path)?;
+    Ok(fingerprint.trim().eq_ignore_ascii_case(&hex_string(&file_sha1(path)?)))
+}
+
+/// Computes the SHA-1 hash of a file without reading it all into memory.
+fn file_sha1(path: &Path) -> Result<[u8; 20], FileError> {
+    let mut file = open_file(path)?;
+    let mut sha1 = Sha1::new();
+    let mut buf = vec![0; 0x10000];
+    loop {
+        let len = file.read(&mut buf).context(IoSnafu { path: path.to_string_lossy() })?;
+        if len == 0 {
+            break;
+        }
+        sha1.update(&buf[..len]);
+    }
+    Ok(sha1.finish())
+}
+
+/// Writes a file using [`write_file`] and creates its parent directories using [`create_dir_all`].
+pub fn write_file_and_dirs<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<(), FileError> {
+    let path = path.as_ref();
+    create_dir_all(path.parent().unwrap())?;
+    write_file(path, contents)
+}
+
 /// Wrapper for [`fs::read_to_string`] with clearer errors.
 pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, FileError> {
     let path = path.as_ref();
@@ -109,7 +283,7 @@ pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, FileError> {
             match err.kind() {
                 io::ErrorKind::NotFound => return FileNotFoundSnafu { path }.fail(),
                 io::ErrorKind::OutOfMemory => return FileOutOfMemorySnafu { path }.fail(),
-                _ => Err(err)?,
+                _ => return Err(err).context(IoSnafu { path }),
             }
         }
     };
@@ -126,7 +300,7 @@ pub fn read_dir<P: AsRef<Path>>(path: P) -> Result<ReadDir, FileError> {
             match err.kind() {
                 io::ErrorKind::NotFound => return DirNotFoundSnafu { path }.fail(),
                 io::ErrorKind::OutOfMemory => return DirOutOfMemorySnafu { path }.fail(),
-                _ => Err(err)?,
+                _ => return Err(err).context(IoSnafu { path }),
             }
         }
     };
@@ -140,7 +314,7 @@ pub fn create_dir_all<P: AsRef<Path>>(path: P) -> Result<(), FileError> {
         let path = path.to_string_lossy();
         match err.kind() {
             io::ErrorKind::NotFound => return DirNotFoundSnafu { path }.fail(),
-            _ => Err(err)?,
+            _ => return Err(err).context(IoSnafu { path }),
         }
     }
     Ok(())
@@ -8,7 +8,10 @@ pub mod compress;
 pub mod crc;
 /// Encryption algorithms.
 pub mod crypto;
-pub(crate) mod io;
+/// File system wrappers with errors that name the file.
+pub mod io;
+/// Parallel processing utilities.
+pub mod parallel;
 /// ROM structs.
 pub mod rom;
 /// String utilities.
@@ -0,0 +1,115 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use snafu::{Backtrace, ResultExt, Snafu};
+
+/// Returns the number of threads to use when no concurrency limit is given, which is the available parallelism of the
+/// system, or 1 if it can't be determined.
+pub fn default_concurrency() -> NonZeroUsize {
+    thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Errors related to [`ThreadPool::new`].
+#[derive(Debug, Snafu)]
+pub enum ThreadPoolError {
+    /// Occurs when the worker threads could not be spawned.
+    #[snafu(display("failed to create a pool of {num_threads} threads: {source}:\n{backtrace}"))]
+    Build {
+        /// Number of threads in the pool.
+        num_threads: usize,
+        /// Source error.
+        source: rayon::ThreadPoolBuildError,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// A pool of worker threads, created once per operation like [`crate::rom::Rom::load`] and shared by all of its parallel
+/// stages.
+pub struct ThreadPool {
+    /// `None` if only one thread is used, in which case all work runs on the caller's thread.
+    pool: Option<rayon::ThreadPool>,
+    num_threads: usize,
+}
+
+impl ThreadPool {
+    /// Creates a pool of `concurrency` threads, or [`default_concurrency`] threads if `None`. No threads are spawned if
+    /// only one thread is needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the worker threads could not be spawned.
+    pub fn new(concurrency: Option<NonZeroUsize>) -> Result<Self, ThreadPoolError> {
+        let num_threads = concurrency.unwrap_or_else(default_concurrency).get();
+        let pool = if num_threads > 1 {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .thread_name(|index| format!("ds-rom-worker-{index}"))
+                .build()
+                .context(BuildSnafu { num_threads })?;
+            Some(pool)
+        } else {
+            None
+        };
+        Ok(Self { pool, num_threads })
+    }
+
+    /// Returns the number of threads in this pool.
+    pub fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    /// Calls `f` on each item using the threads of this pool. Each thread takes the next unprocessed item when it is done
+    /// with its current one, so that a few slow items don't hold up the rest. If only one thread is needed, the items are
+    /// processed on the caller's thread.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first error in item order if `f` fails on any item. Once an item has failed, no more
+    /// items are taken, but the items which other threads are already processing are finished.
+    pub fn try_for_each<T, E, F>(&self, items: &mut [T], f: F) -> Result<(), E>
+    where
+        T: Send,
+        E: Send,
+        F: Fn(&mut T) -> Result<(), E> + Sync,
+    {
+        let num_workers = self.num_threads.min(items.len());
+        let Some(pool) = self.pool.as_ref().filter(|_| num_workers > 1) else {
+            return items.iter_mut().try_for_each(f);
+        };
+
+        // Items are taken in order, so every item before a failed one has already been taken and can't have its error missed
+        let queue = Mutex::new(items.iter_mut().enumerate());
+        let failed = AtomicBool::new(false);
+        let first_error = Mutex::new(None::<(usize, E)>);
+        pool.scope(|scope| {
+            for _ in 0..num_workers {
+                scope.spawn(|_| loop {
+                    if failed.load(Ordering::Acquire) {
+                        break;
+                    }
+                    let Some((index, item)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    if let Err(error) = f(item) {
+                        failed.store(true, Ordering::Release);
+                        let mut first_error = first_error.lock().unwrap();
+                        if first_error.as_ref().is_none_or(|&(first_index, _)| index < first_index) {
+                            *first_error = Some((index, error));
+                        }
+                    }
+                });
+            }
+        });
+        match first_error.into_inner().unwrap() {
+            Some((_, error)) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::borrow::Cow;
 
 use serde::{Deserialize, Serialize};
 
+use super::FileSource;
+
 /// ARM7 program.
 pub struct Arm7<'a> {
     data: Cow<'a, [u8]>,
@@ -9,7 +11,7 @@ pub struct Arm7<'a> {
 }
 
 /// Offsets in the ARM7 program.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub struct Arm7Offsets {
     /// Base address.
     pub base_address: u32,
@@ -27,11 +29,26 @@ impl<'a> Arm7<'a> {
         Self { data: data.into(), offsets }
     }
 
+    /// Returns a copy of this [`Arm7`] which owns its data, so that it can outlive the buffer it was parsed from.
+    pub fn to_owned(&self) -> Arm7<'static> {
+        Arm7 { data: Cow::Owned(self.data.to_vec()), offsets: self.offsets }
+    }
+
+    /// Converts this [`Arm7`] into one which owns its data. Data which is already owned is moved instead of copied.
+    pub fn into_owned(self) -> Arm7<'static> {
+        Arm7 { data: Cow::Owned(self.data.into_owned()), offsets: self.offsets }
+    }
+
     /// Returns a reference to the full data.
     pub fn full_data(&self) -> &[u8] {
         &self.data
     }
 
+    /// Returns where the data of this program is stored, see [`FileSource`].
+    pub fn source(&self) -> FileSource {
+        FileSource::from(&self.data)
+    }
+
     /// Returns the base address of this.
     pub fn base_address(&self) -> u32 {
         self.offsets.base_address
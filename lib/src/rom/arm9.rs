@@ -1,16 +1,24 @@
-use std::{borrow::Cow, io, mem::replace, ops::Range};
+use std::{
+    borrow::Cow,
+    io,
+    mem::{replace, size_of},
+    ops::Range,
+};
 
 use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, Snafu};
 
 use super::{
-    raw::{AutoloadInfo, AutoloadKind, BuildInfo, RawAutoloadInfoError, RawBuildInfoError},
-    Autoload,
+    raw::{self, AutoloadInfo, AutoloadKind, BuildInfo, RawAutoloadInfoError, RawBuildInfoError},
+    Autoload, FileSource,
 };
 use crate::{
     compress::lz77::{Lz77, Lz77DecompressError},
     crc::CRC_16_MODBUS,
-    crypto::blowfish::{Blowfish, BlowfishError, BlowfishKey, BlowfishLevel},
+    crypto::{
+        blowfish::{Blowfish, BlowfishError, BlowfishKey, BlowfishLevel},
+        hmac_sha1::HmacSha1,
+    },
 };
 
 /// ARM9 program.
@@ -20,6 +28,8 @@ pub struct Arm9<'a> {
     offsets: Arm9Offsets,
     originally_compressed: bool,
     originally_encrypted: bool,
+    lenient_build_info: bool,
+    compression_start: usize,
 }
 
 /// Offsets in the ARM9 program.
@@ -33,6 +43,72 @@ pub struct Arm9Offsets {
     pub build_info: u32,
     /// Autoload callback address.
     pub autoload_callback: u32,
+    /// Location of the overlay signature table, if the ARM9 overlays are signed. See [`Arm9OverlaySignatures`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub overlay_signatures: Option<Arm9OverlaySignatures>,
+}
+
+/// Location of the overlay signature table, which holds the HMAC-SHA1 signature of each ARM9 overlay. The table is found
+/// through the third word of the ARM9 footer, see [`Arm9::detect_overlay_signatures`].
+///
+/// The table can be stored in an autoload block such as the ITCM, in which case its offset in the ARM9 program depends on
+/// the order of the autoload blocks. It is therefore located relative to the autoload block, so that it follows the block
+/// when the program is rebuilt by [`Arm9::with_autoloads`].
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Arm9OverlaySignatures {
+    /// How the ARM9 footer refers to the table.
+    pub footer_value: Arm9FooterValue,
+    /// Base address of the autoload block containing the table, or `None` if it's in the main ARM9 code.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub autoload: Option<u32>,
+    /// Offset of the table from the start of the main ARM9 code or of the autoload block.
+    pub offset: u32,
+}
+
+/// How a value in the ARM9 footer refers to a location in the ARM9 program.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Arm9FooterValue {
+    /// Offset from the start of the ARM9 program, as stored in the ROM.
+    RawOffset,
+    /// Address where the table is loaded in memory.
+    Address,
+}
+
+/// Fields of the ARM9 footer which can't be derived from the ARM9 program, normally found in arm9.yaml. The default value
+/// matches the footer generated by [`raw::Arm9Footer::new`].
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Arm9FooterConfig {
+    /// Build info offset to place in the footer, if it differs from [`Arm9Offsets::build_info`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub build_info_offset: Option<u32>,
+    /// Value after the build info offset, normally zero. Games which sign their overlays store the location of the overlay
+    /// signature table here, in which case it's generated from [`Arm9Offsets::overlay_signatures`] instead.
+    #[serde(default)]
+    pub reserved: u32,
+}
+
+impl Arm9FooterConfig {
+    /// Captures the fields of an original footer. The build info offset is only kept if it differs from `build_info_offset`,
+    /// so that it keeps following the ARM9 program otherwise.
+    pub fn from_raw(footer: &raw::Arm9Footer, build_info_offset: u32) -> Self {
+        Self {
+            build_info_offset: (footer.build_info_offset != build_info_offset).then_some(footer.build_info_offset),
+            reserved: footer.reserved,
+        }
+    }
+
+    /// Builds a raw footer, using `build_info_offset` unless it is overridden by this config.
+    pub fn build(&self, build_info_offset: u32) -> raw::Arm9Footer {
+        let mut footer = raw::Arm9Footer::new(self.build_info_offset.unwrap_or(build_info_offset));
+        footer.reserved = self.reserved;
+        footer
+    }
+
+    /// Returns whether this config would generate the default footer.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 const SECURE_AREA_ID: [u8; 8] = [0xff, 0xde, 0xff, 0xe7, 0xff, 0xde, 0xff, 0xe7];
@@ -40,21 +116,13 @@ const SECURE_AREA_ENCRY_OBJ: &[u8] = "encryObj".as_bytes();
 
 const LZ77: Lz77 = Lz77 {};
 
-const COMPRESSION_START: usize = 0x4000;
+/// Offset where LZ77 compression of the ARM9 program starts by default, which leaves the secure area uncompressed. See
+/// [`Arm9::compression_start`].
+pub const DEFAULT_COMPRESSION_START: usize = 0x4000;
 
 /// Errors related to [`Arm9`].
 #[derive(Debug, Snafu)]
 pub enum Arm9Error {
-    /// Occurs when the program is too small to contain a secure area.
-    #[snafu(display("expected {expected:#x} bytes for secure area but had only {actual:#x}:\n{backtrace}"))]
-    DataTooSmall {
-        /// Expected minimum size.
-        expected: usize,
-        /// Actual size.
-        actual: usize,
-        /// Backtrace to the source of the error.
-        backtrace: Backtrace,
-    },
     /// See [`BlowfishError`].
     #[snafu(transparent)]
     Blowfish {
@@ -85,6 +153,16 @@ pub enum Arm9Error {
         /// Source error.
         source: io::Error,
     },
+    /// Occurs when the compression start is past the end of the ARM9 program, see [`Arm9::compression_start`].
+    #[snafu(display("ARM9 compression start {start:#x} is past the end of the program ({length:#x} bytes):\n{backtrace}"))]
+    CompressionStartOutOfBounds {
+        /// Compression start.
+        start: usize,
+        /// Length of the ARM9 program.
+        length: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 /// Errors related to ARM9 autoloads.
@@ -116,37 +194,249 @@ pub enum Arm9AutoloadError {
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
+    /// Occurs when the overlay signature table is in an autoload block which doesn't exist in the ARM9 program.
+    #[snafu(display(
+        "no autoload block is loaded to {base_address:#x}, which contains the overlay signature table:\n{backtrace}"
+    ))]
+    OverlaySignaturesAutoloadNotFound {
+        /// Base address of the autoload block, see [`Arm9OverlaySignatures::autoload`].
+        base_address: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the overlay signature table extends past the end of the ARM9 program.
+    #[snafu(display(
+        "overlay signature table at {start:#x}..{end:#x} extends past the end of the ARM9 program ({length:#x} bytes):\n{backtrace}"
+    ))]
+    OverlaySignaturesOutOfBounds {
+        /// Start offset of the table.
+        start: usize,
+        /// End offset of the table.
+        end: usize,
+        /// Length of the ARM9 program.
+        length: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to changing the build info or offsets of an [`Arm9`].
+#[derive(Debug, Snafu)]
+pub enum Arm9BuildInfoError {
+    /// See [`RawBuildInfoError`].
+    #[snafu(transparent)]
+    RawBuildInfo {
+        /// Source error.
+        source: RawBuildInfoError,
+    },
+    /// Occurs when trying to change the .bss section while the ARM9 program is compressed.
+    #[snafu(display("ARM9 program must be decompressed before changing the .bss section:\n{backtrace}"))]
+    BssCompressed {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the end of the .bss section is before its start.
+    #[snafu(display(".bss end {bss_end:#x} is before .bss start {bss_start:#x}:\n{backtrace}"))]
+    BssEndBeforeStart {
+        /// Start of the .bss section.
+        bss_start: u32,
+        /// End of the .bss section.
+        bss_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the .bss section would start before the end of the build info, which must be part of the code.
+    #[snafu(display(".bss start {bss_start:#x} is before the end of the build info at {build_info_end:#x}:\n{backtrace}"))]
+    BssStartBeforeBuildInfo {
+        /// Start of the .bss section.
+        bss_start: u32,
+        /// End address of the build info.
+        build_info_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the .bss section would start past the autoload blocks, so that the code would include them.
+    #[snafu(display(
+        ".bss start {bss_start:#x} is past the start of the autoload blocks at {autoload_blocks:#x}:\n{backtrace}"
+    ))]
+    BssStartPastAutoloads {
+        /// Start of the .bss section.
+        bss_start: u32,
+        /// Start of the autoload blocks.
+        autoload_blocks: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the autoload blocks and autoload info table are out of order or past the end of the program data.
+    #[snafu(display(
+        "autoload blocks at {autoload_blocks:#x} and autoload info table at {autoload_infos_start:#x}..{autoload_infos_end:#x} don't fit in the program data ending at {data_end:#x}:\n{backtrace}"
+    ))]
+    AutoloadTablesOutOfBounds {
+        /// Start of the autoload blocks.
+        autoload_blocks: u32,
+        /// Start of the autoload info table.
+        autoload_infos_start: u32,
+        /// End of the autoload info table.
+        autoload_infos_end: u32,
+        /// End address of the program data.
+        data_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the autoload callback is outside of the code.
+    #[snafu(display(
+        "autoload callback {address:#x} is outside of the code at {code_start:#x}..{code_end:#x}:\n{backtrace}"
+    ))]
+    AutoloadCallbackOutOfBounds {
+        /// Autoload callback address.
+        address: u32,
+        /// Start address of the code.
+        code_start: u32,
+        /// End address of the code.
+        code_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// A boundary between regions of an [`Arm9`] program, see [`Arm9::markers`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Arm9Marker {
+    /// Address of the boundary.
+    pub address: u32,
+    /// Description of the boundary.
+    pub label: String,
+}
+
+/// Where an address in the ARM9 address space is stored, see [`Arm9::locate`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Arm9Location {
+    /// The address is in the main ARM9 code, before the autoload blocks.
+    Main {
+        /// Offset from the base address.
+        offset: u32,
+    },
+    /// The address is in the code of an autoload block.
+    Autoload {
+        /// Index of the autoload block, see [`Arm9::autoloads`].
+        index: usize,
+        /// Kind of autoload.
+        kind: AutoloadKind,
+        /// Offset from the base address of the autoload block.
+        offset: u32,
+    },
 }
 
 /// Options for [`Arm9::with_two_tcms`].
+#[derive(Default)]
 pub struct Arm9WithTcmsOptions {
     /// Whether the program was compressed originally.
     pub originally_compressed: bool,
     /// Whether the program was encrypted originally.
     pub originally_encrypted: bool,
+    /// See [`Arm9::lenient_build_info`].
+    pub lenient_build_info: bool,
+    /// See [`Arm9::compression_start`], defaults to [`DEFAULT_COMPRESSION_START`] if `None`.
+    pub compression_start: Option<usize>,
+}
+
+/// State of the secure area, the first 0x4000 bytes of an ARM9 program, see [`Arm9::secure_area_state`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecureAreaState {
+    /// The secure area is encrypted, as in retail ROMs.
+    Encrypted,
+    /// The secure area is decrypted and starts with the secure area ID.
+    Decrypted,
+    /// There is no secure area, as in homebrew and some prototypes. The program is either too small to contain one or its
+    /// first 0x4000 bytes are zero, so there is nothing to encrypt or decrypt.
+    Absent,
+}
+
+/// How [`Arm9::probe_compression`] determined whether an ARM9 program is compressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arm9CompressionProbe {
+    /// The build info is valid, and its compressed code end tells whether the program is compressed.
+    BuildInfo(bool),
+    /// The build info can't be trusted, so the program is compressed if it ends with a plausible LZ77 footer.
+    Lz77Footer(bool),
+}
+
+impl Arm9CompressionProbe {
+    /// Returns whether the program is compressed.
+    pub fn is_compressed(self) -> bool {
+        match self {
+            Self::BuildInfo(compressed) | Self::Lz77Footer(compressed) => compressed,
+        }
+    }
 }
 
 impl<'a> Arm9<'a> {
     /// Creates a new ARM9 program from raw data.
+    ///
+    /// If the build info has a missing or corrupted nitrocode, as in some prototype dumps, this logs a warning and enables
+    /// [`Self::lenient_build_info`] instead of failing.
     pub fn new<T: Into<Cow<'a, [u8]>>>(data: T, offsets: Arm9Offsets) -> Result<Self, RawBuildInfoError> {
-        let mut arm9 = Arm9 { data: data.into(), offsets, originally_compressed: false, originally_encrypted: false };
+        let mut arm9 = Arm9 {
+            data: data.into(),
+            offsets,
+            originally_compressed: false,
+            originally_encrypted: false,
+            lenient_build_info: false,
+            compression_start: DEFAULT_COMPRESSION_START,
+        };
+        if let Err(RawBuildInfoError::NoNitrocode { actual, .. }) = arm9.build_info() {
+            log::warn!(
+                "ARM9 build info has no valid nitrocode (found {actual:#x}), using the LZ77 footer to detect compression \
+                 and preserving the build info as-is"
+            );
+            arm9.lenient_build_info = true;
+        }
         arm9.originally_compressed = arm9.is_compressed()?;
         arm9.originally_encrypted = arm9.is_encrypted();
+        if arm9.originally_compressed {
+            if let Some(compression_start) = arm9.detect_compression_start() {
+                arm9.compression_start = compression_start;
+            }
+        }
         Ok(arm9)
     }
 
+    /// Returns a copy of this [`Arm9`] which owns its data, so that it can outlive the buffer it was parsed from.
+    pub fn to_owned(&self) -> Arm9<'static> {
+        Arm9 {
+            data: Cow::Owned(self.data.to_vec()),
+            offsets: self.offsets,
+            originally_compressed: self.originally_compressed,
+            originally_encrypted: self.originally_encrypted,
+            lenient_build_info: self.lenient_build_info,
+            compression_start: self.compression_start,
+        }
+    }
+
+    /// Converts this [`Arm9`] into one which owns its data. Data which is already owned is moved instead of copied.
+    pub fn into_owned(self) -> Arm9<'static> {
+        Arm9 {
+            data: Cow::Owned(self.data.into_owned()),
+            offsets: self.offsets,
+            originally_compressed: self.originally_compressed,
+            originally_encrypted: self.originally_encrypted,
+            lenient_build_info: self.lenient_build_info,
+            compression_start: self.compression_start,
+        }
+    }
+
     /// Creates a new ARM9 program with raw data and two autoloads (ITCM and DTCM).
     ///
     /// # Errors
     ///
-    /// See [`Self::build_info_mut`].
+    /// See [`Self::build_info_mut`]. This function will also return an error if the autoload info table doesn't fit.
     pub fn with_two_tcms(
         mut data: Vec<u8>,
         itcm: Autoload,
         dtcm: Autoload,
         offsets: Arm9Offsets,
         options: Arm9WithTcmsOptions,
-    ) -> Result<Self, RawBuildInfoError> {
+    ) -> Result<Self, Arm9BuildInfoError> {
         let autoload_infos = [itcm.info().clone(), dtcm.info().clone()];
 
         let autoload_blocks = data.len() as u32 + offsets.base_address;
@@ -156,13 +446,18 @@ impl<'a> Arm9<'a> {
         data.extend(bytemuck::bytes_of(&autoload_infos));
         let autoload_infos_end = data.len() as u32 + offsets.base_address;
 
-        let Arm9WithTcmsOptions { originally_compressed, originally_encrypted } = options;
-        let mut arm9 = Self { data: data.into(), offsets, originally_compressed, originally_encrypted };
-
-        let build_info = arm9.build_info_mut()?;
-        build_info.autoload_blocks = autoload_blocks;
-        build_info.autoload_infos_start = autoload_infos_start;
-        build_info.autoload_infos_end = autoload_infos_end;
+        let Arm9WithTcmsOptions { originally_compressed, originally_encrypted, lenient_build_info, compression_start } =
+            options;
+        let compression_start = compression_start.unwrap_or(DEFAULT_COMPRESSION_START);
+        let mut arm9 = Self {
+            data: data.into(),
+            offsets,
+            originally_compressed,
+            originally_encrypted,
+            lenient_build_info,
+            compression_start,
+        };
+        arm9.set_autoload_tables(autoload_blocks, autoload_infos_start..autoload_infos_end)?;
 
         Ok(arm9)
     }
@@ -171,13 +466,13 @@ impl<'a> Arm9<'a> {
     ///
     /// # Errors
     ///
-    /// See [`Self::build_info_mut`].
+    /// See [`Self::build_info_mut`]. This function will also return an error if the autoload info table doesn't fit.
     pub fn with_autoloads(
         mut data: Vec<u8>,
         autoloads: &[Autoload],
         offsets: Arm9Offsets,
         options: Arm9WithTcmsOptions,
-    ) -> Result<Self, RawBuildInfoError> {
+    ) -> Result<Self, Arm9BuildInfoError> {
         let autoload_blocks = data.len() as u32 + offsets.base_address;
 
         for autoload in autoloads {
@@ -190,38 +485,49 @@ impl<'a> Arm9<'a> {
         }
         let autoload_infos_end = data.len() as u32 + offsets.base_address;
 
-        let Arm9WithTcmsOptions { originally_compressed, originally_encrypted } = options;
-        let mut arm9 = Self { data: data.into(), offsets, originally_compressed, originally_encrypted };
-
-        let build_info = arm9.build_info_mut()?;
-        build_info.autoload_blocks = autoload_blocks;
-        build_info.autoload_infos_start = autoload_infos_start;
-        build_info.autoload_infos_end = autoload_infos_end;
+        let Arm9WithTcmsOptions { originally_compressed, originally_encrypted, lenient_build_info, compression_start } =
+            options;
+        let compression_start = compression_start.unwrap_or(DEFAULT_COMPRESSION_START);
+        let mut arm9 = Self {
+            data: data.into(),
+            offsets,
+            originally_compressed,
+            originally_encrypted,
+            lenient_build_info,
+            compression_start,
+        };
+        arm9.set_autoload_tables(autoload_blocks, autoload_infos_start..autoload_infos_end)?;
 
         Ok(arm9)
     }
 
+    /// Returns whether the secure area is encrypted, absent or decrypted.
+    pub fn secure_area_state(&self) -> SecureAreaState {
+        if self.data.len() < 0x4000 || self.data[0..0x4000].iter().all(|&b| b == 0) {
+            SecureAreaState::Absent
+        } else if self.data[0..8] == SECURE_AREA_ID {
+            SecureAreaState::Decrypted
+        } else {
+            SecureAreaState::Encrypted
+        }
+    }
+
     /// Returns whether the secure area is encrypted. See [`Self::originally_encrypted`] for whether the secure area was
     /// encrypted originally.
     pub fn is_encrypted(&self) -> bool {
-        self.data.len() < 8 || self.data[0..8] != SECURE_AREA_ID
+        self.secure_area_state() == SecureAreaState::Encrypted
     }
 
-    /// Decrypts the secure area. Does nothing if already decrypted.
+    /// Decrypts the secure area. Does nothing if already decrypted or if there is no secure area.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the program is too small to contain a secure area, [`Blowfish::decrypt`] fails or
-    /// "encryObj" was not found.
+    /// This function will return an error if [`Blowfish::decrypt`] fails or "encryObj" was not found.
     pub fn decrypt(&mut self, key: &BlowfishKey, gamecode: u32) -> Result<(), Arm9Error> {
         if !self.is_encrypted() {
             return Ok(());
         }
 
-        if self.data.len() < 0x4000 {
-            DataTooSmallSnafu { expected: 0x4000usize, actual: self.data.len() }.fail()?;
-        }
-
         let mut secure_area = [0u8; 0x4000];
         secure_area.clone_from_slice(&self.data[0..0x4000]);
 
@@ -240,23 +546,30 @@ impl<'a> Arm9<'a> {
         Ok(())
     }
 
-    /// Encrypts the secure area. Does nothing if already encrypted.
+    /// Decrypts the secure area with the first key in `keys` that succeeds, and returns its index. Returns `None` if already
+    /// decrypted. This is useful for titles which may need a different key, such as iQue DS titles.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the program is too small to contain a secure area, or the secure area ID was not
-    /// found.
-    pub fn encrypt(&mut self, key: &BlowfishKey, gamecode: u32) -> Result<(), Arm9Error> {
-        if self.is_encrypted() {
-            return Ok(());
+    /// See [`Self::decrypt`]. If "encryObj" was not found with any of the keys, [`Arm9Error::NotEncryObj`] is returned.
+    pub fn decrypt_with_any_key(&mut self, keys: &[&BlowfishKey], gamecode: u32) -> Result<Option<usize>, Arm9Error> {
+        if !self.is_encrypted() {
+            return Ok(None);
         }
-
-        if self.data.len() < 0x4000 {
-            DataTooSmallSnafu { expected: 0x4000usize, actual: self.data.len() }.fail()?;
+        for (index, key) in keys.iter().enumerate() {
+            match self.decrypt(key, gamecode) {
+                Ok(()) => return Ok(Some(index)),
+                Err(Arm9Error::NotEncryObj { .. }) => continue,
+                Err(e) => return Err(e),
+            }
         }
+        NotEncryObjSnafu {}.fail()
+    }
 
-        if self.data[0..8] != SECURE_AREA_ID {
-            NotEncryObjSnafu {}.fail()?;
+    /// Encrypts the secure area. Does nothing if already encrypted or if there is no secure area.
+    pub fn encrypt(&mut self, key: &BlowfishKey, gamecode: u32) -> Result<(), Arm9Error> {
+        if self.secure_area_state() != SecureAreaState::Decrypted {
+            return Ok(());
         }
 
         let secure_area = self.encrypted_secure_area(key, gamecode);
@@ -264,11 +577,13 @@ impl<'a> Arm9<'a> {
         Ok(())
     }
 
-    /// Returns an encrypted copy of the secure area.
+    /// Returns an encrypted copy of the secure area. If there is no secure area, the first 0x4000 bytes are returned as-is,
+    /// padded with zeros.
     pub fn encrypted_secure_area(&self, key: &BlowfishKey, gamecode: u32) -> [u8; 0x4000] {
         let mut secure_area = [0u8; 0x4000];
-        secure_area.copy_from_slice(&self.data[0..0x4000]);
-        if self.is_encrypted() {
+        let size = self.data.len().min(0x4000);
+        secure_area[..size].copy_from_slice(&self.data[..size]);
+        if self.secure_area_state() != SecureAreaState::Decrypted {
             return secure_area;
         }
 
@@ -283,8 +598,11 @@ impl<'a> Arm9<'a> {
         secure_area
     }
 
-    /// Returns a CRC checksum of the encrypted secure area.
+    /// Returns a CRC checksum of the encrypted secure area, or 0 if there is no secure area.
     pub fn secure_area_crc(&self, key: &BlowfishKey, gamecode: u32) -> u16 {
+        if self.secure_area_state() == SecureAreaState::Absent {
+            return 0;
+        }
         let secure_area = self.encrypted_secure_area(key, gamecode);
         let checksum = CRC_16_MODBUS.checksum(&secure_area);
         checksum
@@ -296,7 +614,12 @@ impl<'a> Arm9<'a> {
     ///
     /// See [`BuildInfo::borrow_from_slice`].
     pub fn build_info(&self) -> Result<&BuildInfo, RawBuildInfoError> {
-        BuildInfo::borrow_from_slice(&self.data[self.offsets.build_info as usize..])
+        let data = &self.data[self.offsets.build_info as usize..];
+        if self.lenient_build_info {
+            BuildInfo::borrow_from_slice_lenient(data)
+        } else {
+            BuildInfo::borrow_from_slice(data)
+        }
     }
 
     /// Returns a mutable reference to the build info.
@@ -305,17 +628,158 @@ impl<'a> Arm9<'a> {
     ///
     /// See [`BuildInfo::borrow_from_slice_mut`].
     pub fn build_info_mut(&mut self) -> Result<&mut BuildInfo, RawBuildInfoError> {
-        BuildInfo::borrow_from_slice_mut(&mut self.data.to_mut()[self.offsets.build_info as usize..])
+        let data = &mut self.data.to_mut()[self.offsets.build_info as usize..];
+        if self.lenient_build_info {
+            BuildInfo::borrow_from_slice_lenient_mut(data)
+        } else {
+            BuildInfo::borrow_from_slice_mut(data)
+        }
+    }
+
+    fn data_end(&self) -> u32 {
+        self.base_address() + self.data.len() as u32
+    }
+
+    fn set_autoload_tables(&mut self, autoload_blocks: u32, autoload_infos: Range<u32>) -> Result<(), Arm9BuildInfoError> {
+        let data_end = self.data_end();
+        if autoload_blocks > autoload_infos.start || autoload_infos.start > autoload_infos.end || autoload_infos.end > data_end
+        {
+            return AutoloadTablesOutOfBoundsSnafu {
+                autoload_blocks,
+                autoload_infos_start: autoload_infos.start,
+                autoload_infos_end: autoload_infos.end,
+                data_end,
+            }
+            .fail();
+        }
+        let build_info = self.build_info_mut()?;
+        build_info.autoload_blocks = autoload_blocks;
+        build_info.autoload_infos_start = autoload_infos.start;
+        build_info.autoload_infos_end = autoload_infos.end;
+        Ok(())
+    }
+
+    /// Sets the .bss section of this ARM9 program. The start of the range is also the end of the code, so it must be
+    /// after the build info and no later than the autoload blocks, which normally start right after the code.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if [`Self::build_info_mut`] fails, this ARM9 program is compressed, or the range
+    /// violates the invariants above.
+    pub fn set_bss(&mut self, bss: Range<u32>) -> Result<(), Arm9BuildInfoError> {
+        if self.is_compressed()? {
+            return BssCompressedSnafu {}.fail();
+        }
+        if bss.end < bss.start {
+            return BssEndBeforeStartSnafu { bss_start: bss.start, bss_end: bss.end }.fail();
+        }
+        let build_info_end = self.base_address() + self.build_info_offset() + size_of::<BuildInfo>() as u32;
+        if bss.start < build_info_end {
+            return BssStartBeforeBuildInfoSnafu { bss_start: bss.start, build_info_end }.fail();
+        }
+        let autoload_blocks = self.build_info()?.autoload_blocks;
+        if bss.start > autoload_blocks {
+            return BssStartPastAutoloadsSnafu { bss_start: bss.start, autoload_blocks }.fail();
+        }
+
+        let build_info = self.build_info_mut()?;
+        build_info.bss_start = bss.start;
+        build_info.bss_end = bss.end;
+        Ok(())
+    }
+
+    /// Sets the SDK version in the build info.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::build_info_mut`].
+    pub fn set_sdk_version(&mut self, sdk_version: u32) -> Result<(), RawBuildInfoError> {
+        self.build_info_mut()?.sdk_version = sdk_version;
+        Ok(())
+    }
+
+    /// Sets the autoload callback address in [`Self::offsets`]. The address must be within the code, ignoring the Thumb
+    /// bit.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if [`Self::build_info`] fails or the address is outside of the code.
+    pub fn set_autoload_callback(&mut self, address: u32) -> Result<(), Arm9BuildInfoError> {
+        let code_start = self.base_address();
+        let code_end = self.build_info()?.bss_start;
+        if !(code_start..code_end).contains(&(address & !1)) {
+            return AutoloadCallbackOutOfBoundsSnafu { address, code_start, code_end }.fail();
+        }
+        self.offsets.autoload_callback = address;
+        Ok(())
     }
 
     /// Returns whether this ARM9 program is compressed. See [`Self::originally_compressed`] for whether the program was
-    /// compressed originally.
+    /// compressed originally, and [`Self::probe_compression`] for how it's determined.
     ///
     /// # Errors
     ///
     /// See [`Self::build_info`].
     pub fn is_compressed(&self) -> Result<bool, RawBuildInfoError> {
-        Ok(self.build_info()?.is_compressed())
+        if self.lenient_build_info {
+            Ok(self.probe_compression().is_compressed())
+        } else {
+            Ok(self.build_info()?.is_compressed())
+        }
+    }
+
+    /// Determines whether this ARM9 program is compressed, using the build info if it's valid and the LZ77 footer at the end
+    /// of the program otherwise. A footer is only accepted if the uncompressed part covers the secure area, as compressed
+    /// ARM9 programs start compression after it.
+    pub fn probe_compression(&self) -> Arm9CompressionProbe {
+        if !self.lenient_build_info {
+            if let Ok(build_info) = self.build_info() {
+                return Arm9CompressionProbe::BuildInfo(build_info.is_compressed());
+            }
+        }
+        let compressed =
+            LZ77.footer(&self.data).is_some_and(|footer| self.data.len() - footer.total_size >= DEFAULT_COMPRESSION_START);
+        Arm9CompressionProbe::Lz77Footer(compressed)
+    }
+
+    /// Returns whether the build info is accepted without a valid nitrocode. In this mode, compression is detected by
+    /// [`Self::probe_compression`], and [`Self::compress`] and [`Self::decompress`] leave the build info untouched so that
+    /// its original bytes are preserved.
+    pub fn lenient_build_info(&self) -> bool {
+        self.lenient_build_info
+    }
+
+    /// Sets [`Self::lenient_build_info`].
+    pub fn set_lenient_build_info(&mut self, lenient_build_info: bool) {
+        self.lenient_build_info = lenient_build_info;
+    }
+
+    /// Returns the offset where LZ77 compression starts when compressing this ARM9 program. The bytes before it, which
+    /// include the secure area, are stored uncompressed. This is detected from the original program when it was compressed,
+    /// and is [`DEFAULT_COMPRESSION_START`] otherwise.
+    pub fn compression_start(&self) -> usize {
+        self.compression_start
+    }
+
+    /// Sets [`Self::compression_start`]. It is validated by [`Self::compress`], which fails if it's past the end of the
+    /// program.
+    pub fn set_compression_start(&mut self, compression_start: usize) {
+        self.compression_start = compression_start;
+    }
+
+    /// Detects where compression started in this compressed ARM9 program, using its LZ77 footer. The footer tells how many
+    /// bytes at the start are stored uncompressed, which is the compression start followed by any bytes the compressor
+    /// left as-is because compressing them saved no space. Those bytes are far fewer than [`DEFAULT_COMPRESSION_START`],
+    /// so the start is rounded down to a multiple of it. Returns `None` if there is no plausible LZ77 footer.
+    ///
+    /// If [`DEFAULT_COMPRESSION_START`] or more bytes after the real start are incompressible, they are indistinguishable
+    /// from the bytes before it and the detected start is too high. Compressing from the detected start still produces a
+    /// valid program, but it may not match the original byte for byte. In that case, the real start must be set with
+    /// [`Self::set_compression_start`].
+    pub fn detect_compression_start(&self) -> Option<usize> {
+        let footer = LZ77.footer(&self.data)?;
+        let uncompressed_size = self.data.len() - footer.total_size;
+        Some(uncompressed_size / DEFAULT_COMPRESSION_START * DEFAULT_COMPRESSION_START)
     }
 
     /// Decompresses this ARM9 program. Does nothing if already decompressed.
@@ -329,6 +793,10 @@ impl<'a> Arm9<'a> {
         }
 
         let data: Cow<[u8]> = LZ77.decompress(&self.data)?.into_vec().into();
+        if self.lenient_build_info {
+            self.data = data;
+            return Ok(());
+        }
         let old_data = replace(&mut self.data, data);
         let build_info = match self.build_info_mut() {
             Ok(build_info) => build_info,
@@ -345,13 +813,31 @@ impl<'a> Arm9<'a> {
     ///
     /// # Errors
     ///
-    /// See [`Self::is_compressed`], [`Lz77::compress`] and [`Self::build_info_mut`].
+    /// See [`Self::is_compressed`], [`Lz77::compress`] and [`Self::build_info_mut`]. This function will also return an
+    /// error if [`Self::compression_start`] is past the end of the program.
     pub fn compress(&mut self) -> Result<(), Arm9Error> {
         if self.is_compressed()? {
             return Ok(());
         }
+        if self.compression_start > self.data.len() {
+            return CompressionStartOutOfBoundsSnafu { start: self.compression_start, length: self.data.len() }.fail();
+        }
+
+        let data = LZ77.compress(&self.data, self.compression_start)?.into_vec();
+        self.set_compressed_data(data)
+    }
 
-        let data: Cow<[u8]> = LZ77.compress(&self.data, COMPRESSION_START)?.into_vec().into();
+    /// Replaces the data of this ARM9 program with `data`, which was compressed previously, such as by a cached build.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::build_info_mut`].
+    pub(crate) fn set_compressed_data(&mut self, data: Vec<u8>) -> Result<(), Arm9Error> {
+        let data: Cow<[u8]> = data.into();
+        if self.lenient_build_info {
+            self.data = data;
+            return Ok(());
+        }
         let length = data.len();
         let old_data = replace(&mut self.data, data);
         let base_address = self.base_address();
@@ -413,6 +899,204 @@ impl<'a> Arm9<'a> {
         Ok(autoloads.into_boxed_slice())
     }
 
+    /// Returns the boundaries of the code, autoload blocks, autoload infos and build info of this [`Arm9`], sorted by address.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::autoloads`].
+    pub fn markers(&self) -> Result<Vec<Arm9Marker>, Arm9AutoloadError> {
+        let build_info = self.build_info()?;
+        let autoloads = self.autoloads()?;
+        let mut markers = vec![];
+        let mut marker = |address: u32, label: String| markers.push(Arm9Marker { address, label });
+
+        let build_info_address = self.base_address() + self.build_info_offset();
+        marker(build_info_address, "Build info start".to_string());
+        marker(build_info_address + size_of::<BuildInfo>() as u32, "Build info end".to_string());
+
+        let mut address = build_info.autoload_blocks;
+        for autoload in autoloads.iter() {
+            let kind = autoload.kind();
+            let base_address = autoload.base_address();
+            marker(address, format!("Autoload block {kind} start (loads to {base_address:#x})"));
+            address += autoload.code().len() as u32;
+            marker(address, format!("Autoload block {kind} end"));
+        }
+
+        marker(build_info.autoload_infos_start, "Autoload info table start".to_string());
+        marker(build_info.autoload_infos_end, "Autoload info table end".to_string());
+        marker(build_info.bss_start, "Code end (.bss start)".to_string());
+
+        markers.sort_by_key(|marker| marker.address);
+        Ok(markers)
+    }
+
+    /// Returns where a virtual address is stored, which is either the main ARM9 code or the code of the autoload block
+    /// which loads to that address. Unlike an offset into [`Self::full_data`], the returned location stays valid if the
+    /// autoload blocks are reordered by [`Self::with_autoloads`]. Returns `None` if no code is loaded to the address.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::autoloads`].
+    pub fn locate(&self, address: u32) -> Result<Option<Arm9Location>, Arm9AutoloadError> {
+        let build_info = self.build_info()?;
+        let autoloads = self.autoloads()?;
+        if (self.base_address()..build_info.autoload_blocks).contains(&address) {
+            return Ok(Some(Arm9Location::Main { offset: address - self.base_address() }));
+        }
+        let location = autoloads.iter().enumerate().find_map(|(index, autoload)| {
+            let offset = address.checked_sub(autoload.base_address())?;
+            (offset < autoload.code().len() as u32).then_some(Arm9Location::Autoload { index, kind: autoload.kind(), offset })
+        });
+        Ok(location)
+    }
+
+    /// Returns where a raw offset into [`Self::full_data`] is stored, which is either the main ARM9 code or the autoload block
+    /// whose data is at that offset. Returns `None` if the offset is past the autoload blocks.
+    fn locate_raw_offset(&self, offset: u32) -> Result<Option<Arm9Location>, Arm9AutoloadError> {
+        let build_info = self.build_info()?;
+        let mut load_offset = build_info.autoload_blocks - self.base_address();
+        if offset < load_offset {
+            return Ok(Some(Arm9Location::Main { offset }));
+        }
+        for (index, autoload_info) in self.autoload_infos()?.iter().enumerate() {
+            if (load_offset..load_offset + autoload_info.code_size).contains(&offset) {
+                return Ok(Some(Arm9Location::Autoload { index, kind: autoload_info.kind(), offset: offset - load_offset }));
+            }
+            load_offset += autoload_info.code_size;
+        }
+        Ok(None)
+    }
+
+    /// Detects the location of the overlay signature table from the third word of the ARM9 footer. The footer can hold
+    /// either an address or a raw offset into the program: a value in the address range of the main code or of an
+    /// autoload block is an address, and other values are raw offsets. Returns `None` if the value is zero or points
+    /// outside the program.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::autoloads`].
+    pub fn detect_overlay_signatures(&self, footer_value: u32) -> Result<Option<Arm9OverlaySignatures>, Arm9AutoloadError> {
+        if footer_value == 0 {
+            return Ok(None);
+        }
+        let (kind, location) = if let Some(location) = self.locate(footer_value)? {
+            (Arm9FooterValue::Address, location)
+        } else if let Some(location) = self.locate_raw_offset(footer_value)? {
+            (Arm9FooterValue::RawOffset, location)
+        } else {
+            return Ok(None);
+        };
+        let autoload_infos = self.autoload_infos()?;
+        let signatures = match location {
+            Arm9Location::Main { offset } => Arm9OverlaySignatures { footer_value: kind, autoload: None, offset },
+            Arm9Location::Autoload { index, offset, .. } => {
+                Arm9OverlaySignatures { footer_value: kind, autoload: Some(autoload_infos[index].base_address), offset }
+            }
+        };
+        Ok(Some(signatures))
+    }
+
+    /// Returns the location of the overlay signature table, see [`Arm9OverlaySignatures`].
+    pub fn overlay_signatures(&self) -> Option<Arm9OverlaySignatures> {
+        self.offsets.overlay_signatures
+    }
+
+    /// Sets the location of the overlay signature table, see [`Self::detect_overlay_signatures`].
+    pub fn set_overlay_signatures(&mut self, overlay_signatures: Option<Arm9OverlaySignatures>) {
+        self.offsets.overlay_signatures = overlay_signatures;
+    }
+
+    /// Returns the offset of the overlay signature table in [`Self::full_data`], following the autoload block which contains
+    /// it. Returns `None` if there is no overlay signature table.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if [`Self::autoload_infos`] fails or the autoload block containing the table
+    /// doesn't exist.
+    pub fn overlay_signatures_offset(&self) -> Result<Option<usize>, Arm9AutoloadError> {
+        let Some(overlay_signatures) = self.offsets.overlay_signatures else { return Ok(None) };
+        let Some(base_address) = overlay_signatures.autoload else {
+            return Ok(Some(overlay_signatures.offset as usize));
+        };
+        let build_info = self.build_info()?;
+        let mut load_offset = build_info.autoload_blocks - self.base_address();
+        for autoload_info in self.autoload_infos()? {
+            if autoload_info.base_address == base_address {
+                return Ok(Some((load_offset + overlay_signatures.offset) as usize));
+            }
+            load_offset += autoload_info.code_size;
+        }
+        OverlaySignaturesAutoloadNotFoundSnafu { base_address }.fail()
+    }
+
+    /// Returns the range of the overlay signature table in [`Self::full_data`], which holds one signature for each of the
+    /// `num_overlays` ARM9 overlays. See [`Self::overlay_signatures_offset`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::overlay_signatures_offset`]. This function will also return an error if the table extends past the end
+    /// of the program.
+    pub fn overlay_signatures_range(&self, num_overlays: usize) -> Result<Option<Range<usize>>, Arm9AutoloadError> {
+        let Some(start) = self.overlay_signatures_offset()? else { return Ok(None) };
+        let end = start + num_overlays * size_of::<[u8; 0x14]>();
+        if end > self.data.len() {
+            return OverlaySignaturesOutOfBoundsSnafu { start, end, length: self.data.len() }.fail();
+        }
+        Ok(Some(start..end))
+    }
+
+    /// Returns the overlay signature table, which holds `num_signatures` HMAC-SHA1 signatures. These are the signatures of the
+    /// ARM9 overlays in table order, followed by the signature of the ARM9 overlay table. Returns `None` if there is no
+    /// overlay signature table.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::overlay_signatures_range`].
+    pub fn overlay_signature_slots(&self, num_signatures: usize) -> Result<Option<&[[u8; 0x14]]>, Arm9AutoloadError> {
+        let Some(range) = self.overlay_signatures_range(num_signatures)? else { return Ok(None) };
+        Ok(Some(bytemuck::cast_slice(&self.data[range])))
+    }
+
+    /// Returns a mutable reference to the overlay signature table, see [`Self::overlay_signature_slots`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::overlay_signatures_range`].
+    pub fn overlay_signature_slots_mut(
+        &mut self,
+        num_signatures: usize,
+    ) -> Result<Option<&mut [[u8; 0x14]]>, Arm9AutoloadError> {
+        let Some(range) = self.overlay_signatures_range(num_signatures)? else { return Ok(None) };
+        Ok(Some(bytemuck::cast_slice_mut(&mut self.data.to_mut()[range])))
+    }
+
+    /// Searches this ARM9 program for the 64-byte HMAC-SHA1 key which signs `data` as `signature`, such as the ARM9 overlay
+    /// table and its signature from [`Self::overlay_signature_slots`]. Only keys at 4-byte aligned offsets are considered.
+    /// The program must be decompressed first.
+    pub fn hmac_sha1_key(&self, data: &[u8], signature: &[u8; 0x14]) -> Option<[u8; 0x40]> {
+        (0..self.data.len().saturating_sub(0x3f))
+            .step_by(4)
+            .map(|offset| -> [u8; 0x40] { self.data[offset..offset + 0x40].try_into().unwrap() })
+            .find(|key| HmacSha1::new(key).verify(data, signature))
+    }
+
+    /// Returns the value to place in the third word of the ARM9 footer, which refers to the overlay signature table as it
+    /// did in the original ROM. Returns `None` if there is no overlay signature table.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::overlay_signatures_offset`].
+    pub fn overlay_signatures_footer_value(&self) -> Result<Option<u32>, Arm9AutoloadError> {
+        let Some(overlay_signatures) = self.offsets.overlay_signatures else { return Ok(None) };
+        match overlay_signatures.footer_value {
+            Arm9FooterValue::RawOffset => Ok(self.overlay_signatures_offset()?.map(|offset| offset as u32)),
+            Arm9FooterValue::Address => {
+                Ok(Some(overlay_signatures.autoload.unwrap_or(self.base_address()) + overlay_signatures.offset))
+            }
+        }
+    }
+
     /// Returns the number of unknown autoloads of this [`Arm9`].
     ///
     /// # Errors
@@ -437,6 +1121,11 @@ impl<'a> Arm9<'a> {
         &self.data
     }
 
+    /// Returns where the data of this program is stored, see [`FileSource`].
+    pub fn source(&self) -> FileSource {
+        FileSource::from(&self.data)
+    }
+
     /// Returns the base address.
     pub fn base_address(&self) -> u32 {
         self.offsets.base_address
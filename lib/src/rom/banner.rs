@@ -1,20 +1,26 @@
 use std::{
+    fmt::Display,
     io,
+    mem::size_of,
     path::{Path, PathBuf},
 };
 
-use image::{io::Reader, GenericImageView, ImageError, Rgb, RgbImage};
+use image::{io::Reader, DynamicImage, GenericImageView, ImageError, Rgb, RgbImage, Rgba};
 use serde::{Deserialize, Serialize};
-use snafu::{Backtrace, Snafu};
+use snafu::{Backtrace, ResultExt, Snafu};
 
 use super::{
     raw::{self, BannerBitmap, BannerPalette, BannerVersion, Language},
     ImageSize,
 };
-use crate::{crc::CRC_16_MODBUS, str::Unicode16Array};
+use crate::{
+    crc::CRC_16_MODBUS,
+    io::{read_file, read_to_string, write_file, write_file_and_dirs, FileError},
+    str::{Unicode16Array, Unicode16Error},
+};
 
 /// ROM banner.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Banner {
     version: BannerVersion,
     /// Game title in different languages.
@@ -24,8 +30,121 @@ pub struct Banner {
     /// Keyframes for animated icons.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub keyframes: Option<Vec<BannerKeyframe>>,
+    /// Text files which override the titles in [`Self::title`], see [`Self::load_title_files`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title_files: Option<BannerTitleFiles>,
+    /// CRCs recorded when extracting, see [`BannerCrcs`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub crcs: Option<BannerCrcs>,
+}
+
+/// CRCs of a [`Banner`] as they were in the ROM it was extracted from. They are not used when building, since the CRCs are
+/// always recomputed, but they tell whether the banner has changed since extracting. See [`Banner::crc_mismatches`].
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BannerCrcs {
+    /// CRC of the titles and icon of the original version.
+    pub original: u16,
+    /// CRC which also covers the Chinese title, if the banner has it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub china: Option<u16>,
+    /// CRC which also covers the Korean title, if the banner has it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub korea: Option<u16>,
+    /// CRC of the animated icon, if the banner has it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub animated: Option<u16>,
+}
+
+impl BannerCrcs {
+    /// Reads the CRCs stored in a raw banner. CRCs which the banner version doesn't have are `None`.
+    pub fn from_raw(banner: &raw::Banner) -> Self {
+        let crc = |version: BannerVersion| (banner.version() >= version).then(|| banner.crc(version.crc_index()));
+        Self {
+            original: banner.crc(BannerVersion::Original.crc_index()),
+            china: crc(BannerVersion::China),
+            korea: crc(BannerVersion::Korea),
+            animated: crc(BannerVersion::Animated),
+        }
+    }
+
+    /// Returns the CRC of the given version, or `None` if there is none.
+    pub fn get(&self, version: BannerVersion) -> Option<u16> {
+        match version {
+            BannerVersion::Original => Some(self.original),
+            BannerVersion::China => self.china,
+            BannerVersion::Korea => self.korea,
+            BannerVersion::Animated => self.animated,
+        }
+    }
+}
+
+/// A CRC in [`Banner::crcs`] which differs from the recomputed one, see [`Banner::crc_mismatches`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BannerCrcMismatch {
+    /// Version which the CRC belongs to.
+    pub version: BannerVersion,
+    /// CRC recorded when extracting.
+    pub recorded: u16,
+    /// CRC of the banner as it is now.
+    pub computed: u16,
+}
+
+impl Display for BannerCrcMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} banner CRC changed from {:#06x} to {:#06x}, the banner differs from the extracted one",
+            self.version, self.recorded, self.computed
+        )
+    }
+}
+
+/// Errors related to [`Banner::load_title_files`].
+#[derive(Debug, Snafu)]
+pub enum BannerTitleFileError {
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// Occurs when a title file has more lines than fit in the banner.
+    #[snafu(display("{language} title in '{}' has {lines} lines but the maximum is {MAX_TITLE_LINES}:\n{backtrace}", path.display()))]
+    TooManyLines {
+        /// Language of the title.
+        language: Language,
+        /// Path to the title file.
+        path: PathBuf,
+        /// Number of lines in the title.
+        lines: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a title file does not fit in the banner.
+    #[snafu(display("invalid {language} title in '{}': {source}", path.display()))]
+    InvalidTitle {
+        /// Language of the title.
+        language: Language,
+        /// Path to the title file.
+        path: PathBuf,
+        /// Source error.
+        source: Unicode16Error,
+    },
 }
 
+const MAX_TITLE_LINES: usize = 3;
+
+pub(crate) const LANGUAGES: [Language; 8] = [
+    Language::Japanese,
+    Language::English,
+    Language::French,
+    Language::German,
+    Language::Italian,
+    Language::Spanish,
+    Language::Chinese,
+    Language::Korean,
+];
+
 /// Errors related to [`Banner`].
 #[derive(Debug, Snafu)]
 pub enum BannerError {
@@ -45,75 +164,178 @@ pub enum BannerError {
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
-    /// Occurs when trying to build a banner to place in the ROM, but the version is not yet supported by this library.
-    #[snafu(display("maximum supported banner version is currently {max} but got {actual}:\n{backtrace}"))]
-    VersionNotSupported {
-        /// Max supported version.
-        max: BannerVersion,
-        /// Actual version.
-        actual: BannerVersion,
-        /// Backtrace to the source of the error.
-        backtrace: Backtrace,
+    /// Occurs when a title does not fit in the banner.
+    #[snafu(display("invalid {language} title: {source}"))]
+    Title {
+        /// Language of the title.
+        language: Language,
+        /// Source error.
+        source: Unicode16Error,
     },
 }
 
 impl Banner {
-    fn load_title(banner: &raw::Banner, version: BannerVersion, language: Language) -> Option<String> {
+    /// Returns the version of this [`Banner`].
+    pub fn version(&self) -> BannerVersion {
+        self.version
+    }
+
+    fn load_title<'b>(
+        banner: &'b raw::Banner,
+        version: BannerVersion,
+        language: Language,
+    ) -> Option<&'b Unicode16Array<0x80>> {
         if version.supports_language(language) {
-            if let Some(title) = banner.title(language) {
-                Some(title.to_string())
-            } else {
-                None
-            }
+            banner.title(language)
         } else {
             None
         }
     }
 
+    fn load_raw_title(banner: &raw::Banner, version: BannerVersion, language: Language) -> Option<Vec<u16>> {
+        let title = Self::load_title(banner, version, language)?;
+        if title.is_valid_utf16() {
+            None
+        } else {
+            log::warn!("{language} banner title contains invalid UTF-16, preserving its raw code units");
+            Some(title.units().to_vec())
+        }
+    }
+
     /// Loads from a raw banner.
     pub fn load_raw(banner: &raw::Banner) -> Self {
         let version = banner.version();
+        let title = |language| Self::load_title(banner, version, language).map(|title| title.to_string());
+        let raw_title = |language| Self::load_raw_title(banner, version, language);
+        let mut images = BannerImages::from_bitmap(*banner.bitmap(), *banner.palette());
+        let keyframes = banner.animation().map(|animation| {
+            let keyframes = BannerKeyframe::load_raw_list(&animation.keyframes);
+            images.set_animation(animation.bitmaps, animation.palettes, &keyframes);
+            keyframes
+        });
         Self {
             version,
             title: BannerTitle {
-                japanese: Self::load_title(banner, version, Language::Japanese).unwrap(),
-                english: Self::load_title(banner, version, Language::English).unwrap(),
-                french: Self::load_title(banner, version, Language::French).unwrap(),
-                german: Self::load_title(banner, version, Language::German).unwrap(),
-                italian: Self::load_title(banner, version, Language::Italian).unwrap(),
-                spanish: Self::load_title(banner, version, Language::Spanish).unwrap(),
-                chinese: Self::load_title(banner, version, Language::Chinese),
-                korean: Self::load_title(banner, version, Language::Korean),
+                japanese: title(Language::Japanese).unwrap(),
+                english: title(Language::English).unwrap(),
+                french: title(Language::French).unwrap(),
+                german: title(Language::German).unwrap(),
+                italian: title(Language::Italian).unwrap(),
+                spanish: title(Language::Spanish).unwrap(),
+                chinese: title(Language::Chinese),
+                korean: title(Language::Korean),
+                japanese_raw_utf16: raw_title(Language::Japanese),
+                english_raw_utf16: raw_title(Language::English),
+                french_raw_utf16: raw_title(Language::French),
+                german_raw_utf16: raw_title(Language::German),
+                italian_raw_utf16: raw_title(Language::Italian),
+                spanish_raw_utf16: raw_title(Language::Spanish),
+                chinese_raw_utf16: raw_title(Language::Chinese),
+                korean_raw_utf16: raw_title(Language::Korean),
             },
-            images: BannerImages::from_bitmap(*banner.bitmap(), *banner.palette()),
-            keyframes: None,
+            images,
+            keyframes,
+            title_files: None,
+            crcs: Some(BannerCrcs::from_raw(banner)),
         }
     }
 
+    /// Reads the files in [`Self::title_files`] from the banner directory `path`, and replaces the titles with their
+    /// contents. Each file contains a UTF-8 title of up to three lines, and a trailing newline is ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a file can't be read, or its title doesn't fit in the banner.
+    pub fn load_title_files(&mut self, path: &Path) -> Result<(), BannerTitleFileError> {
+        let Some(title_files) = &self.title_files else {
+            return Ok(());
+        };
+        for language in LANGUAGES {
+            let Some(file_name) = title_files.get(language) else {
+                continue;
+            };
+            let path = path.join(file_name);
+            let contents = read_to_string(&path)?.replace("\r\n", "\n");
+            let title = contents.strip_suffix('\n').unwrap_or(&contents);
+            let lines = title.split('\n').count();
+            if lines > MAX_TITLE_LINES {
+                return TooManyLinesSnafu { language, path, lines }.fail();
+            }
+            title.parse::<Unicode16Array<0x80>>().context(InvalidTitleSnafu { language, path: &path })?;
+            self.title.set(language, title.to_string());
+        }
+        Ok(())
+    }
+
+    /// Writes each title to a file named like `title_en.txt` in the banner directory `path`, and lists them in
+    /// [`Self::title_files`]. Titles which are not valid UTF-16 stay inline, see [`BannerTitle::english_raw_utf16`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a file can't be written.
+    pub fn save_title_files(&mut self, path: &Path) -> Result<(), FileError> {
+        let mut title_files = BannerTitleFiles::default();
+        for language in LANGUAGES {
+            if self.title.raw_mut(language).is_some() {
+                continue;
+            }
+            let Some(title) = self.title.get(language) else {
+                continue;
+            };
+            let file_name = PathBuf::from(format!("title_{}.txt", BannerTitleFiles::language_code(language)));
+            write_file_and_dirs(path.join(&file_name), format!("{title}\n"))?;
+            *title_files.get_mut(language) = Some(file_name);
+        }
+        self.title_files = Some(title_files);
+        Ok(())
+    }
+
     fn crc(&self, banner: &mut raw::Banner, version: BannerVersion) {
         if self.version >= version {
             *banner.crc_mut(version.crc_index()) = CRC_16_MODBUS.checksum(&banner.full_data()[version.crc_range()]);
         }
     }
 
-    /// Builds a raw banner to place in a ROM.
+    /// Builds a raw banner to place in a ROM. Logs each CRC which differs from [`Self::crcs`].
     ///
     /// # Errors
     ///
-    /// This function will return an error if the banner version is not yet supported by this library, or there are too many
-    /// keyframes.
-    pub fn build(&self) -> Result<raw::Banner, BannerError> {
-        // TODO: Increase max version to Animated
-        // The challenge is to convert the animated icon to indexed bitmaps. Each bitmap can use any of the 8 palettes at any
-        // given time according to the keyframes. This means that to convert the PNG animation frames to indexed bitmaps, we
-        // may need more than 8 PNG files if a palette is reused on multiple bitmaps. Then we have to deduplicate indexed
-        // bitmaps with precisely the same indexes. Not very efficient, but it may be our only option for modern image formats.
-        if self.version > BannerVersion::Korea {
-            return VersionNotSupportedSnafu { max: BannerVersion::Korea, actual: self.version }.fail();
+    /// This function will return an error if a title is too long, or there are too many keyframes.
+    pub fn build(&self) -> Result<raw::Banner<'static>, BannerError> {
+        let banner = self.build_raw()?;
+        for mismatch in self.crc_mismatches_in(&banner) {
+            log::info!("{mismatch}");
         }
+        Ok(banner)
+    }
+
+    /// Recomputes the CRCs and returns the ones which differ from [`Self::crcs`]. Returns an empty list if no CRCs were
+    /// recorded.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::build`].
+    pub fn crc_mismatches(&self) -> Result<Vec<BannerCrcMismatch>, BannerError> {
+        Ok(self.crc_mismatches_in(&self.build_raw()?))
+    }
 
+    fn crc_mismatches_in(&self, banner: &raw::Banner) -> Vec<BannerCrcMismatch> {
+        let Some(recorded) = &self.crcs else {
+            return vec![];
+        };
+        let computed = BannerCrcs::from_raw(banner);
+        [BannerVersion::Original, BannerVersion::China, BannerVersion::Korea, BannerVersion::Animated]
+            .into_iter()
+            .filter_map(|version| {
+                let (recorded, computed) = (recorded.get(version)?, computed.get(version)?);
+                (recorded != computed).then_some(BannerCrcMismatch { version, recorded, computed })
+            })
+            .collect()
+    }
+
+    fn build_raw(&self) -> Result<raw::Banner<'static>, BannerError> {
         let mut banner = raw::Banner::new(self.version);
-        self.title.copy_to_banner(&mut banner);
+        self.title.copy_to_banner(&mut banner)?;
 
         *banner.bitmap_mut() = self.images.bitmap;
         *banner.palette_mut() = self.images.palette;
@@ -122,13 +344,17 @@ impl Banner {
             if keyframes.len() > 64 {
                 TooManyKeyframesSnafu { max: 64usize, actual: keyframes.len() }.fail()?;
             }
-
-            let animation = banner.animation_mut().unwrap();
-            for i in 0..keyframes.len() {
-                animation.keyframes[i] = keyframes[i].build();
+        }
+        if let Some(animation) = banner.animation_mut() {
+            if let Some(bitmaps) = &self.images.animation_bitmaps {
+                animation.bitmaps.iter_mut().zip(bitmaps.iter()).for_each(|(dest, bitmap)| *dest = *bitmap);
             }
-            for i in keyframes.len()..64 {
-                animation.keyframes[i] = raw::BannerKeyframe::new();
+            if let Some(palettes) = &self.images.animation_palettes {
+                animation.palettes.iter_mut().zip(palettes.iter()).for_each(|(dest, palette)| *dest = *palette);
+            }
+            let keyframes = self.keyframes.as_deref().unwrap_or_default();
+            for (i, keyframe) in animation.keyframes.iter_mut().enumerate() {
+                *keyframe = keyframes.get(i).map_or(raw::BannerKeyframe::new(), BannerKeyframe::build);
             }
         }
 
@@ -142,7 +368,7 @@ impl Banner {
 }
 
 /// Icon for the [`Banner`].
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone)]
 pub struct BannerImages {
     /// Main bitmap.
     #[serde(skip)]
@@ -161,6 +387,71 @@ pub struct BannerImages {
     pub bitmap_path: PathBuf,
     /// Path to palette PNG.
     pub palette_path: PathBuf,
+    /// How to handle bitmap colors which are not in the palette, see [`BannerRemapPolicy`].
+    #[serde(skip_serializing_if = "BannerRemapPolicy::is_strict", default)]
+    pub remap_policy: BannerRemapPolicy,
+    /// Path to a raw copy of the bitmap and palette, used if the PNGs are missing. See [`Self::load`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub raw_path: Option<PathBuf>,
+    /// Where the bitmap and palette were loaded from by [`Self::load`].
+    #[serde(skip)]
+    pub source: BannerImageSource,
+
+    /// Bitmap PNGs of the animated icon, only present if the banner has one.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub animation_bitmap_files: Vec<BannerAnimationBitmapFile>,
+    /// Paths to the palette PNGs of the animated icon.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub animation_palette_paths: Vec<PathBuf>,
+    /// Path to a raw copy of the animation bitmaps and palettes. Pixels and colors which are unchanged in the PNGs keep their
+    /// raw values, so that palette indexes are preserved even if a palette repeats a color. Also used if the PNGs are missing.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub animation_raw_path: Option<PathBuf>,
+}
+
+/// A bitmap PNG of an animated icon, see [`BannerImages::animation_bitmap_files`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BannerAnimationBitmapFile {
+    /// Path to the bitmap PNG.
+    pub path: PathBuf,
+    /// Index of the animation palette which the PNG is drawn with. A bitmap can be shown with any palette, so this is the
+    /// palette of the first keyframe which shows the bitmap.
+    pub palette: usize,
+}
+
+/// Number of bitmaps and palettes in an animated icon.
+const NUM_ANIMATION_IMAGES: usize = 8;
+
+/// Where [`BannerImages::load`] loaded the bitmap and palette from.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum BannerImageSource {
+    /// The bitmap and palette PNGs.
+    #[default]
+    Png,
+    /// The raw bitmap and palette in [`BannerImages::raw_path`], because the PNGs were missing.
+    Raw,
+}
+
+/// How [`BannerImages::load`] handles bitmap colors which are not in the palette.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BannerRemapPolicy {
+    /// Fails with [`BannerImageError::InvalidPixel`] at the first color which is not in the palette.
+    #[default]
+    Strict,
+    /// Maps each color which is not in the palette to the nearest palette color, and warns with the number of remapped
+    /// pixels.
+    NearestColor,
+    /// Ignores the palette file and builds a new 16-color palette from the bitmap using median cut, then saves it to the
+    /// palette file. The color of the top-left pixel is kept as palette index 0, which is transparent.
+    Quantize,
+}
+
+impl BannerRemapPolicy {
+    /// Returns whether this is [`Self::Strict`].
+    pub fn is_strict(&self) -> bool {
+        *self == Self::Strict
+    }
 }
 
 /// Errors related to [`BannerImages`].
@@ -188,6 +479,44 @@ pub enum BannerImageError {
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// Occurs when the raw bitmap and palette file has the wrong size.
+    #[snafu(display("raw banner icon '{}' must be {expected:#x} bytes but got {actual:#x} bytes:\n{backtrace}", path.display()))]
+    WrongRawSize {
+        /// Path to the raw file.
+        path: PathBuf,
+        /// Expected size.
+        expected: usize,
+        /// Actual size.
+        actual: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when an animated icon has more bitmap or palette files than fit in the banner.
+    #[snafu(display("animated banner icon can have at most {NUM_ANIMATION_IMAGES} {kind} but got {actual}:\n{backtrace}"))]
+    TooManyAnimationImages {
+        /// Kind of image, `bitmaps` or `palettes`.
+        kind: String,
+        /// Actual amount.
+        actual: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when an animation bitmap is drawn with a palette which doesn't exist.
+    #[snafu(display("animated banner icon {bitmap:?} uses palette {palette} which doesn't exist:\n{backtrace}"))]
+    AnimationPaletteNotFound {
+        /// Path to the bitmap.
+        bitmap: PathBuf,
+        /// Palette index.
+        palette: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
     /// Occurs when the bitmap has a pixel not present in the palette.
     #[snafu(display("banner icon {bitmap:?} contains a pixel at {x},{y} which is not present in the palette:\n{backtrace}"))]
     InvalidPixel {
@@ -212,83 +541,349 @@ impl BannerImages {
             animation_palettes: None,
             bitmap_path: "bitmap.png".into(),
             palette_path: "palette.png".into(),
+            remap_policy: BannerRemapPolicy::default(),
+            raw_path: Some("icon.bin".into()),
+            source: BannerImageSource::default(),
+            animation_bitmap_files: vec![],
+            animation_palette_paths: vec![],
+            animation_raw_path: None,
         }
     }
 
-    /// Loads the bitmap and palette
+    /// Sets the bitmaps and palettes of the animated icon, and names their files like `anim_bitmap_0.png`,
+    /// `anim_palette_0.png` and `anim_icon.bin`. Each bitmap PNG is drawn with the palette of the first keyframe which shows
+    /// it, or the first palette if no keyframe does.
+    pub fn set_animation(
+        &mut self,
+        bitmaps: [BannerBitmap; NUM_ANIMATION_IMAGES],
+        palettes: [BannerPalette; NUM_ANIMATION_IMAGES],
+        keyframes: &[BannerKeyframe],
+    ) {
+        self.animation_bitmap_files = (0..NUM_ANIMATION_IMAGES)
+            .map(|i| BannerAnimationBitmapFile {
+                path: format!("anim_bitmap_{i}.png").into(),
+                palette: keyframes.iter().find(|keyframe| keyframe.bitmap == i).map_or(0, |keyframe| keyframe.palette),
+            })
+            .collect();
+        self.animation_palette_paths = (0..NUM_ANIMATION_IMAGES).map(|i| format!("anim_palette_{i}.png").into()).collect();
+        self.animation_raw_path = Some("anim_icon.bin".into());
+        self.animation_bitmaps = Some(bitmaps.into());
+        self.animation_palettes = Some(palettes.into());
+    }
+
+    /// Returns the paths of every file which [`Self::save_bitmap_file`] writes, relative to the banner directory.
+    pub fn file_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        [&self.bitmap_path, &self.palette_path]
+            .into_iter()
+            .chain(&self.raw_path)
+            .chain(self.animation_bitmap_files.iter().map(|file| &file.path))
+            .chain(&self.animation_palette_paths)
+            .chain(&self.animation_raw_path)
+    }
+
+    /// Loads the bitmap and palette, and the animated icon if there is one. Colors which are not in the palette are handled
+    /// according to [`Self::remap_policy`], except that the animated icon can't be quantized.
+    ///
+    /// If the PNGs are missing but [`Self::raw_path`] exists, the raw bitmap and palette are loaded from it instead. The PNGs
+    /// always take priority, and [`Self::source`] is set to where the images were loaded from. The animated icon falls back
+    /// to [`Self::animation_raw_path`] in the same way.
     ///
     /// # Errors
     ///
     /// This function will return an error if [`Reader::open`] or [`Reader::decode`] fails, or if the images are the wrong
-    /// size, or the bitmap has a color not present in the palette.
+    /// size, or the bitmap has a color not present in the palette and the remap policy is [`BannerRemapPolicy::Strict`].
     pub fn load(&mut self, path: &Path) -> Result<(), BannerImageError> {
-        let bitmap_image = Reader::open(path.join(&self.bitmap_path))?.decode()?;
-        if bitmap_image.width() != 32 || bitmap_image.height() != 32 {
+        self.load_icon(path)?;
+        if !self.animation_bitmap_files.is_empty() || !self.animation_palette_paths.is_empty() {
+            self.load_animation(path)?;
+        }
+        Ok(())
+    }
+
+    fn open_image(path: &Path, width: u32, height: u32) -> Result<DynamicImage, BannerImageError> {
+        let image = Reader::open(path)?.decode()?;
+        if image.width() != width || image.height() != height {
             return WrongSizeSnafu {
-                expected: ImageSize { width: 32, height: 32 },
-                actual: ImageSize { width: bitmap_image.width(), height: bitmap_image.height() },
+                expected: ImageSize { width, height },
+                actual: ImageSize { width: image.width(), height: image.height() },
             }
             .fail();
         }
+        Ok(image)
+    }
 
-        let palette_image = Reader::open(path.join(&self.palette_path))?.decode()?;
-        if palette_image.width() != 16 || palette_image.height() != 1 {
-            return WrongSizeSnafu {
-                expected: ImageSize { width: 16, height: 1 },
-                actual: ImageSize { width: palette_image.width(), height: palette_image.height() },
+    fn load_icon(&mut self, path: &Path) -> Result<(), BannerImageError> {
+        let palette_missing = self.remap_policy != BannerRemapPolicy::Quantize && !path.join(&self.palette_path).exists();
+        if !path.join(&self.bitmap_path).exists() || palette_missing {
+            if let Some(raw_path) = self.raw_path.as_ref().map(|raw_path| path.join(raw_path)).filter(|p| p.exists()) {
+                log::warn!("Banner icon PNGs are missing, loading the icon from {}", raw_path.display());
+                return self.load_raw(&raw_path);
             }
-            .fail();
         }
 
+        let bitmap_image = Self::open_image(&path.join(&self.bitmap_path), 32, 32)?;
+
+        let palette_colors = if self.remap_policy == BannerRemapPolicy::Quantize {
+            Self::quantize(&bitmap_image)
+        } else {
+            let palette_image = Self::open_image(&path.join(&self.palette_path), 16, 1)?;
+            palette_image.pixels().map(|(_, _, color)| color).collect()
+        };
+
         let mut bitmap = BannerBitmap([0u8; 0x200]);
+        let mut num_remapped = 0;
         for (x, y, color) in bitmap_image.pixels() {
-            let index = palette_image.pixels().find_map(|(i, _, c)| (color == c).then_some(i));
-            let Some(index) = index else {
-                return InvalidPixelSnafu { bitmap: path.join(&self.bitmap_path), x, y }.fail();
+            let index = match palette_colors.iter().position(|c| *c == color) {
+                Some(index) => index,
+                None if self.remap_policy == BannerRemapPolicy::Strict => {
+                    return InvalidPixelSnafu { bitmap: path.join(&self.bitmap_path), x, y }.fail();
+                }
+                None => {
+                    num_remapped += 1;
+                    Self::nearest_color(&palette_colors, color)
+                }
             };
             bitmap.set_pixel(x as usize, y as usize, index as u8);
         }
 
         let mut palette = BannerPalette([0u16; 16]);
-        for (i, _, color) in palette_image.pixels() {
+        for (i, color) in palette_colors.iter().enumerate() {
             let [r, g, b, _] = color.0;
-            palette.set_color(i as usize, r, g, b);
+            palette.set_color(i, r, g, b);
+        }
+
+        match self.remap_policy {
+            BannerRemapPolicy::Strict => {}
+            BannerRemapPolicy::NearestColor => {
+                if num_remapped > 0 {
+                    let bitmap_path = path.join(&self.bitmap_path);
+                    log::warn!("Remapped {num_remapped} pixels in {} to the nearest palette color", bitmap_path.display());
+                }
+            }
+            BannerRemapPolicy::Quantize => {
+                let palette_path = path.join(&self.palette_path);
+                log::info!("Saving quantized banner palette to {}", palette_path.display());
+                Self::palette_image(&palette).save(palette_path)?;
+            }
         }
 
         self.bitmap = bitmap;
         self.palette = palette;
+        self.source = BannerImageSource::Png;
         Ok(())
     }
 
-    /// Saves to a bitmap and palette file in the given path.
+    fn load_raw(&mut self, raw_path: &Path) -> Result<(), BannerImageError> {
+        let data = read_file(raw_path)?;
+        let bitmap_size = size_of::<BannerBitmap>();
+        let expected = bitmap_size + size_of::<BannerPalette>();
+        if data.len() != expected {
+            return WrongRawSizeSnafu { path: raw_path, expected, actual: data.len() }.fail();
+        }
+        self.bitmap = bytemuck::pod_read_unaligned(&data[..bitmap_size]);
+        self.palette = bytemuck::pod_read_unaligned(&data[bitmap_size..]);
+        self.source = BannerImageSource::Raw;
+        Ok(())
+    }
+
+    fn load_animation(&mut self, path: &Path) -> Result<(), BannerImageError> {
+        for (kind, actual) in
+            [("bitmaps", self.animation_bitmap_files.len()), ("palettes", self.animation_palette_paths.len())]
+        {
+            if actual > NUM_ANIMATION_IMAGES {
+                return TooManyAnimationImagesSnafu { kind, actual }.fail();
+            }
+        }
+        let raw_path = self.animation_raw_path.as_ref().map(|raw_path| path.join(raw_path)).filter(|p| p.exists());
+        let raw = raw_path.as_deref().map(Self::load_animation_raw).transpose()?;
+        let (mut bitmaps, mut palettes) =
+            raw.unwrap_or(([BannerBitmap::default(); NUM_ANIMATION_IMAGES], [BannerPalette::default(); NUM_ANIMATION_IMAGES]));
+
+        let mut pngs = self.animation_bitmap_files.iter().map(|file| &file.path).chain(&self.animation_palette_paths);
+        if let Some(raw_path) = raw_path.filter(|_| pngs.any(|png| !path.join(png).exists())) {
+            log::warn!("Animated banner icon PNGs are missing, loading the animation from {}", raw_path.display());
+            self.animation_bitmaps = Some(bitmaps.into());
+            self.animation_palettes = Some(palettes.into());
+            return Ok(());
+        }
+
+        // Colors which are unchanged keep their raw value, which may have the unused top bit set
+        for (palette, palette_path) in palettes.iter_mut().zip(&self.animation_palette_paths) {
+            let palette_image = Self::open_image(&path.join(palette_path), 16, 1)?;
+            for (x, _, color) in palette_image.pixels() {
+                let [r, g, b, _] = color.0;
+                if palette.get_color(x as usize) != (r, g, b) {
+                    palette.set_color(x as usize, r, g, b);
+                }
+            }
+        }
+
+        let mut num_remapped = 0;
+        for (bitmap, file) in bitmaps.iter_mut().zip(&self.animation_bitmap_files) {
+            let bitmap_path = path.join(&file.path);
+            let Some(palette) = palettes.get(file.palette) else {
+                return AnimationPaletteNotFoundSnafu { bitmap: bitmap_path, palette: file.palette }.fail();
+            };
+            let palette_colors = (0..16)
+                .map(|index| {
+                    let (r, g, b) = palette.get_color(index);
+                    Rgba([r, g, b, 255])
+                })
+                .collect::<Vec<_>>();
+
+            // Pixels which are unchanged keep their raw index, in case the palette has the same color at several indexes
+            let bitmap_image = Self::open_image(&bitmap_path, 32, 32)?;
+            for (x, y, color) in bitmap_image.pixels() {
+                if palette_colors[bitmap.get_pixel(x as usize, y as usize)] == color {
+                    continue;
+                }
+                let index = match palette_colors.iter().position(|c| *c == color) {
+                    Some(index) => index,
+                    None if self.remap_policy == BannerRemapPolicy::Strict => {
+                        return InvalidPixelSnafu { bitmap: bitmap_path, x, y }.fail();
+                    }
+                    None => {
+                        num_remapped += 1;
+                        Self::nearest_color(&palette_colors, color)
+                    }
+                };
+                bitmap.set_pixel(x as usize, y as usize, index as u8);
+            }
+        }
+        if num_remapped > 0 {
+            log::warn!("Remapped {num_remapped} pixels in the animated banner icon to the nearest palette color");
+        }
+
+        self.animation_bitmaps = Some(bitmaps.into());
+        self.animation_palettes = Some(palettes.into());
+        Ok(())
+    }
+
+    fn load_animation_raw(
+        raw_path: &Path,
+    ) -> Result<([BannerBitmap; NUM_ANIMATION_IMAGES], [BannerPalette; NUM_ANIMATION_IMAGES]), BannerImageError> {
+        let data = read_file(raw_path)?;
+        let bitmaps_size = size_of::<[BannerBitmap; NUM_ANIMATION_IMAGES]>();
+        let expected = bitmaps_size + size_of::<[BannerPalette; NUM_ANIMATION_IMAGES]>();
+        if data.len() != expected {
+            return WrongRawSizeSnafu { path: raw_path, expected, actual: data.len() }.fail();
+        }
+        Ok((bytemuck::pod_read_unaligned(&data[..bitmaps_size]), bytemuck::pod_read_unaligned(&data[bitmaps_size..])))
+    }
+
+    /// Saves to a bitmap and palette file in the given path, as well as the raw file if [`Self::raw_path`] is set. The
+    /// animated icon is saved to [`Self::animation_bitmap_files`] and [`Self::animation_palette_paths`], and to
+    /// [`Self::animation_raw_path`] if it is set.
     ///
     /// # Errors
     ///
-    /// See [`RgbImage::save`].
+    /// See [`RgbImage::save`] and [`write_file`].
     pub fn save_bitmap_file(&self, path: &Path) -> Result<(), BannerImageError> {
+        Self::bitmap_image(&self.bitmap, &self.palette).save(path.join(&self.bitmap_path))?;
+        Self::palette_image(&self.palette).save(path.join(&self.palette_path))?;
+        if let Some(raw_path) = &self.raw_path {
+            let mut data = bytemuck::bytes_of(&self.bitmap).to_vec();
+            data.extend_from_slice(bytemuck::bytes_of(&self.palette));
+            write_file(path.join(raw_path), data)?;
+        }
+
+        if let (Some(bitmaps), Some(palettes)) = (&self.animation_bitmaps, &self.animation_palettes) {
+            for (bitmap, file) in bitmaps.iter().zip(&self.animation_bitmap_files) {
+                let palette = palettes.get(file.palette).copied().unwrap_or_default();
+                Self::bitmap_image(bitmap, &palette).save(path.join(&file.path))?;
+            }
+            for (palette, palette_path) in palettes.iter().zip(&self.animation_palette_paths) {
+                Self::palette_image(palette).save(path.join(palette_path))?;
+            }
+            if let Some(raw_path) = &self.animation_raw_path {
+                let mut data = bytemuck::cast_slice::<_, u8>(bitmaps).to_vec();
+                data.extend_from_slice(bytemuck::cast_slice(palettes));
+                write_file(path.join(raw_path), data)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn bitmap_image(bitmap: &BannerBitmap, palette: &BannerPalette) -> RgbImage {
         let mut bitmap_image = RgbImage::new(32, 32);
         for y in 0..32 {
             for x in 0..32 {
-                let index = self.bitmap.get_pixel(x, y);
-                let (r, g, b) = self.palette.get_color(index);
+                let index = bitmap.get_pixel(x, y);
+                let (r, g, b) = palette.get_color(index);
                 bitmap_image.put_pixel(x as u32, y as u32, Rgb([r, g, b]));
             }
         }
+        bitmap_image
+    }
 
+    fn palette_image(palette: &BannerPalette) -> RgbImage {
         let mut palette_image = RgbImage::new(16, 1);
         for index in 0..16 {
-            let (r, g, b) = self.palette.get_color(index);
+            let (r, g, b) = palette.get_color(index);
             palette_image.put_pixel(index as u32, 0, Rgb([r, g, b]));
         }
+        palette_image
+    }
 
-        bitmap_image.save(path.join(&self.bitmap_path))?;
-        palette_image.save(path.join(&self.palette_path))?;
-        Ok(())
+    fn color_distance(a: Rgba<u8>, b: Rgba<u8>) -> u32 {
+        (0..3).map(|i| (a.0[i] as i32 - b.0[i] as i32).pow(2) as u32).sum()
+    }
+
+    fn nearest_color(palette: &[Rgba<u8>], color: Rgba<u8>) -> usize {
+        (0..palette.len()).min_by_key(|&i| Self::color_distance(palette[i], color)).unwrap()
+    }
+
+    /// Builds a 16-color palette from the bitmap using median cut. Index 0 is the color of the top-left pixel, and the
+    /// other colors are split into boxes along their widest channel until there are 15 boxes, each averaged to one color.
+    fn quantize(bitmap_image: &DynamicImage) -> Vec<Rgba<u8>> {
+        let transparent = bitmap_image.get_pixel(0, 0);
+        let mut colors = bitmap_image.pixels().map(|(_, _, color)| color).filter(|c| *c != transparent).collect::<Vec<_>>();
+        colors.sort_unstable_by_key(|c| c.0);
+        colors.dedup();
+
+        let channel_range = |colors: &[Rgba<u8>], channel: usize| {
+            let (min, max) =
+                colors.iter().fold((u8::MAX, u8::MIN), |(min, max), c| (min.min(c.0[channel]), max.max(c.0[channel])));
+            max.saturating_sub(min)
+        };
+        let widest_channel =
+            |colors: &[Rgba<u8>]| (0..3).map(|channel| (channel_range(colors, channel), channel)).max().unwrap();
+
+        let mut boxes = if colors.is_empty() { vec![] } else { vec![colors] };
+        while boxes.len() < 15 {
+            let Some((index, channel)) = boxes
+                .iter()
+                .enumerate()
+                .map(|(index, colors)| (widest_channel(colors), index))
+                .filter(|((range, _), _)| *range > 0)
+                .max_by_key(|((range, _), index)| (*range, std::cmp::Reverse(*index)))
+                .map(|((_, channel), index)| (index, channel))
+            else {
+                break;
+            };
+            let mut colors = boxes.swap_remove(index);
+            colors.sort_unstable_by_key(|c| c.0[channel]);
+            let upper = colors.split_off(colors.len() / 2);
+            boxes.push(colors);
+            boxes.push(upper);
+        }
+
+        let mut palette = vec![transparent];
+        for colors in boxes {
+            let mut sum = [0u32; 3];
+            for color in &colors {
+                (0..3).for_each(|i| sum[i] += color.0[i] as u32);
+            }
+            let len = colors.len() as u32;
+            palette.push(Rgba([(sum[0] / len) as u8, (sum[1] / len) as u8, (sum[2] / len) as u8, 255]));
+        }
+        palette.resize(16, transparent);
+        palette
     }
 }
 
 /// Game title in different languages.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct BannerTitle {
     /// Japanese.
     pub japanese: String,
@@ -308,35 +903,181 @@ pub struct BannerTitle {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Korean.
     pub korean: Option<String>,
+
+    // The raw titles below are only present if the original title was not valid UTF-16, and take priority over the titles
+    // above when building.
+    /// Raw UTF-16 code units of the Japanese title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub japanese_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the English title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub english_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the French title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub french_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the German title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub german_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the Italian title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub italian_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the Spanish title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub spanish_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the Chinese title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub chinese_raw_utf16: Option<Vec<u16>>,
+    /// Raw UTF-16 code units of the Korean title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub korean_raw_utf16: Option<Vec<u16>>,
+}
+
+/// Paths to files which override the titles in [`BannerTitle`], relative to the banner directory. See
+/// [`Banner::load_title_files`].
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct BannerTitleFiles {
+    /// Japanese.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub japanese: Option<PathBuf>,
+    /// English.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub english: Option<PathBuf>,
+    /// French.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub french: Option<PathBuf>,
+    /// German.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub german: Option<PathBuf>,
+    /// Italian.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub italian: Option<PathBuf>,
+    /// Spanish.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub spanish: Option<PathBuf>,
+    /// Chinese.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub chinese: Option<PathBuf>,
+    /// Korean.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub korean: Option<PathBuf>,
+}
+
+impl BannerTitleFiles {
+    /// Returns the path to the title file of the given language, if any.
+    pub fn get(&self, language: Language) -> Option<&PathBuf> {
+        match language {
+            Language::Japanese => self.japanese.as_ref(),
+            Language::English => self.english.as_ref(),
+            Language::French => self.french.as_ref(),
+            Language::German => self.german.as_ref(),
+            Language::Italian => self.italian.as_ref(),
+            Language::Spanish => self.spanish.as_ref(),
+            Language::Chinese => self.chinese.as_ref(),
+            Language::Korean => self.korean.as_ref(),
+        }
+    }
+
+    fn get_mut(&mut self, language: Language) -> &mut Option<PathBuf> {
+        match language {
+            Language::Japanese => &mut self.japanese,
+            Language::English => &mut self.english,
+            Language::French => &mut self.french,
+            Language::German => &mut self.german,
+            Language::Italian => &mut self.italian,
+            Language::Spanish => &mut self.spanish,
+            Language::Chinese => &mut self.chinese,
+            Language::Korean => &mut self.korean,
+        }
+    }
+
+    fn language_code(language: Language) -> &'static str {
+        match language {
+            Language::Japanese => "ja",
+            Language::English => "en",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Italian => "it",
+            Language::Spanish => "es",
+            Language::Chinese => "zh",
+            Language::Korean => "ko",
+        }
+    }
 }
 
 macro_rules! copy_title {
-    ($banner:ident, $language:expr, $title:expr) => {
+    ($banner:ident, $language:expr, $title:expr, $raw_title:expr) => {
         if let Some(title) = $banner.title_mut($language) {
-            *title = Unicode16Array::from_str($title);
+            *title = match $raw_title {
+                Some(raw_title) => Unicode16Array::from_units(raw_title),
+                None => $title.parse(),
+            }
+            .context(TitleSnafu { language: $language })?;
         }
     };
 }
 
 impl BannerTitle {
-    fn copy_to_banner(&self, banner: &mut raw::Banner) {
-        copy_title!(banner, Language::Japanese, &self.japanese);
-        copy_title!(banner, Language::English, &self.english);
-        copy_title!(banner, Language::French, &self.french);
-        copy_title!(banner, Language::German, &self.german);
-        copy_title!(banner, Language::Italian, &self.italian);
-        copy_title!(banner, Language::Spanish, &self.spanish);
+    /// Returns the title of the given language, or `None` if the banner has no title in that language.
+    pub fn get(&self, language: Language) -> Option<&str> {
+        match language {
+            Language::Japanese => Some(&self.japanese),
+            Language::English => Some(&self.english),
+            Language::French => Some(&self.french),
+            Language::German => Some(&self.german),
+            Language::Italian => Some(&self.italian),
+            Language::Spanish => Some(&self.spanish),
+            Language::Chinese => self.chinese.as_deref(),
+            Language::Korean => self.korean.as_deref(),
+        }
+    }
+
+    fn raw_mut(&mut self, language: Language) -> &mut Option<Vec<u16>> {
+        match language {
+            Language::Japanese => &mut self.japanese_raw_utf16,
+            Language::English => &mut self.english_raw_utf16,
+            Language::French => &mut self.french_raw_utf16,
+            Language::German => &mut self.german_raw_utf16,
+            Language::Italian => &mut self.italian_raw_utf16,
+            Language::Spanish => &mut self.spanish_raw_utf16,
+            Language::Chinese => &mut self.chinese_raw_utf16,
+            Language::Korean => &mut self.korean_raw_utf16,
+        }
+    }
+
+    /// Replaces the title of the given language, including its raw UTF-16 code units if any.
+    pub fn set(&mut self, language: Language, title: String) {
+        match language {
+            Language::Japanese => self.japanese = title,
+            Language::English => self.english = title,
+            Language::French => self.french = title,
+            Language::German => self.german = title,
+            Language::Italian => self.italian = title,
+            Language::Spanish => self.spanish = title,
+            Language::Chinese => self.chinese = Some(title),
+            Language::Korean => self.korean = Some(title),
+        }
+        *self.raw_mut(language) = None;
+    }
+
+    fn copy_to_banner(&self, banner: &mut raw::Banner) -> Result<(), BannerError> {
+        copy_title!(banner, Language::Japanese, &self.japanese, &self.japanese_raw_utf16);
+        copy_title!(banner, Language::English, &self.english, &self.english_raw_utf16);
+        copy_title!(banner, Language::French, &self.french, &self.french_raw_utf16);
+        copy_title!(banner, Language::German, &self.german, &self.german_raw_utf16);
+        copy_title!(banner, Language::Italian, &self.italian, &self.italian_raw_utf16);
+        copy_title!(banner, Language::Spanish, &self.spanish, &self.spanish_raw_utf16);
         if let Some(chinese) = &self.chinese {
-            copy_title!(banner, Language::Chinese, chinese);
+            copy_title!(banner, Language::Chinese, chinese, &self.chinese_raw_utf16);
         }
         if let Some(korean) = &self.korean {
-            copy_title!(banner, Language::Korean, korean);
+            copy_title!(banner, Language::Korean, korean, &self.korean_raw_utf16);
         }
+        Ok(())
     }
 }
 
 /// Keyframe for animated icon.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct BannerKeyframe {
     /// Flips the bitmap vertically.
     pub flip_vertically: bool,
@@ -351,6 +1092,24 @@ pub struct BannerKeyframe {
 }
 
 impl BannerKeyframe {
+    /// Loads from a raw keyframe.
+    pub fn load_raw(keyframe: raw::BannerKeyframe) -> Self {
+        Self {
+            flip_vertically: keyframe.flip_vertically(),
+            flip_horizontally: keyframe.flip_horizontally(),
+            palette: keyframe.palette_index() as usize,
+            bitmap: keyframe.bitmap_index() as usize,
+            frame_duration: keyframe.frame_duration() as usize,
+        }
+    }
+
+    /// Loads the keyframes of an animated icon. The list ends at the last nonzero keyframe, since [`Banner::build`] fills the
+    /// rest of the keyframes with zeros.
+    pub fn load_raw_list(keyframes: &[raw::BannerKeyframe]) -> Vec<Self> {
+        let len = keyframes.iter().rposition(|keyframe| keyframe.into_bits() != 0).map_or(0, |last| last + 1);
+        keyframes[..len].iter().copied().map(Self::load_raw).collect()
+    }
+
     /// Builds a raw keyframe.
     ///
     /// # Panics
@@ -0,0 +1,430 @@
+use std::{
+    collections::HashMap,
+    mem::size_of,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+use super::{
+    raw::{
+        self, FileAlloc, RawArm9Error, RawBannerError, RawBuildInfoError, RawFatError, RawFntError, RawHeaderError,
+        RawOverlayError, TableOffset,
+    },
+    FileParseError, FileSystem, OverlayConfig, RomConfig,
+};
+use crate::{
+    compress::lz77::{Lz77, Lz77DecompressError},
+    crypto::sha1::Sha1,
+    io::{create_file, open_file, read_dir, read_file, FileError, IoSnafu},
+    str::hex_string,
+};
+
+/// Input files and output sections of each component of a built ROM, so that build systems can tell which components
+/// changed since the last build. See [`BuildCache`] for reusing the unchanged components.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildMetadata {
+    /// Version of ds-rom which built the ROM.
+    pub version: String,
+    /// Components of the ROM.
+    pub components: Vec<BuildComponent>,
+}
+
+/// A component of a built ROM, see [`BuildMetadata`].
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildComponent {
+    /// Name of the component, e.g. `arm9`, `overlay:12`, `arm7_overlay:0`, `fnt`, `fat`, `banner` or `file:/path`.
+    pub name: String,
+    /// Files which the component was built from.
+    pub inputs: Vec<BuildInput>,
+    /// Section which the component was built into, absent if the section is empty.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub output: Option<BuildOutput>,
+}
+
+/// An input file of a [`BuildComponent`].
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildInput {
+    /// Path to the file or directory.
+    pub path: PathBuf,
+    /// SHA-1 of the file contents. For directories, this is the SHA-1 of the sorted list of paths in the directory, so it
+    /// only changes when files are added, removed or renamed.
+    pub sha1: String,
+}
+
+/// The section of a [`BuildComponent`] in the built ROM.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildOutput {
+    /// ROM offset to the start of the section.
+    pub offset: u32,
+    /// Size of the section.
+    pub size: u32,
+    /// SHA-1 of the section.
+    pub sha1: String,
+}
+
+/// Errors related to [`BuildMetadata`].
+#[derive(Debug, Snafu)]
+pub enum BuildMetadataError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`RawFntError`].
+    #[snafu(transparent)]
+    RawFnt {
+        /// Source error.
+        source: RawFntError,
+    },
+    /// See [`RawFatError`].
+    #[snafu(transparent)]
+    RawFat {
+        /// Source error.
+        source: RawFatError,
+    },
+    /// See [`RawBannerError`].
+    #[snafu(transparent)]
+    RawBanner {
+        /// Source error.
+        source: RawBannerError,
+    },
+    /// See [`FileParseError`].
+    #[snafu(transparent)]
+    FileParse {
+        /// Source error.
+        source: FileParseError,
+    },
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`serde_yml::Error`].
+    #[snafu(transparent)]
+    SerdeYml {
+        /// Source error.
+        source: serde_yml::Error,
+    },
+    /// See [`serde_json::Error`].
+    #[snafu(transparent)]
+    SerdeJson {
+        /// Source error.
+        source: serde_json::Error,
+    },
+}
+
+impl BuildMetadata {
+    /// Lists the inputs of each component in the project at `config_path`, and the sections they were built into in `rom`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a config or input file can't be read, or if the ROM is invalid.
+    pub fn compute<P: AsRef<Path>>(config_path: P, rom: &raw::Rom) -> Result<Self, BuildMetadataError> {
+        let config_path = config_path.as_ref();
+        let path = config_path.parent().unwrap();
+        let config: RomConfig = serde_yml::from_reader(open_file(config_path)?)?;
+        let header = rom.header()?;
+        let fat = rom.fat()?;
+
+        let mut components = vec![];
+        let mut add = |name: String, inputs: &[PathBuf], offset: u32, size: u32| -> Result<(), BuildMetadataError> {
+            let inputs = inputs.iter().map(|input| BuildInput::hash(path.join(input))).collect::<Result<_, _>>()?;
+            let output = rom.data().get(offset as usize..(offset + size) as usize).filter(|section| !section.is_empty());
+            let output = output.map(|section| BuildOutput { offset, size, sha1: hex_string(&Sha1::digest(section)) });
+            components.push(BuildComponent { name, inputs, output });
+            Ok(())
+        };
+        let table = |table: TableOffset| (table.offset, table.size);
+        let alloc = |file_id: u32| fat.get(file_id as usize).map_or((0, 0), |alloc| (alloc.start, alloc.end - alloc.start));
+
+        let header_inputs = [config.header.clone(), config.header_logo.clone()];
+        add("header".to_string(), &header_inputs, 0, size_of::<raw::Header>() as u32)?;
+
+        let mut arm9_inputs = vec![config.arm9_bin.clone(), config.arm9_config.clone()];
+        for autoload in [&config.itcm, &config.dtcm].into_iter().chain(&config.unknown_autoloads) {
+            arm9_inputs.extend([autoload.bin.clone(), autoload.config.clone()]);
+        }
+        add("arm9".to_string(), &arm9_inputs, header.arm9.offset, header.arm9.size)?;
+
+        for (prefix, overlays_path) in [("overlay", &config.arm9_overlays), ("arm7_overlay", &config.arm7_overlays)] {
+            let Some(overlays_path) = overlays_path else { continue };
+            let overlays_dir = overlays_path.parent().unwrap_or(Path::new(""));
+            let overlay_configs: Vec<OverlayConfig> = serde_yml::from_reader(open_file(path.join(overlays_path))?)?;
+            for overlay in overlay_configs.iter().filter(|overlay| !overlay.placeholder) {
+                let inputs = [overlays_path.clone(), overlays_dir.join(&overlay.file_name)];
+                let (offset, size) = alloc(overlay.info.file_id);
+                add(format!("{prefix}:{}", overlay.info.id), &inputs, offset, size)?;
+            }
+        }
+
+        add("arm7".to_string(), &[config.arm7_bin.clone(), config.arm7_config.clone()], header.arm7.offset, header.arm7.size)?;
+
+        if let Some(dsi) = &config.dsi {
+            let arm9i_inputs = [dsi.arm9i_bin.clone(), dsi.arm9i_config.clone()];
+            add("arm9i".to_string(), &arm9i_inputs, header.arm9i.offset, header.arm9i.size)?;
+            let arm7i_inputs = [dsi.arm7i_bin.clone(), dsi.arm7i_config.clone()];
+            add("arm7i".to_string(), &arm7i_inputs, header.arm7i.offset, header.arm7i.size)?;
+        }
+
+        let fnt_inputs = [config.files_dir.clone()].into_iter().chain(config.fnt_order.clone()).collect::<Vec<_>>();
+        let (offset, size) = table(header.file_names);
+        add("fnt".to_string(), &fnt_inputs, offset, size)?;
+        let fat_inputs = [config.files_dir.clone(), config.path_order.clone()].into_iter().chain(config.file_order.clone());
+        let (offset, size) = table(header.file_allocs);
+        add("fat".to_string(), &fat_inputs.collect::<Vec<_>>(), offset, size)?;
+
+        if let Some(banner_path) = &config.banner {
+            let mut banner_inputs = vec![banner_path.clone()];
+            let banner_dir = banner_path.parent().unwrap_or(Path::new(""));
+            banner_inputs.extend(
+                Self::files_in(&path.join(banner_dir))?
+                    .into_iter()
+                    .map(|file| banner_dir.join(file))
+                    .filter(|file| file != banner_path),
+            );
+            let size = rom.banner()?.map_or(0, |banner| banner.version().banner_size() as u32);
+            add("banner".to_string(), &banner_inputs, header.banner_offset, size)?;
+        }
+
+        let fnt = rom.fnt()?;
+        let files = FileSystem::parse(&fnt, fat, rom)?;
+        for (file_path, file) in files.iter_files() {
+            let inputs = [config.files_dir.join(&file_path)];
+            let (offset, size) = alloc(file.id() as u32);
+            add(format!("file:/{file_path}"), &inputs, offset, size)?;
+        }
+
+        Ok(Self { version: env!("CARGO_PKG_VERSION").to_string(), components })
+    }
+
+    /// Returns the component with the given name, see [`BuildComponent::name`].
+    pub fn component(&self, name: &str) -> Option<&BuildComponent> {
+        self.components.iter().find(|component| component.name == name)
+    }
+
+    /// Returns the paths of the files in `dir` and its subdirectories relative to `dir`, sorted.
+    fn files_in(dir: &Path) -> Result<Vec<PathBuf>, FileError> {
+        let mut files = vec![];
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(next) = dirs.pop() {
+            let entries = read_dir(&next)?.collect::<Result<Vec<_>, _>>().context(IoSnafu { path: next.to_string_lossy() })?;
+            for entry in entries {
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.push(path.strip_prefix(dir).unwrap().to_path_buf());
+                }
+            }
+        }
+        files.sort_unstable();
+        Ok(files)
+    }
+
+    /// Loads build metadata from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be opened or has an invalid format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BuildMetadataError> {
+        Ok(serde_json::from_reader(open_file(path)?)?)
+    }
+
+    /// Saves this build metadata to a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be created or serialization fails.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BuildMetadataError> {
+        serde_json::to_writer_pretty(create_file(path)?, self)?;
+        Ok(())
+    }
+}
+
+impl BuildInput {
+    fn hash(path: PathBuf) -> Result<Self, FileError> {
+        let sha1 = if path.is_dir() {
+            let mut sha1 = Sha1::new();
+            for file in BuildMetadata::files_in(&path)? {
+                sha1.update(file.to_string_lossy().replace('\\', "/").as_bytes());
+                sha1.update(b"\n");
+            }
+            sha1.finish()
+        } else {
+            Sha1::digest(&read_file(&path)?)
+        };
+        Ok(Self { path, sha1: hex_string(&sha1) })
+    }
+}
+
+/// Sections of a previous build which are reused by [`super::Rom::load`] instead of being built again, see
+/// [`super::RomLoadOptions::build_cache`]. The compressed ARM9 program and overlays are cached, since compressing them
+/// takes up most of the build time. Cached sections are looked up by the SHA-1 of their decompressed contents, so a program
+/// or overlay which changed since the previous build is always compressed again.
+#[derive(Default)]
+pub struct BuildCache {
+    compressed_arm9: Option<CachedArm9>,
+    compressed_overlays: HashMap<[u8; 20], Box<[u8]>>,
+}
+
+/// The compressed part of an ARM9 program from a previous build. The uncompressed part before it contains the secure area,
+/// which was encrypted after compressing, so it's taken from the program being built instead.
+struct CachedArm9 {
+    /// Size of the whole program when decompressed.
+    decompressed_size: usize,
+    /// Offset where the compressed part starts, which is the same in the compressed and decompressed program.
+    start: usize,
+    /// SHA-1 of the decompressed program from [`Self::start`].
+    sha1: [u8; 20],
+    /// The compressed part.
+    compressed: Box<[u8]>,
+}
+
+/// Errors related to [`BuildCache`].
+#[derive(Debug, Snafu)]
+pub enum BuildCacheError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`RawArm9Error`].
+    #[snafu(transparent)]
+    RawArm9 {
+        /// Source error.
+        source: RawArm9Error,
+    },
+    /// See [`RawBuildInfoError`].
+    #[snafu(transparent)]
+    RawBuildInfo {
+        /// Source error.
+        source: RawBuildInfoError,
+    },
+    /// See [`RawOverlayError`].
+    #[snafu(transparent)]
+    RawOverlay {
+        /// Source error.
+        source: RawOverlayError,
+    },
+    /// See [`RawFatError`].
+    #[snafu(transparent)]
+    RawFat {
+        /// Source error.
+        source: RawFatError,
+    },
+    /// See [`Lz77DecompressError`].
+    #[snafu(transparent)]
+    Lz77Decompress {
+        /// Source error.
+        source: Lz77DecompressError,
+    },
+    /// Occurs when the build metadata was written by a different version of ds-rom, which may compress differently.
+    #[snafu(display("the previous build was made by ds-rom {actual}, but this is version {expected}"))]
+    VersionMismatch {
+        /// Version of this crate.
+        expected: String,
+        /// Version in the build metadata.
+        actual: String,
+    },
+    /// Occurs when a section of the previous ROM doesn't match the build metadata, e.g. because the ROM was built again
+    /// without updating the metadata.
+    #[snafu(display("the {name} section of the previous ROM doesn't match the build metadata"))]
+    StaleSection {
+        /// Name of the component.
+        name: String,
+    },
+}
+
+impl BuildCache {
+    /// Collects the compressed ARM9 program and overlays of `rom`, a ROM which was built previously and described by
+    /// `metadata`. Components named in `refresh`, such as `arm9`, `overlay:12` or `arm7_overlay:0`, are left out so that
+    /// they're compressed again.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `metadata` is from another version of this crate, if the sections of `rom`
+    /// don't match `metadata`, or if the ARM9 program, overlay table or an overlay of `rom` is invalid.
+    pub fn load(metadata: &BuildMetadata, rom: &raw::Rom, refresh: &[String]) -> Result<Self, BuildCacheError> {
+        let version = env!("CARGO_PKG_VERSION");
+        if metadata.version != version {
+            return VersionMismatchSnafu { expected: version, actual: &metadata.version }.fail();
+        }
+        for component in &metadata.components {
+            let Some(output) = &component.output else { continue };
+            let section = rom.data().get(output.offset as usize..(output.offset + output.size) as usize);
+            if section.is_none_or(|section| hex_string(&Sha1::digest(section)) != output.sha1) {
+                return StaleSectionSnafu { name: &component.name }.fail();
+            }
+        }
+
+        let compressed_arm9 = if refresh.iter().any(|name| name == "arm9") { None } else { CachedArm9::load(rom)? };
+
+        let fat = rom.fat()?;
+        let mut compressed_overlays = HashMap::new();
+        let tables = [("overlay", rom.arm9_overlay_table()?), ("arm7_overlay", rom.arm7_overlay_table()?)];
+        for (prefix, overlays) in tables {
+            for overlay in overlays.iter().filter(|overlay| overlay.flags.is_compressed()) {
+                if refresh.contains(&format!("{prefix}:{}", overlay.id)) {
+                    continue;
+                }
+                let Some(&alloc) = fat.get(overlay.file_id as usize) else { continue };
+                let compressed = FileAlloc::into_file(alloc, rom.data());
+                let decompressed = Lz77 {}.decompress(compressed)?;
+                compressed_overlays.insert(Sha1::digest(&decompressed), compressed.into());
+            }
+        }
+        Ok(Self { compressed_arm9, compressed_overlays })
+    }
+
+    /// Returns whether the compressed ARM9 program is cached.
+    pub fn has_arm9(&self) -> bool {
+        self.compressed_arm9.is_some()
+    }
+
+    /// Returns the number of cached overlays.
+    pub fn num_overlays(&self) -> usize {
+        self.compressed_overlays.len()
+    }
+
+    /// Returns the cached compressed contents of an overlay, given its decompressed contents.
+    pub(crate) fn compressed_overlay(&self, data: &[u8]) -> Option<&[u8]> {
+        self.compressed_overlays.get(&Sha1::digest(data)).map(|compressed| compressed.as_ref())
+    }
+
+    /// Returns the cached compressed contents of the ARM9 program, given its decompressed contents. Returns `None` if the
+    /// program changed since the previous build, or if the previous build compressed bytes before `compression_start`.
+    pub(crate) fn compressed_arm9(&self, data: &[u8], compression_start: usize) -> Option<Vec<u8>> {
+        let cached = self.compressed_arm9.as_ref()?;
+        if cached.decompressed_size != data.len() || cached.start < compression_start {
+            return None;
+        }
+        if Sha1::digest(&data[cached.start..]) != cached.sha1 {
+            return None;
+        }
+        Some([&data[..cached.start], &cached.compressed].concat())
+    }
+}
+
+impl CachedArm9 {
+    fn load(rom: &raw::Rom) -> Result<Option<Self>, BuildCacheError> {
+        let arm9 = rom.arm9()?;
+        if !arm9.is_compressed()? {
+            return Ok(None);
+        }
+        let data = arm9.full_data();
+        let Some(footer) = Lz77 {}.footer(data) else { return Ok(None) };
+        let start = data.len() - footer.total_size;
+        let decompressed = Lz77 {}.decompress(data)?;
+        Ok(Some(Self {
+            decompressed_size: decompressed.len(),
+            start,
+            sha1: Sha1::digest(&decompressed[start..]),
+            compressed: data[start..].into(),
+        }))
+    }
+}
@@ -0,0 +1,130 @@
+use std::{backtrace::Backtrace, fmt::Display, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+use crate::{
+    compress::{
+        huffman::{Huffman, HuffmanBitWidth, HuffmanDecompressError},
+        lz10::{Lz10, Lz10DecompressError},
+        rle::{Rle, RleDecompressError},
+    },
+    str::glob_match,
+};
+
+/// Compression format of a file in the ROM, see [`TransparentCompression`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionFormat {
+    /// LZ77 format of the BIOS, see [`Lz10`].
+    Lz10,
+    /// Run-length encoding format of the BIOS, see [`Rle`].
+    Rle,
+    /// Huffman format of the BIOS with 4-bit values, see [`Huffman`].
+    Huffman4,
+    /// Huffman format of the BIOS with 8-bit values, see [`Huffman`].
+    Huffman8,
+}
+
+/// Errors related to [`CompressionFormat::decompress`].
+#[derive(Debug, Snafu)]
+pub enum CompressionFormatError {
+    /// See [`Lz10DecompressError`].
+    #[snafu(transparent)]
+    Lz10 {
+        /// Source error.
+        source: Lz10DecompressError,
+    },
+    /// See [`RleDecompressError`].
+    #[snafu(transparent)]
+    Rle {
+        /// Source error.
+        source: RleDecompressError,
+    },
+    /// See [`HuffmanDecompressError`].
+    #[snafu(transparent)]
+    Huffman {
+        /// Source error.
+        source: HuffmanDecompressError,
+    },
+}
+
+impl CompressionFormat {
+    /// Compresses `bytes` in this format.
+    pub fn compress(self, bytes: &[u8]) -> Box<[u8]> {
+        match self {
+            Self::Lz10 => Lz10 {}.compress(bytes),
+            Self::Rle => Rle {}.compress(bytes),
+            Self::Huffman4 => Huffman {}.compress(bytes, HuffmanBitWidth::Four),
+            Self::Huffman8 => Huffman {}.compress(bytes, HuffmanBitWidth::Eight),
+        }
+    }
+
+    /// Decompresses `bytes` in this format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `bytes` is not valid compressed data in this format.
+    pub fn decompress(self, bytes: &[u8]) -> Result<Box<[u8]>, CompressionFormatError> {
+        match self {
+            Self::Lz10 => Ok(Lz10 {}.decompress(bytes)?),
+            Self::Rle => Ok(Rle {}.decompress(bytes)?),
+            Self::Huffman4 | Self::Huffman8 => {
+                // Data in the other bit width would not be compressed the same way again
+                let header_type = if self == Self::Huffman4 { 0x24 } else { 0x28 };
+                if bytes.first() != Some(&header_type) {
+                    return Err(HuffmanDecompressError::InvalidHeader { backtrace: Backtrace::capture() }.into());
+                }
+                Ok(Huffman {}.decompress(bytes)?)
+            }
+        }
+    }
+}
+
+impl Display for CompressionFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lz10 => write!(f, "LZ10"),
+            Self::Rle => write!(f, "RLE"),
+            Self::Huffman4 => write!(f, "Huffman (4-bit)"),
+            Self::Huffman8 => write!(f, "Huffman (8-bit)"),
+        }
+    }
+}
+
+/// A rule in [`super::RomConfig::transparent_compression`]. Files matching the rule are saved decompressed by
+/// [`super::Rom::save_with_options`] and compressed again by [`super::Rom::load`]. Matching files which fail to decompress
+/// are saved as-is.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransparentCompression {
+    /// Glob pattern matched against paths relative to the files directory. `*` matches any sequence of characters,
+    /// including `/`.
+    pub glob: String,
+    /// Compression format of the matching files.
+    pub format: CompressionFormat,
+}
+
+impl TransparentCompression {
+    /// Returns the format of the first rule matching `path`, if any.
+    pub fn find_format(rules: &[Self], path: &str) -> Option<CompressionFormat> {
+        rules.iter().find(|rule| glob_match(&rule.glob, path)).map(|rule| rule.format)
+    }
+}
+
+/// A file which was saved decompressed because of a [`TransparentCompression`] rule.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CompressedFile {
+    /// Path relative to the files directory.
+    pub path: String,
+    /// Compression format of the file in the original ROM.
+    pub format: CompressionFormat,
+    /// Size of the compressed file in the original ROM.
+    pub size: u32,
+    /// SHA-1 hash of the compressed file in the original ROM.
+    #[serde(with = "crate::str::hex_array")]
+    pub sha1: [u8; 0x14],
+    /// Path to a copy of the compressed file, relative to the config. Only present if compressing the decompressed file
+    /// doesn't reproduce the original, in which case the copy is used as long as the decompressed file is unchanged.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub original: Option<PathBuf>,
+}
@@ -0,0 +1,80 @@
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::str::BlobSize;
+
+/// Sizes of a program or overlay before and after compression, see [`CompressionReport`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ModuleCompression {
+    /// Name of the module, such as `ARM9 overlay 12`.
+    pub name: String,
+    /// Size before compression.
+    pub uncompressed_size: u32,
+    /// Size after compression.
+    pub compressed_size: u32,
+}
+
+impl ModuleCompression {
+    /// Returns the compressed size as a fraction of the uncompressed size.
+    pub fn ratio(&self) -> f64 {
+        ratio(self.uncompressed_size as u64, self.compressed_size as u64)
+    }
+}
+
+impl Display for ModuleCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:#x} -> {:#x} ({:.1}%)", self.name, self.uncompressed_size, self.compressed_size, self.ratio() * 100.0)
+    }
+}
+
+/// Sizes of every module compressed by [`super::Rom::load`], and the time spent compressing them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CompressionReport {
+    /// Compressed modules, in the order they were loaded.
+    pub modules: Vec<ModuleCompression>,
+    /// Time spent compressing, in seconds.
+    pub seconds: f64,
+}
+
+impl CompressionReport {
+    /// Returns the total size of all modules before compression.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.modules.iter().map(|module| module.uncompressed_size as u64).sum()
+    }
+
+    /// Returns the total size of all modules after compression.
+    pub fn compressed_size(&self) -> u64 {
+        self.modules.iter().map(|module| module.compressed_size as u64).sum()
+    }
+
+    /// Returns the total compressed size as a fraction of the total uncompressed size.
+    pub fn ratio(&self) -> f64 {
+        ratio(self.uncompressed_size(), self.compressed_size())
+    }
+
+    pub(crate) fn add_time(&mut self, elapsed: Duration) {
+        self.seconds += elapsed.as_secs_f64();
+    }
+}
+
+impl Display for CompressionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Compressed {} modules: {} -> {} ({:.1}%) in {:.2}s",
+            self.modules.len(),
+            BlobSize(self.uncompressed_size() as usize),
+            BlobSize(self.compressed_size() as usize),
+            self.ratio() * 100.0,
+            self.seconds
+        )
+    }
+}
+
+fn ratio(uncompressed_size: u64, compressed_size: u64) -> f64 {
+    if uncompressed_size == 0 {
+        return 1.0;
+    }
+    compressed_size as f64 / uncompressed_size as f64
+}
@@ -2,6 +2,8 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use super::{DedupStore, MemoryLimits, TransparentCompression};
+
 /// Config file mainly consisting of paths to extracted files.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RomConfig {
@@ -12,6 +14,12 @@ pub struct RomConfig {
     pub header: PathBuf,
     /// Path to header logo PNG
     pub header_logo: PathBuf,
+    /// Path to data between the header and ARM9 program, only present if it's not padding
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pre_arm9: Option<PathBuf>,
+    /// Path to data appended after the padded ROM, such as a custom footer or signature, only present if it's not padding
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub trailing_data: Option<PathBuf>,
 
     /// Path to ARM9 binary
     pub arm9_bin: PathBuf,
@@ -31,18 +39,96 @@ pub struct RomConfig {
     #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
     pub unknown_autoloads: Vec<RomConfigAutoload>,
 
+    /// Paths to the DSi programs and digest hashtables, only present if the ROM has a DSi area
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub dsi: Option<RomConfigDsi>,
+
     /// Path to ARM9 overlays YAML
     pub arm9_overlays: Option<PathBuf>,
+    /// Path to ARM9 overlay table binary, which overrides the table generated from the ARM9 overlays YAML
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub arm9_ovt_bin: Option<PathBuf>,
     /// Path to ARM7 overlays YAML
     pub arm7_overlays: Option<PathBuf>,
 
-    /// Path to banner YAML
-    pub banner: PathBuf,
+    /// Path to banner YAML, absent if the ROM has no banner
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub banner: Option<PathBuf>,
 
     /// Path to asset files directory
     pub files_dir: PathBuf,
     /// Path to path order file
     pub path_order: PathBuf,
+    /// Path to FNT order file, only present if the original FNT didn't list files and directories in the default order
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fnt_order: Option<PathBuf>,
+    /// First file IDs of directories without files, only present if the original FNT didn't give them the ID of the next
+    /// file
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub empty_dir_file_ids: Vec<EmptyDirFileId>,
+    /// Path to exhaustive list of files in the order they're placed in the ROM, overrides [`Self::path_order`] when building
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub file_order: Option<PathBuf>,
+    /// Path to list of files which were not extracted, see [`super::SkippedFile`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub skipped_files: Option<PathBuf>,
+    /// Path to list of files which refer to other sections of the ROM, see [`super::AliasedFile`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub aliased_files: Option<PathBuf>,
+    /// Rules for files which are saved decompressed and compressed again when building, see [`TransparentCompression`]
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
+    pub transparent_compression: Vec<TransparentCompression>,
+    /// Path to list of files which were saved decompressed, see [`super::CompressedFile`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub compressed_files: Option<PathBuf>,
+    /// Content-addressed store which the files directory refers to, see [`DedupStore`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub dedup_store: Option<DedupStore>,
+
+    /// Memory limits to validate program sizes against, defaults to [`MemoryLimits::retail`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub memory_limits: Option<MemoryLimits>,
+    /// Absolute offsets to sections, only present if the original ROM had extra padding before them
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub section_offsets: Option<RomConfigSectionOffsets>,
+    /// Layout of the FAT, only present if the original ROM's FAT doesn't follow the default layout
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fat_layout: Option<FatLayout>,
+    /// Whether the FNT is empty instead of having a root directory, as in some carts without a file system. Only takes
+    /// effect if there are no files.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub empty_fnt: bool,
+    /// Whether files with identical contents are stored once and share their FAT entries, see
+    /// [`super::RomLayout::shared_files`]
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub dedup_identical_files: bool,
+    /// Preservation level the project was extracted with, see [`PreservationLevel`]. Building an [`PreservationLevel::Exact`]
+    /// project fails if the header has consistency problems, instead of only warning about them.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub preservation: Option<PreservationLevel>,
+
+    /// Unknown keys, preserved so that other tools can store their own metadata in the config. These are not validated and
+    /// produce no warnings.
+    #[serde(flatten)]
+    pub extra: serde_yml::Mapping,
+}
+
+/// How much of the original ROM's layout is preserved when extracting, see [`super::RomExtractOptions::preservation`].
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PreservationLevel {
+    /// Only preserves what the game needs to run: file contents, names and IDs are kept, but the FNT is sorted, files are
+    /// placed in ID order, sections are packed with the default padding, and unused FAT entries, trailing data and the
+    /// original capacity are dropped. File IDs change if the original FNT was not sorted.
+    Minimal,
+    /// Preserves everything needed to rebuild a matching ROM in the common case: FNT order, file placement order, section
+    /// offsets, FAT layout, padding value, trailing data and the original capacity. This is what [`super::Rom::extract`]
+    /// does.
+    #[default]
+    Standard,
+    /// Like [`Self::Standard`], but also saves the order of every file instead of simplifying it to directories, always
+    /// uses the original capacity, and makes building fail on header consistency problems.
+    Exact,
 }
 
 /// Path to autoload files
@@ -53,3 +139,76 @@ pub struct RomConfigAutoload {
     /// Path to YAML
     pub config: PathBuf,
 }
+
+/// Paths to the files of the DSi area, see [`super::DsiArea`]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RomConfigDsi {
+    /// Path to ARM9i binary
+    pub arm9i_bin: PathBuf,
+    /// Path to ARM9i YAML
+    pub arm9i_config: PathBuf,
+    /// Path to ARM7i binary
+    pub arm7i_bin: PathBuf,
+    /// Path to ARM7i YAML
+    pub arm7i_config: PathBuf,
+    /// Path to digest sector hashtable binary
+    pub digest_sector_hashtable: PathBuf,
+    /// Path to digest block hashtable binary
+    pub digest_block_hashtable: PathBuf,
+}
+
+/// Absolute offsets to sections in the ROM, overriding the next aligned offset. An offset may be anywhere after the end
+/// of the preceding section, aligned or not.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct RomConfigSectionOffsets {
+    /// Offset to the file name table
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fnt: Option<u32>,
+    /// Offset to the file allocation table
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fat: Option<u32>,
+    /// Offset to the banner
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub banner: Option<u32>,
+    /// Offset to the digest sector hashtable
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub digest_sector_hashtable: Option<u32>,
+    /// Offset to the digest block hashtable
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub digest_block_hashtable: Option<u32>,
+    /// Offset to the ARM9i program, which is also the start of the DSi region
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub arm9i: Option<u32>,
+    /// Offset to the ARM7i program
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub arm7i: Option<u32>,
+}
+
+/// Layout of the file allocation table. By default, overlays occupy the first entries according to their file IDs, files
+/// follow right after and the FAT ends at the last file.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FatLayout {
+    /// Number of entries reserved at the start of the FAT before the first file, defaults to the number of overlays
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reserved_entries: Option<u16>,
+    /// Total number of entries in the FAT, only needed if the FAT has unused entries at the end
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub num_entries: Option<u32>,
+}
+
+/// First file ID of a directory without files, see [`RomConfig::empty_dir_file_ids`]. The FNT stores a first file ID for
+/// every directory, even if it has no files to number.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct EmptyDirFileId {
+    /// Path to the directory as in the FNT order file, `/` for the root directory
+    pub path: String,
+    /// First file ID of the directory in the original FNT
+    pub first_file_id: u16,
+}
+
+impl FatLayout {
+    /// Returns whether this [`FatLayout`] is the default layout.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
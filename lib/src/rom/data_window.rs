@@ -0,0 +1,80 @@
+use snafu::{Backtrace, Snafu};
+
+/// A range within the data of a module or file, used to print only part of it. See [`DataWindow::slice`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DataWindow {
+    /// Start of the window. For modules with a nonzero base address, values at or above the base address are virtual
+    /// addresses, and lower values are offsets from the start of the data. Defaults to the start of the data.
+    pub offset: Option<u32>,
+    /// Size of the window. Defaults to the rest of the data.
+    pub length: Option<u32>,
+}
+
+/// Errors related to [`DataWindow::slice`].
+#[derive(Debug, Snafu)]
+pub enum DataWindowError {
+    /// Occurs when the window starts past the end of the data.
+    #[snafu(display(
+        "offset {offset:#x} is past the end of the data, which spans {base_address:#x}..{end:#x} ({size:#x} bytes):\n{backtrace}"
+    ))]
+    StartOutOfBounds {
+        /// Requested start of the window.
+        offset: u32,
+        /// Address of the start of the data.
+        base_address: u32,
+        /// Address of the end of the data.
+        end: u32,
+        /// Size of the data.
+        size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the window starts inside the data but ends past it.
+    #[snafu(display(
+        "window {start:#x}..{window_end:#x} goes past the end of the data at {end:#x}, the maximum length is {max_length:#x}:\n{backtrace}"
+    ))]
+    EndOutOfBounds {
+        /// Address of the start of the window.
+        start: u32,
+        /// Address of the end of the window.
+        window_end: u64,
+        /// Address of the end of the data.
+        end: u32,
+        /// Largest length which fits in the data.
+        max_length: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+impl DataWindow {
+    /// Returns the part of `data` within this window, along with the address where it starts. `base_address` is the
+    /// address of the start of `data`, or zero if it has none.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the window starts or ends past the end of `data`.
+    pub fn slice<'a>(&self, data: &'a [u8], base_address: u32) -> Result<(&'a [u8], u32), DataWindowError> {
+        let size = data.len();
+        let end = base_address + size as u32;
+        let start = match self.offset {
+            None => 0,
+            Some(offset) if base_address != 0 && offset >= base_address => (offset - base_address) as usize,
+            Some(offset) => offset as usize,
+        };
+        if start > size || (start == size && self.offset.is_some()) {
+            return StartOutOfBoundsSnafu { offset: self.offset.unwrap_or(0), base_address, end, size }.fail();
+        }
+
+        let window_end = match self.length {
+            None => size as u64,
+            Some(length) => start as u64 + length as u64,
+        };
+        if window_end > size as u64 {
+            let start = base_address + start as u32;
+            return EndOutOfBoundsSnafu { start, window_end: base_address as u64 + window_end, end, max_length: end - start }
+                .fail();
+        }
+        Ok((&data[start..window_end as usize], base_address + start as u32))
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, Snafu};
+
+use crate::{
+    crypto::sha1::Sha1,
+    io::{create_dir_all, read_dir, read_file, write_file, FileError},
+    str::hex_string,
+};
+
+/// A content-addressed store of file contents, which can be shared between projects so that files which are identical
+/// across ROMs are only stored once. Each blob is saved as `<path>/<sha1>`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DedupStore {
+    /// Path to the store directory. Relative paths are relative to the project root.
+    pub path: PathBuf,
+    /// How project files refer to blobs in the store.
+    #[serde(default)]
+    pub mode: DedupMode,
+}
+
+/// How project files refer to blobs in a [`DedupStore`].
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupMode {
+    /// Project files are small text files containing the SHA-1 hash of their blob.
+    #[default]
+    Reference,
+    /// Project files are hard links to their blob, falling back to [`Self::Reference`] if hard links are not supported.
+    /// Modifying a hard-linked file in place also modifies the blob for every project using it.
+    Hardlink,
+}
+
+/// Errors related to [`DedupStore`].
+#[derive(Debug, Snafu)]
+pub enum DedupError {
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`io::Error`].
+    #[snafu(transparent)]
+    Io {
+        /// Source error.
+        source: io::Error,
+    },
+    /// Occurs when a project file refers to a blob which is missing from the store.
+    #[snafu(display("the file '{}' refers to the blob {sha1} which is missing from the store:\n{backtrace}", path.display()))]
+    MissingBlob {
+        /// Path to the project file.
+        path: PathBuf,
+        /// SHA-1 hash of the blob.
+        sha1: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the contents of a blob don't match its SHA-1 hash.
+    #[snafu(display("the file '{}' refers to the blob {sha1} which is corrupt, its SHA-1 hash is {actual}:\n{backtrace}", path.display()))]
+    CorruptBlob {
+        /// Path to the project file.
+        path: PathBuf,
+        /// SHA-1 hash of the blob.
+        sha1: String,
+        /// Actual SHA-1 hash of the blob contents.
+        actual: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+impl DedupStore {
+    /// Prefix of reference files, followed by the SHA-1 hash of the blob.
+    pub const REFERENCE_PREFIX: &'static str = "ds-rom-blob:";
+
+    fn store_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.path)
+    }
+
+    /// Adds `contents` to the store unless it already exists, then creates a project file at `file_path` which refers to it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails.
+    pub fn save_file(&self, root: &Path, file_path: &Path, contents: &[u8]) -> Result<(), DedupError> {
+        let sha1 = hex_string(&Sha1::digest(contents));
+        let store_path = self.store_path(root);
+        let blob_path = store_path.join(&sha1);
+        if !blob_path.is_file() {
+            create_dir_all(&store_path)?;
+            write_file(&blob_path, contents)?;
+        }
+
+        // Remove the old file first, since it may be a hard link to another blob
+        if file_path.is_file() {
+            fs::remove_file(file_path)?;
+        }
+        create_dir_all(file_path.parent().unwrap())?;
+        if self.mode == DedupMode::Hardlink && fs::hard_link(&blob_path, file_path).is_ok() {
+            return Ok(());
+        }
+        write_file(file_path, format!("{}{sha1}\n", Self::REFERENCE_PREFIX))?;
+        Ok(())
+    }
+
+    /// Reads a project file, resolving it to the contents of its blob if it's a reference file. Files which are not
+    /// reference files are returned as-is, so that they can be replaced with modified contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails, or the referred blob is missing or corrupt.
+    pub fn load_file(&self, root: &Path, file_path: &Path) -> Result<Vec<u8>, DedupError> {
+        let contents = read_file(file_path)?;
+        let Some(sha1) = Self::parse_reference(&contents) else {
+            return Ok(contents);
+        };
+
+        let blob_path = self.store_path(root).join(sha1);
+        if !blob_path.is_file() {
+            return MissingBlobSnafu { path: file_path, sha1 }.fail();
+        }
+        let blob = read_file(blob_path)?;
+        let actual = hex_string(&Sha1::digest(&blob));
+        if actual != sha1 {
+            return CorruptBlobSnafu { path: file_path, sha1, actual }.fail();
+        }
+        Ok(blob)
+    }
+
+    fn parse_reference(contents: &[u8]) -> Option<&str> {
+        let sha1 = std::str::from_utf8(contents.strip_prefix(Self::REFERENCE_PREFIX.as_bytes())?).ok()?.trim();
+        (sha1.len() == 40 && sha1.bytes().all(|b| b.is_ascii_hexdigit())).then_some(sha1)
+    }
+
+    fn load_files_in(&self, root: &Path, dir: &Path, files: &mut BTreeMap<PathBuf, Vec<u8>>) -> Result<(), DedupError> {
+        for entry in read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.load_files_in(root, &path, files)?;
+            } else {
+                files.insert(path.clone(), self.load_file(root, &path)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads every file in `files_dir` with [`Self::load_file`]. The returned paths are relative to `files_dir`, so that they
+    /// can be passed as external files to [`super::FileSystem::load_with_external_files`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::load_file`].
+    pub fn load_files(&self, root: &Path, files_dir: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, DedupError> {
+        let mut files = BTreeMap::new();
+        self.load_files_in(root, files_dir, &mut files)?;
+        Ok(files.into_iter().map(|(path, contents)| (path.strip_prefix(files_dir).unwrap().to_path_buf(), contents)).collect())
+    }
+}
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use super::{raw::ModcryptArea, FileSource};
+
+/// ARM9i or ARM7i program, which is only loaded by DSi consoles. See [`DsiArea`].
+pub struct DsiProgram<'a> {
+    data: Cow<'a, [u8]>,
+    offsets: DsiProgramOffsets,
+}
+
+/// Offsets in a [`DsiProgram`].
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct DsiProgramOffsets {
+    /// Base address.
+    pub base_address: u32,
+    /// Entrypoint function address.
+    pub entry_function: u32,
+    /// Build info offset from the header, or zero if there is none. Unlike for the ARM9 program, this is not an absolute
+    /// ROM offset in all titles, so it is kept as-is.
+    pub build_info: u32,
+}
+
+/// The DSi area of a DSi-enhanced or DSi-exclusive ROM, placed after the DS area. It contains the digest hashtables which
+/// the DSi uses to verify the ROM, and the programs which are only loaded by DSi consoles.
+///
+/// The digest hashtables are copied as-is, so a ROM only passes verification on a DSi console if the DS and DSi areas are
+/// unchanged. The programs are stored decrypted, and the parts inside the modcrypt areas are encrypted when building, see
+/// [`Self::apply_modcrypt`].
+pub struct DsiArea<'a> {
+    arm9i: DsiProgram<'a>,
+    arm7i: DsiProgram<'a>,
+    digest_sector_hashtable: Cow<'a, [u8]>,
+    digest_block_hashtable: Cow<'a, [u8]>,
+}
+
+impl<'a> DsiProgram<'a> {
+    /// Creates a new DSi program from raw data.
+    pub fn new<T: Into<Cow<'a, [u8]>>>(data: T, offsets: DsiProgramOffsets) -> Self {
+        Self { data: data.into(), offsets }
+    }
+
+    /// Returns a copy of this [`DsiProgram`] which owns its data, so that it can outlive the buffer it was parsed from.
+    pub fn to_owned(&self) -> DsiProgram<'static> {
+        DsiProgram { data: Cow::Owned(self.data.to_vec()), offsets: self.offsets }
+    }
+
+    /// Converts this [`DsiProgram`] into one which owns its data. Data which is already owned is moved instead of copied.
+    pub fn into_owned(self) -> DsiProgram<'static> {
+        DsiProgram { data: Cow::Owned(self.data.into_owned()), offsets: self.offsets }
+    }
+
+    /// Returns a reference to the full data.
+    pub fn full_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns where the data of this program is stored, see [`FileSource`].
+    pub fn source(&self) -> FileSource {
+        FileSource::from(&self.data)
+    }
+
+    /// Returns the base address of this.
+    pub fn base_address(&self) -> u32 {
+        self.offsets.base_address
+    }
+
+    /// Returns the entrypoint function address.
+    pub fn entry_function(&self) -> u32 {
+        self.offsets.entry_function
+    }
+
+    /// Returns the build info offset.
+    pub fn build_info_offset(&self) -> u32 {
+        self.offsets.build_info
+    }
+
+    /// De/encrypts the parts of this program which are inside the modcrypt areas, where the program starts at the ROM offset
+    /// `offset`. The data is only copied if it overlaps an area.
+    pub fn apply_modcrypt(&mut self, offset: u32, areas: &[ModcryptArea]) {
+        let size = self.data.len();
+        for area in areas.iter().filter(|area| area.overlaps(offset, size)) {
+            area.apply(self.data.to_mut(), offset);
+        }
+    }
+
+    /// Returns a reference to the offsets.
+    pub fn offsets(&self) -> &DsiProgramOffsets {
+        &self.offsets
+    }
+}
+
+impl<'a> DsiArea<'a> {
+    /// Creates a new DSi area.
+    pub fn new<T: Into<Cow<'a, [u8]>>>(
+        arm9i: DsiProgram<'a>,
+        arm7i: DsiProgram<'a>,
+        digest_sector_hashtable: T,
+        digest_block_hashtable: T,
+    ) -> Self {
+        Self {
+            arm9i,
+            arm7i,
+            digest_sector_hashtable: digest_sector_hashtable.into(),
+            digest_block_hashtable: digest_block_hashtable.into(),
+        }
+    }
+
+    /// Returns a copy of this [`DsiArea`] which owns its data, so that it can outlive the buffer it was parsed from.
+    pub fn to_owned(&self) -> DsiArea<'static> {
+        DsiArea {
+            arm9i: self.arm9i.to_owned(),
+            arm7i: self.arm7i.to_owned(),
+            digest_sector_hashtable: Cow::Owned(self.digest_sector_hashtable.to_vec()),
+            digest_block_hashtable: Cow::Owned(self.digest_block_hashtable.to_vec()),
+        }
+    }
+
+    /// Converts this [`DsiArea`] into one which owns its data. Data which is already owned is moved instead of copied.
+    pub fn into_owned(self) -> DsiArea<'static> {
+        DsiArea {
+            arm9i: self.arm9i.into_owned(),
+            arm7i: self.arm7i.into_owned(),
+            digest_sector_hashtable: Cow::Owned(self.digest_sector_hashtable.into_owned()),
+            digest_block_hashtable: Cow::Owned(self.digest_block_hashtable.into_owned()),
+        }
+    }
+
+    /// De/encrypts the parts of the ARM9i and ARM7i programs which are inside the modcrypt areas, given the ROM offsets of
+    /// the programs. See [`DsiProgram::apply_modcrypt`].
+    pub fn apply_modcrypt(&mut self, areas: &[ModcryptArea], arm9i_offset: u32, arm7i_offset: u32) {
+        self.arm9i.apply_modcrypt(arm9i_offset, areas);
+        self.arm7i.apply_modcrypt(arm7i_offset, areas);
+    }
+
+    /// Returns the ARM9i program.
+    pub fn arm9i(&self) -> &DsiProgram<'a> {
+        &self.arm9i
+    }
+
+    /// Returns the ARM7i program.
+    pub fn arm7i(&self) -> &DsiProgram<'a> {
+        &self.arm7i
+    }
+
+    /// Returns the digest sector hashtable, which has a SHA1-HMAC for each sector of the DS and DSi areas.
+    pub fn digest_sector_hashtable(&self) -> &[u8] {
+        &self.digest_sector_hashtable
+    }
+
+    /// Returns the digest block hashtable, which has a SHA1-HMAC for each block of the sector hashtable.
+    pub fn digest_block_hashtable(&self) -> &[u8] {
+        &self.digest_block_hashtable
+    }
+}
@@ -1,25 +1,32 @@
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::{BinaryHeap, HashSet},
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
     fmt::Display,
+    fs,
     io::Write,
+    mem::size_of,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
 use encoding_rs::SHIFT_JIS;
-use snafu::{Backtrace, Snafu};
+use snafu::{Backtrace, ResultExt, Snafu};
 
-use super::raw::{self, FileAlloc, Fnt, FntDirectory, FntFile, FntSubtable, RawHeaderError};
+use super::{
+    raw::{self, FileAlloc, Fnt, FntDirectory, FntFile, FntSubtable, RawHeaderError},
+    sort::{fnt_name_cmp, rom_name_cmp},
+    EmptyDirFileId,
+};
 use crate::{
-    io::{read_dir, read_file, FileError},
-    str::BlobSize,
+    crypto::sha1::Sha1,
+    io::{read_dir, read_file, FileError, FileSizeChangedSnafu, IoSnafu},
+    str::{hex_string, BlobSize},
 };
 
 /// Contains files and directories to be placed into a ROM.
 pub struct FileSystem<'a> {
-    num_overlays: usize,
+    num_reserved: usize,
     files: Vec<File<'a>>,
     dirs: Vec<Dir>,
     next_file_id: u16,
@@ -32,7 +39,33 @@ pub struct File<'a> {
     id: u16,
     name: String,
     original_offset: u32,
-    contents: Cow<'a, [u8]>,
+    contents: FileContents<'a>,
+}
+
+#[derive(Clone)]
+enum FileContents<'a> {
+    Loaded(Cow<'a, [u8]>),
+    OnDisk { path: PathBuf, size: usize },
+}
+
+/// Where the contents of a [`File`] or module are stored, see [`File::source`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileSource {
+    /// Borrowed from another buffer, usually the raw ROM it was parsed from.
+    Borrowed,
+    /// Owned by the file itself, for example after loading it from disk or modifying it.
+    Owned,
+    /// Not loaded yet, and read from disk when needed. See [`FileSystem::load_lazy`].
+    OnDisk,
+}
+
+impl From<&Cow<'_, [u8]>> for FileSource {
+    fn from(contents: &Cow<'_, [u8]>) -> Self {
+        match contents {
+            Cow::Borrowed(_) => Self::Borrowed,
+            Cow::Owned(_) => Self::Owned,
+        }
+    }
 }
 
 /// A directory for the [`FileSystem`] struct.
@@ -42,6 +75,36 @@ pub struct Dir {
     name: String,
     parent_id: u16,
     children: Vec<u16>,
+    original_first_file_id: Option<u16>,
+    /// Names of the children in the order they appear in the FNT, if known.
+    fnt_order: Option<Vec<String>>,
+}
+
+/// Result of [`FileSystem::analyze_ordering`].
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct OrderingReport {
+    /// Directories whose children are not in [`fnt_name_cmp`] order in the FNT.
+    pub unsorted_dirs: Vec<UnsortedDir>,
+    /// Whether the file contents are stored in a different order than their file IDs.
+    pub file_image_differs: bool,
+}
+
+/// A directory whose children are not in [`fnt_name_cmp`] order, see [`OrderingReport`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnsortedDir {
+    /// Path to the directory.
+    pub path: String,
+    /// Name of the first child in the first out-of-order pair.
+    pub first: String,
+    /// Name of the second child in the first out-of-order pair, which should have come before `first`.
+    pub second: String,
+}
+
+impl OrderingReport {
+    /// Returns whether every directory in the FNT is in [`fnt_name_cmp`] order.
+    pub fn is_fnt_sorted(&self) -> bool {
+        self.unsorted_dirs.is_empty()
+    }
 }
 
 /// Errors related to [`FileSystem::parse`].
@@ -82,50 +145,428 @@ pub enum FileBuildError {
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
+    /// Occurs when the file IDs in a directory are not sequential, which can't be represented in the FNT.
+    #[snafu(display(
+        "expected file ID {expected} but got {actual} in directory '{name}' ({id:#x}), file IDs must be sequential:\n{backtrace}"
+    ))]
+    NonContiguousFileIds {
+        /// Directory name.
+        name: String,
+        /// Directory ID.
+        id: u16,
+        /// Expected file ID.
+        expected: u16,
+        /// Actual file ID.
+        actual: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`FileSystem::resolve_file_order`].
+#[derive(Debug, Snafu)]
+pub enum FileOrderError {
+    /// Occurs when a path in the file order does not exist in the file system.
+    #[snafu(display("line {line}: the path '{path}' does not exist in the file system:\n{backtrace}"))]
+    FileNotFound {
+        /// Line number, starting at 1.
+        line: usize,
+        /// Path on the line.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a path in the file order is a directory.
+    #[snafu(display("line {line}: the path '{path}' is a directory, the file order must only list files:\n{backtrace}"))]
+    NotAFile {
+        /// Line number, starting at 1.
+        line: usize,
+        /// Path on the line.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a file is listed more than once in the file order.
+    #[snafu(display("line {line}: the file '{path}' was already listed on line {first_line}:\n{backtrace}"))]
+    DuplicateFile {
+        /// Line number, starting at 1.
+        line: usize,
+        /// Line number of the first occurrence, starting at 1.
+        first_line: usize,
+        /// Path on the line.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a file in the file system is not listed in the file order.
+    #[snafu(display("the file '{path}' is missing from the file order:\n{backtrace}"))]
+    MissingFile {
+        /// Path to the file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`FileSystem::add_file`], [`FileSystem::add_dir`], [`FileSystem::remove_file`] and
+/// [`FileSystem::replace_file_contents`].
+#[derive(Debug, Snafu)]
+pub enum FileEditError {
+    /// See [`FilePathError`].
+    #[snafu(transparent)]
+    FilePath {
+        /// Source error.
+        source: FilePathError,
+    },
+    /// Occurs when adding a file or directory to a path which already exists.
+    #[snafu(display("the path '{path}' already exists in the file system:\n{backtrace}"))]
+    PathExists {
+        /// Path to the file or directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a path has no name, such as an empty path or one ending with `/`.
+    #[snafu(display("the path '{path}' has no file or directory name:\n{backtrace}"))]
+    EmptyName {
+        /// Path to the file or directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the parent of a path is a file.
+    #[snafu(display("the parent of '{path}' is a file, not a directory:\n{backtrace}"))]
+    ParentIsFile {
+        /// Path to the file or directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when removing or replacing a file, but the path is a directory.
+    #[snafu(display("'{path}' is a directory, not a file:\n{backtrace}"))]
+    PathIsDir {
+        /// Path to the directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when adding a file would exceed the highest file ID.
+    #[snafu(display("cannot add '{path}', the file system is limited to {max} file IDs:\n{backtrace}"))]
+    TooManyFiles {
+        /// Path to the file.
+        path: String,
+        /// Max number of file IDs, including reserved ones.
+        max: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when adding a directory would exceed the highest directory ID.
+    #[snafu(display("cannot add '{path}', the file system is limited to {max} directories:\n{backtrace}"))]
+    TooManyDirs {
+        /// Path to the directory.
+        path: String,
+        /// Max number of directories, including the root.
+        max: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`FileSystem::resolve_path`].
+#[derive(Debug, Snafu)]
+pub enum FilePathError {
+    /// Occurs when the path doesn't exist in the file system.
+    #[snafu(display("the path '{path}' does not exist in the file system:\n{backtrace}"))]
+    PathNotFound {
+        /// Path to the file or directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a directory along the path has more than one child with the same name, so the path could refer to
+    /// either of them.
+    #[snafu(display("the path '{path}' is ambiguous, it matches the IDs {ids:04x?}:\n{backtrace}"))]
+    AmbiguousPath {
+        /// Path up to and including the ambiguous name.
+        path: String,
+        /// IDs of the files or directories with that name.
+        ids: Vec<u16>,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`FileSystem::open_embedded_rom`].
+#[derive(Snafu, Debug)]
+pub enum RomEmbeddedError {
+    /// Occurs when the given path does not exist in the file system.
+    #[snafu(display("the file '{path}' was not found in the ROM file system:\n{backtrace}"))]
+    NotFound {
+        /// Path to the embedded ROM.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the given path points to a directory.
+    #[snafu(display("'{path}' is a directory, not a file:\n{backtrace}"))]
+    IsDirectory {
+        /// Path to the directory.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`FilePathError`].
+    #[snafu(transparent)]
+    FilePath {
+        /// Source error.
+        source: FilePathError,
+    },
+    /// Occurs when the embedded header has a gamecode with non-printable or non-ASCII characters.
+    #[snafu(display("the file '{path}' does not look like a ROM, its gamecode is {gamecode:02x?}:\n{backtrace}"))]
+    InvalidGamecode {
+        /// Path to the embedded ROM.
+        path: String,
+        /// Raw gamecode bytes.
+        gamecode: [u8; 4],
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the embedded header's CRC checksum is incorrect.
+    #[snafu(display(
+        "the file '{path}' does not look like a ROM, expected header CRC {expected:#06x} but got {actual:#06x}:\n{backtrace}"
+    ))]
+    HeaderCrc {
+        /// Path to the embedded ROM.
+        path: String,
+        /// Computed checksum.
+        expected: u16,
+        /// Checksum in the header.
+        actual: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 const ROOT_DIR_ID: u16 = 0xf000;
+const MAX_DIRS: u16 = 0x1000;
 
 impl<'a> FileSystem<'a> {
-    /// Creates a new [`FileSystem`]. The number of overlays are used to determine the first file ID, since overlays are also
-    /// located in the FAT but not the FNT.
-    pub fn new(num_overlays: usize) -> Self {
-        let root = Dir { id: ROOT_DIR_ID, name: "/".to_string(), parent_id: 0, children: vec![] };
-        Self { num_overlays, files: vec![], dirs: vec![root], next_file_id: num_overlays as u16, next_dir_id: ROOT_DIR_ID + 1 }
+    /// Creates a new [`FileSystem`]. The number of reserved FAT entries is used to determine the first file ID, since
+    /// overlays are also located in the FAT but not the FNT. This is normally the number of overlays, see
+    /// [`super::FatLayout::reserved_entries`].
+    pub fn new(num_reserved: usize) -> Self {
+        let root = Dir {
+            id: ROOT_DIR_ID,
+            name: "/".to_string(),
+            parent_id: 0,
+            children: vec![],
+            original_first_file_id: None,
+            fnt_order: None,
+        };
+        Self { num_reserved, files: vec![], dirs: vec![root], next_file_id: num_reserved as u16, next_dir_id: ROOT_DIR_ID + 1 }
     }
 
-    fn load_in<P: AsRef<Path>>(&mut self, path: P, parent_id: u16) -> Result<(), FileError> {
+    /// Returns a copy of this [`FileSystem`] which owns all file contents, so that it can outlive the buffer it was parsed
+    /// from.
+    pub fn to_owned(&self) -> FileSystem<'static> {
+        FileSystem {
+            num_reserved: self.num_reserved,
+            files: self.files.iter().map(File::to_owned).collect(),
+            dirs: self.dirs.clone(),
+            next_file_id: self.next_file_id,
+            next_dir_id: self.next_dir_id,
+        }
+    }
+
+    /// Converts this [`FileSystem`] into one which owns all file contents. Contents which are already owned are moved
+    /// instead of copied.
+    pub fn into_owned(self) -> FileSystem<'static> {
+        FileSystem {
+            num_reserved: self.num_reserved,
+            files: self.files.into_iter().map(File::into_owned).collect(),
+            dirs: self.dirs,
+            next_file_id: self.next_file_id,
+            next_dir_id: self.next_dir_id,
+        }
+    }
+
+    fn load_in<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        parent_id: u16,
+        parent_path: &str,
+        fnt_order: &HashMap<&str, Vec<String>>,
+        external_files: &mut BTreeMap<PathBuf, Vec<u8>>,
+        lazy: bool,
+    ) -> Result<(), FileError> {
         // Sort children by FNT order so the file/dir IDs become correct
-        let mut children =
-            read_dir(&path)?.collect::<Result<Vec<_>, _>>()?.into_iter().map(|entry| entry.path()).collect::<Vec<_>>();
+        let mut children = read_dir(&path)?
+            .collect::<Result<Vec<_>, _>>()
+            .context(IoSnafu { path: path.as_ref().to_string_lossy() })?
+            .into_iter()
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        children.extend(
+            external_files
+                .keys()
+                .filter(|file| file.parent() == Some(path.as_ref()) && !children.contains(file))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
         children.sort_unstable_by(|a, b| {
-            Self::compare_for_fnt(a.to_string_lossy().as_ref(), a.is_dir(), b.to_string_lossy().as_ref(), b.is_dir())
+            fnt_name_cmp(a.to_string_lossy().as_ref(), b.to_string_lossy().as_ref(), a.is_dir(), b.is_dir())
         });
 
-        for child in children.into_iter() {
+        // The FNT only stores the first file ID of each directory, so all files must get their IDs before any subdirectory
+        let (dirs, files): (Vec<_>, Vec<_>) = children.into_iter().partition(|child| child.is_dir());
+        for child in files {
             let name = child.file_name().unwrap().to_string_lossy().to_string();
-            if child.is_dir() {
-                let child_id = self.next_dir_id;
-                let child_path = path.as_ref().join(&name);
-                self.make_child_dir(name, parent_id);
-                self.load_in(child_path, child_id)?;
-            } else {
-                let contents = read_file(child)?;
-                self.make_child_file(name, parent_id, contents);
-            }
+            let contents = match external_files.remove(&child) {
+                Some(contents) => FileContents::Loaded(contents.into()),
+                None if lazy => {
+                    let metadata = fs::metadata(&child).context(IoSnafu { path: child.to_string_lossy() })?;
+                    FileContents::OnDisk { path: child, size: metadata.len() as usize }
+                }
+                None => FileContents::Loaded(read_file(child)?.into()),
+            };
+            self.make_child_file(name, parent_id, contents);
+        }
+        for child in dirs {
+            let name = child.file_name().unwrap().to_string_lossy().to_string();
+            let child_id = self.next_dir_id;
+            let child_path = format!("{parent_path}/{name}");
+            self.make_child_dir(name, parent_id);
+            self.load_in(child, child_id, &child_path, fnt_order, external_files, lazy)?;
+        }
+
+        if let Some(order) = fnt_order.get(parent_path) {
+            let parent = self.dir(parent_id);
+            let children = self.order_for_fnt(&parent.children, order);
+            let parent = self.dir_mut(parent_id);
+            parent.children = children;
+            parent.fnt_order = Some(order.clone());
+            self.renumber_files_in_order(parent_id);
         }
         Ok(())
     }
 
+    /// Reassigns the file IDs of a directory's files to follow their order among the directory's children, since the FNT
+    /// implies that file IDs are sequential. The files must already have a contiguous range of IDs, as assigned by
+    /// [`Self::load_in`].
+    fn renumber_files_in_order(&mut self, parent_id: u16) {
+        let file_ids = self.dir(parent_id).children.iter().copied().filter(|id| Self::is_file(*id)).collect::<Vec<_>>();
+        let Some(&first_id) = file_ids.iter().min() else {
+            return;
+        };
+        let start = first_id as usize - self.num_reserved;
+        let mut block = self.files.drain(start..start + file_ids.len()).map(Some).collect::<Vec<_>>();
+        let renumbered = file_ids
+            .iter()
+            .zip(first_id..)
+            .map(|(&old_id, new_id)| {
+                let mut file = block[(old_id - first_id) as usize].take().unwrap();
+                file.id = new_id;
+                file
+            })
+            .collect::<Vec<_>>();
+        self.files.splice(start..start, renumbered);
+
+        let mut new_ids = first_id..;
+        for child in &mut self.dir_mut(parent_id).children {
+            if Self::is_file(*child) {
+                *child = new_ids.next().unwrap();
+            }
+        }
+    }
+
     /// Loads a file system from the given root directory. This will traverse and add all folders and files into the
     /// [`FileSystem`] struct.
     ///
     /// # Errors
     ///
     /// This function will return an error if an I/O operation fails.
-    pub fn load<P: AsRef<Path>>(root: P, num_overlays: usize) -> Result<Self, FileError> {
-        let mut files = Self::new(num_overlays);
-        files.load_in(root, ROOT_DIR_ID)?;
+    pub fn load<P: AsRef<Path>>(root: P, num_reserved: usize) -> Result<Self, FileError> {
+        Self::load_with_external_files(root, num_reserved, BTreeMap::new())
+    }
+
+    /// Same as [`Self::load`], but also adds files which are not present in the root directory. The keys of
+    /// `external_files` are paths relative to the root directory, and their parent directories must exist. If a file exists
+    /// both on disk and in `external_files`, the contents in `external_files` are used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails.
+    pub fn load_with_external_files<P: AsRef<Path>>(
+        root: P,
+        num_reserved: usize,
+        external_files: BTreeMap<PathBuf, Vec<u8>>,
+    ) -> Result<Self, FileError> {
+        Self::load_with_fnt_order(root, num_reserved, external_files, &[])
+    }
+
+    /// Same as [`Self::load_with_external_files`], but orders the children of each directory by `fnt_order`, which lists
+    /// paths as returned by [`Self::fnt_order`]. Files and directories which are not listed are inserted in their sorted
+    /// position among their siblings.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails.
+    pub fn load_with_fnt_order<P: AsRef<Path>>(
+        root: P,
+        num_reserved: usize,
+        external_files: BTreeMap<PathBuf, Vec<u8>>,
+        fnt_order: &[String],
+    ) -> Result<Self, FileError> {
+        Self::load_impl(root.as_ref(), num_reserved, external_files, fnt_order, false)
+    }
+
+    /// Same as [`Self::load_with_fnt_order`], but only the sizes of files on disk are read. Their contents are read while
+    /// building, see [`File::read_contents`], so that the whole files directory doesn't need to fit in memory at once.
+    /// The files must not change size until the ROM has been built.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails.
+    pub fn load_lazy<P: AsRef<Path>>(
+        root: P,
+        num_reserved: usize,
+        external_files: BTreeMap<PathBuf, Vec<u8>>,
+        fnt_order: &[String],
+    ) -> Result<Self, FileError> {
+        Self::load_impl(root.as_ref(), num_reserved, external_files, fnt_order, true)
+    }
+
+    fn load_impl(
+        root: &Path,
+        num_reserved: usize,
+        external_files: BTreeMap<PathBuf, Vec<u8>>,
+        fnt_order: &[String],
+        lazy: bool,
+    ) -> Result<Self, FileError> {
+        let mut external_files = external_files.into_iter().map(|(path, contents)| (root.join(path), contents)).collect();
+
+        let mut dir_orders: HashMap<&str, Vec<String>> = HashMap::new();
+        for path in fnt_order {
+            if let Some((parent_path, name)) = path.rsplit_once('/') {
+                dir_orders.entry(parent_path).or_default().push(name.to_string());
+            }
+        }
+
+        let mut files = Self::new(num_reserved);
+        files.load_in(root, ROOT_DIR_ID, "", &dir_orders, &mut external_files, lazy)?;
+        files.warn_duplicate_names(ROOT_DIR_ID, "");
         Ok(files)
     }
 
@@ -157,9 +598,339 @@ impl<'a> FileSystem<'a> {
         &mut self.dirs[id as usize & 0xfff]
     }
 
+    /// Returns the number of files in this [`FileSystem`].
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns the number of directories in this [`FileSystem`], including the root directory. This is also the number of
+    /// subtables in the FNT.
+    pub fn dir_count(&self) -> usize {
+        self.dirs.len()
+    }
+
+    /// Returns the depth of the most deeply nested file or directory, where children of the root directory have a depth
+    /// of 1. Returns 0 if the [`FileSystem`] is empty.
+    pub fn max_depth(&self) -> usize {
+        self.stats().max_depth
+    }
+
+    /// Computes statistics about the files and names in this [`FileSystem`].
+    pub fn stats(&self) -> FsStats {
+        let mut stats = FsStats {
+            file_count: self.file_count(),
+            dir_count: self.dir_count(),
+            total_content_size: self.total_content_size(),
+            ..Default::default()
+        };
+        self.stats_in("", 1, self.dir(ROOT_DIR_ID), &mut stats);
+        stats
+    }
+
+    fn stats_in(&self, path: &str, depth: usize, parent: &Dir, stats: &mut FsStats) {
+        for &child in &parent.children {
+            let name = self.name(child);
+            let path = format!("{path}/{name}");
+            if name.chars().count() > stats.longest_name.chars().count() {
+                stats.longest_name = name.to_string();
+            }
+            if !name.is_ascii() {
+                stats.non_ascii_names += 1;
+            }
+            if depth > stats.max_depth {
+                stats.max_depth = depth;
+                stats.deepest_path = path.clone();
+            }
+            if Self::is_dir(child) {
+                self.stats_in(&path, depth + 1, self.dir(child), stats);
+            }
+        }
+    }
+
+    /// Returns the total size of all file contents.
+    pub fn total_content_size(&self) -> usize {
+        self.files.iter().map(File::size).sum()
+    }
+
+    /// Returns the total size of file contents which are owned by this [`FileSystem`], as opposed to borrowed. See
+    /// [`File::source`].
+    pub fn owned_bytes(&self) -> usize {
+        self.files.iter().filter(|file| file.source() == FileSource::Owned).map(File::size).sum()
+    }
+
+    /// Finds files with identical contents, grouped by their SHA-1 hash. Empty files and files with unique contents are not
+    /// included. The groups are sorted by [`DuplicateGroup::wasted_bytes`] in descending order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the contents of a file loaded by [`Self::load_lazy`] can't be read, see
+    /// [`File::read_contents`].
+    pub fn duplicate_report(&self) -> Result<Vec<DuplicateGroup>, FileError> {
+        let mut files_by_hash = HashMap::new();
+        self.find_duplicates_in("", self.dir(ROOT_DIR_ID), &mut files_by_hash)?;
+
+        let mut groups = files_by_hash
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(sha1, mut files)| {
+                files.sort_unstable_by_key(|&(id, _)| id);
+                let size = self.file(files[0].0).size();
+                DuplicateGroup { sha1: hex_string(&sha1), size, paths: files.into_iter().map(|(_, path)| path).collect() }
+            })
+            .collect::<Vec<_>>();
+        groups.sort_unstable_by(|a, b| b.wasted_bytes().cmp(&a.wasted_bytes()).then_with(|| a.paths.cmp(&b.paths)));
+        Ok(groups)
+    }
+
+    fn find_duplicates_in(
+        &self,
+        path: &str,
+        parent: &Dir,
+        files_by_hash: &mut HashMap<[u8; 0x14], Vec<(u16, String)>>,
+    ) -> Result<(), FileError> {
+        for &child in &parent.children {
+            let path = format!("{path}/{}", self.name(child));
+            if Self::is_dir(child) {
+                self.find_duplicates_in(&path, self.dir(child), files_by_hash)?;
+                continue;
+            }
+            let file = self.file(child);
+            if file.size() > 0 {
+                files_by_hash.entry(Sha1::digest(&file.read_contents()?)).or_default().push((child, path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of FAT entries reserved before the first file, see [`Self::new`].
+    pub fn num_reserved(&self) -> usize {
+        self.num_reserved
+    }
+
+    /// Reserves one more FAT entry before the first file, such as for the file of a new overlay, and returns its file ID. The
+    /// IDs of all files are shifted up by one. Returns `None` if there are no more file IDs.
+    pub fn reserve_entry(&mut self) -> Option<u16> {
+        if self.next_file_id >= ROOT_DIR_ID {
+            return None;
+        }
+        for file in &mut self.files {
+            file.id += 1;
+        }
+        for dir in &mut self.dirs {
+            for child in dir.children.iter_mut().filter(|child| Self::is_file(**child)) {
+                *child += 1;
+            }
+            if let Some(first_file_id) = dir.original_first_file_id.as_mut() {
+                *first_file_id += 1;
+            }
+        }
+        let id = self.num_reserved as u16;
+        self.num_reserved += 1;
+        self.next_file_id += 1;
+        Some(id)
+    }
+
     /// Returns a file.
     pub fn file(&self, id: u16) -> &File {
-        &self.files[id as usize - self.num_overlays]
+        &self.files[id as usize - self.num_reserved]
+    }
+
+    /// Replaces the contents of a file. The file is placed according to its new size when building, see
+    /// [`super::Rom::plan_layout`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not the ID of a file in this [`FileSystem`], see [`Self::find_path`].
+    pub fn set_file_contents<T: Into<Cow<'a, [u8]>>>(&mut self, id: u16, contents: T) {
+        self.files[id as usize - self.num_reserved].contents = FileContents::Loaded(contents.into());
+    }
+
+    /// Replaces the contents of the file at `path`, which is relative to the root directory. Returns the ID of the file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path doesn't exist or is a directory.
+    pub fn replace_file_contents<T: Into<Cow<'a, [u8]>>>(&mut self, path: &str, contents: T) -> Result<u16, FileEditError> {
+        let id = self.find_edited_file(path)?;
+        self.set_file_contents(id, contents);
+        Ok(id)
+    }
+
+    /// Adds a file at `path`, which is relative to the root directory. The file is inserted in [`fnt_name_cmp`] order among
+    /// its siblings and gets a file ID which follows them, so the IDs of later files are shifted up by one. Returns the ID
+    /// of the new file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the parent directory doesn't exist, the path already exists or there are no
+    /// more file IDs.
+    pub fn add_file<T: Into<Cow<'a, [u8]>>>(&mut self, path: &str, contents: T) -> Result<u16, FileEditError> {
+        let (parent_id, name) = self.find_new_child(path)?;
+        if self.next_file_id >= ROOT_DIR_ID {
+            return TooManyFilesSnafu { path, max: ROOT_DIR_ID }.fail();
+        }
+        let position = self.child_position(parent_id, &name, false);
+
+        // File IDs must be sequential within a directory, so the new file takes the ID after its preceding sibling
+        let parent = self.dir(parent_id);
+        let preceding = parent.children[..position].iter().rev().find(|&&child| Self::is_file(child));
+        let following = parent.children[position..].iter().find(|&&child| Self::is_file(child));
+        let id = match (preceding, following) {
+            (Some(&preceding), _) => preceding + 1,
+            (None, Some(&following)) => following,
+            (None, None) => parent.original_first_file_id.map_or(self.next_file_id, |id| id.min(self.next_file_id)),
+        };
+
+        for file in &mut self.files[(id as usize - self.num_reserved)..] {
+            file.id += 1;
+        }
+        for dir in &mut self.dirs {
+            for child in dir.children.iter_mut().filter(|child| Self::is_file(**child) && **child >= id) {
+                *child += 1;
+            }
+            if let Some(first_file_id) = dir.original_first_file_id.as_mut().filter(|first_file_id| **first_file_id > id) {
+                *first_file_id += 1;
+            }
+        }
+        let file = File { id, name, original_offset: 0, contents: FileContents::Loaded(contents.into()) };
+        self.files.insert(id as usize - self.num_reserved, file);
+        self.dir_mut(parent_id).children.insert(position, id);
+        self.next_file_id += 1;
+        Ok(id)
+    }
+
+    /// Removes the file at `path`, which is relative to the root directory. The IDs of later files are shifted down by one,
+    /// so that the FAT built later has no gap. Returns the removed file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path doesn't exist or is a directory.
+    pub fn remove_file(&mut self, path: &str) -> Result<File<'a>, FileEditError> {
+        let id = self.find_edited_file(path)?;
+        let mut file = self.files.remove(id as usize - self.num_reserved);
+        for file in &mut self.files[(id as usize - self.num_reserved)..] {
+            file.id -= 1;
+        }
+        for dir in &mut self.dirs {
+            if let Some(position) = dir.children.iter().position(|&child| child == id) {
+                dir.children.remove(position);
+                if !dir.children.iter().any(|&child| Self::is_file(child)) {
+                    // Directories without files still store a first file ID, which is where this file was
+                    dir.original_first_file_id = Some(id);
+                }
+            }
+            for child in dir.children.iter_mut().filter(|child| Self::is_file(**child) && **child > id) {
+                *child -= 1;
+            }
+            if let Some(first_file_id) = dir.original_first_file_id.as_mut().filter(|first_file_id| **first_file_id > id) {
+                *first_file_id -= 1;
+            }
+        }
+        self.next_file_id -= 1;
+        file.id = 0;
+        Ok(file)
+    }
+
+    /// Adds an empty directory at `path`, which is relative to the root directory. The directory is inserted in
+    /// [`fnt_name_cmp`] order among its siblings. Directory IDs follow the order of the FNT, so the IDs of later directories
+    /// are shifted up by one. Returns the ID of the new directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the parent directory doesn't exist, the path already exists or there are no
+    /// more directory IDs.
+    pub fn add_dir(&mut self, path: &str) -> Result<u16, FileEditError> {
+        let (parent_id, name) = self.find_new_child(path)?;
+        if self.dirs.len() >= MAX_DIRS as usize {
+            return TooManyDirsSnafu { path, max: MAX_DIRS }.fail();
+        }
+        let position = self.child_position(parent_id, &name, true);
+        let id = ROOT_DIR_ID + self.dirs.len() as u16;
+        self.dirs.push(Dir { id, name, parent_id, children: vec![], original_first_file_id: None, fnt_order: None });
+        self.dir_mut(parent_id).children.insert(position, id);
+
+        let new_ids = self.renumber_dirs();
+        Ok(new_ids[&id])
+    }
+
+    fn find_edited_file(&self, path: &str) -> Result<u16, FileEditError> {
+        let stripped_path = path.strip_prefix('/').unwrap_or(path);
+        let id = self.resolve_path(stripped_path)?;
+        if Self::is_dir(id) {
+            return PathIsDirSnafu { path }.fail();
+        }
+        Ok(id)
+    }
+
+    /// Returns the parent directory ID and name of a file or directory to be added.
+    fn find_new_child(&self, path: &str) -> Result<(u16, String), FileEditError> {
+        let stripped_path = path.strip_prefix('/').unwrap_or(path);
+        let (parent_path, name) = stripped_path.rsplit_once('/').unwrap_or(("", stripped_path));
+        if name.is_empty() {
+            return EmptyNameSnafu { path }.fail();
+        }
+        let parent_id = if parent_path.is_empty() {
+            ROOT_DIR_ID
+        } else {
+            match self.resolve_path(parent_path)? {
+                id if Self::is_dir(id) => id,
+                _ => return ParentIsFileSnafu { path }.fail(),
+            }
+        };
+        if self.dir(parent_id).children.iter().any(|&child| self.name(child) == name) {
+            return PathExistsSnafu { path }.fail();
+        }
+        Ok((parent_id, name.to_string()))
+    }
+
+    /// Returns the index in a directory's children where a new child should be inserted.
+    fn child_position(&self, parent_id: u16, name: &str, is_dir: bool) -> usize {
+        let children = &self.dir(parent_id).children;
+        children
+            .iter()
+            .position(|&child| fnt_name_cmp(name, self.name(child), is_dir, Self::is_dir(child)) == Ordering::Less)
+            .unwrap_or(children.len())
+    }
+
+    /// Reassigns directory IDs in the order their subtables are written to the FNT, since a directory's ID is the index of
+    /// its subtable. Returns the new IDs by old ID.
+    fn renumber_dirs(&mut self) -> HashMap<u16, u16> {
+        let mut order = vec![];
+        let mut stack = vec![ROOT_DIR_ID];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.dir(id).children.iter().rev().copied().filter(|&child| Self::is_dir(child)));
+        }
+        let new_ids = order.iter().zip(ROOT_DIR_ID..).map(|(&old, new)| (old, new)).collect::<HashMap<_, _>>();
+
+        let mut dirs = order.iter().map(|&id| self.dir(id).clone()).collect::<Vec<_>>();
+        for dir in &mut dirs {
+            dir.id = new_ids[&dir.id];
+            if !dir.is_root() {
+                dir.parent_id = new_ids[&dir.parent_id];
+            }
+            for child in dir.children.iter_mut().filter(|child| Self::is_dir(**child)) {
+                *child = new_ids[child];
+            }
+        }
+        self.dirs = dirs;
+        self.next_dir_id = ROOT_DIR_ID + self.dirs.len() as u16;
+        new_ids
+    }
+
+    /// Reads the contents of all files which were loaded by [`Self::load_lazy`], so that they can be accessed with
+    /// [`File::contents`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails, or if a file has changed size since it was loaded.
+    pub fn load_contents(&mut self) -> Result<(), FileError> {
+        for file in &mut self.files {
+            if let FileContents::OnDisk { .. } = file.contents {
+                file.contents = FileContents::Loaded(Cow::Owned(file.read_contents()?.into_owned()));
+            }
+        }
+        Ok(())
     }
 
     fn parse_subtable(
@@ -175,13 +946,17 @@ impl<'a> FileSystem<'a> {
 
         let mut max_file_id = 0;
         let mut max_dir_id = 0;
-        for FntFile { id, name } in subtable.iter() {
+        let mut fnt_order = vec![];
+        for FntFile { id, name, .. } in subtable.iter() {
             let name = name.to_string();
+            fnt_order.push(name.clone());
 
             if Self::is_dir(id) {
                 max_dir_id = max_dir_id.max(id);
-                let mut dir = Dir { id, name, parent_id: parent.id, children: vec![] };
-                let (max_child_dir_id, max_child_file_id) = Self::parse_subtable(fnt, fat, rom, &mut dir, dirs, files);
+                let original_first_file_id = Some(fnt.subtables[id as usize & 0xfff].directory.first_file_id);
+                let mut dir =
+                    Dir { id, name, parent_id: parent.id, children: vec![], original_first_file_id, fnt_order: None };
+                let (max_child_file_id, max_child_dir_id) = Self::parse_subtable(fnt, fat, rom, &mut dir, dirs, files);
                 max_dir_id = max_dir_id.max(max_child_dir_id);
                 max_file_id = max_file_id.max(max_child_file_id);
 
@@ -191,10 +966,16 @@ impl<'a> FileSystem<'a> {
                 max_file_id = max_file_id.max(id);
                 let alloc = fat[id as usize];
                 let contents = &rom.data()[alloc.range()];
-                files[id as usize] = Some(File { id, name, original_offset: alloc.start, contents: Cow::Borrowed(contents) });
+                files[id as usize] = Some(File {
+                    id,
+                    name,
+                    original_offset: alloc.start,
+                    contents: FileContents::Loaded(Cow::Borrowed(contents)),
+                });
                 parent.children.push(id);
             }
         }
+        parent.fnt_order = Some(fnt_order);
         (max_file_id, max_dir_id)
     }
 
@@ -207,17 +988,35 @@ impl<'a> FileSystem<'a> {
     pub fn parse(fnt: &Fnt, fat: &[FileAlloc], rom: &'a raw::Rom) -> Result<Self, FileParseError> {
         let num_overlays = rom.num_arm9_overlays()? + rom.num_arm7_overlays()?;
 
-        let mut root = Dir { id: ROOT_DIR_ID, name: "/".to_string(), parent_id: 0, children: vec![] };
-        let mut dirs = vec![None; fnt.subtables.len()];
+        let original_first_file_id = fnt.subtables.first().map(|subtable| subtable.directory.first_file_id);
+        let mut root = Dir {
+            id: ROOT_DIR_ID,
+            name: "/".to_string(),
+            parent_id: 0,
+            children: vec![],
+            original_first_file_id,
+            fnt_order: None,
+        };
+        // An empty FNT has no root subtable, the root directory is then empty
+        let mut dirs = vec![None; fnt.subtables.len().max(1)];
         let mut files = vec![None; fat.len()];
-        let (max_file_id, max_dir_id) = Self::parse_subtable(fnt, fat, rom, &mut root, &mut dirs, &mut files);
+        let (max_file_id, max_dir_id) = if fnt.subtables.is_empty() {
+            (0, ROOT_DIR_ID)
+        } else {
+            Self::parse_subtable(fnt, fat, rom, &mut root, &mut dirs, &mut files)
+        };
         dirs[0] = Some(root);
 
+        // Files normally start right after the overlays and end at the last FAT entry, but some ROMs reserve more entries
+        // at the start or leave unused entries at the end
+        let num_reserved = files.iter().position(Option::is_some).unwrap_or(num_overlays);
+        let num_files = if num_reserved < files.len() { max_file_id as usize + 1 } else { num_reserved };
         let files = files
             .into_iter()
-            .skip(num_overlays)
+            .take(num_files)
+            .skip(num_reserved)
             .enumerate()
-            .map(|(id, f)| f.ok_or(MissingFileIdSnafu { id: id as u16 }.build()))
+            .map(|(id, f)| f.ok_or(MissingFileIdSnafu { id: (id + num_reserved) as u16 }.build()))
             .collect::<Result<Vec<_>, _>>()?;
         let dirs = dirs
             .into_iter()
@@ -225,21 +1024,52 @@ impl<'a> FileSystem<'a> {
             .map(|(id, d)| d.ok_or(MissingDirIdSnafu { id: id as u16 + ROOT_DIR_ID }.build()))
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(FileSystem { files, dirs, num_overlays, next_file_id: max_file_id + 1, next_dir_id: max_dir_id + 1 })
+        let files = FileSystem { files, dirs, num_reserved, next_file_id: num_files as u16, next_dir_id: max_dir_id + 1 };
+        files.warn_duplicate_names(ROOT_DIR_ID, "");
+        Ok(files)
     }
 
-    fn find_first_file_id(&self, parent: &Dir) -> u16 {
-        for child in &parent.children {
-            if Self::is_file(*child) {
-                return *child;
-            } else {
-                return self.find_first_file_id(self.dir(*child));
+    /// Logs a warning for each name which appears more than once in the same directory. Such files and directories can
+    /// still be built by ID, but not looked up by path.
+    fn warn_duplicate_names(&self, parent_id: u16, parent_path: &str) {
+        let children = &self.dir(parent_id).children;
+        for (index, &child) in children.iter().enumerate() {
+            let name = self.name(child);
+            if let Some(&first) = children[..index].iter().find(|&&id| self.name(id) == name) {
+                log::warn!(
+                    "Directory '/{parent_path}' contains '{name}' more than once (IDs {first:#06x} and {child:#06x}), it can \
+                    only be accessed by ID"
+                );
+            }
+            if Self::is_dir(child) {
+                self.warn_duplicate_names(child, &format!("{parent_path}{name}/"));
             }
         }
-        panic!("No first file ID found, directory is empty");
     }
 
-    fn build_subtable(&self, parent: &Dir) -> Result<FntSubtable, FileBuildError> {
+    fn first_file_id(&self, parent: &Dir, next_file_id: u16) -> Result<u16, FileBuildError> {
+        let mut file_ids = parent.children.iter().copied().filter(|id| Self::is_file(*id));
+        let Some(first_file_id) = file_ids.next() else {
+            // Directories without files get the file ID that would have been assigned next
+            return Ok(parent.original_first_file_id.unwrap_or(next_file_id));
+        };
+
+        // The FNT only stores the first file ID, the rest are implied to be sequential
+        for (expected, actual) in (first_file_id + 1..).zip(file_ids) {
+            if expected != actual {
+                return NonContiguousFileIdsSnafu { name: parent.name.clone(), id: parent.id, expected, actual }.fail();
+            }
+        }
+        Ok(first_file_id)
+    }
+
+    fn build_subtable(&self, parent: &Dir, next_file_id: &mut u16) -> Result<FntSubtable<'_>, FileBuildError> {
+        let first_file_id = self.first_file_id(parent, *next_file_id)?;
+        let num_files = parent.children.iter().filter(|id| Self::is_file(**id)).count() as u16;
+        if num_files > 0 {
+            *next_file_id = first_file_id + num_files;
+        }
+
         let mut data = vec![];
 
         for child in &parent.children {
@@ -264,21 +1094,22 @@ impl<'a> FileSystem<'a> {
         }
 
         Ok(FntSubtable {
-            directory: Cow::Owned(FntDirectory {
-                subtable_offset: 0,
-                first_file_id: self.find_first_file_id(parent),
-                parent_id: parent.parent_id,
-            }),
+            directory: Cow::Owned(FntDirectory { subtable_offset: 0, first_file_id, parent_id: parent.parent_id }),
             data: Cow::Owned(data),
         })
     }
 
-    fn build_fnt_recursive(&'a self, subtables: &mut Vec<FntSubtable<'a>>, parent_id: u16) -> Result<(), FileBuildError> {
+    fn build_fnt_recursive(
+        &'a self,
+        subtables: &mut Vec<FntSubtable<'a>>,
+        parent_id: u16,
+        next_file_id: &mut u16,
+    ) -> Result<(), FileBuildError> {
         let parent = &self.dir(parent_id);
-        subtables.push(self.build_subtable(parent)?);
+        subtables.push(self.build_subtable(parent, next_file_id)?);
         for child in &parent.children {
             if Self::is_dir(*child) {
-                self.build_fnt_recursive(subtables, *child)?;
+                self.build_fnt_recursive(subtables, *child, next_file_id)?;
             }
         }
         Ok(())
@@ -288,39 +1119,58 @@ impl<'a> FileSystem<'a> {
     ///
     /// # Errors
     ///
-    /// This function will return an error if a file/directory name contains non-ASCII characters.
+    /// This function will return an error if a file/directory name contains non-ASCII characters, or if the file IDs in a
+    /// directory are not sequential.
     pub fn build_fnt(&self) -> Result<Fnt, FileBuildError> {
         let mut subtables = vec![];
-        self.build_fnt_recursive(&mut subtables, ROOT_DIR_ID)?;
+        let mut next_file_id = self.num_reserved as u16;
+        self.build_fnt_recursive(&mut subtables, ROOT_DIR_ID, &mut next_file_id)?;
         Ok(Fnt { subtables: subtables.into_boxed_slice() })
     }
 
-    fn compare_for_fnt(a: &str, a_dir: bool, b: &str, b_dir: bool) -> Ordering {
-        let files_first = a_dir.cmp(&b_dir);
-        if files_first.is_ne() {
-            return files_first;
-        }
+    fn compare_ids_for_fnt(&self, a: u16, b: u16) -> Ordering {
+        fnt_name_cmp(self.name(a), self.name(b), Self::is_dir(a), Self::is_dir(b))
+    }
 
-        // Convert to Shift-JIS first, *then* convert to lowercase byte-by-byte
-        // without accounting for multibyte characters like コ (83 52, but sorted
-        // as if it was actually ビ / 83 72). This strcasecmp-like behavior was
-        // observed in 999's Japanese file names.
-        let (mut a_bytes, _, _) = SHIFT_JIS.encode(&a);
-        let (mut b_bytes, _, _) = SHIFT_JIS.encode(&b);
-        let a_vec = a_bytes.to_mut();
-        let b_vec = b_bytes.to_mut();
-        a_vec.make_ascii_lowercase();
-        b_vec.make_ascii_lowercase();
+    /// Returns `children` ordered by the names in `fnt_order`. Children which are not in `fnt_order` are inserted in their
+    /// sorted position among the others.
+    fn order_for_fnt(&self, children: &[u16], fnt_order: &[String]) -> Vec<u16> {
+        let ranks = fnt_order.iter().enumerate().map(|(rank, name)| (name.as_str(), rank)).collect::<HashMap<_, _>>();
+        let (mut listed, mut unlisted): (Vec<_>, Vec<_>) =
+            children.iter().copied().partition(|child| ranks.contains_key(self.name(*child)));
+        listed.sort_by_key(|child| ranks[self.name(*child)]);
+        unlisted.sort_by(|a, b| self.compare_ids_for_fnt(*a, *b));
+
+        let mut ordered = Vec::with_capacity(children.len());
+        let (mut listed, mut unlisted) = (listed.into_iter().peekable(), unlisted.into_iter().peekable());
+        while let (Some(&a), Some(&b)) = (listed.peek(), unlisted.peek()) {
+            if self.compare_ids_for_fnt(b, a).is_lt() {
+                ordered.push(b);
+                unlisted.next();
+            } else {
+                ordered.push(a);
+                listed.next();
+            }
+        }
+        ordered.extend(listed);
+        ordered.extend(unlisted);
+        ordered
+    }
 
-        // Lexicographic, case-insensitive Shift-JIS order
-        a_vec.cmp(&b_vec)
+    fn sorted_for_fnt(&self, parent: &Dir) -> Vec<u16> {
+        match &parent.fnt_order {
+            Some(fnt_order) => self.order_for_fnt(&parent.children, fnt_order),
+            None => {
+                let mut children = parent.children.clone();
+                children.sort_by(|a, b| self.compare_ids_for_fnt(*a, *b));
+                children
+            }
+        }
     }
 
     fn sort_for_fnt_in(&mut self, parent_id: u16) {
         let mut parent = self.dir(parent_id).clone();
-        parent
-            .children
-            .sort_by(|a, b| Self::compare_for_fnt(self.name(*a), Self::is_dir(*a), self.name(*b), Self::is_dir(*b)));
+        parent.children = self.sorted_for_fnt(&parent);
 
         for child in &mut parent.children {
             if Self::is_dir(*child) {
@@ -331,19 +1181,140 @@ impl<'a> FileSystem<'a> {
         *self.dir_mut(parent_id) = parent;
     }
 
-    /// Sorts the entire [`FileSystem`] so that it's laid out in the right order for the FNT.
+    /// Sorts the entire [`FileSystem`] so that it's laid out in the right order for the FNT. Directories which were parsed
+    /// from a ROM or loaded with an FNT order keep that order.
     pub fn sort_for_fnt(&mut self) {
         self.sort_for_fnt_in(ROOT_DIR_ID);
     }
 
-    fn compare_for_rom(a: &str, b: &str) -> Ordering {
-        // Lexicographic UTF-8 order
-        a.cmp(b)
+    fn fnt_order_in(&self, parent_id: u16, parent_path: &str, paths: &mut Vec<String>) -> bool {
+        let parent = self.dir(parent_id);
+        let children = self.sorted_for_fnt(parent);
+        let mut default_children = parent.children.clone();
+        default_children.sort_by(|a, b| self.compare_ids_for_fnt(*a, *b));
+
+        let mut is_custom = children != default_children;
+        for child in children {
+            let path = format!("{parent_path}/{}", self.name(child));
+            paths.push(path.clone());
+            if Self::is_dir(child) {
+                is_custom |= self.fnt_order_in(child, &path, paths);
+            }
+        }
+        is_custom
+    }
+
+    /// Returns the paths of all files and directories in FNT order, or `None` if every directory follows the default order
+    /// of [`Self::sort_for_fnt`]. The result can be passed to [`Self::load_with_fnt_order`].
+    pub fn fnt_order(&self) -> Option<Vec<String>> {
+        let mut paths = vec![];
+        self.fnt_order_in(ROOT_DIR_ID, "", &mut paths).then_some(paths)
+    }
+
+    fn empty_dir_file_ids_in(
+        &self,
+        parent_id: u16,
+        parent_path: &str,
+        next_file_id: &mut u16,
+        file_ids: &mut Vec<EmptyDirFileId>,
+    ) {
+        let parent = self.dir(parent_id);
+        let children = self.sorted_for_fnt(parent);
+        let num_files = children.iter().filter(|id| Self::is_file(**id)).count() as u16;
+        match children.iter().copied().find(|id| Self::is_file(*id)) {
+            Some(first_file_id) => *next_file_id = first_file_id + num_files,
+            None => {
+                if let Some(first_file_id) = parent.original_first_file_id.filter(|id| id != next_file_id) {
+                    let path = if parent_path.is_empty() { "/".to_string() } else { parent_path.to_string() };
+                    file_ids.push(EmptyDirFileId { path, first_file_id });
+                }
+            }
+        }
+        for child in children.into_iter().filter(|id| Self::is_dir(*id)) {
+            self.empty_dir_file_ids_in(child, &format!("{parent_path}/{}", self.name(child)), next_file_id, file_ids);
+        }
+    }
+
+    /// Returns the first file ID of each directory without files, if it differs from the ID of the next file which
+    /// [`Self::build_fnt`] gives such directories by default. The result can be passed to
+    /// [`Self::set_empty_dir_file_ids`].
+    pub fn empty_dir_file_ids(&self) -> Vec<EmptyDirFileId> {
+        let mut file_ids = vec![];
+        self.empty_dir_file_ids_in(ROOT_DIR_ID, "", &mut (self.num_reserved as u16), &mut file_ids);
+        file_ids
+    }
+
+    /// Sets the first file IDs of directories without files, as returned by [`Self::empty_dir_file_ids`]. Directories which
+    /// don't exist are skipped with a warning.
+    pub fn set_empty_dir_file_ids(&mut self, file_ids: &[EmptyDirFileId]) {
+        for EmptyDirFileId { path, first_file_id } in file_ids {
+            let id = match path.trim_start_matches('/') {
+                "" => Some(ROOT_DIR_ID),
+                path => self.find_path(path).filter(|&id| Self::is_dir(id)),
+            };
+            let Some(id) = id else {
+                log::warn!(
+                    "Directory '{path}' has a first file ID but doesn't exist, it will be numbered like a new directory"
+                );
+                continue;
+            };
+            self.dir_mut(id).original_first_file_id = Some(*first_file_id);
+        }
+    }
+
+    fn files_in_fnt_order<'f>(&'f self, parent_id: u16, parent_path: &str, files: &mut Vec<(String, &'f File<'f>)>) {
+        for child in self.sorted_for_fnt(self.dir(parent_id)) {
+            let path = format!("{parent_path}{}", self.name(child));
+            if Self::is_dir(child) {
+                self.files_in_fnt_order(child, &format!("{path}/"), files);
+            } else {
+                files.push((path, self.file(child)));
+            }
+        }
+    }
+
+    /// Returns an iterator over every file and its path relative to the root directory, in FNT order. The paths can be
+    /// passed to [`Self::find_path`].
+    pub fn iter_files(&self) -> impl Iterator<Item = (String, &File<'_>)> {
+        let mut files = Vec::with_capacity(self.files.len());
+        self.files_in_fnt_order(ROOT_DIR_ID, "", &mut files);
+        files.into_iter()
+    }
+
+    /// Forgets the original FNT order, so that [`Self::sort_for_fnt`] sorts every directory in the default order.
+    pub fn clear_fnt_order(&mut self) {
+        for dir in &mut self.dirs {
+            dir.fnt_order = None;
+        }
+    }
+
+    fn analyze_ordering_in(&self, parent_id: u16, parent_path: &str, unsorted_dirs: &mut Vec<UnsortedDir>) {
+        let children = &self.dir(parent_id).children;
+        if let Some(pair) = children.windows(2).find(|pair| self.compare_ids_for_fnt(pair[0], pair[1]).is_gt()) {
+            unsorted_dirs.push(UnsortedDir {
+                path: if parent_path.is_empty() { "/".to_string() } else { parent_path.to_string() },
+                first: self.name(pair[0]).to_string(),
+                second: self.name(pair[1]).to_string(),
+            });
+        }
+        for &child in children.iter().filter(|child| Self::is_dir(**child)) {
+            self.analyze_ordering_in(child, &format!("{parent_path}/{}", self.name(child)), unsorted_dirs);
+        }
+    }
+
+    /// Finds directories whose children are not in [`fnt_name_cmp`] order in the FNT, and whether the file contents are
+    /// stored in a different order than their file IDs. Such file systems need [`Self::fnt_order`] and
+    /// [`Self::compute_path_order`] to be rebuilt identically.
+    pub fn analyze_ordering(&self) -> OrderingReport {
+        let mut unsorted_dirs = vec![];
+        self.analyze_ordering_in(ROOT_DIR_ID, "", &mut unsorted_dirs);
+        let file_image_differs = self.files.windows(2).any(|pair| pair[0].original_offset > pair[1].original_offset);
+        OrderingReport { unsorted_dirs, file_image_differs }
     }
 
     fn sort_for_rom_in(&mut self, parent_id: u16) {
         let mut parent = self.dir(parent_id).clone();
-        parent.children.sort_by(|a, b| Self::compare_for_rom(self.name(*a), self.name(*b)));
+        parent.children.sort_by(|a, b| rom_name_cmp(self.name(*a), self.name(*b)));
 
         for child in &mut parent.children {
             if Self::is_dir(*child) {
@@ -359,37 +1330,91 @@ impl<'a> FileSystem<'a> {
         self.sort_for_rom_in(ROOT_DIR_ID);
     }
 
-    fn find_path_in(&self, path: &str, parent_id: u16) -> Option<u16> {
-        let parent = &self.dir(parent_id);
-        let (child_name, next) = path.split_once('/').map(|(c, n)| (c, Some(n))).unwrap_or((path, None));
-        let child = parent.children.iter().find(|id| self.name(**id) == child_name)?;
-        if let Some(next) = next {
-            if Self::is_dir(*child) {
-                self.find_path_in(next, *child)
-            } else {
-                None
-            }
-        } else {
-            Some(*child)
+    /// Returns the ID of the file or directory at the given path, relative to the root directory. Returns [`None`] if the
+    /// path doesn't exist or is ambiguous, see [`Self::resolve_path`].
+    pub fn find_path(&self, path: &str) -> Option<u16> {
+        self.resolve_path(path).ok()
+    }
+
+    /// Opens a ROM embedded as a file in this file system, such as a DS Download Play child. The header of the embedded ROM
+    /// is validated before returning: its gamecode must be printable ASCII and its header CRC must be correct.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path is not a file, or the file doesn't contain a valid ROM header.
+    pub fn open_embedded_rom(&self, path: &str) -> Result<raw::Rom<'_>, RomEmbeddedError> {
+        let stripped_path = path.strip_prefix("/").unwrap_or(path);
+        let id = match self.resolve_path(stripped_path) {
+            Ok(id) => id,
+            Err(FilePathError::PathNotFound { .. }) => return NotFoundSnafu { path }.fail(),
+            Err(e) => return Err(e.into()),
+        };
+        if Self::is_dir(id) {
+            return IsDirectorySnafu { path }.fail();
         }
+
+        let rom = raw::Rom::new(self.file(id).read_contents()?);
+        let header = rom.header()?;
+        if !header.gamecode.0.iter().all(|ch| ch.is_ascii_graphic()) {
+            return InvalidGamecodeSnafu { path, gamecode: header.gamecode.0 }.fail();
+        }
+        let crc = header.compute_header_crc();
+        if crc != header.header_crc {
+            return HeaderCrcSnafu { path, expected: crc, actual: header.header_crc }.fail();
+        }
+
+        Ok(rom)
     }
 
-    fn find_path(&self, path: &str) -> Option<u16> {
-        self.find_path_in(path, ROOT_DIR_ID)
+    /// Returns the ID of the file or directory at the given path, relative to the root directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path doesn't exist, or if a directory along the path has more than one
+    /// child with the same name. The latter can happen in ROMs with a malformed FNT, in which case such files can only be
+    /// accessed by ID.
+    pub fn resolve_path(&self, path: &str) -> Result<u16, FilePathError> {
+        let mut parent_id = ROOT_DIR_ID;
+        let mut resolved_len = 0;
+        let mut components = path.split('/').peekable();
+        while let Some(child_name) = components.next() {
+            resolved_len += child_name.len();
+            let resolved_path = &path[..resolved_len];
+            resolved_len += 1;
+
+            let children = &self.dir(parent_id).children;
+            let mut matches = children.iter().copied().filter(|id| self.name(*id) == child_name);
+            let Some(child) = matches.next() else {
+                return PathNotFoundSnafu { path: resolved_path }.fail();
+            };
+            if matches.next().is_some() {
+                let ids: Vec<u16> = children.iter().copied().filter(|id| self.name(*id) == child_name).collect();
+                return AmbiguousPathSnafu { path: resolved_path, ids }.fail();
+            }
+
+            if components.peek().is_none() {
+                return Ok(child);
+            }
+            if !Self::is_dir(child) {
+                return PathNotFoundSnafu { path }.fail();
+            }
+            parent_id = child;
+        }
+        PathNotFoundSnafu { path }.fail()
     }
 
     fn make_child_dir(&mut self, name: String, parent_id: u16) -> &Dir {
         let id = self.next_dir_id;
-        self.dirs.push(Dir { id, name, parent_id, children: vec![] });
+        self.dirs.push(Dir { id, name, parent_id, children: vec![], original_first_file_id: None, fnt_order: None });
         let parent = self.dir_mut(parent_id);
         parent.children.push(id);
         self.next_dir_id += 1;
         self.dirs.last().unwrap()
     }
 
-    fn make_child_file(&mut self, name: String, parent_id: u16, contents: Vec<u8>) -> &File {
+    fn make_child_file(&mut self, name: String, parent_id: u16, contents: FileContents<'a>) -> &File<'_> {
         let id = self.next_file_id;
-        self.files.push(File { id, name, original_offset: 0, contents: contents.into() });
+        self.files.push(File { id, name, original_offset: 0, contents });
         let parent = self.dir_mut(parent_id);
         parent.children.push(id);
         self.next_file_id += 1;
@@ -436,7 +1461,15 @@ impl<'a> FileSystem<'a> {
             let subdir = if path.trim() == "" {
                 self.dir(ROOT_DIR_ID)
             } else {
-                let Some(child) = self.find_path(path) else { continue };
+                let child = match self.resolve_path(path) {
+                    Ok(child) => child,
+                    // A path listed once per duplicate name refers to each of them in turn
+                    Err(FilePathError::AmbiguousPath { path: ambiguous_path, ids, .. }) if ambiguous_path == path => {
+                        let Some(child) = ids.into_iter().find(|id| !visited.contains(id)) else { continue };
+                        child
+                    }
+                    Err(_) => continue,
+                };
                 if visited.contains(&child) {
                     continue;
                 }
@@ -467,6 +1500,75 @@ impl<'a> FileSystem<'a> {
         max_id
     }
 
+    fn empty_dirs_in(&self, path: &Path, parent: &Dir, empty_dirs: &mut Vec<PathBuf>) {
+        if parent.children.is_empty() {
+            empty_dirs.push(path.to_path_buf());
+        }
+        for child in &parent.children {
+            if Self::is_dir(*child) {
+                self.empty_dirs_in(&path.join(self.name(*child)), self.dir(*child), empty_dirs);
+            }
+        }
+    }
+
+    /// Returns the paths of all directories without children, relative to the root directory. These are not visited by
+    /// [`Self::traverse_files`].
+    pub fn empty_dirs(&self) -> Vec<PathBuf> {
+        let mut empty_dirs = vec![];
+        self.empty_dirs_in(Path::new(""), self.dir(ROOT_DIR_ID), &mut empty_dirs);
+        empty_dirs
+    }
+
+    /// Returns the size of the FNT which [`Self::build_fnt`] would produce, without building it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a file/directory name can't be encoded as Shift-JIS.
+    pub fn fnt_size(&self) -> Result<usize, FileBuildError> {
+        self.fnt_size_in(self.dir(ROOT_DIR_ID))
+    }
+
+    fn fnt_size_in(&self, parent: &Dir) -> Result<usize, FileBuildError> {
+        // Directory entry, and the 0-byte terminating the subtable
+        let mut size = size_of::<FntDirectory>() + 1;
+        for &child in &parent.children {
+            let name = self.name(child);
+            let (sjis_name, _, had_errors) = SHIFT_JIS.encode(name);
+            if had_errors {
+                return EncodingFailedSnafu { name }.fail();
+            }
+            // Length byte and name, followed by the directory ID for subdirectories
+            size += 1 + sjis_name.len().min(0x7f);
+            if Self::is_dir(child) {
+                size += size_of::<u16>() + self.fnt_size_in(self.dir(child))?;
+            }
+        }
+        Ok(size)
+    }
+
+    /// Returns the number of FAT entries needed by the files, including the reserved entries before the first file.
+    /// Overlays with file IDs past the last file need more entries, see [`super::Rom::plan_layout`].
+    pub fn num_fat_entries(&self) -> usize {
+        (self.max_file_id() as usize + 1).max(self.num_reserved)
+    }
+
+    /// Returns the size of the FAT needed by the files, see [`Self::num_fat_entries`].
+    pub fn fat_size(&self) -> usize {
+        self.num_fat_entries() * size_of::<FileAlloc>()
+    }
+
+    /// Returns an estimate of how many bytes the files take up in the ROM when each file is aligned to `alignment` bytes.
+    /// Files which refer to other sections of the ROM are counted as well, so this is an upper bound.
+    pub fn estimated_file_image_size(&self, alignment: usize) -> usize {
+        let alignment = alignment.max(1);
+        self.files.iter().map(|file| file.size().div_ceil(alignment) * alignment).sum()
+    }
+
+    /// Returns whether this [`FileSystem`] has no files or directories.
+    pub fn is_empty(&self) -> bool {
+        self.dir(ROOT_DIR_ID).children.is_empty()
+    }
+
     /// Returns the max file ID of this [`FileSystem`].
     pub fn max_file_id(&self) -> u16 {
         self.max_file_id_in(ROOT_DIR_ID)
@@ -488,13 +1590,14 @@ impl<'a> FileSystem<'a> {
                     parent_id: parent.id,
                     path_name: path,
                     offset: self.file(*child).original_offset,
+                    empty: self.file(*child).size() == 0,
                 });
             }
         }
     }
 
     fn are_paths_sorted(&self, paths: &[PathOrder]) -> bool {
-        paths.windows(2).all(|w| Self::compare_for_rom(self.name(w[0].id), self.name(w[1].id)).is_lt())
+        paths.windows(2).all(|w| rom_name_cmp(self.name(w[0].id), self.name(w[1].id)).is_lt())
     }
 
     /// Computes the path order that the [`FileSystem`] is currently in. This can be saved and reused in
@@ -543,7 +1646,13 @@ impl<'a> FileSystem<'a> {
                 // Replace the children with their parent
                 let offset = paths[children_start].offset;
                 paths.drain(children_start..children_end);
-                paths.insert(children_start, PathOrder { id: parent_id, parent_id: parent.parent_id, path_name, offset });
+                paths.insert(children_start, PathOrder {
+                    id: parent_id,
+                    parent_id: parent.parent_id,
+                    path_name,
+                    offset,
+                    empty: false,
+                });
             } else {
                 children_start = children_end;
             }
@@ -551,9 +1660,84 @@ impl<'a> FileSystem<'a> {
 
         paths.into_iter().map(|p| p.path_name).collect()
     }
+
+    /// Computes the path of every file in the order they were placed in the ROM, without simplifying it to directories like
+    /// [`Self::compute_path_order`]. This can be passed to [`Self::resolve_file_order`] for games where the simplified path
+    /// order doesn't reproduce the original order.
+    pub fn compute_file_order(&self) -> Vec<String> {
+        let mut path_order = BinaryHeap::new();
+        self.traverse_and_compute_path_order("", &mut path_order, self.dir(ROOT_DIR_ID));
+        path_order.into_sorted_vec().into_iter().map(|p| p.path_name).collect()
+    }
+
+    /// Resolves an exhaustive list of file paths, one per line, into file IDs in the same order. Empty lines are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a path is not a file in this [`FileSystem`], a file is listed more than once, or
+    /// a file is not listed at all.
+    pub fn resolve_file_order<S: AsRef<str>>(&self, file_order: &[S]) -> Result<Vec<u16>, FileOrderError> {
+        let mut lines = HashMap::<u16, usize>::new();
+        let mut ids = Vec::with_capacity(self.files.len());
+        for (index, path) in file_order.iter().enumerate() {
+            let path = path.as_ref().trim();
+            let line = index + 1;
+            if path.is_empty() {
+                continue;
+            }
+            let Some(id) = self.find_path(path.strip_prefix('/').unwrap_or(path)) else {
+                return FileNotFoundSnafu { line, path }.fail();
+            };
+            if Self::is_dir(id) {
+                return NotAFileSnafu { line, path }.fail();
+            }
+            if let Some(&first_line) = lines.get(&id) {
+                return DuplicateFileSnafu { line, first_line, path }.fail();
+            }
+            lines.insert(id, line);
+            ids.push(id);
+        }
+
+        let mut missing = None;
+        self.traverse_files(["/"], |file, path| {
+            if missing.is_none() && !lines.contains_key(&file.id()) {
+                missing = Some(format!("/{}", path.join(file.name()).to_string_lossy().replace('\\', "/")));
+            }
+        });
+        if let Some(path) = missing {
+            return MissingFileSnafu { path }.fail();
+        }
+        Ok(ids)
+    }
 }
 
 impl<'a> File<'a> {
+    /// Returns a copy of this [`File`] which owns its contents.
+    pub fn to_owned(&self) -> File<'static> {
+        File {
+            id: self.id,
+            name: self.name.clone(),
+            original_offset: self.original_offset,
+            contents: match &self.contents {
+                FileContents::Loaded(contents) => FileContents::Loaded(Cow::Owned(contents.to_vec())),
+                FileContents::OnDisk { path, size } => FileContents::OnDisk { path: path.clone(), size: *size },
+            },
+        }
+    }
+
+    /// Converts this [`File`] into one which owns its contents. Contents which are already owned are moved instead of copied.
+    pub fn into_owned(self) -> File<'static> {
+        File {
+            id: self.id,
+            name: self.name,
+            original_offset: self.original_offset,
+            contents: match self.contents {
+                FileContents::Loaded(contents) => FileContents::Loaded(Cow::Owned(contents.into_owned())),
+                FileContents::OnDisk { path, size } => FileContents::OnDisk { path, size },
+            },
+        }
+    }
+
     /// Returns a reference to the name of this [`File`].
     pub fn name(&self) -> &str {
         &self.name
@@ -565,8 +1749,59 @@ impl<'a> File<'a> {
     }
 
     /// Returns a reference to the contents of this [`File`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file was loaded by [`FileSystem::load_lazy`] and its contents haven't been loaded with
+    /// [`FileSystem::load_contents`]. Use [`Self::read_contents`] to read them either way.
     pub fn contents(&self) -> &[u8] {
-        &self.contents
+        match &self.contents {
+            FileContents::Loaded(contents) => contents,
+            FileContents::OnDisk { path, .. } => {
+                panic!("contents of '{}' are not loaded, see File::read_contents", path.display())
+            }
+        }
+    }
+
+    /// Returns the contents of this [`File`], reading them from disk if it was loaded by [`FileSystem::load_lazy`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails, or if the file has changed size since it was loaded.
+    pub fn read_contents(&self) -> Result<Cow<'_, [u8]>, FileError> {
+        match &self.contents {
+            FileContents::Loaded(contents) => Ok(Cow::Borrowed(contents)),
+            FileContents::OnDisk { path, size } => {
+                let contents = read_file(path)?;
+                if contents.len() != *size {
+                    return FileSizeChangedSnafu { path: path.to_string_lossy(), expected: *size, actual: contents.len() }
+                        .fail();
+                }
+                Ok(Cow::Owned(contents))
+            }
+        }
+    }
+
+    /// Returns the size of this [`File`]'s contents, without reading them from disk.
+    pub fn size(&self) -> usize {
+        match &self.contents {
+            FileContents::Loaded(contents) => contents.len(),
+            FileContents::OnDisk { size, .. } => *size,
+        }
+    }
+
+    /// Returns the offset to this [`File`] in the ROM it was parsed from, or 0 if it was not parsed from a ROM.
+    pub fn original_offset(&self) -> u32 {
+        self.original_offset
+    }
+
+    /// Returns where the contents of this [`File`] are stored. Borrowed contents cost no extra memory, but tie the file to
+    /// the lifetime of the buffer they were borrowed from.
+    pub fn source(&self) -> FileSource {
+        match &self.contents {
+            FileContents::Loaded(contents) => FileSource::from(contents),
+            FileContents::OnDisk { .. } => FileSource::OnDisk,
+        }
     }
 }
 
@@ -583,17 +1818,84 @@ struct PathOrder {
     parent_id: u16,
     path_name: String,
     offset: u32,
+    empty: bool,
 }
 
 impl PartialOrd for PathOrder {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.offset.partial_cmp(&other.offset)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for PathOrder {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.offset.cmp(&other.offset)
+        // An empty file has the same offset as the file after it, so it must be placed first to keep that offset
+        self.offset.cmp(&other.offset).then(other.empty.cmp(&self.empty))
+    }
+}
+
+/// Statistics about a [`FileSystem`], see [`FileSystem::stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FsStats {
+    /// Number of files, see [`FileSystem::file_count`].
+    pub file_count: usize,
+    /// Number of directories including the root, see [`FileSystem::dir_count`].
+    pub dir_count: usize,
+    /// Total size of all file contents, see [`FileSystem::total_content_size`].
+    pub total_content_size: usize,
+    /// Depth of the most deeply nested file or directory, see [`FileSystem::max_depth`].
+    pub max_depth: usize,
+    /// Path of the first file or directory found at [`Self::max_depth`].
+    pub deepest_path: String,
+    /// Longest file or directory name, in characters.
+    pub longest_name: String,
+    /// Number of file and directory names with non-ASCII characters.
+    pub non_ascii_names: usize,
+}
+
+impl Display for FsStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Files ............. : {}", self.file_count)?;
+        writeln!(f, "Directories ....... : {}", self.dir_count)?;
+        writeln!(f, "Total size ........ : {} ({:#x})", BlobSize(self.total_content_size), self.total_content_size)?;
+        writeln!(f, "Max depth ......... : {} ({})", self.max_depth, self.deepest_path)?;
+        writeln!(f, "Longest name ...... : {} ({} characters)", self.longest_name, self.longest_name.chars().count())?;
+        write!(f, "Non-ASCII names ... : {}", self.non_ascii_names)
+    }
+}
+
+/// Files with identical contents, see [`FileSystem::duplicate_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// SHA-1 hash of the contents.
+    pub sha1: String,
+    /// Size of each file.
+    pub size: usize,
+    /// Paths of the files, ordered by file ID.
+    pub paths: Vec<String>,
+}
+
+impl DuplicateGroup {
+    /// Returns how many bytes would be saved by storing the contents only once.
+    pub fn wasted_bytes(&self) -> usize {
+        self.size * (self.paths.len() - 1)
+    }
+}
+
+impl Display for DuplicateGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} copies of {} ({}), {} wasted:",
+            self.paths.len(),
+            BlobSize(self.size),
+            self.sha1,
+            BlobSize(self.wasted_bytes())
+        )?;
+        for path in &self.paths {
+            write!(f, "\n  {path}")?;
+        }
+        Ok(())
     }
 }
 
@@ -616,7 +1918,7 @@ impl<'a> Display for DisplayFileSystem<'a> {
                 write!(f, "{}", Self { files, parent_id: *child, indent: self.indent + 2 })?;
             } else {
                 let file = files.file(*child);
-                let size = BlobSize(file.contents.len()).to_string();
+                let size = BlobSize(file.size()).to_string();
                 write!(f, "{i}0x{:04x}: {: <48}{size: >7}", file.id, file.name)?;
                 writeln!(f)?;
             }
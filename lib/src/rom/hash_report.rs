@@ -0,0 +1,180 @@
+use std::{mem::size_of, path::Path};
+
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+use super::raw::{self, RawBannerError, RawHeaderError, TableOffset};
+use crate::{
+    crc::CRC_32_ISO_HDLC,
+    crypto::sha1::Sha1,
+    io::{create_file, open_file, FileError},
+    str::hex_string,
+};
+
+/// Hashes of a ROM and its sections, used to verify a built ROM against the original ROM without keeping the original
+/// around. Saved as [`Self::FILE_NAME`] next to the config when extracting a ROM.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RomHashReport {
+    /// Size of the ROM file.
+    pub size: u32,
+    /// CRC-32 of the whole ROM.
+    pub crc32: String,
+    /// SHA-1 of the whole ROM.
+    pub sha1: String,
+    /// Header CRC, as stored in the header.
+    pub header_crc: u16,
+    /// Logo CRC, as stored in the header.
+    pub logo_crc: u16,
+    /// Secure area CRC, as stored in the header.
+    pub secure_area_crc: u16,
+    /// Hashes of each section in the ROM.
+    pub sections: Vec<RomSectionHash>,
+}
+
+/// Hash of a section in a ROM, see [`RomHashReport`].
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RomSectionHash {
+    /// Name of the section.
+    pub name: String,
+    /// ROM offset to the start of the section.
+    pub offset: u32,
+    /// Size of the section.
+    pub size: u32,
+    /// SHA-1 of the section.
+    pub sha1: String,
+}
+
+/// A value compared by [`RomHashReport::compare`].
+pub struct RomHashComparison {
+    /// Name of the compared value.
+    pub field: String,
+    /// Value in the original ROM.
+    pub expected: String,
+    /// Value in the compared ROM, or `None` if the compared ROM doesn't have the section.
+    pub actual: Option<String>,
+}
+
+impl RomHashComparison {
+    /// Returns whether the compared value matches the original value.
+    pub fn matches(&self) -> bool {
+        self.actual.as_ref().is_some_and(|actual| *actual == self.expected)
+    }
+}
+
+/// Errors related to [`RomHashReport`].
+#[derive(Debug, Snafu)]
+pub enum RomHashReportError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`RawBannerError`].
+    #[snafu(transparent)]
+    RawBanner {
+        /// Source error.
+        source: RawBannerError,
+    },
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`serde_yml::Error`].
+    #[snafu(transparent)]
+    SerdeYml {
+        /// Source error.
+        source: serde_yml::Error,
+    },
+}
+
+impl RomHashReport {
+    /// File name of the hash report of the original ROM, placed next to the config.
+    pub const FILE_NAME: &'static str = "original.meta.yaml";
+
+    /// Computes the hashes of a raw ROM.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header or banner is invalid.
+    pub fn compute(rom: &raw::Rom) -> Result<Self, RomHashReportError> {
+        let header = rom.header()?;
+        let data = rom.data();
+
+        let sections = report_sections(rom)?
+            .into_iter()
+            .filter_map(|(name, offset, size)| {
+                let section = data.get(offset as usize..(offset + size) as usize)?;
+                Some(RomSectionHash { name, offset, size, sha1: hex_string(&Sha1::digest(section)) })
+            })
+            .collect();
+
+        Ok(Self {
+            size: data.len() as u32,
+            crc32: format!("{:08x}", CRC_32_ISO_HDLC.checksum(data)),
+            sha1: hex_string(&Sha1::digest(data)),
+            header_crc: header.header_crc,
+            logo_crc: header.logo_crc,
+            secure_area_crc: header.secure_area_crc,
+            sections,
+        })
+    }
+
+    /// Compares this report of the original ROM to the report of another ROM. Sections are matched by name.
+    pub fn compare(&self, other: &RomHashReport) -> Vec<RomHashComparison> {
+        let mut comparisons = vec![];
+        let mut compare = |field: &str, expected: String, actual: Option<String>| {
+            comparisons.push(RomHashComparison { field: field.to_string(), expected, actual })
+        };
+        compare("Size", format!("{:#x}", self.size), Some(format!("{:#x}", other.size)));
+        compare("CRC-32", self.crc32.clone(), Some(other.crc32.clone()));
+        compare("SHA-1", self.sha1.clone(), Some(other.sha1.clone()));
+        compare("Header CRC", format!("{:#06x}", self.header_crc), Some(format!("{:#06x}", other.header_crc)));
+        compare("Logo CRC", format!("{:#06x}", self.logo_crc), Some(format!("{:#06x}", other.logo_crc)));
+        compare("Secure area CRC", format!("{:#06x}", self.secure_area_crc), Some(format!("{:#06x}", other.secure_area_crc)));
+        for section in &self.sections {
+            let actual = other.sections.iter().find(|s| s.name == section.name).map(|s| s.sha1.clone());
+            compare(&section.name, section.sha1.clone(), actual);
+        }
+        comparisons
+    }
+
+    /// Loads a hash report from a YAML file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be opened or has an invalid format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RomHashReportError> {
+        Ok(serde_yml::from_reader(open_file(path)?)?)
+    }
+
+    /// Saves this hash report to a YAML file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be created or serialization fails.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RomHashReportError> {
+        serde_yml::to_writer(create_file(path)?, self)?;
+        Ok(())
+    }
+}
+
+/// Returns the name, ROM offset and size of each nonempty section hashed by [`RomHashReport`].
+pub(crate) fn report_sections(rom: &raw::Rom) -> Result<Vec<(String, u32, u32)>, RawBannerError> {
+    let header = rom.header()?;
+    let banner = rom.banner()?;
+    let table = |name: &str, table: TableOffset| (name.to_string(), table.offset, table.size);
+    let sections = [
+        ("Header".to_string(), 0, size_of::<raw::Header>() as u32),
+        ("ARM9 program".to_string(), header.arm9.offset, header.arm9.size),
+        table("ARM9 overlay table", header.arm9_overlays),
+        ("ARM7 program".to_string(), header.arm7.offset, header.arm7.size),
+        table("ARM7 overlay table", header.arm7_overlays),
+        table("FNT", header.file_names),
+        table("FAT", header.file_allocs),
+        ("Banner".to_string(), header.banner_offset, banner.map_or(0, |banner| banner.version().banner_size() as u32)),
+    ];
+    Ok(sections.into_iter().filter(|(_, _, size)| *size > 0).collect())
+}
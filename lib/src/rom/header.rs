@@ -1,21 +1,30 @@
-use std::mem::{offset_of, size_of};
+use std::{fmt::Display, mem::size_of};
 
-use serde::{Deserialize, Serialize};
-use snafu::Snafu;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
 
 use super::{
     raw::{
         self, AccessControl, Capacity, Delay, DsFlags, DsiFlags, DsiFlags2, HeaderVersion, ProgramOffset, RegionFlags,
         TableOffset,
     },
-    BuildContext, Rom,
+    BuildContext, DsiProgram, Rom,
 };
 use crate::{
     crc::CRC_16_MODBUS,
-    str::{AsciiArray, AsciiArrayError},
+    str::{hex_string, AsciiArray, AsciiArrayError, BlobSize},
 };
+const UNITCODE_DS: u8 = 0;
+const UNITCODE_DSI_ENHANCED: u8 = 2;
+const UNITCODE_DSI_EXCLUSIVE: u8 = 3;
+const RSA_SHA1_SIZE: usize = 0x80;
+const DS_FLAGS_RESERVED: u8 = 0x3c;
+const DSI_FLAGS_2_RESERVED: u32 = 0xffffff00;
+/// The DSi region starts at a multiple of this, see [`raw::Header::ds_rom_region_end`].
+pub(crate) const DSI_REGION_ALIGNMENT: u32 = 0x80000;
+
 /// ROM header.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Header {
     /// Values for the original header version, [`HeaderVersion::Original`].
     #[serde(flatten)]
@@ -23,16 +32,21 @@ pub struct Header {
     /// Values for DS games after DSi release, [`HeaderVersion::DsPostDsi`].
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ds_post_dsi: Option<HeaderDsPostDsi>,
+    /// Values for DSi-enhanced and DSi-exclusive games, only present if the ROM has a DSi area. See [`super::DsiArea`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub dsi: Option<HeaderDsi>,
 }
 
 /// Values for the original header version, [`HeaderVersion::Original`].
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct HeaderOriginal {
     /// Short game title, normally in uppercase letters.
     pub title: String,
     /// 4-character game code in uppercase letters.
+    #[serde(deserialize_with = "deserialize_gamecode")]
     pub gamecode: AsciiArray<4>,
     /// 2-character maker code, normally "01".
+    #[serde(deserialize_with = "deserialize_makercode")]
     pub makercode: AsciiArray<2>,
     /// Unit code, depends on which platform (DS, DSi) this game is for.
     pub unitcode: u8,
@@ -52,10 +66,16 @@ pub struct HeaderOriginal {
     pub rom_nand_end: u16,
     /// NAND end of RW area in multiples of 0x20000 (0x80000 on DSi).
     pub rw_nand_end: u16,
+    /// ROM capacity of the original ROM, used if it's larger than the capacity needed for the built ROM.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub capacity: Option<Capacity>,
+    /// Always uses [`Self::capacity`], even if the built ROM needs a larger capacity.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub preserve_capacity: bool,
 }
 
 /// Values for DS games after DSi release, [`HeaderVersion::DsPostDsi`].
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct HeaderDsPostDsi {
     /// DSi-exclusive flags.
     pub dsi_flags_2: DsiFlags2,
@@ -69,18 +89,121 @@ pub struct HeaderDsPostDsi {
     pub rsa_sha1: Box<[u8]>,
 }
 
+/// Values for DSi-enhanced and DSi-exclusive games. Offsets to the DSi programs and digest hashtables are derived when
+/// building, but the digest areas, modcrypt areas and SHA1-HMACs are copied as-is since they can't be recomputed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HeaderDsi {
+    /// DSi-specific flags.
+    pub dsi_flags: DsiFlags,
+    /// MBK1 to MBK5, the WRAM slot settings.
+    pub memory_banks_wram: [u32; 5],
+    /// MBK6 to MBK8 for ARM9, the WRAM area settings.
+    pub memory_banks_arm9: [u32; 3],
+    /// MBK6 to MBK8 for ARM7, the WRAM area settings.
+    pub memory_banks_arm7: [u32; 3],
+    /// MBK9, the WRAM slot write protection.
+    pub memory_bank_9: u32,
+    /// Regions the game can be played in.
+    pub region_flags: RegionFlags,
+    /// Access control.
+    pub access_control: AccessControl,
+    /// ARM7 SCFG_EXT7 setting.
+    pub arm7_scfg_ext7_setting: u32,
+    /// Range of the DS area which the digest sector hashtable covers.
+    pub digest_ds_area: TableOffset,
+    /// Range of the DSi area which the digest sector hashtable covers.
+    pub digest_dsi_area: TableOffset,
+    /// Size of each sector hashed in the digest sector hashtable.
+    pub digest_sector_size: u32,
+    /// Number of sectors per block in the digest block hashtable.
+    pub digest_sector_count: u32,
+    /// Sizes of the shared2/0000 to shared2/0005 files on the SD card/NAND.
+    pub sd_shared2_sizes: [u8; 6],
+    /// EULA version.
+    pub eula_version: u8,
+    /// Use age ratings.
+    pub use_ratings: bool,
+    /// Age ratings.
+    pub age_ratings: [u8; 0x10],
+    /// File type, the upper half of the title ID.
+    pub file_type: u32,
+    /// Size of the public.sav file on the SD card/NAND.
+    pub sd_public_sav_size: u32,
+    /// Size of the private.sav file on the SD card/NAND.
+    pub sd_private_sav_size: u32,
+    /// Modcrypt area 1, see [`raw::Header::modcrypt_areas`]. The area is copied as-is, so it must still cover the same part
+    /// of the ARM9i or ARM7i program after building.
+    pub modcrypt_area_1: TableOffset,
+    /// Modcrypt area 2, see [`Self::modcrypt_area_1`].
+    pub modcrypt_area_2: TableOffset,
+    /// SHA1-HMAC of ARM9 program including secure area.
+    pub sha1_hmac_arm9_with_secure_area: [u8; 0x14],
+    /// SHA1-HMAC of ARM7 program.
+    pub sha1_hmac_arm7: [u8; 0x14],
+    /// SHA1-HMAC of digest section.
+    pub sha1_hmac_digest: [u8; 0x14],
+    /// SHA1-HMAC of decrypted ARM9i.
+    pub sha1_hmac_arm9i: [u8; 0x14],
+    /// SHA1-HMAC of decrypted ARM7i.
+    pub sha1_hmac_arm7i: [u8; 0x14],
+    /// SHA1-HMAC of ARM9 program excluding secure area.
+    pub sha1_hmac_arm9: [u8; 0x14],
+}
+
 /// Errors related to [`Header::build`].
 #[derive(Snafu, Debug)]
 pub enum HeaderBuildError {
-    /// See [`AsciiArrayError`].
-    #[snafu(transparent)]
+    /// Occurs when a header field could not be converted to an [`AsciiArray`].
+    #[snafu(display("invalid header {field}: {source}"))]
     AsciiArray {
+        /// Name of the field.
+        field: &'static str,
         /// Source error.
         source: AsciiArrayError,
     },
+    /// Occurs when a value in the [`BuildContext`] is missing. [`Rom::build`] fills in every value from the ROM layout, so
+    /// this only happens when building a header with a partial context.
+    #[snafu(display("cannot build header: the {field} is missing in the build context, build the whole ROM to compute it"))]
+    MissingContext {
+        /// Name of the missing value.
+        field: &'static str,
+    },
+}
+
+/// Errors related to [`Header::apply_overrides`].
+#[derive(Snafu, Debug)]
+pub enum HeaderOverrideError {
+    /// Occurs when a field doesn't exist or can't be overridden, see [`Header::OVERRIDABLE_FIELDS`].
+    #[snafu(display("header field '{field}' can't be overridden, expected one of: {}", Header::OVERRIDABLE_FIELDS.join(", ")))]
+    UnknownField {
+        /// Name of the field.
+        field: String,
+    },
+    /// Occurs when a value can't be parsed for its field.
+    #[snafu(display("invalid value '{value}' for header field '{field}': {reason}"))]
+    InvalidValue {
+        /// Name of the field.
+        field: String,
+        /// Value which failed to parse.
+        value: String,
+        /// Why the value is invalid.
+        reason: String,
+    },
 }
 
 impl Header {
+    /// Fields which can be overridden by [`Self::apply_overrides`].
+    pub const OVERRIDABLE_FIELDS: &'static [&'static str] = &[
+        "unitcode",
+        "seed_select",
+        "autostart",
+        "normal_cmd_setting",
+        "key1_cmd_setting",
+        "secure_area_delay",
+        "rom_nand_end",
+        "rw_nand_end",
+    ];
+
     /// Loads from a raw header.
     pub fn load_raw(header: &raw::Header) -> Self {
         let version = header.version();
@@ -98,6 +221,8 @@ impl Header {
                 secure_area_delay: header.secure_area_delay,
                 rom_nand_end: header.rom_nand_end,
                 rw_nand_end: header.rw_nand_end,
+                capacity: Some(header.capacity),
+                preserve_capacity: false,
             },
             ds_post_dsi: (version >= HeaderVersion::DsPostDsi).then_some(HeaderDsPostDsi {
                 dsi_flags_2: header.dsi_flags_2,
@@ -106,31 +231,76 @@ impl Header {
                 sha1_hmac_unk2: header.sha1_hmac_unk2,
                 rsa_sha1: Box::new(header.rsa_sha1),
             }),
+            dsi: header.has_dsi_area().then_some(HeaderDsi {
+                dsi_flags: header.dsi_flags,
+                memory_banks_wram: header.memory_banks_wram,
+                memory_banks_arm9: header.memory_banks_arm9,
+                memory_banks_arm7: header.memory_banks_arm7,
+                memory_bank_9: header.memory_bank_9,
+                region_flags: header.region_flags,
+                access_control: header.access_control,
+                arm7_scfg_ext7_setting: header.arm7_scfg_ext7_setting,
+                digest_ds_area: header.digest_ds_area,
+                digest_dsi_area: header.digest_dsi_area,
+                digest_sector_size: header.digest_sector_size,
+                digest_sector_count: header.digest_sector_count,
+                sd_shared2_sizes: [
+                    header.sd_shared2_0000_size,
+                    header.sd_shared2_0001_size,
+                    header.sd_shared2_0002_size,
+                    header.sd_shared2_0003_size,
+                    header.sd_shared2_0004_size,
+                    header.sd_shared2_0005_size,
+                ],
+                eula_version: header.eula_version,
+                use_ratings: header.use_ratings,
+                age_ratings: header.age_ratings,
+                file_type: header.file_type,
+                sd_public_sav_size: header.sd_public_sav_size,
+                sd_private_sav_size: header.sd_private_sav_size,
+                modcrypt_area_1: header.modcrypt_area_1,
+                modcrypt_area_2: header.modcrypt_area_2,
+                sha1_hmac_arm9_with_secure_area: header.sha1_hmac_arm9_with_secure_area,
+                sha1_hmac_arm7: header.sha1_hmac_arm7,
+                sha1_hmac_digest: header.sha1_hmac_digest,
+                sha1_hmac_arm9i: header.sha1_hmac_arm9i,
+                sha1_hmac_arm7i: header.sha1_hmac_arm7i,
+                sha1_hmac_arm9: header.sha1_hmac_arm9,
+            }),
         }
     }
 
     /// Builds a raw header.
     ///
-    /// # Panics
-    ///
-    /// Panics if a value is missing in the `context`.
-    ///
     /// # Errors
     ///
-    /// This function will return an error if the title contains a non-ASCII character.
+    /// This function will return an error if the title contains a non-ASCII character or is too long, or if a required
+    /// value is missing in the `context`.
     pub fn build(&self, context: &BuildContext, rom: &Rom) -> Result<raw::Header, HeaderBuildError> {
+        let rom_size = context.rom_size.context(MissingContextSnafu { field: "ROM size" })?;
+        // The DSi area is stored on the same chip, after the DS area
+        let total_size = context.dsi_rom_size.unwrap_or(rom_size);
+        let capacity = self.capacity(total_size);
+        if capacity.size() < total_size as u64 {
+            log::warn!(
+                "The ROM size {} ({total_size:#x}) exceeds the capacity {capacity} declared in the header, it will not fit on \
+                 the chip",
+                BlobSize(total_size as usize)
+            );
+        }
+
         let logo = rom.header_logo().compress();
         let arm9 = rom.arm9();
         let arm7 = rom.arm7();
-        let arm9_offset = context.arm9_offset.expect("ARM9 offset must be known");
-        let arm7_offset = context.arm7_offset.expect("ARM7 offset must be known");
+        let arm9_offset = context.arm9_offset.context(MissingContextSnafu { field: "ARM9 offset" })?;
+        let arm7_offset = context.arm7_offset.context(MissingContextSnafu { field: "ARM7 offset" })?;
         let mut header = raw::Header {
-            title: AsciiArray::from_str(&self.original.title)?,
+            title: self.original.title.parse().context(AsciiArraySnafu { field: "title" })?,
             gamecode: self.original.gamecode,
             makercode: self.original.makercode,
             unitcode: self.original.unitcode,
             seed_select: self.original.seed_select,
-            capacity: Capacity::from_size(context.rom_size.expect("ROM size must be known")),
+            capacity,
             reserved0: [0; 7],
             dsi_flags: DsiFlags::new(),
             ds_flags: self.original.ds_flags,
@@ -148,23 +318,27 @@ impl Header {
                 base_addr: arm7.base_address(),
                 size: arm7.full_data().len() as u32,
             },
-            file_names: context.fnt_offset.expect("FNT offset must be known"),
-            file_allocs: context.fat_offset.expect("FAT offset must be known"),
+            file_names: context.fnt_offset.context(MissingContextSnafu { field: "FNT offset" })?,
+            file_allocs: context.fat_offset.context(MissingContextSnafu { field: "FAT offset" })?,
             arm9_overlays: context.arm9_ovt_offset.unwrap_or_default(),
             arm7_overlays: context.arm7_ovt_offset.unwrap_or_default(),
             normal_cmd_setting: self.original.normal_cmd_setting,
             key1_cmd_setting: self.original.key1_cmd_setting,
-            banner_offset: context.banner_offset.map(|b| b.offset).expect("Banner offset must be known"),
+            banner_offset: context.banner_offset.map(|b| b.offset).context(MissingContextSnafu { field: "banner offset" })?,
             secure_area_crc: if let Some(key) = context.blowfish_key {
                 arm9.secure_area_crc(key, self.original.gamecode.to_le_u32())
             } else {
                 0
             },
             secure_area_delay: self.original.secure_area_delay,
-            arm9_autoload_callback: context.arm9_autoload_callback.expect("ARM9 autoload callback must be known"),
-            arm7_autoload_callback: context.arm7_autoload_callback.expect("ARM7 autoload callback must be known"),
+            arm9_autoload_callback: context
+                .arm9_autoload_callback
+                .context(MissingContextSnafu { field: "ARM9 autoload callback" })?,
+            arm7_autoload_callback: context
+                .arm7_autoload_callback
+                .context(MissingContextSnafu { field: "ARM7 autoload callback" })?,
             secure_area_disable: 0,
-            rom_size_ds: context.rom_size.expect("ROM size must be known"),
+            rom_size_ds: rom_size,
             header_size: size_of::<raw::Header>() as u32,
             arm9_build_info_offset: context.arm9_build_info_offset.map(|offset| offset + arm9_offset).unwrap_or(0),
             arm7_build_info_offset: context.arm7_build_info_offset.map(|offset| offset + arm7_offset).unwrap_or(0),
@@ -182,7 +356,7 @@ impl Header {
             debug_ram_addr: 0,
             reserved3: [0; 0x4],
             reserved4: [0; 0x10],
-            // The below fields are for DSi only and are not supported yet
+            // The below fields are for DSi only, see `build_dsi`
             memory_banks_wram: [0; 5],
             memory_banks_arm9: [0; 3],
             memory_banks_arm7: [0; 3],
@@ -239,13 +413,90 @@ impl Header {
             header.sha1_hmac_banner = ds_post_dsi.sha1_hmac_banner;
             header.sha1_hmac_unk1 = ds_post_dsi.sha1_hmac_unk1;
             header.sha1_hmac_unk2 = ds_post_dsi.sha1_hmac_unk2;
-            header.rsa_sha1.copy_from_slice(&ds_post_dsi.rsa_sha1);
+            let len = ds_post_dsi.rsa_sha1.len().min(RSA_SHA1_SIZE);
+            header.rsa_sha1[..len].copy_from_slice(&ds_post_dsi.rsa_sha1[..len]);
+        }
+        if let Some(dsi) = &self.dsi {
+            self.build_dsi(dsi, &mut header, context, rom)?;
         }
+        header.clear_unused_fields(self.version());
 
-        header.header_crc = CRC_16_MODBUS.checksum(&bytemuck::bytes_of(&header)[0..offset_of!(raw::Header, header_crc)]);
+        header.header_crc = header.compute_header_crc();
         Ok(header)
     }
 
+    fn build_dsi(
+        &self,
+        dsi: &HeaderDsi,
+        header: &mut raw::Header,
+        context: &BuildContext,
+        rom: &Rom,
+    ) -> Result<(), HeaderBuildError> {
+        header.dsi_flags = dsi.dsi_flags;
+        header.memory_banks_wram = dsi.memory_banks_wram;
+        header.memory_banks_arm9 = dsi.memory_banks_arm9;
+        header.memory_banks_arm7 = dsi.memory_banks_arm7;
+        header.memory_bank_9 = dsi.memory_bank_9;
+        header.region_flags = dsi.region_flags;
+        header.access_control = dsi.access_control;
+        header.arm7_scfg_ext7_setting = dsi.arm7_scfg_ext7_setting;
+        header.digest_ds_area = dsi.digest_ds_area;
+        header.digest_dsi_area = dsi.digest_dsi_area;
+        header.digest_sector_size = dsi.digest_sector_size;
+        header.digest_sector_count = dsi.digest_sector_count;
+        header.banner_size = context.banner_offset.map(|b| b.size).context(MissingContextSnafu { field: "banner offset" })?;
+        [
+            header.sd_shared2_0000_size,
+            header.sd_shared2_0001_size,
+            header.sd_shared2_0002_size,
+            header.sd_shared2_0003_size,
+            header.sd_shared2_0004_size,
+            header.sd_shared2_0005_size,
+        ] = dsi.sd_shared2_sizes;
+        header.eula_version = dsi.eula_version;
+        header.use_ratings = dsi.use_ratings;
+        header.age_ratings = dsi.age_ratings;
+        header.gamecode_rev = self.original.gamecode;
+        header.gamecode_rev.0.reverse();
+        header.file_type = dsi.file_type;
+        header.sd_public_sav_size = dsi.sd_public_sav_size;
+        header.sd_private_sav_size = dsi.sd_private_sav_size;
+        header.modcrypt_area_1 = dsi.modcrypt_area_1;
+        header.modcrypt_area_2 = dsi.modcrypt_area_2;
+        header.sha1_hmac_arm9_with_secure_area = dsi.sha1_hmac_arm9_with_secure_area;
+        header.sha1_hmac_arm7 = dsi.sha1_hmac_arm7;
+        header.sha1_hmac_digest = dsi.sha1_hmac_digest;
+        header.sha1_hmac_arm9i = dsi.sha1_hmac_arm9i;
+        header.sha1_hmac_arm7i = dsi.sha1_hmac_arm7i;
+        header.sha1_hmac_arm9 = dsi.sha1_hmac_arm9;
+
+        let Some(dsi_area) = rom.dsi_area() else {
+            return Ok(());
+        };
+        let arm9i_offset = context.arm9i_offset.context(MissingContextSnafu { field: "ARM9i offset" })?;
+        let arm7i_offset = context.arm7i_offset.context(MissingContextSnafu { field: "ARM7i offset" })?;
+        let program = |program: &DsiProgram, offset: u32| {
+            let offset = ProgramOffset {
+                offset,
+                entry: program.entry_function(),
+                base_addr: program.base_address(),
+                size: program.full_data().len() as u32,
+            };
+            (offset, program.build_info_offset())
+        };
+        (header.arm9i, header.arm9i_build_info_offset) = program(dsi_area.arm9i(), arm9i_offset);
+        (header.arm7i, header.arm7i_build_info_offset) = program(dsi_area.arm7i(), arm7i_offset);
+        header.digest_sector_hashtable =
+            context.digest_sector_hashtable_offset.context(MissingContextSnafu { field: "digest sector hashtable offset" })?;
+        header.digest_block_hashtable =
+            context.digest_block_hashtable_offset.context(MissingContextSnafu { field: "digest block hashtable offset" })?;
+        let dsi_region_start = context.dsi_region_start.context(MissingContextSnafu { field: "DSi region start" })?;
+        header.ds_rom_region_end = (dsi_region_start / DSI_REGION_ALIGNMENT) as u16;
+        header.rom_size_dsi = context.dsi_rom_size.context(MissingContextSnafu { field: "DSi ROM size" })?;
+        header.dsi_rom_region_end = header.rom_size_dsi.div_ceil(DSI_REGION_ALIGNMENT) as u16;
+        Ok(())
+    }
+
     /// Returns the version of this [`Header`].
     pub fn version(&self) -> HeaderVersion {
         if self.ds_post_dsi.is_some() {
@@ -254,4 +505,364 @@ impl Header {
             HeaderVersion::Original
         }
     }
+
+    /// Returns the capacity to build a ROM of the given size with. The original capacity is used if it's set and either larger
+    /// than the needed capacity or [`HeaderOriginal::preserve_capacity`] is set.
+    pub fn capacity(&self, rom_size: u32) -> Capacity {
+        let needed = Capacity::from_size(rom_size);
+        match self.original.capacity {
+            Some(capacity) if self.original.preserve_capacity || capacity > needed => capacity,
+            _ => needed,
+        }
+    }
+
+    /// Overrides simple scalar fields of this header, such as when building with different boot settings than the ones in
+    /// the extracted header. Each override is a field name from [`Self::OVERRIDABLE_FIELDS`] and a value, which is decimal
+    /// or hexadecimal if prefixed with `0x`. The header is left unchanged if any override fails.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a field can't be overridden or its value doesn't fit the field.
+    pub fn apply_overrides(&mut self, overrides: &[(String, String)]) -> Result<(), HeaderOverrideError> {
+        let mut header = self.clone();
+        for (field, value) in overrides {
+            header.apply_override(field, value)?;
+        }
+        *self = header;
+        Ok(())
+    }
+
+    fn apply_override(&mut self, field: &str, value: &str) -> Result<(), HeaderOverrideError> {
+        let original = &mut self.original;
+        match field {
+            "unitcode" => original.unitcode = parse_override(field, value)?,
+            "seed_select" => original.seed_select = parse_override(field, value)?,
+            "autostart" => original.autostart = parse_override(field, value)?,
+            "normal_cmd_setting" => original.normal_cmd_setting = parse_override(field, value)?,
+            "key1_cmd_setting" => original.key1_cmd_setting = parse_override(field, value)?,
+            "secure_area_delay" => original.secure_area_delay = Delay(parse_override(field, value)?),
+            "rom_nand_end" => original.rom_nand_end = parse_override(field, value)?,
+            "rw_nand_end" => original.rw_nand_end = parse_override(field, value)?,
+            _ => return UnknownFieldSnafu { field }.fail(),
+        }
+        log::info!("Overriding header {field} with {value}");
+        Ok(())
+    }
+
+    /// Cross-checks the version and unit code of this header against what `rom` contains, such as DSi-only fields on a
+    /// DS-only game or a DSi unit code without a DSi program. Returns a finding with a remediation hint for each
+    /// inconsistency, which [`Rom::build`] reports before building the header.
+    pub fn check_consistency(&self, rom: &Rom) -> Vec<HeaderConsistencyFinding> {
+        let mut findings = vec![];
+        let mut finding = |field: &'static str, problem: String, hint: &str| {
+            findings.push(HeaderConsistencyFinding { field, problem, hint: hint.to_string() });
+        };
+
+        let original = &self.original;
+        let unitcode = original.unitcode;
+        match unitcode {
+            UNITCODE_DS => {
+                if rom.dsi_area().is_some() {
+                    finding(
+                        "unitcode",
+                        "unit code 0 declares a DS-only game, but the ROM has ARM9i/ARM7i programs".to_string(),
+                        "set unitcode to 2 or 3, or remove the dsi entries from the config",
+                    );
+                }
+            }
+            UNITCODE_DSI_ENHANCED | UNITCODE_DSI_EXCLUSIVE => {
+                if rom.dsi_area().is_none() {
+                    finding(
+                        "unitcode",
+                        format!("unit code {unitcode} declares a DSi program, but the ROM has no ARM9i/ARM7i program"),
+                        "set unitcode to 0 or add the dsi entries to the config",
+                    );
+                } else if self.dsi.is_none() {
+                    finding(
+                        "dsi",
+                        "the ROM has ARM9i/ARM7i programs, but the header has no DSi fields".to_string(),
+                        "add the dsi fields to the header",
+                    );
+                }
+                if self.ds_post_dsi.is_none() {
+                    finding(
+                        "unitcode",
+                        format!("unit code {unitcode} requires a header made after the DSi release"),
+                        "add the ds_post_dsi fields to the header",
+                    );
+                }
+            }
+            _ => finding(
+                "unitcode",
+                format!("unknown unit code {unitcode}"),
+                "use 0 for DS, 2 for DSi-enhanced or 3 for DSi-only",
+            ),
+        }
+        let reserved = original.ds_flags.into_bits() & DS_FLAGS_RESERVED;
+        if reserved != 0 {
+            finding(
+                "ds_flags",
+                format!("reserved bits {reserved:#x} are set"),
+                "clear the reserved bits, they are zero in retail ROMs",
+            );
+        }
+
+        if let Some(banner) = rom.banner() {
+            let banner_version = banner.version();
+            if original.ds_flags.china_region() && !banner_version.has_chinese() {
+                finding(
+                    "ds_flags",
+                    format!("China flag is set, but banner version {banner_version} has no Chinese title"),
+                    "raise the banner version or clear the China flag",
+                );
+            }
+            if original.ds_flags.korea_region() && !banner_version.has_korean() {
+                finding(
+                    "ds_flags",
+                    format!("Korea flag is set, but banner version {banner_version} has no Korean title"),
+                    "raise the banner version or clear the Korea flag",
+                );
+            }
+            if (banner_version.has_animation() || banner.keyframes.is_some()) && self.ds_post_dsi.is_none() {
+                finding(
+                    "ds_post_dsi",
+                    "the banner has an animated icon, which is only shown by the DSi menu, but the header predates the DSi"
+                        .to_string(),
+                    "add the ds_post_dsi fields to the header or remove the animation",
+                );
+            }
+        }
+
+        let Some(ds_post_dsi) = &self.ds_post_dsi else {
+            return findings;
+        };
+        let flags = ds_post_dsi.dsi_flags_2;
+        if flags.into_bits() == 0 {
+            finding(
+                "dsi_flags_2",
+                "DSi flags are zero, so the built header will be read as the original version".to_string(),
+                "set dsi_flags_2 or remove the ds_post_dsi fields",
+            );
+        }
+        let reserved = flags.into_bits() & DSI_FLAGS_2_RESERVED;
+        if reserved != 0 {
+            finding(
+                "dsi_flags_2",
+                format!("reserved bits {reserved:#x} are set"),
+                "clear the reserved bits, they are zero in retail ROMs",
+            );
+        }
+        if flags.tsc_dsi_mode() && unitcode == UNITCODE_DS {
+            finding(
+                "dsi_flags_2",
+                "touchscreen is in DSi mode, but unit code 0 declares a DS-only game".to_string(),
+                "clear tsc_dsi_mode or change the unit code",
+            );
+        }
+        if flags.has_icon_sha1() && ds_post_dsi.sha1_hmac_banner.iter().all(|&b| b == 0) {
+            finding(
+                "sha1_hmac_banner",
+                "the icon HMAC is flagged as present but is zero".to_string(),
+                "copy sha1_hmac_banner from the original ROM or clear has_icon_sha1",
+            );
+        }
+        if flags.has_header_rsa() && ds_post_dsi.rsa_sha1.iter().all(|&b| b == 0) {
+            finding(
+                "rsa_sha1",
+                "the header signature is flagged as present but is zero".to_string(),
+                "copy rsa_sha1 from the original ROM or clear has_header_rsa",
+            );
+        }
+        if ds_post_dsi.rsa_sha1.len() != RSA_SHA1_SIZE {
+            finding(
+                "rsa_sha1",
+                format!("signature is {:#x} bytes, expected {RSA_SHA1_SIZE:#x}", ds_post_dsi.rsa_sha1.len()),
+                "copy rsa_sha1 from the original ROM, it will be truncated or zero-padded",
+            );
+        }
+        if ds_post_dsi.sha1_hmac_unk1.iter().chain(&ds_post_dsi.sha1_hmac_unk2).all(|&b| b == 0) && flags.into_bits() != 0 {
+            finding(
+                "sha1_hmac_unk1",
+                "the overlay and ARM9 HMACs are zero, so the DSi will refuse to run the game".to_string(),
+                "copy sha1_hmac_unk1 and sha1_hmac_unk2 from the original ROM",
+            );
+        }
+
+        findings
+    }
+
+    /// Compares this header to a raw header, field by field. Fields which this header controls are compared against their
+    /// expected value, while fields derived by [`Self::build`] are listed without one.
+    pub fn compare_to_raw(&self, header: &raw::Header) -> Vec<HeaderFieldComparison> {
+        let mut fields = vec![];
+        macro_rules! controlled {
+            ($field:literal, $expected:expr, $actual:expr) => {
+                fields.push(HeaderFieldComparison { field: $field, expected: Some($expected), actual: $actual })
+            };
+        }
+        macro_rules! derived {
+            ($field:literal, $actual:expr) => {
+                fields.push(HeaderFieldComparison { field: $field, expected: None, actual: $actual })
+            };
+        }
+
+        let original = &self.original;
+        controlled!("title", original.title.clone(), header.title.to_string());
+        controlled!("gamecode", original.gamecode.to_string(), header.gamecode.to_string());
+        controlled!("makercode", original.makercode.to_string(), header.makercode.to_string());
+        controlled!("unitcode", format!("{:#x}", original.unitcode), format!("{:#x}", header.unitcode));
+        controlled!("seed_select", format!("{:#x}", original.seed_select), format!("{:#x}", header.seed_select));
+        controlled!(
+            "ds_flags",
+            format!("{:#x}", original.ds_flags.into_bits()),
+            format!("{:#x}", header.ds_flags.into_bits())
+        );
+        controlled!("autostart", format!("{:#x}", original.autostart), format!("{:#x}", header.autostart));
+        controlled!(
+            "normal_cmd_setting",
+            format!("{:#x}", original.normal_cmd_setting),
+            format!("{:#x}", header.normal_cmd_setting)
+        );
+        controlled!(
+            "key1_cmd_setting",
+            format!("{:#x}", original.key1_cmd_setting),
+            format!("{:#x}", header.key1_cmd_setting)
+        );
+        controlled!(
+            "secure_area_delay",
+            format!("{:#x}", original.secure_area_delay.0),
+            format!("{:#x}", header.secure_area_delay.0)
+        );
+        controlled!("rom_nand_end", format!("{:#x}", original.rom_nand_end), format!("{:#x}", header.rom_nand_end));
+        controlled!("rw_nand_end", format!("{:#x}", original.rw_nand_end), format!("{:#x}", header.rw_nand_end));
+
+        if let Some(ds_post_dsi) = &self.ds_post_dsi {
+            controlled!(
+                "dsi_flags_2",
+                format!("{:#x}", ds_post_dsi.dsi_flags_2.into_bits()),
+                format!("{:#x}", header.dsi_flags_2.into_bits())
+            );
+            controlled!("sha1_hmac_banner", hex_string(&ds_post_dsi.sha1_hmac_banner), hex_string(&header.sha1_hmac_banner));
+            controlled!("sha1_hmac_unk1", hex_string(&ds_post_dsi.sha1_hmac_unk1), hex_string(&header.sha1_hmac_unk1));
+            controlled!("sha1_hmac_unk2", hex_string(&ds_post_dsi.sha1_hmac_unk2), hex_string(&header.sha1_hmac_unk2));
+            controlled!("rsa_sha1", hex_string(&ds_post_dsi.rsa_sha1), hex_string(&header.rsa_sha1));
+        }
+
+        match original.capacity {
+            Some(capacity) => controlled!("capacity", capacity.to_string(), header.capacity.to_string()),
+            None => derived!("capacity", header.capacity.to_string()),
+        }
+        derived!("arm9.offset", format!("{:#x}", header.arm9.offset));
+        derived!("arm9.entry", format!("{:#x}", header.arm9.entry));
+        derived!("arm9.base_addr", format!("{:#x}", header.arm9.base_addr));
+        derived!("arm9.size", format!("{:#x}", header.arm9.size));
+        derived!("arm7.offset", format!("{:#x}", header.arm7.offset));
+        derived!("arm7.entry", format!("{:#x}", header.arm7.entry));
+        derived!("arm7.base_addr", format!("{:#x}", header.arm7.base_addr));
+        derived!("arm7.size", format!("{:#x}", header.arm7.size));
+        derived!("file_names", format!("{:#x} ({:#x} bytes)", header.file_names.offset, header.file_names.size));
+        derived!("file_allocs", format!("{:#x} ({:#x} bytes)", header.file_allocs.offset, header.file_allocs.size));
+        derived!("arm9_overlays", format!("{:#x} ({:#x} bytes)", header.arm9_overlays.offset, header.arm9_overlays.size));
+        derived!("arm7_overlays", format!("{:#x} ({:#x} bytes)", header.arm7_overlays.offset, header.arm7_overlays.size));
+        derived!("banner_offset", format!("{:#x}", header.banner_offset));
+        derived!("secure_area_crc", format!("{:#x}", header.secure_area_crc));
+        derived!("arm9_autoload_callback", format!("{:#x}", header.arm9_autoload_callback));
+        derived!("arm7_autoload_callback", format!("{:#x}", header.arm7_autoload_callback));
+        derived!("rom_size_ds", format!("{:#x}", header.rom_size_ds));
+        derived!("header_size", format!("{:#x}", header.header_size));
+        derived!("arm9_build_info_offset", format!("{:#x}", header.arm9_build_info_offset));
+        if header.has_dsi_area() {
+            derived!("arm9i.offset", format!("{:#x}", header.arm9i.offset));
+            derived!("arm9i.size", format!("{:#x}", header.arm9i.size));
+            derived!("arm7i.offset", format!("{:#x}", header.arm7i.offset));
+            derived!("arm7i.size", format!("{:#x}", header.arm7i.size));
+            derived!(
+                "digest_sector_hashtable",
+                format!("{:#x} ({:#x} bytes)", header.digest_sector_hashtable.offset, header.digest_sector_hashtable.size)
+            );
+            derived!(
+                "digest_block_hashtable",
+                format!("{:#x} ({:#x} bytes)", header.digest_block_hashtable.offset, header.digest_block_hashtable.size)
+            );
+            derived!("ds_rom_region_end", format!("{:#x}", header.ds_rom_region_end));
+            derived!("dsi_rom_region_end", format!("{:#x}", header.dsi_rom_region_end));
+            derived!("rom_size_dsi", format!("{:#x}", header.rom_size_dsi));
+        }
+        derived!("logo_crc", format!("{:#x}", header.logo_crc));
+        derived!("header_crc", format!("{:#x}", header.header_crc));
+
+        fields
+    }
+}
+
+/// Parses an override value for an integer field, see [`Header::apply_overrides`].
+/// Deserializes an [`AsciiArray`] header field, naming the field in the error like [`HeaderBuildError::AsciiArray`].
+fn deserialize_ascii_field<'de, D, const N: usize>(deserializer: D, field: &str) -> Result<AsciiArray<N>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let string = String::deserialize(deserializer)?;
+    string.parse().map_err(|error: AsciiArrayError| de::Error::custom(format!("invalid header {field}: {error}")))
+}
+
+fn deserialize_gamecode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AsciiArray<4>, D::Error> {
+    deserialize_ascii_field(deserializer, "gamecode")
+}
+
+fn deserialize_makercode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AsciiArray<2>, D::Error> {
+    deserialize_ascii_field(deserializer, "makercode")
+}
+
+fn parse_override<T: TryFrom<u64>>(field: &str, value: &str) -> Result<T, HeaderOverrideError> {
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse::<u64>(),
+    };
+    let reason = match parsed {
+        Ok(number) => match T::try_from(number) {
+            Ok(number) => return Ok(number),
+            Err(_) => format!("doesn't fit in {} bits", size_of::<T>() * 8),
+        },
+        Err(e) => e.to_string(),
+    };
+    InvalidValueSnafu { field, value, reason }.fail()
+}
+
+/// An inconsistency found by [`Header::check_consistency`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderConsistencyFinding {
+    /// Name of the header field which is inconsistent.
+    pub field: &'static str,
+    /// Description of the inconsistency.
+    pub problem: String,
+    /// How to resolve the inconsistency.
+    pub hint: String,
+}
+
+impl Display for HeaderConsistencyFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "header {}: {}, {}", self.field, self.problem, self.hint)
+    }
+}
+
+/// A raw header field compared by [`Header::compare_to_raw`].
+pub struct HeaderFieldComparison {
+    /// Name of the raw header field.
+    pub field: &'static str,
+    /// Value in the plain header, or `None` if the field is derived when building the ROM.
+    pub expected: Option<String>,
+    /// Value in the raw header.
+    pub actual: String,
+}
+
+impl HeaderFieldComparison {
+    /// Returns whether the field is derived when building the ROM, instead of being controlled by the plain header.
+    pub fn is_derived(&self) -> bool {
+        self.expected.is_none()
+    }
+
+    /// Returns whether the raw value matches the expected value. Derived fields always match.
+    pub fn matches(&self) -> bool {
+        self.expected.as_ref().is_none_or(|expected| *expected == self.actual)
+    }
 }
@@ -0,0 +1,312 @@
+use std::fmt::Display;
+
+use snafu::{Backtrace, Snafu};
+
+use super::raw::{FileAlloc, TableOffset};
+
+/// Location of a section in a ROM, see [`RomLayout`].
+#[derive(Clone, Copy, Default)]
+pub struct RomLayoutSection {
+    /// ROM offset to the start of the section.
+    pub offset: u32,
+    /// Size of the section.
+    pub size: u32,
+    /// Whether the size is an estimate, for example when a module was loaded without compressing it.
+    pub estimated: bool,
+}
+
+impl RomLayoutSection {
+    /// Returns the end offset of this [`RomLayoutSection`].
+    pub fn end(&self) -> u32 {
+        self.offset + self.size
+    }
+
+    /// Returns this section as a [`TableOffset`].
+    pub fn table_offset(&self) -> TableOffset {
+        TableOffset { offset: self.offset, size: self.size }
+    }
+}
+
+/// Location of the DSi area in a ROM, see [`RomLayout::dsi`].
+#[derive(Clone, Copy)]
+pub struct RomLayoutDsi {
+    /// Digest sector hashtable.
+    pub digest_sector_hashtable: RomLayoutSection,
+    /// Digest block hashtable.
+    pub digest_block_hashtable: RomLayoutSection,
+    /// ARM9i program, which starts the DSi region.
+    pub arm9i: RomLayoutSection,
+    /// ARM7i program.
+    pub arm7i: RomLayoutSection,
+    /// Start of the DSi region, which is where the DS region ends.
+    pub region_start: u32,
+    /// Size of the ROM including the DSi region.
+    pub rom_size: u32,
+}
+
+/// Errors related to [`RomLayout::validate`].
+#[derive(Debug, Snafu)]
+pub enum RomLayoutError {
+    /// Occurs when the FAT section doesn't fit the file allocations exactly, so writing them would overrun the next
+    /// section or leave stale data.
+    #[snafu(display(
+        "the FAT section is {section_size:#x} bytes but its {entries} entries take {table_size:#x} bytes:\n{backtrace}"
+    ))]
+    FatSizeMismatch {
+        /// Size of the FAT section.
+        section_size: u32,
+        /// Number of file allocations.
+        entries: usize,
+        /// Size of the file allocations.
+        table_size: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a section starts before the section which is written ahead of it.
+    #[snafu(display("{section} at {offset:#x} is placed before {previous} at {previous_offset:#x}:\n{backtrace}"))]
+    SectionOrder {
+        /// Name of the section.
+        section: String,
+        /// Offset of the section.
+        offset: u32,
+        /// Name of the section written ahead of it.
+        previous: String,
+        /// Offset of the section written ahead of it.
+        previous_offset: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a section starts before the section which is written ahead of it has ended.
+    #[snafu(display("{section} at {offset:#x} overlaps {previous} which ends at {previous_end:#x}:\n{backtrace}"))]
+    SectionOverlap {
+        /// Name of the section.
+        section: String,
+        /// Offset of the section.
+        offset: u32,
+        /// Name of the section written ahead of it.
+        previous: String,
+        /// End of the section written ahead of it.
+        previous_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a section which can't have a configured offset is not aligned.
+    #[snafu(display("{section} at {offset:#x} is not aligned to {alignment:#x} bytes:\n{backtrace}"))]
+    UnalignedSection {
+        /// Name of the section.
+        section: String,
+        /// Offset of the section.
+        offset: u32,
+        /// Required alignment.
+        alignment: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Alignment of every section which can't be moved by [`super::RomConfig::section_offsets`].
+const SECTION_ALIGNMENT: u32 = 0x200;
+
+/// Returns the size of a FAT with `num_entries` entries.
+pub(crate) fn fat_size(num_entries: usize) -> u32 {
+    (num_entries * size_of::<FileAlloc>()) as u32
+}
+
+/// Planned layout of a built ROM, see [`super::Rom::plan_layout`].
+pub struct RomLayout {
+    /// Header.
+    pub header: RomLayoutSection,
+    /// Data between the header and ARM9 program, if any.
+    pub pre_arm9: Option<RomLayoutSection>,
+    /// ARM9 program, including its footer.
+    pub arm9: RomLayoutSection,
+    /// ARM9 overlay table, if there are any ARM9 overlays.
+    pub arm9_overlay_table: Option<RomLayoutSection>,
+    /// ARM9 overlays, in the same order as [`super::Rom::arm9_overlays`].
+    pub arm9_overlays: Vec<RomLayoutSection>,
+    /// ARM7 program.
+    pub arm7: RomLayoutSection,
+    /// ARM7 overlay table, if there are any ARM7 overlays.
+    pub arm7_overlay_table: Option<RomLayoutSection>,
+    /// ARM7 overlays, in the same order as [`super::Rom::arm7_overlays`].
+    pub arm7_overlays: Vec<RomLayoutSection>,
+    /// File name table.
+    pub fnt: RomLayoutSection,
+    /// File allocation table.
+    pub fat: RomLayoutSection,
+    /// Banner, if the ROM has one.
+    pub banner: Option<RomLayoutSection>,
+    /// Region containing all files, if there are any.
+    pub files: Option<RomLayoutSection>,
+    /// File allocations indexed by file ID, including overlays.
+    pub file_allocs: Vec<FileAlloc>,
+    /// File IDs in the order they're placed in the ROM. Files in [`Self::shared_files`] are not included.
+    pub file_order: Vec<u16>,
+    /// Files which are not stored themselves because they share the contents of an identical file, as pairs of the file ID
+    /// and the ID of the stored file. Only used if [`super::RomConfig::dedup_identical_files`] is enabled.
+    pub shared_files: Vec<(u16, u16)>,
+    /// DSi area, if the ROM has one.
+    pub dsi: Option<RomLayoutDsi>,
+    /// Size of the ROM before padding.
+    pub rom_size: u32,
+    /// Size of the ROM after padding.
+    pub padded_size: u32,
+    /// Data appended after the padded ROM, if any.
+    pub trailing_data: Option<RomLayoutSection>,
+}
+
+impl RomLayout {
+    /// Returns the name and location of each section, ordered by offset.
+    pub fn sections(&self) -> Vec<(String, RomLayoutSection)> {
+        let mut sections = vec![("Header".to_string(), self.header)];
+        sections.extend(self.pre_arm9.map(|section| ("Pre-ARM9 data".to_string(), section)));
+        sections.push(("ARM9 program".to_string(), self.arm9));
+        sections.extend(self.arm9_overlay_table.map(|section| ("ARM9 overlay table".to_string(), section)));
+        sections.extend(self.arm9_overlays.iter().enumerate().map(|(id, &section)| (format!("ARM9 overlay {id}"), section)));
+        sections.push(("ARM7 program".to_string(), self.arm7));
+        sections.extend(self.arm7_overlay_table.map(|section| ("ARM7 overlay table".to_string(), section)));
+        sections.extend(self.arm7_overlays.iter().enumerate().map(|(id, &section)| (format!("ARM7 overlay {id}"), section)));
+        sections.push(("FNT".to_string(), self.fnt));
+        sections.push(("FAT".to_string(), self.fat));
+        if let Some(banner) = self.banner {
+            sections.push(("Banner".to_string(), banner));
+        }
+        sections.extend(self.files.map(|section| ("Files".to_string(), section)));
+        if let Some(dsi) = &self.dsi {
+            sections.push(("Digest sectors".to_string(), dsi.digest_sector_hashtable));
+            sections.push(("Digest blocks".to_string(), dsi.digest_block_hashtable));
+            sections.push(("ARM9i program".to_string(), dsi.arm9i));
+            sections.push(("ARM7i program".to_string(), dsi.arm7i));
+        }
+        sections.extend(self.trailing_data.map(|section| ("Trailing data".to_string(), section)));
+        sections.sort_by_key(|(_, section)| section.offset);
+        sections
+    }
+
+    /// Returns the name and location of each section after the header, in the order they're written. Stored files are
+    /// listed separately, and each section is paired with whether it must be aligned to [`SECTION_ALIGNMENT`].
+    fn sections_in_write_order(&self) -> Vec<(String, RomLayoutSection, bool)> {
+        let mut sections = vec![("ARM9 program".to_string(), self.arm9, true)];
+        sections.extend(self.arm9_overlay_table.map(|section| ("ARM9 overlay table".to_string(), section, true)));
+        sections
+            .extend(self.arm9_overlays.iter().enumerate().map(|(id, &section)| (format!("ARM9 overlay {id}"), section, true)));
+        sections.push(("ARM7 program".to_string(), self.arm7, true));
+        sections.extend(self.arm7_overlay_table.map(|section| ("ARM7 overlay table".to_string(), section, true)));
+        sections
+            .extend(self.arm7_overlays.iter().enumerate().map(|(id, &section)| (format!("ARM7 overlay {id}"), section, true)));
+        sections.push(("FNT".to_string(), self.fnt, false));
+        sections.push(("FAT".to_string(), self.fat, false));
+        sections.extend(self.banner.map(|section| ("Banner".to_string(), section, false)));
+        sections.extend(self.file_order.iter().map(|&id| {
+            let alloc = self.file_allocs[id as usize];
+            (
+                format!("File {id}"),
+                RomLayoutSection { offset: alloc.start, size: alloc.end - alloc.start, estimated: false },
+                true,
+            )
+        }));
+        if let Some(dsi) = &self.dsi {
+            sections.push(("Digest sectors".to_string(), dsi.digest_sector_hashtable, false));
+            sections.push(("Digest blocks".to_string(), dsi.digest_block_hashtable, false));
+            sections.push(("ARM9i program".to_string(), dsi.arm9i, false));
+            sections.push(("ARM7i program".to_string(), dsi.arm7i, false));
+        }
+        sections.extend(self.trailing_data.map(|section| ("Trailing data".to_string(), section, false)));
+        sections
+    }
+
+    /// Returns how many bytes were saved by [`Self::shared_files`], not counting alignment.
+    pub fn shared_bytes(&self) -> u32 {
+        self.shared_files
+            .iter()
+            .map(|&(id, _)| {
+                let alloc = self.file_allocs[id as usize];
+                alloc.end - alloc.start
+            })
+            .sum()
+    }
+
+    /// Checks that the sections are consistent with the tables written into them, and that they can be written in order.
+    /// This is done before writing a ROM, so that a mistake in the layout becomes an error instead of a corrupted ROM.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - [`Self::fat`] is not exactly the size of [`Self::file_allocs`].
+    /// - A section starts before the end of the section written ahead of it.
+    /// - A section which is always placed at an aligned offset, like a program, overlay or file, is not aligned.
+    pub fn validate(&self) -> Result<(), RomLayoutError> {
+        let table_size = fat_size(self.file_allocs.len());
+        if self.fat.size != table_size {
+            return FatSizeMismatchSnafu { section_size: self.fat.size, entries: self.file_allocs.len(), table_size }.fail();
+        }
+
+        // The pre-ARM9 data is written into the unused part of the header, so it may overlap the header but nothing after
+        let mut previous = ("Header".to_string(), self.header);
+        if let Some(pre_arm9) = self.pre_arm9.filter(|pre_arm9| pre_arm9.end() > self.header.end()) {
+            previous = ("Pre-ARM9 data".to_string(), pre_arm9);
+        }
+        for (section, location, aligned) in self.sections_in_write_order() {
+            let (previous_name, previous_location) = &previous;
+            if location.offset < previous_location.offset {
+                return SectionOrderSnafu {
+                    section,
+                    offset: location.offset,
+                    previous: previous_name,
+                    previous_offset: previous_location.offset,
+                }
+                .fail();
+            }
+            if location.offset < previous_location.end() {
+                return SectionOverlapSnafu {
+                    section,
+                    offset: location.offset,
+                    previous: previous_name,
+                    previous_end: previous_location.end(),
+                }
+                .fail();
+            }
+            if aligned && location.offset % SECTION_ALIGNMENT != 0 {
+                return UnalignedSectionSnafu { section, offset: location.offset, alignment: SECTION_ALIGNMENT }.fail();
+            }
+            previous = (section, location);
+        }
+        Ok(())
+    }
+
+    /// Returns whether any section size is an estimate.
+    pub fn is_estimated(&self) -> bool {
+        self.sections().iter().any(|(_, section)| section.estimated)
+    }
+}
+
+impl Display for RomLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Section               Start      End        Size")?;
+        for (name, section) in self.sections() {
+            let estimated = if section.estimated { " (estimated)" } else { "" };
+            writeln!(f, "{name:<20}  {:#010x} {:#010x} {:#x}{estimated}", section.offset, section.end(), section.size)?;
+        }
+        writeln!(f, "ROM size ........ : {:#x}", self.rom_size)?;
+        write!(f, "Padded size ..... : {:#x}", self.padded_size)?;
+        if self.is_estimated() {
+            write!(f, " (estimated)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`super::Rom::build_to_writer`].
+pub struct BuildSummary {
+    /// Layout which the ROM was built with.
+    pub layout: RomLayout,
+    /// Number of bytes written, including padding and trailing data.
+    pub size: u64,
+}
+
+impl Display for BuildSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.layout)?;
+        write!(f, "Written size .... : {:#x}", self.size)
+    }
+}
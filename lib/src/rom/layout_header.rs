@@ -0,0 +1,188 @@
+use std::fmt::Display;
+
+use snafu::Snafu;
+
+use super::{
+    raw::{AutoloadKind, RawBuildInfoError},
+    Arm9AutoloadError, Processor, Rom,
+};
+
+/// Constants describing where the ARM9 program, autoload blocks, ARM7 program and overlays are loaded, for use in
+/// hand-written code of decomp projects. See [`LayoutHeader::new`].
+pub struct LayoutHeader {
+    symbols: Vec<LayoutSymbol>,
+    regions: Vec<LayoutRegion>,
+}
+
+/// A named constant in a [`LayoutHeader`], such as `OVERLAY_12_BASE`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutSymbol {
+    /// Name of the constant.
+    pub name: String,
+    /// Value of the constant.
+    pub value: u32,
+}
+
+/// A memory region in a [`LayoutHeader`], used for the `MEMORY` command of [`LayoutHeaderFormat::Ld`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutRegion {
+    /// Name of the region, such as `ITCM` or `OVERLAY_12`.
+    pub name: String,
+    /// Start address of the region.
+    pub origin: u32,
+    /// Size of the region, including .bss.
+    pub length: u32,
+}
+
+/// Output formats of [`LayoutHeader::display`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutHeaderFormat {
+    /// C header with `#define` lines.
+    C,
+    /// GNU assembler include file with `.set` lines.
+    Asm,
+    /// Linker script fragment with a `MEMORY` command and `PROVIDE` lines.
+    Ld,
+}
+
+/// Errors related to [`LayoutHeader`].
+#[derive(Debug, Snafu)]
+pub enum LayoutHeaderError {
+    /// See [`RawBuildInfoError`].
+    #[snafu(transparent)]
+    RawBuildInfo {
+        /// Source error.
+        source: RawBuildInfoError,
+    },
+    /// See [`Arm9AutoloadError`].
+    #[snafu(transparent)]
+    Arm9Autoload {
+        /// Source error.
+        source: Arm9AutoloadError,
+    },
+}
+
+impl LayoutHeader {
+    /// Collects the layout of a ROM. Overlays are sorted by ID, so the output only depends on the layout itself.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ARM9 program is compressed or its build info or autoload infos are
+    /// invalid.
+    pub fn new(rom: &Rom) -> Result<Self, LayoutHeaderError> {
+        let mut header = Self { symbols: vec![], regions: vec![] };
+
+        let arm9 = rom.arm9();
+        let bss = arm9.bss()?;
+        header.symbol("ARM9_BASE", arm9.base_address());
+        header.symbol("ARM9_ENTRY", arm9.entry_function());
+        header.symbol("ARM9_AUTOLOAD_CALLBACK", arm9.autoload_callback());
+        header.symbol("ARM9_BSS_START", bss.start);
+        header.symbol("ARM9_BSS_END", bss.end);
+        header.symbol("ARM9_BSS_SIZE", bss.len() as u32);
+        header.region("ARM9", arm9.base_address(), bss.end - arm9.base_address());
+
+        for (index, autoload) in arm9.autoloads()?.iter().enumerate() {
+            let prefix = match autoload.kind() {
+                AutoloadKind::Itcm => "ITCM".to_string(),
+                AutoloadKind::Dtcm => "DTCM".to_string(),
+                AutoloadKind::Unknown(_) => format!("AUTOLOAD_{index}"),
+            };
+            header.module(&prefix, autoload.base_address(), autoload.code().len() as u32, autoload.bss_size());
+        }
+
+        let arm7 = rom.arm7();
+        header.symbol("ARM7_BASE", arm7.base_address());
+        header.symbol("ARM7_ENTRY", arm7.entry_function());
+
+        for processor in [Processor::Arm9, Processor::Arm7] {
+            let mut overlays = rom.overlays(processor).iter().collect::<Vec<_>>();
+            overlays.sort_by_key(|overlay| overlay.id());
+            for overlay in overlays {
+                let prefix = match processor {
+                    Processor::Arm9 => format!("OVERLAY_{}", overlay.id()),
+                    Processor::Arm7 => format!("ARM7_OVERLAY_{}", overlay.id()),
+                };
+                header.module(&prefix, overlay.base_address(), overlay.code_size(), overlay.bss_size());
+            }
+        }
+
+        Ok(header)
+    }
+
+    fn symbol(&mut self, name: &str, value: u32) {
+        self.symbols.push(LayoutSymbol { name: name.to_string(), value });
+    }
+
+    fn region(&mut self, name: &str, origin: u32, length: u32) {
+        self.regions.push(LayoutRegion { name: name.to_string(), origin, length });
+    }
+
+    fn module(&mut self, prefix: &str, base_address: u32, code_size: u32, bss_size: u32) {
+        self.symbol(&format!("{prefix}_BASE"), base_address);
+        self.symbol(&format!("{prefix}_SIZE"), code_size);
+        self.symbol(&format!("{prefix}_BSS_SIZE"), bss_size);
+        self.symbol(&format!("{prefix}_END"), base_address + code_size + bss_size);
+        self.region(prefix, base_address, code_size + bss_size);
+    }
+
+    /// Returns the constants of this [`LayoutHeader`].
+    pub fn symbols(&self) -> &[LayoutSymbol] {
+        &self.symbols
+    }
+
+    /// Returns the memory regions of this [`LayoutHeader`].
+    pub fn regions(&self) -> &[LayoutRegion] {
+        &self.regions
+    }
+
+    /// Creates a [`DisplayLayoutHeader`] which implements [`Display`] as the contents of an include file in the given
+    /// format.
+    pub fn display(&self, format: LayoutHeaderFormat) -> DisplayLayoutHeader<'_> {
+        DisplayLayoutHeader { header: self, format }
+    }
+}
+
+/// Can be used to display the contents of a [`LayoutHeader`] include file.
+pub struct DisplayLayoutHeader<'a> {
+    header: &'a LayoutHeader,
+    format: LayoutHeaderFormat,
+}
+
+impl Display for DisplayLayoutHeader<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header = self.header;
+        writeln!(f, "/* Generated by dsrom, do not edit. */")?;
+        writeln!(f)?;
+        match self.format {
+            LayoutHeaderFormat::C => {
+                writeln!(f, "#ifndef DSROM_LAYOUT_H")?;
+                writeln!(f, "#define DSROM_LAYOUT_H")?;
+                writeln!(f)?;
+                for LayoutSymbol { name, value } in &header.symbols {
+                    writeln!(f, "#define {name} {value:#010x}")?;
+                }
+                writeln!(f)?;
+                writeln!(f, "#endif")?;
+            }
+            LayoutHeaderFormat::Asm => {
+                for LayoutSymbol { name, value } in &header.symbols {
+                    writeln!(f, "    .set {name}, {value:#010x}")?;
+                }
+            }
+            LayoutHeaderFormat::Ld => {
+                writeln!(f, "MEMORY")?;
+                writeln!(f, "{{")?;
+                for LayoutRegion { name, origin, length } in &header.regions {
+                    writeln!(f, "    {name} (rwx) : ORIGIN = {origin:#010x}, LENGTH = {length:#x}")?;
+                }
+                writeln!(f, "}}")?;
+                writeln!(f)?;
+                for LayoutSymbol { name, value } in &header.symbols {
+                    writeln!(f, "PROVIDE({name} = {value:#010x});")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
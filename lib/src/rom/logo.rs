@@ -3,7 +3,7 @@ use std::{fmt::Display, io, path::Path};
 use image::{io::Reader, GenericImageView, GrayImage, ImageError, Luma};
 use snafu::{Backtrace, Snafu};
 
-use crate::compress::huffman::{NibbleHuffman, NibbleHuffmanCode};
+use crate::compress::huffman::{data_to_diff16, diff16_to_data, NibbleHuffman, NibbleHuffmanCode};
 
 /// Huffman codes for every combination of 4 pixels
 const HUFFMAN: NibbleHuffman = NibbleHuffman {
@@ -35,6 +35,7 @@ const LOGO_HEADER: u32 = 0x0000d082;
 const LOGO_FOOTER: u32 = 0xfff4c307;
 
 /// Header logo.
+#[derive(Clone, PartialEq, Eq)]
 pub struct Logo {
     pixels: [u8; SIZE],
 }
@@ -152,6 +153,35 @@ fn reverse32(data: &mut [u8]) {
 }
 
 impl Logo {
+    /// Size of the 1bpp bitmap in bytes, see [`Self::from_bitmap`].
+    pub const BITMAP_SIZE: usize = SIZE;
+    /// Height of the logo in pixels.
+    pub const HEIGHT: usize = HEIGHT;
+    /// Width of the logo in pixels.
+    pub const WIDTH: usize = WIDTH;
+
+    /// Creates a [`Logo`] from a 1bpp bitmap. Rows are stored top to bottom, and each byte holds 8 pixels with the leftmost
+    /// pixel in the most significant bit. A set bit is a black pixel.
+    pub fn from_bitmap(bitmap: &[u8; SIZE]) -> Self {
+        Self { pixels: *bitmap }
+    }
+
+    /// Returns the 1bpp bitmap of this [`Logo`], see [`Self::from_bitmap`].
+    pub fn bitmap(&self) -> &[u8; SIZE] {
+        &self.pixels
+    }
+
+    /// Creates a [`Logo`] by calling `pixel(x, y)` for every pixel, which returns `true` for black pixels.
+    pub fn from_fn(pixel: impl Fn(usize, usize) -> bool) -> Self {
+        let mut logo = Self::default();
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                logo.set_pixel(x, y, pixel(x, y));
+            }
+        }
+        logo
+    }
+
     /// Saves this [`Logo`] to a PNG image.
     ///
     /// # Errors
@@ -226,11 +256,11 @@ impl Logo {
         }
 
         let len = bytes.len();
-        let mut diff = &mut bytes[4..len - 4];
+        let diff = &mut bytes[4..len - 4];
         if diff.len() != SIZE {
             WrongSizeSnafu { expected: SIZE, actual: diff.len() }.fail()?;
         }
-        HUFFMAN.diff16_to_data(&mut diff);
+        diff16_to_data(diff).unwrap();
 
         let mut logo = Logo::default();
         logo.load_tiles(diff);
@@ -241,7 +271,7 @@ impl Logo {
     pub fn compress(&self) -> [u8; 0x9c] {
         let mut diff = [0u8; SIZE + 8];
         self.store_tiles(&mut diff[4..SIZE + 4]);
-        HUFFMAN.data_to_diff16(&mut diff[4..SIZE + 4]);
+        data_to_diff16(&mut diff[4..SIZE + 4]).unwrap();
 
         diff[0..4].copy_from_slice(&LOGO_HEADER.to_le_bytes());
         diff[SIZE + 4..SIZE + 8].copy_from_slice(&LOGO_FOOTER.to_le_bytes());
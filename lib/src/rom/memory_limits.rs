@@ -0,0 +1,121 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Arm9, Overlay, Processor, Rom};
+
+const MAIN_RAM_START: u32 = 0x2000000;
+const WRAM_START: u32 = 0x3000000;
+const DTCM_SIZE: u32 = 0x4000;
+
+/// Memory limits for programs and overlays, used to warn about ROMs that would not fit in memory when booted.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MemoryLimits {
+    /// End of main RAM available to the ARM9 program and its overlays. The DTCM is also a limit if it's placed in main RAM.
+    pub arm9_main_ram_end: u32,
+    /// End of main RAM, used for ARM7 programs and overlays placed in main RAM.
+    pub main_ram_end: u32,
+    /// End of WRAM, used for ARM7 programs placed in WRAM.
+    pub arm7_wram_end: u32,
+}
+
+impl Default for MemoryLimits {
+    fn default() -> Self {
+        Self::retail()
+    }
+}
+
+/// A program or overlay which exceeds its [`MemoryLimits`].
+pub struct MemoryLimitFinding {
+    /// Name of the program or overlay.
+    pub name: String,
+    /// End address, including uninitialized data.
+    pub end_address: u32,
+    /// Address which the program or overlay should not exceed.
+    pub limit: u32,
+}
+
+impl MemoryLimits {
+    /// Limits for retail units with 4 MB of main RAM.
+    pub fn retail() -> Self {
+        Self { arm9_main_ram_end: 0x23e0000, main_ram_end: 0x2400000, arm7_wram_end: 0x3810000 }
+    }
+
+    /// Limits for debug units with 8 MB of main RAM.
+    pub fn debug() -> Self {
+        Self { arm9_main_ram_end: 0x27e0000, main_ram_end: 0x2800000, arm7_wram_end: 0x3810000 }
+    }
+
+    fn arm9_limit(&self, arm9: &Arm9, dtcm_base_address: Option<u32>) -> u32 {
+        match dtcm_base_address {
+            Some(dtcm) if (arm9.base_address()..self.arm9_main_ram_end).contains(&dtcm) => dtcm,
+            _ => self.arm9_main_ram_end,
+        }
+    }
+
+    fn check_overlays(
+        overlays: &[Overlay],
+        processor: Processor,
+        limit: u32,
+        dtcm_base_address: Option<u32>,
+        findings: &mut Vec<MemoryLimitFinding>,
+    ) {
+        for overlay in overlays {
+            let base_address = overlay.base_address();
+            // Overlays outside of main RAM are placed in ITCM or WRAM
+            if !(MAIN_RAM_START..WRAM_START).contains(&base_address) {
+                continue;
+            }
+            if dtcm_base_address.is_some_and(|dtcm| (dtcm..dtcm + DTCM_SIZE).contains(&base_address)) {
+                continue;
+            }
+            if base_address >= limit || overlay.end_address() > limit {
+                findings.push(MemoryLimitFinding {
+                    name: format!("{processor} overlay {}", overlay.id()),
+                    end_address: overlay.end_address(),
+                    limit,
+                });
+            }
+        }
+    }
+
+    /// Checks whether the programs and overlays of a [`Rom`] fit within these limits, and returns a finding for each one
+    /// that does not. The ARM9 program is skipped if it has no build info, and the ARM7 program's uninitialized data is not
+    /// accounted for.
+    pub fn check(&self, rom: &Rom) -> Vec<MemoryLimitFinding> {
+        let mut findings = vec![];
+
+        let arm9 = rom.arm9();
+        let dtcm_base_address = rom.dtcm_base_address();
+        let arm9_limit = self.arm9_limit(arm9, dtcm_base_address);
+        if let Ok(end_address) = arm9.end_address() {
+            if end_address > arm9_limit {
+                findings.push(MemoryLimitFinding { name: "ARM9 program".to_string(), end_address, limit: arm9_limit });
+            }
+        }
+        Self::check_overlays(rom.arm9_overlays(), Processor::Arm9, arm9_limit, dtcm_base_address, &mut findings);
+
+        let arm7 = rom.arm7();
+        let arm7_end = arm7.base_address() + arm7.full_data().len() as u32;
+        let arm7_limit = if arm7.base_address() >= WRAM_START { self.arm7_wram_end } else { self.main_ram_end };
+        if arm7_end > arm7_limit {
+            findings.push(MemoryLimitFinding { name: "ARM7 program".to_string(), end_address: arm7_end, limit: arm7_limit });
+        }
+        Self::check_overlays(rom.arm7_overlays(), Processor::Arm7, self.main_ram_end, None, &mut findings);
+
+        findings
+    }
+}
+
+impl Display for MemoryLimitFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ends at {:#x}, exceeding the limit {:#x} by {:#x} bytes",
+            self.name,
+            self.end_address,
+            self.limit,
+            self.end_address - self.limit
+        )
+    }
+}
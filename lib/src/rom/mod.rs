@@ -3,23 +3,61 @@ mod arm9;
 mod autoload;
 mod banner;
 mod build_info;
+mod build_metadata;
+mod compressed_files;
+mod compression_report;
 mod config;
+mod data_window;
+mod dedup;
+mod dsi;
 mod file;
+mod hash_report;
 mod header;
+mod layout;
+mod layout_header;
 mod logo;
+mod memory_limits;
 mod overlay;
+mod overlay_table;
+mod processor;
+/// Consistency checks for extracted projects.
+pub mod project;
+mod project_manifest;
 /// Raw ROM access.
 pub mod raw;
 mod rom;
+mod rom_comparison;
+mod rom_diff;
+mod save_summary;
+mod schema;
+/// Name comparisons used to sort files and directories.
+pub mod sort;
 
 pub use arm7::*;
 pub use arm9::*;
 pub use autoload::*;
 pub use banner::*;
 pub use build_info::*;
+pub use build_metadata::*;
+pub use compressed_files::*;
+pub use compression_report::*;
 pub use config::*;
+pub use data_window::*;
+pub use dedup::*;
+pub use dsi::*;
 pub use file::*;
+pub use hash_report::*;
 pub use header::*;
+pub use layout::*;
+pub use layout_header::*;
 pub use logo::*;
+pub use memory_limits::*;
 pub use overlay::*;
+pub use overlay_table::*;
+pub use processor::*;
+pub use project_manifest::*;
 pub use rom::*;
+pub use rom_comparison::*;
+pub use rom_diff::*;
+pub use save_summary::*;
+pub use schema::*;
@@ -1,9 +1,16 @@
-use std::{borrow::Cow, io};
+use std::{borrow::Cow, fmt::Display, io};
 
 use serde::{Deserialize, Serialize};
+use snafu::{Backtrace, Snafu};
 
-use super::raw::{self, FileAlloc, OverlayCompressedSize, RawHeaderError};
-use crate::compress::lz77::{Lz77, Lz77DecompressError};
+use super::{
+    raw::{self, FileAlloc, OverlayFlags, RawHeaderError},
+    FileSource,
+};
+use crate::{
+    compress::lz77::{Lz77, Lz77DecompressError},
+    crypto::hmac_sha1::HmacSha1,
+};
 
 /// An overlay module for ARM9/ARM7.
 #[derive(Clone)]
@@ -11,6 +18,9 @@ pub struct Overlay<'a> {
     originally_compressed: bool,
     info: OverlayInfo,
     data: Cow<'a, [u8]>,
+    config_extra: serde_yml::Mapping,
+    file_name: Option<String>,
+    placeholder: bool,
 }
 
 const LZ77: Lz77 = Lz77 {};
@@ -18,17 +28,59 @@ const LZ77: Lz77 = Lz77 {};
 impl<'a> Overlay<'a> {
     /// Creates a new [`Overlay`] from plain data.
     pub fn new<T: Into<Cow<'a, [u8]>>>(data: T, info: OverlayInfo, originally_compressed: bool) -> Self {
-        Self { originally_compressed, info, data: data.into() }
+        Self {
+            originally_compressed,
+            info,
+            data: data.into(),
+            config_extra: serde_yml::Mapping::new(),
+            file_name: None,
+            placeholder: false,
+        }
+    }
+
+    /// Creates a placeholder [`Overlay`], see [`Self::is_placeholder`].
+    pub fn placeholder(info: OverlayInfo) -> Self {
+        Self { placeholder: true, ..Self::new(vec![], info, false) }
+    }
+
+    /// Returns a copy of this [`Overlay`] which owns its data, so that it can outlive the buffer it was parsed from.
+    pub fn to_owned(&self) -> Overlay<'static> {
+        Overlay {
+            originally_compressed: self.originally_compressed,
+            info: self.info.clone(),
+            data: Cow::Owned(self.data.to_vec()),
+            config_extra: self.config_extra.clone(),
+            file_name: self.file_name.clone(),
+            placeholder: self.placeholder,
+        }
+    }
+
+    /// Converts this [`Overlay`] into one which owns its data. Data which is already owned is moved instead of copied.
+    pub fn into_owned(self) -> Overlay<'static> {
+        Overlay {
+            originally_compressed: self.originally_compressed,
+            info: self.info,
+            data: Cow::Owned(self.data.into_owned()),
+            config_extra: self.config_extra,
+            file_name: self.file_name,
+            placeholder: self.placeholder,
+        }
     }
 
     /// Parses an [`Overlay`] from a FAT and ROM.
     pub fn parse(overlay: &raw::Overlay, fat: &[FileAlloc], rom: &'a raw::Rom) -> Result<Self, RawHeaderError> {
+        if overlay.is_placeholder() {
+            return Ok(Self::placeholder(OverlayInfo::new(overlay)));
+        }
         let alloc = fat[overlay.file_id as usize];
         let data = &rom.data()[alloc.range()];
         Ok(Self {
-            originally_compressed: overlay.compressed.is_compressed() != 0,
+            originally_compressed: overlay.flags.is_compressed(),
             info: OverlayInfo::new(overlay),
             data: Cow::Borrowed(data),
+            config_extra: serde_yml::Mapping::new(),
+            file_name: None,
+            placeholder: false,
         })
     }
 
@@ -42,10 +94,10 @@ impl<'a> Overlay<'a> {
             ctor_start: self.ctor_start(),
             ctor_end: self.ctor_end(),
             file_id: self.file_id(),
-            compressed: if self.is_compressed() {
-                OverlayCompressedSize::new().with_size(self.data.len()).with_is_compressed(1)
+            flags: if self.is_compressed() {
+                self.info.flags.with_size(self.data.len()).with_is_compressed(true)
             } else {
-                OverlayCompressedSize::new().with_size(0).with_is_compressed(0)
+                self.info.flags.with_size(0).with_is_compressed(false)
             },
         }
     }
@@ -85,17 +137,103 @@ impl<'a> Overlay<'a> {
         self.info.ctor_end
     }
 
+    /// Returns the layout of the static initializer list between [`Self::ctor_start`] and [`Self::ctor_end`], see
+    /// [`CtorLayout::detect`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::ctor_functions`].
+    pub fn ctor_layout(&self) -> Result<CtorLayout, OverlayCtorError> {
+        Ok(CtorLayout::detect(&self.ctor_words()?))
+    }
+
+    /// Returns the static initializers of this [`Overlay`], in the order they are called. The list is decoded with the
+    /// given layout, or with the one returned by [`Self::ctor_layout`] if `None`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the overlay is compressed, if the .ctor section is misaligned or not within
+    /// the code of the overlay, or if a count-prefixed list has more entries than fit in the section.
+    pub fn ctor_functions(&self, layout: Option<CtorLayout>) -> Result<Vec<CtorEntry>, OverlayCtorError> {
+        let words = self.ctor_words()?;
+        let layout = layout.unwrap_or_else(|| CtorLayout::detect(&words));
+        let pointers = match layout {
+            CtorLayout::Terminated => &words[..],
+            CtorLayout::CountPrefixed => {
+                let Some((&count, pointers)) = words.split_first() else {
+                    return CtorCountTooLargeSnafu { count: 0u32, available: 0usize }.fail();
+                };
+                if count as usize > pointers.len() {
+                    return CtorCountTooLargeSnafu { count, available: pointers.len() }.fail();
+                }
+                &pointers[..count as usize]
+            }
+        };
+        Ok(pointers.iter().take_while(|&&pointer| pointer != 0).map(|&pointer| CtorEntry::new(pointer)).collect())
+    }
+
+    fn ctor_words(&self) -> Result<Vec<u32>, OverlayCtorError> {
+        if self.is_compressed() {
+            return CtorCompressedSnafu {}.fail();
+        }
+        let (start, end) = (self.ctor_start(), self.ctor_end());
+        if start == end {
+            return Ok(vec![]);
+        }
+        let base_address = self.base_address();
+        let code_end = base_address + self.code_size();
+        if start < base_address || start > end || end > code_end {
+            return CtorOutOfBoundsSnafu { start, end, base_address, code_end }.fail();
+        }
+        if start % 4 != 0 {
+            return CtorMisalignedSnafu { start }.fail();
+        }
+        let data = &self.data[(start - base_address) as usize..(end - base_address) as usize];
+        Ok(data.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect())
+    }
+
     /// Returns the file ID of this [`Overlay`].
     pub fn file_id(&self) -> u32 {
         self.info.file_id
     }
 
+    pub(crate) fn set_id(&mut self, id: u16) {
+        self.info.id = id as u32;
+    }
+
+    pub(crate) fn set_file_id(&mut self, file_id: u32) {
+        self.info.file_id = file_id;
+    }
+
+    /// Returns whether this [`Overlay`] is a placeholder entry in the overlay table, see [`raw::Overlay::is_placeholder`]. A
+    /// placeholder has no code and no file, and its entry is built as-is so that the overlay table keeps its length.
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+
     /// Returns whether this [`Overlay`] is compressed. See [`Self::originally_compressed`] for whether this overlay was
     /// compressed originally.
     pub fn is_compressed(&self) -> bool {
         self.info.compressed
     }
 
+    /// Returns whether this [`Overlay`] is flagged as signed in the overlay table. The signature itself is neither verified
+    /// nor regenerated, so the flag is preserved as-is when building.
+    pub fn is_signed(&self) -> bool {
+        self.info.flags.is_signed()
+    }
+
+    /// Returns the HMAC-SHA1 signature of this [`Overlay`], computed over its data as it is stored in the ROM. The data must
+    /// be compressed first if the overlay is compressed in the ROM.
+    pub fn compute_signature(&self, hmac: &HmacSha1) -> [u8; 20] {
+        hmac.compute(&self.data)
+    }
+
+    /// Returns whether `signature` is the HMAC-SHA1 signature of this [`Overlay`], see [`Self::compute_signature`].
+    pub fn verify_signature(&self, hmac: &HmacSha1, signature: &[u8; 20]) -> bool {
+        hmac.verify(&self.data, signature)
+    }
+
     /// Decompresses this [`Overlay`], but does nothing if already decompressed.
     pub fn decompress(&mut self) -> Result<(), Lz77DecompressError> {
         if !self.is_compressed() {
@@ -120,6 +258,12 @@ impl<'a> Overlay<'a> {
         Ok(())
     }
 
+    /// Replaces the data of this [`Overlay`] with `data`, which was compressed previously, such as by a cached build.
+    pub(crate) fn set_compressed_data(&mut self, data: Vec<u8>) {
+        self.data = data.into();
+        self.info.compressed = true;
+    }
+
     /// Returns a reference to the code of this [`Overlay`].
     pub fn code(&self) -> &[u8] {
         &self.data[..self.code_size() as usize]
@@ -130,6 +274,11 @@ impl<'a> Overlay<'a> {
         &self.data
     }
 
+    /// Returns where the data of this [`Overlay`] is stored, see [`FileSource`].
+    pub fn source(&self) -> FileSource {
+        FileSource::from(&self.data)
+    }
+
     /// Returns a reference to the info of this [`Overlay`].
     pub fn info(&self) -> &OverlayInfo {
         &self.info
@@ -139,6 +288,183 @@ impl<'a> Overlay<'a> {
     pub fn originally_compressed(&self) -> bool {
         self.originally_compressed
     }
+
+    /// Returns the unknown keys of this [`Overlay`]'s entry in the overlay config, see [`super::OverlayConfig::extra`].
+    pub fn config_extra(&self) -> &serde_yml::Mapping {
+        &self.config_extra
+    }
+
+    /// Sets the unknown keys to write to this [`Overlay`]'s entry in the overlay config.
+    pub fn set_config_extra(&mut self, config_extra: serde_yml::Mapping) {
+        self.config_extra = config_extra;
+    }
+
+    /// Returns the file name to save this [`Overlay`] as, or `None` to use [`Self::default_file_name`].
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Sets the file name to save this [`Overlay`] as, or `None` to use [`Self::default_file_name`].
+    pub fn set_file_name(&mut self, file_name: Option<String>) {
+        self.file_name = file_name;
+    }
+
+    /// Returns the default file name of an overlay, which is its ID padded with zeros to at least three digits, or to the
+    /// number of digits in `max_id` so that the names sort by ID.
+    pub fn default_file_name(id: u16, max_id: u16) -> String {
+        let width = max_id.to_string().len().max(3);
+        format!("ov{id:0width$}.bin")
+    }
+}
+
+/// Errors related to [`Overlay::ctor_functions`].
+#[derive(Debug, Snafu)]
+pub enum OverlayCtorError {
+    /// Occurs when reading static initializers from a compressed overlay.
+    #[snafu(display("overlay must be decompressed before reading its static initializers:\n{backtrace}"))]
+    CtorCompressed {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the .ctor section is not within the code of the overlay.
+    #[snafu(display(
+        ".ctor section {start:#x}..{end:#x} is not within the overlay code {base_address:#x}..{code_end:#x}:\n{backtrace}"
+    ))]
+    CtorOutOfBounds {
+        /// Start address of the .ctor section.
+        start: u32,
+        /// End address of the .ctor section.
+        end: u32,
+        /// Base address of the overlay.
+        base_address: u32,
+        /// End address of the overlay code.
+        code_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the .ctor section is not aligned to a word.
+    #[snafu(display(".ctor section at {start:#x} must be 4-aligned:\n{backtrace}"))]
+    CtorMisaligned {
+        /// Start address of the .ctor section.
+        start: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a count-prefixed static initializer list has more entries than fit in the .ctor section.
+    #[snafu(display("static initializer count {count} exceeds the {available} entries in the .ctor section:\n{backtrace}"))]
+    CtorCountTooLarge {
+        /// Count in the first word of the .ctor section.
+        count: u32,
+        /// Number of words after the count.
+        available: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`super::Rom::insert_overlay`] and [`super::Rom::remove_overlay`].
+#[derive(Debug, Snafu)]
+pub enum OverlayEditError {
+    /// Occurs when inserting an overlay past the end of the overlay list.
+    #[snafu(
+        visibility(pub(crate)),
+        display("cannot insert an overlay at index {index}, there are only {len} overlays:\n{backtrace}")
+    )]
+    IndexOutOfRange {
+        /// Index to insert at.
+        index: usize,
+        /// Number of overlays.
+        len: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when inserting an overlay without renumbering, but another overlay already has its ID.
+    #[snafu(visibility(pub(crate)), display("overlay ID {id} is already in use:\n{backtrace}"))]
+    DuplicateId {
+        /// Overlay ID.
+        id: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when removing an overlay ID which doesn't exist.
+    #[snafu(visibility(pub(crate)), display("there is no overlay with ID {id}:\n{backtrace}"))]
+    OverlayNotFound {
+        /// Overlay ID.
+        id: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the FAT has no room for the file of a new overlay.
+    #[snafu(visibility(pub(crate)), display("cannot add an overlay, there are no more file IDs:\n{backtrace}"))]
+    TooManyFileIds {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Layout of the static initializer list in the .ctor section of an [`Overlay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CtorLayout {
+    /// Function pointers which end at the first null pointer or at the end of the section.
+    Terminated,
+    /// The first word is the number of function pointers which follow it.
+    CountPrefixed,
+}
+
+impl CtorLayout {
+    /// Lowest address a static initializer can have. The ARM9 runs code from main RAM and ITCM, which are mapped above
+    /// this address, so a first word below it is not a function pointer.
+    pub const MIN_FUNCTION_ADDRESS: u32 = 0x1000000;
+
+    /// Detects the layout of a static initializer list. Which layout is used varies between SDK versions, but the version
+    /// in the build info doesn't reliably tell them apart, so the layout is detected from the list itself:
+    /// - If the first word is nonzero, below [`Self::MIN_FUNCTION_ADDRESS`] and at most the number of remaining words,
+    ///   it's a count, see [`Self::CountPrefixed`].
+    /// - Otherwise, the list is [`Self::Terminated`]. A null pointer ends it early, and any words after it are padding.
+    pub fn detect(words: &[u32]) -> Self {
+        match words.split_first() {
+            Some((&first, rest)) if first != 0 && first < Self::MIN_FUNCTION_ADDRESS && first as usize <= rest.len() => {
+                Self::CountPrefixed
+            }
+            _ => Self::Terminated,
+        }
+    }
+}
+
+impl Display for CtorLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Terminated => write!(f, "null-terminated"),
+            Self::CountPrefixed => write!(f, "count-prefixed"),
+        }
+    }
+}
+
+/// A static initializer in the .ctor section of an [`Overlay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CtorEntry {
+    /// Address of the function, with the Thumb bit cleared.
+    pub address: u32,
+    /// Whether the function is Thumb code, which is bit 0 of the function pointer.
+    pub thumb: bool,
+}
+
+impl CtorEntry {
+    /// Decodes a function pointer into a [`CtorEntry`].
+    pub fn new(pointer: u32) -> Self {
+        Self { address: pointer & !1, thumb: pointer & 1 != 0 }
+    }
+
+    /// Returns the function pointer as stored in the .ctor section.
+    pub fn pointer(&self) -> u32 {
+        self.address | self.thumb as u32
+    }
+}
+
+impl Display for CtorEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#010x} ({})", self.address, if self.thumb { "thumb" } else { "arm" })
+    }
 }
 
 /// Info of an [`Overlay`], similar to an entry in the overlay table.
@@ -158,8 +484,19 @@ pub struct OverlayInfo {
     pub ctor_end: u32,
     /// File ID for the FAT.
     pub file_id: u32,
-    /// Whether the overlay is compressed.
+    /// Whether the overlay is compressed. This is the only source of the compressed flag, see [`Self::flags`].
     pub compressed: bool,
+    /// Raw flags, used to preserve the signed and reserved bits. The compressed flag is always cleared here, as it's derived
+    /// from [`Self::compressed`] when building. The compressed size is kept to estimate the layout before compressing, and is
+    /// also set when building.
+    #[serde(default, deserialize_with = "deserialize_flags")]
+    pub flags: OverlayFlags,
+}
+
+/// Deserializes [`OverlayInfo::flags`], clearing the compressed flag so that it can't disagree with
+/// [`OverlayInfo::compressed`].
+fn deserialize_flags<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<OverlayFlags, D::Error> {
+    Ok(OverlayFlags::deserialize(deserializer)?.with_is_compressed(false))
 }
 
 impl OverlayInfo {
@@ -173,7 +510,8 @@ impl OverlayInfo {
             ctor_start: overlay.ctor_start,
             ctor_end: overlay.ctor_end,
             file_id: overlay.file_id,
-            compressed: overlay.compressed.is_compressed() != 0,
+            compressed: overlay.flags.is_compressed(),
+            flags: overlay.flags.with_is_compressed(false),
         }
     }
 }
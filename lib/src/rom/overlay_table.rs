@@ -0,0 +1,205 @@
+use std::fmt::Display;
+
+use snafu::{Backtrace, Snafu};
+
+use super::{
+    raw::{self, FileAlloc, RawOverlayError},
+    Overlay,
+};
+use crate::{crypto::hmac_sha1::HmacSha1, str::hex_string};
+
+/// A raw overlay table which is placed in the ROM as-is, instead of being built from each [`Overlay`].
+#[derive(Clone)]
+pub struct OverlayTable {
+    overlays: Vec<raw::Overlay>,
+}
+
+/// Errors related to [`OverlayTable`].
+#[derive(Debug, Snafu)]
+pub enum OverlayTableError {
+    /// See [`RawOverlayError`].
+    #[snafu(transparent)]
+    RawOverlay {
+        /// Source error.
+        source: RawOverlayError,
+    },
+    /// Occurs when the overlay table does not have one entry per overlay.
+    #[snafu(display("the overlay table has {actual} entries but there are {expected} overlays:\n{backtrace}"))]
+    CountMismatch {
+        /// Number of overlays.
+        expected: usize,
+        /// Number of overlay table entries.
+        actual: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a signature does not match the overlay table, see [`OverlayTable::check_signature`].
+    #[snafu(display("the overlay table signature is {actual} but the table is signed as {expected}:\n{backtrace}"))]
+    SignatureMismatch {
+        /// Signature computed from the table, in hexadecimal.
+        expected: String,
+        /// Provided signature, in hexadecimal.
+        actual: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+impl OverlayTable {
+    /// Creates an [`OverlayTable`] from raw bytes, such as the overlay table of a ROM.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input is not a multiple of the overlay table entry size.
+    pub fn from_raw_bytes(data: &[u8]) -> Result<Self, OverlayTableError> {
+        Ok(Self { overlays: raw::Overlay::read_from_slice(data)? })
+    }
+
+    /// Creates an [`OverlayTable`] from raw entries, such as those returned by [`raw::Rom::arm9_overlay_table`].
+    pub fn new(overlays: Vec<raw::Overlay>) -> Self {
+        Self { overlays }
+    }
+
+    /// Builds an [`OverlayTable`] from a list of overlays.
+    pub fn from_overlays(overlays: &[Overlay]) -> Self {
+        Self { overlays: overlays.iter().map(|overlay| overlay.build()).collect() }
+    }
+
+    /// Returns the raw bytes of this [`OverlayTable`].
+    pub fn as_raw_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.overlays)
+    }
+
+    /// Returns a reference to the entries of this [`OverlayTable`].
+    pub fn overlays(&self) -> &[raw::Overlay] {
+        &self.overlays
+    }
+
+    /// Returns the number of entries in this [`OverlayTable`].
+    pub fn len(&self) -> usize {
+        self.overlays.len()
+    }
+
+    /// Returns whether this [`OverlayTable`] has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.overlays.is_empty()
+    }
+
+    /// Appends an entry to this [`OverlayTable`].
+    pub fn push(&mut self, overlay: raw::Overlay) {
+        self.overlays.push(overlay);
+    }
+
+    /// Inserts an entry at `index`, shifting later entries back. The IDs of the entries are left as-is, see
+    /// [`Self::renumber`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than [`Self::len`].
+    pub fn insert(&mut self, index: usize, overlay: raw::Overlay) {
+        self.overlays.insert(index, overlay);
+    }
+
+    /// Removes the entry with the overlay ID `id` and returns it, or `None` if there is no such entry. The IDs of the
+    /// remaining entries are left as-is, see [`Self::renumber`].
+    pub fn remove(&mut self, id: u32) -> Option<raw::Overlay> {
+        let index = self.overlays.iter().position(|overlay| overlay.id == id)?;
+        Some(self.overlays.remove(index))
+    }
+
+    /// Sets the overlay ID of each entry to its index in this [`OverlayTable`].
+    pub fn renumber(&mut self) {
+        for (index, overlay) in self.overlays.iter_mut().enumerate() {
+            overlay.id = index as u32;
+        }
+    }
+
+    /// Checks that this [`OverlayTable`] has one entry per overlay.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the number of entries and overlays differ.
+    pub fn validate(&self, overlays: &[Overlay]) -> Result<(), OverlayTableError> {
+        if self.overlays.len() != overlays.len() {
+            return CountMismatchSnafu { expected: overlays.len(), actual: self.overlays.len() }.fail();
+        }
+        Ok(())
+    }
+
+    /// Returns the HMAC-SHA1 signature of the entries of this [`OverlayTable`].
+    pub fn compute_signature(&self, hmac: &HmacSha1) -> [u8; 20] {
+        hmac.compute(self.as_raw_bytes())
+    }
+
+    /// Returns whether `signature` is the HMAC-SHA1 signature of the entries of this [`OverlayTable`]. The signature is
+    /// not stored in the table, so it must be provided along with the key.
+    pub fn verify_signature(&self, hmac: &HmacSha1, signature: &[u8; 20]) -> bool {
+        hmac.verify(self.as_raw_bytes(), signature)
+    }
+
+    /// Checks that `signature` is the HMAC-SHA1 signature of the entries of this [`OverlayTable`], like
+    /// [`Self::verify_signature`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error with the expected signature if they differ, see [`Self::compute_signature`].
+    pub fn check_signature(&self, hmac: &HmacSha1, signature: &[u8; 20]) -> Result<(), OverlayTableError> {
+        let expected = self.compute_signature(hmac);
+        if expected != *signature {
+            return SignatureMismatchSnafu { expected: hex_string(&expected), actual: hex_string(signature) }.fail();
+        }
+        Ok(())
+    }
+
+    /// Creates a [`DisplayOverlayTable`] which implements [`Display`]. Each entry is shown along with the FAT entry of its
+    /// file, and inconsistencies between the two are flagged inline with `(!)`.
+    pub fn display_with_fat<'a>(&'a self, fat: &'a [FileAlloc], indent: usize) -> DisplayOverlayTable<'a> {
+        DisplayOverlayTable { table: self, fat, indent }
+    }
+}
+
+/// Can be used to display an [`OverlayTable`] cross-referenced with the FAT, see [`OverlayTable::display_with_fat`].
+pub struct DisplayOverlayTable<'a> {
+    table: &'a OverlayTable,
+    fat: &'a [FileAlloc],
+    indent: usize,
+}
+
+impl Display for DisplayOverlayTable<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = format!("{:indent$}", "", indent = self.indent + 2);
+        for (index, overlay) in self.table.overlays.iter().enumerate() {
+            writeln!(f, "{:indent$}Overlay {index}:", "", indent = self.indent)?;
+            write!(f, "{}", overlay.display(self.indent + 2))?;
+
+            let Some(alloc) = self.fat.get(overlay.file_id as usize) else {
+                writeln!(f, "{i}FAT entry ........ : (!) file ID is outside the FAT of {} entries", self.fat.len())?;
+                continue;
+            };
+            if alloc.start > alloc.end {
+                writeln!(f, "{i}FAT entry ........ : {:#x}..{:#x} (!) ends before it starts", alloc.start, alloc.end)?;
+                continue;
+            }
+            let length = alloc.end - alloc.start;
+            writeln!(f, "{i}FAT entry ........ : {:#x}..{:#x}", alloc.start, alloc.end)?;
+
+            // Compressed overlays store their compressed size in the flags, otherwise the file is the code as-is
+            let (declared_size, declared_by) = if overlay.flags.is_compressed() {
+                (overlay.flags.size() as u32, "compressed size")
+            } else {
+                (overlay.code_size, "code size")
+            };
+            write!(f, "{i}File length ...... : {length:#x}")?;
+            if length == declared_size {
+                writeln!(f, " (matches {declared_by})")?;
+            } else {
+                writeln!(f, " (!) differs from {declared_by} {declared_size:#x}")?;
+            }
+            if overlay.flags.is_compressed() && overlay.code_size > 0 {
+                let ratio = overlay.flags.size() as f64 / overlay.code_size as f64 * 100.0;
+                writeln!(f, "{i}Compressed ratio . : {ratio:.1}% of {:#x}", overlay.code_size)?;
+            }
+        }
+        Ok(())
+    }
+}
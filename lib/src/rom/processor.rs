@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Processor which runs a program or overlay.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Processor {
+    /// Main processor.
+    Arm9,
+    /// Sub processor.
+    Arm7,
+}
+
+impl Processor {
+    /// Returns the lowercase name of this [`Processor`], as used in paths such as `arm9_overlays`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Arm9 => "arm9",
+            Self::Arm7 => "arm7",
+        }
+    }
+}
+
+impl Display for Processor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Arm9 => write!(f, "ARM9"),
+            Self::Arm7 => write!(f, "ARM7"),
+        }
+    }
+}
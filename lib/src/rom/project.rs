@@ -0,0 +1,386 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+use snafu::Snafu;
+
+use super::{
+    raw::AutoloadInfo, AliasedFile, Arm7Offsets, Arm9BuildConfig, Banner, DsiProgramOffsets, Header, OverlayConfig, RomConfig,
+    RomConfigAutoload, SkippedFile,
+};
+use crate::io::{create_file, open_file, read_to_string, write_file, FileError};
+
+/// An inconsistency in an extracted project, found by [`check`].
+pub enum ProjectIssue {
+    /// A file referenced by a config does not exist.
+    MissingFile {
+        /// Config which references the file.
+        config: PathBuf,
+        /// Path to the missing file.
+        path: PathBuf,
+    },
+    /// A config could not be parsed.
+    InvalidConfig {
+        /// Path to the config.
+        path: PathBuf,
+        /// Parse error message.
+        message: String,
+    },
+    /// An overlay file listed in an overlays config does not exist.
+    MissingOverlayFile {
+        /// Path to the overlays config.
+        config: PathBuf,
+        /// Overlay ID.
+        id: u32,
+        /// Path to the missing overlay file.
+        path: PathBuf,
+    },
+    /// An overlay file does not have the code size from its config.
+    OverlaySizeMismatch {
+        /// Path to the overlay file.
+        path: PathBuf,
+        /// Code size in the overlays config.
+        expected: u32,
+        /// Actual file size.
+        actual: u64,
+    },
+    /// A binary file next to an overlays config is not listed in it.
+    UnlistedOverlayFile {
+        /// Path to the overlays config.
+        config: PathBuf,
+        /// Path to the unlisted file.
+        path: PathBuf,
+    },
+    /// The ARM9 overlay table binary does not have one entry per overlay in the overlays config.
+    OverlayCountMismatch {
+        /// Path to the overlay table binary.
+        path: PathBuf,
+        /// Number of overlays in the overlays config.
+        expected: usize,
+        /// Number of entries in the overlay table.
+        actual: usize,
+    },
+    /// A path in the path order file does not exist in the files directory.
+    UnresolvedPathOrder {
+        /// Path to the path order file.
+        path_order: PathBuf,
+        /// Line number, starting from 1.
+        line: usize,
+        /// The unresolved path.
+        path: String,
+    },
+}
+
+/// Errors related to [`fix`].
+#[derive(Debug, Snafu)]
+pub enum ProjectFixError {
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`serde_yml::Error`].
+    #[snafu(transparent)]
+    SerdeYml {
+        /// Source error.
+        source: serde_yml::Error,
+    },
+}
+
+impl ProjectIssue {
+    /// Returns whether [`fix`] can repair this issue.
+    pub fn is_fixable(&self) -> bool {
+        matches!(self, Self::MissingOverlayFile { .. } | Self::UnresolvedPathOrder { .. })
+    }
+}
+
+impl Display for ProjectIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingFile { config, path } => {
+                write!(f, "{}: file '{}' does not exist", config.display(), path.display())
+            }
+            Self::InvalidConfig { path, message } => write!(f, "{}: failed to parse: {message}", path.display()),
+            Self::MissingOverlayFile { config, id, path } => {
+                write!(f, "{}: file '{}' for overlay {id} does not exist", config.display(), path.display())
+            }
+            Self::OverlaySizeMismatch { path, expected, actual } => {
+                write!(f, "{}: file is {actual:#x} bytes but the overlay code size is {expected:#x}", path.display())
+            }
+            Self::UnlistedOverlayFile { config, path } => {
+                write!(f, "{}: file '{}' is not listed as an overlay", config.display(), path.display())
+            }
+            Self::OverlayCountMismatch { path, expected, actual } => {
+                write!(f, "{}: overlay table has {actual} entries but there are {expected} overlays", path.display())
+            }
+            Self::UnresolvedPathOrder { path_order, line, path } => {
+                write!(f, "{}:{line}: path '{path}' does not exist", path_order.display())
+            }
+        }
+    }
+}
+
+struct Checker<'a> {
+    root: &'a Path,
+    issues: Vec<ProjectIssue>,
+}
+
+impl<'a> Checker<'a> {
+    fn check_exists(&mut self, config: &Path, path: &Path) -> bool {
+        let exists = self.root.join(path).exists();
+        if !exists {
+            self.issues.push(ProjectIssue::MissingFile { config: config.to_path_buf(), path: path.to_path_buf() });
+        }
+        exists
+    }
+
+    fn parse<T: DeserializeOwned>(&mut self, config: &Path, path: &Path) -> Option<T> {
+        if !self.check_exists(config, path) {
+            return None;
+        }
+        let result = open_file(self.root.join(path))
+            .map_err(|e| e.to_string())
+            .and_then(|file| serde_yml::from_reader(file).map_err(|e| e.to_string()));
+        match result {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.issues.push(ProjectIssue::InvalidConfig { path: path.to_path_buf(), message });
+                None
+            }
+        }
+    }
+
+    fn check_autoload(&mut self, config: &Path, autoload: &RomConfigAutoload) {
+        self.check_exists(config, &autoload.bin);
+        self.parse::<AutoloadInfo>(config, &autoload.config);
+    }
+
+    fn check_overlays(&mut self, config: &Path, overlays_config: &Path) -> Option<usize> {
+        let overlays: Vec<OverlayConfig> = self.parse(config, overlays_config)?;
+        let overlays_dir = overlays_config.parent().unwrap_or(Path::new(""));
+
+        for overlay in overlays.iter().filter(|overlay| !overlay.placeholder) {
+            let path = overlays_dir.join(&overlay.file_name);
+            match self.root.join(&path).metadata() {
+                Ok(metadata) if metadata.len() != overlay.info.code_size as u64 => {
+                    self.issues.push(ProjectIssue::OverlaySizeMismatch {
+                        path,
+                        expected: overlay.info.code_size,
+                        actual: metadata.len(),
+                    });
+                }
+                Ok(_) => {}
+                Err(_) => self.issues.push(ProjectIssue::MissingOverlayFile {
+                    config: overlays_config.to_path_buf(),
+                    id: overlay.info.id,
+                    path,
+                }),
+            }
+        }
+
+        let listed = overlays.iter().map(|overlay| overlay.file_name.as_str()).collect::<HashSet<_>>();
+        if let Ok(entries) = self.root.join(overlays_dir).read_dir() {
+            let mut unlisted = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with("ov") && name.ends_with(".bin") && !listed.contains(name.as_str()))
+                .collect::<Vec<_>>();
+            unlisted.sort();
+            for name in unlisted {
+                self.issues.push(ProjectIssue::UnlistedOverlayFile {
+                    config: overlays_config.to_path_buf(),
+                    path: overlays_dir.join(name),
+                });
+            }
+        }
+
+        Some(overlays.len())
+    }
+
+    fn check_path_order(&mut self, config: &Path, rom_config: &RomConfig) {
+        if !self.check_exists(config, &rom_config.path_order) {
+            return;
+        }
+        let skipped_files = match &rom_config.skipped_files {
+            Some(skipped_files) => self.parse::<Vec<SkippedFile>>(config, skipped_files).unwrap_or_default(),
+            None => vec![],
+        };
+        let aliased_files = match &rom_config.aliased_files {
+            Some(aliased_files) => self.parse::<Vec<AliasedFile>>(config, aliased_files).unwrap_or_default(),
+            None => vec![],
+        };
+        let skipped_paths = skipped_files
+            .iter()
+            .map(|file| file.path.as_str())
+            .chain(aliased_files.iter().map(|file| file.path.as_str()))
+            .collect::<HashSet<_>>();
+
+        let Ok(path_order) = read_to_string(self.root.join(&rom_config.path_order)) else { return };
+        let files_dir = self.root.join(&rom_config.files_dir);
+        for (index, line) in path_order.lines().enumerate() {
+            let path = line.trim();
+            let relative = path.strip_prefix('/').unwrap_or(path);
+            if relative.is_empty() || skipped_paths.contains(relative) || files_dir.join(relative).exists() {
+                continue;
+            }
+            // Skipped files can be in skipped directories
+            if skipped_paths.iter().any(|skipped| skipped.strip_prefix(relative).is_some_and(|rest| rest.starts_with('/'))) {
+                continue;
+            }
+            self.issues.push(ProjectIssue::UnresolvedPathOrder {
+                path_order: rom_config.path_order.clone(),
+                line: index + 1,
+                path: path.to_string(),
+            });
+        }
+    }
+}
+
+/// Checks an extracted project for inconsistencies, such as missing files, configs that fail to parse, overlays that don't
+/// match their config, and path order entries that don't resolve. Paths in the returned issues are relative to the
+/// directory of `config_path`, except for `config_path` itself.
+pub fn check(config_path: &Path) -> Vec<ProjectIssue> {
+    let root = config_path.parent().unwrap_or(Path::new(""));
+    let mut checker = Checker { root, issues: vec![] };
+    let config = Path::new(config_path.file_name().unwrap_or_default());
+
+    let Some(rom_config) = checker.parse::<RomConfig>(Path::new(""), config) else {
+        return checker.issues;
+    };
+
+    checker.parse::<Header>(config, &rom_config.header);
+    checker.check_exists(config, &rom_config.header_logo);
+    if let Some(pre_arm9) = &rom_config.pre_arm9 {
+        checker.check_exists(config, pre_arm9);
+    }
+
+    checker.check_exists(config, &rom_config.arm9_bin);
+    checker.parse::<Arm9BuildConfig>(config, &rom_config.arm9_config);
+    checker.check_exists(config, &rom_config.arm7_bin);
+    checker.parse::<Arm7Offsets>(config, &rom_config.arm7_config);
+
+    checker.check_autoload(config, &rom_config.itcm);
+    checker.check_autoload(config, &rom_config.dtcm);
+    for autoload in &rom_config.unknown_autoloads {
+        checker.check_autoload(config, autoload);
+    }
+
+    let num_arm9_overlays =
+        rom_config.arm9_overlays.as_ref().and_then(|overlays_config| checker.check_overlays(config, overlays_config));
+    if let Some(arm9_ovt_bin) = &rom_config.arm9_ovt_bin {
+        if let Ok(metadata) = root.join(arm9_ovt_bin).metadata() {
+            let actual = metadata.len() as usize / size_of::<super::raw::Overlay>();
+            let expected = num_arm9_overlays.unwrap_or(0);
+            if actual != expected {
+                checker.issues.push(ProjectIssue::OverlayCountMismatch { path: arm9_ovt_bin.clone(), expected, actual });
+            }
+        } else {
+            checker.check_exists(config, arm9_ovt_bin);
+        }
+    }
+    if let Some(arm7_overlays) = &rom_config.arm7_overlays {
+        checker.check_overlays(config, arm7_overlays);
+    }
+    if let Some(dsi) = &rom_config.dsi {
+        checker.check_exists(config, &dsi.arm9i_bin);
+        checker.parse::<DsiProgramOffsets>(config, &dsi.arm9i_config);
+        checker.check_exists(config, &dsi.arm7i_bin);
+        checker.parse::<DsiProgramOffsets>(config, &dsi.arm7i_config);
+        checker.check_exists(config, &dsi.digest_sector_hashtable);
+        checker.check_exists(config, &dsi.digest_block_hashtable);
+    }
+
+    if let Some(banner) = &rom_config.banner {
+        checker.parse::<Banner>(config, banner);
+    }
+
+    if checker.check_exists(config, &rom_config.files_dir) {
+        checker.check_path_order(config, &rom_config);
+    }
+    if let Some(fnt_order) = &rom_config.fnt_order {
+        checker.check_exists(config, fnt_order);
+    }
+    if let Some(file_order) = &rom_config.file_order {
+        checker.check_exists(config, file_order);
+    }
+
+    checker.issues
+}
+
+/// Repairs the issues which are safe to repair, see [`ProjectIssue::is_fixable`]. Overlays with missing files are removed
+/// from their overlays config, and unresolved paths are removed from the path order file. Returns the number of repaired
+/// issues.
+///
+/// # Errors
+///
+/// This function will return an error if a config fails to be read or written.
+pub fn fix(config_path: &Path, issues: &[ProjectIssue]) -> Result<usize, ProjectFixError> {
+    let root = config_path.parent().unwrap_or(Path::new(""));
+    let mut num_fixed = 0;
+
+    let mut overlay_configs = issues
+        .iter()
+        .filter_map(|issue| match issue {
+            ProjectIssue::MissingOverlayFile { config, .. } => Some(config),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    overlay_configs.sort();
+    overlay_configs.dedup();
+    for overlays_config in overlay_configs {
+        let missing_ids = issues
+            .iter()
+            .filter_map(|issue| match issue {
+                ProjectIssue::MissingOverlayFile { config, id, .. } if config == overlays_config => Some(*id),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let mut overlays: Vec<OverlayConfig> = serde_yml::from_reader(open_file(root.join(overlays_config))?)?;
+        let num_overlays = overlays.len();
+        overlays.retain(|overlay| !missing_ids.contains(&overlay.info.id));
+        num_fixed += num_overlays - overlays.len();
+        log::info!(
+            "Removing {} overlays with missing files from {}",
+            num_overlays - overlays.len(),
+            overlays_config.display()
+        );
+        serde_yml::to_writer(create_file(root.join(overlays_config))?, &overlays)?;
+    }
+
+    let mut path_orders = issues
+        .iter()
+        .filter_map(|issue| match issue {
+            ProjectIssue::UnresolvedPathOrder { path_order, .. } => Some(path_order),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    path_orders.sort();
+    path_orders.dedup();
+    for path_order_path in path_orders {
+        let unresolved_lines = issues
+            .iter()
+            .filter_map(|issue| match issue {
+                ProjectIssue::UnresolvedPathOrder { path_order, line, .. } if path_order == path_order_path => Some(*line),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let path_order = read_to_string(root.join(path_order_path))?;
+        let mut contents = String::new();
+        for (index, line) in path_order.lines().enumerate() {
+            if unresolved_lines.contains(&(index + 1)) {
+                continue;
+            }
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        num_fixed += unresolved_lines.len();
+        log::info!("Removing {} unresolved paths from {}", unresolved_lines.len(), path_order_path.display());
+        write_file(root.join(path_order_path), contents)?;
+    }
+
+    Ok(num_fixed)
+}
@@ -0,0 +1,285 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Display, path::Path};
+
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+use super::{raw, Rom, RomConfig, RomHashReport, RomLoadOptions};
+use crate::{
+    crypto::sha1::Sha1,
+    io::{create_file, open_file, FileError},
+    str::hex_string,
+};
+
+/// Description of an extracted project for newcomers and tools, saved as [`Self::FILE_NAME`] next to the config when
+/// extracting. Building compares it to the current library version and options, see [`Self::check`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProjectManifest {
+    /// Version of ds-rom which extracted the project.
+    pub ds_rom_version: String,
+    /// Fingerprint of the original ROM.
+    pub rom: ProjectRomInfo,
+    /// Options in effect when extracting.
+    pub options: ProjectOptions,
+    /// Number of modules and files in the project.
+    pub counts: ProjectCounts,
+    /// Purpose of each file and directory in the project, by path relative to the project directory.
+    pub files: BTreeMap<String, String>,
+}
+
+/// Fingerprint of the original ROM, see [`ProjectManifest`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProjectRomInfo {
+    /// Game title in the header.
+    pub title: String,
+    /// Game code in the header.
+    pub gamecode: String,
+    /// Size of the ROM file.
+    pub size: u32,
+    /// SHA-1 of the whole ROM.
+    pub sha1: String,
+}
+
+/// Options in effect when extracting a project, see [`ProjectManifest`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectOptions {
+    /// Whether the ARM9 program was compressed, and so gets compressed again when building.
+    pub compress: bool,
+    /// Whether the ARM9 program was encrypted, and so gets encrypted again when building.
+    pub encrypt: bool,
+    /// Whether the exact order of every file was preserved in a file order list.
+    pub full_file_order: bool,
+    /// Whether the original FNT order was preserved because it wasn't sorted.
+    pub fnt_order: bool,
+    /// Whether some files were skipped and must be loaded from the original ROM when building.
+    pub skipped_files: bool,
+}
+
+/// Number of modules and files in a project, see [`ProjectManifest`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProjectCounts {
+    /// Number of ARM9 overlays.
+    pub arm9_overlays: usize,
+    /// Number of ARM7 overlays.
+    pub arm7_overlays: usize,
+    /// Number of files in the file system.
+    pub files: usize,
+    /// Total size of the files in the file system.
+    pub file_bytes: usize,
+}
+
+/// A difference found by [`ProjectManifest::check`] which may cause a rebuilt ROM to not match the original ROM.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProjectManifestWarning {
+    /// The project was extracted with a different version of ds-rom.
+    Version {
+        /// Version which extracted the project.
+        extracted: String,
+        /// Current version.
+        current: String,
+        /// How the extracting version compares to the current version, or `None` if either version couldn't be parsed.
+        ordering: Option<Ordering>,
+    },
+    /// An option is off when building but was on when extracting.
+    Option {
+        /// Name of the option.
+        name: String,
+    },
+}
+
+/// Errors related to [`ProjectManifest`].
+#[derive(Debug, Snafu)]
+pub enum ProjectManifestError {
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`serde_yml::Error`].
+    #[snafu(transparent)]
+    SerdeYml {
+        /// Source error.
+        source: serde_yml::Error,
+    },
+}
+
+impl ProjectManifest {
+    /// Version of this library, which is recorded in new manifests.
+    pub const CURRENT_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    /// File name of the manifest, placed next to the config.
+    pub const FILE_NAME: &'static str = "PROJECT.yaml";
+
+    /// Describes a project extracted from `raw_rom` into `rom`. The `config` is the one saved by [`Rom::save`], as it
+    /// lists files which are only known after saving, such as skipped files.
+    pub fn new(raw_rom: &raw::Rom, rom: &Rom, config: &RomConfig) -> Self {
+        let header = rom.header();
+        Self {
+            ds_rom_version: Self::CURRENT_VERSION.to_string(),
+            rom: ProjectRomInfo {
+                title: header.original.title.clone(),
+                gamecode: header.original.gamecode.to_string(),
+                size: raw_rom.data().len() as u32,
+                sha1: hex_string(&Sha1::digest(raw_rom.data())),
+            },
+            options: ProjectOptions {
+                compress: rom.arm9().originally_compressed(),
+                encrypt: rom.arm9().originally_encrypted(),
+                full_file_order: rom.file_order().is_some(),
+                fnt_order: config.fnt_order.is_some(),
+                skipped_files: config.skipped_files.is_some(),
+            },
+            counts: ProjectCounts {
+                arm9_overlays: rom.arm9_overlays().len(),
+                arm7_overlays: rom.arm7_overlays().len(),
+                files: rom.files().file_count(),
+                file_bytes: rom.files().total_content_size(),
+            },
+            files: Self::describe_files(config),
+        }
+    }
+
+    fn describe_files(config: &RomConfig) -> BTreeMap<String, String> {
+        let mut files = BTreeMap::new();
+        let mut describe = |path: &Path, purpose: &str| {
+            files.insert(path.to_string_lossy().replace('\\', "/"), purpose.to_string());
+        };
+        describe("config.yaml".as_ref(), "Main config, lists the paths below and ROM-wide settings");
+        describe(Self::FILE_NAME.as_ref(), "This manifest");
+        describe(RomHashReport::FILE_NAME.as_ref(), "Hashes of the original ROM, used to verify rebuilt ROMs");
+        describe(&config.header, "Header fields");
+        describe(&config.header_logo, "Logo shown when booting the game");
+        if let Some(pre_arm9) = &config.pre_arm9 {
+            describe(pre_arm9, "Data between the header and the ARM9 program");
+        }
+        if let Some(trailing_data) = &config.trailing_data {
+            describe(trailing_data, "Data after the end of the ROM, appended when building");
+        }
+        describe(&config.arm9_bin, "ARM9 main program, decompressed and decrypted");
+        describe(&config.arm9_config, "ARM9 addresses and build settings");
+        describe(&config.itcm.bin, "ITCM autoload block, loaded by the ARM9 program at boot");
+        describe(&config.itcm.config, "ITCM addresses");
+        describe(&config.dtcm.bin, "DTCM autoload block, loaded by the ARM9 program at boot");
+        describe(&config.dtcm.config, "DTCM addresses");
+        for autoload in &config.unknown_autoloads {
+            describe(&autoload.bin, "Autoload block of unknown purpose");
+            describe(&autoload.config, "Autoload block addresses");
+        }
+        if let Some(arm9_overlays) = &config.arm9_overlays {
+            describe(arm9_overlays, "ARM9 overlays and their settings, next to the overlay binaries");
+        }
+        if let Some(arm9_ovt_bin) = &config.arm9_ovt_bin {
+            describe(arm9_ovt_bin, "ARM9 overlay table used as-is");
+        }
+        describe(&config.arm7_bin, "ARM7 sub program");
+        describe(&config.arm7_config, "ARM7 addresses");
+        if let Some(arm7_overlays) = &config.arm7_overlays {
+            describe(arm7_overlays, "ARM7 overlays and their settings, next to the overlay binaries");
+        }
+        if let Some(dsi) = &config.dsi {
+            describe(&dsi.arm9i_bin, "ARM9i program, only loaded by DSi consoles");
+            describe(&dsi.arm9i_config, "ARM9i addresses");
+            describe(&dsi.arm7i_bin, "ARM7i program, only loaded by DSi consoles");
+            describe(&dsi.arm7i_config, "ARM7i addresses");
+            describe(&dsi.digest_sector_hashtable, "DSi digest sector hashtable used as-is");
+            describe(&dsi.digest_block_hashtable, "DSi digest block hashtable used as-is");
+        }
+        if let Some(banner) = &config.banner {
+            describe(banner, "Banner titles and icon settings, next to the icon images");
+        }
+        describe(&config.files_dir, "Files of the file system");
+        describe(&config.path_order, "Order of files and directories in the ROM");
+        if let Some(fnt_order) = &config.fnt_order {
+            describe(fnt_order, "Original order of names in the FNT, which was not sorted");
+        }
+        if let Some(file_order) = &config.file_order {
+            describe(file_order, "Exact order of every file in the ROM, overrides the path order");
+        }
+        if let Some(skipped_files) = &config.skipped_files {
+            describe(skipped_files, "Files which were not extracted and are loaded from the original ROM");
+        }
+        if let Some(aliased_files) = &config.aliased_files {
+            describe(aliased_files, "Files which refer to other sections of the ROM");
+        }
+        if let Some(compressed_files) = &config.compressed_files {
+            describe(compressed_files, "Files which were saved decompressed and are compressed again when building");
+        }
+        files
+    }
+
+    /// Compares this manifest to the current library version and the options used to load `rom`, and returns the
+    /// differences which may cause the rebuilt ROM to not match the original ROM.
+    pub fn check(&self, rom: &Rom, options: &RomLoadOptions) -> Vec<ProjectManifestWarning> {
+        let mut warnings = vec![];
+        if self.ds_rom_version != Self::CURRENT_VERSION {
+            warnings.push(ProjectManifestWarning::Version {
+                extracted: self.ds_rom_version.clone(),
+                current: Self::CURRENT_VERSION.to_string(),
+                ordering: compare_versions(&self.ds_rom_version, Self::CURRENT_VERSION),
+            });
+        }
+
+        let mut option = |extracted: bool, current: bool, name: &str| {
+            if extracted && !current {
+                warnings.push(ProjectManifestWarning::Option { name: name.to_string() });
+            }
+        };
+        option(self.options.compress, options.compress, "compress");
+        option(self.options.encrypt, options.encrypt, "encrypt");
+        option(self.options.full_file_order, rom.file_order().is_some(), "full_file_order");
+        option(self.options.fnt_order, rom.config().fnt_order.is_some(), "fnt_order");
+        option(self.options.skipped_files, options.original_rom.is_some(), "original_rom");
+        warnings
+    }
+
+    /// Loads a manifest from a YAML file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be opened or has an invalid format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ProjectManifestError> {
+        Ok(serde_yml::from_reader(open_file(path)?)?)
+    }
+
+    /// Saves this manifest to a YAML file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be created or serialization fails.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ProjectManifestError> {
+        serde_yml::to_writer(create_file(path)?, self)?;
+        Ok(())
+    }
+}
+
+/// Compares two `major.minor.patch` versions, ignoring pre-release and build suffixes. Returns `None` if either version
+/// can't be parsed.
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let parse = |version: &str| -> Option<[u32; 3]> {
+        let core = version.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(str::parse::<u32>);
+        let version = [parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?];
+        parts.next().is_none().then_some(version)
+    };
+    Some(parse(a)?.cmp(&parse(b)?))
+}
+
+impl Display for ProjectManifestWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Version { extracted, current, ordering } => {
+                let relation = match ordering {
+                    Some(Ordering::Less) => "an older",
+                    Some(Ordering::Greater) => "a newer",
+                    _ => "a different",
+                };
+                write!(
+                    f,
+                    "Project was extracted with {relation} version of ds-rom ({extracted}, current is {current}), the rebuilt ROM may not match"
+                )
+            }
+            Self::Option { name } => {
+                write!(f, "Option '{name}' was in effect when extracting but not now, the rebuilt ROM may not match")
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::mem::{align_of, size_of};
+use std::{
+    fmt::Display,
+    mem::{align_of, size_of},
+};
 
 use bytemuck::{Pod, PodCastError, Zeroable};
 use snafu::{Backtrace, Snafu};
@@ -12,7 +15,9 @@ pub struct Arm9Footer {
     nitrocode: u32,
     /// Offset to [super::BuildInfo].
     pub build_info_offset: u32,
-    reserved: u32,
+    /// Normally zero. Games which sign their overlays store the offset or address of the overlay signature table here, see
+    /// [`crate::rom::Arm9::detect_overlay_signatures`].
+    pub reserved: u32,
 }
 
 /// Errors related to [`Arm9Footer`].
@@ -115,4 +120,26 @@ impl Arm9Footer {
         footer.check_nitrocode()?;
         Ok(footer)
     }
+
+    /// Creates a [`DisplayArm9Footer`] which implements [`Display`].
+    pub fn display(&self, indent: usize) -> DisplayArm9Footer<'_> {
+        DisplayArm9Footer { footer: self, indent }
+    }
+}
+
+/// Can be used to display values in [`Arm9Footer`].
+pub struct DisplayArm9Footer<'a> {
+    footer: &'a Arm9Footer,
+    indent: usize,
+}
+
+impl<'a> Display for DisplayArm9Footer<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = format!("{:indent$}", "", indent = self.indent);
+        let footer = &self.footer;
+        writeln!(f, "{i}Nitrocode ........ : {:#010x}", footer.nitrocode)?;
+        writeln!(f, "{i}Build info offset  : {:#x}", footer.build_info_offset)?;
+        writeln!(f, "{i}Reserved ......... : {:#x}", footer.reserved)?;
+        Ok(())
+    }
 }
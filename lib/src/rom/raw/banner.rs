@@ -75,19 +75,16 @@ impl<'a> Banner<'a> {
         }
     }
 
-    /// Reinterprets a `&[u8]` as a reference to [`Banner`].
-    ///
-    /// # Errors
-    ///
-    /// This function will return an error if the input has an unknown banner version, or has the wrong size for its version,
-    /// or is not aligned enough.
-    pub fn borrow_from_slice(data: &'a [u8]) -> Result<Self, RawBannerError> {
+    fn check_data(data: &[u8]) -> Result<(BannerVersion, usize), RawBannerError> {
         let addr = data as *const [u8] as *const () as usize;
         if addr % 2 != 0 {
             return MisalignedSnafu { expected: 2usize, actual: 1usize << addr.trailing_zeros() as usize }.fail();
         }
 
-        let version_value = u16::from_le_bytes([data[0], data[1]]);
+        let Some(version_bytes) = data.get(0..2) else {
+            return InvalidSizeSnafu { version: 0u16, expected: 2usize, actual: data.len() }.fail();
+        };
+        let version_value = u16::from_le_bytes([version_bytes[0], version_bytes[1]]);
         let Some(version) = BannerVersion::from_u16(version_value) else {
             return UnknownVersionSnafu { version: version_value }.fail();
         };
@@ -95,17 +92,40 @@ impl<'a> Banner<'a> {
         if data.len() < size {
             return InvalidSizeSnafu { version: version_value, expected: size, actual: data.len() }.fail();
         }
-        let data = &data[..size];
+        Ok((version, size))
+    }
 
-        let mut bitmap = [0u8; 0x200];
-        bitmap.copy_from_slice(&data[0x20..0x220]);
+    /// Reinterprets a `&[u8]` as a reference to [`Banner`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input has an unknown banner version, or has the wrong size for its version,
+    /// or is not aligned enough.
+    pub fn borrow_from_slice(data: &'a [u8]) -> Result<Self, RawBannerError> {
+        let (version, size) = Self::check_data(data)?;
+        Ok(Self { version, data: Cow::Borrowed(&data[..size]) })
+    }
 
-        let mut palette = [0u16; 16];
-        for i in 0..16 {
-            palette[i] = u16::from_le_bytes([data[0x220 + i * 2], data[0x221 + i * 2]]);
-        }
+    /// Creates a [`Banner`] which owns its data. Any data past the banner size of its version is discarded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input has an unknown banner version, or has the wrong size for its version,
+    /// or is not aligned enough.
+    pub fn from_owned(mut data: Vec<u8>) -> Result<Banner<'static>, RawBannerError> {
+        let (version, size) = Self::check_data(&data)?;
+        data.truncate(size);
+        Ok(Banner { version, data: Cow::Owned(data) })
+    }
+
+    /// Returns a copy of this [`Banner`] which owns its data, so that it can outlive the data it was borrowed from.
+    pub fn to_owned(&self) -> Banner<'static> {
+        Banner { version: self.version, data: Cow::Owned(self.data.to_vec()) }
+    }
 
-        Ok(Self { version, data: Cow::Borrowed(data) })
+    /// Converts this [`Banner`] into one which owns its data. Data which is already owned is moved instead of copied.
+    pub fn into_owned(self) -> Banner<'static> {
+        Banner { version: self.version, data: Cow::Owned(self.data.into_owned()) }
     }
 
     /// Returns the version of this [`Banner`].
@@ -214,7 +234,8 @@ pub struct DisplayBanner<'a> {
 macro_rules! write_title {
     ($f:ident, $fmt:literal, $banner:ident, $language:expr) => {
         if let Some(title) = $banner.title($language) {
-            writeln!($f, $fmt, '\n', title, '\n')
+            let end = if title.is_valid_utf16() { "\n" } else { "\n(contains invalid UTF-16, replaced with U+FFFD)\n" };
+            writeln!($f, $fmt, '\n', title, end)
         } else {
             Ok(())
         }
@@ -117,6 +117,37 @@ impl BuildInfo {
         Ok(build_info)
     }
 
+    /// Reinterprets a `&[u8]` as a reference to [`BuildInfo`] like [`Self::borrow_from_slice`], but accepts a missing or
+    /// corrupted nitrocode, as found in some prototype dumps.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input is too small or not aligned enough.
+    pub fn borrow_from_slice_lenient(data: &'_ [u8]) -> Result<&'_ Self, RawBuildInfoError> {
+        let size = size_of::<Self>();
+        Self::check_size(data)?;
+        let addr = data as *const [u8] as *const () as usize;
+        Self::handle_pod_cast(bytemuck::try_from_bytes(&data[..size]), addr)
+    }
+
+    /// Reinterprets a `&mut [u8]` as a mutable reference to [`BuildInfo`] like [`Self::borrow_from_slice_mut`], but accepts
+    /// a missing or corrupted nitrocode, as found in some prototype dumps.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input is too small or not aligned enough.
+    pub fn borrow_from_slice_lenient_mut(data: &'_ mut [u8]) -> Result<&'_ mut Self, RawBuildInfoError> {
+        let size = size_of::<Self>();
+        Self::check_size(data)?;
+        let addr = data as *const [u8] as *const () as usize;
+        Self::handle_pod_cast(bytemuck::try_from_bytes_mut(&mut data[..size]), addr)
+    }
+
+    /// Returns whether this [`BuildInfo`] ends with a valid nitrocode.
+    pub fn has_nitrocode(&self) -> bool {
+        self.check_nitrocode().is_ok()
+    }
+
     /// Reinterprets a `&mut [u8]` as a mutable reference to [`BuildInfo`].
     ///
     /// # Errors
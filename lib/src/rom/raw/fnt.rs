@@ -63,6 +63,31 @@ pub enum RawFntError {
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
+    /// Occurs when the directory list is larger than the FNT, due to an invalid number of directories in the root entry.
+    #[snafu(display("{num_dirs} directories don't fit in the file name table of {fnt_size:#x} bytes:\n{backtrace}"))]
+    DirectoriesOutOfBounds {
+        /// Number of directories according to the root entry.
+        num_dirs: usize,
+        /// Size of the FNT.
+        fnt_size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a subtable offset points into the directory list or past the end of the FNT.
+    #[snafu(display(
+        "subtable offset {offset:#x} of directory {dir_index} is outside of the subtable data in the file name table of \
+        {fnt_size:#x} bytes:\n{backtrace}"
+    ))]
+    SubtableOutOfBounds {
+        /// Index of the directory in the directory list.
+        dir_index: usize,
+        /// Subtable offset of the directory.
+        offset: u32,
+        /// Size of the FNT.
+        fnt_size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 impl<'a> Fnt<'a> {
@@ -85,12 +110,17 @@ impl<'a> Fnt<'a> {
         }
     }
 
-    /// Reinterprets a `&[u8]` as an [`Fnt`].
+    /// Reinterprets a `&[u8]` as an [`Fnt`]. An empty input is a valid FNT without any directories, which some carts
+    /// without a file system have.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the input is too small or not aligned enough.
+    /// This function will return an error if the input is too small or not aligned enough, or if the directory list or a
+    /// subtable offset is out of bounds.
     pub fn borrow_from_slice(data: &'a [u8]) -> Result<Self, RawFntError> {
+        if data.is_empty() {
+            return Ok(Self { subtables: Box::new([]) });
+        }
         Self::check_size(data)?;
         let addr = data as *const [u8] as *const () as usize;
         if addr % 4 != 0 {
@@ -102,12 +132,30 @@ impl<'a> Fnt<'a> {
 
         // the root entry has no parent, so `parent_id` is instead the number of directories
         let num_dirs = root_dir.parent_id as usize;
-        let directories: &[FntDirectory] = Self::handle_pod_cast(bytemuck::try_cast_slice(&data[..size * num_dirs]));
+        let subtables_start = size * num_dirs;
+        if subtables_start > data.len() {
+            return DirectoriesOutOfBoundsSnafu { num_dirs, fnt_size: data.len() }.fail();
+        }
+        let directories: &[FntDirectory] = Self::handle_pod_cast(bytemuck::try_cast_slice(&data[..subtables_start]));
+
+        let subtable_range = subtables_start..data.len();
+        if let Some((dir_index, directory)) =
+            directories.iter().enumerate().find(|(_, dir)| !subtable_range.contains(&(dir.subtable_offset as usize)))
+        {
+            return SubtableOutOfBoundsSnafu { dir_index, offset: directory.subtable_offset, fnt_size: data.len() }.fail();
+        }
 
         let mut subtables = Vec::with_capacity(directories.len());
         for directory in directories {
             let start = directory.subtable_offset as usize;
-            subtables.push(FntSubtable { directory: Cow::Borrowed(directory), data: Cow::Borrowed(&data[start..]) });
+            // End at the next subtable, so that a missing terminator doesn't make this subtable include the next one
+            let end = directories
+                .iter()
+                .map(|dir| dir.subtable_offset as usize)
+                .filter(|&offset| offset > start)
+                .min()
+                .unwrap_or(data.len());
+            subtables.push(FntSubtable { directory: Cow::Borrowed(directory), data: Cow::Borrowed(&data[start..end]) });
         }
 
         Ok(Self { subtables: subtables.into_boxed_slice() })
@@ -167,11 +215,21 @@ impl<'a> Iterator for IterFntSubtable<'a> {
 
         let length = self.data[0] as usize & 0x7f;
         let subdir = self.data[0] & 0x80 != 0;
+        let entry_size = 1 + length + if subdir { size_of::<u16>() } else { 0 };
+        if self.data.len() < entry_size {
+            log::warn!("The FNT subtable ends in the middle of an entry, ignoring the remaining {} bytes", self.data.len());
+            self.data = &[];
+            return None;
+        }
         self.data = &self.data[1..];
 
-        let (name, _, had_errors) = SHIFT_JIS.decode(&self.data[..length]);
-        if had_errors {
-            log::warn!("The file name '{name}' contains a malformed byte sequence");
+        let raw_name = &self.data[..length];
+        let name = SHIFT_JIS.decode_without_bom_handling(raw_name).0;
+        if *SHIFT_JIS.encode(&name).0 != *raw_name {
+            log::warn!(
+                "The file name '{name}' can't be encoded back to its original bytes {raw_name:02x?}, so it will change when \
+                rebuilding"
+            );
         }
 
         self.data = &self.data[length..];
@@ -186,7 +244,7 @@ impl<'a> Iterator for IterFntSubtable<'a> {
             id
         };
 
-        Some(FntFile { id, name })
+        Some(FntFile { id, name, raw_name })
     }
 }
 
@@ -194,6 +252,8 @@ impl<'a> Iterator for IterFntSubtable<'a> {
 pub struct FntFile<'a> {
     /// File ID if less than `0xf000`, otherwise it's a directory ID.
     pub id: u16,
-    /// File/directory name.
+    /// File/directory name, decoded from Shift-JIS.
     pub name: Cow<'a, str>,
+    /// File/directory name as stored in the FNT, before decoding.
+    pub raw_name: &'a [u8],
 }
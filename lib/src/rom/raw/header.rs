@@ -1,6 +1,7 @@
 use std::{
     fmt::Display,
-    mem::{align_of, size_of},
+    mem::{align_of, offset_of, size_of},
+    ops::Range,
 };
 
 use bitfield_struct::bitfield;
@@ -9,6 +10,8 @@ use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, Snafu};
 
 use crate::{
+    crc::CRC_16_MODBUS,
+    crypto::modcrypt::Modcrypt,
     rom::Logo,
     str::{AsciiArray, BlobSize},
 };
@@ -232,7 +235,165 @@ pub enum RawHeaderError {
     },
 }
 
+macro_rules! field_offsets {
+    ($($field:ident),* $(,)?) => {
+        [$((stringify!($field), offset_of!(Header, $field))),*]
+    };
+}
+
+/// Name and offset of every field in [`Header`], in the order they appear.
+const FIELD_OFFSETS: [(&str, usize); 92] = field_offsets![
+    title,
+    gamecode,
+    makercode,
+    unitcode,
+    seed_select,
+    capacity,
+    reserved0,
+    dsi_flags,
+    ds_flags,
+    rom_version,
+    autostart,
+    arm9,
+    arm7,
+    file_names,
+    file_allocs,
+    arm9_overlays,
+    arm7_overlays,
+    normal_cmd_setting,
+    key1_cmd_setting,
+    banner_offset,
+    secure_area_crc,
+    secure_area_delay,
+    arm9_autoload_callback,
+    arm7_autoload_callback,
+    secure_area_disable,
+    rom_size_ds,
+    header_size,
+    arm9_build_info_offset,
+    arm7_build_info_offset,
+    ds_rom_region_end,
+    dsi_rom_region_end,
+    rom_nand_end,
+    rw_nand_end,
+    reserved1,
+    reserved2,
+    logo,
+    logo_crc,
+    header_crc,
+    debug_rom_offset,
+    debug_size,
+    debug_ram_addr,
+    reserved3,
+    reserved4,
+    memory_banks_wram,
+    memory_banks_arm9,
+    memory_banks_arm7,
+    memory_bank_9,
+    region_flags,
+    access_control,
+    arm7_scfg_ext7_setting,
+    dsi_flags_2,
+    arm9i,
+    arm7i,
+    digest_ds_area,
+    digest_dsi_area,
+    digest_sector_hashtable,
+    digest_block_hashtable,
+    digest_sector_size,
+    digest_sector_count,
+    banner_size,
+    sd_shared2_0000_size,
+    sd_shared2_0001_size,
+    eula_version,
+    use_ratings,
+    rom_size_dsi,
+    sd_shared2_0002_size,
+    sd_shared2_0003_size,
+    sd_shared2_0004_size,
+    sd_shared2_0005_size,
+    arm9i_build_info_offset,
+    arm7i_build_info_offset,
+    modcrypt_area_1,
+    modcrypt_area_2,
+    gamecode_rev,
+    file_type,
+    sd_public_sav_size,
+    sd_private_sav_size,
+    reserved5,
+    age_ratings,
+    sha1_hmac_arm9_with_secure_area,
+    sha1_hmac_arm7,
+    sha1_hmac_digest,
+    sha1_hmac_banner,
+    sha1_hmac_arm9i,
+    sha1_hmac_arm7i,
+    sha1_hmac_unk1,
+    sha1_hmac_unk2,
+    sha1_hmac_arm9,
+    reserved6,
+    debug_args,
+    rsa_sha1,
+    reserved7,
+];
+
 impl Header {
+    /// Returns the name and byte range of every field, in the order they appear in the header.
+    pub fn field_ranges() -> impl Iterator<Item = (&'static str, Range<usize>)> {
+        FIELD_OFFSETS.iter().enumerate().map(|(index, &(name, offset))| {
+            let end = FIELD_OFFSETS.get(index + 1).map_or(size_of::<Self>(), |&(_, next)| next);
+            (name, offset..end)
+        })
+    }
+
+    /// Zeroes every field which is not used by the given version, see [`HeaderVersion::uses_field`].
+    pub fn clear_unused_fields(&mut self, version: HeaderVersion) {
+        let bytes = bytemuck::bytes_of_mut(self);
+        for (_, offset, size, since) in FIELDS_SINCE_VERSION {
+            if version < since {
+                bytes[offset..offset + size].fill(0);
+            }
+        }
+    }
+
+    /// Returns the CRC of every field before [`Self::header_crc`].
+    pub fn compute_header_crc(&self) -> u16 {
+        CRC_16_MODBUS.checksum(&bytemuck::bytes_of(self)[0..offset_of!(Self, header_crc)])
+    }
+
+    /// Updates [`Self::logo_crc`] and [`Self::header_crc`] to match the current contents of this [`Header`]. The secure
+    /// area CRC is left as-is, since it requires the Blowfish key.
+    pub fn update_crcs(&mut self) {
+        self.logo_crc = CRC_16_MODBUS.checksum(&self.logo);
+        self.header_crc = self.compute_header_crc();
+    }
+
+    /// Returns whether the unit code declares a DSi program and the ROM has an ARM9i program, see
+    /// [`super::Rom::dsi_area`].
+    pub fn has_dsi_area(&self) -> bool {
+        self.unitcode & 0x2 != 0 && self.arm9i.size != 0
+    }
+
+    /// Returns the modcrypt areas which are in use, or an empty list if [`DsiFlags::modcrypted`] is not set. The key is the
+    /// first 16 bytes of this header if [`DsiFlags::modcrypt_debug_key`] is set, otherwise it's derived from the gamecode and
+    /// ARM9i SHA1-HMAC, see [`Modcrypt::retail_key`]. The counters of area 1 and 2 are the first 16 bytes of the ARM9 and
+    /// ARM7 SHA1-HMACs respectively.
+    pub fn modcrypt_areas(&self) -> Vec<ModcryptArea> {
+        if !self.dsi_flags.modcrypted() {
+            return vec![];
+        }
+        let key = if self.dsi_flags.modcrypt_debug_key() {
+            bytemuck::bytes_of(self)[..16].try_into().unwrap()
+        } else {
+            Modcrypt::retail_key(&self.gamecode.0, &self.sha1_hmac_arm9i)
+        };
+        [(self.modcrypt_area_1, &self.sha1_hmac_arm9_with_secure_area), (self.modcrypt_area_2, &self.sha1_hmac_arm7)]
+            .into_iter()
+            .filter(|(area, _)| area.size != 0)
+            .map(|(area, sha1_hmac)| ModcryptArea { area, modcrypt: Modcrypt::new(&key, sha1_hmac[..16].try_into().unwrap()) })
+            .collect()
+    }
+
     /// Returns the version of this [`Header`].
     pub fn version(&self) -> HeaderVersion {
         if self.dsi_flags_2.0 != 0 {
@@ -242,6 +403,53 @@ impl Header {
         }
     }
 
+    /// Returns the region indicated by the last character of the gamecode, if it's a known region.
+    pub fn gamecode_region(&self) -> Option<&'static str> {
+        match self.gamecode.0[3] {
+            b'A' => Some("All regions"),
+            b'C' => Some("China"),
+            b'D' => Some("Germany"),
+            b'E' => Some("USA"),
+            b'F' => Some("France"),
+            b'I' => Some("Italy"),
+            b'J' => Some("Japan"),
+            b'K' => Some("Korea"),
+            b'O' => Some("International"),
+            b'P' => Some("Europe"),
+            b'S' => Some("Spain"),
+            b'U' => Some("Australia"),
+            _ => None,
+        }
+    }
+
+    /// Returns whether `other` looks like a build of the same game: its header CRC is valid, and the title, gamecode,
+    /// makercode and unitcode match this header. An original ROM is a build of the same game too, so this is not enough to
+    /// tell whether a file is safe to overwrite, see [`crate::io::is_previous_build`].
+    pub fn same_game(&self, other: &Header) -> bool {
+        other.header_crc == other.compute_header_crc()
+            && self.title.0 == other.title.0
+            && self.gamecode.0 == other.gamecode.0
+            && self.makercode.0 == other.makercode.0
+            && self.unitcode == other.unitcode
+    }
+
+    /// Returns a description of how the China and Korea flags in [`Self::ds_flags`] disagree with the gamecode region, if
+    /// they do. iQue DS titles have a China gamecode and the China flag set.
+    pub fn region_mismatch(&self) -> Option<String> {
+        let region = self.gamecode.0[3];
+        for (flag, code, name) in
+            [(self.ds_flags.china_region(), b'C', "China"), (self.ds_flags.korea_region(), b'K', "Korea")]
+        {
+            if flag && region != code {
+                return Some(format!("{name} flag is set, but the gamecode region is not {name}"));
+            }
+            if !flag && region == code {
+                return Some(format!("gamecode region is {name}, but the {name} flag is not set"));
+            }
+        }
+        None
+    }
+
     fn check_size(data: &'_ [u8]) -> Result<(), RawHeaderError> {
         let size = size_of::<Self>();
         if data.len() < size {
@@ -309,6 +517,11 @@ impl<'a> Display for DisplayHeader<'a> {
         writeln!(f, "{i}Makercode ............... : {}", header.makercode)?;
         writeln!(f, "{i}Unitcode ................ : {}", header.unitcode)?;
         writeln!(f, "{i}DS flags ................ : {}", header.ds_flags)?;
+        write!(f, "{i}Region .................. : {}", header.gamecode_region().unwrap_or("Unknown"))?;
+        if let Some(mismatch) = header.region_mismatch() {
+            write!(f, " ({mismatch})")?;
+        }
+        writeln!(f)?;
         writeln!(f, "{i}DSi flags ............... : {}", header.dsi_flags)?;
         writeln!(f, "{i}Capacity ................ : {}", header.capacity)?;
         writeln!(f, "{i}ROM size (DS) ........... : {} ({:#x})", BlobSize(header.rom_size_ds as usize), header.rom_size_ds)?;
@@ -357,6 +570,32 @@ pub enum HeaderVersion {
     DsPostDsi,
 }
 
+/// Fields which are only used since a later [`HeaderVersion`], as `(name, offset, size, version)`. Fields not listed here are
+/// used by every version.
+const FIELDS_SINCE_VERSION: [(&str, usize, usize, HeaderVersion); 5] = [
+    ("dsi_flags_2", offset_of!(Header, dsi_flags_2), size_of::<DsiFlags2>(), HeaderVersion::DsPostDsi),
+    ("sha1_hmac_banner", offset_of!(Header, sha1_hmac_banner), 0x14, HeaderVersion::DsPostDsi),
+    ("sha1_hmac_unk1", offset_of!(Header, sha1_hmac_unk1), 0x14, HeaderVersion::DsPostDsi),
+    ("sha1_hmac_unk2", offset_of!(Header, sha1_hmac_unk2), 0x14, HeaderVersion::DsPostDsi),
+    ("rsa_sha1", offset_of!(Header, rsa_sha1), 0x80, HeaderVersion::DsPostDsi),
+];
+
+impl HeaderVersion {
+    /// Returns whether this version uses the given raw header field. Fields from later versions are zero in older headers.
+    pub fn uses_field(self, field: &str) -> bool {
+        FIELDS_SINCE_VERSION.iter().find(|(name, _, _, _)| *name == field).is_none_or(|(_, _, _, since)| self >= *since)
+    }
+
+    /// Returns the number of bytes at the start of the header which are used by this version. The rest of the header is
+    /// reserved and normally zero.
+    pub fn used_size(self) -> usize {
+        match self {
+            HeaderVersion::Original => 0x200,
+            HeaderVersion::DsPostDsi => 0x1000,
+        }
+    }
+}
+
 impl Display for HeaderVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -366,8 +605,9 @@ impl Display for HeaderVersion {
     }
 }
 
-/// ROM capacity.
-#[derive(Clone, Copy)]
+/// ROM capacity. Serialized as the raw value, see the [`Display`] implementation for the size it represents.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Capacity(pub u8);
 
 impl Capacity {
@@ -376,6 +616,11 @@ impl Capacity {
         let bits = 32 - size.leading_zeros() as u8;
         Self(bits.saturating_sub(17))
     }
+
+    /// Returns the size of this capacity in bytes.
+    pub fn size(&self) -> u64 {
+        0x20000u64.checked_shl(self.0 as u32).unwrap_or(u64::MAX)
+    }
 }
 
 impl Display for Capacity {
@@ -389,15 +634,16 @@ impl Display for Capacity {
 
 /// DSi-specific flags.
 #[bitfield(u8)]
+#[derive(Serialize, Deserialize)]
 pub struct DsiFlags {
     /// If `true`, the ROM has a DSi area.
-    dsi_title: bool,
+    pub dsi_title: bool,
     /// If `true`, the ROM is modcrypted.
-    modcrypted: bool,
+    pub modcrypted: bool,
     /// If `true`, use debug key, otherwise retail key.
-    modcrypt_debug_key: bool,
+    pub modcrypt_debug_key: bool,
     /// Disable debug?
-    disable_debug: bool,
+    pub disable_debug: bool,
     /// Reserved, zero.
     #[bits(4)]
     reserved: u8,
@@ -443,9 +689,9 @@ pub struct DsFlags {
     #[bits(4)]
     reserved: u8,
     /// Released in Korea if `true`.
-    korea_region: bool,
+    pub korea_region: bool,
     /// Released in China if `true`.
-    china_region: bool,
+    pub china_region: bool,
 }
 
 impl Display for DsFlags {
@@ -504,7 +750,7 @@ impl<'a> Display for DisplayProgramOffset<'a> {
 
 /// Offset to a table in the ROM.
 #[repr(C)]
-#[derive(Clone, Copy, Zeroable, Pod, Default)]
+#[derive(Clone, Copy, Zeroable, Pod, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TableOffset {
     /// ROM offset to start of table.
     pub offset: u32,
@@ -519,6 +765,36 @@ impl TableOffset {
     }
 }
 
+/// An area of a DSi ROM which is encrypted with modcrypt, see [`Header::modcrypt_areas`].
+#[derive(Clone)]
+pub struct ModcryptArea {
+    /// ROM offset and size of the area.
+    pub area: TableOffset,
+    /// Keystream of the area.
+    pub modcrypt: Modcrypt,
+}
+
+impl ModcryptArea {
+    /// Returns whether `size` bytes starting at the ROM offset `offset` overlap this area.
+    pub fn overlaps(&self, offset: u32, size: usize) -> bool {
+        let (start, end) = (offset as u64, offset as u64 + size as u64);
+        let (area_start, area_end) = (self.area.offset as u64, self.area.offset as u64 + self.area.size as u64);
+        start < area_end && area_start < end
+    }
+
+    /// De/encrypts the part of `data` which is inside this area, where `data` starts at the ROM offset `offset`. The rest of
+    /// `data` is left as-is.
+    pub fn apply(&self, data: &mut [u8], offset: u32) {
+        if !self.overlaps(offset, data.len()) {
+            return;
+        }
+        let start = self.area.offset.saturating_sub(offset) as usize;
+        let end = ((self.area.offset as u64 + self.area.size as u64 - offset as u64) as usize).min(data.len());
+        let area_offset = (offset as usize + start) - self.area.offset as usize;
+        self.modcrypt.apply(&mut data[start..end], area_offset);
+    }
+}
+
 /// Can be used to display values inside [`TableOffset`].
 pub struct DisplayTableOffset<'a> {
     offset: &'a TableOffset,
@@ -547,6 +823,7 @@ impl Display for Delay {
 
 /// Region flags, only used in DSi titles.
 #[bitfield(u32)]
+#[derive(Serialize, Deserialize)]
 pub struct RegionFlags {
     japan: bool,
     usa: bool,
@@ -579,6 +856,7 @@ impl Display for RegionFlags {
 
 /// Access control flags.
 #[bitfield(u32)]
+#[derive(Serialize, Deserialize)]
 pub struct AccessControl {
     common_client_key: bool,
     aes_slot_b: bool,
@@ -607,17 +885,17 @@ pub struct AccessControl {
 #[derive(Serialize, Deserialize)]
 pub struct DsiFlags2 {
     /// Touchscreen/Sound Controller (TSC) in DSi (true) or DS (false) mode
-    tsc_dsi_mode: bool,
-    require_eula_agreement: bool,
+    pub tsc_dsi_mode: bool,
+    pub require_eula_agreement: bool,
     /// If true, use banner.sav to override default banner icon
-    dynamic_icon: bool,
+    pub dynamic_icon: bool,
     /// If true, show Wi-Fi Connection icon in launcher
-    launcher_wfc_icon: bool,
+    pub launcher_wfc_icon: bool,
     /// If true, show DS Wireless icon in launcher
-    launcher_wireless_icon: bool,
-    has_icon_sha1: bool,
-    has_header_rsa: bool,
-    developer_app: bool,
+    pub launcher_wireless_icon: bool,
+    pub has_icon_sha1: bool,
+    pub has_header_rsa: bool,
+    pub developer_app: bool,
     #[bits(24)]
     reserved: u32,
 }
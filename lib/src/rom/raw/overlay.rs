@@ -6,6 +6,7 @@ use std::{
 
 use bitfield_struct::bitfield;
 use bytemuck::{Pod, PodCastError, Zeroable};
+use serde::{de, Deserialize, Serialize};
 use snafu::{Backtrace, Snafu};
 
 use super::RawHeaderError;
@@ -28,8 +29,8 @@ pub struct Overlay {
     pub ctor_end: u32,
     /// File ID for the FAT.
     pub file_id: u32,
-    /// Compressed size.
-    pub compressed: OverlayCompressedSize,
+    /// Flags and compressed size.
+    pub flags: OverlayFlags,
 }
 
 /// Errors related to [`Overlay`].
@@ -57,6 +58,31 @@ pub enum RawOverlayError {
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
+    /// Occurs when the ARM9 overlay table is stored inside the compressed ARM9 program, so it can't be read from the ROM
+    /// directly. Use [`super::Rom::arm9_overlay_table_with`] with the decompressed ARM9 program instead.
+    #[snafu(visibility(pub(crate)), display(
+        "the ARM9 overlay table at {offset:#x} is inside the compressed ARM9 program, decompress it and use Rom::arm9_overlay_table_with:\n{backtrace}"
+    ))]
+    InsideCompressedArm9 {
+        /// ROM offset of the overlay table.
+        offset: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the ARM9 overlay table starts inside the ARM9 program but ends past it.
+    #[snafu(visibility(pub(crate)), display(
+        "the ARM9 overlay table {offset:#x}..{end:#x} goes past the end of the ARM9 program at {arm9_end:#x}:\n{backtrace}"
+    ))]
+    OutOfArm9Bounds {
+        /// ROM offset of the overlay table.
+        offset: u32,
+        /// ROM offset to the end of the overlay table.
+        end: u32,
+        /// ROM offset to the end of the ARM9 program, or the decompressed ARM9 program if given.
+        arm9_end: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 impl Overlay {
@@ -92,6 +118,36 @@ impl Overlay {
         Self::handle_pod_cast(bytemuck::try_cast_slice(&data), addr)
     }
 
+    /// Copies a `&[u8]` into a list of [`Overlay`]s. Unlike [`Self::borrow_from_slice`], the input doesn't need to be
+    /// aligned.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the input is the wrong size.
+    pub fn read_from_slice(data: &[u8]) -> Result<Vec<Self>, RawOverlayError> {
+        Self::check_size(data)?;
+        Ok(data.chunks_exact(size_of::<Self>()).map(bytemuck::pod_read_unaligned).collect())
+    }
+
+    /// Returns whether this entry is a placeholder, such as padding added by the linker. Every field besides the ID is zero,
+    /// so its file ID refers to FAT entry 0 even though the entry has no file of its own.
+    pub fn is_placeholder(&self) -> bool {
+        self.base_addr == 0
+            && self.code_size == 0
+            && self.bss_size == 0
+            && self.ctor_start == 0
+            && self.ctor_end == 0
+            && self.file_id == 0
+            && self.flags.0 == 0
+    }
+
+    /// Returns the flags and compressed size of this entry. The field was renamed to [`Self::flags`] when the signed and
+    /// reserved bits were added.
+    #[deprecated(note = "use the `flags` field instead")]
+    pub fn compressed(&self) -> OverlayFlags {
+        self.flags
+    }
+
     /// Creates a [`DisplayOverlay`] which implements [`Display`].
     pub fn display(&self, indent: usize) -> DisplayOverlay {
         DisplayOverlay { overlay: self, indent }
@@ -115,20 +171,74 @@ impl<'a> Display for DisplayOverlay<'a> {
         writeln!(f, "{i}.bss size ........ : {:#x}", overlay.bss_size)?;
         writeln!(f, "{i}.ctor start ...... : {:#x}", overlay.ctor_start)?;
         writeln!(f, "{i}.ctor end ........ : {:#x}", overlay.ctor_end)?;
-        writeln!(f, "{i}Compressed size .. : {:#x}", overlay.compressed.size())?;
-        writeln!(f, "{i}Is compressed .... : {}", overlay.compressed.is_compressed() != 0)?;
+        writeln!(f, "{i}Flags ............ : {:#010x} ({})", overlay.flags.0, overlay.flags)?;
         Ok(())
     }
 }
 
-/// Overlay compressed size bitfield.
+/// Former name of [`OverlayFlags`], from before the signed and reserved bits were added. Note that
+/// [`OverlayFlags::is_compressed`] is now a `bool` instead of a `u8`.
+#[deprecated(note = "renamed to `OverlayFlags`")]
+pub type OverlayCompressedSize = OverlayFlags;
+
+/// Overlay flags bitfield, also containing the compressed size.
 #[bitfield(u32)]
-pub struct OverlayCompressedSize {
+pub struct OverlayFlags {
     /// Compressed size, zero if not compressed.
     #[bits(24)]
     pub size: usize,
-    pub is_compressed: u8,
+    /// Whether the overlay is compressed.
+    pub is_compressed: bool,
+    /// Whether the overlay is signed.
+    pub is_signed: bool,
+    /// Reserved, zero.
+    #[bits(6)]
+    pub reserved: u8,
 }
 
-unsafe impl Zeroable for OverlayCompressedSize {}
-unsafe impl Pod for OverlayCompressedSize {}
+unsafe impl Zeroable for OverlayFlags {}
+unsafe impl Pod for OverlayFlags {}
+
+impl Display for OverlayFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut flags = vec![];
+        if self.is_compressed() {
+            flags.push(format!("compressed (size {:#x})", self.size()));
+        } else if self.size() != 0 {
+            flags.push(format!("size {:#x}", self.size()));
+        }
+        if self.is_signed() {
+            flags.push("signed".to_string());
+        }
+        if self.reserved() != 0 {
+            flags.push(format!("reserved {:#x}", self.reserved()));
+        }
+
+        if flags.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", flags.join(", "))
+        }
+    }
+}
+
+impl Serialize for OverlayFlags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&format!("{:#010x}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for OverlayFlags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string: String = Deserialize::deserialize(deserializer)?;
+        let hex = string.strip_prefix("0x").unwrap_or(&string);
+        let value = u32::from_str_radix(hex, 16).map_err(de::Error::custom)?;
+        Ok(Self(value))
+    }
+}
@@ -1,16 +1,23 @@
-use std::{borrow::Cow, io::Read, mem::size_of, path::Path};
+use std::{borrow::Cow, collections::HashMap, fmt::Display, mem::size_of, path::Path};
 
-use snafu::Snafu;
+use snafu::{Backtrace, Snafu};
 
 use super::{
-    Arm9Footer, Arm9FooterError, Banner, FileAlloc, Fnt, Header, Overlay, RawBannerError, RawBuildInfoError, RawFatError,
-    RawFntError, RawHeaderError, RawOverlayError,
+    Arm9Footer, Arm9FooterError, AutoloadKind, Banner, FileAlloc, Fnt, Header, InsideCompressedArm9Snafu,
+    OutOfArm9BoundsSnafu, Overlay, ProgramOffset, RawBannerError, RawBuildInfoError, RawFatError, RawFntError, RawHeaderError,
+    RawOverlayError,
 };
 use crate::{
-    io::{open_file, write_file, FileError},
-    rom::{Arm7, Arm7Offsets, Arm9, Arm9Offsets},
+    compress::lz77::Lz77,
+    io::{read_file, write_file_atomic, FileError},
+    rom::{
+        Arm7, Arm7Offsets, Arm9, Arm9AutoloadError, Arm9Error, Arm9Location, Arm9Offsets, DsiArea, DsiProgram,
+        DsiProgramOffsets, FileParseError, FileSystem, Processor,
+    },
 };
 
+const LZ77: Lz77 = Lz77 {};
+
 /// A raw DS ROM, see the plain struct [here](super::super::Rom).
 pub struct Rom<'a> {
     data: Cow<'a, [u8]>,
@@ -39,23 +46,315 @@ pub enum RawArm9Error {
     },
 }
 
+/// Errors related to [`Rom::dsi_area`].
+#[derive(Debug, Snafu)]
+pub enum RawDsiError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// Occurs when a section of the DSi area ends past the end of the ROM, such as in a trimmed dump.
+    #[snafu(display("the {section} ends at {end:#x}, past the end of the ROM at {rom_size:#x}:\n{backtrace}"))]
+    DsiSectionOutOfBounds {
+        /// Name of the section.
+        section: &'static str,
+        /// End offset of the section.
+        end: usize,
+        /// Size of the ROM.
+        rom_size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`Rom::decrypt_modcrypt`] and [`Rom::encrypt_modcrypt`].
+#[derive(Debug, Snafu)]
+pub enum RawModcryptError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// Occurs when a modcrypt area ends past the end of the ROM.
+    #[snafu(display("modcrypt area {offset:#x}..{end:#x} ends past the end of the ROM at {rom_size:#x}:\n{backtrace}"))]
+    ModcryptAreaOutOfBounds {
+        /// Start offset of the area.
+        offset: u32,
+        /// End offset of the area.
+        end: u64,
+        /// Size of the ROM.
+        rom_size: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// What a ROM offset points to, see [`Rom::locate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Located {
+    /// A section which is not in the FAT, such as the header, a program or the banner.
+    Section {
+        /// Name of the section.
+        name: String,
+        /// Offset relative to the start of the section.
+        offset: u32,
+    },
+    /// A file or overlay in the FAT.
+    File {
+        /// FAT index of the file.
+        id: u16,
+        /// Path of the file in the FNT, if it has one.
+        path: Option<String>,
+        /// Name of the overlay, if the file is an overlay.
+        overlay: Option<String>,
+        /// Offset relative to the start of the file.
+        offset: u32,
+    },
+    /// Padding or unused space between sections and files.
+    Gap {
+        /// Name of the closest section or file before the offset, if any.
+        previous: Option<String>,
+        /// Name of the closest section or file after the offset, if any.
+        next: Option<String>,
+        /// Offset relative to the end of the previous section or file, or the start of the ROM.
+        offset: u32,
+    },
+    /// Past the end of the ROM.
+    PastEnd {
+        /// Size of the ROM.
+        size: u32,
+    },
+}
+
+impl Display for Located {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Located::Section { name, offset } => write!(f, "{name} (+{offset:#x})"),
+            Located::File { id, path: Some(path), offset, .. } => write!(f, "file {path} (+{offset:#x}), FAT id {id:#x}"),
+            Located::File { id, overlay: Some(overlay), offset, .. } => {
+                write!(f, "{overlay} (+{offset:#x}), FAT id {id:#x}")
+            }
+            Located::File { id, offset, .. } => write!(f, "unnamed file (+{offset:#x}), FAT id {id:#x}"),
+            Located::Gap { previous, next, offset } => {
+                write!(f, "padding")?;
+                if let Some(previous) = previous {
+                    write!(f, " after {previous} (+{offset:#x})")?;
+                }
+                match (previous, next) {
+                    (Some(_), Some(next)) => write!(f, ", before {next}"),
+                    (None, Some(next)) => write!(f, " before {next}"),
+                    (_, None) => write!(f, " at the end of the ROM"),
+                }
+            }
+            Located::PastEnd { size } => write!(f, "past the end of the ROM, which is {size:#x} bytes"),
+        }
+    }
+}
+
+/// Errors related to [`Rom::locate`].
+#[derive(Debug, Snafu)]
+pub enum RawLocateError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`RawOverlayError`].
+    #[snafu(transparent)]
+    RawOverlay {
+        /// Source error.
+        source: RawOverlayError,
+    },
+    /// See [`RawFntError`].
+    #[snafu(transparent)]
+    RawFnt {
+        /// Source error.
+        source: RawFntError,
+    },
+    /// See [`RawFatError`].
+    #[snafu(transparent)]
+    RawFat {
+        /// Source error.
+        source: RawFatError,
+    },
+    /// See [`RawBannerError`].
+    #[snafu(transparent)]
+    RawBanner {
+        /// Source error.
+        source: RawBannerError,
+    },
+    /// See [`FileParseError`].
+    #[snafu(transparent)]
+    FileParse {
+        /// Source error.
+        source: FileParseError,
+    },
+}
+
+/// A module loaded into the ARM9 address space, see [`VaddrLocation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaddrModule {
+    /// The main ARM9 code, before the autoload blocks.
+    Arm9Main,
+    /// The code of an autoload block in the ARM9 program.
+    Autoload {
+        /// Index of the autoload block, see [`Arm9::autoloads`].
+        index: usize,
+        /// Kind of autoload.
+        kind: AutoloadKind,
+    },
+    /// An ARM9 overlay.
+    Overlay {
+        /// Overlay ID.
+        id: u16,
+        /// FAT index of the overlay file.
+        file_id: u32,
+    },
+}
+
+/// Where a virtual address in the ARM9 address space is stored in the ROM, see [`Rom::vaddr_to_rom_offset`] and
+/// [`Rom::rom_offset_to_vaddr`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VaddrLocation {
+    /// Virtual address.
+    pub address: u32,
+    /// Module which is loaded to the address.
+    pub module: VaddrModule,
+    /// Offset from the base address of the module.
+    pub offset: u32,
+    /// ROM offset of the byte loaded to the address, or `None` if no byte is stored for it, either because the address is
+    /// in uninitialized data or because it's in the compressed part of the module.
+    pub rom_offset: Option<u32>,
+    /// Whether the address is in uninitialized data (.bss), which has no stored bytes.
+    pub bss: bool,
+    /// Whether the module is stored compressed. Only the bytes before the compressed part at the end of the module are
+    /// stored as-is and have a ROM offset.
+    pub compressed: bool,
+}
+
+/// Errors related to [`Rom::vaddr_to_rom_offset`] and [`Rom::rom_offset_to_vaddr`].
+#[derive(Debug, Snafu)]
+pub enum RawVaddrError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`RawArm9Error`].
+    #[snafu(transparent)]
+    RawArm9 {
+        /// Source error.
+        source: RawArm9Error,
+    },
+    /// See [`RawBuildInfoError`].
+    #[snafu(transparent)]
+    RawBuildInfo {
+        /// Source error.
+        source: RawBuildInfoError,
+    },
+    /// See [`Arm9Error`].
+    #[snafu(transparent)]
+    Arm9 {
+        /// Source error.
+        source: Arm9Error,
+    },
+    /// See [`Arm9AutoloadError`].
+    #[snafu(transparent)]
+    Arm9Autoload {
+        /// Source error.
+        source: Arm9AutoloadError,
+    },
+    /// See [`RawOverlayError`].
+    #[snafu(transparent)]
+    RawOverlay {
+        /// Source error.
+        source: RawOverlayError,
+    },
+    /// See [`RawFatError`].
+    #[snafu(transparent)]
+    RawFat {
+        /// Source error.
+        source: RawFatError,
+    },
+}
+
+impl Display for VaddrModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VaddrModule::Arm9Main => write!(f, "ARM9 program"),
+            VaddrModule::Autoload { kind, .. } => write!(f, "{kind} autoload"),
+            VaddrModule::Overlay { id, .. } => write!(f, "ARM9 overlay {id}"),
+        }
+    }
+}
+
+impl Display for VaddrLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}: {} (+{:#x})", self.address, self.module, self.offset)?;
+        match self.rom_offset {
+            _ if self.bss => write!(f, ", in .bss with no stored bytes"),
+            Some(rom_offset) => write!(f, ", ROM offset {rom_offset:#x}"),
+            None => write!(f, ", in the compressed part of the module"),
+        }
+    }
+}
+
+/// A region of the ROM, see [`Rom::locate`].
+enum Region {
+    Section(String),
+    File(u16),
+}
+
+/// Number of files on each side of the DS region end of a DSi-enhanced ROM, see [`Rom::file_regions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileRegions {
+    /// ROM offset where the DS region ends and the DSi region starts.
+    pub ds_region_end: u32,
+    /// Number of non-empty files in the DS region.
+    pub ds_files: usize,
+    /// Number of non-empty files in the DSi region.
+    pub dsi_files: usize,
+}
+
+impl Display for FileRegions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files in the DS region and {} files in the DSi region starting at {:#x}",
+            self.ds_files, self.dsi_files, self.ds_region_end
+        )
+    }
+}
+
 impl<'a> Rom<'a> {
     /// Creates a new ROM from raw data.
     pub fn new<T: Into<Cow<'a, [u8]>>>(data: T) -> Self {
         Self { data: data.into() }
     }
 
+    /// Returns a copy of this [`Rom`] which owns its data.
+    pub fn to_owned(&self) -> Rom<'static> {
+        Rom { data: Cow::Owned(self.data.to_vec()) }
+    }
+
+    /// Converts this [`Rom`] into one which owns its data. Data which is already owned, such as when loaded with
+    /// [`Self::from_file`], is moved instead of copied.
+    pub fn into_owned(self) -> Rom<'static> {
+        Rom { data: Cow::Owned(self.data.into_owned()) }
+    }
+
     /// Loads from a ROM file.
     ///
     /// # Errors
     ///
     /// This function will return an error if an I/O operation fails.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
-        let mut file = open_file(path)?;
-        let size = file.metadata()?.len();
-        let mut buf = vec![0; size as usize];
-        file.read_exact(&mut buf)?;
-        let data: Cow<[u8]> = buf.into();
+        let data: Cow<[u8]> = read_file(path)?.into();
         Ok(Self::new(data))
     }
 
@@ -68,6 +367,15 @@ impl<'a> Rom<'a> {
         Header::borrow_from_slice(self.data.as_ref())
     }
 
+    /// Returns a mutable reference to the header of this [`Rom`]. Call [`Header::update_crcs`] after modifying it.
+    ///
+    /// # Errors
+    ///
+    /// See [`Header::borrow_from_slice_mut`].
+    pub fn header_mut(&mut self) -> Result<&mut Header, RawHeaderError> {
+        Header::borrow_from_slice_mut(self.data.to_mut())
+    }
+
     /// Returns the ARM9 program of this [`Rom`].
     ///
     /// # Errors
@@ -94,9 +402,60 @@ impl<'a> Rom<'a> {
             entry_function: header.arm9.entry,
             build_info: build_info_offset,
             autoload_callback: header.arm9_autoload_callback,
+            overlay_signatures: None,
         })?)
     }
 
+    /// Returns the data between the end of the header and the start of the ARM9 program, or `None` if it's only padding.
+    /// See [`super::HeaderVersion::used_size`] for where the header ends.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::header`].
+    pub fn pre_arm9(&self) -> Result<Option<&[u8]>, RawHeaderError> {
+        let header = self.header()?;
+        let start = header.version().used_size();
+        let end = header.arm9.offset as usize;
+        if end <= start {
+            return Ok(None);
+        }
+        let data = &self.data[start..end];
+        if data.iter().all(|&byte| byte == data[0]) {
+            Ok(None)
+        } else {
+            Ok(Some(data))
+        }
+    }
+
+    /// Returns the offset where the official ROM data ends, including padding up to the next power of two. For trimmed
+    /// dumps which are smaller than that, this is the ROM size in the header instead. DSi ROMs end at the larger of the DS
+    /// and DSi ROM sizes.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::header`].
+    pub fn official_end(&self) -> Result<usize, RawHeaderError> {
+        let header = self.header()?;
+        let rom_size = header.rom_size_ds.max(header.rom_size_dsi) as usize;
+        let padded_size = if rom_size >= 128 * 1024 { rom_size.next_power_of_two() } else { rom_size };
+        Ok(if self.data.len() >= padded_size { padded_size } else { rom_size.min(self.data.len()) })
+    }
+
+    /// Returns the data appended after the official ROM data, such as a custom footer or signature, or `None` if there is
+    /// none or it's only padding. See [`Self::official_end`] for where the official ROM data ends.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::header`].
+    pub fn trailing_data(&self) -> Result<Option<&[u8]>, RawHeaderError> {
+        let data = &self.data[self.official_end()?..];
+        if data.iter().all(|&byte| byte == data[0]) {
+            Ok(None)
+        } else {
+            Ok(Some(data))
+        }
+    }
+
     /// Returns a reference to the ARM9 footer of this [`Rom`].
     ///
     /// # Errors
@@ -125,19 +484,56 @@ impl<'a> Rom<'a> {
 
     /// Returns the ARM9 overlay table of this [`Rom`].
     ///
+    /// Some titles store the overlay table inside the ARM9 program. If the program is compressed, the table can't be read
+    /// from the ROM directly, and [`Self::arm9_overlay_table_with`] must be used instead.
+    ///
     /// # Errors
     ///
-    /// See [`Self::header`] and [`Overlay::borrow_from_slice`].
+    /// See [`Self::header`] and [`Overlay::borrow_from_slice`]. Also returns
+    /// [`RawOverlayError::InsideCompressedArm9`] if the table is inside the compressed ARM9 program.
     pub fn arm9_overlay_table(&self) -> Result<&[Overlay], RawOverlayError> {
         let header = self.header()?;
-        let start = header.arm9_overlays.offset as usize;
-        let end = start + header.arm9_overlays.size as usize;
+        let start = header.arm9_overlays.offset;
+        let end = start + header.arm9_overlays.size;
         if start == 0 && end == 0 {
-            Ok(&[])
-        } else {
-            let data = &self.data[start..end];
-            Overlay::borrow_from_slice(data)
+            return Ok(&[]);
         }
+        if (header.arm9.offset..header.arm9.offset + header.arm9.size).contains(&start) {
+            let compressed = self.arm9().is_ok_and(|arm9| arm9.is_compressed().unwrap_or(false));
+            if compressed {
+                return InsideCompressedArm9Snafu { offset: start }.fail();
+            }
+        }
+        let data = &self.data[start as usize..end as usize];
+        Overlay::borrow_from_slice(data)
+    }
+
+    /// Returns the ARM9 overlay table of this [`Rom`], reading it from `arm9` if the table is stored inside the ARM9
+    /// program. Otherwise, this is the same as [`Self::arm9_overlay_table`].
+    ///
+    /// Pass the decompressed ARM9 program to read tables which are inside a compressed program. The table is read at the
+    /// same offset from the start of the program as in the ROM, since decompressing doesn't move the program's contents.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::header`] and [`Overlay::borrow_from_slice`]. Also returns [`RawOverlayError::OutOfArm9Bounds`] if the
+    /// table goes past the end of `arm9`.
+    pub fn arm9_overlay_table_with<'b>(&'b self, arm9: &'b Arm9) -> Result<&'b [Overlay], RawOverlayError> {
+        let header = self.header()?;
+        let start = header.arm9_overlays.offset;
+        let end = start + header.arm9_overlays.size;
+        let arm9_start = header.arm9.offset;
+        if (start == 0 && end == 0) || !(arm9_start..arm9_start + header.arm9.size).contains(&start) {
+            let data = &self.data[start as usize..end as usize];
+            return Overlay::borrow_from_slice(data);
+        }
+        let arm9_data = arm9.full_data();
+        let arm9_end = arm9_start + arm9_data.len() as u32;
+        if end > arm9_end {
+            return OutOfArm9BoundsSnafu { offset: start, end, arm9_end }.fail();
+        }
+        let data = &arm9_data[(start - arm9_start) as usize..(end - arm9_start) as usize];
+        Overlay::borrow_from_slice(data)
     }
 
     /// Returns the number of ARM9 overlays in this [`Rom`].
@@ -174,6 +570,83 @@ impl<'a> Rom<'a> {
         }))
     }
 
+    /// Returns the DSi area of this [`Rom`], or `None` if it has none. See [`Header::has_dsi_area`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header is invalid or a section of the DSi area is out of bounds.
+    pub fn dsi_area(&self) -> Result<Option<DsiArea<'_>>, RawDsiError> {
+        let header = self.header()?;
+        if !header.has_dsi_area() {
+            return Ok(None);
+        }
+        let slice = |section: &'static str, offset: u32, size: u32| {
+            let start = offset as usize;
+            let end = start + size as usize;
+            if end > self.data.len() {
+                return DsiSectionOutOfBoundsSnafu { section, end, rom_size: self.data.len() }.fail();
+            }
+            Ok(&self.data[start..end])
+        };
+        let program = |section: &'static str, offset: &ProgramOffset, build_info_offset: u32| {
+            let data = slice(section, offset.offset, offset.size)?;
+            let offsets = DsiProgramOffsets {
+                base_address: offset.base_addr,
+                entry_function: offset.entry,
+                build_info: build_info_offset,
+            };
+            Ok::<_, RawDsiError>(DsiProgram::new(data, offsets))
+        };
+
+        let arm9i = program("ARM9i program", &header.arm9i, header.arm9i_build_info_offset)?;
+        let arm7i = program("ARM7i program", &header.arm7i, header.arm7i_build_info_offset)?;
+        let sectors = &header.digest_sector_hashtable;
+        let blocks = &header.digest_block_hashtable;
+        let sector_hashtable = slice("digest sector hashtable", sectors.offset, sectors.size)?;
+        let block_hashtable = slice("digest block hashtable", blocks.offset, blocks.size)?;
+        Ok(Some(DsiArea::new(arm9i, arm7i, sector_hashtable, block_hashtable)))
+    }
+
+    /// Decrypts the modcrypt areas of this [`Rom`] in place, see [`Header::modcrypt_areas`]. Does nothing if the ROM is not
+    /// modcrypted. The header is left as-is, so it still says that the ROM is modcrypted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header is invalid or a modcrypt area is out of bounds.
+    pub fn decrypt_modcrypt(&mut self) -> Result<(), RawModcryptError> {
+        self.apply_modcrypt()
+    }
+
+    /// Encrypts the modcrypt areas of this [`Rom`] in place, after they were decrypted with [`Self::decrypt_modcrypt`].
+    /// Modcrypt uses AES in counter mode, so this is the same operation as decrypting.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header is invalid or a modcrypt area is out of bounds.
+    pub fn encrypt_modcrypt(&mut self) -> Result<(), RawModcryptError> {
+        self.apply_modcrypt()
+    }
+
+    fn apply_modcrypt(&mut self) -> Result<(), RawModcryptError> {
+        let areas = self.header()?.modcrypt_areas();
+        for area in &areas {
+            let offset = area.area.offset;
+            let end = offset as u64 + area.area.size as u64;
+            if end > self.data.len() as u64 {
+                return ModcryptAreaOutOfBoundsSnafu { offset, end, rom_size: self.data.len() }.fail();
+            }
+        }
+        if areas.is_empty() {
+            return Ok(());
+        }
+        let data = self.data.to_mut();
+        for area in &areas {
+            let range = area.area.offset as usize..(area.area.offset + area.area.size) as usize;
+            area.apply(&mut data[range], area.area.offset);
+        }
+        Ok(())
+    }
+
     /// Returns the ARM7 overlay table of this [`Rom`].
     ///
     /// # Errors
@@ -230,16 +703,37 @@ impl<'a> Rom<'a> {
         Ok(allocs)
     }
 
-    /// Returns the banner of this [`Rom`].
+    /// Returns how many files are in the DS and DSi regions of the file image, or `None` if this isn't a DSi-enhanced ROM.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::fat`].
+    pub fn file_regions(&self) -> Result<Option<FileRegions>, RawFatError> {
+        let header = self.header()?;
+        if header.ds_rom_region_end == 0 {
+            return Ok(None);
+        }
+        let ds_region_end = header.ds_rom_region_end as u32 * 0x80000;
+        let files = self.fat()?.iter().filter(|alloc| alloc.end > alloc.start);
+        let (ds_files, dsi_files) =
+            files.fold((0, 0), |(ds, dsi), alloc| if alloc.start >= ds_region_end { (ds, dsi + 1) } else { (ds + 1, dsi) });
+        Ok(Some(FileRegions { ds_region_end, ds_files, dsi_files }))
+    }
+
+    /// Returns the banner of this [`Rom`], or `None` if the header has no banner offset, as in some homebrew and prototype
+    /// ROMs.
     ///
     /// # Errors
     ///
     /// See [`Self::header`] and [`Banner::borrow_from_slice`].
-    pub fn banner(&self) -> Result<Banner, RawBannerError> {
+    pub fn banner(&self) -> Result<Option<Banner<'_>>, RawBannerError> {
         let header = self.header()?;
+        if header.banner_offset == 0 {
+            return Ok(None);
+        }
         let start = header.banner_offset as usize;
         let data = &self.data[start..];
-        Banner::borrow_from_slice(data)
+        Ok(Some(Banner::borrow_from_slice(data)?))
     }
 
     /// Returns the padding value between sections of this [`Rom`].
@@ -249,16 +743,316 @@ impl<'a> Rom<'a> {
     /// See [`Self::header`] and [`Self::banner`].
     pub fn padding_value(&self) -> Result<u8, RawBannerError> {
         let header = self.header()?;
-        let banner = self.banner()?;
 
         // The banner has a known size which is never a multiple of 512,
         // so it can't coincide with the start of another section.
         //
         // Therefore, we can use the first byte after the banner to determine
         // the padding value.
+        //
+        // Without a banner, the same is done after the FAT if it doesn't end on
+        // a 512-byte boundary.
+
+        if let Some(banner) = self.banner()? {
+            let end = header.banner_offset as usize + banner.version().banner_size();
+            return Ok(self.data[end]);
+        }
+        let end = (header.file_allocs.offset + header.file_allocs.size) as usize;
+        match self.data.get(end) {
+            Some(&value) if !end.is_multiple_of(0x200) => Ok(value),
+            _ => Ok(0xff),
+        }
+    }
+
+    /// Returns which section or file contains `offset`, and where in it. Sections take priority over files which refer to
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header, overlay tables, FNT, FAT or banner are invalid.
+    pub fn locate(&self, offset: u32) -> Result<Located, RawLocateError> {
+        let size = self.data.len() as u32;
+        if offset >= size {
+            return Ok(Located::PastEnd { size });
+        }
+
+        let header = self.header()?;
+        let fat = self.fat()?;
+        let mut regions = vec![];
+        let mut section = |start: u32, size: u32, name: &str| {
+            if size > 0 {
+                regions.push((start, start + size, Region::Section(name.to_string())));
+            }
+        };
+        let header_end = header.version().used_size() as u32;
+        section(0, header_end, "Header");
+        if let Some(pre_arm9) = self.pre_arm9()? {
+            section(header_end, pre_arm9.len() as u32, "Pre-ARM9 data");
+        }
+        section(header.arm9.offset, header.arm9.size, "ARM9 program");
+        if self.arm9_footer().is_ok() {
+            section(header.arm9.offset + header.arm9.size, size_of::<Arm9Footer>() as u32, "ARM9 footer");
+        }
+        section(header.arm9_overlays.offset, header.arm9_overlays.size, "ARM9 overlay table");
+        section(header.arm7.offset, header.arm7.size, "ARM7 program");
+        section(header.arm7_overlays.offset, header.arm7_overlays.size, "ARM7 overlay table");
+        section(header.file_names.offset, header.file_names.size, "File name table");
+        section(header.file_allocs.offset, header.file_allocs.size, "File allocation table");
+        if let Some(banner) = self.banner()? {
+            section(header.banner_offset, banner.version().banner_size() as u32, "Banner");
+        }
+        if header.has_dsi_area() {
+            section(header.digest_sector_hashtable.offset, header.digest_sector_hashtable.size, "Digest sector hashtable");
+            section(header.digest_block_hashtable.offset, header.digest_block_hashtable.size, "Digest block hashtable");
+            section(header.arm9i.offset, header.arm9i.size, "ARM9i program");
+            section(header.arm7i.offset, header.arm7i.size, "ARM7i program");
+        }
+        if let Some(trailing_data) = self.trailing_data()? {
+            section(size - trailing_data.len() as u32, trailing_data.len() as u32, "Trailing data");
+        }
+        regions.extend(
+            fat.iter()
+                .enumerate()
+                .filter(|(_, alloc)| alloc.end > alloc.start)
+                .map(|(id, alloc)| (alloc.start, alloc.end, Region::File(id as u16))),
+        );
+
+        let overlays = self.overlay_names()?;
+        let mut paths = None;
+        let mut region_name = |region: &Region| -> Result<(Option<String>, Option<String>), RawLocateError> {
+            let Region::File(id) = region else { return Ok((None, None)) };
+            if let Some(overlay) = overlays.get(id) {
+                return Ok((None, Some(overlay.clone())));
+            }
+            if paths.is_none() {
+                paths = Some(self.file_paths()?);
+            }
+            Ok((paths.as_ref().unwrap().get(id).cloned(), None))
+        };
+
+        let containing = regions
+            .iter()
+            .filter(|(start, end, _)| (*start..*end).contains(&offset))
+            .min_by_key(|(_, _, region)| matches!(region, Region::File(_)));
+        if let Some((start, _, region)) = containing {
+            return Ok(match region {
+                Region::Section(name) => Located::Section { name: name.clone(), offset: offset - start },
+                Region::File(id) => {
+                    let (path, overlay) = region_name(region)?;
+                    Located::File { id: *id, path, overlay, offset: offset - start }
+                }
+            });
+        }
+
+        let mut describe = |region: &Region| -> Result<String, RawLocateError> {
+            Ok(match region {
+                Region::Section(name) => name.clone(),
+                Region::File(id) => match region_name(region)? {
+                    (Some(path), _) => format!("file {path}"),
+                    (_, Some(overlay)) => overlay,
+                    (None, None) => format!("unnamed file {id:#x}"),
+                },
+            })
+        };
+        let previous = regions.iter().filter(|(_, end, _)| *end <= offset).max_by_key(|(_, end, _)| *end);
+        let next = regions.iter().filter(|(start, _, _)| *start > offset).min_by_key(|(start, _, _)| *start);
+        Ok(Located::Gap {
+            offset: offset - previous.map_or(0, |(_, end, _)| *end),
+            previous: previous.map(|(_, _, region)| describe(region)).transpose()?,
+            next: next.map(|(_, _, region)| describe(region)).transpose()?,
+        })
+    }
+
+    /// Returns the ARM9 program decompressed, and the number of bytes at the start of the stored program which are not
+    /// compressed, or `None` if the program isn't compressed.
+    fn decompressed_arm9(&self) -> Result<(Arm9<'_>, Option<u32>), RawVaddrError> {
+        let mut arm9 = self.arm9()?;
+        if !arm9.is_compressed()? {
+            return Ok((arm9, None));
+        }
+        let uncompressed_size = Self::uncompressed_size(arm9.full_data());
+        arm9.decompress()?;
+        Ok((arm9, Some(uncompressed_size)))
+    }
+
+    /// Returns the number of bytes at the start of LZ77 compressed `data` which are stored as-is.
+    fn uncompressed_size(data: &[u8]) -> u32 {
+        LZ77.footer(data).map_or(0, |footer| (data.len() - footer.total_size) as u32)
+    }
+
+    /// Returns the ROM offset of the byte at `offset` in a module stored at `start`, unless it's in the compressed part
+    /// which starts after `uncompressed_size` bytes.
+    fn stored_offset(start: u32, offset: u32, uncompressed_size: Option<u32>) -> Option<u32> {
+        match uncompressed_size {
+            Some(size) if offset >= size => None,
+            _ => Some(start + offset),
+        }
+    }
+
+    /// Returns where a virtual address in the ARM9 address space is stored in this ROM. This covers the main ARM9 code,
+    /// the autoload blocks and the ARM9 overlays, including their uninitialized data. Overlays which share an address range
+    /// are all returned, since only one of them is loaded at a time. Returns an empty list if no module is loaded to the
+    /// address.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header, ARM9 program, overlay table or FAT are invalid, or the ARM9
+    /// program fails to decompress.
+    pub fn vaddr_to_rom_offset(&self, address: u32) -> Result<Vec<VaddrLocation>, RawVaddrError> {
+        let header = self.header()?;
+        let (arm9, uncompressed_size) = self.decompressed_arm9()?;
+        let compressed = uncompressed_size.is_some();
+        let build_info = arm9.build_info()?;
+        let autoloads = arm9.autoloads()?;
+        let mut locations = vec![];
+
+        let arm9_location = |module: VaddrModule, offset: u32, data_offset: Option<u32>| VaddrLocation {
+            address,
+            module,
+            offset,
+            rom_offset: data_offset
+                .and_then(|data_offset| Self::stored_offset(header.arm9.offset, data_offset, uncompressed_size)),
+            bss: data_offset.is_none(),
+            compressed,
+        };
+        match arm9.locate(address)? {
+            Some(Arm9Location::Main { offset }) => {
+                locations.push(arm9_location(VaddrModule::Arm9Main, offset, Some(offset)));
+            }
+            Some(Arm9Location::Autoload { index, kind, offset }) => {
+                let block_start = build_info.autoload_blocks - arm9.base_address()
+                    + autoloads[..index].iter().map(|autoload| autoload.code().len() as u32).sum::<u32>();
+                let module = VaddrModule::Autoload { index, kind };
+                locations.push(arm9_location(module, offset, Some(block_start + offset)));
+            }
+            None => {
+                if arm9.bss()?.contains(&address) {
+                    locations.push(arm9_location(VaddrModule::Arm9Main, address - arm9.base_address(), None));
+                }
+                for (index, autoload) in autoloads.iter().enumerate() {
+                    let offset = address.wrapping_sub(autoload.base_address());
+                    let code_size = autoload.code().len() as u32;
+                    if (code_size..code_size + autoload.bss_size()).contains(&offset) {
+                        let module = VaddrModule::Autoload { index, kind: autoload.kind() };
+                        locations.push(arm9_location(module, offset, None));
+                    }
+                }
+            }
+        }
+
+        let fat = self.fat()?;
+        for overlay in self.arm9_overlay_table_with(&arm9)? {
+            let offset = address.wrapping_sub(overlay.base_addr);
+            if offset >= overlay.code_size + overlay.bss_size {
+                continue;
+            }
+            let compressed = overlay.flags.is_compressed();
+            let bss = offset >= overlay.code_size;
+            let rom_offset = match fat.get(overlay.file_id as usize) {
+                Some(alloc) if !bss => {
+                    let data = &self.data[alloc.start as usize..alloc.end as usize];
+                    let uncompressed_size = compressed.then(|| Self::uncompressed_size(data));
+                    Self::stored_offset(alloc.start, offset, uncompressed_size)
+                }
+                _ => None,
+            };
+            let module = VaddrModule::Overlay { id: overlay.id as u16, file_id: overlay.file_id };
+            locations.push(VaddrLocation { address, module, offset, rom_offset, bss, compressed });
+        }
+        Ok(locations)
+    }
+
+    /// Returns which virtual address in the ARM9 address space the byte at a ROM offset is loaded to. This is the inverse
+    /// of [`Self::vaddr_to_rom_offset`]. Returns `None` if the offset is not in the main ARM9 code, an autoload block or
+    /// an ARM9 overlay, or if it's in the compressed part of a module.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::vaddr_to_rom_offset`].
+    pub fn rom_offset_to_vaddr(&self, rom_offset: u32) -> Result<Option<VaddrLocation>, RawVaddrError> {
+        let header = self.header()?;
+        let (arm9, uncompressed_size) = self.decompressed_arm9()?;
+
+        let arm9_start = header.arm9.offset;
+        if (arm9_start..arm9_start + header.arm9.size).contains(&rom_offset) {
+            let data_offset = rom_offset - arm9_start;
+            if Self::stored_offset(arm9_start, data_offset, uncompressed_size).is_none() {
+                return Ok(None);
+            }
+            let location = |module: VaddrModule, base_address: u32, offset: u32| VaddrLocation {
+                address: base_address + offset,
+                module,
+                offset,
+                rom_offset: Some(rom_offset),
+                bss: false,
+                compressed: uncompressed_size.is_some(),
+            };
+
+            let mut block_start = arm9.build_info()?.autoload_blocks - arm9.base_address();
+            if data_offset < block_start {
+                return Ok(Some(location(VaddrModule::Arm9Main, arm9.base_address(), data_offset)));
+            }
+            for (index, autoload) in arm9.autoloads()?.iter().enumerate() {
+                let block_end = block_start + autoload.code().len() as u32;
+                if (block_start..block_end).contains(&data_offset) {
+                    let module = VaddrModule::Autoload { index, kind: autoload.kind() };
+                    return Ok(Some(location(module, autoload.base_address(), data_offset - block_start)));
+                }
+                block_start = block_end;
+            }
+            return Ok(None);
+        }
+
+        let fat = self.fat()?;
+        for overlay in self.arm9_overlay_table_with(&arm9)? {
+            let Some(alloc) = fat.get(overlay.file_id as usize) else {
+                continue;
+            };
+            if !(alloc.start..alloc.end).contains(&rom_offset) {
+                continue;
+            }
+            let offset = rom_offset - alloc.start;
+            let compressed = overlay.flags.is_compressed();
+            let data = &self.data[alloc.start as usize..alloc.end as usize];
+            let uncompressed_size = compressed.then(|| Self::uncompressed_size(data));
+            if offset >= overlay.code_size || Self::stored_offset(alloc.start, offset, uncompressed_size).is_none() {
+                return Ok(None);
+            }
+            return Ok(Some(VaddrLocation {
+                address: overlay.base_addr + offset,
+                module: VaddrModule::Overlay { id: overlay.id as u16, file_id: overlay.file_id },
+                offset,
+                rom_offset: Some(rom_offset),
+                bss: false,
+                compressed,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Returns the names of the overlays in this ROM by file ID, such as `ARM9 overlay 3`.
+    pub(crate) fn overlay_names(&self) -> Result<HashMap<u16, String>, RawOverlayError> {
+        let mut overlays = HashMap::new();
+        for (processor, table) in
+            [(Processor::Arm9, self.arm9_overlay_table()?), (Processor::Arm7, self.arm7_overlay_table()?)]
+        {
+            for overlay in table {
+                overlays.insert(overlay.file_id as u16, format!("{processor} overlay {}", overlay.id));
+            }
+        }
+        Ok(overlays)
+    }
 
-        let end = header.banner_offset as usize + banner.version().banner_size();
-        Ok(self.data[end])
+    /// Returns the paths of the files in this ROM's file system by file ID, such as `/data/a.bin`.
+    pub(crate) fn file_paths(&self) -> Result<HashMap<u16, String>, RawLocateError> {
+        let fnt = self.fnt()?;
+        let fat = self.fat()?;
+        let files = FileSystem::parse(&fnt, fat, self)?;
+        let mut paths = HashMap::new();
+        files.traverse_files(["/"], |file, dir| {
+            paths.insert(file.id(), dir.join(file.name()).to_string_lossy().replace('\\', "/"));
+        });
+        Ok(paths)
     }
 
     /// Returns a reference to the data of this [`Rom`].
@@ -266,12 +1060,13 @@ impl<'a> Rom<'a> {
         &self.data
     }
 
-    /// Saves this ROM to a new file.
+    /// Saves this ROM to a file. The file is written atomically using [`write_file_atomic`], so an interrupted save
+    /// never leaves a truncated ROM behind.
     ///
     /// # Errors
     ///
     /// This function will return an error if an I/O operation fails.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FileError> {
-        write_file(path, self.data())
+        write_file_atomic(path, |writer| writer.write_all(self.data()))
     }
 }
@@ -1,39 +1,69 @@
 use std::{
-    io::{self, Cursor, Write},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::Infallible,
+    fmt::Display,
+    io::{self, Cursor, Seek, SeekFrom, Write},
     mem::size_of,
-    path::Path,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Instant,
 };
 
 use serde::{Deserialize, Serialize};
-use snafu::Snafu;
+use snafu::{Backtrace, OptionExt, Snafu};
 
 use super::{
+    layout::fat_size,
     raw::{
-        self, Arm9Footer, RawArm9Error, RawBannerError, RawBuildInfoError, RawFatError, RawFntError, RawHeaderError,
-        RawOverlayError, TableOffset,
+        self, Arm9Footer, ModcryptArea, RawArm9Error, RawBannerError, RawBuildInfoError, RawDsiError, RawFatError,
+        RawFntError, RawHeaderError, RawOverlayError, TableOffset,
     },
-    Arm7, Arm9, Arm9AutoloadError, Arm9Error, Arm9Offsets, Autoload, Banner, BannerError, BannerImageError, BuildInfo,
-    FileBuildError, FileParseError, FileSystem, Header, HeaderBuildError, Logo, LogoError, LogoLoadError, LogoSaveError,
-    Overlay, OverlayInfo, RomConfigAutoload,
+    Arm7, Arm9, Arm9AutoloadError, Arm9BuildInfoError, Arm9Error, Arm9FooterConfig, Arm9Offsets, Autoload, Banner,
+    BannerError, BannerImageError, BannerTitleFileError, BuildCache, BuildInfo, BuildSummary, CompressedFile,
+    CompressionReport, DedupError, DedupStore, DsiArea, DsiProgram, DuplicateIdSnafu, File, FileBuildError, FileEditError,
+    FileOrderError, FileParseError, FileSystem, Header, HeaderBuildError, IndexOutOfRangeSnafu, Logo, LogoError,
+    LogoLoadError, LogoSaveError, ModuleCompression, Overlay, OverlayEditError, OverlayInfo, OverlayNotFoundSnafu,
+    OverlayTable, OverlayTableError, PartialSaveError, PreservationLevel, Processor, RomComparison, RomConfigAutoload,
+    RomConfigDsi, RomDiffError, RomEmbeddedError, RomLayout, RomLayoutDsi, RomLayoutError, RomLayoutSection, SaveSummary,
+    SaveWriter, SecureAreaState, TooManyFileIdsSnafu, TransparentCompression, DEFAULT_COMPRESSION_START,
+    DEFAULT_MAX_MISMATCHES, DSI_REGION_ALIGNMENT, LANGUAGES,
 };
 use crate::{
     compress::lz77::Lz77DecompressError,
-    crypto::blowfish::BlowfishKey,
-    io::{create_dir_all, create_file, create_file_and_dirs, open_file, read_file, read_to_string, FileError},
-    rom::{raw::FileAlloc, Arm9WithTcmsOptions, RomConfig},
+    crypto::{blowfish::BlowfishKey, hmac_sha1::HmacSha1, sha1::Sha1},
+    io::{create_dir_all, open_file, read_file, read_to_string, FileError},
+    parallel::{ThreadPool, ThreadPoolError},
+    rom::{raw::FileAlloc, Arm9WithTcmsOptions, FatLayout, RomConfig, RomConfigSectionOffsets},
+    str::{glob_match, hex_string, BlobSize},
 };
 
 /// A plain ROM.
 pub struct Rom<'a> {
     header: Header,
     header_logo: Logo,
+    pre_arm9: Option<Cow<'a, [u8]>>,
+    trailing_data: Option<Cow<'a, [u8]>>,
     arm9: Arm9<'a>,
+    /// Base address of the DTCM, found while the ARM9 program was decompressed so [`MemoryLimits::check`] doesn't have to
+    /// decompress it again.
+    dtcm_base_address: Option<u32>,
+    arm9_footer: Arm9FooterConfig,
+    blowfish_key_sha1: Option<[u8; 0x14]>,
+    arm9_config_extra: serde_yml::Mapping,
     arm9_overlays: Vec<Overlay<'a>>,
+    arm9_overlay_table: Option<OverlayTable>,
     arm7: Arm7<'a>,
     arm7_overlays: Vec<Overlay<'a>>,
-    banner: Banner,
+    dsi: Option<DsiArea<'a>>,
+    banner: Option<Banner>,
     files: FileSystem<'a>,
     path_order: Vec<String>,
+    file_order: Option<Vec<String>>,
+    aliased_files: Vec<AliasedFile>,
+    compression_report: CompressionReport,
+    dev_padding: bool,
+    strict_header: bool,
     config: RomConfig,
 }
 
@@ -88,6 +118,12 @@ pub enum RomExtractError {
         /// Source error.
         source: RawArm9Error,
     },
+    /// See [`RawDsiError`].
+    #[snafu(transparent)]
+    RawDsi {
+        /// Source error.
+        source: RawDsiError,
+    },
     /// See [`Arm9AutoloadError`]
     #[snafu(transparent)]
     Arm9Autoload {
@@ -135,6 +171,56 @@ pub enum RomBuildError {
         /// Source error.
         source: HeaderBuildError,
     },
+    /// See [`FileOrderError`].
+    #[snafu(transparent)]
+    FileOrder {
+        /// Source error.
+        source: FileOrderError,
+    },
+    /// See [`FileError`].
+    #[snafu(transparent)]
+    File {
+        /// Source error.
+        source: FileError,
+    },
+    /// See [`RomLayoutError`].
+    #[snafu(transparent)]
+    RomLayout {
+        /// Source error.
+        source: RomLayoutError,
+    },
+    /// Occurs when [`RomLoadOptions::strict_header`] is set and [`Header::check_consistency`] finds problems.
+    #[snafu(display("the header is inconsistent with the ROM contents:\n{findings}\n{backtrace}"))]
+    InconsistentHeader {
+        /// Findings, one per line.
+        findings: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a section has a configured offset, but the preceding content has grown past it.
+    #[snafu(display(
+        "cannot place {section} at {offset:#x} since the preceding content ends at {position:#x}:\n{backtrace}"
+    ))]
+    SectionOffsetExceeded {
+        /// Name of the section.
+        section: &'static str,
+        /// Configured offset.
+        offset: u32,
+        /// End of the preceding content.
+        position: u64,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when writing the ROM has already passed the offset of the next section to write.
+    #[snafu(display("cannot pad to {offset:#x} since {position:#x} bytes were already written:\n{backtrace}"))]
+    PaddingOverrun {
+        /// Offset to pad to.
+        offset: u32,
+        /// Current write position.
+        position: u64,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 /// Errors related to [`Rom::save`] and [`Rom::load`].
@@ -143,6 +229,24 @@ pub enum RomSaveError {
     /// Occurs when the ROM is encrypted but no Blowfish key was provided.
     #[snafu(display("blowfish key is required because ARM9 program is encrypted"))]
     BlowfishKeyNeeded,
+    /// Occurs when the ARM9 config records which Blowfish key was used, but none of the provided keys match it.
+    #[snafu(display("none of the provided blowfish keys match the SHA-1 hash {sha1} from the ARM9 config:\n{backtrace}"))]
+    BlowfishKeyNotFound {
+        /// SHA-1 hash of the expected key, in hexadecimal.
+        sha1: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the ARM9 program is configured to be encrypted but the header has no gamecode, which seeds the
+    /// encryption. Encrypting anyway would produce a secure area which no console can decrypt.
+    #[snafu(display(
+        "ARM9 program is configured to be encrypted, but the header has no gamecode to seed the encryption with. Set the \
+         gamecode in the header, or build without encryption:\n{backtrace}"
+    ))]
+    EncryptionGamecodeMissing {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
     /// See [`io::Error`].
     #[snafu(transparent)]
     Io {
@@ -155,6 +259,12 @@ pub enum RomSaveError {
         /// Source error.
         source: FileError,
     },
+    /// See [`ThreadPoolError`].
+    #[snafu(transparent)]
+    ThreadPool {
+        /// Source error.
+        source: ThreadPoolError,
+    },
     /// See [`serde_yml::Error`].
     #[snafu(transparent)]
     SerdeJson {
@@ -191,18 +301,136 @@ pub enum RomSaveError {
         /// Source error.
         source: Arm9AutoloadError,
     },
+    /// See [`Arm9BuildInfoError`].
+    #[snafu(transparent)]
+    Arm9BuildInfo {
+        /// Source error.
+        source: Arm9BuildInfoError,
+    },
     /// See [`BannerImageError`].
     #[snafu(transparent)]
     BannerImage {
         /// Source error.
         source: BannerImageError,
     },
+    /// See [`BannerTitleFileError`].
+    #[snafu(transparent)]
+    BannerTitleFile {
+        /// Source error.
+        source: BannerTitleFileError,
+    },
+    /// See [`DedupError`].
+    #[snafu(transparent)]
+    Dedup {
+        /// Source error.
+        source: DedupError,
+    },
     /// See [`Lz77DecompressError`].
     #[snafu(transparent)]
     Lz77Decompress {
         /// Source error.
         source: Lz77DecompressError,
     },
+    /// See [`OverlayTableError`].
+    #[snafu(transparent)]
+    OverlayTable {
+        /// Source error.
+        source: OverlayTableError,
+    },
+    /// Occurs when a file was skipped by [`Rom::save_with_options`] but no original ROM was provided when loading.
+    #[snafu(display("the file '{path}' was skipped during extraction, so the original ROM is required:\n{backtrace}"))]
+    OriginalRomNeeded {
+        /// Path to the skipped file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a skipped file in the original ROM does not match its recorded size and hash.
+    #[snafu(display("the file '{path}' in the original ROM does not match its recorded size and SHA-1 hash:\n{backtrace}"))]
+    SkippedFileMismatch {
+        /// Path to the skipped file.
+        path: String,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when two overlays in an overlays config have the same file name.
+    #[snafu(display("overlays {first_id} and {second_id} both use the file name '{file_name}':\n{backtrace}"))]
+    DuplicateOverlayFileName {
+        /// File name of the overlays.
+        file_name: String,
+        /// ID of the first overlay using the file name.
+        first_id: u32,
+        /// ID of the second overlay using the file name.
+        second_id: u32,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when a file listed in an overlays config does not exist.
+    #[snafu(display("the file '{}' for overlay {id} does not exist:\n{backtrace}", path.display()))]
+    MissingOverlayFile {
+        /// Overlay ID.
+        id: u32,
+        /// Path to the missing file.
+        path: PathBuf,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`Rom::set_path_order`].
+#[derive(Snafu, Debug)]
+pub enum RomPathOrderError {
+    /// Occurs when one or more paths in the path order do not exist in the file system.
+    #[snafu(display("the following paths in the path order were not found in the file system: {paths:?}:\n{backtrace}"))]
+    UnresolvedPaths {
+        /// Paths which were not found.
+        paths: Vec<String>,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+/// Errors related to [`Rom::resign_arm9_overlays`] and [`Rom::arm9_overlay_hmac_sha1_key`].
+#[derive(Snafu, Debug)]
+pub enum RomResignError {
+    /// See [`Arm9Error`].
+    #[snafu(transparent)]
+    Arm9 {
+        /// Source error.
+        source: Arm9Error,
+    },
+    /// See [`Arm9AutoloadError`].
+    #[snafu(transparent)]
+    Arm9Autoload {
+        /// Source error.
+        source: Arm9AutoloadError,
+    },
+    /// See [`RawBuildInfoError`].
+    #[snafu(transparent)]
+    RawBuildInfo {
+        /// Source error.
+        source: RawBuildInfoError,
+    },
+    /// Occurs when the ARM9 program is encrypted but no Blowfish key was provided.
+    #[snafu(display("blowfish key is required because ARM9 program is encrypted:\n{backtrace}"))]
+    ResignBlowfishKeyNeeded {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the ARM9 program has no overlay signature table, see [`Arm9::overlay_signatures`].
+    #[snafu(display("the ARM9 program has no overlay signature table:\n{backtrace}"))]
+    NoOverlaySignatures {
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when an overlay to sign doesn't exist.
+    #[snafu(display("ARM9 overlay {id} does not exist:\n{backtrace}"))]
+    ResignOverlayNotFound {
+        /// Overlay ID.
+        id: u16,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
 }
 
 /// Config file for the ARM9 main module.
@@ -213,11 +441,30 @@ pub struct Arm9BuildConfig {
     pub offsets: Arm9Offsets,
     /// Whether this module is encrypted in the ROM.
     pub encrypted: bool,
+    /// Whether this module has no secure area, as in homebrew, so it is never encrypted or decrypted. See
+    /// [`SecureAreaState::Absent`].
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub no_secure_area: bool,
     /// Whether this module is compressed in the ROM.
     pub compressed: bool,
     /// Build info for this module.
     #[serde(flatten)]
     pub build_info: BuildInfo,
+    /// SHA-1 hash of the Blowfish key which decrypted this module, so that the same key is used when building.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub blowfish_key_sha1: Option<[u8; 0x14]>,
+    /// Footer fields to preserve, if they differ from the generated footer.
+    #[serde(skip_serializing_if = "Arm9FooterConfig::is_default", default)]
+    pub footer: Arm9FooterConfig,
+    /// Whether the build info has a missing or corrupted nitrocode, see [`Arm9::lenient_build_info`].
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub lenient_build_info: bool,
+    /// Offset where compression starts, if it differs from [`DEFAULT_COMPRESSION_START`]. See [`Arm9::compression_start`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub compression_start: Option<u32>,
+    /// Unknown keys, preserved so that other tools can store their own metadata in the config.
+    #[serde(flatten)]
+    pub extra: serde_yml::Mapping,
 }
 
 /// Overlay configuration, extending [`OverlayInfo`] with more fields.
@@ -226,8 +473,69 @@ pub struct OverlayConfig {
     /// See [`OverlayInfo`].
     #[serde(flatten)]
     pub info: OverlayInfo,
-    /// Name of binary file.
+    /// Name of binary file, empty for placeholders.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
     pub file_name: String,
+    /// Whether this is a placeholder entry in the overlay table, which has no binary file. See [`Overlay::is_placeholder`].
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub placeholder: bool,
+    /// Unknown keys, preserved so that other tools can store their own metadata in the config.
+    #[serde(flatten)]
+    pub extra: serde_yml::Mapping,
+}
+
+/// A file which was not saved by [`Rom::save_with_options`], and is instead loaded from the original ROM.
+#[derive(Serialize, Deserialize)]
+pub struct SkippedFile {
+    /// Path relative to the files directory.
+    pub path: String,
+    /// File size.
+    pub size: u32,
+    /// Offset to the file in the original ROM.
+    pub rom_offset: u32,
+    /// SHA-1 hash of the file contents.
+    #[serde(with = "crate::str::hex_array")]
+    pub sha1: [u8; 0x14],
+}
+
+/// A file whose FAT entry pointed at another section of the original ROM, such as the banner. It is not saved by
+/// [`Rom::save_with_options`], and [`Rom::build`] points its FAT entry at the new location of that section instead.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AliasedFile {
+    /// Path relative to the files directory.
+    pub path: String,
+    /// Section which the file refers to.
+    pub section: AliasedSection,
+    /// Size of the section in the original ROM.
+    pub size: u32,
+}
+
+/// A section of the ROM which an [`AliasedFile`] can refer to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasedSection {
+    /// The ARM9 program, excluding its footer.
+    Arm9,
+    /// The ARM9 overlay table.
+    Arm9OverlayTable,
+    /// The ARM7 program.
+    Arm7,
+    /// The ARM7 overlay table.
+    Arm7OverlayTable,
+    /// The banner.
+    Banner,
+}
+
+impl Display for AliasedSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Arm9 => write!(f, "ARM9 program"),
+            Self::Arm9OverlayTable => write!(f, "ARM9 overlay table"),
+            Self::Arm7 => write!(f, "ARM7 program"),
+            Self::Arm7OverlayTable => write!(f, "ARM7 overlay table"),
+            Self::Banner => write!(f, "banner"),
+        }
+    }
 }
 
 impl<'a> Rom<'a> {
@@ -242,10 +550,14 @@ impl<'a> Rom<'a> {
 
         let config: RomConfig = serde_yml::from_reader(open_file(config_path)?)?;
         let path = config_path.parent().unwrap();
+        let pool = ThreadPool::new(options.concurrency)?;
 
         // --------------------- Load header ---------------------
         let header: Header = serde_yml::from_reader(open_file(path.join(&config.header))?)?;
         let header_logo = Logo::from_png(path.join(&config.header_logo))?;
+        let pre_arm9 = config.pre_arm9.as_ref().map(|pre_arm9| read_file(path.join(pre_arm9))).transpose()?.map(Cow::from);
+        let trailing_data =
+            config.trailing_data.as_ref().map(|trailing_data| read_file(path.join(trailing_data))).transpose()?.map(Cow::from);
 
         // --------------------- Load ARM9 program ---------------------
         let arm9_build_config: Arm9BuildConfig = serde_yml::from_reader(open_file(path.join(&config.arm9_config))?)?;
@@ -262,6 +574,7 @@ impl<'a> Rom<'a> {
         let dtcm = read_file(path.join(&config.dtcm.bin))?;
         let dtcm_info = serde_yml::from_reader(open_file(path.join(&config.dtcm.config))?)?;
         let dtcm = Autoload::new(dtcm, dtcm_info);
+        let dtcm_base_address = Some(dtcm.base_address());
         autoloads.push(dtcm);
 
         for unknown_autoload in &config.unknown_autoloads {
@@ -275,26 +588,71 @@ impl<'a> Rom<'a> {
         let mut arm9 = Arm9::with_autoloads(arm9, &autoloads, arm9_build_config.offsets, Arm9WithTcmsOptions {
             originally_compressed: arm9_build_config.compressed,
             originally_encrypted: arm9_build_config.encrypted,
+            lenient_build_info: arm9_build_config.lenient_build_info,
+            compression_start: arm9_build_config.compression_start.map(|start| start as usize),
         })?;
-        arm9_build_config.build_info.assign_to_raw(arm9.build_info_mut()?);
+        let build_info = &arm9_build_config.build_info;
+        arm9.set_bss(build_info.bss_start..build_info.bss_end)?;
+        arm9.set_sdk_version(build_info.sdk_version)?;
+        // The overlay signature table may have moved along with its autoload block, so it's located before compressing
+        let mut arm9_footer = arm9_build_config.footer;
+        if let Some(footer_value) = arm9.overlay_signatures_footer_value()? {
+            arm9_footer.reserved = footer_value;
+        }
+        let mut compression_report = CompressionReport::default();
         if arm9_build_config.compressed && options.compress {
-            log::info!("Compressing ARM9 program");
-            arm9.compress()?;
+            let uncompressed_size = arm9.full_data().len() as u32;
+            let start = Instant::now();
+            let cached =
+                options.build_cache.and_then(|cache| cache.compressed_arm9(arm9.full_data(), arm9.compression_start()));
+            if let Some(compressed) = cached {
+                log::info!("Reusing compressed ARM9 program from the build cache");
+                arm9.set_compressed_data(compressed)?;
+            } else {
+                log::info!("Compressing ARM9 program");
+                arm9.compress()?;
+            }
+            compression_report.add_time(start.elapsed());
+            let module = ModuleCompression {
+                name: "ARM9 program".to_string(),
+                uncompressed_size,
+                compressed_size: arm9.full_data().len() as u32,
+            };
+            log::info!("{module}");
+            compression_report.modules.push(module);
         }
-        if arm9_build_config.encrypted && options.encrypt {
-            let Some(key) = options.key else {
+        if arm9_build_config.no_secure_area {
+            log::info!("ARM9 program has no secure area, it will not be encrypted");
+        } else if arm9_build_config.encrypted && options.encrypt {
+            let keys = options.key_candidates();
+            if keys.is_empty() {
                 return BlowfishKeyNeededSnafu {}.fail();
+            }
+            let sha1 = arm9_build_config.blowfish_key_sha1.as_ref();
+            let Some(key) = BlowfishKey::select(&keys, sha1) else {
+                let sha1 = hex_string(sha1.unwrap());
+                return BlowfishKeyNotFoundSnafu { sha1 }.fail();
             };
+            if header.original.gamecode.to_le_u32() == 0 {
+                return EncryptionGamecodeMissingSnafu {}.fail();
+            }
             log::info!("Encrypting ARM9 program");
             arm9.encrypt(key, header.original.gamecode.to_le_u32())?;
         }
 
         // --------------------- Load ARM9 overlays ---------------------
         let arm9_overlays = if let Some(arm9_overlays_config) = &config.arm9_overlays {
-            Self::load_overlays(&path.join(arm9_overlays_config), "arm9", &options)?
+            Self::load_overlays(&path.join(arm9_overlays_config), Processor::Arm9, &options, &pool, &mut compression_report)?
         } else {
             vec![]
         };
+        let arm9_overlay_table = if let Some(arm9_ovt_bin) = &config.arm9_ovt_bin {
+            let arm9_overlay_table = OverlayTable::from_raw_bytes(&read_file(path.join(arm9_ovt_bin))?)?;
+            Self::validate_arm9_overlay_table(&arm9_overlay_table, &arm9_overlays)?;
+            Some(arm9_overlay_table)
+        } else {
+            None
+        };
 
         // --------------------- Load ARM7 program ---------------------
         let arm7 = read_file(path.join(&config.arm7_bin))?;
@@ -303,48 +661,223 @@ impl<'a> Rom<'a> {
 
         // --------------------- Load ARM7 overlays ---------------------
         let arm7_overlays = if let Some(arm7_overlays_config) = &config.arm7_overlays {
-            Self::load_overlays(&path.join(arm7_overlays_config), "arm7", &options)?
+            Self::load_overlays(&path.join(arm7_overlays_config), Processor::Arm7, &options, &pool, &mut compression_report)?
         } else {
             vec![]
         };
 
+        if !compression_report.modules.is_empty() {
+            log::info!("{compression_report}");
+        }
+
+        // --------------------- Load DSi area ---------------------
+        let dsi = config.dsi.as_ref().map(|dsi_config| Self::load_dsi_area(path, dsi_config)).transpose()?;
+
         // --------------------- Load banner ---------------------
-        let banner_path = path.join(&config.banner);
-        let banner_dir = banner_path.parent().unwrap();
-        let mut banner: Banner = serde_yml::from_reader(open_file(&banner_path)?)?;
-        banner.images.load(banner_dir)?;
+        let banner = if let Some(banner_path) = &config.banner {
+            let banner_path = path.join(banner_path);
+            let banner_dir = banner_path.parent().unwrap();
+            let mut banner: Banner = serde_yml::from_reader(open_file(&banner_path)?)?;
+            banner.images.load(banner_dir)?;
+            banner.load_title_files(banner_dir)?;
+            Some(banner)
+        } else {
+            None
+        };
 
         // --------------------- Load files ---------------------
         let num_overlays = arm9_overlays.len() + arm7_overlays.len();
-        let (files, path_order) = if options.load_files {
+        let num_reserved = config.fat_layout.and_then(|layout| layout.reserved_entries).map_or(num_overlays, usize::from);
+        let (files, path_order, file_order, aliased_files) = if options.load_files {
             log::info!("Loading ROM assets");
-            let files = FileSystem::load(path.join(&config.files_dir), num_overlays)?;
+            let files_dir = path.join(&config.files_dir);
+            let mut external_files = if let Some(skipped_files) = &config.skipped_files {
+                Self::load_skipped_files(&path.join(skipped_files), &files_dir, &options)?
+            } else {
+                BTreeMap::new()
+            };
+            let aliased_files = if let Some(aliased_files) = &config.aliased_files {
+                Self::load_aliased_files(&path.join(aliased_files), &files_dir, &mut external_files)?
+            } else {
+                vec![]
+            };
+            let rom_files = external_files.keys().cloned().collect::<HashSet<_>>();
+            if let Some(dedup_store) = &config.dedup_store {
+                external_files.extend(dedup_store.load_files(path, &files_dir)?);
+            }
+            let fnt_order = match &config.fnt_order {
+                Some(fnt_order) => read_to_string(path.join(fnt_order))?.trim().lines().map(|l| l.to_string()).collect(),
+                None => vec![],
+            };
+            let mut files = if options.lazy_files {
+                FileSystem::load_lazy(files_dir, num_reserved, external_files, &fnt_order)?
+            } else {
+                FileSystem::load_with_fnt_order(files_dir, num_reserved, external_files, &fnt_order)?
+            };
+            files.set_empty_dir_file_ids(&config.empty_dir_file_ids);
+            if let Some(compressed_files) = &config.compressed_files {
+                Self::compress_files(&mut files, path, &path.join(compressed_files), &rom_files, &pool)?;
+            }
             let path_order =
                 read_to_string(path.join(&config.path_order))?.trim().lines().map(|l| l.to_string()).collect::<Vec<_>>();
-            (files, path_order)
+            let file_order = match &config.file_order {
+                Some(file_order) => {
+                    Some(read_to_string(path.join(file_order))?.trim().lines().map(|l| l.to_string()).collect())
+                }
+                None => None,
+            };
+            (files, path_order, file_order, aliased_files)
         } else {
-            (FileSystem::new(num_overlays), vec![])
+            (FileSystem::new(num_reserved), vec![], None, vec![])
         };
 
-        Ok(Self { header, header_logo, arm9, arm9_overlays, arm7, arm7_overlays, banner, files, path_order, config })
+        Ok(Self {
+            header,
+            header_logo,
+            pre_arm9,
+            trailing_data,
+            arm9,
+            dtcm_base_address,
+            arm9_footer,
+            blowfish_key_sha1: arm9_build_config.blowfish_key_sha1,
+            arm9_config_extra: arm9_build_config.extra,
+            arm9_overlays,
+            arm9_overlay_table,
+            arm7,
+            arm7_overlays,
+            dsi,
+            banner,
+            files,
+            path_order,
+            file_order,
+            aliased_files,
+            compression_report,
+            dev_padding: options.dev_padding,
+            strict_header: options.strict_header || config.preservation == Some(PreservationLevel::Exact),
+            config,
+        })
+    }
+
+    fn load_dsi_area(path: &Path, config: &RomConfigDsi) -> Result<DsiArea<'static>, RomSaveError> {
+        let arm9i_offsets = serde_yml::from_reader(open_file(path.join(&config.arm9i_config))?)?;
+        let arm9i = DsiProgram::new(read_file(path.join(&config.arm9i_bin))?, arm9i_offsets);
+        let arm7i_offsets = serde_yml::from_reader(open_file(path.join(&config.arm7i_config))?)?;
+        let arm7i = DsiProgram::new(read_file(path.join(&config.arm7i_bin))?, arm7i_offsets);
+        let digest_sector_hashtable = read_file(path.join(&config.digest_sector_hashtable))?;
+        let digest_block_hashtable = read_file(path.join(&config.digest_block_hashtable))?;
+        Ok(DsiArea::new(arm9i, arm7i, digest_sector_hashtable, digest_block_hashtable))
+    }
+
+    /// Reads the list of aliased files and adds an empty placeholder for each of them, so that they keep their path and ID.
+    fn load_aliased_files(
+        aliased_files_path: &Path,
+        files_dir: &Path,
+        external_files: &mut BTreeMap<PathBuf, Vec<u8>>,
+    ) -> Result<Vec<AliasedFile>, RomSaveError> {
+        let mut aliased_files: Vec<AliasedFile> = serde_yml::from_reader(open_file(aliased_files_path)?)?;
+        aliased_files.retain(|aliased_file| {
+            if files_dir.join(&aliased_file.path).is_file() {
+                log::info!("Using {} from disk instead of the {}", aliased_file.path, aliased_file.section);
+                return false;
+            }
+            external_files.insert(PathBuf::from(&aliased_file.path), vec![]);
+            true
+        });
+        Ok(aliased_files)
+    }
+
+    fn load_skipped_files(
+        skipped_files_path: &Path,
+        files_dir: &Path,
+        options: &RomLoadOptions,
+    ) -> Result<BTreeMap<PathBuf, Vec<u8>>, RomSaveError> {
+        let skipped_files: Vec<SkippedFile> = serde_yml::from_reader(open_file(skipped_files_path)?)?;
+        let mut external_files = BTreeMap::new();
+        for skipped_file in skipped_files {
+            let path = skipped_file.path;
+            if files_dir.join(&path).is_file() {
+                log::info!("Using {path} from disk instead of the original ROM");
+                continue;
+            }
+            let Some(original_rom) = options.original_rom else {
+                return OriginalRomNeededSnafu { path }.fail();
+            };
+
+            let start = skipped_file.rom_offset as usize;
+            let end = start + skipped_file.size as usize;
+            let Some(contents) = original_rom.data().get(start..end) else {
+                return SkippedFileMismatchSnafu { path }.fail();
+            };
+            if Sha1::digest(contents) != skipped_file.sha1 {
+                return SkippedFileMismatchSnafu { path }.fail();
+            }
+            external_files.insert(PathBuf::from(path), contents.to_vec());
+        }
+        Ok(external_files)
     }
 
-    fn load_overlays(config_path: &Path, processor: &str, options: &RomLoadOptions) -> Result<Vec<Overlay<'a>>, RomSaveError> {
+    fn load_overlays(
+        config_path: &Path,
+        processor: Processor,
+        options: &RomLoadOptions,
+        pool: &ThreadPool,
+        compression_report: &mut CompressionReport,
+    ) -> Result<Vec<Overlay<'a>>, RomSaveError> {
         let path = config_path.parent().unwrap();
         let mut overlays = vec![];
         let overlay_configs: Vec<OverlayConfig> = serde_yml::from_reader(open_file(config_path)?)?;
         let num_overlays = overlay_configs.len();
+        let mut file_name_ids = HashMap::new();
         for mut config in overlay_configs.into_iter() {
-            let data = read_file(path.join(config.file_name))?;
+            if config.placeholder {
+                let mut overlay = Overlay::placeholder(config.info);
+                overlay.set_config_extra(config.extra);
+                overlays.push(overlay);
+                continue;
+            }
+            if let Some(first_id) = file_name_ids.insert(config.file_name.clone(), config.info.id) {
+                let (file_name, second_id) = (config.file_name, config.info.id);
+                return DuplicateOverlayFileNameSnafu { file_name, first_id, second_id }.fail();
+            }
+            let file_path = path.join(&config.file_name);
+            if !file_path.is_file() {
+                return MissingOverlayFileSnafu { id: config.info.id, path: file_path }.fail();
+            }
+            let data = read_file(file_path)?;
             let compressed = config.info.compressed;
             config.info.compressed = false;
             let mut overlay = Overlay::new(data, config.info, compressed);
-            if compressed && options.compress {
-                log::info!("Compressing {processor} overlay {}/{}", overlay.id(), num_overlays - 1);
-                overlay.compress()?;
-            }
+            overlay.set_config_extra(config.extra);
+            overlay.set_file_name(Some(config.file_name));
             overlays.push(overlay);
         }
+        if options.compress {
+            let start = Instant::now();
+            let mut jobs = overlays.iter_mut().map(|overlay| (overlay, None)).collect::<Vec<_>>();
+            pool.try_for_each(&mut jobs, |(overlay, module)| {
+                if overlay.originally_compressed() {
+                    let uncompressed_size = overlay.full_data().len() as u32;
+                    let cached = options.build_cache.and_then(|cache| cache.compressed_overlay(overlay.full_data()));
+                    if let Some(compressed) = cached {
+                        log::info!("Reusing compressed {} overlay {} from the build cache", processor.as_str(), overlay.id());
+                        overlay.set_compressed_data(compressed.to_vec());
+                    } else {
+                        log::info!("Compressing {} overlay {}/{}", processor.as_str(), overlay.id(), num_overlays - 1);
+                        overlay.compress()?;
+                    }
+                    let compression = ModuleCompression {
+                        name: format!("{processor} overlay {}", overlay.id()),
+                        uncompressed_size,
+                        compressed_size: overlay.full_data().len() as u32,
+                    };
+                    log::info!("{compression}");
+                    *module = Some(compression);
+                }
+                Ok::<_, RomSaveError>(())
+            })?;
+            compression_report.add_time(start.elapsed());
+            compression_report.modules.extend(jobs.into_iter().filter_map(|(_, module)| module));
+        }
         Ok(overlays)
     }
 
@@ -354,34 +887,247 @@ impl<'a> Rom<'a> {
     ///
     /// This function will return an error if a file could not be created or the a component of the ROM has an invalid format.
     pub fn save<P: AsRef<Path>>(&self, path: P, key: Option<&BlowfishKey>) -> Result<(), RomSaveError> {
-        let path = path.as_ref();
+        self.save_with_options(path, RomSaveOptions { key, ..Default::default() })
+    }
+
+    fn should_skip_file(file: &File, path: &str, options: &RomSaveOptions) -> bool {
+        // Only files which came from a ROM can be loaded from it later
+        if file.original_offset() == 0 {
+            return false;
+        }
+        options.skip_files_larger_than.is_some_and(|max_size| file.size() > max_size)
+            || options.skip_files_matching.iter().any(|pattern| glob_match(pattern, path))
+    }
+
+    /// Decompresses the files matching `rules`, and returns their records along with their decompressed contents by file ID.
+    /// Files which fail to decompress are saved as-is.
+    fn decompress_files(
+        &self,
+        rules: &[TransparentCompression],
+        skipped_file_ids: &HashSet<u16>,
+        pool: &ThreadPool,
+    ) -> (Vec<CompressedFile>, HashMap<u16, Box<[u8]>>) {
+        if rules.is_empty() {
+            return (vec![], HashMap::new());
+        }
+        let mut jobs = vec![];
+        self.files.traverse_files(["/"], |file, dir| {
+            if skipped_file_ids.contains(&file.id()) {
+                return;
+            }
+            let file_path = dir.join(file.name()).to_string_lossy().replace('\\', "/");
+            if let Some(format) = TransparentCompression::find_format(rules, &file_path) {
+                jobs.push((file.id(), file_path, format, None));
+            }
+        });
+
+        let files = &self.files;
+        let Ok(()) = pool.try_for_each(&mut jobs, |(id, file_path, format, result)| {
+            let Ok(contents) = files.file(*id).read_contents() else {
+                // Saving the file as-is will report the error
+                return Ok(());
+            };
+            let Ok(decompressed) = format.decompress(&contents) else {
+                log::warn!("{file_path} could not be decompressed as {format}, saving it as-is");
+                return Ok::<_, Infallible>(());
+            };
+            let sha1 = Sha1::digest(&contents);
+            let original = (Sha1::digest(&format.compress(&decompressed)) != sha1).then(|| {
+                log::info!("Compressing {file_path} doesn't reproduce the original, keeping a copy of it");
+                PathBuf::from("compressed_originals").join(&file_path)
+            });
+            let size = contents.len() as u32;
+            let compressed_file = CompressedFile { path: file_path.clone(), format: *format, size, sha1, original };
+            *result = Some((compressed_file, decompressed));
+            Ok(())
+        });
+
+        let mut compressed_files = vec![];
+        let mut decompressed_files = HashMap::new();
+        for (id, _, _, result) in jobs {
+            if let Some((compressed_file, decompressed)) = result {
+                compressed_files.push(compressed_file);
+                decompressed_files.insert(id, decompressed);
+            }
+        }
+        (compressed_files, decompressed_files)
+    }
+
+    /// Compresses the files which were decompressed by [`Self::decompress_files`]. Files in `excluded_paths` were loaded
+    /// from the original ROM and are left as-is.
+    fn compress_files(
+        files: &mut FileSystem,
+        path: &Path,
+        compressed_files_path: &Path,
+        excluded_paths: &HashSet<PathBuf>,
+        pool: &ThreadPool,
+    ) -> Result<(), RomSaveError> {
+        let compressed_files: Vec<CompressedFile> = serde_yml::from_reader(open_file(compressed_files_path)?)?;
+        let mut jobs = compressed_files
+            .into_iter()
+            .filter(|file| !excluded_paths.contains(Path::new(&file.path)))
+            .filter_map(|file| Some((files.find_path(&file.path)?, file.format, file.original, vec![])))
+            .collect::<Vec<_>>();
+
+        let files_ref = &*files;
+        pool.try_for_each(&mut jobs, |(id, format, original, result)| {
+            let contents = files_ref.file(*id).read_contents()?;
+            if let Some(original) = original {
+                let original = read_file(path.join(original))?;
+                if format.decompress(&original).is_ok_and(|decompressed| *decompressed == *contents) {
+                    *result = original;
+                    return Ok(());
+                }
+            }
+            *result = format.compress(&contents).into_vec();
+            Ok::<_, RomSaveError>(())
+        })?;
+
+        for (id, _, _, compressed) in jobs {
+            files.set_file_contents(id, compressed);
+        }
+        Ok(())
+    }
+
+    /// Saves this ROM to a path as separate files, see [`RomSaveOptions`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a file could not be created or the a component of the ROM has an invalid format.
+    pub fn save_with_options<P: AsRef<Path>>(&self, path: P, options: RomSaveOptions) -> Result<(), RomSaveError> {
+        self.save_with_summary(path, options).map(|_| ()).map_err(|error| *error.source)
+    }
+
+    /// Saves this ROM like [`Self::save_with_options`], and returns which files were written. If saving fails partway, the
+    /// error lists the files which were written before it. Saving again with [`RomSaveOptions::skip_unchanged_files`] then
+    /// only writes the files which are missing or incomplete.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::save_with_options`].
+    pub fn save_with_summary<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: RomSaveOptions,
+    ) -> Result<SaveSummary, PartialSaveError> {
+        let writer = SaveWriter::new(options.skip_unchanged_files);
+        match self.save_to_writer(path.as_ref(), options, &writer) {
+            Ok(()) => Ok(writer.into_summary()),
+            Err(source) => Err(PartialSaveError { source: Box::new(source), summary: writer.into_summary() }),
+        }
+    }
+
+    fn save_to_writer(&self, path: &Path, options: RomSaveOptions, writer: &SaveWriter) -> Result<(), RomSaveError> {
         create_dir_all(path)?;
+        let pool = ThreadPool::new(options.concurrency)?;
 
         log::info!("Saving ROM to directory {}", path.display());
 
+        let aliased_paths = self.aliased_files.iter().map(|file| file.path.as_str()).collect::<HashSet<_>>();
+        let mut skipped_files = vec![];
+        let mut skipped_file_ids = HashSet::new();
+        let mut read_error = None;
+        self.files.traverse_files(["/"], |file, dir| {
+            let file_path = dir.join(file.name()).to_string_lossy().replace('\\', "/");
+            if aliased_paths.contains(file_path.as_str()) {
+                // Aliased files are rebuilt from the section they refer to
+                skipped_file_ids.insert(file.id());
+            } else if Self::should_skip_file(file, &file_path, &options) {
+                log::info!("Skipping {file_path} ({})", BlobSize(file.size()));
+                skipped_file_ids.insert(file.id());
+                match file.read_contents() {
+                    Ok(contents) => skipped_files.push(SkippedFile {
+                        path: file_path,
+                        size: file.size() as u32,
+                        rom_offset: file.original_offset(),
+                        sha1: Sha1::digest(&contents),
+                    }),
+                    Err(error) => {
+                        read_error.get_or_insert(error);
+                    }
+                }
+            }
+        });
+        if let Some(error) = read_error {
+            return Err(error.into());
+        }
+
+        let transparent_compression = if options.transparent_compression.is_empty() {
+            &self.config.transparent_compression
+        } else {
+            &options.transparent_compression
+        };
+        let (compressed_files, decompressed_files) = self.decompress_files(transparent_compression, &skipped_file_ids, &pool);
+
         // --------------------- Save config ---------------------
-        serde_yml::to_writer(create_file_and_dirs(path.join("config.yaml"))?, &self.config)?;
+        let fnt_order = self.files.fnt_order();
+        let mut config = self.config.clone();
+        config.skipped_files = (!skipped_files.is_empty())
+            .then(|| self.config.skipped_files.clone().unwrap_or_else(|| "skipped_files.yaml".into()));
+        config.aliased_files = (!self.aliased_files.is_empty())
+            .then(|| self.config.aliased_files.clone().unwrap_or_else(|| "aliased_files.yaml".into()));
+        config.transparent_compression = transparent_compression.clone();
+        config.compressed_files = (!compressed_files.is_empty())
+            .then(|| self.config.compressed_files.clone().unwrap_or_else(|| "compressed_files.yaml".into()));
+        if let Some(dedup_store) = &options.dedup_store {
+            config.dedup_store = Some(dedup_store.clone());
+        }
+        config.fnt_order =
+            fnt_order.is_some().then(|| self.config.fnt_order.clone().unwrap_or_else(|| "fnt_order.txt".into()));
+        config.empty_dir_file_ids = self.files.empty_dir_file_ids();
+        config.file_order =
+            self.file_order.is_some().then(|| self.config.file_order.clone().unwrap_or_else(|| "file_order.txt".into()));
+        writer.write_yaml(path.join("config.yaml"), &config)?;
+        if let Some(skipped_files_path) = &config.skipped_files {
+            writer.write_yaml(path.join(skipped_files_path), &skipped_files)?;
+        }
+        if let Some(aliased_files_path) = &config.aliased_files {
+            writer.write_yaml(path.join(aliased_files_path), &self.aliased_files)?;
+        }
+        if let Some(compressed_files_path) = &config.compressed_files {
+            writer.write_yaml(path.join(compressed_files_path), &compressed_files)?;
+            for compressed_file in &compressed_files {
+                let (Some(original), Some(id)) = (&compressed_file.original, self.files.find_path(&compressed_file.path))
+                else {
+                    continue;
+                };
+                writer.write(path.join(original), self.files.file(id).read_contents()?)?;
+            }
+        }
 
         // --------------------- Save header ---------------------
-        serde_yml::to_writer(create_file_and_dirs(path.join(&self.config.header))?, &self.header)?;
-        self.header_logo.save_png(path.join(&self.config.header_logo))?;
+        writer.write_yaml(path.join(&self.config.header), &self.header)?;
+        let header_logo_path = path.join(&self.config.header_logo);
+        self.header_logo.save_png(&header_logo_path)?;
+        writer.record_existing(&header_logo_path);
+        if let (Some(pre_arm9_path), Some(pre_arm9)) = (&self.config.pre_arm9, &self.pre_arm9) {
+            writer.write(path.join(pre_arm9_path), pre_arm9)?;
+        }
+        if let (Some(trailing_data_path), Some(trailing_data)) = (&self.config.trailing_data, &self.trailing_data) {
+            writer.write(path.join(trailing_data_path), trailing_data)?;
+        }
 
         // --------------------- Save ARM9 program ---------------------
-        let arm9_build_config = self.arm9_build_config()?;
-        serde_yml::to_writer(create_file_and_dirs(path.join(&self.config.arm9_config))?, &arm9_build_config)?;
+        let mut arm9_build_config = self.arm9_build_config()?;
         let mut plain_arm9 = self.arm9.clone();
-        if plain_arm9.is_encrypted() {
-            let Some(key) = key else {
+        if arm9_build_config.no_secure_area {
+            log::info!("ARM9 program has no secure area, it will not be decrypted");
+        } else if plain_arm9.is_encrypted() {
+            let keys = options.key_candidates();
+            if keys.is_empty() {
                 return BlowfishKeyNeededSnafu {}.fail();
-            };
+            }
             log::info!("Decrypting ARM9 program");
-            plain_arm9.decrypt(key, self.header.original.gamecode.to_le_u32())?;
+            if let Some(index) = plain_arm9.decrypt_with_any_key(&keys, self.header.original.gamecode.to_le_u32())? {
+                arm9_build_config.blowfish_key_sha1 = Some(keys[index].sha1());
+            }
         }
+        writer.write_yaml(path.join(&self.config.arm9_config), &arm9_build_config)?;
         if plain_arm9.is_compressed()? {
             log::info!("Decompressing ARM9 program");
             plain_arm9.decompress()?;
         }
-        create_file_and_dirs(path.join(&self.config.arm9_bin))?.write(plain_arm9.code()?)?;
+        writer.write(path.join(&self.config.arm9_bin), plain_arm9.code()?)?;
 
         // --------------------- Save autoloads ---------------------
         let mut unknown_autoloads = self.config.unknown_autoloads.iter();
@@ -394,50 +1140,93 @@ impl<'a> Rom<'a> {
                     (path.join(&unknown_autoload.bin), path.join(&unknown_autoload.config))
                 }
             };
-            create_file_and_dirs(bin_path)?.write(autoload.code())?;
-            serde_yml::to_writer(create_file_and_dirs(config_path)?, autoload.info())?;
+            writer.write(bin_path, autoload.code())?;
+            writer.write_yaml(config_path, autoload.info())?;
         }
 
         // --------------------- Save ARM9 overlays ---------------------
         if let Some(arm9_overlays_config) = &self.config.arm9_overlays {
-            Self::save_overlays(&path.join(arm9_overlays_config), &self.arm9_overlays, "arm9")?;
+            Self::save_overlays(&path.join(arm9_overlays_config), &self.arm9_overlays, Processor::Arm9, &pool, writer)?;
+        }
+
+        if let (Some(arm9_ovt_bin), Some(arm9_overlay_table)) = (&self.config.arm9_ovt_bin, &self.arm9_overlay_table) {
+            writer.write(path.join(arm9_ovt_bin), arm9_overlay_table.as_raw_bytes())?;
         }
 
         // --------------------- Save ARM7 program ---------------------
-        create_file_and_dirs(path.join(&self.config.arm7_bin))?.write(self.arm7.full_data())?;
-        serde_yml::to_writer(create_file_and_dirs(path.join(&self.config.arm7_config))?, self.arm7.offsets())?;
+        writer.write(path.join(&self.config.arm7_bin), self.arm7.full_data())?;
+        writer.write_yaml(path.join(&self.config.arm7_config), self.arm7.offsets())?;
 
         // --------------------- Save ARM7 overlays ---------------------
         if let Some(arm7_overlays_config) = &self.config.arm7_overlays {
-            Self::save_overlays(&path.join(arm7_overlays_config), &self.arm7_overlays, "arm7")?;
+            Self::save_overlays(&path.join(arm7_overlays_config), &self.arm7_overlays, Processor::Arm7, &pool, writer)?;
+        }
+
+        // --------------------- Save DSi area ---------------------
+        if let (Some(dsi_config), Some(dsi)) = (&self.config.dsi, &self.dsi) {
+            writer.write(path.join(&dsi_config.arm9i_bin), dsi.arm9i().full_data())?;
+            writer.write_yaml(path.join(&dsi_config.arm9i_config), dsi.arm9i().offsets())?;
+            writer.write(path.join(&dsi_config.arm7i_bin), dsi.arm7i().full_data())?;
+            writer.write_yaml(path.join(&dsi_config.arm7i_config), dsi.arm7i().offsets())?;
+            writer.write(path.join(&dsi_config.digest_sector_hashtable), dsi.digest_sector_hashtable())?;
+            writer.write(path.join(&dsi_config.digest_block_hashtable), dsi.digest_block_hashtable())?;
         }
 
         // --------------------- Save banner ---------------------
-        {
-            let banner_path = path.join(&self.config.banner);
+        if let (Some(banner_path), Some(banner)) = (&self.config.banner, &self.banner) {
+            let banner_path = path.join(banner_path);
             let banner_dir = banner_path.parent().unwrap();
-            serde_yml::to_writer(create_file_and_dirs(&banner_path)?, &self.banner)?;
-            self.banner.images.save_bitmap_file(banner_dir)?;
+            let mut banner = banner.clone();
+            if options.split_banner_titles {
+                banner.save_title_files(banner_dir)?;
+                let title_files = banner.title_files.as_ref().unwrap();
+                for file_name in LANGUAGES.into_iter().filter_map(|language| title_files.get(language)) {
+                    writer.record_existing(&banner_dir.join(file_name));
+                }
+            }
+            writer.write_yaml(&banner_path, &banner)?;
+            banner.images.save_bitmap_file(banner_dir)?;
+            for image_path in banner.images.file_paths() {
+                writer.record_existing(&banner_dir.join(image_path));
+            }
         }
 
         // --------------------- Save files ---------------------
         {
             log::info!("Saving ROM assets");
             let files_path = path.join(&self.config.files_dir);
-            self.files.traverse_files(["/"], |file, path| {
-                let path = files_path.join(path);
-                // TODO: Rewrite traverse_files as an iterator so these errors can be returned
-                create_dir_all(&path).expect("failed to create file directory");
-                create_file(&path.join(file.name()))
-                    .expect("failed to create file")
-                    .write(file.contents())
-                    .expect("failed to write file");
-            });
+            let mut files = vec![];
+            self.files.traverse_files(["/"], |file, path| files.push((file.id(), files_path.join(path))));
+            for (id, dir) in files {
+                let file = self.files.file(id);
+                create_dir_all(&dir)?;
+                if skipped_file_ids.contains(&file.id()) {
+                    continue;
+                }
+                let contents = match decompressed_files.get(&id) {
+                    Some(contents) => Cow::Borrowed(&**contents),
+                    None => file.read_contents()?,
+                };
+                let file_path = dir.join(file.name());
+                match &options.dedup_store {
+                    Some(dedup_store) => {
+                        dedup_store.save_file(path, &file_path, &contents)?;
+                        writer.record(&file_path, contents.len() as u64);
+                    }
+                    None => writer.write(file_path, &contents)?,
+                }
+            }
+            for empty_dir in self.files.empty_dirs() {
+                create_dir_all(files_path.join(empty_dir))?;
+            }
+        }
+        let lines = |paths: &[String]| paths.iter().map(|path| format!("{path}\n")).collect::<String>();
+        writer.write(path.join(&self.config.path_order), lines(&self.path_order))?;
+        if let (Some(fnt_order_path), Some(fnt_order)) = (&config.fnt_order, fnt_order) {
+            writer.write(path.join(fnt_order_path), lines(&fnt_order))?;
         }
-        let mut path_order_file = create_file_and_dirs(path.join(&self.config.path_order))?;
-        for path in &self.path_order {
-            path_order_file.write(path.as_bytes())?;
-            path_order_file.write("\n".as_bytes())?;
+        if let (Some(file_order_path), Some(file_order)) = (&config.file_order, &self.file_order) {
+            writer.write(path.join(file_order_path), lines(file_order))?;
         }
 
         Ok(())
@@ -448,61 +1237,269 @@ impl<'a> Rom<'a> {
         Ok(Arm9BuildConfig {
             offsets: *self.arm9.offsets(),
             encrypted: self.arm9.is_encrypted(),
+            no_secure_area: self.arm9.secure_area_state() == SecureAreaState::Absent,
             compressed: self.arm9.is_compressed()?,
             build_info: self.arm9.build_info()?.clone().into(),
+            blowfish_key_sha1: self.blowfish_key_sha1,
+            // The footer refers to the overlay signature table, which is saved as its location instead
+            footer: match self.arm9.overlay_signatures() {
+                Some(_) => Arm9FooterConfig { reserved: 0, ..self.arm9_footer },
+                None => self.arm9_footer,
+            },
+            lenient_build_info: self.arm9.lenient_build_info(),
+            compression_start: (self.arm9.compression_start() != DEFAULT_COMPRESSION_START)
+                .then_some(self.arm9.compression_start() as u32),
+            extra: self.arm9_config_extra.clone(),
         })
     }
 
-    fn save_overlays(config_path: &Path, overlays: &[Overlay], processor: &str) -> Result<(), RomSaveError> {
+    fn save_overlays(
+        config_path: &Path,
+        overlays: &[Overlay],
+        processor: Processor,
+        pool: &ThreadPool,
+        writer: &SaveWriter,
+    ) -> Result<(), RomSaveError> {
         if !overlays.is_empty() {
             let overlays_path = config_path.parent().unwrap();
             create_dir_all(overlays_path)?;
 
-            let mut configs = vec![];
-            for overlay in overlays {
-                let name = format!("ov{:03}", overlay.id());
-
-                let mut plain_overlay = overlay.clone();
-                configs.push(OverlayConfig { info: plain_overlay.info().clone(), file_name: format!("{name}.bin") });
-
+            let max_id = overlays.iter().map(|overlay| overlay.id()).max().unwrap_or(0);
+            let configs = overlays
+                .iter()
+                .map(|overlay| OverlayConfig {
+                    info: overlay.info().clone(),
+                    file_name: if overlay.is_placeholder() {
+                        String::new()
+                    } else {
+                        overlay
+                            .file_name()
+                            .map(|file_name| file_name.to_string())
+                            .unwrap_or_else(|| Overlay::default_file_name(overlay.id(), max_id))
+                    },
+                    placeholder: overlay.is_placeholder(),
+                    extra: overlay.config_extra().clone(),
+                })
+                .collect::<Vec<_>>();
+
+            let mut overlay_files = overlays
+                .iter()
+                .zip(configs.iter().map(|config| &config.file_name))
+                .filter(|(overlay, _)| !overlay.is_placeholder())
+                .collect::<Vec<_>>();
+            pool.try_for_each(&mut overlay_files, |(overlay, file_name)| {
+                let mut plain_overlay = (*overlay).clone();
                 if plain_overlay.is_compressed() {
-                    log::info!("Decompressing {processor} overlay {}/{}", overlay.id(), overlays.len() - 1);
+                    log::info!("Decompressing {} overlay {}/{}", processor.as_str(), overlay.id(), overlays.len() - 1);
                     plain_overlay.decompress()?;
                 }
-                create_file(overlays_path.join(format!("{name}.bin")))?.write(plain_overlay.code())?;
-            }
-            serde_yml::to_writer(create_file(config_path)?, &configs)?;
+                writer.write(overlays_path.join(file_name), plain_overlay.code())?;
+                Ok::<_, RomSaveError>(())
+            })?;
+            writer.write_yaml(config_path, &configs)?;
         }
         Ok(())
     }
 
-    /// Extracts from a raw ROM.
+    /// Returns the offsets of sections which were not placed at the next aligned offset in the original ROM, as
+    /// [`Self::build`] would otherwise not reproduce them. The ARM9 footer only counts towards the end of the programs if
+    /// `has_arm9_footer` is true.
+    fn custom_section_offsets(
+        header: &raw::Header,
+        fat: &[FileAlloc],
+        num_overlays: usize,
+        has_arm9_footer: bool,
+    ) -> Option<RomConfigSectionOffsets> {
+        let align = |offset: u32| (offset + 0x1ff) & !0x1ff;
+        let differs = |offset: u32, default: u32| (offset != default).then_some(offset);
+
+        let arm9_footer_size = if has_arm9_footer { size_of::<Arm9Footer>() as u32 } else { 0 };
+        let programs_end = [
+            header.arm9.offset + header.arm9.size + arm9_footer_size,
+            header.arm9_overlays.offset + header.arm9_overlays.size,
+            header.arm7.offset + header.arm7.size,
+            header.arm7_overlays.offset + header.arm7_overlays.size,
+        ]
+        .into_iter()
+        .chain(fat.iter().take(num_overlays).map(|alloc| alloc.end))
+        .max()
+        .unwrap_or(0);
+
+        let mut section_offsets = RomConfigSectionOffsets {
+            fnt: differs(header.file_names.offset, align(programs_end)),
+            fat: differs(header.file_allocs.offset, align(header.file_names.offset + header.file_names.size)),
+            banner: differs(header.banner_offset, align(header.file_allocs.offset + header.file_allocs.size))
+                .filter(|_| header.banner_offset != 0),
+            ..Default::default()
+        };
+        if header.has_dsi_area() {
+            let sectors = header.digest_sector_hashtable;
+            let blocks = header.digest_block_hashtable;
+            let sectors_end = sectors.offset + sectors.size;
+            let blocks_end = blocks.offset + blocks.size;
+            section_offsets.digest_sector_hashtable = differs(sectors.offset, align(header.rom_size_ds));
+            section_offsets.digest_block_hashtable = differs(blocks.offset, align(sectors_end));
+            section_offsets.arm9i = differs(header.arm9i.offset, align(blocks_end).next_multiple_of(DSI_REGION_ALIGNMENT));
+            section_offsets.arm7i = differs(header.arm7i.offset, align(header.arm9i.offset + header.arm9i.size));
+        }
+        let is_default = section_offsets.fnt.is_none()
+            && section_offsets.fat.is_none()
+            && section_offsets.banner.is_none()
+            && section_offsets.digest_sector_hashtable.is_none()
+            && section_offsets.digest_block_hashtable.is_none()
+            && section_offsets.arm9i.is_none()
+            && section_offsets.arm7i.is_none();
+        (!is_default).then_some(section_offsets)
+    }
+
+    /// Extracts from a raw ROM, preserving its layout with [`PreservationLevel::Standard`].
     ///
     /// # Errors
     ///
     /// This function will return an error if a component is missing from the raw ROM.
     pub fn extract(rom: &'a raw::Rom) -> Result<Self, RomExtractError> {
+        Self::extract_with_options(rom, RomExtractOptions::default())
+    }
+
+    /// Extracts from a raw ROM, preserving as much of its layout as the options say. See
+    /// [`RomExtractOptions::preservation`] for presets.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a component is missing from the raw ROM.
+    pub fn extract_with_options(rom: &'a raw::Rom, options: RomExtractOptions) -> Result<Self, RomExtractError> {
         let header = rom.header()?;
         let fnt = rom.fnt()?;
         let fat = rom.fat()?;
         let banner = rom.banner()?;
-        let file_root = FileSystem::parse(&fnt, fat, rom)?;
-        let path_order = file_root.compute_path_order();
+        let mut file_root = FileSystem::parse(&fnt, fat, rom)?;
+        let aliased_files = Self::find_aliased_files(header, banner.as_ref(), &file_root);
+        for aliased_file in &aliased_files {
+            log::info!("{} refers to the {}, it will not be extracted", aliased_file.path, aliased_file.section);
+        }
+        match rom.file_regions()? {
+            Some(regions) if regions.dsi_files > 0 => log::warn!(
+                "{regions}. Files will not be kept in the DSi region when building, which may break the ROM on DSi consoles"
+            ),
+            Some(regions) => log::info!("{regions}"),
+            None => {}
+        }
+        let ordering = file_root.analyze_ordering();
+        if !ordering.is_fnt_sorted() {
+            if options.preserve_fnt_order {
+                log::info!(
+                    "FNT is not sorted in {} directories, preserving its order in fnt_order.txt",
+                    ordering.unsorted_dirs.len()
+                );
+            } else {
+                log::warn!(
+                    "FNT is not sorted in {} directories, file IDs will change when building",
+                    ordering.unsorted_dirs.len()
+                );
+                file_root.clear_fnt_order();
+            }
+        }
+        if ordering.file_image_differs && options.preserve_path_order {
+            log::info!("Files are not stored in file ID order, preserving their order in path_order.txt");
+        }
+        let path_order = if options.preserve_path_order { file_root.compute_path_order() } else { vec!["/".to_string()] };
+        let file_order = options.full_file_order.then(|| file_root.compute_file_order());
+
+        let mut arm9 = rom.arm9()?;
+        let decompressed_arm9 = if arm9.is_compressed()? {
+            let mut decompressed_arm9 = arm9.clone();
+            decompressed_arm9.decompress()?;
+            Some(decompressed_arm9)
+        } else {
+            None
+        };
+
+        let dtcm_base_address =
+            decompressed_arm9.as_ref().unwrap_or(&arm9).autoload_infos().ok().and_then(|infos| {
+                infos.iter().find(|info| info.kind() == raw::AutoloadKind::Dtcm).map(|dtcm| dtcm.base_address)
+            });
 
-        let arm9_overlays =
-            rom.arm9_overlay_table()?.iter().map(|ov| Overlay::parse(ov, fat, rom)).collect::<Result<Vec<_>, _>>()?;
+        let arm9_overlays = rom
+            .arm9_overlay_table_with(decompressed_arm9.as_ref().unwrap_or(&arm9))?
+            .iter()
+            .map(|ov| Overlay::parse(ov, fat, rom))
+            .collect::<Result<Vec<_>, _>>()?;
         let arm7_overlays =
             rom.arm7_overlay_table()?.iter().map(|ov| Overlay::parse(ov, fat, rom)).collect::<Result<Vec<_>, _>>()?;
+        for (processor, overlays) in [(Processor::Arm9, &arm9_overlays), (Processor::Arm7, &arm7_overlays)] {
+            let placeholders = overlays.iter().filter(|ov| ov.is_placeholder()).map(|ov| ov.id()).collect::<Vec<_>>();
+            if !placeholders.is_empty() {
+                log::warn!(
+                    "{processor} overlays {placeholders:?} are placeholder entries with no code, they will be kept in the overlay table without a file"
+                );
+            }
+        }
+        let signed_overlays = arm9_overlays.iter().filter(|ov| ov.is_signed()).map(|ov| ov.id()).collect::<Vec<_>>();
+        if !signed_overlays.is_empty() {
+            log::warn!(
+                "ARM9 overlays {signed_overlays:?} are flagged as signed, their signatures will not be verified or updated when building"
+            );
+        }
+
+        let pre_arm9 = rom.pre_arm9()?;
+        let trailing_data = rom.trailing_data()?.filter(|_| options.preserve_trailing_data);
+        if let Some(trailing_data) = trailing_data {
+            log::info!(
+                "Found {:#x} bytes of data after the end of the ROM, it will be appended when building",
+                trailing_data.len()
+            );
+        }
+        if let (false, Ok(footer)) = (signed_overlays.is_empty(), rom.arm9_footer()) {
+            let overlay_signatures =
+                decompressed_arm9.as_ref().unwrap_or(&arm9).detect_overlay_signatures(footer.reserved).ok().flatten();
+            if let Some(overlay_signatures) = overlay_signatures {
+                log::info!("Found the overlay signature table at {:#x} in the ARM9 footer", footer.reserved);
+                arm9.set_overlay_signatures(Some(overlay_signatures));
+            }
+        }
+        let has_arm9_footer = rom.arm9_footer().is_ok();
+        let arm9_footer = match rom.arm9_footer() {
+            Ok(footer) => Arm9FooterConfig::from_raw(footer, arm9.build_info_offset()),
+            Err(_) => Arm9FooterConfig::default(),
+        };
 
-        let arm9 = rom.arm9()?;
+        let mut dsi = rom.dsi_area()?;
+        if let Some(dsi) = &mut dsi {
+            let modcrypt_areas = header.modcrypt_areas();
+            if !modcrypt_areas.is_empty() {
+                log::info!("Decrypting {} modcrypt areas, they will be encrypted again when building", modcrypt_areas.len());
+                dsi.apply_modcrypt(&modcrypt_areas, header.arm9i.offset, header.arm7i.offset);
+            }
+        }
 
-        let num_unknown_autoloads = if arm9.is_compressed()? {
-            let mut decompressed_arm9 = arm9.clone();
-            decompressed_arm9.decompress()?;
-            decompressed_arm9.num_unknown_autoloads()?
-        } else {
-            arm9.num_unknown_autoloads()?
+        let empty_fnt = header.file_names.size == 0;
+        if empty_fnt {
+            let overlay_file_ids = arm9_overlays
+                .iter()
+                .chain(&arm7_overlays)
+                .filter(|ov| !ov.is_placeholder())
+                .map(|ov| ov.file_id())
+                .collect::<Vec<_>>();
+            let num_unnamed = fat
+                .iter()
+                .enumerate()
+                .filter(|(id, alloc)| alloc.end > alloc.start && !overlay_file_ids.contains(&(*id as u32)))
+                .count();
+            if num_unnamed > 0 {
+                log::warn!(
+                    "FNT is empty but {num_unnamed} FAT entries besides overlays have data, which will not be preserved when building"
+                );
+            }
+        }
+
+        let num_unknown_autoloads = decompressed_arm9.as_ref().unwrap_or(&arm9).num_unknown_autoloads()?;
+        let num_overlays = arm9_overlays.len() + arm7_overlays.len();
+        let num_used_fat_entries = Self::num_used_fat_entries(&arm9_overlays, &arm7_overlays, &file_root);
+        let fat_layout = FatLayout {
+            reserved_entries: (file_root.num_reserved() != num_overlays).then_some(file_root.num_reserved() as u16),
+            num_entries: (fat.len() > num_used_fat_entries).then_some(fat.len() as u32),
         };
+
         let unknown_autoloads = (0..num_unknown_autoloads)
             .map(|index| RomConfigAutoload {
                 bin: format!("arm9/unk_autoload_{index}.bin").into(),
@@ -511,9 +1508,11 @@ impl<'a> Rom<'a> {
             .collect();
 
         let config = RomConfig {
-            padding_value: rom.padding_value()?,
+            padding_value: if options.preserve_padding_value { rom.padding_value()? } else { 0xff },
             header: "header.yaml".into(),
             header_logo: "header_logo.png".into(),
+            pre_arm9: pre_arm9.map(|_| "pre_arm9.bin".into()),
+            trailing_data: trailing_data.map(|_| "trailing_data.bin".into()),
             arm9_bin: "arm9/arm9.bin".into(),
             arm9_config: "arm9/arm9.yaml".into(),
             arm7_bin: "arm7/arm7.bin".into(),
@@ -521,164 +1520,729 @@ impl<'a> Rom<'a> {
             itcm: RomConfigAutoload { bin: "arm9/itcm.bin".into(), config: "arm9/itcm.yaml".into() },
             unknown_autoloads,
             dtcm: RomConfigAutoload { bin: "arm9/dtcm.bin".into(), config: "arm9/dtcm.yaml".into() },
+            dsi: dsi.as_ref().map(|_| RomConfigDsi {
+                arm9i_bin: "arm9i/arm9i.bin".into(),
+                arm9i_config: "arm9i/arm9i.yaml".into(),
+                arm7i_bin: "arm7i/arm7i.bin".into(),
+                arm7i_config: "arm7i/arm7i.yaml".into(),
+                digest_sector_hashtable: "dsi/digest_sectors.bin".into(),
+                digest_block_hashtable: "dsi/digest_blocks.bin".into(),
+            }),
             arm9_overlays: if arm9_overlays.is_empty() { None } else { Some("arm9_overlays/overlays.yaml".into()) },
+            arm9_ovt_bin: None,
             arm7_overlays: if arm7_overlays.is_empty() { None } else { Some("arm7_overlays/overlays.yaml".into()) },
-            banner: "banner/banner.yaml".into(),
+            banner: banner.is_some().then(|| "banner/banner.yaml".into()),
             files_dir: "files/".into(),
             path_order: "path_order.txt".into(),
+            fnt_order: (options.preserve_fnt_order && !ordering.is_fnt_sorted()).then(|| "fnt_order.txt".into()),
+            empty_dir_file_ids: file_root.empty_dir_file_ids(),
+            file_order: file_order.as_ref().map(|_| "file_order.txt".into()),
+            skipped_files: None,
+            aliased_files: (!aliased_files.is_empty()).then(|| "aliased_files.yaml".into()),
+            transparent_compression: vec![],
+            compressed_files: None,
+            dedup_store: None,
+            memory_limits: None,
+            section_offsets: Self::custom_section_offsets(header, fat, num_overlays, has_arm9_footer)
+                .filter(|_| options.preserve_section_offsets),
+            fat_layout: (options.preserve_fat_layout && !fat_layout.is_default()).then_some(fat_layout),
+            empty_fnt,
+            dedup_identical_files: false,
+            preservation: options.level,
+            extra: serde_yml::Mapping::new(),
         };
 
+        let mut plain_header = Header::load_raw(header);
+        if !options.preserve_capacity {
+            plain_header.original.capacity = None;
+        }
+        plain_header.original.preserve_capacity = options.exact_capacity && plain_header.original.capacity.is_some();
+
         Ok(Self {
-            header: Header::load_raw(&header),
+            header: plain_header,
             header_logo: Logo::decompress(&header.logo)?,
+            pre_arm9: pre_arm9.map(Cow::Borrowed),
+            trailing_data: trailing_data.map(Cow::Borrowed),
             arm9,
+            dtcm_base_address,
+            arm9_footer,
+            blowfish_key_sha1: None,
+            arm9_config_extra: serde_yml::Mapping::new(),
             arm9_overlays,
+            arm9_overlay_table: None,
             arm7: rom.arm7()?,
             arm7_overlays,
-            banner: Banner::load_raw(&banner),
+            dsi,
+            banner: banner.as_ref().map(Banner::load_raw),
             files: file_root,
             path_order,
+            file_order,
+            aliased_files,
+            compression_report: CompressionReport::default(),
+            dev_padding: false,
+            strict_header: options.level == Some(PreservationLevel::Exact),
             config,
         })
     }
 
-    /// Builds a raw ROM.
-    ///
-    /// # Errors
-    ///
-    /// This function will return an error if an I/O operation fails or a component fails to build.
-    pub fn build(mut self, key: Option<&BlowfishKey>) -> Result<raw::Rom<'a>, RomBuildError> {
-        let mut context = BuildContext::default();
-        context.blowfish_key = key;
+    /// Returns the files whose contents are exactly one of the ROM's other sections.
+    fn find_aliased_files(header: &raw::Header, banner: Option<&raw::Banner>, files: &FileSystem) -> Vec<AliasedFile> {
+        let sections = [
+            (AliasedSection::Arm9, header.arm9.offset, header.arm9.size),
+            (AliasedSection::Arm9OverlayTable, header.arm9_overlays.offset, header.arm9_overlays.size),
+            (AliasedSection::Arm7, header.arm7.offset, header.arm7.size),
+            (AliasedSection::Arm7OverlayTable, header.arm7_overlays.offset, header.arm7_overlays.size),
+            (AliasedSection::Banner, header.banner_offset, banner.map_or(0, |banner| banner.full_data().len() as u32)),
+        ];
+        let mut aliased_files = vec![];
+        files.traverse_files(["/"], |file, dir| {
+            let size = file.size() as u32;
+            if size == 0 {
+                return;
+            }
+            let Some(&(section, ..)) =
+                sections.iter().find(|&&(_, offset, section_size)| file.original_offset() == offset && size == section_size)
+            else {
+                return;
+            };
+            let path = dir.join(file.name()).to_string_lossy().replace('\\', "/");
+            aliased_files.push(AliasedFile { path, section, size });
+        });
+        aliased_files
+    }
 
-        let mut cursor = Cursor::new(Vec::with_capacity(128 * 1024)); // smallest possible ROM
+    /// Returns a copy of this [`Rom`] which owns all of its data, so that it can outlive the raw ROM it was extracted from.
+    pub fn to_owned(&self) -> Rom<'static> {
+        Rom {
+            header: self.header.clone(),
+            header_logo: self.header_logo.clone(),
+            pre_arm9: self.pre_arm9.as_ref().map(|pre_arm9| Cow::Owned(pre_arm9.to_vec())),
+            trailing_data: self.trailing_data.as_ref().map(|trailing_data| Cow::Owned(trailing_data.to_vec())),
+            arm9: self.arm9.to_owned(),
+            dtcm_base_address: self.dtcm_base_address,
+            arm9_footer: self.arm9_footer,
+            blowfish_key_sha1: self.blowfish_key_sha1,
+            arm9_config_extra: self.arm9_config_extra.clone(),
+            arm9_overlays: self.arm9_overlays.iter().map(Overlay::to_owned).collect(),
+            arm9_overlay_table: self.arm9_overlay_table.clone(),
+            arm7: self.arm7.to_owned(),
+            arm7_overlays: self.arm7_overlays.iter().map(Overlay::to_owned).collect(),
+            dsi: self.dsi.as_ref().map(DsiArea::to_owned),
+            banner: self.banner.clone(),
+            files: self.files.to_owned(),
+            path_order: self.path_order.clone(),
+            file_order: self.file_order.clone(),
+            aliased_files: self.aliased_files.clone(),
+            compression_report: self.compression_report.clone(),
+            dev_padding: self.dev_padding,
+            strict_header: self.strict_header,
+            config: self.config.clone(),
+        }
+    }
 
-        // --------------------- Write header placeholder ---------------------
-        context.header_offset = Some(cursor.position() as u32);
-        cursor.write(&[0u8; size_of::<raw::Header>()])?;
-        self.align(&mut cursor)?;
+    /// Converts this [`Rom`] into one which owns all of its data, so that it can outlive the raw ROM it was extracted from.
+    /// Data which is already owned, such as decompressed modules or files loaded by [`Self::load`], is moved instead of
+    /// copied.
+    ///
+    /// ```no_run
+    /// use ds_rom::rom::{raw, Rom};
+    ///
+    /// fn extract_rom(path: &str) -> Rom<'static> {
+    ///     let raw_rom = raw::Rom::from_file(path).unwrap();
+    ///     let rom = Rom::extract(&raw_rom).unwrap();
+    ///     rom.into_owned()
+    /// }
+    ///
+    /// let rom = extract_rom("rom.nds");
+    /// println!("{}", rom.header().original.title);
+    /// ```
+    pub fn into_owned(self) -> Rom<'static> {
+        Rom {
+            header: self.header,
+            header_logo: self.header_logo,
+            pre_arm9: self.pre_arm9.map(|pre_arm9| Cow::Owned(pre_arm9.into_owned())),
+            trailing_data: self.trailing_data.map(|trailing_data| Cow::Owned(trailing_data.into_owned())),
+            arm9: self.arm9.into_owned(),
+            dtcm_base_address: self.dtcm_base_address,
+            arm9_footer: self.arm9_footer,
+            blowfish_key_sha1: self.blowfish_key_sha1,
+            arm9_config_extra: self.arm9_config_extra,
+            arm9_overlays: self.arm9_overlays.into_iter().map(Overlay::into_owned).collect(),
+            arm9_overlay_table: self.arm9_overlay_table,
+            arm7: self.arm7.into_owned(),
+            arm7_overlays: self.arm7_overlays.into_iter().map(Overlay::into_owned).collect(),
+            dsi: self.dsi.map(DsiArea::into_owned),
+            banner: self.banner,
+            files: self.files.into_owned(),
+            path_order: self.path_order,
+            file_order: self.file_order,
+            aliased_files: self.aliased_files,
+            compression_report: self.compression_report,
+            dev_padding: self.dev_padding,
+            strict_header: self.strict_header,
+            config: self.config,
+        }
+    }
+
+    /// Returns the planned layout of [`Self::build`] without building anything. If a program or overlay is configured to be
+    /// compressed but was loaded without compressing it, its size is estimated from its compressed size in the config, or
+    /// from its uncompressed size if there is none.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the FNT or banner fails to build, or a section is placed after its configured
+    /// offset.
+    pub fn plan_layout(&mut self) -> Result<RomLayout, RomBuildError> {
+        let banner = self.banner.as_ref().map(Banner::build).transpose()?;
+        let (layout, _) = self.compute_layout(true, banner.as_ref())?;
+        Ok(layout)
+    }
+
+    fn overlay_size(overlay: &Overlay, estimate_sizes: bool) -> (u32, bool) {
+        let size = overlay.full_data().len() as u32;
+        if estimate_sizes && overlay.originally_compressed() && !overlay.is_compressed() {
+            let compressed_size = overlay.info().flags.size() as u32;
+            (if compressed_size != 0 { compressed_size } else { size }, true)
+        } else {
+            (size, false)
+        }
+    }
+
+    fn place_overlays(
+        overlays: &[Overlay],
+        position: &mut u32,
+        file_allocs: &mut [FileAlloc],
+        estimate_sizes: bool,
+    ) -> (Option<RomLayoutSection>, Vec<RomLayoutSection>) {
+        if overlays.is_empty() {
+            return (None, vec![]);
+        }
+        let table_size = (overlays.len() * size_of::<raw::Overlay>()) as u32;
+        let table = Self::place_section(position, table_size, false);
+        let sections = overlays
+            .iter()
+            .map(|overlay| {
+                let (size, estimated) = Self::overlay_size(overlay, estimate_sizes);
+                let section = Self::place_section(position, size, estimated);
+                if overlay.is_placeholder() {
+                    // Placeholders have no file, so their file ID may belong to another overlay
+                    return section;
+                }
+                file_allocs[overlay.file_id() as usize] = FileAlloc { start: section.offset, end: section.end() };
+                section
+            })
+            .collect();
+        (Some(table), sections)
+    }
+
+    /// Returns the file IDs in the order they're placed in the ROM, following [`Self::file_order`] if it is set and
+    /// [`Self::path_order`] otherwise.
+    fn file_ids_in_rom_order(&mut self) -> Result<Vec<u16>, FileOrderError> {
+        if let Some(file_order) = &self.file_order {
+            return self.files.resolve_file_order(file_order);
+        }
+        self.files.sort_for_rom();
+        let mut ids = vec![];
+        // Files which the path order doesn't cover, such as ones added with FileSystem::add_file, are placed last
+        let path_order = self.path_order.iter().map(|s| s.as_str()).chain(["/"]);
+        self.files.traverse_files(path_order, |file, _| ids.push(file.id()));
+        Ok(ids)
+    }
+
+    fn place_section(position: &mut u32, size: u32, estimated: bool) -> RomLayoutSection {
+        let section = RomLayoutSection { offset: *position, size, estimated };
+        *position = Self::align(section.end());
+        section
+    }
+
+    /// Moves `position` to the configured `offset` of a section, if any. The offset doesn't have to be aligned, but it must
+    /// not be before `previous_end`, where the preceding content ends.
+    fn seek_section_offset(
+        position: &mut u32,
+        previous_end: u32,
+        section: &'static str,
+        offset: Option<u32>,
+    ) -> Result<(), RomBuildError> {
+        let Some(offset) = offset else { return Ok(()) };
+        if previous_end > offset {
+            return SectionOffsetExceededSnafu { section, offset, position: previous_end as u64 }.fail();
+        }
+        *position = offset;
+        Ok(())
+    }
+
+    /// Returns the number of FAT entries needed to fit all overlays and files.
+    fn num_used_fat_entries(arm9_overlays: &[Overlay], arm7_overlays: &[Overlay], files: &FileSystem) -> usize {
+        let max_overlay_file_id = arm9_overlays
+            .iter()
+            .chain(arm7_overlays)
+            .filter(|overlay| !overlay.is_placeholder())
+            .map(|overlay| overlay.file_id() as usize + 1)
+            .max();
+        files.num_fat_entries().max(max_overlay_file_id.unwrap_or(0))
+    }
+
+    fn align(position: u32) -> u32 {
+        (position + 0x1ff) & !0x1ff
+    }
+
+    /// Computes the layout used by both [`Self::plan_layout`] and [`Self::build`], and returns it along with the built FNT.
+    fn compute_layout(
+        &mut self,
+        estimate_sizes: bool,
+        banner: Option<&raw::Banner>,
+    ) -> Result<(RomLayout, Box<[u8]>), RomBuildError> {
+        let section_offsets = self.config.section_offsets.unwrap_or_default();
+        let mut position = 0;
+
+        let header = RomLayoutSection { offset: 0, size: size_of::<raw::Header>() as u32, estimated: false };
+        position = position.max(header.end());
+        let pre_arm9 = self.pre_arm9.as_ref().map(|pre_arm9| RomLayoutSection {
+            offset: self.header.version().used_size() as u32,
+            size: pre_arm9.len() as u32,
+            estimated: false,
+        });
+        if let Some(pre_arm9) = &pre_arm9 {
+            position = position.max(pre_arm9.end());
+        }
+        position = Self::align(position);
+
+        let arm9_estimated = estimate_sizes
+            && self.arm9.originally_compressed()
+            && self.arm9.is_compressed().is_ok_and(|compressed| !compressed);
+        let arm9_size = (self.arm9.full_data().len() + size_of::<Arm9Footer>()) as u32;
+        let arm9 = Self::place_section(&mut position, arm9_size, arm9_estimated);
+
+        let num_fat_entries = Self::num_used_fat_entries(&self.arm9_overlays, &self.arm7_overlays, &self.files)
+            .max(self.config.fat_layout.and_then(|layout| layout.num_entries).unwrap_or(0) as usize);
+        let mut file_allocs = vec![FileAlloc::default(); num_fat_entries];
+
+        let (arm9_overlay_table, arm9_overlays) =
+            Self::place_overlays(&self.arm9_overlays, &mut position, &mut file_allocs, estimate_sizes);
+        let arm7 = Self::place_section(&mut position, self.arm7.full_data().len() as u32, false);
+        let (arm7_overlay_table, arm7_overlays) =
+            Self::place_overlays(&self.arm7_overlays, &mut position, &mut file_allocs, estimate_sizes);
+
+        self.files.sort_for_fnt();
+        let empty_fnt = self.config.empty_fnt && self.files.is_empty();
+        if self.config.empty_fnt && !empty_fnt {
+            log::warn!("The FNT was empty in the original ROM, but files have been added so it will not be empty");
+        }
+        // Planning the layout only needs the size of the FNT
+        let fnt_data: Box<[u8]> = if empty_fnt || estimate_sizes { Box::new([]) } else { self.files.build_fnt()?.build()? };
+        let fnt_size = if estimate_sizes && !empty_fnt { self.files.fnt_size()? } else { fnt_data.len() };
+        let programs_end = [arm9, arm7]
+            .into_iter()
+            .chain(arm9_overlay_table)
+            .chain(arm7_overlay_table)
+            .chain(arm9_overlays.iter().copied())
+            .chain(arm7_overlays.iter().copied())
+            .map(|section| section.end())
+            .max()
+            .unwrap_or(0);
+        Self::seek_section_offset(&mut position, programs_end, "FNT", section_offsets.fnt)?;
+        let fnt = Self::place_section(&mut position, fnt_size as u32, false);
+
+        Self::seek_section_offset(&mut position, fnt.end(), "FAT", section_offsets.fat)?;
+        let fat = Self::place_section(&mut position, fat_size(file_allocs.len()), false);
+
+        let banner = if let Some(banner) = banner {
+            Self::seek_section_offset(&mut position, fat.end(), "banner", section_offsets.banner)?;
+            Some(Self::place_section(&mut position, banner.full_data().len() as u32, false))
+        } else {
+            None
+        };
+
+        let file_order = self.file_ids_in_rom_order()?;
+        let aliased_files = self
+            .aliased_files
+            .iter()
+            .filter_map(|aliased_file| Some((self.files.find_path(&aliased_file.path)?, aliased_file)))
+            .collect::<Vec<_>>();
+        let file_order = file_order
+            .into_iter()
+            .filter(|id| !aliased_files.iter().any(|(aliased_id, _)| aliased_id == id))
+            .collect::<Vec<_>>();
+        let mut stored_files = HashMap::<[u8; 0x14], u16>::new();
+        let mut shared_files = vec![];
+        let mut stored_order = Vec::with_capacity(file_order.len());
+        let files_start = position;
+        for id in file_order {
+            let file = self.files.file(id);
+            if self.config.dedup_identical_files && file.size() > 0 {
+                let sha1 = Sha1::digest(&file.read_contents()?);
+                if let Some(&stored_id) = stored_files.get(&sha1) {
+                    file_allocs[id as usize] = file_allocs[stored_id as usize];
+                    shared_files.push((id, stored_id));
+                    continue;
+                }
+                stored_files.insert(sha1, id);
+            }
+            position = Self::align(position);
+            let start = position;
+            let end = start + file.size() as u32;
+            file_allocs[id as usize] = FileAlloc { start, end };
+            stored_order.push(id);
+            position = end;
+        }
+        let files = (position > files_start).then_some(RomLayoutSection {
+            offset: files_start,
+            size: position - files_start,
+            estimated: false,
+        });
+
+        for (id, aliased_file) in aliased_files {
+            let section = match aliased_file.section {
+                AliasedSection::Arm9 => Some(RomLayoutSection { size: self.arm9.full_data().len() as u32, ..arm9 }),
+                AliasedSection::Arm9OverlayTable => arm9_overlay_table,
+                AliasedSection::Arm7 => Some(arm7),
+                AliasedSection::Arm7OverlayTable => arm7_overlay_table,
+                AliasedSection::Banner => banner,
+            };
+            let Some(section) = section else {
+                log::warn!("{} refers to the {}, which no longer exists", aliased_file.path, aliased_file.section);
+                continue;
+            };
+            if section.size != aliased_file.size {
+                log::warn!(
+                    "{} refers to the {}, which changed size from {:#x} to {:#x} bytes",
+                    aliased_file.path,
+                    aliased_file.section,
+                    aliased_file.size,
+                    section.size
+                );
+            }
+            file_allocs[id as usize] = FileAlloc { start: section.offset, end: section.end() };
+        }
+
+        let rom_size = position;
+        let dsi = self.dsi.as_ref().map(|dsi| Self::place_dsi_area(dsi, rom_size, &section_offsets)).transpose()?;
+        let total_size = dsi.map(|dsi| dsi.rom_size).unwrap_or(rom_size);
+        let padded_size = if self.dev_padding {
+            Self::align(total_size)
+        } else if total_size >= 128 * 1024 {
+            total_size.next_power_of_two()
+        } else {
+            total_size
+        };
+        let trailing_data = self.trailing_data.as_ref().map(|trailing_data| RomLayoutSection {
+            offset: padded_size,
+            size: trailing_data.len() as u32,
+            estimated: false,
+        });
+
+        let layout = RomLayout {
+            header,
+            pre_arm9,
+            arm9,
+            arm9_overlay_table,
+            arm9_overlays,
+            arm7,
+            arm7_overlay_table,
+            arm7_overlays,
+            fnt,
+            fat,
+            banner,
+            files,
+            file_allocs,
+            file_order: stored_order,
+            shared_files,
+            dsi,
+            rom_size,
+            padded_size,
+            trailing_data,
+        };
+        Ok((layout, fnt_data))
+    }
+
+    /// Places the DSi area after the DS area which ends at `rom_size`. The ARM9i program starts the DSi region, so it is
+    /// aligned to [`DSI_REGION_ALIGNMENT`].
+    fn place_dsi_area(
+        dsi: &DsiArea,
+        rom_size: u32,
+        section_offsets: &RomConfigSectionOffsets,
+    ) -> Result<RomLayoutDsi, RomBuildError> {
+        let mut position = Self::align(rom_size);
+        Self::seek_section_offset(
+            &mut position,
+            rom_size,
+            "digest sector hashtable",
+            section_offsets.digest_sector_hashtable,
+        )?;
+        let digest_sector_hashtable = Self::place_section(&mut position, dsi.digest_sector_hashtable().len() as u32, false);
+        Self::seek_section_offset(
+            &mut position,
+            digest_sector_hashtable.end(),
+            "digest block hashtable",
+            section_offsets.digest_block_hashtable,
+        )?;
+        let digest_block_hashtable = Self::place_section(&mut position, dsi.digest_block_hashtable().len() as u32, false);
+
+        position = position.next_multiple_of(DSI_REGION_ALIGNMENT);
+        Self::seek_section_offset(&mut position, digest_block_hashtable.end(), "ARM9i program", section_offsets.arm9i)?;
+        let region_start = position & !(DSI_REGION_ALIGNMENT - 1);
+        let arm9i = Self::place_section(&mut position, dsi.arm9i().full_data().len() as u32, false);
+        Self::seek_section_offset(&mut position, arm9i.end(), "ARM7i program", section_offsets.arm7i)?;
+        let arm7i = Self::place_section(&mut position, dsi.arm7i().full_data().len() as u32, false);
+
+        Ok(RomLayoutDsi { digest_sector_hashtable, digest_block_hashtable, arm9i, arm7i, region_start, rom_size: arm7i.end() })
+    }
+
+    /// Builds a raw ROM in memory. See [`Self::build_to_writer`] to write it elsewhere without holding a copy of the ROM.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails or a component fails to build.
+    pub fn build(self, key: Option<&BlowfishKey>) -> Result<raw::Rom<'a>, RomBuildError> {
+        let mut cursor = Cursor::new(vec![]);
+        self.build_to_writer(&mut cursor, key)?;
+        Ok(raw::Rom::new(cursor.into_inner()))
+    }
+
+    /// Builds a ROM and streams it to `writer`, starting at its current position. Sections are written in order, and the
+    /// writer only seeks back to write the header once everything else is written.
+    ///
+    /// ```no_run
+    /// use std::{fs::File, io::BufWriter};
+    ///
+    /// use ds_rom::rom::Rom;
+    ///
+    /// let rom = Rom::load("extract/config.yaml", Default::default()).unwrap();
+    /// let mut writer = BufWriter::new(File::create("rom.nds").unwrap());
+    /// let summary = rom.build_to_writer(&mut writer, None).unwrap();
+    /// println!("{summary}");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an I/O operation fails or a component fails to build.
+    pub fn build_to_writer<W: Write + Seek>(
+        mut self,
+        writer: W,
+        key: Option<&BlowfishKey>,
+    ) -> Result<BuildSummary, RomBuildError> {
+        for finding in self.config.memory_limits.unwrap_or_default().check(&self) {
+            log::warn!("{finding}");
+        }
+        let header_findings = self.header.check_consistency(&self);
+        if self.strict_header && !header_findings.is_empty() {
+            let findings = header_findings.iter().map(|finding| finding.to_string()).collect::<Vec<_>>().join("\n");
+            return InconsistentHeaderSnafu { findings }.fail();
+        }
+        for finding in header_findings {
+            log::warn!("{finding}");
+        }
+
+        let banner = self.banner.as_ref().map(Banner::build).transpose()?;
+        let (layout, fnt) = self.compute_layout(false, banner.as_ref())?;
+        if self.dev_padding {
+            log::warn!(
+                "Building a development ROM: padded to {:#x} bytes instead of {:#x}, and the capacity is kept from the original \
+                ROM. Don't distribute or verify this ROM",
+                layout.padded_size,
+                if layout.rom_size >= 128 * 1024 { layout.rom_size.next_power_of_two() } else { layout.rom_size }
+            );
+            self.header.original.preserve_capacity |= self.header.original.capacity.is_some();
+        }
+        if !layout.shared_files.is_empty() {
+            log::warn!(
+                "Deduplicated {} identical files, saving {}. Some games may not expect files to share data, for example if \
+                they write to a file through one path and read the original contents through another",
+                layout.shared_files.len(),
+                BlobSize(layout.shared_bytes() as usize)
+            );
+        }
+        // The header is built first, since it has the modcrypt keys and counters
+        let context = self.build_context(&layout, key);
+        let header = self.header.build(&context, &self)?;
+        let mut cursor = RomWriter::new(writer)?;
+        self.write_layout(&mut cursor, &layout, &fnt, banner.as_ref(), &header.modcrypt_areas())?;
+        let size = cursor.position();
+
+        // --------------------- Update header ---------------------
+        cursor.set_position(layout.header.offset as u64)?;
+        cursor.write_all(bytemuck::bytes_of(&header))?;
+        self.write_pre_arm9(&mut cursor)?;
+        cursor.set_position(size)?;
+        cursor.flush()?;
+
+        Ok(BuildSummary { layout, size })
+    }
+
+    /// Returns the [`BuildContext`] for a ROM built with the given layout.
+    fn build_context<'k>(&self, layout: &RomLayout, key: Option<&'k BlowfishKey>) -> BuildContext<'k> {
+        BuildContext {
+            header_offset: Some(layout.header.offset),
+            arm9_offset: Some(layout.arm9.offset),
+            arm7_offset: Some(layout.arm7.offset),
+            fnt_offset: Some(layout.fnt.table_offset()),
+            fat_offset: Some(layout.fat.table_offset()),
+            arm9_ovt_offset: layout.arm9_overlay_table.map(|table| table.table_offset()),
+            arm7_ovt_offset: layout.arm7_overlay_table.map(|table| table.table_offset()),
+            banner_offset: Some(layout.banner.map(|banner| banner.table_offset()).unwrap_or_default()),
+            blowfish_key: key,
+            arm9_autoload_callback: Some(self.arm9.autoload_callback()),
+            arm7_autoload_callback: Some(self.arm7.autoload_callback()),
+            arm9_build_info_offset: Some(self.arm9.build_info_offset()),
+            arm7_build_info_offset: None,
+            rom_size: Some(layout.rom_size),
+            arm9i_offset: layout.dsi.map(|dsi| dsi.arm9i.offset),
+            arm7i_offset: layout.dsi.map(|dsi| dsi.arm7i.offset),
+            digest_sector_hashtable_offset: layout.dsi.map(|dsi| dsi.digest_sector_hashtable.table_offset()),
+            digest_block_hashtable_offset: layout.dsi.map(|dsi| dsi.digest_block_hashtable.table_offset()),
+            dsi_region_start: layout.dsi.map(|dsi| dsi.region_start),
+            dsi_rom_size: layout.dsi.map(|dsi| dsi.rom_size),
+        }
+    }
+
+    /// Writes every section of the ROM according to the layout, except for the header which is left zeroed.
+    fn write_layout<W: Write + Seek>(
+        &self,
+        cursor: &mut RomWriter<W>,
+        layout: &RomLayout,
+        fnt: &[u8],
+        banner: Option<&raw::Banner>,
+        modcrypt_areas: &[ModcryptArea],
+    ) -> Result<(), RomBuildError> {
+        layout.validate()?;
+
+        // --------------------- Write header placeholder ---------------------
+        self.pad_to(cursor, layout.header.offset)?;
+        cursor.write_all(&[0u8; size_of::<raw::Header>()])?;
+        self.write_pre_arm9(cursor)?;
 
         // --------------------- Write ARM9 program ---------------------
-        context.arm9_offset = Some(cursor.position() as u32);
-        context.arm9_autoload_callback = Some(self.arm9.autoload_callback());
-        context.arm9_build_info_offset = Some(self.arm9.build_info_offset());
-        cursor.write(self.arm9.full_data())?;
-        let footer = Arm9Footer::new(self.arm9.build_info_offset());
-        cursor.write(bytemuck::bytes_of(&footer))?;
-        self.align(&mut cursor)?;
-
-        let max_file_id = self.files.max_file_id();
-        let mut file_allocs = vec![FileAlloc::default(); max_file_id as usize + 1];
-
-        if !self.arm9_overlays.is_empty() {
+        self.pad_to(cursor, layout.arm9.offset)?;
+        cursor.write_all(self.arm9.full_data())?;
+        let footer = self.arm9_footer.build(self.arm9.build_info_offset());
+        cursor.write_all(bytemuck::bytes_of(&footer))?;
+
+        if let Some(table) = layout.arm9_overlay_table {
             // --------------------- Write ARM9 overlay table ---------------------
-            context.arm9_ovt_offset = Some(TableOffset {
-                offset: cursor.position() as u32,
-                size: (self.arm9_overlays.len() * size_of::<raw::Overlay>()) as u32,
-            });
-            for overlay in &self.arm9_overlays {
-                let raw = overlay.build();
-                cursor.write(bytemuck::bytes_of(&raw))?;
+            self.pad_to(cursor, table.offset)?;
+            if let Some(arm9_overlay_table) = &self.arm9_overlay_table {
+                cursor.write_all(arm9_overlay_table.as_raw_bytes())?;
+            } else {
+                for overlay in &self.arm9_overlays {
+                    let raw = overlay.build();
+                    cursor.write_all(bytemuck::bytes_of(&raw))?;
+                }
             }
-            self.align(&mut cursor)?;
 
             // --------------------- Write ARM9 overlays ---------------------
-            for overlay in &self.arm9_overlays {
-                let start = cursor.position() as u32;
-                let end = start + overlay.full_data().len() as u32;
-                file_allocs[overlay.file_id() as usize] = FileAlloc { start, end };
-
-                cursor.write(overlay.full_data())?;
-                self.align(&mut cursor)?;
+            for (overlay, section) in self.arm9_overlays.iter().zip(&layout.arm9_overlays) {
+                self.pad_to(cursor, section.offset)?;
+                cursor.write_all(overlay.full_data())?;
             }
         }
 
         // --------------------- Write ARM7 program ---------------------
-        context.arm7_offset = Some(cursor.position() as u32);
-        context.arm7_autoload_callback = Some(self.arm7.autoload_callback());
-        context.arm7_build_info_offset = None;
-        cursor.write(self.arm7.full_data())?;
-        self.align(&mut cursor)?;
+        self.pad_to(cursor, layout.arm7.offset)?;
+        cursor.write_all(self.arm7.full_data())?;
 
-        if !self.arm7_overlays.is_empty() {
+        if let Some(table) = layout.arm7_overlay_table {
             // --------------------- Write ARM7 overlay table ---------------------
-            context.arm7_ovt_offset = Some(TableOffset {
-                offset: cursor.position() as u32,
-                size: (self.arm7_overlays.len() * size_of::<raw::Overlay>()) as u32,
-            });
+            self.pad_to(cursor, table.offset)?;
             for overlay in &self.arm7_overlays {
                 let raw = overlay.build();
-                cursor.write(bytemuck::bytes_of(&raw))?;
+                cursor.write_all(bytemuck::bytes_of(&raw))?;
             }
-            self.align(&mut cursor)?;
 
             // --------------------- Write ARM7 overlays ---------------------
-            for overlay in &self.arm7_overlays {
-                let start = cursor.position() as u32;
-                let end = start + overlay.full_data().len() as u32;
-                file_allocs[overlay.file_id() as usize] = FileAlloc { start, end };
-
-                cursor.write(overlay.full_data())?;
-                self.align(&mut cursor)?;
+            for (overlay, section) in self.arm7_overlays.iter().zip(&layout.arm7_overlays) {
+                self.pad_to(cursor, section.offset)?;
+                cursor.write_all(overlay.full_data())?;
             }
         }
 
         // --------------------- Write file name table (FNT) ---------------------
-        self.files.sort_for_fnt();
-        let fnt = self.files.build_fnt()?.build()?;
-        context.fnt_offset = Some(TableOffset { offset: cursor.position() as u32, size: fnt.len() as u32 });
-        cursor.write(&fnt)?;
-        self.align(&mut cursor)?;
-
-        // --------------------- Write file allocation table (FAT) placeholder ---------------------
-        context.fat_offset =
-            Some(TableOffset { offset: cursor.position() as u32, size: (file_allocs.len() * size_of::<FileAlloc>()) as u32 });
-        cursor.write(bytemuck::cast_slice(&file_allocs))?;
-        self.align(&mut cursor)?;
-
-        // --------------------- Write banner ---------------------
-        let banner = self.banner.build()?;
-        context.banner_offset = Some(TableOffset { offset: cursor.position() as u32, size: banner.full_data().len() as u32 });
-        cursor.write(banner.full_data())?;
-        self.align(&mut cursor)?;
+        self.pad_to(cursor, layout.fnt.offset)?;
+        cursor.write_all(fnt)?;
 
-        // --------------------- Write files ---------------------
-        self.files.sort_for_rom();
-        self.files.traverse_files(self.path_order.iter().map(|s| s.as_str()), |file, _| {
-            // TODO: Rewrite traverse_files as an iterator so these errors can be returned
-            self.align(&mut cursor).expect("failed to align before file");
+        // --------------------- Write file allocation table (FAT) ---------------------
+        self.pad_to(cursor, layout.fat.offset)?;
+        cursor.write_all(bytemuck::cast_slice(&layout.file_allocs))?;
 
-            let contents = file.contents();
-            let start = cursor.position() as u32;
-            let end = start + contents.len() as u32;
-            file_allocs[file.id() as usize] = FileAlloc { start, end };
+        if let (Some(banner), Some(section)) = (banner, layout.banner) {
+            // --------------------- Write banner ---------------------
+            self.pad_to(cursor, section.offset)?;
+            cursor.write_all(banner.full_data())?;
+        }
 
-            cursor.write(contents).expect("failed to write file contents");
-        });
+        // --------------------- Write files ---------------------
+        for &id in &layout.file_order {
+            self.pad_to(cursor, layout.file_allocs[id as usize].start)?;
+            cursor.write_all(&self.files.file(id).read_contents()?)?;
+        }
+
+        self.pad_to(cursor, layout.rom_size)?;
+
+        if let (Some(dsi), Some(dsi_layout)) = (&self.dsi, &layout.dsi) {
+            // --------------------- Write digest hashtables ---------------------
+            self.pad_to(cursor, dsi_layout.digest_sector_hashtable.offset)?;
+            cursor.write_all(dsi.digest_sector_hashtable())?;
+            self.pad_to(cursor, dsi_layout.digest_block_hashtable.offset)?;
+            cursor.write_all(dsi.digest_block_hashtable())?;
+
+            // --------------------- Write ARM9i and ARM7i programs ---------------------
+            for (program, section) in [(dsi.arm9i(), dsi_layout.arm9i), (dsi.arm7i(), dsi_layout.arm7i)] {
+                let mut program = DsiProgram::new(program.full_data(), *program.offsets());
+                program.apply_modcrypt(section.offset, modcrypt_areas);
+                self.pad_to(cursor, section.offset)?;
+                cursor.write_all(program.full_data())?;
+            }
+        }
 
         // --------------------- Write padding ---------------------
-        context.rom_size = Some(cursor.position() as u32);
-        while !cursor.position().is_power_of_two() && cursor.position() >= 128 * 1024 {
-            cursor.write(&[self.config.padding_value])?;
+        self.pad_to(cursor, layout.padded_size)?;
+
+        // --------------------- Write trailing data ---------------------
+        if let Some(trailing_data) = &self.trailing_data {
+            cursor.write_all(trailing_data)?;
         }
+        Ok(())
+    }
 
-        // --------------------- Update FAT ---------------------
-        cursor.set_position(context.fat_offset.unwrap().offset as u64);
-        cursor.write(&bytemuck::cast_slice(&file_allocs))?;
+    /// Compares a built ROM to the original, header field by header field, section by section and file by file. At most
+    /// [`DEFAULT_MAX_MISMATCHES`] differing byte ranges are collected, use [`RomComparison::compute`] to change the limit.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header, overlay tables, FNT, FAT or banner of either ROM is invalid.
+    pub fn compare(original: &raw::Rom, other: &raw::Rom) -> Result<RomComparison, RomDiffError> {
+        RomComparison::compute(original, other, DEFAULT_MAX_MISMATCHES)
+    }
 
-        // --------------------- Update header ---------------------
-        cursor.set_position(context.header_offset.unwrap() as u64);
-        let header = self.header.build(&context, &self)?;
-        cursor.write(bytemuck::bytes_of(&header))?;
+    /// Opens a ROM embedded as a file in this ROM's file system, such as a DS Download Play child. See
+    /// [`FileSystem::open_embedded_rom`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path is not a file, or the file doesn't contain a valid ROM header.
+    pub fn open_embedded_rom(&self, path: &str) -> Result<raw::Rom<'_>, RomEmbeddedError> {
+        self.files.open_embedded_rom(path)
+    }
 
-        Ok(raw::Rom::new(cursor.into_inner()))
+    fn write_pre_arm9<W: Write + Seek>(&self, cursor: &mut RomWriter<W>) -> Result<(), RomBuildError> {
+        let Some(pre_arm9) = &self.pre_arm9 else {
+            return Ok(());
+        };
+        // Writing past the end of the header also extends the ROM, so the ARM9 program will be placed after this region
+        let offset = self.header.version().used_size() as u64;
+        let end = cursor.position().max(offset + pre_arm9.len() as u64);
+        cursor.set_position(offset)?;
+        cursor.write_all(pre_arm9)?;
+        cursor.set_position(end)?;
+        Ok(())
     }
 
-    fn align(&self, cursor: &mut Cursor<Vec<u8>>) -> Result<(), RomBuildError> {
-        let padding = (!cursor.position() + 1) & 0x1ff;
-        for _ in 0..padding {
-            cursor.write(&[self.config.padding_value])?;
+    /// Pads the ROM up to `offset`, which must not be behind the current position.
+    fn pad_to<W: Write + Seek>(&self, cursor: &mut RomWriter<W>, offset: u32) -> Result<(), RomBuildError> {
+        let position = cursor.position();
+        if position > offset as u64 {
+            return PaddingOverrunSnafu { offset, position }.fail();
         }
+        cursor.write_all(&vec![self.config.padding_value; (offset as u64 - position) as usize])?;
         Ok(())
     }
 
@@ -692,11 +2256,281 @@ impl<'a> Rom<'a> {
         &self.arm9
     }
 
+    /// Returns the base address of the DTCM, or `None` if the ARM9 program has none.
+    pub(crate) fn dtcm_base_address(&self) -> Option<u32> {
+        self.dtcm_base_address
+    }
+
+    /// Returns the ARM9 footer fields which are preserved from the original ROM.
+    pub fn arm9_footer(&self) -> &Arm9FooterConfig {
+        &self.arm9_footer
+    }
+
+    /// Sets the ARM9 footer fields to place in the ROM. Use [`Arm9FooterConfig::default`] to generate the footer normally.
+    pub fn set_arm9_footer(&mut self, arm9_footer: Arm9FooterConfig) {
+        self.arm9_footer = arm9_footer;
+    }
+
+    /// Returns the SHA-1 hash of the Blowfish key which decrypted the ARM9 program when it was saved, if known. See
+    /// [`BlowfishKey::select`] for choosing the same key when building.
+    pub fn blowfish_key_sha1(&self) -> Option<&[u8; 0x14]> {
+        self.blowfish_key_sha1.as_ref()
+    }
+
     /// Returns a reference to the ARM9 overlays of this [`Rom`].
     pub fn arm9_overlays(&self) -> &[Overlay] {
         &self.arm9_overlays
     }
 
+    /// Returns the ARM9 overlay table which overrides the one built from [`Self::arm9_overlays`], if any.
+    pub fn arm9_overlay_table_override(&self) -> Option<&OverlayTable> {
+        self.arm9_overlay_table.as_ref()
+    }
+
+    fn validate_arm9_overlay_table(
+        arm9_overlay_table: &OverlayTable,
+        arm9_overlays: &[Overlay],
+    ) -> Result<(), OverlayTableError> {
+        arm9_overlay_table.validate(arm9_overlays)?;
+        log::warn!("Using a custom ARM9 overlay table, its flags and signatures will not be updated to match the overlays");
+        Ok(())
+    }
+
+    /// Sets an ARM9 overlay table to place in the ROM as-is, instead of building it from [`Self::arm9_overlays`]. Passing
+    /// `None` restores the default behavior. The table is saved to [`RomConfig::arm9_ovt_bin`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the table does not have one entry per ARM9 overlay.
+    pub fn set_arm9_overlay_table_override(
+        &mut self,
+        arm9_overlay_table: Option<OverlayTable>,
+    ) -> Result<(), OverlayTableError> {
+        if let Some(arm9_overlay_table) = &arm9_overlay_table {
+            Self::validate_arm9_overlay_table(arm9_overlay_table, &self.arm9_overlays)?;
+            self.config.arm9_ovt_bin.get_or_insert_with(|| "arm9_overlays/ovt.bin".into());
+        } else {
+            self.config.arm9_ovt_bin = None;
+        }
+        self.arm9_overlay_table = arm9_overlay_table;
+        Ok(())
+    }
+
+    /// Returns the ARM9 overlay table as it will be built, which is the override if there is one.
+    fn built_arm9_overlay_table(&self) -> OverlayTable {
+        match &self.arm9_overlay_table {
+            Some(arm9_overlay_table) => arm9_overlay_table.clone(),
+            None => OverlayTable::from_overlays(&self.arm9_overlays),
+        }
+    }
+
+    /// Returns a decrypted and decompressed copy of the ARM9 program, and the index of the key in `keys` which decrypted it.
+    fn plain_arm9(&self, keys: &[&BlowfishKey]) -> Result<(Arm9<'static>, Option<usize>), RomResignError> {
+        let mut arm9 = self.arm9.to_owned();
+        if arm9.is_encrypted() && keys.is_empty() {
+            return ResignBlowfishKeyNeededSnafu {}.fail();
+        }
+        let key_index = arm9.decrypt_with_any_key(keys, self.header.original.gamecode.to_le_u32())?;
+        if arm9.is_compressed()? {
+            arm9.decompress()?;
+        }
+        Ok((arm9, key_index))
+    }
+
+    /// Searches the ARM9 program for the HMAC-SHA1 key of the overlay signatures, see [`Arm9::hmac_sha1_key`]. The key is
+    /// recognized by the signature of the ARM9 overlay table, which is the last entry of the overlay signature table. The
+    /// ARM9 program is decrypted with one of `keys` if needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ARM9 program can't be decrypted or decompressed, or it has no overlay
+    /// signature table.
+    pub fn arm9_overlay_hmac_sha1_key(&self, keys: &[&BlowfishKey]) -> Result<Option<[u8; 0x40]>, RomResignError> {
+        let (arm9, _) = self.plain_arm9(keys)?;
+        let num_overlays = self.arm9_overlays.len();
+        let slots = arm9.overlay_signature_slots(num_overlays + 1)?.context(NoOverlaySignaturesSnafu)?;
+        let table = self.built_arm9_overlay_table();
+        Ok(arm9.hmac_sha1_key(table.as_raw_bytes(), &slots[num_overlays]))
+    }
+
+    /// Signs the given ARM9 overlays, or every signed overlay if `overlay_ids` is empty, and the ARM9 overlay table. The
+    /// signatures are written to the overlay signature table of the ARM9 program, see [`Arm9::overlay_signature_slots`].
+    /// To do so, the ARM9 program is decrypted with one of `keys` and decompressed, then compressed and encrypted again as
+    /// it was, and [`Self::blowfish_key_sha1`] records which key to encrypt it with when building. Returns the IDs of the
+    /// signed overlays.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an overlay doesn't exist, the ARM9 program has no overlay signature table, or
+    /// it can't be decrypted, decompressed, compressed or encrypted.
+    pub fn resign_arm9_overlays(
+        &mut self,
+        hmac: &HmacSha1,
+        overlay_ids: &[u16],
+        keys: &[&BlowfishKey],
+    ) -> Result<Vec<u16>, RomResignError> {
+        if let Some(&id) = overlay_ids.iter().find(|&&id| !self.arm9_overlays.iter().any(|overlay| overlay.id() == id)) {
+            return ResignOverlayNotFoundSnafu { id }.fail();
+        }
+        let compressed = self.arm9.is_compressed()?;
+        let (mut arm9, key_index) = self.plain_arm9(keys)?;
+        let num_overlays = self.arm9_overlays.len();
+        let table_signature = self.built_arm9_overlay_table().compute_signature(hmac);
+        let slots = arm9.overlay_signature_slots_mut(num_overlays + 1)?.context(NoOverlaySignaturesSnafu)?;
+
+        let mut signed_ids = vec![];
+        for (slot, overlay) in slots.iter_mut().zip(&self.arm9_overlays) {
+            let sign = if overlay_ids.is_empty() { overlay.is_signed() } else { overlay_ids.contains(&overlay.id()) };
+            if sign {
+                *slot = overlay.compute_signature(hmac);
+                signed_ids.push(overlay.id());
+            }
+        }
+        slots[num_overlays] = table_signature;
+
+        if compressed {
+            arm9.compress()?;
+        }
+        if let Some(key_index) = key_index {
+            arm9.encrypt(keys[key_index], self.header.original.gamecode.to_le_u32())?;
+            self.blowfish_key_sha1 = Some(keys[key_index].sha1());
+        }
+        self.arm9 = arm9;
+        Ok(signed_ids)
+    }
+
+    /// Inserts an overlay at `index` among the overlays of the given processor, and returns its overlay ID. If `renumber`
+    /// is true, every overlay of the processor gets its index as its ID, otherwise `overlay` keeps its ID.
+    ///
+    /// The overlay gets the first file ID which is reserved in the FAT but not used by another overlay. If there is none,
+    /// one more FAT entry is reserved with [`FileSystem::reserve_entry`], which shifts the IDs of all files up by one.
+    /// Placeholders keep their file ID instead, see [`Overlay::is_placeholder`]. An entry is also inserted into the ARM9
+    /// overlay table override, if any.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `index` is out of range, if `renumber` is false and the overlay ID is already
+    /// in use, or if there are no more file IDs.
+    pub fn insert_overlay(
+        &mut self,
+        processor: Processor,
+        index: usize,
+        mut overlay: Overlay<'a>,
+        renumber: bool,
+    ) -> Result<u16, OverlayEditError> {
+        let overlays = self.overlays(processor);
+        if index > overlays.len() {
+            return IndexOutOfRangeSnafu { index, len: overlays.len() }.fail();
+        }
+        if !renumber && overlays.iter().any(|other| other.id() == overlay.id()) {
+            return DuplicateIdSnafu { id: overlay.id() }.fail();
+        }
+
+        // Placeholders have no file, so they keep their file ID of 0
+        if !overlay.is_placeholder() {
+            let used_file_ids = self
+                .arm9_overlays
+                .iter()
+                .chain(&self.arm7_overlays)
+                .filter(|ov| !ov.is_placeholder())
+                .map(|ov| ov.file_id())
+                .collect::<HashSet<_>>();
+            let free_file_id = (0..self.files.num_reserved() as u32).find(|file_id| !used_file_ids.contains(file_id));
+            let file_id = match free_file_id {
+                Some(file_id) => file_id,
+                None => self.files.reserve_entry().context(TooManyFileIdsSnafu)? as u32,
+            };
+            overlay.set_file_id(file_id);
+        }
+
+        if processor == Processor::Arm9 {
+            if let Some(arm9_overlay_table) = &mut self.arm9_overlay_table {
+                arm9_overlay_table.insert(index, overlay.build());
+                if renumber {
+                    arm9_overlay_table.renumber();
+                }
+            }
+        }
+        let overlays = self.overlays_mut(processor);
+        overlays.insert(index, overlay);
+        if renumber {
+            overlays.iter_mut().enumerate().for_each(|(id, overlay)| overlay.set_id(id as u16));
+        }
+        let id = overlays[index].id();
+        self.update_overlay_config(processor);
+        Ok(id)
+    }
+
+    /// Appends an overlay to the overlays of the given processor and returns its overlay ID, which is its index. See
+    /// [`Self::insert_overlay`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are no more file IDs.
+    pub fn push_overlay(&mut self, processor: Processor, overlay: Overlay<'a>) -> Result<u16, OverlayEditError> {
+        let index = self.overlays(processor).len();
+        self.insert_overlay(processor, index, overlay, true)
+    }
+
+    /// Removes the overlay with the ID `id` from the overlays of the given processor and returns it. If `renumber` is true,
+    /// every remaining overlay of the processor gets its index as its ID, otherwise the IDs are kept. The file ID of the
+    /// removed overlay stays reserved in the FAT and is reused by the next inserted overlay. The entry with the same ID is
+    /// also removed from the ARM9 overlay table override, if any.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no overlay with the ID `id`.
+    pub fn remove_overlay(&mut self, processor: Processor, id: u16, renumber: bool) -> Result<Overlay<'a>, OverlayEditError> {
+        let Some(index) = self.overlays(processor).iter().position(|overlay| overlay.id() == id) else {
+            return OverlayNotFoundSnafu { id }.fail();
+        };
+
+        if processor == Processor::Arm9 {
+            if let Some(arm9_overlay_table) = &mut self.arm9_overlay_table {
+                arm9_overlay_table.remove(id as u32);
+                if renumber {
+                    arm9_overlay_table.renumber();
+                }
+            }
+        }
+        let overlays = self.overlays_mut(processor);
+        let overlay = overlays.remove(index);
+        if renumber {
+            overlays.iter_mut().enumerate().for_each(|(id, overlay)| overlay.set_id(id as u16));
+        }
+        self.update_overlay_config(processor);
+        Ok(overlay)
+    }
+
+    fn overlays_mut(&mut self, processor: Processor) -> &mut Vec<Overlay<'a>> {
+        match processor {
+            Processor::Arm9 => &mut self.arm9_overlays,
+            Processor::Arm7 => &mut self.arm7_overlays,
+        }
+    }
+
+    /// Updates the config after inserting or removing overlays, so that the overlays are saved and the FAT layout is loaded
+    /// the same way.
+    fn update_overlay_config(&mut self, processor: Processor) {
+        let is_empty = self.overlays(processor).is_empty();
+        let (overlays_config, default_path) = match processor {
+            Processor::Arm9 => (&mut self.config.arm9_overlays, "arm9_overlays/overlays.yaml"),
+            Processor::Arm7 => (&mut self.config.arm7_overlays, "arm7_overlays/overlays.yaml"),
+        };
+        if is_empty {
+            *overlays_config = None;
+        } else {
+            overlays_config.get_or_insert_with(|| default_path.into());
+        }
+
+        // The number of reserved FAT entries defaults to the number of overlays when loading
+        let num_overlays = self.arm9_overlays.len() + self.arm7_overlays.len();
+        let num_reserved = self.files.num_reserved();
+        let mut fat_layout = self.config.fat_layout.unwrap_or_default();
+        fat_layout.reserved_entries = (num_reserved != num_overlays).then_some(num_reserved as u16);
+        self.config.fat_layout = (!fat_layout.is_default()).then_some(fat_layout);
+    }
+
     /// Returns a reference to the ARM7 program of this [`Rom`].
     pub fn arm7(&self) -> &Arm7 {
         &self.arm7
@@ -707,18 +2541,144 @@ impl<'a> Rom<'a> {
         &self.arm7_overlays
     }
 
+    /// Returns a reference to the DSi area of this [`Rom`], or `None` if it has none.
+    pub fn dsi_area(&self) -> Option<&DsiArea<'a>> {
+        self.dsi.as_ref()
+    }
+
+    /// Returns a reference to the overlays of the given processor, see [`Self::arm9_overlays`] and
+    /// [`Self::arm7_overlays`].
+    pub fn overlays(&self, processor: Processor) -> &[Overlay<'a>] {
+        match processor {
+            Processor::Arm9 => &self.arm9_overlays,
+            Processor::Arm7 => &self.arm7_overlays,
+        }
+    }
+
     /// Returns a reference to the header of this [`Rom`].
     pub fn header(&self) -> &Header {
         &self.header
     }
 
+    /// Returns a mutable reference to the header of this [`Rom`].
+    pub fn header_mut(&mut self) -> &mut Header {
+        &mut self.header
+    }
+
+    /// Returns a reference to the banner of this [`Rom`], or `None` if it has no banner.
+    pub fn banner(&self) -> Option<&Banner> {
+        self.banner.as_ref()
+    }
+
+    /// Returns a mutable reference to the banner of this [`Rom`], or `None` if it has no banner.
+    pub fn banner_mut(&mut self) -> Option<&mut Banner> {
+        self.banner.as_mut()
+    }
+
+    /// Returns a reference to the file system of this [`Rom`].
+    pub fn files(&self) -> &FileSystem<'a> {
+        &self.files
+    }
+
+    /// Returns a mutable reference to the file system of this [`Rom`].
+    pub fn files_mut(&mut self) -> &mut FileSystem<'a> {
+        &mut self.files
+    }
+
+    /// Returns the contents of the file at `path`, which is relative to the root directory and may start with `/`. Returns
+    /// `None` if the path doesn't exist or is a directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file contents are not loaded and can't be read, see
+    /// [`File::read_contents`].
+    pub fn file_contents(&self, path: &str) -> Result<Option<Cow<'_, [u8]>>, FileError> {
+        let Some(id) = self.files.find_path(path.strip_prefix("/").unwrap_or(path)) else { return Ok(None) };
+        if !FileSystem::is_file(id) {
+            return Ok(None);
+        }
+        self.files.file(id).read_contents().map(Some)
+    }
+
+    /// Replaces the contents of the file at `path`, which is relative to the root directory and may start with `/`. Returns
+    /// the ID of the file. See [`FileSystem::replace_file_contents`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path doesn't exist or is a directory.
+    pub fn set_file_contents<T: Into<Cow<'a, [u8]>>>(&mut self, path: &str, contents: T) -> Result<u16, FileEditError> {
+        self.files.replace_file_contents(path, contents)
+    }
+
+    /// Returns an iterator over every file and its path in FNT order, see [`FileSystem::iter_files`].
+    pub fn iter_files(&self) -> impl Iterator<Item = (String, &File<'_>)> {
+        self.files.iter_files()
+    }
+
+    /// Returns the order in which files and directories are placed in the ROM. See [`FileSystem::traverse_files`].
+    pub fn path_order(&self) -> &[String] {
+        &self.path_order
+    }
+
+    /// Sets the order in which files and directories are placed in the ROM. See [`FileSystem::traverse_files`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a path does not exist in the file system. In that case, the path order is left
+    /// unchanged.
+    pub fn set_path_order(&mut self, path_order: Vec<String>) -> Result<(), RomPathOrderError> {
+        let unresolved = path_order
+            .iter()
+            .filter(|path| {
+                let path = path.strip_prefix('/').unwrap_or(path);
+                !path.trim().is_empty() && self.files.find_path(path).is_none()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unresolved.is_empty() {
+            return UnresolvedPathsSnafu { paths: unresolved }.fail();
+        }
+        self.path_order = path_order;
+        Ok(())
+    }
+
+    /// Returns the exhaustive list of files in the order they're placed in the ROM, if it overrides [`Self::path_order`].
+    pub fn file_order(&self) -> Option<&[String]> {
+        self.file_order.as_deref()
+    }
+
+    /// Sets an exhaustive list of files in the order they're placed in the ROM, overriding [`Self::path_order`]. See
+    /// [`FileSystem::compute_file_order`] to get the current order of every file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the list doesn't contain every file exactly once. In that case, the file order
+    /// is left unchanged.
+    pub fn set_file_order(&mut self, file_order: Option<Vec<String>>) -> Result<(), FileOrderError> {
+        if let Some(file_order) = &file_order {
+            self.files.resolve_file_order(file_order)?;
+        }
+        self.file_order = file_order;
+        Ok(())
+    }
+
+    /// Returns the files which refer to other sections of the ROM instead of their own data, see [`AliasedFile`].
+    pub fn aliased_files(&self) -> &[AliasedFile] {
+        &self.aliased_files
+    }
+
+    /// Returns the sizes of the programs and overlays which [`Self::load`] compressed, see [`CompressionReport`].
+    pub fn compression_report(&self) -> &CompressionReport {
+        &self.compression_report
+    }
+
     /// Returns the [`RomConfig`] consisting of paths to extracted files.
     pub fn config(&self) -> &RomConfig {
         &self.config
     }
 }
 
-/// Build context, generated during [`Rom::build`] and later passed to [`Header::build`] to fill in the header.
+/// Build context, derived from the [`RomLayout`] in [`Rom::build`] and later passed to [`Header::build`] to fill in the header.
 #[derive(Default)]
 pub struct BuildContext<'a> {
     /// Header offset.
@@ -749,22 +2709,201 @@ pub struct BuildContext<'a> {
     pub arm7_build_info_offset: Option<u32>,
     /// Total ROM size.
     pub rom_size: Option<u32>,
+    /// ARM9i program offset.
+    pub arm9i_offset: Option<u32>,
+    /// ARM7i program offset.
+    pub arm7i_offset: Option<u32>,
+    /// Digest sector hashtable offset.
+    pub digest_sector_hashtable_offset: Option<TableOffset>,
+    /// Digest block hashtable offset.
+    pub digest_block_hashtable_offset: Option<TableOffset>,
+    /// Start of the DSi region.
+    pub dsi_region_start: Option<u32>,
+    /// ROM size including the DSi area.
+    pub dsi_rom_size: Option<u32>,
+}
+
+/// Writes a ROM while keeping track of the position, so that padding doesn't need to query the underlying writer. Positions
+/// are relative to where the writer was when the ROM started.
+struct RomWriter<W> {
+    writer: W,
+    start: u64,
+    position: u64,
+}
+
+impl<W: Write + Seek> RomWriter<W> {
+    fn new(mut writer: W) -> io::Result<Self> {
+        let start = writer.stream_position()?;
+        Ok(Self { writer, start, position: 0 })
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn set_position(&mut self, position: u64) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(self.start + position))?;
+        self.position = position;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for RomWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
 }
 
 /// Options for [`Rom::load`].
+#[derive(Clone)]
 pub struct RomLoadOptions<'a> {
     /// Blowfish encryption key.
     pub key: Option<&'a BlowfishKey>,
+    /// Additional Blowfish keys, such as for iQue DS titles. The key recorded in the ARM9 config is picked among these and
+    /// [`Self::key`], or [`Self::key`] if none was recorded.
+    pub keys: &'a [BlowfishKey],
     /// If true (default), compress ARM9 and overlays if they are configured with `compressed: true`.
     pub compress: bool,
     /// If true (default), encrypt ARM9 if it's configured with `encrypted: true`.
     pub encrypt: bool,
     /// If true (default), load asset files.
     pub load_files: bool,
+    /// If true, only the sizes of asset files are read when loading, and their contents are read while building. This
+    /// lowers the peak memory use of [`Rom::build`], but the files must not change size in between. See
+    /// [`FileSystem::load_lazy`].
+    pub lazy_files: bool,
+    /// The ROM which this ROM was extracted from, used to load files that were skipped by [`Rom::save_with_options`].
+    pub original_rom: Option<&'a raw::Rom<'a>>,
+    /// Max number of threads in the [`ThreadPool`] shared by every stage, defaults to
+    /// [`crate::parallel::default_concurrency`].
+    pub concurrency: Option<NonZeroUsize>,
+    /// If true, [`Rom::build`] pads the ROM to the next 0x200 bytes instead of the next power of two, and keeps the capacity
+    /// of the original ROM. This makes development builds faster to copy, but they won't match the original ROM.
+    pub dev_padding: bool,
+    /// If true, [`Rom::build`] fails if [`Header::check_consistency`] finds problems, instead of only warning about them.
+    pub strict_header: bool,
+    /// Compressed overlays from a previous build, which are reused instead of compressing overlays that haven't changed.
+    pub build_cache: Option<&'a BuildCache>,
 }
 
 impl<'a> Default for RomLoadOptions<'a> {
     fn default() -> Self {
-        Self { key: None, compress: true, encrypt: true, load_files: true }
+        Self {
+            key: None,
+            keys: &[],
+            compress: true,
+            encrypt: true,
+            load_files: true,
+            lazy_files: false,
+            original_rom: None,
+            concurrency: None,
+            dev_padding: false,
+            strict_header: false,
+            build_cache: None,
+        }
+    }
+}
+
+impl<'a> RomLoadOptions<'a> {
+    fn key_candidates(&self) -> Vec<&'a BlowfishKey> {
+        self.key.into_iter().chain(self.keys).collect()
+    }
+}
+
+/// Options for [`Rom::extract_with_options`]. Each option preserves a detail of the original ROM's layout which the game
+/// doesn't depend on, but which is needed to rebuild a matching ROM. Use [`Self::preservation`] to set them coherently.
+#[derive(Clone, Debug)]
+pub struct RomExtractOptions {
+    /// Preset which these options were set from, recorded in the config so that [`Rom::load`] knows which guarantees were
+    /// intended. `None` if the options were set individually.
+    pub level: Option<PreservationLevel>,
+    /// Preserves the order of the FNT if it isn't sorted. Otherwise, the FNT is sorted when building, which changes the file
+    /// IDs.
+    pub preserve_fnt_order: bool,
+    /// Preserves the order in which files are placed in the ROM. Otherwise, files are placed in file ID order.
+    pub preserve_path_order: bool,
+    /// Saves the order of every file in the ROM, instead of simplifying it to directories. See
+    /// [`FileSystem::compute_file_order`].
+    pub full_file_order: bool,
+    /// Preserves extra padding before sections, see [`RomConfigSectionOffsets`].
+    pub preserve_section_offsets: bool,
+    /// Preserves reserved and unused FAT entries, see [`FatLayout`].
+    pub preserve_fat_layout: bool,
+    /// Preserves the byte value used for padding between sections. Otherwise, 0xff is used.
+    pub preserve_padding_value: bool,
+    /// Preserves data after the end of the ROM, such as a signature.
+    pub preserve_trailing_data: bool,
+    /// Preserves the original capacity, which is used if it's larger than the capacity needed for the built ROM.
+    pub preserve_capacity: bool,
+    /// Always uses the original capacity, even if the built ROM needs a larger one. See
+    /// [`super::HeaderOriginal::preserve_capacity`].
+    pub exact_capacity: bool,
+}
+
+impl RomExtractOptions {
+    /// Returns the options for a [`PreservationLevel`].
+    pub fn preservation(level: PreservationLevel) -> Self {
+        let standard = level >= PreservationLevel::Standard;
+        let exact = level >= PreservationLevel::Exact;
+        Self {
+            level: Some(level),
+            preserve_fnt_order: standard,
+            preserve_path_order: standard,
+            full_file_order: exact,
+            preserve_section_offsets: standard,
+            preserve_fat_layout: standard,
+            preserve_padding_value: standard,
+            preserve_trailing_data: standard,
+            preserve_capacity: standard,
+            exact_capacity: exact,
+        }
+    }
+}
+
+impl Default for RomExtractOptions {
+    fn default() -> Self {
+        Self::preservation(PreservationLevel::default())
+    }
+}
+
+/// Options for [`Rom::save_with_options`].
+#[derive(Default)]
+pub struct RomSaveOptions<'a> {
+    /// Blowfish encryption key.
+    pub key: Option<&'a BlowfishKey>,
+    /// Additional Blowfish keys to try if [`Self::key`] fails to decrypt the ARM9 program, such as for iQue DS titles. The
+    /// key which succeeds is recorded in the ARM9 config.
+    pub keys: &'a [BlowfishKey],
+    /// Skips saving files larger than this many bytes. Skipped files are recorded with their size, offset and SHA-1 hash so
+    /// they can be loaded from the original ROM, see [`RomLoadOptions::original_rom`].
+    pub skip_files_larger_than: Option<usize>,
+    /// Skips saving files whose path relative to the files directory matches one of these glob patterns. `*` matches any
+    /// sequence of characters, including `/`.
+    pub skip_files_matching: Vec<String>,
+    /// Max number of threads in the [`ThreadPool`] shared by every stage, defaults to
+    /// [`crate::parallel::default_concurrency`].
+    pub concurrency: Option<NonZeroUsize>,
+    /// Saves file contents into a content-addressed store instead of the files directory, see [`DedupStore`]. The store is
+    /// recorded in the config so that [`Rom::load`] can resolve the files.
+    pub dedup_store: Option<DedupStore>,
+    /// Saves files matching these rules decompressed, overriding [`RomConfig::transparent_compression`] if not empty. The
+    /// rules are recorded in the config so that [`Rom::load`] compresses the files again.
+    pub transparent_compression: Vec<TransparentCompression>,
+    /// Saves each banner title to its own text file, see [`Banner::save_title_files`].
+    pub split_banner_titles: bool,
+    /// Leaves files which already exist with the same contents untouched, such as when saving again after
+    /// [`Rom::save_with_summary`] failed partway.
+    pub skip_unchanged_files: bool,
+}
+
+impl<'a> RomSaveOptions<'a> {
+    fn key_candidates(&self) -> Vec<&'a BlowfishKey> {
+        self.key.into_iter().chain(self.keys).collect()
     }
 }
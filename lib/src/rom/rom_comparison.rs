@@ -0,0 +1,163 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::{
+    raw,
+    rom_diff::{first_mismatch, RomDiff, RomDiffError, RomDiffSection, RomDiffSectionKind},
+};
+use crate::str::hex_string;
+
+/// Default maximum number of differing byte ranges collected by [`super::Rom::compare`].
+pub const DEFAULT_MAX_MISMATCHES: usize = 64;
+
+/// Header fields up to this size have their values included in a [`HeaderFieldDiff`].
+const MAX_FIELD_VALUE_SIZE: usize = 0x20;
+
+/// Result of [`super::Rom::compare`]. Lists which header fields differ, and compares every section, overlay and file of
+/// the two ROMs. See [`RomDiff`].
+#[derive(Serialize)]
+pub struct RomComparison {
+    /// Raw header fields which differ between the ROMs, in the order they appear in the header.
+    pub header_fields: Vec<HeaderFieldDiff>,
+    /// Section and file differences.
+    #[serde(flatten)]
+    pub diff: RomDiff,
+}
+
+/// A raw header field which differs between two ROMs, see [`RomComparison`].
+#[derive(Serialize)]
+pub struct HeaderFieldDiff {
+    /// Name of the field in [`raw::Header`].
+    pub name: String,
+    /// Offset of the field in the header.
+    pub offset: u32,
+    /// Size of the field in bytes.
+    pub size: u32,
+    /// Offset of the first differing byte in the header.
+    pub first_mismatch: u32,
+    /// Hexadecimal bytes of the field in the original and other ROM. Omitted for large fields, such as the logo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<(String, String)>,
+}
+
+impl RomComparison {
+    /// Compares `other` to `original`. At most `max_mismatches` differing byte ranges are collected, see
+    /// [`RomDiff::compute`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header, overlay tables, FNT, FAT or banner of either ROM is invalid.
+    pub fn compute(original: &raw::Rom, other: &raw::Rom, max_mismatches: usize) -> Result<Self, RomDiffError> {
+        let diff = RomDiff::compute(original, other, max_mismatches)?;
+        let original_header = bytemuck::bytes_of(original.header()?);
+        let other_header = bytemuck::bytes_of(other.header()?);
+        let header_fields = raw::Header::field_ranges()
+            .filter(|(_, range)| original_header[range.clone()] != other_header[range.clone()])
+            .map(|(name, range)| {
+                let original_value = &original_header[range.clone()];
+                let other_value = &other_header[range.clone()];
+                HeaderFieldDiff {
+                    name: name.to_string(),
+                    offset: range.start as u32,
+                    size: range.len() as u32,
+                    first_mismatch: range.start as u32 + first_mismatch(original_value, other_value),
+                    values: (range.len() <= MAX_FIELD_VALUE_SIZE)
+                        .then(|| (hex_string(original_value), hex_string(other_value))),
+                }
+            })
+            .collect();
+        Ok(Self { header_fields, diff })
+    }
+
+    /// Returns whether the ROMs are identical.
+    pub fn identical(&self) -> bool {
+        self.diff.identical
+    }
+
+    /// Returns a [`Display`] implementation which only summarizes which sections match, and lists the files which differ.
+    pub fn display_brief(&self) -> DisplayBriefRomComparison<'_> {
+        DisplayBriefRomComparison { comparison: self }
+    }
+}
+
+impl Display for HeaderFieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:#x}): differs at {:#x}", self.name, self.offset, self.first_mismatch)?;
+        if let Some((original, other)) = &self.values {
+            write!(f, ", {original} -> {other}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RomComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.diff)?;
+        if !self.header_fields.is_empty() {
+            write!(f, "\nHeader fields:")?;
+            for field in &self.header_fields {
+                write!(f, "\n  {field}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns which ROM a differing section without a first mismatch is missing from.
+fn missing_from(section: &RomDiffSection) -> &'static str {
+    if section.original.is_none() {
+        "original"
+    } else {
+        "other"
+    }
+}
+
+/// Summarizes a [`RomComparison`], see [`RomComparison::display_brief`].
+pub struct DisplayBriefRomComparison<'a> {
+    comparison: &'a RomComparison,
+}
+
+impl Display for DisplayBriefRomComparison<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let RomComparison { header_fields, diff } = self.comparison;
+        if diff.identical {
+            return write!(f, "ROMs are identical ({:#x} bytes)", diff.original_size);
+        } else if diff.original_size != diff.other_size {
+            write!(f, "ROMs differ, size {:#x} -> {:#x}", diff.original_size, diff.other_size)?;
+        } else {
+            write!(f, "ROMs differ")?;
+        }
+
+        let mut num_files = 0;
+        let mut differing_files = vec![];
+        for section in &diff.sections {
+            if section.kind == RomDiffSectionKind::File {
+                num_files += 1;
+                if !section.matches {
+                    differing_files.push(section);
+                }
+                continue;
+            }
+            write!(f, "\n{}: ", section.name)?;
+            match (section.matches, section.first_mismatch) {
+                (true, _) => write!(f, "matches")?,
+                (false, Some(offset)) => write!(f, "differs at {offset:#x}")?,
+                (false, None) => write!(f, "missing in the {} ROM", missing_from(section))?,
+            }
+            if section.name == "Header" && !header_fields.is_empty() {
+                let names = header_fields.iter().map(|field| field.name.as_str()).collect::<Vec<_>>();
+                write!(f, " ({})", names.join(", "))?;
+            }
+        }
+
+        write!(f, "\nFiles: {} of {num_files} match", num_files - differing_files.len())?;
+        for section in differing_files {
+            match section.first_mismatch {
+                Some(offset) => write!(f, "\n  {}: differs at {offset:#x}", section.name)?,
+                None => write!(f, "\n  {}: missing in the {} ROM", section.name, missing_from(section))?,
+            }
+        }
+        Ok(())
+    }
+}
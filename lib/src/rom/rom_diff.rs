@@ -0,0 +1,305 @@
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use serde::Serialize;
+use snafu::Snafu;
+
+use super::{
+    hash_report::report_sections,
+    raw::{self, RawBannerError, RawFatError, RawHeaderError, RawLocateError, RawOverlayError},
+};
+use crate::{crypto::sha1::Sha1, str::hex_string};
+
+/// Differences between two ROM images. Sections and files are compared as a whole first, and only differing ones are
+/// compared byte by byte. See [`RomDiff::compute`].
+#[derive(Serialize)]
+pub struct RomDiff {
+    /// Size of the original ROM.
+    pub original_size: u32,
+    /// Size of the other ROM.
+    pub other_size: u32,
+    /// Whether the ROMs are identical, including padding and other data outside of sections and files.
+    pub identical: bool,
+    /// Header sections, followed by files, in the order they appear in the original ROM.
+    pub sections: Vec<RomDiffSection>,
+    /// Whether some mismatches were left out because the limit passed to [`RomDiff::compute`] was reached.
+    pub truncated: bool,
+}
+
+/// A section or file compared by [`RomDiff`].
+#[derive(Serialize)]
+pub struct RomDiffSection {
+    /// Name of the section, or the path or overlay name of the file.
+    pub name: String,
+    /// What kind of section this is.
+    pub kind: RomDiffSectionKind,
+    /// ROM range of the section in the original ROM, or `None` if the original ROM doesn't have it.
+    pub original: Option<Range<u32>>,
+    /// ROM range of the section in the other ROM, or `None` if the other ROM doesn't have it.
+    pub other: Option<Range<u32>>,
+    /// Whether the section has the same contents in both ROMs.
+    pub matches: bool,
+    /// SHA-1 of the section in each ROM. Only computed for sections which differ.
+    pub sha1: Option<(String, String)>,
+    /// Offset of the first differing byte, relative to the start of the section. If one section is a prefix of the
+    /// other, this is the size of the shorter one. Unlike [`Self::mismatches`], this is not limited by
+    /// [`RomDiff::compute`].
+    pub first_mismatch: Option<u32>,
+    /// Differing byte ranges, relative to the start of the section. Only covers the bytes which both ROMs have.
+    pub mismatches: Vec<Range<u32>>,
+}
+
+/// Kind of [`RomDiffSection`].
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RomDiffSectionKind {
+    /// A section pointed to by the header, such as the header itself, the ARM9 program or the FAT.
+    Section,
+    /// An ARM9 or ARM7 overlay.
+    Overlay,
+    /// A file in the file system, or a FAT entry which has no path or overlay.
+    File,
+}
+
+/// Errors related to [`RomDiff`].
+#[derive(Debug, Snafu)]
+pub enum RomDiffError {
+    /// See [`RawHeaderError`].
+    #[snafu(transparent)]
+    RawHeader {
+        /// Source error.
+        source: RawHeaderError,
+    },
+    /// See [`RawBannerError`].
+    #[snafu(transparent)]
+    RawBanner {
+        /// Source error.
+        source: RawBannerError,
+    },
+    /// See [`RawOverlayError`].
+    #[snafu(transparent)]
+    RawOverlay {
+        /// Source error.
+        source: RawOverlayError,
+    },
+    /// See [`RawFatError`].
+    #[snafu(transparent)]
+    RawFat {
+        /// Source error.
+        source: RawFatError,
+    },
+    /// See [`RawLocateError`].
+    #[snafu(transparent)]
+    RawLocate {
+        /// Source error.
+        source: RawLocateError,
+    },
+}
+
+/// Size of the chunks which are compared as a whole before looking for differing bytes.
+const CHUNK_SIZE: usize = 0x1000;
+
+impl RomDiff {
+    /// Compares `other` to `original`. Header sections are matched by name, and files are matched by path or overlay
+    /// name. At most `max_mismatches` differing byte ranges are collected in total.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header, overlay tables, FNT, FAT or banner of either ROM is invalid.
+    pub fn compute(original: &raw::Rom, other: &raw::Rom, max_mismatches: usize) -> Result<Self, RomDiffError> {
+        let original_sections = Self::named_sections(original)?;
+        let other_sections = Self::named_sections(other)?;
+        let identical = original.data() == other.data();
+
+        let mut other_ranges: HashMap<&str, Range<u32>> =
+            other_sections.iter().map(|(name, _, range)| (name.as_str(), range.clone())).collect();
+        let mut sections = vec![];
+        let mut num_mismatches = 0;
+        let mut truncated = false;
+        for (name, kind, range) in &original_sections {
+            let other_range = other_ranges.remove(name.as_str());
+            let section = match other_range {
+                Some(other_range) if !identical => {
+                    let original_data = &original.data()[range.start as usize..range.end as usize];
+                    let other_data = &other.data()[other_range.start as usize..other_range.end as usize];
+                    let mut section = RomDiffSection {
+                        name: name.clone(),
+                        kind: *kind,
+                        original: Some(range.clone()),
+                        other: Some(other_range),
+                        matches: original_data == other_data,
+                        sha1: None,
+                        first_mismatch: None,
+                        mismatches: vec![],
+                    };
+                    if !section.matches {
+                        section.sha1 = Some((hex_string(&Sha1::digest(original_data)), hex_string(&Sha1::digest(other_data))));
+                        section.first_mismatch = Some(first_mismatch(original_data, other_data));
+                        let max = max_mismatches - num_mismatches;
+                        truncated |= find_mismatches(original_data, other_data, max, &mut section.mismatches);
+                        num_mismatches += section.mismatches.len();
+                    }
+                    section
+                }
+                other_range => RomDiffSection {
+                    name: name.clone(),
+                    kind: *kind,
+                    original: Some(range.clone()),
+                    matches: other_range.is_some(),
+                    other: other_range,
+                    sha1: None,
+                    first_mismatch: None,
+                    mismatches: vec![],
+                },
+            };
+            sections.push(section);
+        }
+        for (name, kind, range) in &other_sections {
+            if other_ranges.contains_key(name.as_str()) {
+                sections.push(RomDiffSection {
+                    name: name.clone(),
+                    kind: *kind,
+                    original: None,
+                    other: Some(range.clone()),
+                    matches: false,
+                    sha1: None,
+                    first_mismatch: None,
+                    mismatches: vec![],
+                });
+            }
+        }
+
+        Ok(Self {
+            original_size: original.data().len() as u32,
+            other_size: other.data().len() as u32,
+            identical,
+            sections,
+            truncated,
+        })
+    }
+
+    /// Returns the header sections and nonempty files of `rom`, with files sorted by ROM offset.
+    fn named_sections(rom: &raw::Rom) -> Result<Vec<(String, RomDiffSectionKind, Range<u32>)>, RomDiffError> {
+        let size = rom.data().len() as u32;
+        let mut sections: Vec<_> = report_sections(rom)?
+            .into_iter()
+            .map(|(name, offset, size)| (name, RomDiffSectionKind::Section, offset..offset + size))
+            .filter(|(_, _, range)| range.end <= size)
+            .collect();
+
+        let overlays = rom.overlay_names()?;
+        let paths = rom.file_paths()?;
+        let mut files: Vec<_> = rom
+            .fat()?
+            .iter()
+            .enumerate()
+            .filter(|(_, alloc)| alloc.end > alloc.start && alloc.end <= size)
+            .map(|(id, alloc)| {
+                let id = id as u16;
+                let (name, kind) = match (overlays.get(&id), paths.get(&id)) {
+                    (Some(overlay), _) => (overlay.clone(), RomDiffSectionKind::Overlay),
+                    (None, Some(path)) => (path.clone(), RomDiffSectionKind::File),
+                    (None, None) => (format!("file {id:#x}"), RomDiffSectionKind::File),
+                };
+                (name, kind, alloc.start..alloc.end)
+            })
+            .collect();
+        files.sort_by_key(|(_, _, range)| range.start);
+        sections.extend(files);
+        Ok(sections)
+    }
+
+    /// Returns the sections which differ between the two ROMs, including sections which only one of them has.
+    pub fn differing(&self) -> impl Iterator<Item = &RomDiffSection> + '_ {
+        self.sections.iter().filter(|section| !section.matches)
+    }
+}
+
+/// Returns the offset of the first byte where `a` and `b` differ, or the length of the shorter one if it is a prefix of
+/// the other.
+pub(crate) fn first_mismatch(a: &[u8], b: &[u8]) -> u32 {
+    let len = a.len().min(b.len());
+    let chunk_start = (0..len).step_by(CHUNK_SIZE).find(|&start| {
+        let end = (start + CHUNK_SIZE).min(len);
+        a[start..end] != b[start..end]
+    });
+    let Some(chunk_start) = chunk_start else { return len as u32 };
+    (chunk_start..len).find(|&i| a[i] != b[i]).unwrap_or(len) as u32
+}
+
+/// Appends the byte ranges where `a` and `b` differ to `mismatches`, comparing whole chunks first to skip over equal
+/// data quickly. Returns `true` if more than `max` ranges were found, in which case only the first `max` are appended.
+fn find_mismatches(a: &[u8], b: &[u8], max: usize, mismatches: &mut Vec<Range<u32>>) -> bool {
+    let len = a.len().min(b.len());
+    let mut run_start = None;
+    let mut found = 0;
+    for chunk_start in (0..len).step_by(CHUNK_SIZE) {
+        let chunk_end = (chunk_start + CHUNK_SIZE).min(len);
+        if run_start.is_none() && a[chunk_start..chunk_end] == b[chunk_start..chunk_end] {
+            continue;
+        }
+        for i in chunk_start..chunk_end {
+            match (run_start, a[i] == b[i]) {
+                (None, false) => run_start = Some(i),
+                (Some(start), true) => {
+                    if found == max {
+                        return true;
+                    }
+                    mismatches.push(start as u32..i as u32);
+                    found += 1;
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(start) = run_start {
+        if found == max {
+            return true;
+        }
+        mismatches.push(start as u32..len as u32);
+    }
+    false
+}
+
+impl Display for RomDiffSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (original, other) = match (&self.original, &self.other) {
+            (None, _) => return write!(f, "{}: missing in the original ROM", self.name),
+            (_, None) => return write!(f, "{}: missing in the other ROM", self.name),
+            (Some(original), Some(other)) => (original, other),
+        };
+        if self.matches {
+            return write!(f, "{}: matches ({:#x} bytes)", self.name, original.len());
+        }
+        write!(f, "{}: differs", self.name)?;
+        if original.len() != other.len() {
+            write!(f, ", size {:#x} -> {:#x}", original.len(), other.len())?;
+        }
+        if let Some((original_sha1, other_sha1)) = &self.sha1 {
+            write!(f, ", SHA-1 {original_sha1} -> {other_sha1}")?;
+        }
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {:#x}..{:#x}", mismatch.start, mismatch.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RomDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.identical {
+            write!(f, "ROMs are identical ({:#x} bytes)", self.original_size)?;
+        } else if self.original_size != self.other_size {
+            write!(f, "ROMs differ, size {:#x} -> {:#x}", self.original_size, self.other_size)?;
+        } else {
+            write!(f, "ROMs differ")?;
+        }
+        for section in &self.sections {
+            write!(f, "\n{section}")?;
+        }
+        if self.truncated {
+            write!(f, "\nMore mismatches were found but not listed")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use snafu::Snafu;
+
+use super::RomSaveError;
+use crate::{
+    io::{write_file_and_dirs, FileError},
+    str::BlobSize,
+};
+
+/// Result of [`super::Rom::save_with_summary`], listing the files which were saved.
+#[derive(Clone, Debug, Default)]
+pub struct SaveSummary {
+    /// Paths of the files which were written, in the order they were written.
+    pub written: Vec<PathBuf>,
+    /// Number of bytes written to the files in [`Self::written`].
+    pub bytes_written: u64,
+    /// Number of files which already had the right contents and were not written again, see
+    /// [`super::RomSaveOptions::skip_unchanged_files`].
+    pub unchanged: usize,
+}
+
+impl Display for SaveSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} files ({} written)", self.written.len(), BlobSize(self.bytes_written as usize))?;
+        if self.unchanged > 0 {
+            write!(f, ", {} unchanged", self.unchanged)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`super::Rom::save_with_summary`], along with the files which were saved before the error occurred.
+#[derive(Debug, Snafu)]
+#[snafu(display("{source}"))]
+pub struct PartialSaveError {
+    /// The error which stopped saving.
+    pub source: Box<RomSaveError>,
+    /// Files which were saved before the error occurred.
+    pub summary: SaveSummary,
+}
+
+/// Writes the files of a saved ROM and records them in a [`SaveSummary`].
+pub(crate) struct SaveWriter {
+    skip_unchanged: bool,
+    summary: Mutex<SaveSummary>,
+}
+
+impl SaveWriter {
+    pub fn new(skip_unchanged: bool) -> Self {
+        Self { skip_unchanged, summary: Mutex::new(SaveSummary::default()) }
+    }
+
+    /// Writes `contents` to `path`, creating its parent directories. If enabled, the file is left as-is if it already has
+    /// the same contents.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<(), FileError> {
+        let (path, contents) = (path.as_ref(), contents.as_ref());
+        if self.skip_unchanged && Self::is_unchanged(path, contents) {
+            self.summary.lock().unwrap().unchanged += 1;
+            return Ok(());
+        }
+        write_file_and_dirs(path, contents)?;
+        self.record(path, contents.len() as u64);
+        Ok(())
+    }
+
+    /// Serializes `value` to YAML and writes it to `path`, see [`Self::write`].
+    pub fn write_yaml<P: AsRef<Path>, T: Serialize>(&self, path: P, value: &T) -> Result<(), RomSaveError> {
+        let yaml = serde_yml::to_string(value)?;
+        self.write(path, yaml)?;
+        Ok(())
+    }
+
+    /// Records a file which was written by other means.
+    pub fn record(&self, path: &Path, size: u64) {
+        let mut summary = self.summary.lock().unwrap();
+        summary.written.push(path.to_path_buf());
+        summary.bytes_written += size;
+    }
+
+    /// Records a file which was written by other means, such as an image encoder, with its size on disk.
+    pub fn record_existing(&self, path: &Path) {
+        let size = fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+        self.record(path, size);
+    }
+
+    fn is_unchanged(path: &Path, contents: &[u8]) -> bool {
+        fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.len() == contents.len() as u64)
+            && fs::read(path).is_ok_and(|existing| existing == contents)
+    }
+
+    pub fn into_summary(self) -> SaveSummary {
+        self.summary.into_inner().unwrap()
+    }
+}
@@ -0,0 +1,808 @@
+use std::{fmt::Display, path::PathBuf};
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use super::{
+    raw::{
+        AccessControl, BannerVersion, Capacity, Delay, DsFlags, DsiFlags, DsiFlags2, OverlayFlags, RegionFlags, TableOffset,
+    },
+    Arm9BuildConfig, Arm9FooterConfig, Arm9FooterValue, Arm9Offsets, Arm9OverlaySignatures, Banner, BannerAnimationBitmapFile,
+    BannerCrcs, BannerImages, BannerKeyframe, BannerRemapPolicy, BannerTitle, BannerTitleFiles, BuildInfo, CompressionFormat,
+    DedupMode, DedupStore, EmptyDirFileId, FatLayout, Header, HeaderDsPostDsi, HeaderDsi, HeaderOriginal, MemoryLimits,
+    OverlayConfig, OverlayInfo, PreservationLevel, RomConfig, RomConfigAutoload, RomConfigDsi, RomConfigSectionOffsets,
+    TransparentCompression,
+};
+use crate::str::AsciiArray;
+
+/// A [JSON Schema](https://json-schema.org/) describing the YAML format of a project file, see [`SchemaKind`]. Only the
+/// keywords needed to describe the project files are supported.
+#[derive(Serialize, Clone, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    /// JSON Schema dialect, only set on the root schema.
+    #[serde(rename = "$schema", skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<String>,
+    /// Comment, used on the root schema to record the version of ds-rom which generated it.
+    #[serde(rename = "$comment", skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Short title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Description of the value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON type of the value.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<SchemaType>,
+    /// Properties of an object, in the order they are saved in.
+    #[serde(skip_serializing_if = "Vec::is_empty", serialize_with = "serialize_properties")]
+    pub properties: Vec<(String, Schema)>,
+    /// Names of properties which must be present in an object.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    /// Schema of every item in an array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Schema>>,
+    /// Minimum number of items in an array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+    /// Maximum number of items in an array.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    /// Minimum value of an integer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<u64>,
+    /// Maximum value of an integer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<u64>,
+    /// Regular expression which a string must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Allowed values of a string.
+    #[serde(rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    /// Schemas of which the value must match at least one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub any_of: Vec<Schema>,
+}
+
+/// JSON types used by [`Schema::kind`].
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaType {
+    /// Mapping of property names to values.
+    Object,
+    /// Sequence of values.
+    Array,
+    /// Text, including paths.
+    String,
+    /// Integer. Hexadecimal literals such as `0x2000000` are integers in YAML.
+    Integer,
+    /// `true` or `false`.
+    Boolean,
+    /// No value.
+    Null,
+}
+
+fn serialize_properties<S: Serializer>(properties: &[(String, Schema)], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(properties.len()))?;
+    for (name, schema) in properties {
+        map.serialize_entry(name, schema)?;
+    }
+    map.end()
+}
+
+impl Schema {
+    fn of_type(kind: SchemaType) -> Self {
+        Self { kind: Some(kind), ..Default::default() }
+    }
+
+    /// Returns an integer schema with the given maximum value.
+    pub fn integer(maximum: u64) -> Self {
+        Self { minimum: Some(0), maximum: Some(maximum), ..Self::of_type(SchemaType::Integer) }
+    }
+
+    /// Returns a string schema which only allows the given values.
+    pub fn enumeration(values: &[&str]) -> Self {
+        Self { values: values.iter().map(|value| value.to_string()).collect(), ..Self::of_type(SchemaType::String) }
+    }
+
+    /// Returns a string schema which must match a regular expression.
+    pub fn pattern(pattern: &str) -> Self {
+        Self { pattern: Some(pattern.to_string()), ..Self::of_type(SchemaType::String) }
+    }
+
+    /// Returns an empty object schema, to which properties are added with [`Self::property`] and [`Self::optional`].
+    pub fn object(description: &str) -> Self {
+        Self::of_type(SchemaType::Object).describe(description)
+    }
+
+    /// Returns this schema with a description.
+    pub fn describe(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Adds a property which must be present.
+    pub fn property<T: ConfigSchema>(mut self, name: &str, description: &str) -> Self {
+        self.properties.push((name.to_string(), T::schema().describe(description)));
+        self.required.push(name.to_string());
+        self
+    }
+
+    /// Adds a property which may be left out, in which case a default value is used.
+    pub fn optional<T: ConfigSchema>(mut self, name: &str, description: &str) -> Self {
+        self.properties.push((name.to_string(), T::schema().describe(description)));
+        self
+    }
+
+    /// Adds the properties of `T`, for fields with `#[serde(flatten)]`.
+    pub fn flatten<T: ConfigSchema>(mut self) -> Self {
+        let schema = T::schema();
+        self.properties.extend(schema.properties);
+        self.required.extend(schema.required);
+        self
+    }
+
+    /// Returns this schema as pretty-printed JSON, ending with a newline.
+    pub fn to_json(&self) -> String {
+        // Serializing only fails for maps with non-string keys, which schemas don't have
+        let mut json = serde_json::to_string_pretty(self).unwrap();
+        json.push('\n');
+        json
+    }
+
+    /// Returns the schema of a property, if this is an object schema with that property.
+    pub fn get(&self, name: &str) -> Option<&Schema> {
+        self.properties.iter().find(|(property, _)| property == name).map(|(_, schema)| schema)
+    }
+}
+
+/// Types with a YAML format in the project files which can be described by a [`Schema`]. The schemas are maintained by
+/// hand next to the serde attributes they describe, so new config fields must be added here as well.
+pub trait ConfigSchema {
+    /// Returns the schema of this type.
+    fn schema() -> Schema;
+}
+
+/// Project files which [`SchemaKind::schema`] can describe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// The ROM config, normally `config.yaml`. See [`RomConfig`].
+    Config,
+    /// The ARM9 config, normally `arm9/arm9.yaml`. See [`Arm9BuildConfig`].
+    Arm9,
+    /// An overlay list, normally `arm9_overlays/overlays.yaml`. See [`OverlayConfig`].
+    Overlays,
+    /// The banner, normally `banner/banner.yaml`. See [`Banner`].
+    Banner,
+    /// The header, normally `header.yaml`. See [`Header`].
+    Header,
+}
+
+impl SchemaKind {
+    /// Every kind of project file.
+    pub const ALL: [Self; 5] = [Self::Config, Self::Arm9, Self::Overlays, Self::Banner, Self::Header];
+
+    /// Returns the default file name of this project file.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Config => "config.yaml",
+            Self::Arm9 => "arm9.yaml",
+            Self::Overlays => "overlays.yaml",
+            Self::Banner => "banner.yaml",
+            Self::Header => "header.yaml",
+        }
+    }
+
+    /// Returns the root schema of this project file, tagged with the version of ds-rom.
+    pub fn schema(self) -> Schema {
+        let schema = match self {
+            Self::Config => RomConfig::schema(),
+            Self::Arm9 => Arm9BuildConfig::schema(),
+            Self::Overlays => Vec::<OverlayConfig>::schema().describe("Overlays and their settings"),
+            Self::Banner => Banner::schema(),
+            Self::Header => Header::schema(),
+        };
+        Schema {
+            dialect: Some("https://json-schema.org/draft/2020-12/schema".to_string()),
+            comment: Some(format!("Generated by ds-rom {}", env!("CARGO_PKG_VERSION"))),
+            title: Some(self.file_name().to_string()),
+            ..schema
+        }
+    }
+}
+
+impl Display for SchemaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config => write!(f, "config"),
+            Self::Arm9 => write!(f, "arm9"),
+            Self::Overlays => write!(f, "overlays"),
+            Self::Banner => write!(f, "banner"),
+            Self::Header => write!(f, "header"),
+        }
+    }
+}
+
+macro_rules! integer_schema {
+    ($($ty:ty),*) => {
+        $(
+            impl ConfigSchema for $ty {
+                fn schema() -> Schema {
+                    Schema::integer(<$ty>::MAX as u64)
+                }
+            }
+        )*
+    };
+}
+
+integer_schema!(u8, u16, u32, u64, usize);
+
+impl ConfigSchema for bool {
+    fn schema() -> Schema {
+        Schema::of_type(SchemaType::Boolean)
+    }
+}
+
+impl ConfigSchema for String {
+    fn schema() -> Schema {
+        Schema::of_type(SchemaType::String)
+    }
+}
+
+impl ConfigSchema for PathBuf {
+    fn schema() -> Schema {
+        Schema::of_type(SchemaType::String)
+    }
+}
+
+impl<T: ConfigSchema> ConfigSchema for Option<T> {
+    fn schema() -> Schema {
+        Schema { any_of: vec![T::schema(), Schema::of_type(SchemaType::Null)], ..Default::default() }
+    }
+}
+
+impl<T: ConfigSchema> ConfigSchema for Vec<T> {
+    fn schema() -> Schema {
+        Schema { items: Some(Box::new(T::schema())), ..Schema::of_type(SchemaType::Array) }
+    }
+}
+
+impl<T: ConfigSchema> ConfigSchema for Box<[T]> {
+    fn schema() -> Schema {
+        Vec::<T>::schema()
+    }
+}
+
+impl<T: ConfigSchema, const N: usize> ConfigSchema for [T; N] {
+    fn schema() -> Schema {
+        Schema { min_items: Some(N), max_items: Some(N), ..Vec::<T>::schema() }
+    }
+}
+
+impl<const N: usize> ConfigSchema for AsciiArray<N> {
+    fn schema() -> Schema {
+        Schema::pattern(&format!("^[\\x00-\\x7f]{{0,{N}}}$"))
+    }
+}
+
+// Bitfields and wrappers which are saved as their raw integer
+
+macro_rules! raw_integer_schema {
+    ($($ty:ty => $raw:ty),*) => {
+        $(
+            impl ConfigSchema for $ty {
+                fn schema() -> Schema {
+                    <$raw>::schema()
+                }
+            }
+        )*
+    };
+}
+
+raw_integer_schema!(
+    Capacity => u8, Delay => u16, DsFlags => u8, DsiFlags => u8, DsiFlags2 => u32, RegionFlags => u32, AccessControl => u32
+);
+
+impl ConfigSchema for OverlayFlags {
+    fn schema() -> Schema {
+        Schema::pattern("^(0x)?[0-9a-fA-F]{1,8}$")
+    }
+}
+
+impl ConfigSchema for TableOffset {
+    fn schema() -> Schema {
+        Schema::object("Offset and size of a ROM section")
+            .property::<u32>("offset", "ROM offset")
+            .property::<u32>("size", "Size in bytes")
+    }
+}
+
+impl ConfigSchema for RomConfig {
+    fn schema() -> Schema {
+        Schema::object("Config file mainly consisting of paths to extracted files")
+            .property::<u8>("padding_value", "Byte value to append between ROM sections")
+            .property::<PathBuf>("header", "Path to header YAML")
+            .property::<PathBuf>("header_logo", "Path to header logo PNG")
+            .optional::<Option<PathBuf>>(
+                "pre_arm9",
+                "Path to data between the header and ARM9 program, only present if it's not padding",
+            )
+            .optional::<Option<PathBuf>>(
+                "trailing_data",
+                "Path to data appended after the padded ROM, such as a custom footer or signature, only present if it's not \
+                padding",
+            )
+            .property::<PathBuf>("arm9_bin", "Path to ARM9 binary")
+            .property::<PathBuf>("arm9_config", "Path to ARM9 YAML")
+            .property::<PathBuf>("arm7_bin", "Path to ARM7 binary")
+            .property::<PathBuf>("arm7_config", "Path to ARM7 YAML")
+            .property::<RomConfigAutoload>("itcm", "Path to ITCM files")
+            .property::<RomConfigAutoload>("dtcm", "Path to DTCM files")
+            .optional::<Vec<RomConfigAutoload>>("unknown_autoloads", "Path to unknown autoloads")
+            .optional::<Option<RomConfigDsi>>(
+                "dsi",
+                "Paths to the DSi programs and digest hashtables, only present if the ROM has a DSi area",
+            )
+            .optional::<Option<PathBuf>>("arm9_overlays", "Path to ARM9 overlays YAML")
+            .optional::<Option<PathBuf>>(
+                "arm9_ovt_bin",
+                "Path to ARM9 overlay table binary, which overrides the table generated from the ARM9 overlays YAML",
+            )
+            .optional::<Option<PathBuf>>("arm7_overlays", "Path to ARM7 overlays YAML")
+            .optional::<Option<PathBuf>>("banner", "Path to banner YAML, absent if the ROM has no banner")
+            .property::<PathBuf>("files_dir", "Path to asset files directory")
+            .property::<PathBuf>("path_order", "Path to path order file")
+            .optional::<Option<PathBuf>>(
+                "fnt_order",
+                "Path to FNT order file, only present if the original FNT didn't list files and directories in the default \
+                order",
+            )
+            .optional::<Vec<EmptyDirFileId>>(
+                "empty_dir_file_ids",
+                "First file IDs of directories without files, only present if the original FNT didn't give them the ID of \
+                the next file",
+            )
+            .optional::<Option<PathBuf>>(
+                "file_order",
+                "Path to exhaustive list of files in the order they're placed in the ROM, overrides path_order when building",
+            )
+            .optional::<Option<PathBuf>>("skipped_files", "Path to list of files which were not extracted")
+            .optional::<Option<PathBuf>>("aliased_files", "Path to list of files which refer to other sections of the ROM")
+            .optional::<Vec<TransparentCompression>>(
+                "transparent_compression",
+                "Rules for files which are saved decompressed and compressed again when building",
+            )
+            .optional::<Option<PathBuf>>("compressed_files", "Path to list of files which were saved decompressed")
+            .optional::<Option<DedupStore>>("dedup_store", "Content-addressed store which the files directory refers to")
+            .optional::<Option<MemoryLimits>>(
+                "memory_limits",
+                "Memory limits to validate program sizes against, defaults to the limits of retail units",
+            )
+            .optional::<Option<RomConfigSectionOffsets>>(
+                "section_offsets",
+                "Absolute offsets to sections, only present if the original ROM had extra padding before them",
+            )
+            .optional::<Option<FatLayout>>(
+                "fat_layout",
+                "Layout of the FAT, only present if the original ROM's FAT doesn't follow the default layout",
+            )
+            .optional::<bool>(
+                "empty_fnt",
+                "Whether the FNT is empty instead of having a root directory, as in some carts without a file system. Only \
+                takes effect if there are no files.",
+            )
+            .optional::<bool>(
+                "dedup_identical_files",
+                "Whether files with identical contents are stored once and share their FAT entries",
+            )
+            .optional::<Option<PreservationLevel>>(
+                "preservation",
+                "Preservation level the project was extracted with. Building an `exact` project fails if the header has \
+                consistency problems.",
+            )
+    }
+}
+
+impl ConfigSchema for RomConfigAutoload {
+    fn schema() -> Schema {
+        Schema::object("Path to autoload files")
+            .property::<PathBuf>("bin", "Path to binary")
+            .property::<PathBuf>("config", "Path to YAML")
+    }
+}
+
+impl ConfigSchema for RomConfigDsi {
+    fn schema() -> Schema {
+        Schema::object("Paths to the files of the DSi area")
+            .property::<PathBuf>("arm9i_bin", "Path to ARM9i binary")
+            .property::<PathBuf>("arm9i_config", "Path to ARM9i YAML")
+            .property::<PathBuf>("arm7i_bin", "Path to ARM7i binary")
+            .property::<PathBuf>("arm7i_config", "Path to ARM7i YAML")
+            .property::<PathBuf>("digest_sector_hashtable", "Path to digest sector hashtable binary")
+            .property::<PathBuf>("digest_block_hashtable", "Path to digest block hashtable binary")
+    }
+}
+
+impl ConfigSchema for RomConfigSectionOffsets {
+    fn schema() -> Schema {
+        Schema::object("Absolute offsets to sections in the ROM, overriding the next aligned offset")
+            .optional::<Option<u32>>("fnt", "Offset to the file name table")
+            .optional::<Option<u32>>("fat", "Offset to the file allocation table")
+            .optional::<Option<u32>>("banner", "Offset to the banner")
+            .optional::<Option<u32>>("digest_sector_hashtable", "Offset to the digest sector hashtable")
+            .optional::<Option<u32>>("digest_block_hashtable", "Offset to the digest block hashtable")
+            .optional::<Option<u32>>("arm9i", "Offset to the ARM9i program, which is also the start of the DSi region")
+            .optional::<Option<u32>>("arm7i", "Offset to the ARM7i program")
+    }
+}
+
+impl ConfigSchema for EmptyDirFileId {
+    fn schema() -> Schema {
+        Schema::object("First file ID of a directory without files")
+            .property::<String>("path", "Path to the directory as in the FNT order file, `/` for the root directory")
+            .property::<u16>("first_file_id", "First file ID of the directory in the original FNT")
+    }
+}
+
+impl ConfigSchema for FatLayout {
+    fn schema() -> Schema {
+        Schema::object("Layout of the file allocation table")
+            .optional::<Option<u16>>(
+                "reserved_entries",
+                "Number of entries reserved at the start of the FAT before the first file, defaults to the number of overlays",
+            )
+            .optional::<Option<u32>>(
+                "num_entries",
+                "Total number of entries in the FAT, only needed if the FAT has unused entries at the end",
+            )
+    }
+}
+
+impl ConfigSchema for TransparentCompression {
+    fn schema() -> Schema {
+        Schema::object("Files which are saved decompressed and compressed again when building")
+            .property::<String>(
+                "glob",
+                "Glob pattern matched against paths relative to the files directory. `*` matches any sequence of \
+                characters, including `/`.",
+            )
+            .property::<CompressionFormat>("format", "Compression format of the matching files")
+    }
+}
+
+impl ConfigSchema for CompressionFormat {
+    fn schema() -> Schema {
+        Schema::enumeration(&["lz10", "rle", "huffman4", "huffman8"])
+    }
+}
+
+impl ConfigSchema for DedupStore {
+    fn schema() -> Schema {
+        Schema::object("Content-addressed store of file contents, which can be shared between projects")
+            .property::<PathBuf>("path", "Path to the store directory. Relative paths are relative to the project root.")
+            .optional::<DedupMode>("mode", "How project files refer to blobs in the store")
+    }
+}
+
+impl ConfigSchema for PreservationLevel {
+    fn schema() -> Schema {
+        Schema::enumeration(&["minimal", "standard", "exact"])
+    }
+}
+
+impl ConfigSchema for DedupMode {
+    fn schema() -> Schema {
+        Schema::enumeration(&["reference", "hardlink"])
+    }
+}
+
+impl ConfigSchema for MemoryLimits {
+    fn schema() -> Schema {
+        Schema::object("Memory limits for programs and overlays")
+            .property::<u32>(
+                "arm9_main_ram_end",
+                "End of main RAM available to the ARM9 program and its overlays. The DTCM is also a limit if it's placed \
+                in main RAM.",
+            )
+            .property::<u32>("main_ram_end", "End of main RAM, used for ARM7 programs and overlays placed in main RAM.")
+            .property::<u32>("arm7_wram_end", "End of WRAM, used for ARM7 programs placed in WRAM.")
+    }
+}
+
+impl ConfigSchema for Arm9BuildConfig {
+    fn schema() -> Schema {
+        Schema::object("Config file for the ARM9 main module")
+            .flatten::<Arm9Offsets>()
+            .property::<bool>("encrypted", "Whether this module is encrypted in the ROM.")
+            .optional::<bool>(
+                "no_secure_area",
+                "Whether this module has no secure area, as in homebrew, so it is never encrypted or decrypted.",
+            )
+            .property::<bool>("compressed", "Whether this module is compressed in the ROM.")
+            .flatten::<BuildInfo>()
+            .optional::<Option<[u8; 0x14]>>(
+                "blowfish_key_sha1",
+                "SHA-1 hash of the Blowfish key which decrypted this module, so that the same key is used when building.",
+            )
+            .optional::<Arm9FooterConfig>("footer", "Footer fields to preserve, if they differ from the generated footer.")
+            .optional::<bool>("lenient_build_info", "Whether the build info has a missing or corrupted nitrocode.")
+            .optional::<Option<u32>>("compression_start", "Offset where compression starts, if it differs from 0x4000.")
+    }
+}
+
+impl ConfigSchema for Arm9Offsets {
+    fn schema() -> Schema {
+        Schema::object("Offsets in the ARM9 program")
+            .property::<u32>("base_address", "Base address.")
+            .property::<u32>("entry_function", "Entrypoint function address.")
+            .property::<u32>("build_info", "Build info offset.")
+            .property::<u32>("autoload_callback", "Autoload callback address.")
+            .optional::<Option<Arm9OverlaySignatures>>(
+                "overlay_signatures",
+                "Location of the overlay signature table, if the ARM9 overlays are signed.",
+            )
+    }
+}
+
+impl ConfigSchema for Arm9OverlaySignatures {
+    fn schema() -> Schema {
+        Schema::object("Location of the overlay signature table in the ARM9 program")
+            .property::<Arm9FooterValue>("footer_value", "How the ARM9 footer refers to the table.")
+            .optional::<Option<u32>>(
+                "autoload",
+                "Base address of the autoload block containing the table, or null if it's in the main ARM9 code.",
+            )
+            .property::<u32>("offset", "Offset of the table from the start of the main ARM9 code or of the autoload block.")
+    }
+}
+
+impl ConfigSchema for Arm9FooterValue {
+    fn schema() -> Schema {
+        Schema::enumeration(&["raw_offset", "address"])
+    }
+}
+
+impl ConfigSchema for BuildInfo {
+    fn schema() -> Schema {
+        Schema::object("Build info for the ARM9 program")
+            .property::<u32>("bss_start", "Start of the uninitialized section.")
+            .property::<u32>("bss_end", "End of the uninitialized section.")
+            .property::<u32>("sdk_version", "SDK version?")
+    }
+}
+
+impl ConfigSchema for Arm9FooterConfig {
+    fn schema() -> Schema {
+        Schema::object("Fields of the ARM9 footer which can't be derived from the ARM9 program")
+            .optional::<Option<u32>>(
+                "build_info_offset",
+                "Build info offset to place in the footer, if it differs from the build info offset of the program.",
+            )
+            .optional::<u32>(
+                "reserved",
+                "Value after the build info offset, normally zero. Generated from overlay_signatures if the ARM9 overlays are \
+                signed.",
+            )
+    }
+}
+
+impl ConfigSchema for OverlayConfig {
+    fn schema() -> Schema {
+        Schema::object("Overlay configuration")
+            .flatten::<OverlayInfo>()
+            .optional::<String>("file_name", "Name of binary file, empty for placeholders.")
+            .optional::<bool>(
+                "placeholder",
+                "Whether this is a placeholder entry in the overlay table, which has no binary file.",
+            )
+    }
+}
+
+impl ConfigSchema for OverlayInfo {
+    fn schema() -> Schema {
+        Schema::object("Info of an overlay, similar to an entry in the overlay table")
+            .property::<u32>("id", "Overlay ID.")
+            .property::<u32>("base_address", "Base address.")
+            .property::<u32>("code_size", "Initialized size.")
+            .property::<u32>("bss_size", "Uninitialized size.")
+            .property::<u32>("ctor_start", "Offset to start of .ctor section.")
+            .property::<u32>("ctor_end", "Offset to end of .ctor section.")
+            .property::<u32>("file_id", "File ID for the FAT.")
+            .property::<bool>("compressed", "Whether the overlay is compressed.")
+            .optional::<OverlayFlags>(
+                "flags",
+                "Raw flags as a hexadecimal string, used to preserve the signed and reserved bits. The compressed size and \
+                flag are set when building.",
+            )
+    }
+}
+
+impl ConfigSchema for Banner {
+    fn schema() -> Schema {
+        Schema::object("ROM banner")
+            .property::<BannerVersion>("version", "Banner version, which decides the available titles and animation.")
+            .property::<BannerTitle>("title", "Game title in different languages.")
+            .property::<BannerImages>("images", "Icon to show on the home screen.")
+            .optional::<Option<Vec<BannerKeyframe>>>("keyframes", "Keyframes for animated icons.")
+            .optional::<Option<BannerTitleFiles>>("title_files", "Text files which override the titles in `title`.")
+            .optional::<Option<BannerCrcs>>("crcs", "CRCs recorded when extracting, only used to detect changes.")
+    }
+}
+
+impl ConfigSchema for BannerCrcs {
+    fn schema() -> Schema {
+        Schema::object("CRCs of the banner as they were in the extracted ROM")
+            .property::<u16>("original", "CRC of the titles and icon of the original version.")
+            .optional::<Option<u16>>("china", "CRC which also covers the Chinese title.")
+            .optional::<Option<u16>>("korea", "CRC which also covers the Korean title.")
+            .optional::<Option<u16>>("animated", "CRC of the animated icon.")
+    }
+}
+
+impl ConfigSchema for BannerVersion {
+    fn schema() -> Schema {
+        Schema::enumeration(&["Original", "China", "Korea", "Animated"])
+    }
+}
+
+impl ConfigSchema for BannerTitle {
+    fn schema() -> Schema {
+        Schema::object("Game title in different languages")
+            .property::<String>("japanese", "Japanese.")
+            .property::<String>("english", "English.")
+            .property::<String>("french", "French.")
+            .property::<String>("german", "German.")
+            .property::<String>("italian", "Italian.")
+            .property::<String>("spanish", "Spanish.")
+            .optional::<Option<String>>("chinese", "Chinese.")
+            .optional::<Option<String>>("korean", "Korean.")
+            .optional::<Option<Vec<u16>>>("japanese_raw_utf16", "Raw UTF-16 code units of the Japanese title.")
+            .optional::<Option<Vec<u16>>>("english_raw_utf16", "Raw UTF-16 code units of the English title.")
+            .optional::<Option<Vec<u16>>>("french_raw_utf16", "Raw UTF-16 code units of the French title.")
+            .optional::<Option<Vec<u16>>>("german_raw_utf16", "Raw UTF-16 code units of the German title.")
+            .optional::<Option<Vec<u16>>>("italian_raw_utf16", "Raw UTF-16 code units of the Italian title.")
+            .optional::<Option<Vec<u16>>>("spanish_raw_utf16", "Raw UTF-16 code units of the Spanish title.")
+            .optional::<Option<Vec<u16>>>("chinese_raw_utf16", "Raw UTF-16 code units of the Chinese title.")
+            .optional::<Option<Vec<u16>>>("korean_raw_utf16", "Raw UTF-16 code units of the Korean title.")
+    }
+}
+
+impl ConfigSchema for BannerTitleFiles {
+    fn schema() -> Schema {
+        Schema::object("Paths to files which override the titles, relative to the banner directory")
+            .optional::<Option<PathBuf>>("japanese", "Japanese.")
+            .optional::<Option<PathBuf>>("english", "English.")
+            .optional::<Option<PathBuf>>("french", "French.")
+            .optional::<Option<PathBuf>>("german", "German.")
+            .optional::<Option<PathBuf>>("italian", "Italian.")
+            .optional::<Option<PathBuf>>("spanish", "Spanish.")
+            .optional::<Option<PathBuf>>("chinese", "Chinese.")
+            .optional::<Option<PathBuf>>("korean", "Korean.")
+    }
+}
+
+impl ConfigSchema for BannerImages {
+    fn schema() -> Schema {
+        Schema::object("Icon for the banner")
+            .property::<PathBuf>("bitmap_path", "Path to bitmap PNG.")
+            .property::<PathBuf>("palette_path", "Path to palette PNG.")
+            .optional::<BannerRemapPolicy>("remap_policy", "How to handle bitmap colors which are not in the palette.")
+            .optional::<Option<PathBuf>>(
+                "raw_path",
+                "Path to a raw copy of the bitmap and palette, used if the PNGs are missing.",
+            )
+            .optional::<Vec<BannerAnimationBitmapFile>>("animation_bitmap_files", "Bitmap PNGs of the animated icon.")
+            .optional::<Vec<PathBuf>>("animation_palette_paths", "Paths to the palette PNGs of the animated icon.")
+            .optional::<Option<PathBuf>>(
+                "animation_raw_path",
+                "Path to a raw copy of the animation bitmaps and palettes, which preserves the palette indexes of unchanged \
+                pixels and is used if the PNGs are missing.",
+            )
+    }
+}
+
+impl ConfigSchema for BannerAnimationBitmapFile {
+    fn schema() -> Schema {
+        Schema::object("Bitmap PNG of an animated icon")
+            .property::<PathBuf>("path", "Path to the bitmap PNG.")
+            .property::<usize>("palette", "Index of the animation palette which the PNG is drawn with.")
+    }
+}
+
+impl ConfigSchema for BannerRemapPolicy {
+    fn schema() -> Schema {
+        Schema::enumeration(&["strict", "nearest_color", "quantize"])
+    }
+}
+
+impl ConfigSchema for BannerKeyframe {
+    fn schema() -> Schema {
+        Schema::object("Keyframe for animated icon")
+            .property::<bool>("flip_vertically", "Flips the bitmap vertically.")
+            .property::<bool>("flip_horizontally", "Flips the bitmap horizontally.")
+            .property::<usize>("palette", "Palette index.")
+            .property::<usize>("bitmap", "Bitmap index.")
+            .property::<usize>("frame_duration", "Duration in frames.")
+    }
+}
+
+impl ConfigSchema for Header {
+    fn schema() -> Schema {
+        Schema::object("ROM header")
+            .flatten::<HeaderOriginal>()
+            .optional::<Option<HeaderDsPostDsi>>("ds_post_dsi", "Values for DS games after DSi release.")
+            .optional::<Option<HeaderDsi>>(
+                "dsi",
+                "Values for DSi-enhanced and DSi-exclusive games, only present if the ROM has a DSi area.",
+            )
+    }
+}
+
+impl ConfigSchema for HeaderOriginal {
+    fn schema() -> Schema {
+        Schema::object("Values for the original header version")
+            .property::<AsciiArray<12>>("title", "Short game title, normally in uppercase letters.")
+            .property::<AsciiArray<4>>("gamecode", "4-character game code in uppercase letters.")
+            .property::<AsciiArray<2>>("makercode", "2-character maker code, normally \"01\".")
+            .property::<u8>("unitcode", "Unit code, depends on which platform (DS, DSi) this game is for.")
+            .property::<u8>("seed_select", "Encryption seed select.")
+            .property::<DsFlags>("ds_flags", "Flags for both DS and DSi.")
+            .property::<u8>("autostart", "Autostart, can skip \"Health and Safety\" screen.")
+            .property::<u32>("normal_cmd_setting", "Port 0x40001a4 setting for normal commands.")
+            .property::<u32>("key1_cmd_setting", "Port 0x40001a4 setting for KEY1 commands.")
+            .property::<Delay>("secure_area_delay", "Delay to wait for secure area.")
+            .property::<u16>("rom_nand_end", "NAND end of ROM area in multiples of 0x20000 (0x80000 on DSi).")
+            .property::<u16>("rw_nand_end", "NAND end of RW area in multiples of 0x20000 (0x80000 on DSi).")
+            .optional::<Option<Capacity>>(
+                "capacity",
+                "ROM capacity of the original ROM, used if it's larger than the capacity needed for the built ROM.",
+            )
+            .optional::<bool>("preserve_capacity", "Always uses `capacity`, even if the built ROM needs a larger capacity.")
+    }
+}
+
+impl ConfigSchema for HeaderDsPostDsi {
+    fn schema() -> Schema {
+        Schema::object("Values for DS games after DSi release")
+            .property::<DsiFlags2>("dsi_flags_2", "DSi-exclusive flags.")
+            .property::<[u8; 0x14]>("sha1_hmac_banner", "SHA1-HMAC of banner.")
+            .property::<[u8; 0x14]>("sha1_hmac_unk1", "Unknown SHA1-HMAC, defined by some games.")
+            .property::<[u8; 0x14]>("sha1_hmac_unk2", "Unknown SHA1-HMAC, defined by some games.")
+            .property::<Box<[u8]>>("rsa_sha1", "RSA-SHA1 signature of the header.")
+    }
+}
+
+impl ConfigSchema for HeaderDsi {
+    fn schema() -> Schema {
+        Schema::object("Values for DSi-enhanced and DSi-exclusive games")
+            .property::<DsiFlags>("dsi_flags", "DSi-specific flags.")
+            .property::<[u32; 5]>("memory_banks_wram", "MBK1 to MBK5, the WRAM slot settings.")
+            .property::<[u32; 3]>("memory_banks_arm9", "MBK6 to MBK8 for ARM9, the WRAM area settings.")
+            .property::<[u32; 3]>("memory_banks_arm7", "MBK6 to MBK8 for ARM7, the WRAM area settings.")
+            .property::<u32>("memory_bank_9", "MBK9, the WRAM slot write protection.")
+            .property::<RegionFlags>("region_flags", "Regions the game can be played in.")
+            .property::<AccessControl>("access_control", "Access control.")
+            .property::<u32>("arm7_scfg_ext7_setting", "ARM7 SCFG_EXT7 setting.")
+            .property::<TableOffset>("digest_ds_area", "Range of the DS area which the digest sector hashtable covers.")
+            .property::<TableOffset>("digest_dsi_area", "Range of the DSi area which the digest sector hashtable covers.")
+            .property::<u32>("digest_sector_size", "Size of each sector hashed in the digest sector hashtable.")
+            .property::<u32>("digest_sector_count", "Number of sectors per block in the digest block hashtable.")
+            .property::<[u8; 6]>("sd_shared2_sizes", "Sizes of the shared2/0000 to shared2/0005 files on the SD card/NAND.")
+            .property::<u8>("eula_version", "EULA version.")
+            .property::<bool>("use_ratings", "Use age ratings.")
+            .property::<[u8; 0x10]>("age_ratings", "Age ratings.")
+            .property::<u32>("file_type", "File type, the upper half of the title ID.")
+            .property::<u32>("sd_public_sav_size", "Size of the public.sav file on the SD card/NAND.")
+            .property::<u32>("sd_private_sav_size", "Size of the private.sav file on the SD card/NAND.")
+            .property::<TableOffset>("modcrypt_area_1", "Modcrypt area 1, copied as-is.")
+            .property::<TableOffset>("modcrypt_area_2", "Modcrypt area 2, copied as-is.")
+            .property::<[u8; 0x14]>("sha1_hmac_arm9_with_secure_area", "SHA1-HMAC of ARM9 program including secure area.")
+            .property::<[u8; 0x14]>("sha1_hmac_arm7", "SHA1-HMAC of ARM7 program.")
+            .property::<[u8; 0x14]>("sha1_hmac_digest", "SHA1-HMAC of digest section.")
+            .property::<[u8; 0x14]>("sha1_hmac_arm9i", "SHA1-HMAC of decrypted ARM9i.")
+            .property::<[u8; 0x14]>("sha1_hmac_arm7i", "SHA1-HMAC of decrypted ARM7i.")
+            .property::<[u8; 0x14]>("sha1_hmac_arm9", "SHA1-HMAC of ARM9 program excluding secure area.")
+    }
+}
@@ -0,0 +1,34 @@
+use std::cmp::Ordering;
+
+use encoding_rs::SHIFT_JIS;
+
+/// Compares two names in the order they appear in an FNT subtable, which also decides the order of file IDs.
+///
+/// Files are sorted before directories. Within each group, the names are encoded to Shift-JIS and then compared
+/// byte-by-byte after lowercasing each ASCII byte, like `strcasecmp`. Since the lowercasing is done per byte without
+/// accounting for multibyte characters, the second byte of a multibyte character is lowercased too if it happens to be in
+/// the range `A-Z`. For example, コ (83 52) is sorted as if it was ビ (83 72). This quirk was observed in the Japanese file
+/// names of 999: Nine Hours, Nine Persons, Nine Doors.
+pub fn fnt_name_cmp(a: &str, b: &str, a_is_dir: bool, b_is_dir: bool) -> Ordering {
+    let files_first = a_is_dir.cmp(&b_is_dir);
+    if files_first.is_ne() {
+        return files_first;
+    }
+
+    // Convert to Shift-JIS first, *then* convert to lowercase byte-by-byte
+    let (mut a_bytes, _, _) = SHIFT_JIS.encode(a);
+    let (mut b_bytes, _, _) = SHIFT_JIS.encode(b);
+    let a_vec = a_bytes.to_mut();
+    let b_vec = b_bytes.to_mut();
+    a_vec.make_ascii_lowercase();
+    b_vec.make_ascii_lowercase();
+
+    // Lexicographic, case-insensitive Shift-JIS order
+    a_vec.cmp(&b_vec)
+}
+
+/// Compares two names in the order their contents are appended to the ROM, which is lexicographic UTF-8 order. Unlike
+/// [`fnt_name_cmp`], this is case-sensitive and doesn't separate files from directories.
+pub fn rom_name_cmp(a: &str, b: &str) -> Ordering {
+    a.cmp(b)
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use bytemuck::{Pod, Zeroable};
 use serde::{de, Deserialize, Serialize};
@@ -12,29 +12,52 @@ pub struct AsciiArray<const N: usize>(pub [u8; N]);
 #[derive(Debug, Snafu)]
 pub enum AsciiArrayError {
     /// Occurs when an input character is not in ASCII.
-    #[snafu(display("the provided string '{string}' contains one or more non-ASCII characters:\n{backtrace}"))]
+    #[snafu(display(
+        "the provided string '{string}' contains the non-ASCII character '{character}' at byte {index}:\n{backtrace}"
+    ))]
     NotAscii {
         /// The invalid string.
         string: String,
+        /// The first non-ASCII character.
+        character: char,
+        /// Byte index of the non-ASCII character.
+        index: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+    /// Occurs when the input string does not fit in the array.
+    #[snafu(display(
+        "the provided string '{string}' is {length} characters long but the maximum length is {max_length}:\n{backtrace}"
+    ))]
+    TooLong {
+        /// The invalid string.
+        string: String,
+        /// Length of the string.
+        length: usize,
+        /// Maximum length of the array.
+        max_length: usize,
         /// Backtrace to the source of the error.
         backtrace: Backtrace,
     },
 }
 
-impl<const N: usize> AsciiArray<N> {
+impl<const N: usize> FromStr for AsciiArray<N> {
+    type Err = AsciiArrayError;
+
     /// Loads from a `&str`.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the string contains a non-ASCII character.
-    pub fn from_str(string: &str) -> Result<Self, AsciiArrayError> {
-        let mut chars = [0u8; N];
-        for (i, ch) in string.chars().take(N).enumerate() {
-            if !ch.is_ascii() {
-                return NotAsciiSnafu { string: string.to_string() }.fail();
-            }
-            chars[i] = ch as u8;
+    /// This function will return an error if the string contains a non-ASCII character or is longer than `N` characters.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        if let Some((index, character)) = string.char_indices().find(|(_, ch)| !ch.is_ascii()) {
+            return NotAsciiSnafu { string, character, index }.fail();
+        }
+        if string.len() > N {
+            return TooLongSnafu { string, length: string.len(), max_length: N }.fail();
         }
+        let mut chars = [0u8; N];
+        chars[..string.len()].copy_from_slice(string.as_bytes());
         Ok(Self(chars))
     }
 }
@@ -73,7 +96,7 @@ impl<'de, const N: usize> Deserialize<'de> for AsciiArray<N> {
         D: serde::Deserializer<'de>,
     {
         let string: String = Deserialize::deserialize(deserializer)?;
-        Ok(Self::from_str(&string).map_err(de::Error::custom)?)
+        string.parse().map_err(de::Error::custom)
     }
 }
 
@@ -84,39 +107,81 @@ pub struct Unicode16Array<const N: usize>(pub [u16; N]);
 unsafe impl<const N: usize> Zeroable for Unicode16Array<N> {}
 unsafe impl<const N: usize> Pod for Unicode16Array<N> {}
 
+/// Errors related to [`Unicode16Array`].
+#[derive(Debug, Snafu)]
+#[snafu(module)]
+pub enum Unicode16Error {
+    /// Occurs when the input string does not fit in the array.
+    #[snafu(display(
+        "the provided string '{string}' is {length} UTF-16 code units long but the maximum length is {max_length}:\n{backtrace}"
+    ))]
+    TooLong {
+        /// The invalid string.
+        string: String,
+        /// Length of the string in UTF-16 code units.
+        length: usize,
+        /// Maximum length of the array.
+        max_length: usize,
+        /// Backtrace to the source of the error.
+        backtrace: Backtrace,
+    },
+}
+
+impl<const N: usize> FromStr for Unicode16Array<N> {
+    type Err = Unicode16Error;
+
+    /// Loads from a `&str`. The last code unit is reserved for the null terminator.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the string is longer than `N - 1` UTF-16 code units.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let length = string.encode_utf16().count();
+        let max_length = N.saturating_sub(1);
+        if length > max_length {
+            return unicode16_error::TooLongSnafu { string, length, max_length }.fail();
+        }
+        let mut chars = [0u16; N];
+        for (i, ch) in string.encode_utf16().enumerate() {
+            chars[i] = ch;
+        }
+        Ok(Self(chars))
+    }
+}
+
 impl<const N: usize> Unicode16Array<N> {
-    /// Loads from a `&str`.
-    pub fn from_str(string: &str) -> Self {
+    /// Loads from UTF-16 code units, which are not required to be valid UTF-16.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there are more than `N` code units.
+    pub fn from_units(units: &[u16]) -> Result<Self, Unicode16Error> {
+        if units.len() > N {
+            let string = String::from_utf16_lossy(units);
+            return unicode16_error::TooLongSnafu { string, length: units.len(), max_length: N }.fail();
+        }
         let mut chars = [0u16; N];
-        let mut i = 0;
-        for ch in string.chars() {
-            let mut codepoints = [0u16; 2];
-            ch.encode_utf16(&mut codepoints);
+        chars[..units.len()].copy_from_slice(units);
+        Ok(Self(chars))
+    }
 
-            let len = if codepoints[1] != 0 { 2 } else { 1 };
-            if i + len >= N {
-                break;
-            }
+    /// Returns the code units up to the first null terminator.
+    pub fn units(&self) -> &[u16] {
+        let len = self.0.iter().position(|&ch| ch == 0).unwrap_or(N);
+        &self.0[..len]
+    }
 
-            for j in 0..len {
-                chars[i] = codepoints[j];
-                i += 1;
-            }
-        }
-        Self(chars)
+    /// Returns whether the code units up to the first null terminator are valid UTF-16, i.e. have no unpaired surrogates.
+    pub fn is_valid_utf16(&self) -> bool {
+        char::decode_utf16(self.units().iter().copied()).all(|ch| ch.is_ok())
     }
 }
 
 impl<const N: usize> Display for Unicode16Array<N> {
+    /// Writes the string up to the first null terminator. Invalid UTF-16 is replaced with U+FFFD.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for ch in self.0 {
-            if ch == 0 {
-                break;
-            }
-            let Some(ch) = char::from_u32(ch as u32) else {
-                break;
-            };
-            write!(f, "{ch}")?;
+        for ch in char::decode_utf16(self.units().iter().copied()) {
+            write!(f, "{}", ch.unwrap_or(char::REPLACEMENT_CHARACTER))?;
         }
         Ok(())
     }
@@ -135,6 +200,76 @@ impl Display for BlobSize {
     }
 }
 
+/// Returns whether `text` matches the glob `pattern`, where `*` matches any sequence of characters and `?` matches a single
+/// character.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => {
+                let Some((star, matched)) = backtrack else { return false };
+                p = star + 1;
+                t = matched + 1;
+                backtrack = Some((star, matched + 1));
+            }
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+/// Returns `bytes` as a lowercase hexadecimal string without separators.
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Serializes a byte array, such as a SHA-1 hash, as a hexadecimal string like [`hex_string`]. For use with
+/// `#[serde(with = "crate::str::hex_array")]`. Lists of bytes are also accepted when deserializing, as written by older
+/// versions.
+pub(crate) mod hex_array {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use super::hex_string;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum HexArray {
+        Hex(String),
+        Bytes(Vec<u8>),
+    }
+
+    pub fn serialize<S: Serializer, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex_string(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error> {
+        let bytes = match HexArray::deserialize(deserializer)? {
+            HexArray::Hex(hex) => {
+                if hex.len() != N * 2 || !hex.is_ascii() {
+                    return Err(de::Error::custom(format!("expected {} hexadecimal digits but got '{hex}'", N * 2)));
+                }
+                (0..N)
+                    .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|error| de::Error::custom(format!("invalid hexadecimal string '{hex}': {error}")))?
+            }
+            HexArray::Bytes(bytes) => bytes,
+        };
+        let length = bytes.len();
+        bytes.try_into().map_err(|_| de::Error::invalid_length(length, &format!("{N} bytes").as_str()))
+    }
+}
+
 /// For debugging purposes.
 #[allow(unused)]
 pub(crate) fn write_hex(f: &mut std::fmt::Formatter<'_>, data: &[u8]) -> std::fmt::Result {
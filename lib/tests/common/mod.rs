@@ -0,0 +1,163 @@
+#![allow(dead_code)]
+
+use std::{fs, mem::offset_of, path::Path};
+
+use anyhow::Result;
+use ds_rom::{
+    crc::CRC_16_MODBUS,
+    rom::{
+        raw::{self, BannerBitmap, BannerPalette, Capacity, Delay, DsFlags, NITROCODE},
+        Arm9BuildConfig, Arm9FooterConfig, Arm9Offsets, BannerImages, BuildInfo, Header, HeaderOriginal, Logo,
+    },
+};
+
+pub const CONFIG: &str = "
+padding_value: 255
+header: header.yaml
+header_logo: header_logo.png
+arm9_bin: arm9/arm9.bin
+arm9_config: arm9/arm9.yaml
+arm7_bin: arm7/arm7.bin
+arm7_config: arm7/arm7.yaml
+itcm: { bin: arm9/itcm.bin, config: arm9/itcm.yaml }
+dtcm: { bin: arm9/dtcm.bin, config: arm9/dtcm.yaml }
+arm9_overlays: arm9_overlays/overlays.yaml
+arm7_overlays: null
+banner: banner/banner.yaml
+files_dir: files/
+path_order: path_order.txt
+";
+
+pub const OVERLAYS: &str = "
+- { id: 0, base_address: 0x2100000, code_size: 0x20, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: 0, compressed: false, file_name: ov000.bin }
+";
+
+pub const BANNER: &str = "
+version: Original
+title:
+  japanese: Test
+  english: Test
+  french: Test
+  german: Test
+  italian: Test
+  spanish: Test
+images:
+  bitmap_path: bitmap.png
+  palette_path: palette.png
+";
+
+/// Writes a small but complete project to `root`, with an ARM9 program, two TCM autoloads, one overlay, an ARM7 program,
+/// a banner and three files.
+pub fn generate_project(root: &Path) -> Result<()> {
+    let write = |path: &str, contents: &[u8]| -> Result<()> {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, contents)?;
+        Ok(())
+    };
+
+    write("config.yaml", CONFIG.as_bytes())?;
+
+    let header = Header {
+        original: HeaderOriginal {
+            title: "TEST".to_string(),
+            gamecode: "ATSE".parse()?,
+            makercode: "01".parse()?,
+            unitcode: 0,
+            seed_select: 0,
+            ds_flags: DsFlags::new(),
+            autostart: 0,
+            normal_cmd_setting: 0x416657,
+            key1_cmd_setting: 0x81808f8,
+            secure_area_delay: Delay(0xd7e),
+            rom_nand_end: 0,
+            rw_nand_end: 0,
+            capacity: None::<Capacity>,
+            preserve_capacity: false,
+        },
+        ds_post_dsi: None,
+        dsi: None,
+    };
+    write("header.yaml", serde_yml::to_string(&header)?.as_bytes())?;
+    Logo::default().save_png(root.join("header_logo.png"))?;
+
+    let mut arm9 = vec![0u8; 0x1000];
+    for (i, byte) in arm9.iter_mut().enumerate().skip(0x900) {
+        *byte = i as u8;
+    }
+    // Secure area ID of a decrypted ARM9 program
+    arm9[0..8].copy_from_slice(&[0xff, 0xde, 0xff, 0xe7, 0xff, 0xde, 0xff, 0xe7]);
+    arm9[0x81c..0x820].copy_from_slice(&NITROCODE.to_le_bytes());
+    arm9[0x820..0x824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    write("arm9/arm9.bin", &arm9)?;
+    let arm9_config = Arm9BuildConfig {
+        offsets: Arm9Offsets {
+            base_address: 0x2000000,
+            entry_function: 0x2000900,
+            build_info: 0x800,
+            autoload_callback: 0x2000a00,
+            overlay_signatures: None,
+        },
+        encrypted: false,
+        no_secure_area: false,
+        compressed: false,
+        // The code ends where the autoload blocks start
+        build_info: BuildInfo { bss_start: 0x2001000, bss_end: 0x2002000, sdk_version: 0x4000000 },
+        blowfish_key_sha1: None,
+        footer: Arm9FooterConfig::default(),
+        lenient_build_info: false,
+        compression_start: None,
+        extra: Default::default(),
+    };
+    write("arm9/arm9.yaml", serde_yml::to_string(&arm9_config)?.as_bytes())?;
+    write("arm9/itcm.bin", &[0x11; 0x20])?;
+    write("arm9/itcm.yaml", b"{ base_address: 0x1ff8000, code_size: 0x20, bss_size: 0 }")?;
+    write("arm9/dtcm.bin", &[0x22; 0x10])?;
+    write("arm9/dtcm.yaml", b"{ base_address: 0x27e0000, code_size: 0x10, bss_size: 0x40 }")?;
+
+    write("arm9_overlays/overlays.yaml", OVERLAYS.as_bytes())?;
+    write("arm9_overlays/ov000.bin", &[0x33; 0x20])?;
+
+    write("arm7/arm7.bin", &[0x44; 0x400])?;
+    write("arm7/arm7.yaml", b"{ base_address: 0x2380000, entry_function: 0x2380000, build_info: 0, autoload_callback: 0 }")?;
+
+    write("banner/banner.yaml", BANNER.as_bytes())?;
+    let mut palette = BannerPalette::default();
+    palette.0[1] = 0x7fff;
+    BannerImages::from_bitmap(BannerBitmap([0x10; 0x200]), palette).save_bitmap_file(&root.join("banner"))?;
+
+    write("files/data/a.bin", &[0xaa; 0x10])?;
+    write("files/data/b.bin", &[0xbb; 0x30])?;
+    write("files/c.bin", &[0xcc; 0x8])?;
+    write("path_order.txt", b"/data/a.bin\n/data/b.bin\n/c.bin\n")?;
+    Ok(())
+}
+
+pub fn assert_crcs(rom: &raw::Rom) -> Result<()> {
+    let header = rom.header()?;
+    let header_crc = CRC_16_MODBUS.checksum(&bytemuck::bytes_of(header)[0..offset_of!(raw::Header, header_crc)]);
+    assert_eq!(header.header_crc, header_crc);
+    assert_eq!(header.logo_crc, CRC_16_MODBUS.checksum(&header.logo));
+
+    let banner = rom.banner()?.unwrap();
+    let version = banner.version();
+    let banner_crc = CRC_16_MODBUS.checksum(&banner.full_data()[version.crc_range()]);
+    assert_eq!(banner.crc(version.crc_index()), banner_crc);
+    Ok(())
+}
+
+pub fn assert_fat_consistent(rom: &raw::Rom, files: &ds_rom::rom::FileSystem) -> Result<()> {
+    let fat = rom.fat()?;
+    let mut ranges = fat.iter().map(|alloc| alloc.range()).collect::<Vec<_>>();
+    assert!(ranges.iter().all(|range| range.start <= range.end && range.end <= rom.data().len()));
+    ranges.sort_by_key(|range| range.start);
+    assert!(ranges.windows(2).all(|pair| pair[0].end <= pair[1].start), "{ranges:x?}");
+
+    let mut num_files = 0;
+    files.traverse_files(["/"], |file, _| {
+        assert_eq!(file.contents(), fat[file.id() as usize].into_file(rom.data()));
+        num_files += 1;
+    });
+    assert_eq!(num_files + files.num_reserved(), fat.len());
+    Ok(())
+}
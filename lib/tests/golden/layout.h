@@ -0,0 +1,27 @@
+/* Generated by dsrom, do not edit. */
+
+#ifndef DSROM_LAYOUT_H
+#define DSROM_LAYOUT_H
+
+#define ARM9_BASE 0x02000000
+#define ARM9_ENTRY 0x02000900
+#define ARM9_AUTOLOAD_CALLBACK 0x02000a00
+#define ARM9_BSS_START 0x02001000
+#define ARM9_BSS_END 0x02002000
+#define ARM9_BSS_SIZE 0x00001000
+#define ITCM_BASE 0x01ff8000
+#define ITCM_SIZE 0x00000020
+#define ITCM_BSS_SIZE 0x00000000
+#define ITCM_END 0x01ff8020
+#define DTCM_BASE 0x027e0000
+#define DTCM_SIZE 0x00000010
+#define DTCM_BSS_SIZE 0x00000040
+#define DTCM_END 0x027e0050
+#define ARM7_BASE 0x02380000
+#define ARM7_ENTRY 0x02380000
+#define OVERLAY_0_BASE 0x02100000
+#define OVERLAY_0_SIZE 0x00000020
+#define OVERLAY_0_BSS_SIZE 0x00000000
+#define OVERLAY_0_END 0x02100020
+
+#endif
@@ -0,0 +1,22 @@
+/* Generated by dsrom, do not edit. */
+
+    .set ARM9_BASE, 0x02000000
+    .set ARM9_ENTRY, 0x02000900
+    .set ARM9_AUTOLOAD_CALLBACK, 0x02000a00
+    .set ARM9_BSS_START, 0x02001000
+    .set ARM9_BSS_END, 0x02002000
+    .set ARM9_BSS_SIZE, 0x00001000
+    .set ITCM_BASE, 0x01ff8000
+    .set ITCM_SIZE, 0x00000020
+    .set ITCM_BSS_SIZE, 0x00000000
+    .set ITCM_END, 0x01ff8020
+    .set DTCM_BASE, 0x027e0000
+    .set DTCM_SIZE, 0x00000010
+    .set DTCM_BSS_SIZE, 0x00000040
+    .set DTCM_END, 0x027e0050
+    .set ARM7_BASE, 0x02380000
+    .set ARM7_ENTRY, 0x02380000
+    .set OVERLAY_0_BASE, 0x02100000
+    .set OVERLAY_0_SIZE, 0x00000020
+    .set OVERLAY_0_BSS_SIZE, 0x00000000
+    .set OVERLAY_0_END, 0x02100020
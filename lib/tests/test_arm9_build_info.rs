@@ -0,0 +1,209 @@
+use anyhow::Result;
+use ds_rom::rom::{
+    raw::{AutoloadInfo, NITROCODE},
+    Arm9, Arm9BuildInfoError, Arm9CompressionProbe, Arm9Error, Arm9Offsets, Arm9WithTcmsOptions, Autoload,
+    DEFAULT_COMPRESSION_START,
+};
+
+fn arm9() -> Result<Arm9<'static>> {
+    let mut data = vec![0u8; 0x5000];
+    data[0x481c..0x4820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x4820..0x4824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x4800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+    let itcm = Autoload::new(vec![1; 0x20], AutoloadInfo { base_address: 0x1ff8000, code_size: 0x20, bss_size: 0 });
+    let dtcm = Autoload::new(vec![2; 0x10], AutoloadInfo { base_address: 0x27e0000, code_size: 0x10, bss_size: 0x40 });
+    let options = Arm9WithTcmsOptions::default();
+    let mut arm9 = Arm9::with_autoloads(data, &[itcm, dtcm], offsets, options)?;
+    arm9.set_bss(0x2005000..0x2006000)?;
+    Ok(arm9)
+}
+
+#[test]
+fn test_set_bss() -> Result<()> {
+    let mut arm9 = arm9()?;
+    assert_eq!(arm9.bss()?, 0x2005000..0x2006000);
+
+    let error = arm9.set_bss(0x2005000..0x2004000).unwrap_err();
+    assert!(matches!(error, Arm9BuildInfoError::BssEndBeforeStart { bss_start: 0x2005000, bss_end: 0x2004000, .. }));
+    let error = arm9.set_bss(0x2004800..0x2006000).unwrap_err();
+    assert!(matches!(error, Arm9BuildInfoError::BssStartBeforeBuildInfo { build_info_end: 0x2004824, .. }));
+    let error = arm9.set_bss(0x2005004..0x2006000).unwrap_err();
+    assert!(matches!(error, Arm9BuildInfoError::BssStartPastAutoloads { autoload_blocks: 0x2005000, .. }));
+    assert_eq!(arm9.bss()?, 0x2005000..0x2006000);
+
+    // The code may end before the autoload blocks
+    arm9.set_bss(0x2004900..0x2006000)?;
+    arm9.build_info_mut()?.compressed_code_end = 0x2004000;
+    let error = arm9.set_bss(0x2005000..0x2006000).unwrap_err();
+    assert!(matches!(error, Arm9BuildInfoError::BssCompressed { .. }));
+    Ok(())
+}
+
+#[test]
+fn test_set_sdk_version() -> Result<()> {
+    let mut arm9 = arm9()?;
+    arm9.set_sdk_version(0x4000000)?;
+    assert_eq!(arm9.build_info()?.sdk_version, 0x4000000);
+    Ok(())
+}
+
+#[test]
+fn test_set_autoload_callback() -> Result<()> {
+    let mut arm9 = arm9()?;
+    arm9.set_autoload_callback(0x2000901)?;
+    assert_eq!(arm9.autoload_callback(), 0x2000901);
+    assert_eq!(arm9.offsets().autoload_callback, 0x2000901);
+
+    let error = arm9.set_autoload_callback(0x2005000).unwrap_err();
+    assert!(matches!(error, Arm9BuildInfoError::AutoloadCallbackOutOfBounds { code_end: 0x2005000, .. }));
+    let error = arm9.set_autoload_callback(0x1fffffc).unwrap_err();
+    assert!(matches!(error, Arm9BuildInfoError::AutoloadCallbackOutOfBounds { code_start: 0x2000000, .. }));
+    assert_eq!(arm9.autoload_callback(), 0x2000901);
+    Ok(())
+}
+
+#[test]
+fn test_corrupted_nitrocode() -> Result<()> {
+    let mut data = vec![0u8; 0x6000];
+    for (i, byte) in data.iter_mut().enumerate().skip(0x4000) {
+        *byte = (i / 0x10) as u8;
+    }
+    // Build info with .bss after the code
+    data[0x80c..0x810].copy_from_slice(&0x2006000u32.to_le_bytes());
+    data[0x810..0x814].copy_from_slice(&0x2007000u32.to_le_bytes());
+    data[0x81c..0x820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x820..0x824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+
+    let mut arm9 = Arm9::new(data.clone(), offsets)?;
+    assert!(!arm9.lenient_build_info());
+    assert_eq!(arm9.probe_compression(), Arm9CompressionProbe::BuildInfo(false));
+    arm9.compress()?;
+    assert_eq!(arm9.probe_compression(), Arm9CompressionProbe::BuildInfo(true));
+    let compressed_code_end = arm9.build_info()?.compressed_code_end;
+
+    // Corrupt the nitrocode of the compressed program
+    let mut corrupted = arm9.full_data().to_vec();
+    corrupted[0x81c..0x820].fill(0);
+    let mut arm9 = Arm9::new(corrupted.clone(), offsets)?;
+    assert!(arm9.lenient_build_info());
+    assert!(arm9.originally_compressed());
+    assert_eq!(arm9.probe_compression(), Arm9CompressionProbe::Lz77Footer(true));
+    assert_eq!(arm9.bss()?, 0x2006000..0x2007000);
+
+    // The build info is left as-is, so the corrupted bytes survive a round trip
+    arm9.decompress()?;
+    assert_eq!(arm9.probe_compression(), Arm9CompressionProbe::Lz77Footer(false));
+    assert!(!arm9.is_compressed()?);
+    assert_eq!(arm9.full_data().len(), data.len());
+    assert_eq!(arm9.full_data()[0x4000..], data[0x4000..]);
+    assert_eq!(arm9.build_info()?.compressed_code_end, compressed_code_end);
+    assert_eq!(arm9.full_data()[0x81c..0x820], [0; 4]);
+    arm9.compress()?;
+    assert!(arm9.full_data() == corrupted);
+
+    // Without the flag, the corrupted build info is rejected
+    arm9.set_lenient_build_info(false);
+    assert!(arm9.is_compressed().is_err());
+    assert_eq!(arm9.probe_compression(), Arm9CompressionProbe::Lz77Footer(true));
+    Ok(())
+}
+
+fn compressible_arm9(size: usize) -> Result<Arm9<'static>> {
+    let mut data = vec![0u8; size];
+    for (i, byte) in data.iter_mut().enumerate().skip(0x4000) {
+        *byte = (i / 0x10) as u8;
+    }
+    data[0x81c..0x820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x820..0x824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+    Ok(Arm9::new(data, offsets)?)
+}
+
+#[test]
+fn test_compression_start() -> Result<()> {
+    for compression_start in [DEFAULT_COMPRESSION_START, 0x8000] {
+        let mut arm9 = compressible_arm9(0xa000)?;
+        assert_eq!(arm9.compression_start(), DEFAULT_COMPRESSION_START);
+        let data = arm9.full_data().to_vec();
+        arm9.set_compression_start(compression_start);
+        arm9.compress()?;
+        let compressed = arm9.full_data().to_vec();
+        assert_eq!(compressed[0x4000..compression_start], data[0x4000..compression_start]);
+
+        let mut arm9 = Arm9::new(compressed.clone(), *arm9.offsets())?;
+        assert_eq!(arm9.detect_compression_start(), Some(compression_start));
+        assert_eq!(arm9.compression_start(), compression_start);
+        arm9.decompress()?;
+        assert_eq!(arm9.full_data()[0x4000..], data[0x4000..]);
+        arm9.compress()?;
+        assert!(arm9.full_data() == compressed, "{compression_start:#x}");
+    }
+
+    let arm9 = compressible_arm9(0xa000)?;
+    assert_eq!(arm9.detect_compression_start(), None);
+    Ok(())
+}
+
+#[test]
+fn test_compression_start_out_of_bounds() -> Result<()> {
+    let mut arm9 = compressible_arm9(0x5000)?;
+    let data = arm9.full_data().to_vec();
+    arm9.set_compression_start(0x5001);
+    let result = arm9.compress();
+    assert!(matches!(result, Err(Arm9Error::CompressionStartOutOfBounds { start: 0x5001, length: 0x5000, .. })), "{result:?}");
+    assert!(arm9.full_data() == data);
+    Ok(())
+}
+
+#[test]
+fn test_detect_compression_start_incompressible() -> Result<()> {
+    // Incompressible bytes right after the compression start are stored as-is, like the bytes before it
+    let mut arm9 = compressible_arm9(0xe000)?;
+    let mut data = arm9.full_data().to_vec();
+    let mut state = 0x12345678u32;
+    for byte in &mut data[0x4000..0x8800] {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        *byte = state as u8;
+    }
+    arm9 = Arm9::new(data.clone(), *arm9.offsets())?;
+    arm9.compress()?;
+    let compressed = arm9.full_data().to_vec();
+
+    let mut arm9 = Arm9::new(compressed.clone(), *arm9.offsets())?;
+    assert_eq!(arm9.detect_compression_start(), Some(0x8000));
+    arm9.decompress()?;
+    assert!(arm9.full_data() == data);
+
+    // Compressing from the detected start round-trips the program, but only the real start reproduces the original
+    let offsets = *arm9.offsets();
+    arm9.compress()?;
+    let mut recompressed = Arm9::new(arm9.full_data().to_vec(), offsets)?;
+    recompressed.decompress()?;
+    assert!(recompressed.full_data() == data);
+    arm9.decompress()?;
+    arm9.set_compression_start(DEFAULT_COMPRESSION_START);
+    arm9.compress()?;
+    assert!(arm9.full_data() == compressed);
+    Ok(())
+}
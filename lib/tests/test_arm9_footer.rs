@@ -0,0 +1,48 @@
+use ds_rom::rom::{
+    raw::{Arm9Footer, NITROCODE},
+    Arm9FooterConfig,
+};
+
+fn footer_bytes(build_info_offset: u32, reserved: u32) -> Vec<u8> {
+    [NITROCODE, build_info_offset, reserved].iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+#[test]
+fn test_arm9_footer_nonzero_reserved() {
+    let data: Vec<u32> = vec![NITROCODE, 0x1234, 0xdeadbeef];
+    let bytes: &[u8] = bytemuck::cast_slice(&data);
+    let footer = Arm9Footer::borrow_from_slice(bytes).unwrap();
+
+    let config = Arm9FooterConfig::from_raw(footer, 0x1234);
+    assert_eq!(config.build_info_offset, None);
+    assert_eq!(config.reserved, 0xdeadbeef);
+    assert!(!config.is_default());
+
+    let yaml = serde_yml::to_string(&config).unwrap();
+    let config: Arm9FooterConfig = serde_yml::from_str(&yaml).unwrap();
+    let built = config.build(0x1234);
+    assert_eq!(bytemuck::bytes_of(&built), footer_bytes(0x1234, 0xdeadbeef));
+
+    // The build info offset follows the ARM9 program unless it originally differed
+    let built = config.build(0x5678);
+    assert_eq!(bytemuck::bytes_of(&built), footer_bytes(0x5678, 0xdeadbeef));
+}
+
+#[test]
+fn test_arm9_footer_differing_build_info_offset() {
+    let data: Vec<u32> = vec![NITROCODE, 0x4321, 0];
+    let footer = Arm9Footer::borrow_from_slice(bytemuck::cast_slice(&data)).unwrap();
+
+    let config = Arm9FooterConfig::from_raw(footer, 0x1234);
+    assert_eq!(config.build_info_offset, Some(0x4321));
+    let built = config.build(0x1234);
+    assert_eq!(bytemuck::bytes_of(&built), footer_bytes(0x4321, 0));
+}
+
+#[test]
+fn test_arm9_footer_default() {
+    let config = Arm9FooterConfig::default();
+    assert!(config.is_default());
+    let built = config.build(0x1234);
+    assert_eq!(bytemuck::bytes_of(&built), bytemuck::bytes_of(&Arm9Footer::new(0x1234)));
+}
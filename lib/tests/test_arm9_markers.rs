@@ -0,0 +1,112 @@
+use anyhow::Result;
+use ds_rom::rom::{
+    raw::{AutoloadInfo, AutoloadKind, NITROCODE},
+    Arm9, Arm9FooterValue, Arm9Location, Arm9Offsets, Arm9OverlaySignatures, Arm9WithTcmsOptions, Autoload,
+};
+
+#[test]
+fn test_arm9_markers() -> Result<()> {
+    let mut data = vec![0u8; 0x5000];
+    data[0x481c..0x4820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x4820..0x4824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x4800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+    let itcm = Autoload::new(vec![1; 0x20], AutoloadInfo { base_address: 0x1ff8000, code_size: 0x20, bss_size: 0 });
+    let dtcm = Autoload::new(vec![2; 0x10], AutoloadInfo { base_address: 0x27e0000, code_size: 0x10, bss_size: 0x40 });
+    let mut arm9 = Arm9::with_autoloads(data, &[itcm, dtcm], offsets, Arm9WithTcmsOptions::default())?;
+    arm9.build_info_mut()?.bss_start = 0x2005048;
+
+    let markers = arm9.markers()?.into_iter().map(|m| (m.address, m.label)).collect::<Vec<_>>();
+    let expected = [
+        (0x2004800, "Build info start"),
+        (0x2004824, "Build info end"),
+        (0x2005000, "Autoload block ITCM start (loads to 0x1ff8000)"),
+        (0x2005020, "Autoload block ITCM end"),
+        (0x2005020, "Autoload block DTCM start (loads to 0x27e0000)"),
+        (0x2005030, "Autoload block DTCM end"),
+        (0x2005030, "Autoload info table start"),
+        (0x2005048, "Autoload info table end"),
+        (0x2005048, "Code end (.bss start)"),
+    ]
+    .map(|(address, label)| (address, label.to_string()));
+    assert_eq!(markers, expected);
+    Ok(())
+}
+
+#[test]
+fn test_arm9_locate() -> Result<()> {
+    let mut data = vec![0u8; 0x5000];
+    data[0x481c..0x4820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x4820..0x4824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x4800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+    let itcm = Autoload::new(vec![1; 0x20], AutoloadInfo { base_address: 0x1ff8000, code_size: 0x20, bss_size: 0 });
+    let dtcm = Autoload::new(vec![2; 0x10], AutoloadInfo { base_address: 0x27e0000, code_size: 0x10, bss_size: 0x40 });
+    let arm9 = Arm9::with_autoloads(data, &[dtcm, itcm], offsets, Arm9WithTcmsOptions::default())?;
+
+    assert_eq!(arm9.locate(0x2000010)?, Some(Arm9Location::Main { offset: 0x10 }));
+    assert_eq!(arm9.locate(0x1ff8004)?, Some(Arm9Location::Autoload { index: 1, kind: AutoloadKind::Itcm, offset: 4 }));
+    assert_eq!(arm9.locate(0x27e000c)?, Some(Arm9Location::Autoload { index: 0, kind: AutoloadKind::Dtcm, offset: 0xc }));
+    // Raw offsets of the autoload blocks and .bss of autoloads are not code
+    assert_eq!(arm9.locate(0x2005000)?, None);
+    assert_eq!(arm9.locate(0x27e0010)?, None);
+    Ok(())
+}
+
+#[test]
+fn test_arm9_overlay_signatures_in_autoload() -> Result<()> {
+    let mut data = vec![0u8; 0x5000];
+    data[0x481c..0x4820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x4820..0x4824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x4800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+    let mut itcm_code = vec![1; 0x40];
+    itcm_code[0x10..0x38].copy_from_slice(&[0xaa; 0x28]);
+    let itcm = || Autoload::new(itcm_code.clone(), AutoloadInfo { base_address: 0x1ff8000, code_size: 0x40, bss_size: 0 });
+    let dtcm = || Autoload::new(vec![2; 0x10], AutoloadInfo { base_address: 0x27e0000, code_size: 0x10, bss_size: 0x40 });
+
+    // The DTCM block comes first, so the raw offset of the table is 0x5000 + 0x10 + 0x10
+    let arm9 = Arm9::with_autoloads(data.clone(), &[dtcm(), itcm()], offsets, Arm9WithTcmsOptions::default())?;
+    let expected = Arm9OverlaySignatures { footer_value: Arm9FooterValue::RawOffset, autoload: Some(0x1ff8000), offset: 0x10 };
+    assert_eq!(arm9.detect_overlay_signatures(0x5020)?, Some(expected));
+    let address = Arm9OverlaySignatures { footer_value: Arm9FooterValue::Address, ..expected };
+    assert_eq!(arm9.detect_overlay_signatures(0x1ff8010)?, Some(address));
+    let main = Arm9OverlaySignatures { footer_value: Arm9FooterValue::RawOffset, autoload: None, offset: 0x100 };
+    assert_eq!(arm9.detect_overlay_signatures(0x100)?, Some(main));
+    assert_eq!(arm9.detect_overlay_signatures(0)?, None);
+    assert_eq!(arm9.detect_overlay_signatures(0x100000)?, None);
+
+    // Rebuilding with the ITCM block first moves the table, and the footer value follows it
+    let offsets = Arm9Offsets { overlay_signatures: Some(expected), ..offsets };
+    let arm9 = Arm9::with_autoloads(data.clone(), &[itcm(), dtcm()], offsets, Arm9WithTcmsOptions::default())?;
+    assert_eq!(arm9.overlay_signatures_offset()?, Some(0x5010));
+    assert_eq!(arm9.overlay_signatures_footer_value()?, Some(0x5010));
+    let range = arm9.overlay_signatures_range(2)?.unwrap();
+    assert_eq!(range, 0x5010..0x5038);
+    assert!(arm9.full_data()[range].iter().all(|&b| b == 0xaa));
+    assert!(arm9.overlay_signatures_range(0x100).is_err());
+
+    let offsets = Arm9Offsets { overlay_signatures: Some(address), ..offsets };
+    let arm9 = Arm9::with_autoloads(data.clone(), &[itcm(), dtcm()], offsets, Arm9WithTcmsOptions::default())?;
+    assert_eq!(arm9.overlay_signatures_footer_value()?, Some(0x1ff8010));
+
+    // The table can't be found without its autoload block
+    let arm9 = Arm9::with_autoloads(data, &[dtcm()], offsets, Arm9WithTcmsOptions::default())?;
+    assert!(arm9.overlay_signatures_offset().is_err());
+    Ok(())
+}
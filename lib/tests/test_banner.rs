@@ -0,0 +1,281 @@
+use ds_rom::{
+    crc::CRC_16_MODBUS,
+    rom::{
+        self,
+        raw::{Banner, BannerVersion, Language},
+        BannerCrcMismatch, BannerCrcs,
+    },
+    str::Unicode16Array,
+};
+
+fn assert_same_accessors(a: &Banner, b: &Banner) {
+    assert_eq!(a.version(), b.version());
+    for index in 0..4 {
+        assert_eq!(a.crc(index), b.crc(index));
+    }
+    assert_eq!(a.bitmap().0, b.bitmap().0);
+    assert_eq!(a.palette().0, b.palette().0);
+    for language in [Language::Japanese, Language::English, Language::Chinese, Language::Korean] {
+        assert_eq!(a.title(language).map(|t| t.0), b.title(language).map(|t| t.0));
+    }
+    assert_eq!(a.full_data(), b.full_data());
+}
+
+fn make_banner() -> Banner<'static> {
+    let mut banner = Banner::new(BannerVersion::China);
+    *banner.crc_mut(0) = 0x1234;
+    banner.bitmap_mut().set_pixel(3, 5, 7);
+    banner.palette_mut().set_color(7, 0xf8, 0x80, 0x08);
+    let Ok(title) = "Title\nSubtitle".parse::<Unicode16Array<0x80>>() else { panic!("title should fit") };
+    *banner.title_mut(Language::English).unwrap() = title;
+    banner
+}
+
+#[test]
+fn test_banner_borrowed_and_owned() {
+    let banner = make_banner();
+    let mut data = banner.full_data().to_vec();
+    data.extend([0xff; 0x20]);
+
+    let borrowed = Banner::borrow_from_slice(&data).unwrap();
+    let owned = Banner::from_owned(data.clone()).unwrap();
+    assert_same_accessors(&banner, &borrowed);
+    assert_same_accessors(&borrowed, &owned);
+    assert_eq!(owned.full_data().len(), BannerVersion::China.banner_size());
+
+    let copy = borrowed.to_owned();
+    drop(data);
+    assert_same_accessors(&banner, &copy);
+}
+
+#[test]
+fn test_banner_too_small() {
+    assert!(Banner::from_owned(vec![]).is_err());
+    assert!(Banner::from_owned(vec![1, 0, 0, 0]).is_err());
+}
+
+#[test]
+fn test_banner_invalid_utf16_title() {
+    let mut banner = make_banner();
+    // Unpaired high surrogate followed by a regular character
+    let units = [0x41, 0xd800, 0x42];
+    let Ok(title) = Unicode16Array::from_units(&units) else { panic!("title should fit") };
+    assert!(!title.is_valid_utf16());
+    *banner.title_mut(Language::Japanese).unwrap() = title;
+
+    let display = banner.display(0).to_string();
+    assert!(display.contains("contains invalid UTF-16"), "{display}");
+
+    let loaded = rom::Banner::load_raw(&banner);
+    assert_eq!(loaded.title.japanese, "A\u{fffd}B");
+    assert_eq!(loaded.title.japanese_raw_utf16.as_deref(), Some(units.as_slice()));
+    assert_eq!(loaded.title.english, "Title\nSubtitle");
+    assert!(loaded.title.english_raw_utf16.is_none());
+
+    let yaml = serde_yml::to_string(&loaded.title).unwrap();
+    assert!(yaml.contains("japanese_raw_utf16"), "{yaml}");
+    assert!(!yaml.contains("english_raw_utf16"), "{yaml}");
+
+    let rebuilt = loaded.build().unwrap();
+    assert_eq!(rebuilt.title(Language::Japanese).unwrap().0, banner.title(Language::Japanese).unwrap().0);
+    assert_eq!(rebuilt.title(Language::English).unwrap().0, banner.title(Language::English).unwrap().0);
+}
+
+/// Writes a 32x32 bitmap with 18 colors, and a palette with the first 16 of them. The two extra colors are close to palette
+/// colors 3 and 9.
+fn write_18_color_images(path: &std::path::Path) {
+    let mut colors = (0..16u8).map(|i| [i * 16, 255 - i * 16, (i % 4) * 64]).collect::<Vec<_>>();
+    colors.push([colors[3][0] + 8, colors[3][1], colors[3][2]]);
+    colors.push([colors[9][0], colors[9][1] - 8, colors[9][2]]);
+
+    let bitmap = image::RgbImage::from_fn(32, 32, |x, y| image::Rgb(colors[((y * 32 + x) / 57) as usize]));
+    let palette = image::RgbImage::from_fn(16, 1, |x, _| image::Rgb(colors[x as usize]));
+    std::fs::create_dir_all(path).unwrap();
+    bitmap.save(path.join("bitmap.png")).unwrap();
+    palette.save(path.join("palette.png")).unwrap();
+}
+
+#[test]
+fn test_banner_images_remap_policy() {
+    let path = std::env::temp_dir().join("ds-rom-test-banner-remap");
+    write_18_color_images(&path);
+    let mut images = rom::BannerImages::from_bitmap(rom::raw::BannerBitmap([0; 0x200]), rom::raw::BannerPalette::default());
+
+    let error = images.load(&path).unwrap_err();
+    assert!(matches!(error, rom::BannerImageError::InvalidPixel { x: 16, y: 28, .. }), "{error}");
+
+    images.remap_policy = rom::BannerRemapPolicy::NearestColor;
+    images.load(&path).unwrap();
+    let index_of = |i: usize| ((i * 57) % 32, (i * 57) / 32);
+    for i in 0..16 {
+        let (x, y) = index_of(i);
+        assert_eq!(images.bitmap.get_pixel(x, y), i);
+    }
+    assert_eq!(images.bitmap.get_pixel(index_of(16).0, index_of(16).1), 3);
+    assert_eq!(images.bitmap.get_pixel(31, 31), 9);
+
+    images.remap_policy = rom::BannerRemapPolicy::Quantize;
+    images.load(&path).unwrap();
+    let palette = image::open(path.join("palette.png")).unwrap().to_rgb8();
+    assert_eq!(palette.dimensions(), (16, 1));
+    // The top-left color stays at index 0, and every pixel maps to the palette color nearest to it
+    assert_eq!(images.bitmap.get_pixel(0, 0), 0);
+    let bitmap = image::open(path.join("bitmap.png")).unwrap().to_rgb8();
+    for (x, y, color) in bitmap.enumerate_pixels() {
+        let (r, g, b) = images.palette.get_color(images.bitmap.get_pixel(x as usize, y as usize));
+        let distance = (0..3).map(|i| (color.0[i] as i32 - [r, g, b][i] as i32).abs()).max().unwrap();
+        assert!(distance <= 16, "pixel {x},{y} {color:?} mapped to {:?}", (r, g, b));
+    }
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_banner_images_raw_fallback() {
+    let path = std::env::temp_dir().join("ds-rom-test-banner-raw-fallback");
+    std::fs::create_dir_all(&path).unwrap();
+    let banner = make_banner();
+    let images = rom::BannerImages::from_bitmap(*banner.bitmap(), *banner.palette());
+    images.save_bitmap_file(&path).unwrap();
+    assert_eq!(std::fs::metadata(path.join("icon.bin")).unwrap().len(), 0x220);
+
+    // The PNGs take priority over the raw file
+    let mut loaded = images.clone();
+    loaded.bitmap = rom::raw::BannerBitmap([0; 0x200]);
+    loaded.load(&path).unwrap();
+    assert_eq!(loaded.source, rom::BannerImageSource::Png);
+    assert_eq!(loaded.bitmap.0, banner.bitmap().0);
+
+    std::fs::remove_file(path.join("palette.png")).unwrap();
+    let mut loaded = images.clone();
+    loaded.bitmap = rom::raw::BannerBitmap([0; 0x200]);
+    loaded.load(&path).unwrap();
+    assert_eq!(loaded.source, rom::BannerImageSource::Raw);
+    assert_eq!(loaded.bitmap.0, banner.bitmap().0);
+    assert_eq!(loaded.palette.0, banner.palette().0);
+
+    std::fs::write(path.join("icon.bin"), [0; 0x200]).unwrap();
+    let error = loaded.load(&path).unwrap_err();
+    assert!(matches!(error, rom::BannerImageError::WrongRawSize { expected: 0x220, actual: 0x200, .. }), "{error}");
+
+    // Without a raw file, the missing PNG is an error
+    std::fs::remove_file(path.join("icon.bin")).unwrap();
+    assert!(loaded.load(&path).is_err());
+    loaded.raw_path = None;
+    assert!(loaded.load(&path).is_err());
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn test_banner_crcs() {
+    let mut banner = make_banner();
+    let china_crc = CRC_16_MODBUS.checksum(&banner.full_data()[BannerVersion::China.crc_range()]);
+    *banner.crc_mut(BannerVersion::China.crc_index()) = china_crc;
+
+    let mut loaded = rom::Banner::load_raw(&banner);
+    let crcs = loaded.crcs.unwrap();
+    assert_eq!(crcs, BannerCrcs { original: 0x1234, china: Some(china_crc), korea: None, animated: None });
+    let yaml = serde_yml::to_string(&crcs).unwrap();
+    assert!(yaml.contains("china") && !yaml.contains("korea"), "{yaml}");
+    assert_eq!(serde_yml::from_str::<BannerCrcs>(&yaml).unwrap(), crcs);
+
+    // The original CRC in the raw banner was made up, so only it differs when recomputed
+    let rebuilt = loaded.build().unwrap();
+    let original_crc = rebuilt.crc(BannerVersion::Original.crc_index());
+    assert_eq!(loaded.crc_mismatches().unwrap(), [BannerCrcMismatch {
+        version: BannerVersion::Original,
+        recorded: 0x1234,
+        computed: original_crc
+    }]);
+
+    // Changing a title changes both CRCs
+    loaded.crcs = Some(BannerCrcs::from_raw(&rebuilt));
+    assert!(loaded.crc_mismatches().unwrap().is_empty());
+    loaded.title.english = "Changed".to_string();
+    let mismatches = loaded.crc_mismatches().unwrap();
+    assert_eq!(mismatches.iter().map(|mismatch| mismatch.version).collect::<Vec<_>>(), [
+        BannerVersion::Original,
+        BannerVersion::China
+    ]);
+    assert!(mismatches[0].to_string().contains(&format!("{original_crc:#06x}")), "{}", mismatches[0]);
+
+    loaded.crcs = None;
+    assert!(loaded.crc_mismatches().unwrap().is_empty());
+}
+
+fn make_animated_banner() -> Banner<'static> {
+    let mut banner = Banner::new(BannerVersion::Animated);
+    banner.bitmap_mut().set_pixel(1, 2, 3);
+    banner.palette_mut().set_color(3, 0xf8, 0x00, 0x00);
+    let animation = banner.animation_mut().unwrap();
+    for (i, bitmap) in animation.bitmaps.iter_mut().enumerate() {
+        bitmap.set_pixel(i, i, 1);
+        bitmap.set_pixel(31 - i, i, 2);
+    }
+    for (i, palette) in animation.palettes.iter_mut().enumerate() {
+        palette.set_color(1, 0x08 * i as u8, 0xf8, 0x00);
+        // Same color as index 1, which a PNG can't tell apart
+        palette.set_color(2, 0x08 * i as u8, 0xf8, 0x00);
+        palette.0[3] = 0x8000 | i as u16;
+    }
+    for (i, keyframe) in animation.keyframes.iter_mut().take(5).enumerate() {
+        *keyframe = rom::raw::BannerKeyframe::new()
+            .with_frame_duration(6)
+            .with_bitmap_index(i as u8 % 4)
+            .with_palette_index(7 - i as u8)
+            .with_flip_horizontally(i == 2);
+    }
+    for version in [BannerVersion::Original, BannerVersion::China, BannerVersion::Korea, BannerVersion::Animated] {
+        *banner.crc_mut(version.crc_index()) = CRC_16_MODBUS.checksum(&banner.full_data()[version.crc_range()]);
+    }
+    banner
+}
+
+#[test]
+fn test_banner_animation_round_trip() {
+    let path = std::env::temp_dir().join("ds-rom-test-banner-animation");
+    if path.exists() {
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+    std::fs::create_dir_all(&path).unwrap();
+    let banner = make_animated_banner();
+
+    let loaded = rom::Banner::load_raw(&banner);
+    let keyframes = loaded.keyframes.as_ref().unwrap();
+    assert_eq!(keyframes.len(), 5);
+    assert!(keyframes[2].flip_horizontally);
+    let files = &loaded.images.animation_bitmap_files;
+    assert_eq!(files.iter().map(|file| file.palette).collect::<Vec<_>>(), [7, 6, 5, 4, 0, 0, 0, 0]);
+    assert_eq!(files[0].path, std::path::Path::new("anim_bitmap_0.png"));
+    assert_eq!(loaded.build().unwrap().full_data(), banner.full_data());
+
+    loaded.images.save_bitmap_file(&path).unwrap();
+    assert_eq!(std::fs::metadata(path.join("anim_icon.bin")).unwrap().len(), 0x1100);
+    let yaml = serde_yml::to_string(&loaded).unwrap();
+    let load = || {
+        let mut banner: rom::Banner = serde_yml::from_str(&yaml).unwrap();
+        banner.images.load(&path).map(|()| banner)
+    };
+    let reloaded = load().unwrap();
+    assert_eq!(reloaded.keyframes.as_ref().unwrap().len(), 5);
+    assert_eq!(reloaded.build().unwrap().full_data(), banner.full_data());
+
+    // Edited pixels are mapped to the first index with their color
+    let mut image = image::open(path.join("anim_bitmap_1.png")).unwrap().to_rgb8();
+    *image.get_pixel_mut(31, 1) = *image.get_pixel(1, 1);
+    *image.get_pixel_mut(5, 5) = *image.get_pixel(1, 1);
+    image.save(path.join("anim_bitmap_1.png")).unwrap();
+    let edited = load().unwrap().build().unwrap();
+    let bitmap = &edited.animation().unwrap().bitmaps[1];
+    assert_eq!([bitmap.get_pixel(1, 1), bitmap.get_pixel(30, 1), bitmap.get_pixel(5, 5)], [1, 2, 1]);
+    assert_ne!(edited.crc(BannerVersion::Animated.crc_index()), banner.crc(BannerVersion::Animated.crc_index()));
+
+    // The raw copy is used if the PNGs are missing
+    std::fs::remove_file(path.join("anim_palette_3.png")).unwrap();
+    assert_eq!(load().unwrap().build().unwrap().full_data(), banner.full_data());
+    std::fs::remove_file(path.join("anim_icon.bin")).unwrap();
+    assert!(load().is_err());
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
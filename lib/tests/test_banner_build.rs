@@ -0,0 +1,78 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::rom::{raw::Language, Rom, RomSaveOptions};
+
+use crate::common::{generate_project, CONFIG};
+
+#[test]
+fn test_banner_title_files() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-banner-title-files");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let extract_path = root.join("extract");
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let rom = Rom::extract(&build)?;
+    rom.save_with_options(&extract_path, RomSaveOptions { split_banner_titles: true, ..Default::default() })?;
+
+    let title_path = extract_path.join("banner/title_en.txt");
+    assert_eq!(fs::read_to_string(&title_path)?, "Test\n");
+    assert!(!extract_path.join("banner/title_zh.txt").exists());
+    let banner_yaml = fs::read_to_string(extract_path.join("banner/banner.yaml"))?;
+    assert!(banner_yaml.contains("title_files:") && banner_yaml.contains("english: title_en.txt"), "{banner_yaml}");
+
+    // The file overrides the inline title
+    fs::write(&title_path, "Translated\r\ntitle\r\n")?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    let banner = rebuilt.banner()?.unwrap();
+    assert_eq!(banner.title(Language::English).unwrap().to_string(), "Translated\ntitle");
+    assert_eq!(banner.title(Language::French).unwrap().to_string(), "Test");
+
+    fs::write(&title_path, "One\nTwo\nThree\nFour\n")?;
+    let Err(error) = Rom::load(extract_path.join("config.yaml"), Default::default()) else {
+        panic!("expected too many lines")
+    };
+    assert!(error.to_string().starts_with("English title in '"), "{error}");
+    assert!(error.to_string().contains("title_en.txt' has 4 lines but the maximum is 3"), "{error}");
+
+    fs::write(&title_path, "x".repeat(0x81))?;
+    let Err(error) = Rom::load(extract_path.join("config.yaml"), Default::default()) else { panic!("expected a long title") };
+    assert!(error.to_string().starts_with("invalid English title in '"), "{error}");
+    assert!(error.to_string().contains("title_en.txt': the provided string"), "{error}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_missing_banner() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-missing-banner");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let config = CONFIG.lines().filter(|line| !line.starts_with("banner:")).collect::<Vec<_>>().join("\n");
+    fs::write(root.join("config.yaml"), config)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(original.header()?.banner_offset, 0);
+    assert!(original.banner()?.is_none());
+    assert_eq!(original.padding_value()?, 0xff);
+
+    let rom = Rom::extract(&original)?;
+    assert!(rom.banner().is_none());
+    assert!(rom.config().banner.is_none());
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+    assert!(!extract_path.join("banner").exists());
+    assert!(!fs::read_to_string(extract_path.join("config.yaml"))?.contains("banner:"));
+
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.data(), original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::{
+    crypto::blowfish::BlowfishKey,
+    rom::{raw::NITROCODE, Arm9, Arm9Error, Arm9Offsets, SecureAreaState},
+};
+
+const SECURE_AREA_ID: [u8; 8] = [0xff, 0xde, 0xff, 0xe7, 0xff, 0xde, 0xff, 0xe7];
+
+fn make_key(name: &str, seed: u8) -> Result<BlowfishKey> {
+    let path = std::env::temp_dir().join(format!("ds-rom-test-{name}.bin"));
+    let bios = (0..0x30 + 0x1048).map(|i| (i as u8).wrapping_mul(seed).wrapping_add(seed)).collect::<Vec<_>>();
+    fs::write(&path, bios)?;
+    let key = BlowfishKey::from_arm7_bios_path(&path)?;
+    fs::remove_file(&path)?;
+    Ok(key)
+}
+
+fn make_arm9() -> Arm9<'static> {
+    let mut data = vec![0u8; 0x5000];
+    data[0..8].copy_from_slice(&SECURE_AREA_ID);
+    data[8..0x800].iter_mut().enumerate().for_each(|(i, byte)| *byte = i as u8);
+    data[0x481c..0x4820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x4820..0x4824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets {
+        base_address: 0x2000000,
+        entry_function: 0x2000800,
+        build_info: 0x4800,
+        autoload_callback: 0,
+        overlay_signatures: None,
+    };
+    Arm9::new(data, offsets).unwrap()
+}
+
+#[test]
+fn test_decrypt_with_any_key() -> Result<()> {
+    let retail_key = make_key("retail-key", 3)?;
+    let ique_key = make_key("ique-key", 7)?;
+    let gamecode = u32::from_le_bytes(*b"ABCC");
+
+    let plain = make_arm9();
+    let mut arm9 = plain.clone();
+    arm9.encrypt(&ique_key, gamecode)?;
+    assert!(arm9.is_encrypted());
+
+    let mut wrong = arm9.clone();
+    assert!(matches!(wrong.decrypt_with_any_key(&[&retail_key], gamecode), Err(Arm9Error::NotEncryObj { .. })));
+
+    let keys = [&retail_key, &ique_key];
+    assert_eq!(arm9.decrypt_with_any_key(&keys, gamecode)?, Some(1));
+    assert_eq!(arm9.full_data(), plain.full_data());
+    assert_eq!(arm9.decrypt_with_any_key(&keys, gamecode)?, None);
+
+    let sha1 = ique_key.sha1();
+    assert_ne!(sha1, retail_key.sha1());
+    assert!(BlowfishKey::select(&keys, Some(&sha1)).is_some_and(|key| key.sha1() == sha1));
+    assert!(BlowfishKey::select(&keys, None).is_some_and(|key| key.sha1() == retail_key.sha1()));
+    assert!(BlowfishKey::select(&keys, Some(&[0; 0x14])).is_none());
+    Ok(())
+}
+
+#[test]
+fn test_secure_area_state() -> Result<()> {
+    let key = make_key("secure-area-state-key", 5)?;
+    let gamecode = u32::from_le_bytes(*b"ABCC");
+
+    let mut arm9 = make_arm9();
+    assert_eq!(arm9.secure_area_state(), SecureAreaState::Decrypted);
+    arm9.encrypt(&key, gamecode)?;
+    assert_eq!(arm9.secure_area_state(), SecureAreaState::Encrypted);
+    assert!(arm9.is_encrypted());
+
+    // Zeroed secure area
+    let mut data = make_arm9().full_data().to_vec();
+    data[0..0x4000].fill(0);
+    let offsets = *make_arm9().offsets();
+    let mut zeroed = Arm9::new(data.clone(), offsets)?;
+    assert_eq!(zeroed.secure_area_state(), SecureAreaState::Absent);
+    assert!(!zeroed.is_encrypted());
+    assert_eq!(zeroed.secure_area_crc(&key, gamecode), 0);
+    zeroed.encrypt(&key, gamecode)?;
+    zeroed.decrypt(&key, gamecode)?;
+    assert_eq!(zeroed.full_data(), data);
+
+    // Too small to contain a secure area, even if it starts with arbitrary bytes
+    let mut data = vec![0x5a; 0x1000];
+    data[0x81c..0x820].copy_from_slice(&NITROCODE.to_le_bytes());
+    data[0x820..0x824].copy_from_slice(&NITROCODE.swap_bytes().to_le_bytes());
+    let offsets = Arm9Offsets { build_info: 0x800, ..offsets };
+    let mut small = Arm9::new(data.clone(), offsets)?;
+    assert_eq!(small.secure_area_state(), SecureAreaState::Absent);
+    assert_eq!(small.decrypt_with_any_key(&[&key], gamecode)?, None);
+    small.encrypt(&key, gamecode)?;
+    assert_eq!(small.full_data(), data);
+    Ok(())
+}
@@ -0,0 +1,298 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use ds_rom::{
+    crypto::sha1::Sha1,
+    rom::{
+        raw, Arm9BuildConfig, BuildCache, BuildCacheError, BuildMetadata, Header, ModuleCompression, PreservationLevel,
+        Processor, ProjectManifest, ProjectManifestWarning, Rom, RomExtractOptions, RomLoadOptions,
+    },
+};
+
+use crate::common::{generate_project, CONFIG};
+
+#[test]
+fn test_compression_report() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-compression-report");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let overlays_path = root.join("arm9_overlays/overlays.yaml");
+    fs::write(&overlays_path, fs::read_to_string(&overlays_path)?.replace("compressed: false", "compressed: true"))?;
+
+    let rom = Rom::load(root.join("config.yaml"), RomLoadOptions { compress: true, ..Default::default() })?;
+    let report = rom.compression_report();
+    assert_eq!(rom.overlays(Processor::Arm9).len(), 1);
+    assert!(rom.overlays(Processor::Arm7).is_empty());
+    let compressed_size = rom.arm9_overlays()[0].full_data().len() as u32;
+    assert_eq!(report.modules, [ModuleCompression {
+        name: "ARM9 overlay 0".to_string(),
+        uncompressed_size: 0x20,
+        compressed_size
+    }]);
+    assert_eq!(report.ratio(), compressed_size as f64 / 0x20 as f64);
+    assert_eq!(
+        report.modules[0].to_string(),
+        format!("ARM9 overlay 0: 0x20 -> {compressed_size:#x} ({:.1}%)", report.ratio() * 100.0)
+    );
+
+    // Nothing is compressed unless requested
+    let rom = Rom::load(root.join("config.yaml"), RomLoadOptions { compress: false, ..Default::default() })?;
+    assert!(rom.compression_report().modules.is_empty());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_build_metadata() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-build-metadata");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let config_path = root.join("config.yaml");
+    let overlays_path = root.join("arm9_overlays/overlays.yaml");
+    fs::write(&overlays_path, fs::read_to_string(&overlays_path)?.replace("compressed: false", "compressed: true"))?;
+
+    let built = Rom::load(&config_path, Default::default())?.build(None)?;
+    let metadata = BuildMetadata::compute(&config_path, &built)?;
+    let names = metadata.components.iter().map(|component| component.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, [
+        "header",
+        "arm9",
+        "overlay:0",
+        "arm7",
+        "fnt",
+        "fat",
+        "banner",
+        "file:/c.bin",
+        "file:/data/a.bin",
+        "file:/data/b.bin"
+    ]);
+
+    let hex = |bytes: &[u8]| Sha1::digest(bytes).iter().map(|b| format!("{b:02x}")).collect::<String>();
+    let overlay = metadata.component("overlay:0").unwrap();
+    let inputs = overlay.inputs.iter().map(|input| (input.path.clone(), input.sha1.clone())).collect::<Vec<_>>();
+    assert_eq!(inputs, [
+        (root.join("arm9_overlays/overlays.yaml"), hex(fs::read(&overlays_path)?.as_slice())),
+        (root.join("arm9_overlays/ov000.bin"), hex(&[0x33; 0x20])),
+    ]);
+    let output = overlay.output.as_ref().unwrap();
+    let alloc = built.fat()?[0];
+    assert_eq!((output.offset, output.size), (alloc.start, alloc.end - alloc.start));
+    assert_eq!(output.sha1, hex(alloc.into_file(built.data())));
+    let banner_inputs =
+        metadata.component("banner").unwrap().inputs.iter().map(|input| input.path.clone()).collect::<Vec<_>>();
+    assert_eq!(banner_inputs, [
+        root.join("banner/banner.yaml"),
+        root.join("banner/bitmap.png"),
+        root.join("banner/icon.bin"),
+        root.join("banner/palette.png")
+    ]);
+
+    let metadata_path = root.join("build_meta.json");
+    metadata.save(&metadata_path)?;
+    assert_eq!(BuildMetadata::load(&metadata_path)?.components.len(), metadata.components.len());
+
+    // Unchanged overlays are reused, unless they're refreshed
+    let cache = BuildCache::load(&metadata, &built, &[])?;
+    assert_eq!(cache.num_overlays(), 1);
+    assert_eq!(BuildCache::load(&metadata, &built, &["overlay:0".to_string()])?.num_overlays(), 0);
+    let cached_options = RomLoadOptions { build_cache: Some(&cache), ..Default::default() };
+    let rom = Rom::load(&config_path, cached_options.clone())?;
+    assert_eq!(rom.compression_report().modules.len(), 1);
+    assert_eq!(rom.build(None)?.data(), built.data());
+
+    // Changed overlays are compressed again
+    fs::write(root.join("arm9_overlays/ov000.bin"), [0x34; 0x20])?;
+    let expected = Rom::load(&config_path, Default::default())?.build(None)?;
+    assert_ne!(expected.data(), built.data());
+    assert_eq!(Rom::load(&config_path, cached_options)?.build(None)?.data(), expected.data());
+
+    // The cache can't be loaded from a different ROM or version
+    let result = BuildCache::load(&metadata, &expected, &[]).err();
+    assert!(matches!(&result, Some(BuildCacheError::StaleSection { name }) if name == "overlay:0"), "{result:?}");
+    let other_version = BuildMetadata { version: "0.0.0".to_string(), ..metadata };
+    let result = BuildCache::load(&other_version, &built, &[]).err();
+    assert!(matches!(result, Some(BuildCacheError::VersionMismatch { .. })), "{result:?}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_build_cache_arm9() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-build-cache-arm9");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let config_path = root.join("config.yaml");
+    // Compression starts after the first 0x4000 bytes, so the program must be larger than that
+    let arm9_path = root.join("arm9/arm9.bin");
+    let mut arm9 = fs::read(&arm9_path)?;
+    arm9.extend((0x1000..0x5000).map(|i: u32| (i / 0x10) as u8));
+    fs::write(&arm9_path, &arm9)?;
+    let arm9_config_path = root.join("arm9/arm9.yaml");
+    let mut arm9_config: Arm9BuildConfig = serde_yml::from_str(&fs::read_to_string(&arm9_config_path)?)?;
+    arm9_config.compressed = true;
+    arm9_config.build_info.bss_start = 0x2005000;
+    arm9_config.build_info.bss_end = 0x2006000;
+    fs::write(&arm9_config_path, serde_yml::to_string(&arm9_config)?)?;
+
+    let built = Rom::load(&config_path, Default::default())?.build(None)?;
+    assert!(Rom::extract(&built)?.arm9().is_compressed()?);
+    let metadata = BuildMetadata::compute(&config_path, &built)?;
+    let cache = BuildCache::load(&metadata, &built, &[])?;
+    assert!(cache.has_arm9());
+    assert!(!BuildCache::load(&metadata, &built, &["arm9".to_string()])?.has_arm9());
+    let cached_options = RomLoadOptions { build_cache: Some(&cache), ..Default::default() };
+    let rom = Rom::load(&config_path, cached_options.clone())?;
+    assert_eq!(rom.compression_report().modules[0].name, "ARM9 program");
+    assert_eq!(rom.build(None)?.data(), built.data());
+
+    // Changes before the compressed part are taken from the new program, and changes after it compress the program again
+    for offset in [0x100, 0x4800] {
+        let mut modified = arm9.clone();
+        modified[offset] ^= 0xff;
+        fs::write(&arm9_path, &modified)?;
+        let expected = Rom::load(&config_path, Default::default())?.build(None)?;
+        assert_ne!(expected.data(), built.data());
+        assert_eq!(Rom::load(&config_path, cached_options.clone())?.build(None)?.data(), expected.data());
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_project_manifest() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-project-manifest");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let rom = Rom::extract(&original)?;
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+
+    let manifest = ProjectManifest::new(&original, &rom, rom.config());
+    assert_eq!(manifest.ds_rom_version, ProjectManifest::CURRENT_VERSION);
+    assert_eq!(manifest.rom.size, original.data().len() as u32);
+    assert_eq!(manifest.rom.sha1.len(), 40);
+    assert_eq!(manifest.counts.arm9_overlays, 1);
+    assert_eq!(manifest.counts.files, 3);
+    assert_eq!(manifest.counts.file_bytes, 0x48);
+    assert!(manifest.files.contains_key("config.yaml"));
+    assert!(manifest.files.contains_key("path_order.txt"));
+    assert!(manifest.files.contains_key(ProjectManifest::FILE_NAME));
+
+    let manifest_path = extract_path.join(ProjectManifest::FILE_NAME);
+    manifest.save(&manifest_path)?;
+    let loaded = ProjectManifest::load(&manifest_path)?;
+    assert_eq!(loaded, manifest);
+
+    let rom = Rom::load(extract_path.join("config.yaml"), Default::default())?;
+    assert_eq!(loaded.check(&rom, &Default::default()), []);
+
+    let mut older = loaded.clone();
+    older.ds_rom_version = "0.0.1".to_string();
+    older.options.fnt_order = true;
+    let warnings = older.check(&rom, &Default::default());
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].to_string().contains("an older version of ds-rom (0.0.1"), "{}", warnings[0]);
+    assert_eq!(warnings[1], ProjectManifestWarning::Option { name: "fnt_order".to_string() });
+
+    older.options.compress = true;
+    let warnings = older.check(&rom, &RomLoadOptions { compress: false, ..Default::default() });
+    assert!(warnings.contains(&ProjectManifestWarning::Option { name: "compress".to_string() }));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_preservation_levels() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-preservation-levels");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let banner_offset = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?.header()?.banner_offset;
+
+    // Unsorted FNT, files stored in reverse, zero padding with a gap before the banner, unused FAT entries and a signature
+    // after the ROM
+    fs::write(root.join("fnt_order.txt"), "/data\n/data/b.bin\n/data/a.bin\n/c.bin\n")?;
+    fs::write(root.join("path_order.txt"), "/c.bin\n/data/b.bin\n/data/a.bin\n")?;
+    let config = CONFIG.replace("padding_value: 255", "padding_value: 0");
+    let extra_config = format!(
+        "fnt_order: fnt_order.txt\nfat_layout:\n  num_entries: 8\nsection_offsets:\n  banner: {}\n",
+        banner_offset + 0x400
+    );
+    fs::write(root.join("config.yaml"), config + &extra_config)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(build.header()?.banner_offset, banner_offset + 0x400);
+    let mut data = build.data().to_vec();
+    data.extend((0..0x88).map(|i| i as u8));
+    let original = raw::Rom::new(data);
+
+    let save = |level: PreservationLevel| -> Result<PathBuf> {
+        let path = root.join(format!("{level:?}"));
+        Rom::extract_with_options(&original, RomExtractOptions::preservation(level))?.save(&path, None)?;
+        Ok(path)
+    };
+    let load_config =
+        |path: &Path| -> Result<serde_yml::Value> { Ok(serde_yml::from_str(&fs::read_to_string(path.join("config.yaml"))?)?) };
+
+    for level in [PreservationLevel::Standard, PreservationLevel::Exact] {
+        let path = save(level)?;
+        let rebuilt = Rom::load(path.join("config.yaml"), Default::default())?.build(None)?;
+        assert!(rebuilt.data() == original.data(), "{level:?} does not round-trip\n{}", Rom::compare(&original, &rebuilt)?);
+    }
+    assert_eq!(Rom::extract(&original)?.config().preservation, Some(PreservationLevel::Standard));
+
+    let exact = root.join("Exact");
+    let config = load_config(&exact)?;
+    assert_eq!(config["preservation"], "exact");
+    assert!(exact.join(config["file_order"].as_str().unwrap()).is_file());
+    let header: Header = serde_yml::from_str(&fs::read_to_string(exact.join("header.yaml"))?)?;
+    assert!(header.original.preserve_capacity);
+
+    // Exact projects treat header inconsistencies as errors
+    let mut header = header;
+    header.original.unitcode = 2;
+    fs::write(exact.join("header.yaml"), serde_yml::to_string(&header)?)?;
+    let error = Rom::load(exact.join("config.yaml"), Default::default())?.build(None).err().unwrap();
+    assert!(error.to_string().contains("header unitcode: "), "{error}");
+
+    let minimal = save(PreservationLevel::Minimal)?;
+    let config = load_config(&minimal)?;
+    assert_eq!(config["preservation"], "minimal");
+    assert_eq!(config["padding_value"], 0xff);
+    for key in ["fnt_order", "file_order", "fat_layout", "trailing_data", "section_offsets"] {
+        assert!(config.get(key).is_none(), "{key} is preserved");
+    }
+    let rebuilt = Rom::load(minimal.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.header()?.banner_offset, banner_offset);
+    assert_eq!(rebuilt.trailing_data()?, None);
+    assert_eq!(rebuilt.fat()?.len(), 4);
+    let fnt = rebuilt.fnt()?.subtables[0].iter().map(|file| file.name.to_string()).collect::<Vec<_>>();
+    assert_eq!(fnt, ["c.bin", "data"]);
+    let rebuilt = Rom::extract(&rebuilt)?;
+    let files = rebuilt.files();
+    let offsets =
+        ["c.bin", "data/a.bin", "data/b.bin"].map(|path| files.file(files.find_path(path).unwrap()).original_offset());
+    assert!(offsets.is_sorted(), "{offsets:x?}");
+    assert_eq!(files.file(files.find_path("data/b.bin").unwrap()).contents(), [0xbb; 0x30]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::Result;
+use ds_rom::{
+    compress::{
+        huffman::{data_to_diff16, diff16_to_data, Diff16Error, Huffman, HuffmanBitWidth, HuffmanDecompressError},
+        lz10::{Lz10, Lz10DecompressError},
+        lz77::{Lz77, Lz77MatchFinder},
+        rle::{Rle, RleDecompressError},
+    },
+    rom::{raw, Rom},
+};
+
+/// Returns test data with runs, repeated sequences and noise.
+fn test_data() -> Vec<u8> {
+    let mut data = vec![0u8; 0x100];
+    data.extend(b"the quick brown fox jumps over the lazy dog, the quick brown fox".iter().cycle().take(0x1000));
+    let mut seed = 0x12345678u32;
+    data.extend((0..0x2000).map(|_| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 24) as u8
+    }));
+    data.extend([0xaa; 0x300]);
+    data
+}
+
+#[test]
+fn test_lz10() -> Result<()> {
+    let compressed = Lz10 {}.compress(b"ABABABAB");
+    assert_eq!(*compressed, [0x10, 0x08, 0x00, 0x00, 0x20, 0x41, 0x42, 0x30, 0x01, 0x00, 0x00, 0x00]);
+    assert_eq!(*Lz10 {}.decompress(&compressed)?, *b"ABABABAB");
+
+    for data in [vec![], vec![0x55], test_data()] {
+        let compressed = Lz10 {}.compress(&data);
+        assert_eq!(compressed.len() % 4, 0);
+        assert_eq!(*Lz10 {}.decompress(&compressed)?, *data);
+    }
+    assert!(Lz10 {}.compress(&test_data()).len() < test_data().len());
+
+    // Runs use a distance of 1 unless the result must be decompressible to VRAM
+    let unsafe_compressed = Lz10 {}.compress_with(b"AAAAAAAA", false);
+    assert_eq!(*unsafe_compressed, [0x10, 0x08, 0x00, 0x00, 0x40, 0x41, 0x40, 0x00]);
+    assert_eq!(*Lz10 {}.decompress(&unsafe_compressed)?, *b"AAAAAAAA");
+    assert!(Lz10 {}.compress_with(&test_data(), false).len() <= Lz10 {}.compress(&test_data()).len());
+
+    assert!(matches!(Lz10 {}.decompress(&[0x30, 0x08, 0x00, 0x00]), Err(Lz10DecompressError::InvalidHeader { .. })));
+    assert!(matches!(Lz10 {}.decompress(&compressed[..6]), Err(Lz10DecompressError::Truncated { .. })));
+    assert!(matches!(
+        Lz10 {}.decompress(&[0x10, 0x08, 0x00, 0x00, 0x80, 0x30, 0x01]),
+        Err(Lz10DecompressError::OutOfBounds { distance: 2, available: 0, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_lz10_random() -> Result<()> {
+    let mut seed = 0x0badf00du32;
+    let mut random = |bound: u32| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 8) % bound
+    };
+    for _ in 0..200 {
+        // Small alphabets make plenty of matches at all lengths and distances
+        let size = random(0x2000);
+        let alphabet = random(16) + 1;
+        let data = (0..size).map(|_| random(alphabet) as u8).collect::<Vec<_>>();
+        for vram_safe in [true, false] {
+            let compressed = Lz10 {}.compress_with(&data, vram_safe);
+            assert_eq!(*Lz10 {}.decompress(&compressed)?, *data);
+
+            // Corrupted data must fail or decompress to something, but never panic
+            let mut corrupted = compressed.to_vec();
+            for _ in 0..8 {
+                let index = 4 + random(corrupted.len() as u32 - 4) as usize;
+                corrupted[index] = random(256) as u8;
+            }
+            let _ = Lz10 {}.decompress(&corrupted);
+            let _ = Lz10 {}.decompress(&corrupted[..random(corrupted.len() as u32) as usize]);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_rle() -> Result<()> {
+    let compressed = Rle {}.compress(b"AAAAAB");
+    assert_eq!(*compressed, [0x30, 0x06, 0x00, 0x00, 0x82, 0x41, 0x00, 0x42]);
+    assert_eq!(*Rle {}.decompress(&compressed)?, *b"AAAAAB");
+
+    for data in [vec![], vec![0x55], test_data()] {
+        let compressed = Rle {}.compress(&data);
+        assert_eq!(compressed.len() % 4, 0);
+        assert_eq!(*Rle {}.decompress(&compressed)?, *data);
+    }
+
+    assert!(matches!(Rle {}.decompress(&[0x10, 0x06, 0x00, 0x00]), Err(RleDecompressError::InvalidHeader { .. })));
+    assert!(matches!(Rle {}.decompress(&[0x30, 0x06, 0x00, 0x00, 0x05, 0x41]), Err(RleDecompressError::Truncated { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_huffman() -> Result<()> {
+    let compressed = Huffman {}.compress(b"AAAB", HuffmanBitWidth::Eight);
+    assert_eq!(*compressed, [0x28, 0x04, 0x00, 0x00, 0x01, 0xc0, 0x42, 0x41, 0x00, 0x00, 0x00, 0xe0]);
+    assert_eq!(*Huffman {}.decompress(&compressed)?, *b"AAAB");
+
+    let mut seed = 0x2468ace0u32;
+    let mut random = |bound: u32| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % bound
+    };
+    // Uniform data makes a complete tree, and skewed data makes deep trees
+    let uniform = (0..0x2000).map(|i| i as u8).collect::<Vec<_>>();
+    let mut skewed = (0..=255u8).collect::<Vec<_>>();
+    let (mut count, mut next_count) = (1, 1);
+    for value in 0..24u8 {
+        skewed.extend(std::iter::repeat_n(value, count));
+        (count, next_count) = (next_count, count + next_count);
+    }
+    let mut random_data = vec![];
+    for _ in 0..16 {
+        let bound = random(256) + 1;
+        random_data.push((0..0x400).map(|_| random(bound) as u8).collect::<Vec<_>>());
+    }
+    for data in [vec![], vec![0x55], vec![0x55; 0x20], test_data(), uniform, skewed].into_iter().chain(random_data) {
+        for bit_width in [HuffmanBitWidth::Four, HuffmanBitWidth::Eight] {
+            let compressed = Huffman {}.compress(&data, bit_width);
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(*Huffman {}.decompress(&compressed)?, *data);
+        }
+    }
+    assert!(Huffman {}.compress(&[0x11; 0x100], HuffmanBitWidth::Four).len() < 0x100);
+
+    assert!(matches!(Huffman {}.decompress(&[0x20, 0x04, 0x00, 0x00]), Err(HuffmanDecompressError::InvalidHeader { .. })));
+    assert!(matches!(Huffman {}.decompress(&compressed[..8]), Err(HuffmanDecompressError::Truncated { .. })));
+    assert!(matches!(
+        Huffman {}.decompress(&[0x28, 0x04, 0x00, 0x00, 0x01, 0x01, 0x42, 0x41, 0x00, 0x00, 0x00, 0x00]),
+        Err(HuffmanDecompressError::OutOfBounds { offset: 5, end: 8, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_diff16() -> Result<()> {
+    let mut data = [0x34, 0x12, 0x35, 0x12, 0x00, 0x00];
+    data_to_diff16(&mut data)?;
+    assert_eq!(data, [0x34, 0x12, 0x01, 0x00, 0xcb, 0xed]);
+    diff16_to_data(&mut data)?;
+    assert_eq!(data, [0x34, 0x12, 0x35, 0x12, 0x00, 0x00]);
+
+    let mut seed = 0x7e57da7au32;
+    let mut random = |bound: u32| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 8) % bound
+    };
+    for _ in 0..100 {
+        let length = random(0x200) as usize * 2;
+        let original = (0..length).map(|_| random(256) as u8).collect::<Vec<_>>();
+        let mut data = original.clone();
+        data_to_diff16(&mut data)?;
+        diff16_to_data(&mut data)?;
+        assert_eq!(data, original);
+    }
+
+    assert!(matches!(data_to_diff16(&mut [0; 3]), Err(Diff16Error::OddLength { length: 3, .. })));
+    assert!(matches!(diff16_to_data(&mut [0; 1]), Err(Diff16Error::OddLength { length: 1, .. })));
+    Ok(())
+}
+
+/// Returns code-like test data, with many short repeats at varying distances like in ARM programs.
+fn code_like_data(size: usize) -> Vec<u8> {
+    let mut seed = 0x9abcdef0u32;
+    let mut data = Vec::with_capacity(size);
+    while data.len() < size {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let instruction = match seed >> 30 {
+            0 => 0xe1a00000,
+            1 => 0xe5900000 | (seed >> 8) & 0xfff,
+            2 => 0xeb000000 | (seed >> 4) & 0xffff,
+            _ => seed,
+        };
+        data.extend(instruction.to_le_bytes());
+    }
+    data.truncate(size);
+    data
+}
+
+fn assert_match_finders_agree(data: &[u8], start: usize, name: &str) -> Result<()> {
+    let naive = Lz77 {}.compress_with(data, start, Lz77MatchFinder::Naive)?;
+    let hash_chain = Lz77 {}.compress_with(data, start, Lz77MatchFinder::HashChain)?;
+    assert!(naive == hash_chain, "{name}: compressed outputs differ");
+    assert_eq!(*Lz77 {}.decompress(&hash_chain)?, *data, "{name}");
+    Ok(())
+}
+
+#[test]
+fn test_lz77_match_finders() -> Result<()> {
+    for size in [0x20, 0x21, 0x22, 0x23, 0x100] {
+        assert_match_finders_agree(&vec![0; size], 0, &format!("{size} zeros"))?;
+    }
+    assert_match_finders_agree(&test_data(), 0, "test data")?;
+    assert_match_finders_agree(&test_data(), 0x100, "test data after 0x100")?;
+    // Long enough for matches at the maximum distance
+    assert_match_finders_agree(&code_like_data(0x6000), 0x4000, "code-like data")?;
+    let mut repeated = code_like_data(0x1010);
+    repeated.extend_from_within(..0x1010);
+    assert_match_finders_agree(&repeated, 0, "repeated code-like data")?;
+
+    // ARM9 programs and overlays of the ROMs used by test_extract_build, if any
+    let roms_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms");
+    for entry in roms_dir.read_dir()? {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new("nds")) {
+            continue;
+        }
+        let raw_rom = raw::Rom::from_file(&path)?;
+        let rom = Rom::extract(&raw_rom)?;
+        let name = path.file_name().unwrap().to_string_lossy();
+        let mut arm9 = rom.arm9().clone();
+        if arm9.is_compressed()? {
+            arm9.decompress()?;
+            assert_match_finders_agree(arm9.full_data(), 0x4000, &format!("{name} ARM9 program"))?;
+        }
+        for overlay in rom.arm9_overlays().iter().chain(rom.arm7_overlays()) {
+            let mut overlay = overlay.clone();
+            if overlay.is_compressed() {
+                overlay.decompress()?;
+                assert_match_finders_agree(overlay.full_data(), 0, &format!("{name} overlay {}", overlay.id()))?;
+            }
+        }
+    }
+    Ok(())
+}
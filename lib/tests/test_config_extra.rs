@@ -0,0 +1,91 @@
+use anyhow::Result;
+use ds_rom::rom::{Arm9BuildConfig, OverlayConfig, RomConfig};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_yml::Value;
+
+const CONFIG: &str = "
+padding_value: 255
+header: header.yaml
+header_logo: header_logo.png
+arm9_bin: arm9/arm9.bin
+arm9_config: arm9/arm9.yaml
+arm7_bin: arm7/arm7.bin
+arm7_config: arm7/arm7.yaml
+itcm: { bin: arm9/itcm.bin, config: arm9/itcm.yaml }
+dtcm: { bin: arm9/dtcm.bin, config: arm9/dtcm.yaml }
+arm9_overlays: arm9_overlays/overlays.yaml
+arm7_overlays: null
+banner: banner/banner.yaml
+files_dir: files/
+path_order: path_order.txt
+dsd:
+  delinks: delinks.txt
+  symbols: [main, ov000]
+";
+
+const ARM9_CONFIG: &str = "
+base_address: 0x2000000
+entry_function: 0x2000800
+build_info: 0x4000
+autoload_callback: 0x2000900
+encrypted: true
+compressed: false
+bss_start: 0x2100000
+bss_end: 0x2200000
+sdk_version: 0x4000000
+dsd: { modules: 3 }
+";
+
+const OVERLAY_CONFIG: &str = "
+id: 0
+base_address: 0x2100000
+code_size: 4
+bss_size: 0
+ctor_start: 0
+ctor_end: 0
+file_id: 0
+compressed: false
+file_name: ov000.bin
+dsd: { name: main_menu }
+";
+
+fn round_trip<T: Serialize + DeserializeOwned>(yaml: &str) -> Result<(T, String)> {
+    let config: T = serde_yml::from_str(yaml)?;
+    let yaml = serde_yml::to_string(&config)?;
+    Ok((serde_yml::from_str(&yaml)?, yaml))
+}
+
+#[test]
+fn test_rom_config_extra() -> Result<()> {
+    let (config, yaml) = round_trip::<RomConfig>(CONFIG)?;
+    assert_eq!(config.extra.len(), 1);
+    let expected: Value = serde_yml::from_str("{ delinks: delinks.txt, symbols: [main, ov000] }")?;
+    assert_eq!(config.extra.get("dsd"), Some(&expected));
+    assert_eq!(config.padding_value, 255);
+    assert!(yaml.contains("dsd:"), "{yaml}");
+    Ok(())
+}
+
+#[test]
+fn test_arm9_build_config_extra() -> Result<()> {
+    let (config, _) = round_trip::<Arm9BuildConfig>(ARM9_CONFIG)?;
+    assert_eq!(config.extra.len(), 1);
+    assert_eq!(config.extra.get("dsd"), Some(&serde_yml::from_str::<Value>("{ modules: 3 }")?));
+    assert_eq!(config.offsets.base_address, 0x2000000);
+    assert_eq!(config.build_info.sdk_version, 0x4000000);
+    assert!(config.encrypted);
+    Ok(())
+}
+
+#[test]
+fn test_overlay_config_extra() -> Result<()> {
+    let (config, _) = round_trip::<OverlayConfig>(OVERLAY_CONFIG)?;
+    assert_eq!(config.extra.len(), 1);
+    assert_eq!(config.extra.get("dsd"), Some(&serde_yml::from_str::<Value>("{ name: main_menu }")?));
+    assert_eq!(config.file_name, "ov000.bin");
+    assert_eq!(config.info.base_address, 0x2100000);
+
+    let (config, _) = round_trip::<OverlayConfig>("{ id: 0, base_address: 0, code_size: 0, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: 0, compressed: false, file_name: a.bin }")?;
+    assert!(config.extra.is_empty());
+    Ok(())
+}
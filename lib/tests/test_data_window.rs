@@ -0,0 +1,54 @@
+use ds_rom::rom::{DataWindow, DataWindowError};
+
+const BASE: u32 = 0x2000000;
+
+fn data() -> Vec<u8> {
+    (0..0x40).collect()
+}
+
+#[test]
+fn test_data_window_defaults() {
+    let data = data();
+    let (slice, address) = DataWindow::default().slice(&data, BASE).unwrap();
+    assert_eq!((slice, address), (data.as_slice(), BASE));
+}
+
+#[test]
+fn test_data_window_address_and_offset() {
+    let data = data();
+
+    // Virtual address of a code module
+    let window = DataWindow { offset: Some(BASE + 0x10), length: Some(0x8) };
+    assert_eq!(window.slice(&data, BASE).unwrap(), (&data[0x10..0x18], BASE + 0x10));
+
+    // Offset from the start of the module
+    let window = DataWindow { offset: Some(0x10), length: None };
+    assert_eq!(window.slice(&data, BASE).unwrap(), (&data[0x10..], BASE + 0x10));
+
+    // Modules without a base address only take offsets
+    let window = DataWindow { offset: Some(0x30), length: Some(0x10) };
+    assert_eq!(window.slice(&data, 0).unwrap(), (&data[0x30..], 0x30));
+}
+
+#[test]
+fn test_data_window_out_of_bounds() {
+    let data = data();
+
+    let error = DataWindow { offset: Some(BASE + 0x40), length: None }.slice(&data, BASE).unwrap_err();
+    assert!(matches!(error, DataWindowError::StartOutOfBounds { size: 0x40, .. }), "{error}");
+    assert!(error.to_string().starts_with("offset 0x2000040 is past the end of the data, which spans 0x2000000..0x2000040"));
+
+    // Crosses the end of the data
+    let error = DataWindow { offset: Some(BASE + 0x38), length: Some(0x10) }.slice(&data, BASE).unwrap_err();
+    assert!(
+        matches!(error, DataWindowError::EndOutOfBounds { start: 0x2000038, window_end: 0x2000048, max_length: 0x8, .. }),
+        "{error}"
+    );
+
+    let error = DataWindow { offset: None, length: Some(u32::MAX) }.slice(&data, 0).unwrap_err();
+    assert!(matches!(error, DataWindowError::EndOutOfBounds { max_length: 0x40, .. }), "{error}");
+
+    // Ends exactly at the end of the data
+    let window = DataWindow { offset: Some(0x3f), length: Some(1) };
+    assert_eq!(window.slice(&data, BASE).unwrap(), (&data[0x3f..], BASE + 0x3f));
+}
@@ -0,0 +1,65 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use ds_rom::rom::{DedupError, DedupMode, DedupStore};
+
+#[test]
+fn test_dedup_store() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-dedup");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let store = DedupStore { path: "../store".into(), mode: DedupMode::Reference };
+    let project_a = root.join("a");
+    let project_b = root.join("b");
+
+    store.save_file(&project_a, &project_a.join("files/x.bin"), b"shared")?;
+    store.save_file(&project_b, &project_b.join("files/sub/y.bin"), b"shared")?;
+    store.save_file(&project_b, &project_b.join("files/z.bin"), b"unique")?;
+    assert_eq!(fs::read_dir(root.join("store"))?.count(), 2);
+
+    let reference = fs::read_to_string(project_a.join("files/x.bin"))?;
+    assert!(reference.starts_with(DedupStore::REFERENCE_PREFIX), "{reference}");
+    assert_eq!(store.load_file(&project_a, &project_a.join("files/x.bin"))?, b"shared");
+
+    let files = store.load_files(&project_b, &project_b.join("files"))?;
+    let files = files.into_iter().collect::<Vec<_>>();
+    assert_eq!(files, [(PathBuf::from("sub/y.bin"), b"shared".to_vec()), (PathBuf::from("z.bin"), b"unique".to_vec())]);
+
+    // Modified files replace their reference and are loaded as-is
+    fs::write(project_a.join("files/x.bin"), b"modified")?;
+    assert_eq!(store.load_file(&project_a, &project_a.join("files/x.bin"))?, b"modified");
+
+    let blob = fs::read_dir(root.join("store"))?
+        .map(|entry| entry.unwrap().path())
+        .find(|path| fs::read(path).unwrap() == b"unique")
+        .unwrap();
+    fs::write(&blob, b"corrupted")?;
+    let error = store.load_file(&project_b, &project_b.join("files/z.bin")).unwrap_err();
+    assert!(matches!(&error, DedupError::CorruptBlob { path, .. } if path.ends_with("z.bin")), "{error}");
+    fs::remove_file(&blob)?;
+    let error = store.load_file(&project_b, &project_b.join("files/z.bin")).unwrap_err();
+    assert!(matches!(&error, DedupError::MissingBlob { path, .. } if path.ends_with("z.bin")), "{error}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_dedup_store_hardlink() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-dedup-hardlink");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let store = DedupStore { path: "store".into(), mode: DedupMode::Hardlink };
+    let file_path = root.join("files/x.bin");
+    store.save_file(&root, &file_path, b"contents")?;
+    // Saving again must replace the link instead of writing through it
+    store.save_file(&root, &file_path, b"contents 2")?;
+    assert_eq!(store.load_file(&root, &file_path)?, b"contents 2");
+    assert_eq!(fs::read_dir(root.join("store"))?.count(), 2);
+    assert!(fs::read_dir(root.join("store"))?.any(|entry| fs::read(entry.unwrap().path()).unwrap() == b"contents"));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use ds_rom::rom::{
+    raw::{AccessControl, DsiFlags, DsiFlags2, RegionFlags, TableOffset},
+    DsiProgramOffsets, Header, HeaderDsPostDsi, HeaderDsi, Rom,
+};
+
+use crate::common::{assert_crcs, generate_project, CONFIG};
+
+/// Adds a DSi area to a project generated by [`generate_project`], given the header of a DS build of it.
+fn convert_to_dsi_project(path: &Path, ds_header: &Header) -> Result<()> {
+    let mut header = ds_header.clone();
+    header.original.unitcode = 2;
+    header.ds_post_dsi = Some(HeaderDsPostDsi {
+        dsi_flags_2: DsiFlags2::new().with_tsc_dsi_mode(true),
+        sha1_hmac_banner: [0; 0x14],
+        sha1_hmac_unk1: [0x11; 0x14],
+        sha1_hmac_unk2: [0x22; 0x14],
+        rsa_sha1: Box::new([0x33; 0x80]),
+    });
+    header.dsi = Some(HeaderDsi {
+        dsi_flags: DsiFlags::new(),
+        memory_banks_wram: [0x8084_8c80, 0x8c88_8480, 0x8c88_8480, 0x9094_989c, 0x9094_989c],
+        memory_banks_arm9: [0x0000_0000, 0x0720_0000, 0x0000_0000],
+        memory_banks_arm7: [0x0800_3000, 0x0a00_3000, 0x0b00_3000],
+        memory_bank_9: 0xff00_0000,
+        region_flags: RegionFlags::from_bits(0xffff_ffff),
+        access_control: AccessControl::from_bits(0x0000_0138),
+        arm7_scfg_ext7_setting: 0x0080_0000,
+        digest_ds_area: TableOffset { offset: 0x4000, size: 0x7000 },
+        digest_dsi_area: TableOffset { offset: 0x80000, size: 0x400 },
+        digest_sector_size: 0x400,
+        digest_sector_count: 0x20,
+        sd_shared2_sizes: [0; 6],
+        eula_version: 1,
+        use_ratings: false,
+        age_ratings: [0; 0x10],
+        file_type: 0x0003_0000,
+        sd_public_sav_size: 0,
+        sd_private_sav_size: 0,
+        modcrypt_area_1: TableOffset::default(),
+        modcrypt_area_2: TableOffset::default(),
+        sha1_hmac_arm9_with_secure_area: [0x44; 0x14],
+        sha1_hmac_arm7: [0x55; 0x14],
+        sha1_hmac_digest: [0x66; 0x14],
+        sha1_hmac_arm9i: [0x77; 0x14],
+        sha1_hmac_arm7i: [0x88; 0x14],
+        sha1_hmac_arm9: [0x99; 0x14],
+    });
+    fs::write(path.join("header.yaml"), serde_yml::to_string(&header)?)?;
+    let offsets = |base_address| DsiProgramOffsets { base_address, entry_function: base_address, build_info: 0 };
+    fs::create_dir_all(path.join("arm9i"))?;
+    fs::write(path.join("arm9i/arm9i.bin"), [0x9a; 0x300])?;
+    fs::write(path.join("arm9i/arm9i.yaml"), serde_yml::to_string(&offsets(0x2400000))?)?;
+    fs::create_dir_all(path.join("arm7i"))?;
+    fs::write(path.join("arm7i/arm7i.bin"), [0x7a; 0x100])?;
+    fs::write(path.join("arm7i/arm7i.yaml"), serde_yml::to_string(&offsets(0x2e80000))?)?;
+    fs::create_dir_all(path.join("dsi"))?;
+    fs::write(path.join("dsi/digest_sectors.bin"), [0xd5; 0x28])?;
+    fs::write(path.join("dsi/digest_blocks.bin"), [0xdb; 0x14])?;
+    let dsi_config = "dsi:
+  arm9i_bin: arm9i/arm9i.bin
+  arm9i_config: arm9i/arm9i.yaml
+  arm7i_bin: arm7i/arm7i.bin
+  arm7i_config: arm7i/arm7i.yaml
+  digest_sector_hashtable: dsi/digest_sectors.bin
+  digest_block_hashtable: dsi/digest_blocks.bin
+";
+    fs::write(path.join("config.yaml"), format!("{CONFIG}{dsi_config}"))?;
+    Ok(())
+}
+
+#[test]
+fn test_dsi_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-dsi-round-trip");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    generate_project(&original_path)?;
+
+    // A pure DS ROM has no DSi area
+    let ds_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert!(ds_build.dsi_area()?.is_none());
+    let ds_rom = Rom::extract(&ds_build)?;
+    assert!(ds_rom.config().dsi.is_none() && ds_rom.header().dsi.is_none());
+
+    convert_to_dsi_project(&original_path, ds_rom.header())?;
+
+    let rom = Rom::load(original_path.join("config.yaml"), Default::default())?;
+    assert!(rom.header().check_consistency(&rom).is_empty());
+    let first_build = rom.build(None)?;
+    assert_crcs(&first_build)?;
+    let raw_header = first_build.header()?;
+    assert_eq!(raw_header.unitcode, 2);
+    assert_eq!(raw_header.rom_size_ds, ds_build.header()?.rom_size_ds);
+    assert!(raw_header.digest_sector_hashtable.offset >= raw_header.rom_size_ds);
+    assert_eq!(raw_header.arm9i.offset % 0x80000, 0);
+    assert_eq!(raw_header.ds_rom_region_end as u32 * 0x80000, raw_header.arm9i.offset);
+    assert_eq!(raw_header.rom_size_dsi, raw_header.arm7i.offset + raw_header.arm7i.size);
+    assert_eq!(raw_header.arm9i.base_addr, 0x2400000);
+    assert_eq!(raw_header.sha1_hmac_arm9i, [0x77; 0x14]);
+    assert_eq!(raw_header.gamecode_rev.to_string(), "ESTA");
+
+    let dsi_area = first_build.dsi_area()?.unwrap();
+    assert_eq!(dsi_area.arm9i().full_data(), [0x9a; 0x300]);
+    assert_eq!(dsi_area.arm7i().full_data(), [0x7a; 0x100]);
+    assert_eq!(dsi_area.digest_sector_hashtable(), [0xd5; 0x28]);
+    assert_eq!(dsi_area.digest_block_hashtable(), [0xdb; 0x14]);
+
+    let extract_path = root.join("extract");
+    let extracted = Rom::extract(&first_build)?;
+    assert!(extracted.config().dsi.is_some() && extracted.config().section_offsets.is_none());
+    extracted.save(&extract_path, None)?;
+    assert!(ds_rom::rom::project::check(&extract_path.join("config.yaml")).is_empty());
+    let second_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(second_build.data(), first_build.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_modcrypt_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-modcrypt-round-trip");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    generate_project(&original_path)?;
+    let ds_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    convert_to_dsi_project(&original_path, Rom::extract(&ds_build)?.header())?;
+    let plain_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    let plain_header = *plain_build.header()?;
+    let (arm9i, arm7i) = (plain_header.arm9i, plain_header.arm7i);
+
+    for debug_key in [false, true] {
+        // Area 1 only covers part of the ARM9i program, and area 2 covers the whole ARM7i program
+        let mut rom = Rom::load(original_path.join("config.yaml"), Default::default())?;
+        let dsi = rom.header_mut().dsi.as_mut().unwrap();
+        dsi.dsi_flags = DsiFlags::new().with_modcrypted(true).with_modcrypt_debug_key(debug_key);
+        dsi.modcrypt_area_1 = TableOffset { offset: arm9i.offset + 0x100, size: 0x108 };
+        dsi.modcrypt_area_2 = TableOffset { offset: arm7i.offset, size: arm7i.size };
+        let encrypted_build = rom.build(None)?;
+        assert_eq!(encrypted_build.header()?.modcrypt_areas().len(), 2);
+
+        let arm9i_range = arm9i.offset as usize..(arm9i.offset + arm9i.size) as usize;
+        let arm7i_range = arm7i.offset as usize..(arm7i.offset + arm7i.size) as usize;
+        let (plain_arm9i, encrypted_arm9i) = (&plain_build.data()[arm9i_range.clone()], &encrypted_build.data()[arm9i_range]);
+        assert_eq!(encrypted_arm9i[..0x100], plain_arm9i[..0x100]);
+        assert_ne!(encrypted_arm9i[0x100..0x208], plain_arm9i[0x100..0x208]);
+        assert_eq!(encrypted_arm9i[0x208..], plain_arm9i[0x208..]);
+        assert_ne!(encrypted_build.data()[arm7i_range.clone()], plain_build.data()[arm7i_range.clone()]);
+
+        let mut decrypted_build = encrypted_build.to_owned();
+        decrypted_build.decrypt_modcrypt()?;
+        assert_eq!(decrypted_build.data()[arm7i_range.clone()], plain_build.data()[arm7i_range]);
+        assert_eq!(decrypted_build.dsi_area()?.unwrap().arm9i().full_data(), plain_arm9i);
+        decrypted_build.encrypt_modcrypt()?;
+        assert_eq!(decrypted_build.data(), encrypted_build.data());
+
+        let extract_path = root.join("extract");
+        let extracted = Rom::extract(&encrypted_build)?;
+        let dsi_area = extracted.dsi_area().unwrap();
+        assert_eq!(dsi_area.arm9i().full_data(), [0x9a; 0x300]);
+        assert_eq!(dsi_area.arm7i().full_data(), [0x7a; 0x100]);
+        extracted.save(&extract_path, None)?;
+        assert_eq!(fs::read(extract_path.join("arm9i/arm9i.bin"))?, [0x9a; 0x300]);
+        let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+        assert_eq!(rebuilt.data(), encrypted_build.data());
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
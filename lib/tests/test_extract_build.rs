@@ -43,10 +43,20 @@ fn test_extract_build() -> Result<()> {
         let build_path = path.with_file_name(format!("build_{file_name}"));
         let config_path = extract_path.join("config.yaml");
 
-        let rom = Rom::load(&config_path, Default::default())?;
+        let mut rom = Rom::load(&config_path, Default::default())?;
+        let layout = rom.plan_layout()?;
         let raw_rom = rom.build(Some(&key))?;
         raw_rom.save(&build_path)?;
 
+        // The planned layout must match the built ROM
+        let header = raw_rom.header()?;
+        assert_eq!(layout.arm9.offset, header.arm9.offset);
+        assert_eq!(layout.arm7.offset, header.arm7.offset);
+        assert_eq!(layout.fnt.offset, header.file_names.offset);
+        assert_eq!(layout.fat.offset, header.file_allocs.offset);
+        assert_eq!(layout.banner.map_or(0, |banner| banner.offset), header.banner_offset);
+        assert_eq!(layout.padded_size as usize, raw_rom.data().len());
+
         // Compare
         let target = fs::read(&path)?;
         let build = fs::read(&build_path)?;
@@ -0,0 +1,452 @@
+mod common;
+
+use std::{borrow::Cow, collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::Result;
+use ds_rom::rom::{
+    raw::{self, Fnt, FntDirectory, FntSubtable, RawFntError},
+    EmptyDirFileId, FatLayout, FileBuildError, FileOrderError, FileSource, FileSystem, Rom, RomEmbeddedError, UnsortedDir,
+};
+
+use crate::common::generate_project;
+
+#[test]
+fn test_empty_dir_first_file_id() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-empty-dir");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("a"))?;
+    fs::create_dir_all(root.join("b"))?;
+    fs::write(root.join("root.bin"), [0])?;
+    fs::write(root.join("b/x.bin"), [1])?;
+
+    let mut files = FileSystem::load(&root, 2)?;
+    files.sort_for_fnt();
+    let fnt = files.build_fnt()?;
+
+    let first_file_ids = fnt.subtables.iter().map(|s| s.directory.first_file_id).collect::<Vec<_>>();
+    // Overlays use IDs 0 and 1, so files start at 2. The empty directory "a" gets the ID that "b/x.bin" was assigned.
+    assert_eq!(first_file_ids, [2, 3, 3]);
+    assert_eq!(files.empty_dirs(), [PathBuf::from("a")]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_empty_dir_file_id_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-empty-dir-file-id");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+    fs::create_dir_all(original_path.join("files/empty"))?;
+    let built = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+
+    // Give the empty directory a first file ID other than the one of the next file
+    let empty_id = Rom::extract(&built)?.files().find_path("empty").unwrap();
+    let fnt_offset = built.header()?.file_names.offset as usize;
+    let first_file_id_offset = fnt_offset + (empty_id as usize & 0xfff) * size_of::<FntDirectory>() + 4;
+    let mut data = built.data().to_vec();
+    assert_eq!(data[first_file_id_offset..first_file_id_offset + 2], 4u16.to_le_bytes());
+    data[first_file_id_offset..first_file_id_offset + 2].copy_from_slice(&1u16.to_le_bytes());
+    let original = raw::Rom::new(data);
+
+    let extracted = Rom::extract(&original)?;
+    assert_eq!(extracted.files().empty_dir_file_ids(), [EmptyDirFileId { path: "/empty".into(), first_file_id: 1 }]);
+    extracted.save(&extract_path, None)?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.fnt()?.build()?, original.fnt()?.build()?);
+    assert!(rebuilt.data() == original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_non_contiguous_file_ids() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-non-contiguous-file-ids");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(&root)?;
+    fs::write(root.join("a.bin"), [0])?;
+    fs::write(root.join("b.bin"), [1])?;
+
+    // The original FNT numbered b.bin first, so sorting the directory without renumbering leaves the IDs out of order
+    let fnt_order = ["/b.bin", "/a.bin"].map(String::from);
+    let mut files = FileSystem::load_with_fnt_order(&root, 0, BTreeMap::new(), &fnt_order)?;
+    assert_eq!(files.find_path("a.bin"), Some(1));
+    files.clear_fnt_order();
+    files.sort_for_fnt();
+    let Err(error) = files.build_fnt() else { panic!("built an FNT with non-contiguous file IDs") };
+    assert!(matches!(error, FileBuildError::NonContiguousFileIds { expected: 2, actual: 0, .. }), "{error}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_reserved_fat_entries() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-reserved-fat-entries");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("a"))?;
+    fs::write(root.join("root.bin"), [0])?;
+    fs::write(root.join("a/x.bin"), [1])?;
+
+    // Two overlays, but the original FAT reserved five entries before the first file
+    let mut files = FileSystem::load(&root, 5)?;
+    assert_eq!(files.num_reserved(), 5);
+    files.sort_for_fnt();
+    let fnt = files.build_fnt()?;
+
+    let first_file_ids = fnt.subtables.iter().map(|s| s.directory.first_file_id).collect::<Vec<_>>();
+    assert_eq!(first_file_ids, [5, 6]);
+    assert_eq!(files.max_file_id(), 6);
+    assert_eq!(files.total_content_size(), 2);
+    assert_eq!(files.owned_bytes(), 2);
+    assert_eq!(files.file(5).source(), FileSource::Owned);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_fat_layout_config() -> Result<()> {
+    let layout: FatLayout = serde_yml::from_str("num_entries: 12")?;
+    assert!(layout == FatLayout { reserved_entries: None, num_entries: Some(12) });
+    assert!(!layout.is_default());
+    assert!(serde_yml::from_str::<FatLayout>("{}")?.is_default());
+    assert_eq!(
+        serde_yml::to_string(&FatLayout { reserved_entries: Some(3), num_entries: None })?.trim(),
+        "reserved_entries: 3"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_interleaved_fnt_order() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-interleaved-fnt-order");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("sub"))?;
+    fs::write(root.join("a.bin"), [0])?;
+    fs::write(root.join("m.bin"), [1])?;
+    fs::write(root.join("z.bin"), [2])?;
+    fs::write(root.join("sub/x.bin"), [3])?;
+
+    // The directory precedes a file in the original FNT, and m.bin is a new file missing from the FNT order
+    let fnt_order = ["/a.bin", "/sub", "/sub/x.bin", "/z.bin"].map(String::from);
+    let mut files = FileSystem::load_with_fnt_order(&root, 0, BTreeMap::new(), &fnt_order)?;
+    files.sort_for_rom();
+    files.sort_for_fnt();
+    let fnt = files.build_fnt()?;
+
+    let root_entries = fnt.subtables[0].iter().map(|file| (file.id, file.name.to_string())).collect::<Vec<_>>();
+    assert_eq!(root_entries, [(0, "a.bin".into()), (1, "m.bin".into()), (0xf001, "sub".into()), (2, "z.bin".into())]);
+    let first_file_ids = fnt.subtables.iter().map(|s| s.directory.first_file_id).collect::<Vec<_>>();
+    assert_eq!(first_file_ids, [0, 3]);
+
+    let expected_order = ["/a.bin", "/m.bin", "/sub", "/sub/x.bin", "/z.bin"];
+    assert_eq!(files.fnt_order().unwrap(), expected_order);
+
+    let report = files.analyze_ordering();
+    assert_eq!(report.unsorted_dirs, [UnsortedDir { path: "/".into(), first: "sub".into(), second: "z.bin".into() }]);
+    assert!(!report.file_image_differs);
+
+    // Without an FNT order, files come first
+    let files = FileSystem::load(&root, 0)?;
+    assert!(files.fnt_order().is_none());
+    assert!(files.analyze_ordering().is_fnt_sorted());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_analyze_ordering() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-analyze-ordering");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("sub"))?;
+    fs::write(root.join("a.bin"), [0])?;
+    fs::write(root.join("z.bin"), [1])?;
+    fs::write(root.join("sub/x.bin"), [2])?;
+
+    // ROM order places the directory between the files, but the FNT lists files first
+    let mut files = FileSystem::load(&root, 0)?;
+    files.sort_for_rom();
+    let report = files.analyze_ordering();
+    assert_eq!(report.unsorted_dirs, [UnsortedDir { path: "/".into(), first: "sub".into(), second: "z.bin".into() }]);
+    assert!(!report.file_image_differs);
+
+    files.sort_for_fnt();
+    assert!(files.analyze_ordering().is_fnt_sorted());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_file_order() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-file-order");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("a"))?;
+    fs::create_dir_all(root.join("b"))?;
+    fs::write(root.join("a/x.bin"), [0])?;
+    fs::write(root.join("a/y.bin"), [1])?;
+    fs::write(root.join("b/z.bin"), [2])?;
+
+    let files = FileSystem::load(&root, 0)?;
+    let id = |path: &str| files.find_path(path).unwrap();
+
+    // Files interleaved across directories
+    let file_order = ["/a/y.bin", "/b/z.bin", "", "a/x.bin"];
+    assert_eq!(files.resolve_file_order(&file_order)?, [id("a/y.bin"), id("b/z.bin"), id("a/x.bin")]);
+
+    let error = files.resolve_file_order(&["/a/y.bin", "/b/z.bin", "/a/w.bin"]).unwrap_err();
+    assert!(matches!(error, FileOrderError::FileNotFound { line: 3, .. }), "{error}");
+    let error = files.resolve_file_order(&["/a/y.bin", "/b"]).unwrap_err();
+    assert!(matches!(error, FileOrderError::NotAFile { line: 2, .. }), "{error}");
+    let error = files.resolve_file_order(&["/a/y.bin", "/b/z.bin", "/a/y.bin"]).unwrap_err();
+    assert!(matches!(error, FileOrderError::DuplicateFile { line: 3, first_line: 1, .. }), "{error}");
+    let error = files.resolve_file_order(&["/a/y.bin", "/b/z.bin"]).unwrap_err();
+    assert!(matches!(&error, FileOrderError::MissingFile { path, .. } if path == "/a/x.bin"), "{error}");
+
+    assert_eq!(files.compute_file_order().len(), 3);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_size_estimates() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-size-estimates");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("data/empty"))?;
+    fs::create_dir_all(root.join("テキスト"))?;
+    fs::write(root.join("a.bin"), [0; 0x10])?;
+    fs::write(root.join("data/b.bin"), [0; 0x200])?;
+    fs::write(root.join("data/c.bin"), [0; 0x201])?;
+    fs::write(root.join("テキスト/メッセージ.bin"), [])?;
+    // Names are truncated to 0x7f bytes in the FNT
+    fs::write(root.join("data").join("x".repeat(0x90)), [0; 3])?;
+
+    let mut files = FileSystem::load(&root, 3)?;
+    files.sort_for_fnt();
+    assert_eq!(files.fnt_size()?, files.build_fnt()?.build()?.len());
+
+    // 3 reserved entries and 5 files
+    assert_eq!(files.num_fat_entries(), 8);
+    assert_eq!(files.fat_size(), 8 * 8);
+    assert_eq!(files.estimated_file_image_size(0x200), 0x200 + 0x200 + 0x400 + 0x200);
+    assert_eq!(files.estimated_file_image_size(1), 0x10 + 0x200 + 0x201 + 3);
+
+    let empty = FileSystem::new(2);
+    assert_eq!(empty.fnt_size()?, empty.build_fnt()?.build()?.len());
+    assert_eq!(empty.estimated_file_image_size(0x200), 0);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_stats() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-fs-stats");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("data/empty"))?;
+    fs::create_dir_all(root.join("data/deep/er"))?;
+    fs::create_dir_all(root.join("テキスト"))?;
+    fs::write(root.join("a.bin"), [0; 0x10])?;
+    fs::write(root.join("data/b.bin"), [0; 0x200])?;
+    fs::write(root.join("data/deep/er/c.bin"), [0; 0x8])?;
+    fs::write(root.join("テキスト/メッセージ.bin"), [])?;
+    fs::write(root.join("data/long_file_name.bin"), [0; 3])?;
+
+    let mut files = FileSystem::load(&root, 0)?;
+    files.sort_for_fnt();
+    assert_eq!(files.file_count(), 5);
+    assert_eq!(files.dir_count(), 6);
+    assert_eq!(files.max_depth(), 4);
+
+    let stats = files.stats();
+    assert_eq!(stats.file_count, 5);
+    assert_eq!(stats.dir_count, 6);
+    assert_eq!(stats.total_content_size, 0x10 + 0x200 + 0x8 + 3);
+    assert_eq!(stats.deepest_path, "/data/deep/er/c.bin");
+    assert_eq!(stats.longest_name, "long_file_name.bin");
+    assert_eq!(stats.non_ascii_names, 2);
+    let text = stats.to_string();
+    assert!(text.contains("Max depth ......... : 4 (/data/deep/er/c.bin)"), "{text}");
+
+    let empty = FileSystem::new(0);
+    assert_eq!(empty.dir_count(), 1);
+    assert_eq!(empty.max_depth(), 0);
+    assert_eq!(empty.stats().deepest_path, "");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_shift_jis_names() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-shift-jis-names");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("データ"))?;
+    fs::write(root.join("データ/テスト.bin"), [0])?;
+    fs::write(root.join("ｶﾅ.bin"), [1])?;
+
+    let mut files = FileSystem::load(&root, 0)?;
+    files.sort_for_fnt();
+    let fnt = files.build_fnt()?;
+    let root_names = fnt.subtables[0].iter().map(|file| (file.name.to_string(), file.raw_name.to_vec())).collect::<Vec<_>>();
+    assert_eq!(root_names, [
+        ("ｶﾅ.bin".to_string(), b"\xb6\xc5.bin".to_vec()),
+        ("データ".to_string(), b"\x83f\x81[\x83^".to_vec())
+    ]);
+    let sub_names = fnt.subtables[1].iter().map(|file| file.raw_name.to_vec()).collect::<Vec<_>>();
+    assert_eq!(sub_names, [b"\x83e\x83X\x83g.bin".to_vec()]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_truncated_fnt_subtable() {
+    let directory = FntDirectory { subtable_offset: 0, first_file_id: 0, parent_id: 1 };
+    // A complete file entry, followed by a directory entry which is missing its ID
+    let subtable = FntSubtable { directory: Cow::Owned(directory), data: Cow::Borrowed(b"\x01a\x81b") };
+    let names = subtable.iter().map(|file| file.name.to_string()).collect::<Vec<_>>();
+    assert_eq!(names, ["a"]);
+
+    let subtable = FntSubtable { directory: Cow::Owned(directory), data: Cow::Borrowed(b"\x05ab") };
+    assert_eq!(subtable.iter().count(), 0);
+}
+
+/// Copies the FNT bytes into a 4-aligned buffer, as [`Fnt::borrow_from_slice`] requires.
+fn aligned_fnt(bytes: &[u8]) -> Vec<u32> {
+    let mut words = vec![0u32; bytes.len().div_ceil(4)];
+    bytemuck::cast_slice_mut::<u32, u8>(&mut words)[..bytes.len()].copy_from_slice(bytes);
+    words
+}
+
+fn borrow_fnt(words: &[u32], len: usize) -> Result<Fnt<'_>, RawFntError> {
+    Fnt::borrow_from_slice(&bytemuck::cast_slice(words)[..len])
+}
+
+#[test]
+fn test_fnt_subtable_bounds() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-fnt-subtable-bounds");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("dir"))?;
+    fs::write(root.join("dir/b.bin"), [0])?;
+    fs::write(root.join("a.bin"), [1])?;
+    let mut files = FileSystem::load(&root, 0)?;
+    files.sort_for_fnt();
+    let bytes = files.build_fnt()?.build()?;
+    let words = aligned_fnt(&bytes);
+
+    // Each subtable ends where the next one starts
+    let fnt = borrow_fnt(&words, bytes.len())?;
+    let names = fnt.subtables.iter().map(|s| s.iter().map(|f| f.name.to_string()).collect::<Vec<_>>()).collect::<Vec<_>>();
+    assert_eq!(names, [vec!["a.bin", "dir"], vec!["b.bin"]]);
+    assert_eq!(
+        fnt.subtables[0].directory.subtable_offset as usize + fnt.subtables[0].data.len(),
+        fnt.subtables[1].directory.subtable_offset as usize
+    );
+
+    // Truncating the FNT anywhere must not panic
+    for len in 0..bytes.len() {
+        if let Ok(fnt) = borrow_fnt(&words, len) {
+            fnt.subtables.iter().for_each(|subtable| subtable.iter().for_each(drop));
+        }
+    }
+    assert!(matches!(borrow_fnt(&words, 12).err().unwrap(), RawFntError::DirectoriesOutOfBounds { num_dirs: 2, .. }));
+    let error = borrow_fnt(&words, 0x12).err().unwrap();
+    assert!(matches!(error, RawFntError::SubtableOutOfBounds { dir_index: 1, offset: 0x1d, fnt_size: 0x12, .. }), "{error}");
+
+    // Subtable offsets pointing into the directory list
+    let mut self_referencing = bytes.to_vec();
+    self_referencing[0..4].copy_from_slice(&0u32.to_le_bytes());
+    let words = aligned_fnt(&self_referencing);
+    let error = borrow_fnt(&words, self_referencing.len()).err().unwrap();
+    assert!(matches!(error, RawFntError::SubtableOutOfBounds { dir_index: 0, offset: 0, .. }), "{error}");
+    self_referencing[0..4].copy_from_slice(&0x10u32.to_le_bytes());
+    self_referencing[8..12].copy_from_slice(&0x8u32.to_le_bytes());
+    let words = aligned_fnt(&self_referencing);
+    let error = borrow_fnt(&words, self_referencing.len()).err().unwrap();
+    assert!(matches!(error, RawFntError::SubtableOutOfBounds { dir_index: 1, offset: 8, .. }), "{error}");
+
+    // A subtable without a terminator doesn't run into the next subtable
+    let mut unterminated = bytes.to_vec();
+    // The root subtable's terminator is right before the second subtable
+    let root_end = u32::from_le_bytes(unterminated[8..12].try_into()?) as usize - 1;
+    unterminated.remove(root_end);
+    unterminated[8..12].copy_from_slice(&(root_end as u32).to_le_bytes());
+    let words = aligned_fnt(&unterminated);
+    let fnt = borrow_fnt(&words, unterminated.len())?;
+    assert_eq!(fnt.subtables[0].iter().count(), 2);
+    assert_eq!(fnt.subtables[1].iter().map(|file| file.name.to_string()).collect::<Vec<_>>(), ["b.bin"]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_open_embedded_rom() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-open-embedded-rom");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("child"))?;
+
+    let embedded_rom = |gamecode: &[u8; 4], fix_crc: bool| {
+        let mut header: raw::Header = bytemuck::Zeroable::zeroed();
+        header.gamecode.0 = *gamecode;
+        if fix_crc {
+            header.header_crc = header.compute_header_crc();
+        }
+        bytemuck::bytes_of(&header).to_vec()
+    };
+    fs::write(root.join("child/valid.srl"), embedded_rom(b"ABCE", true))?;
+    fs::write(root.join("child/gamecode.srl"), embedded_rom(&[0, 0xff, b'A', b'B'], true))?;
+    fs::write(root.join("child/crc.srl"), embedded_rom(b"ABCE", false))?;
+    fs::write(root.join("child/short.srl"), [0; 0x10])?;
+
+    let files = FileSystem::load(&root, 0)?;
+
+    let rom = files.open_embedded_rom("/child/valid.srl")?;
+    assert_eq!(&rom.header()?.gamecode.0, b"ABCE");
+    assert!(files.open_embedded_rom("child/valid.srl").is_ok());
+
+    let error = files.open_embedded_rom("child/missing.srl").err().unwrap();
+    assert!(matches!(error, RomEmbeddedError::NotFound { .. }), "{error}");
+    let error = files.open_embedded_rom("child").err().unwrap();
+    assert!(matches!(error, RomEmbeddedError::IsDirectory { .. }), "{error}");
+    let error = files.open_embedded_rom("child/gamecode.srl").err().unwrap();
+    assert!(matches!(error, RomEmbeddedError::InvalidGamecode { gamecode: [0, 0xff, b'A', b'B'], .. }), "{error}");
+    let error = files.open_embedded_rom("child/crc.srl").err().unwrap();
+    assert!(matches!(error, RomEmbeddedError::HeaderCrc { actual: 0, .. }), "{error}");
+    let error = files.open_embedded_rom("child/short.srl").err().unwrap();
+    assert!(matches!(error, RomEmbeddedError::RawHeader { .. }), "{error}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use ds_rom::rom::{raw, RomHashReport};
+
+fn make_rom() -> Vec<u8> {
+    let mut header: raw::Header = bytemuck::Zeroable::zeroed();
+    header.arm9.offset = 0x4000;
+    header.arm9.size = 0x100;
+    header.banner_offset = 0x4200;
+    header.header_crc = 0x1234;
+
+    let mut data = vec![0u8; 0x5000];
+    data[..size_of::<raw::Header>()].copy_from_slice(bytemuck::bytes_of(&header));
+    data[0x4200..0x4202].copy_from_slice(&1u16.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_hash_report_compare() -> anyhow::Result<()> {
+    let data = make_rom();
+    let original = RomHashReport::compute(&raw::Rom::new(data.as_slice()))?;
+    assert_eq!(original.size, 0x5000);
+    assert_eq!(original.header_crc, 0x1234);
+    let names = original.sections.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["Header", "ARM9 program", "Banner"]);
+
+    let yaml = serde_yml::to_string(&original)?;
+    let original: RomHashReport = serde_yml::from_str(&yaml)?;
+    assert!(original.compare(&RomHashReport::compute(&raw::Rom::new(data.as_slice()))?).iter().all(|c| c.matches()));
+
+    let mut modified = data.clone();
+    modified[0x4010] = 0xff;
+    let comparisons = original.compare(&RomHashReport::compute(&raw::Rom::new(modified))?);
+    let mismatches = comparisons.iter().filter(|c| !c.matches()).map(|c| c.field.as_str()).collect::<Vec<_>>();
+    assert_eq!(mismatches, ["CRC-32", "SHA-1", "ARM9 program"]);
+    Ok(())
+}
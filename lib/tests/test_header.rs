@@ -0,0 +1,132 @@
+use ds_rom::{
+    rom::{raw, Header},
+    str::AsciiArray,
+};
+
+#[test]
+fn test_header_compare_to_raw() {
+    let mut raw_header: raw::Header = bytemuck::Zeroable::zeroed();
+    raw_header.gamecode = AsciiArray(*b"ABCE");
+    raw_header.arm9.offset = 0x4000;
+    let header = Header::load_raw(&raw_header);
+
+    let comparisons = header.compare_to_raw(&raw_header);
+    assert!(comparisons.iter().all(|c| c.matches()));
+    let arm9_offset = comparisons.iter().find(|c| c.field == "arm9.offset").unwrap();
+    assert!(arm9_offset.is_derived());
+    assert_eq!(arm9_offset.actual, "0x4000");
+
+    raw_header.gamecode = AsciiArray(*b"ABCJ");
+    raw_header.arm9.offset = 0x8000;
+    let mismatches = header.compare_to_raw(&raw_header).into_iter().filter(|c| !c.matches()).collect::<Vec<_>>();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].field, "gamecode");
+    assert_eq!(mismatches[0].expected.as_deref(), Some("ABCE"));
+    assert_eq!(mismatches[0].actual, "ABCJ");
+}
+
+#[test]
+fn test_header_region_mismatch() {
+    let mut raw_header: raw::Header = bytemuck::Zeroable::zeroed();
+    raw_header.gamecode = AsciiArray(*b"ABCC");
+    assert_eq!(raw_header.gamecode_region(), Some("China"));
+    assert!(raw_header.region_mismatch().is_some());
+
+    raw_header.ds_flags = raw::DsFlags::from_bits(0x80);
+    assert!(raw_header.region_mismatch().is_none());
+    assert!(!raw_header.display(0).to_string().contains("region is not"));
+
+    raw_header.gamecode = AsciiArray(*b"ABCE");
+    let display = raw_header.display(0).to_string();
+    assert!(display.contains("China flag is set, but the gamecode region is not China"), "{display}");
+}
+
+#[test]
+fn test_header_capacity() {
+    let mut raw_header: raw::Header = bytemuck::Zeroable::zeroed();
+    raw_header.capacity = raw::Capacity(7);
+    let mut header = Header::load_raw(&raw_header);
+
+    // The original capacity is kept for smaller ROMs, but grows if the ROM no longer fits
+    assert_eq!(header.capacity(0x100000).0, 7);
+    assert_eq!(header.capacity(0x2000001).0, 9);
+    header.original.preserve_capacity = true;
+    assert_eq!(header.capacity(0x2000001).0, 7);
+    header.original.capacity = None;
+    assert_eq!(header.capacity(0xf0000).0, 3);
+
+    assert_eq!(raw::Capacity(7).to_string(), "16MB");
+    assert_eq!(raw::Capacity(7).size(), 16 * 1024 * 1024);
+
+    let yaml = serde_yml::to_string(&Header::load_raw(&raw_header)).unwrap();
+    assert!(yaml.contains("capacity: 7\n"), "{yaml}");
+    assert!(!yaml.contains("preserve_capacity"));
+    let header: Header = serde_yml::from_str(&yaml).unwrap();
+    assert_eq!(header.original.capacity.map(|c| c.0), Some(7));
+}
+
+#[test]
+fn test_header_version_fields() {
+    assert!(raw::HeaderVersion::Original.uses_field("title"));
+    assert!(!raw::HeaderVersion::Original.uses_field("sha1_hmac_banner"));
+    assert!(raw::HeaderVersion::DsPostDsi.uses_field("sha1_hmac_banner"));
+
+    let mut raw_header: raw::Header = bytemuck::Zeroable::zeroed();
+    raw_header.gamecode = AsciiArray(*b"ABCE");
+    raw_header.sha1_hmac_banner = [0xaa; 0x14];
+    raw_header.rsa_sha1 = [0xbb; 0x80];
+
+    // Original headers don't have the post-DSi fields, so they are dropped when loading and zeroed when building
+    let header = Header::load_raw(&raw_header);
+    assert!(header.version() == raw::HeaderVersion::Original);
+    assert!(header.ds_post_dsi.is_none());
+    let mut original = raw_header;
+    original.clear_unused_fields(header.version());
+    assert_eq!(original.sha1_hmac_banner, [0; 0x14]);
+    assert_eq!(original.rsa_sha1, [0; 0x80]);
+    assert_eq!(original.gamecode.0, raw_header.gamecode.0);
+
+    raw_header.dsi_flags_2 = raw::DsiFlags2::from_bits(0x1);
+    let header = Header::load_raw(&raw_header);
+    assert!(header.version() == raw::HeaderVersion::DsPostDsi);
+    assert_eq!(header.ds_post_dsi.as_ref().unwrap().sha1_hmac_banner, [0xaa; 0x14]);
+    let mut post_dsi = raw_header;
+    post_dsi.clear_unused_fields(header.version());
+    assert!(bytemuck::bytes_of(&post_dsi) == bytemuck::bytes_of(&raw_header));
+}
+
+#[test]
+fn test_header_same_game() {
+    let mut header: raw::Header = bytemuck::Zeroable::zeroed();
+    header.gamecode = AsciiArray(*b"ABCE");
+    header.update_crcs();
+
+    let mut other = header;
+    other.arm9.size = 0x1234;
+    other.update_crcs();
+    assert!(header.same_game(&other));
+
+    other.header_crc ^= 1;
+    assert!(!header.same_game(&other));
+
+    other.gamecode = AsciiArray(*b"ABCJ");
+    other.update_crcs();
+    assert!(!header.same_game(&other));
+}
+
+#[test]
+fn test_header_ascii_field_errors() {
+    let mut raw_header: raw::Header = bytemuck::Zeroable::zeroed();
+    raw_header.gamecode = AsciiArray(*b"ABCE");
+    raw_header.makercode = AsciiArray(*b"01");
+    let yaml = serde_yml::to_string(&Header::load_raw(&raw_header)).unwrap();
+
+    for (field, from, to) in
+        [("gamecode", "gamecode: ABCE", "gamecode: ABCDE"), ("makercode", "makercode: '01'", "makercode: 0é")]
+    {
+        assert!(yaml.contains(from), "{yaml}");
+        let Err(error) = serde_yml::from_str::<Header>(&yaml.replace(from, to)) else { panic!("expected an error") };
+        let message = error.to_string();
+        assert!(message.contains(&format!("invalid header {field}:")), "{message}");
+    }
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::{
+    crypto::blowfish::BlowfishKey,
+    rom::{
+        raw::{DsFlags, DsiFlags2, TableOffset},
+        BuildContext, Header, HeaderBuildError, HeaderDsPostDsi, HeaderOverrideError, Rom, RomLoadOptions, RomSaveError,
+    },
+    str::AsciiArray,
+};
+
+use crate::common::{assert_crcs, generate_project};
+
+#[test]
+fn test_header_consistency() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-header-consistency");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    assert_eq!(rom.header().check_consistency(&rom), []);
+
+    let fields = |header: &Header| -> Vec<&'static str> {
+        header.check_consistency(&rom).into_iter().map(|finding| finding.field).collect()
+    };
+
+    let mut header = rom.header().clone();
+    header.original.unitcode = 2;
+    assert_eq!(fields(&header), ["unitcode", "unitcode"]);
+    header.original.unitcode = 7;
+    assert_eq!(fields(&header), ["unitcode"]);
+
+    let mut header = rom.header().clone();
+    header.original.ds_flags = DsFlags::new().with_korea_region(true);
+    assert_eq!(fields(&header), ["ds_flags"]);
+    header.original.ds_flags = DsFlags::from_bits(0x04);
+    assert_eq!(fields(&header), ["ds_flags"]);
+
+    let mut header = rom.header().clone();
+    header.ds_post_dsi = Some(HeaderDsPostDsi {
+        dsi_flags_2: DsiFlags2::new(),
+        sha1_hmac_banner: [0; 0x14],
+        sha1_hmac_unk1: [0; 0x14],
+        sha1_hmac_unk2: [0; 0x14],
+        rsa_sha1: Box::new([0; 0x40]),
+    });
+    assert_eq!(fields(&header), ["dsi_flags_2", "rsa_sha1"]);
+    let ds_post_dsi = header.ds_post_dsi.as_mut().unwrap();
+    ds_post_dsi.dsi_flags_2 = DsiFlags2::new().with_tsc_dsi_mode(true).with_has_icon_sha1(true).with_has_header_rsa(true);
+    ds_post_dsi.rsa_sha1 = Box::new([0; 0x80]);
+    assert_eq!(fields(&header), ["dsi_flags_2", "sha1_hmac_banner", "rsa_sha1", "sha1_hmac_unk1"]);
+    let ds_post_dsi = header.ds_post_dsi.as_mut().unwrap();
+    ds_post_dsi.sha1_hmac_banner = [0xaa; 0x14];
+    ds_post_dsi.sha1_hmac_unk1 = [0xbb; 0x14];
+    ds_post_dsi.rsa_sha1 = Box::new([0xcc; 0x80]);
+    header.original.unitcode = 2;
+    let findings = header.check_consistency(&rom);
+    assert_eq!(findings.len(), 1);
+    assert!(findings[0].to_string().starts_with("header unitcode: unit code 2 declares a DSi program"), "{}", findings[0]);
+
+    // Inconsistencies are warnings by default, and errors in strict mode
+    let mut header = rom.header().clone();
+    header.original.unitcode = 2;
+    fs::write(root.join("header.yaml"), serde_yml::to_string(&header)?)?;
+    Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let strict = RomLoadOptions { strict_header: true, ..Default::default() };
+    let error = Rom::load(root.join("config.yaml"), strict)?.build(None).err().unwrap();
+    assert!(error.to_string().contains("header unitcode: "), "{error}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_encryption_without_gamecode() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-encryption-without-gamecode");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let bios_path = root.join("bios7.bin");
+    fs::write(&bios_path, (0..0x30 + 0x1048).map(|i| i as u8).collect::<Vec<_>>())?;
+    let key = BlowfishKey::from_arm7_bios_path(&bios_path)?;
+    let arm9_config_path = root.join("arm9/arm9.yaml");
+    fs::write(&arm9_config_path, fs::read_to_string(&arm9_config_path)?.replace("encrypted: false", "encrypted: true"))?;
+    let options = || RomLoadOptions { key: Some(&key), ..Default::default() };
+
+    let header_path = root.join("header.yaml");
+    let mut header: Header = serde_yml::from_str(&fs::read_to_string(&header_path)?)?;
+    header.original.gamecode = AsciiArray([0; 4]);
+    fs::write(&header_path, serde_yml::to_string(&header)?)?;
+    let error = Rom::load(root.join("config.yaml"), options()).err().unwrap();
+    assert!(matches!(error, RomSaveError::EncryptionGamecodeMissing { .. }), "{error}");
+    let rom = Rom::load(root.join("config.yaml"), RomLoadOptions { encrypt: false, ..options() })?;
+
+    // A partial build context is an error instead of a panic
+    let error = rom.header().build(&BuildContext::default(), &rom).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "cannot build header: the ROM size is missing in the build context, build the whole ROM to compute it"
+    );
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_no_secure_area() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-no-secure-area");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let bios_path = root.join("bios7.bin");
+    fs::write(&bios_path, (0..0x30 + 0x1048).map(|i| i as u8).collect::<Vec<_>>())?;
+    let key = BlowfishKey::from_arm7_bios_path(&bios_path)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    // The ARM9 program is too small to have a secure area, so no key is needed to extract it
+    let extract_path = root.join("extract");
+    Rom::extract(&original)?.save(&extract_path, None)?;
+    let arm9_config_path = extract_path.join("arm9/arm9.yaml");
+    let arm9_config = fs::read_to_string(&arm9_config_path)?;
+    assert!(arm9_config.contains("no_secure_area: true"));
+
+    // Nothing is encrypted even if the config says so
+    fs::write(&arm9_config_path, arm9_config.replace("encrypted: false", "encrypted: true"))?;
+    let options = RomLoadOptions { key: Some(&key), ..Default::default() };
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), options)?.build(None)?;
+    assert_eq!(rebuilt.data(), original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_header_build_missing_context() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-header-build-missing-context");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let full_context = || BuildContext {
+        header_offset: Some(0),
+        arm9_offset: Some(0x4000),
+        arm7_offset: Some(0x8000),
+        fnt_offset: Some(TableOffset { offset: 0x9000, size: 0x20 }),
+        fat_offset: Some(TableOffset { offset: 0x9200, size: 0x20 }),
+        banner_offset: Some(TableOffset { offset: 0x9400, size: 0x840 }),
+        arm9_autoload_callback: Some(0x2000a00),
+        arm7_autoload_callback: Some(0),
+        rom_size: Some(0xa000),
+        ..Default::default()
+    };
+    rom.header().build(&full_context(), &rom)?;
+
+    let cases: [(&str, fn(&mut BuildContext)); 8] = [
+        ("ROM size", |context| context.rom_size = None),
+        ("ARM9 offset", |context| context.arm9_offset = None),
+        ("ARM7 offset", |context| context.arm7_offset = None),
+        ("FNT offset", |context| context.fnt_offset = None),
+        ("FAT offset", |context| context.fat_offset = None),
+        ("banner offset", |context| context.banner_offset = None),
+        ("ARM9 autoload callback", |context| context.arm9_autoload_callback = None),
+        ("ARM7 autoload callback", |context| context.arm7_autoload_callback = None),
+    ];
+    for (field, remove) in cases {
+        let mut context = full_context();
+        remove(&mut context);
+        let error = rom.header().build(&context, &rom).err().unwrap();
+        assert!(matches!(error, HeaderBuildError::MissingContext { field: missing } if missing == field), "{error}");
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_header_overrides() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-header-overrides");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let overrides = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>();
+
+    rom.header_mut().apply_overrides(&overrides(&[("secure_area_delay", "0x051e"), ("autostart", "4")]))?;
+    let build = rom.build(None)?;
+    let header = build.header()?;
+    assert_eq!(header.secure_area_delay.0, 0x51e);
+    assert_eq!(header.autostart, 4);
+    assert_crcs(&build)?;
+
+    let mut header = Rom::load(root.join("config.yaml"), Default::default())?.header().clone();
+    let error = header.apply_overrides(&overrides(&[("autostart", "1"), ("title", "HACK")])).err().unwrap();
+    assert!(matches!(&error, HeaderOverrideError::UnknownField { field } if field == "title"), "{error}");
+    assert!(error.to_string().contains("expected one of: unitcode, seed_select"), "{error}");
+    // The header is unchanged if any override fails
+    assert_eq!(header.original.autostart, 0);
+
+    let error = header.apply_overrides(&overrides(&[("autostart", "0x100")])).err().unwrap();
+    assert_eq!(error.to_string(), "invalid value '0x100' for header field 'autostart': doesn't fit in 8 bits");
+    let error = header.apply_overrides(&overrides(&[("rom_nand_end", "ten")])).err().unwrap();
+    assert!(matches!(error, HeaderOverrideError::InvalidValue { .. }), "{error}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
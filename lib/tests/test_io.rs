@@ -0,0 +1,105 @@
+mod common;
+
+use std::{
+    fs,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+};
+
+use anyhow::Result;
+use ds_rom::{
+    io::{atomic_temp_path, write_file_atomic, write_file_atomic_with},
+    rom::Rom,
+};
+
+use crate::common::generate_project;
+
+/// Writer which fails once `limit` bytes have been written to it, like a disk running out of space.
+struct FailingWriter<W> {
+    writer: W,
+    limit: u64,
+}
+
+impl<W: Write> Write for FailingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.limit < buf.len() as u64 {
+            return Err(io::Error::new(io::ErrorKind::StorageFull, "no space left"));
+        }
+        self.limit -= buf.len() as u64;
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: Seek> Seek for FailingWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.writer.seek(pos)
+    }
+}
+
+#[test]
+fn test_write_file_atomic() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-write-file-atomic");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(&root)?;
+    let path = root.join("out.nds");
+
+    write_file_atomic(&path, |writer| writer.write_all(&[1, 2, 3]))?;
+    assert_eq!(fs::read(&path)?, [1, 2, 3]);
+
+    // Failing mid-write keeps the previous file and removes the temporary file
+    let result = write_file_atomic(&path, |writer| {
+        writer.write_all(&[4, 5])?;
+        Err(io::Error::other("interrupted"))
+    });
+    assert!(result.unwrap_err().to_string().contains("interrupted"));
+    assert_eq!(fs::read(&path)?, [1, 2, 3]);
+    assert!(!atomic_temp_path(&path).exists());
+    assert_eq!(fs::read_dir(&root)?.count(), 1);
+
+    // A failed first write leaves nothing behind
+    let new_path = root.join("new.nds");
+    assert!(write_file_atomic(&new_path, |_| Err(io::Error::other("out of memory"))).is_err());
+    assert!(!new_path.exists());
+    assert_eq!(fs::read_dir(&root)?.count(), 1);
+
+    write_file_atomic(&path, |writer| writer.write_all(&[6]))?;
+    assert_eq!(fs::read(&path)?, [6]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_build_rom_atomic_failing_writer() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-build-rom-atomic");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let project = root.join("project");
+    generate_project(&project)?;
+    let path = root.join("out.nds");
+    fs::write(&path, [1, 2, 3])?;
+
+    // Same as `dsrom build`, but the writer fails partway through the ROM
+    let rom = Rom::load(project.join("config.yaml"), Default::default())?;
+    let result = write_file_atomic_with(&path, |file| -> Result<_> {
+        let writer = FailingWriter { writer: file, limit: 0x8000 };
+        Ok(rom.build_to_writer(BufWriter::new(writer), None)?)
+    });
+    assert!(format!("{:?}", result.err().unwrap()).contains("no space left"));
+    assert_eq!(fs::read(&path)?, [1, 2, 3]);
+    assert!(!atomic_temp_path(&path).exists());
+
+    let rom = Rom::load(project.join("config.yaml"), Default::default())?;
+    let summary = write_file_atomic_with(&path, |file| -> Result<_> { Ok(rom.build_to_writer(BufWriter::new(file), None)?) })?;
+    assert_eq!(fs::metadata(&path)?.len(), summary.size);
+    assert!(!atomic_temp_path(&path).exists());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
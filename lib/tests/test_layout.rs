@@ -0,0 +1,305 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use ds_rom::rom::{
+    raw::{self, Capacity, FileAlloc},
+    LayoutHeader, LayoutHeaderFormat, MemoryLimits, Rom, RomBuildError, RomLayoutError, RomLoadOptions,
+};
+
+use crate::common::{generate_project, OVERLAYS};
+
+fn dev_options() -> RomLoadOptions<'static> {
+    RomLoadOptions { dev_padding: true, ..Default::default() }
+}
+
+#[test]
+fn test_dev_padding() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-dev-padding");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::write(root.join("files/data/big.bin"), vec![0x77; 0x20000])?;
+    fs::write(root.join("path_order.txt"), "/data/a.bin\n/data/b.bin\n/data/big.bin\n/c.bin\n")?;
+    let header_path = root.join("header.yaml");
+    let mut header: serde_yml::Value = serde_yml::from_str(&fs::read_to_string(&header_path)?)?;
+    header["capacity"] = 3.into();
+    fs::write(&header_path, serde_yml::to_string(&header)?)?;
+
+    let default_build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let mut dev_rom = Rom::load(root.join("config.yaml"), dev_options())?;
+    let layout = dev_rom.plan_layout()?;
+    let dev_build = dev_rom.build(None)?;
+
+    // The default build is padded to a power of two, the dev build only to the next 0x200 bytes
+    let rom_size = default_build.header()?.rom_size_ds;
+    assert_eq!(dev_build.header()?.rom_size_ds, rom_size);
+    assert_eq!(default_build.data().len(), rom_size.next_power_of_two() as usize);
+    assert_eq!(dev_build.data().len(), rom_size.next_multiple_of(0x200) as usize);
+    assert_eq!(layout.padded_size as usize, dev_build.data().len());
+    // The planned FNT size is computed without building the FNT
+    let file_names = dev_build.header()?.file_names;
+    assert_eq!((layout.fnt.offset, layout.fnt.size), (file_names.offset, file_names.size));
+    assert!(default_build.data().starts_with(dev_build.data()));
+
+    // The capacity is kept even though the ROM doesn't fit the smaller capacity
+    assert!(default_build.header()?.capacity == Capacity(3));
+    assert!(dev_build.header()?.capacity == Capacity(3));
+    fs::write(&header_path, fs::read_to_string(&header_path)?.replace("capacity: 3", "capacity: 0"))?;
+    let default_build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let dev_build = Rom::load(root.join("config.yaml"), dev_options())?.build(None)?;
+    assert!(default_build.header()?.capacity == Capacity::from_size(rom_size));
+    assert!(dev_build.header()?.capacity == Capacity(0));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_file_regions() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-file-regions");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    // Pushes c.bin past the first 0x80000 bytes
+    fs::write(root.join("files/data/big.bin"), vec![0x77; 0x80000])?;
+    fs::write(root.join("path_order.txt"), "/data/a.bin\n/data/b.bin\n/data/big.bin\n/c.bin\n")?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rom.file_regions()?, None);
+
+    rom.header_mut()?.ds_rom_region_end = 1;
+    let regions = rom.file_regions()?.unwrap();
+    // Overlay, a.bin, b.bin and big.bin are in the DS region
+    assert_eq!(regions, raw::FileRegions { ds_region_end: 0x80000, ds_files: 4, dsi_files: 1 });
+    assert_eq!(regions.to_string(), "4 files in the DS region and 1 files in the DSi region starting at 0x80000");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_layout_header() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-layout-header");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let rom = Rom::load(root.join("config.yaml"), RomLoadOptions { compress: false, ..Default::default() })?;
+    let header = LayoutHeader::new(&rom)?;
+
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    for (format, file_name) in
+        [(LayoutHeaderFormat::C, "layout.h"), (LayoutHeaderFormat::Asm, "layout.s"), (LayoutHeaderFormat::Ld, "layout.ld")]
+    {
+        let expected = fs::read_to_string(golden.join(file_name))?;
+        assert_eq!(header.display(format).to_string(), expected, "{file_name}");
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_planned_layout_matches_build() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-planned-layout");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let layout = rom.plan_layout()?;
+    assert!(!layout.is_estimated());
+    let raw_rom = rom.build(None)?;
+
+    // The planned layout must match the built ROM
+    let header = raw_rom.header()?;
+    assert_eq!(layout.arm9.offset, header.arm9.offset);
+    assert_eq!(layout.arm7.offset, header.arm7.offset);
+    assert_eq!(layout.fnt.offset, header.file_names.offset);
+    assert_eq!(layout.fat.offset, header.file_allocs.offset);
+    assert_eq!(layout.fat.size, header.file_allocs.size);
+    assert_eq!(
+        layout.arm9_overlay_table.map(|table| (table.offset, table.size)),
+        Some((header.arm9_overlays.offset, header.arm9_overlays.size))
+    );
+    assert_eq!(layout.banner.map_or(0, |banner| banner.offset), header.banner_offset);
+    assert_eq!(layout.rom_size, header.rom_size_ds);
+    assert_eq!(layout.padded_size as usize, raw_rom.data().len());
+    let fat = raw_rom.fat()?;
+    assert_eq!(layout.file_allocs.len(), fat.len());
+    for (planned, built) in layout.file_allocs.iter().zip(fat) {
+        assert_eq!((planned.start, planned.end), (built.start, built.end));
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_layout_fat_size_mismatch() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-layout-fat-size");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let mut layout = rom.plan_layout()?;
+    layout.validate()?;
+    assert_eq!(layout.fat.size as usize, layout.file_allocs.len() * size_of::<FileAlloc>());
+
+    // A FAT entry added after placing the FAT would overrun the banner
+    layout.file_allocs.push(FileAlloc::default());
+    let error = layout.validate().unwrap_err();
+    assert!(matches!(error, RomLayoutError::FatSizeMismatch { .. }));
+    assert!(error.to_string().contains(&format!("{} entries", layout.file_allocs.len())));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_layout_section_placement() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-layout-section-placement");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let layout = rom.plan_layout()?;
+    layout.validate()?;
+
+    let mut overlapping = rom.plan_layout()?;
+    overlapping.fat.offset = overlapping.fnt.end() - 4;
+    let error = overlapping.validate().unwrap_err();
+    assert!(
+        matches!(&error, RomLayoutError::SectionOverlap { section, previous, .. } if section == "FAT" && previous == "FNT")
+    );
+
+    let mut out_of_order = rom.plan_layout()?;
+    out_of_order.arm7.offset = out_of_order.arm9.offset;
+    let error = out_of_order.validate().unwrap_err();
+    assert!(matches!(&error, RomLayoutError::SectionOrder { section, .. } if section == "ARM7 program"));
+
+    let mut unaligned = rom.plan_layout()?;
+    unaligned.arm7.offset += 4;
+    let error = unaligned.validate().unwrap_err();
+    assert!(matches!(&error, RomLayoutError::UnalignedSection { section, alignment: 0x200, .. } if section == "ARM7 program"));
+
+    // Unlike the ARM7 program, the FNT can be moved to any offset after the preceding section
+    let mut moved_fnt = rom.plan_layout()?;
+    moved_fnt.fnt.offset += 4;
+    moved_fnt.validate()?;
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_memory_limits() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-memory-limits");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    // Overlay 1 starts past the end of main RAM for the ARM9, and overlay 2 is placed in DTCM
+    let overlays = format!(
+        "{OVERLAYS}\
+- {{ id: 1, base_address: 0x23f0000, code_size: 0x20, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: 1, compressed: false, file_name: ov001.bin }}
+- {{ id: 2, base_address: 0x27e0100, code_size: 0x20, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: 2, compressed: false, file_name: ov002.bin }}
+"
+    );
+    fs::write(root.join("arm9_overlays/overlays.yaml"), overlays)?;
+    fs::write(root.join("arm9_overlays/ov001.bin"), [0x55; 0x20])?;
+    fs::write(root.join("arm9_overlays/ov002.bin"), [0x66; 0x20])?;
+
+    let rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let check = |rom: &Rom| {
+        MemoryLimits::retail()
+            .check(rom)
+            .into_iter()
+            .map(|finding| (finding.name, finding.end_address, finding.limit))
+            .collect::<Vec<_>>()
+    };
+    let expected = [("ARM9 overlay 1".to_string(), 0x23f0020, 0x23e0000)];
+    assert_eq!(check(&rom), expected);
+    assert_eq!(check(&Rom::extract(&rom.build(None)?)?), expected);
+    assert!(MemoryLimits::debug().check(&Rom::load(root.join("config.yaml"), Default::default())?).is_empty());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Returns the end of the programs and overlays, where the FNT may start at the earliest.
+fn programs_end(rom: &mut Rom) -> Result<u32> {
+    let layout = rom.plan_layout()?;
+    let sections = [layout.arm9, layout.arm7].into_iter().chain(layout.arm9_overlay_table).chain(layout.arm9_overlays);
+    Ok(sections.map(|section| section.end()).max().unwrap())
+}
+
+#[test]
+fn test_unaligned_section_offsets_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-unaligned-section-offsets");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let project_path = root.join("project");
+    let extract_path = root.join("extract");
+    generate_project(&project_path)?;
+
+    // Place the FNT shortly after the programs and the FAT right after the FNT, instead of at the next aligned offsets
+    let mut rom = Rom::load(project_path.join("config.yaml"), Default::default())?;
+    let fnt_offset = programs_end(&mut rom)? + 0x14;
+    let fnt_size = rom.plan_layout()?.fnt.size;
+    let fat_offset = (fnt_offset + fnt_size).next_multiple_of(4);
+    assert!(fnt_offset % 0x200 != 0 && fat_offset % 0x200 != 0);
+    let config = fs::read_to_string(project_path.join("config.yaml"))?;
+    let section_offsets = format!("section_offsets: {{ fnt: {fnt_offset:#x}, fat: {fat_offset:#x} }}\n");
+    fs::write(project_path.join("config.yaml"), config + &section_offsets)?;
+
+    let original = Rom::load(project_path.join("config.yaml"), Default::default())?.build(None)?;
+    let header = original.header()?;
+    assert_eq!((header.file_names.offset, header.file_allocs.offset), (fnt_offset, fat_offset));
+
+    let extracted = Rom::extract(&original)?;
+    let offsets = extracted.config().section_offsets.unwrap();
+    assert_eq!((offsets.fnt, offsets.fat, offsets.banner), (Some(fnt_offset), Some(fat_offset), None));
+    extracted.save(&extract_path, None)?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert!(rebuilt.data() == original.data(), "rebuilt ROM differs from the original");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_section_offset_exceeded() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-section-offset-exceeded");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let programs_end = programs_end(&mut Rom::load(root.join("config.yaml"), Default::default())?)?;
+
+    // The FNT may start exactly where the programs end, but not before that
+    let config = fs::read_to_string(root.join("config.yaml"))?;
+    fs::write(root.join("config.yaml"), format!("{config}section_offsets: {{ fnt: {programs_end:#x} }}\n"))?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(build.header()?.file_names.offset, programs_end);
+
+    let fnt_offset = programs_end - 4;
+    fs::write(root.join("config.yaml"), format!("{config}section_offsets: {{ fnt: {fnt_offset:#x} }}\n"))?;
+    let Err(error) = Rom::load(root.join("config.yaml"), Default::default())?.build(None) else {
+        panic!("expected an error for an FNT offset inside the programs");
+    };
+    assert!(
+        matches!(error, RomBuildError::SectionOffsetExceeded { section: "FNT", offset, position, .. }
+            if offset == fnt_offset && position == programs_end as u64),
+        "{error}"
+    );
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
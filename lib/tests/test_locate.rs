@@ -0,0 +1,112 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::rom::{
+    raw::{AutoloadKind, Located, VaddrLocation, VaddrModule},
+    Rom,
+};
+
+use crate::common::{generate_project, OVERLAYS};
+
+#[test]
+fn test_locate() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-locate");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let rom = Rom::extract(&build)?;
+    let header = build.header()?;
+    let fat = build.fat()?;
+
+    assert_eq!(build.locate(0x10)?, Located::Section { name: "Header".to_string(), offset: 0x10 });
+    let arm9_offset = header.arm9.offset;
+    assert_eq!(build.locate(arm9_offset + 0x24)?, Located::Section { name: "ARM9 program".to_string(), offset: 0x24 });
+    let banner = build.locate(header.banner_offset)?;
+    assert_eq!(banner.to_string(), "Banner (+0x0)");
+
+    let c_id = rom.files().find_path("c.bin").unwrap();
+    let c = build.locate(fat[c_id as usize].start + 2)?;
+    assert_eq!(c, Located::File { id: c_id, path: Some("c.bin".to_string()), overlay: None, offset: 2 });
+    assert_eq!(c.to_string(), format!("file c.bin (+0x2), FAT id {c_id:#x}"));
+    let overlay = build.locate(fat[0].start)?;
+    assert_eq!(overlay.to_string(), "ARM9 overlay 0 (+0x0), FAT id 0x0");
+
+    // Between the end of the header and the ARM9 program
+    let header_end = header.version().used_size() as u32;
+    let gap = build.locate(header_end + 0x10)?;
+    assert_eq!(gap, Located::Gap {
+        previous: Some("Header".to_string()),
+        next: Some("ARM9 program".to_string()),
+        offset: 0x10
+    });
+    assert_eq!(gap.to_string(), "padding after Header (+0x10), before ARM9 program");
+
+    let size = build.data().len() as u32;
+    assert_eq!(build.locate(size)?, Located::PastEnd { size });
+    assert_eq!(build.locate(size + 0x100)?.to_string(), format!("past the end of the ROM, which is {size:#x} bytes"));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_vaddr_locate() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-vaddr-locate");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let arm9_offset = build.header()?.arm9.offset;
+    let overlay_start = build.fat()?[0].start;
+
+    let main = build.vaddr_to_rom_offset(0x2000905)?;
+    assert_eq!(main, [VaddrLocation {
+        address: 0x2000905,
+        module: VaddrModule::Arm9Main,
+        offset: 0x905,
+        rom_offset: Some(arm9_offset + 0x905),
+        bss: false,
+        compressed: false,
+    }]);
+    assert_eq!(build.data()[arm9_offset as usize + 0x905], 0x05);
+    assert_eq!(build.rom_offset_to_vaddr(arm9_offset + 0x905)?, Some(main[0]));
+
+    let itcm = build.vaddr_to_rom_offset(0x1ff8004)?;
+    assert_eq!(itcm.len(), 1);
+    assert_eq!(itcm[0].module, VaddrModule::Autoload { index: 0, kind: AutoloadKind::Itcm });
+    let itcm_offset = itcm[0].rom_offset.unwrap();
+    assert_eq!(build.data()[itcm_offset as usize], 0x11);
+    assert_eq!(build.rom_offset_to_vaddr(itcm_offset)?.map(|location| location.address), Some(0x1ff8004));
+
+    let dtcm_bss = build.vaddr_to_rom_offset(0x27e0014)?;
+    assert_eq!(dtcm_bss.len(), 1);
+    assert!(dtcm_bss[0].bss);
+    assert_eq!(dtcm_bss[0].rom_offset, None);
+    assert_eq!(dtcm_bss[0].to_string(), "0x27e0014: DTCM autoload (+0x14), in .bss with no stored bytes");
+
+    let overlay = build.vaddr_to_rom_offset(0x2100010)?;
+    assert_eq!(overlay.len(), 1);
+    assert_eq!(overlay[0].module, VaddrModule::Overlay { id: 0, file_id: 0 });
+    assert_eq!(overlay[0].rom_offset, Some(overlay_start + 0x10));
+    assert_eq!(overlay[0].to_string(), format!("0x2100010: ARM9 overlay 0 (+0x10), ROM offset {:#x}", overlay_start + 0x10));
+    assert_eq!(build.rom_offset_to_vaddr(overlay_start + 0x10)?, Some(overlay[0]));
+
+    assert_eq!(build.vaddr_to_rom_offset(0x2100020)?, []);
+    assert_eq!(build.rom_offset_to_vaddr(0x10)?, None);
+
+    // Compressed overlays have no stored bytes for their compressed part
+    fs::write(root.join("arm9_overlays/overlays.yaml"), OVERLAYS.replace("compressed: false", "compressed: true"))?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let overlay = build.vaddr_to_rom_offset(0x2100010)?;
+    assert!(overlay[0].compressed);
+    assert_eq!(overlay[0].rom_offset, None);
+    assert_eq!(overlay[0].to_string(), "0x2100010: ARM9 overlay 0 (+0x10), in the compressed part of the module");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
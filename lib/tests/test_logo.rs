@@ -0,0 +1,37 @@
+use anyhow::Result;
+use ds_rom::rom::Logo;
+
+fn assert_round_trip(logo: &Logo) -> Result<()> {
+    let compressed = logo.compress();
+    let decompressed = Logo::decompress(&compressed)?;
+    assert!(decompressed == *logo, "expected:\n{logo}\ngot:\n{decompressed}");
+    assert!(decompressed.compress() == compressed);
+    Ok(())
+}
+
+#[test]
+fn test_logo_bitmap() {
+    let mut bitmap = [0u8; Logo::BITMAP_SIZE];
+    bitmap[0] = 0b1000_0001;
+    bitmap[Logo::WIDTH / 8] = 0b0100_0000;
+    let logo = Logo::from_bitmap(&bitmap);
+    assert!(logo.get_pixel(0, 0));
+    assert!(logo.get_pixel(7, 0));
+    assert!(!logo.get_pixel(1, 0));
+    assert!(logo.get_pixel(1, 1));
+    assert_eq!(logo.bitmap(), &bitmap);
+
+    let from_fn = Logo::from_fn(|x, y| (x == 0 || x == 7) && y == 0 || (x, y) == (1, 1));
+    assert_eq!(from_fn.bitmap(), &bitmap);
+}
+
+#[test]
+fn test_logo_compress_round_trip() -> Result<()> {
+    assert_eq!(Logo::BITMAP_SIZE, Logo::WIDTH * Logo::HEIGHT / 8);
+    assert_round_trip(&Logo::from_fn(|_, _| false))?;
+    assert_round_trip(&Logo::from_fn(|_, _| true))?;
+    assert_round_trip(&Logo::from_fn(|x, y| (x + y) % 2 == 0))?;
+    assert_round_trip(&Logo::from_fn(|x, y| (x / 8 + y / 8) % 2 == 0))?;
+    assert_round_trip(&Logo::from_fn(|x, _| x < Logo::WIDTH / 2))?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use ds_rom::{
+    crypto::{aes::Aes128, modcrypt::Modcrypt},
+    rom::raw::{ModcryptArea, TableOffset},
+};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_aes128() {
+    // Test cases from FIPS-197, appendices B and C.1
+    let key = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+    let mut block = [0x32, 0x43, 0xf6, 0xa8, 0x88, 0x5a, 0x30, 0x8d, 0x31, 0x31, 0x98, 0xa2, 0xe0, 0x37, 0x07, 0x34];
+    Aes128::new(&key).encrypt_block(&mut block);
+    assert_eq!(hex(&block), "3925841d02dc09fbdc118597196a0b32");
+
+    let key = std::array::from_fn(|i| i as u8);
+    let mut block = std::array::from_fn(|i| (i as u8) * 0x11);
+    Aes128::new(&key).encrypt_block(&mut block);
+    assert_eq!(hex(&block), "69c4e0d86a7b0430d8cdb78070b4c55a");
+}
+
+#[test]
+fn test_modcrypt_keystream() {
+    let key = Modcrypt::retail_key(b"ATSE", &[0x77; 0x14]);
+    let modcrypt = Modcrypt::new(&key, &[0x44; 16]);
+    let plain = (0..0x45).map(|i| i as u8).collect::<Vec<_>>();
+
+    let mut encrypted = plain.clone();
+    modcrypt.apply(&mut encrypted, 0);
+    assert_ne!(encrypted, plain);
+
+    // Starting partway through the area continues the same keystream, even within a block
+    for offset in [1, 0x10, 0x17, 0x44] {
+        let mut part = plain[offset..].to_vec();
+        modcrypt.apply(&mut part, offset);
+        assert_eq!(part, encrypted[offset..]);
+    }
+
+    let mut decrypted = encrypted.clone();
+    modcrypt.apply(&mut decrypted, 0);
+    assert_eq!(decrypted, plain);
+
+    // The counter is a little-endian number, so the first byte carries into the second
+    let mut counter = [0u8; 16];
+    counter[0] = 0xff;
+    let mut next_counter = [0u8; 16];
+    next_counter[1] = 0x01;
+    let mut stream = [0u8; 0x20];
+    Modcrypt::new(&key, &counter).apply(&mut stream, 0);
+    let mut next_stream = [0u8; 0x10];
+    Modcrypt::new(&key, &next_counter).apply(&mut next_stream, 0);
+    assert_eq!(stream[0x10..], next_stream);
+}
+
+#[test]
+fn test_modcrypt_scramble_key() {
+    // Key X and Y cancel out, leaving the scrambler constant rotated left by 42 bits
+    let key = Modcrypt::scramble_key(&[0x5a; 16], &[0x5a; 16]);
+    assert_eq!(u128::from_le_bytes(key), 0xfffefb4e295902582a680f5f1a4f3e79u128.rotate_left(42));
+
+    let key_x = *b"NintendoATSEESTA";
+    assert_eq!(Modcrypt::retail_key(b"ATSE", &[0x77; 0x14]), Modcrypt::scramble_key(&key_x, &[0x77; 16]));
+}
+
+#[test]
+fn test_modcrypt_area() {
+    let modcrypt = Modcrypt::new(&[0x12; 16], &[0x34; 16]);
+    let area = ModcryptArea { area: TableOffset { offset: 0x1010, size: 0x20 }, modcrypt: modcrypt.clone() };
+    assert!(!area.overlaps(0x1000, 0x10));
+    assert!(area.overlaps(0x1000, 0x11));
+    assert!(area.overlaps(0x102f, 1));
+    assert!(!area.overlaps(0x1030, 0x10));
+
+    // Only the part inside the area is changed
+    let mut data = vec![0u8; 0x40];
+    area.apply(&mut data, 0x1008);
+    let mut expected = vec![0u8; 0x20];
+    modcrypt.apply(&mut expected, 0);
+    assert_eq!(data[..8], [0; 8]);
+    assert_eq!(data[8..0x28], expected);
+    assert_eq!(data[0x28..], [0; 0x18]);
+
+    let mut data = vec![0u8; 8];
+    area.apply(&mut data, 0x1018);
+    assert_eq!(data, expected[8..0x10]);
+}
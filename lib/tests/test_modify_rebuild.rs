@@ -0,0 +1,344 @@
+mod common;
+
+use std::{
+    fs,
+    io::{BufWriter, Cursor},
+    path::Path,
+};
+
+use anyhow::Result;
+use ds_rom::{
+    io::{build_fingerprint_path, is_previous_build, write_build_fingerprint},
+    rom::{
+        raw::{self, Language, Located},
+        Rom, RomSaveOptions,
+    },
+};
+
+use crate::common::{assert_crcs, assert_fat_consistent, generate_project, CONFIG};
+
+#[test]
+fn test_modify_and_rebuild() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-modify-rebuild");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+
+    // First build, then extract it like a retail ROM
+    let first_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_crcs(&first_build)?;
+    let first = Rom::extract(&first_build)?;
+    assert_fat_consistent(&first_build, first.files())?;
+    // path_order.txt placed the subdirectory before c.bin, which has a lower file ID
+    let ordering = first.files().analyze_ordering();
+    assert!(ordering.is_fnt_sorted() && ordering.file_image_differs);
+    first.save(&extract_path, None)?;
+    let a_id = first.files().find_path("data/a.bin").unwrap();
+    let first_ids = [a_id, first.files().find_path("data/b.bin").unwrap(), first.files().find_path("c.bin").unwrap()];
+
+    // Replace a file with larger contents, add a new file and edit the English title
+    let new_contents = vec![0x5a; 0x123];
+    fs::write(extract_path.join("files/data/a.bin"), &new_contents)?;
+    fs::write(extract_path.join("files/data/new.bin"), [0x66; 0x40])?;
+    let banner_path = extract_path.join("banner/banner.yaml");
+    let mut banner: serde_yml::Value = serde_yml::from_str(&fs::read_to_string(&banner_path)?)?;
+    banner["title"]["english"] = "Modified title".into();
+    fs::write(&banner_path, serde_yml::to_string(&banner)?)?;
+
+    let second_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_crcs(&second_build)?;
+    let second = Rom::extract(&second_build)?;
+    assert_fat_consistent(&second_build, second.files())?;
+
+    // The replaced file keeps its path and ID, and the new file gets an unused ID
+    assert_eq!(second.files().find_path("data/a.bin"), Some(a_id));
+    assert_eq!(second.files().file(a_id).contents(), new_contents);
+    let new_id = second.files().find_path("data/new.bin").unwrap();
+    assert!(!first_ids.contains(&new_id));
+    assert!(new_id as usize >= second.files().num_reserved());
+    assert_eq!(second.files().file(new_id).contents(), [0x66; 0x40]);
+    for path in ["data/b.bin", "c.bin"] {
+        let (first_id, second_id) = (first.files().find_path(path).unwrap(), second.files().find_path(path).unwrap());
+        assert_eq!(first.files().file(first_id).contents(), second.files().file(second_id).contents());
+    }
+
+    let banner = second_build.banner()?.unwrap();
+    let title = banner.title(Language::English).unwrap();
+    assert!(title.to_string().starts_with("Modified title"), "{title}");
+
+    // Untouched sections are byte-identical to the first build
+    assert_eq!(first_build.arm9()?.full_data(), second_build.arm9()?.full_data());
+    assert_eq!(first_build.arm7()?.full_data(), second_build.arm7()?.full_data());
+    let (first_ovt, second_ovt) = (first_build.arm9_overlay_table()?, second_build.arm9_overlay_table()?);
+    assert_eq!(bytemuck::cast_slice::<_, u8>(first_ovt), bytemuck::cast_slice::<_, u8>(second_ovt));
+    let (first_fat, second_fat) = (first_build.fat()?, second_build.fat()?);
+    for id in 0..first.files().num_reserved() {
+        assert_eq!(first_fat[id].into_file(first_build.data()), second_fat[id].into_file(second_build.data()));
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_missing_file_error() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-missing-file");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::remove_file(root.join("arm9/itcm.bin"))?;
+
+    let error = Rom::load(root.join("config.yaml"), Default::default()).err().unwrap();
+    let message = error.to_string();
+    assert!(message.contains("itcm.bin"), "{message}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_modify_in_memory() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-modify-in-memory");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&original)?.into_owned();
+
+    rom.banner_mut().unwrap().title.set(Language::English, "Modified".to_string());
+    let id = rom.files().find_path("data/b.bin").unwrap();
+    rom.files_mut().set_file_contents(id, vec![0x5b; 0x100]);
+    let modified = rom.build(None)?;
+    assert_crcs(&modified)?;
+
+    let rom = Rom::extract(&modified)?;
+    assert_eq!(rom.banner().unwrap().title.get(Language::English), Some("Modified"));
+    assert_eq!(rom.banner().unwrap().title.get(Language::French), Some("Test"));
+    assert_eq!(rom.files().file(id).contents(), [0x5b; 0x100]);
+    assert_fat_consistent(&modified, rom.files())?;
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_build_to_writer() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-build-to-writer");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::write(root.join("trailing_data.bin"), [0x7e; 0x30])?;
+    fs::write(root.join("config.yaml"), format!("{CONFIG}trailing_data: trailing_data.bin\n"))?;
+
+    let in_memory = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    let path = root.join("streamed.nds");
+    let summary = Rom::load(root.join("config.yaml"), Default::default())?
+        .build_to_writer(BufWriter::new(fs::File::create(&path)?), None)?;
+    let streamed = fs::read(&path)?;
+    assert_eq!(streamed, in_memory.data());
+    assert_eq!(summary.size, streamed.len() as u64);
+    assert_eq!(summary.layout.padded_size + 0x30, summary.size as u32);
+    assert_eq!(summary.layout.fat.offset, in_memory.header()?.file_allocs.offset);
+
+    // The ROM is written relative to where the writer starts
+    let mut cursor = Cursor::new(vec![0xee; 0x10]);
+    cursor.set_position(0x10);
+    Rom::load(root.join("config.yaml"), Default::default())?.build_to_writer(&mut cursor, None)?;
+    assert_eq!(cursor.position(), 0x10 + summary.size);
+    assert_eq!(&cursor.get_ref()[0x10..], in_memory.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_partial_save() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-partial-save");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let rom = Rom::extract(&original)?;
+
+    // A directory in place of a file makes saving fail partway
+    let extract_path = root.join("extract");
+    let blocked_path = extract_path.join("files/data/b.bin");
+    fs::create_dir_all(&blocked_path)?;
+    let error = rom.save_with_summary(&extract_path, Default::default()).unwrap_err();
+    let summary = error.summary;
+    assert!(summary.written.contains(&extract_path.join("config.yaml")));
+    assert!(!summary.written.contains(&blocked_path));
+    assert!(!summary.written.contains(&extract_path.join("path_order.txt")));
+    let mut bytes_written = 0;
+    for path in &summary.written {
+        bytes_written += fs::metadata(path)?.len();
+    }
+    assert_eq!(summary.bytes_written, bytes_written);
+    assert!(summary.to_string().starts_with(&format!("{} files (", summary.written.len())));
+
+    // Saving again only writes what is missing
+    fs::remove_dir(&blocked_path)?;
+    let options = RomSaveOptions { skip_unchanged_files: true, ..Default::default() };
+    let resumed = rom.save_with_summary(&extract_path, options)?;
+    assert!(resumed.unchanged > 0);
+    assert!(!resumed.written.contains(&extract_path.join("config.yaml")));
+    assert!(resumed.written.contains(&blocked_path));
+    assert!(resumed.written.contains(&extract_path.join("path_order.txt")));
+
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.data(), original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_overwrite_original_rom() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-overwrite-original-rom");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let rom_path = root.join("out.nds");
+
+    // The original ROM is a build of the same game, but has no fingerprint, so it must not be overwritten
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    original.save(&rom_path)?;
+    let header = original.header()?;
+    assert!(header.same_game(header));
+    assert!(!is_previous_build(&rom_path)?);
+
+    write_build_fingerprint(&rom_path)?;
+    assert!(is_previous_build(&rom_path)?);
+    assert!(build_fingerprint_path(&rom_path).exists());
+
+    // Editing the output after it was built also prevents overwriting it
+    let mut bytes = fs::read(&rom_path)?;
+    *bytes.last_mut().unwrap() ^= 0xff;
+    fs::write(&rom_path, bytes)?;
+    assert!(!is_previous_build(&rom_path)?);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_update_header_crcs() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-update-header-crcs");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let secure_area_crc = rom.header()?.secure_area_crc;
+
+    // Edit the header in place, as one would with a hex editor
+    let header = rom.header_mut()?;
+    header.title = "EDITED".parse()?;
+    assert_ne!(header.header_crc, header.compute_header_crc());
+    header.update_crcs();
+
+    assert_crcs(&rom)?;
+    assert_eq!(rom.header()?.secure_area_crc, secure_area_crc);
+    let extracted = Rom::extract(&rom)?;
+    assert_eq!(extracted.header().original.title, "EDITED");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_trailing_data() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-trailing-data");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(build.trailing_data()?, None);
+    let official_size = build.data().len();
+    assert_eq!(build.official_end()?, official_size);
+
+    // Append a signature-like blob after the padded ROM
+    let signature = (0..0x88).map(|i| i as u8).collect::<Vec<_>>();
+    let mut data = build.data().to_vec();
+    data.extend_from_slice(&signature);
+    let signed = raw::Rom::new(data);
+    assert_eq!(signed.trailing_data()?, Some(signature.as_slice()));
+    let located = signed.locate(official_size as u32 + 4)?;
+    assert_eq!(located, Located::Section { name: "Trailing data".to_string(), offset: 4 });
+
+    // Padding after the ROM is not trailing data
+    let mut data = build.data().to_vec();
+    data.extend_from_slice(&[0xff; 0x200]);
+    assert_eq!(raw::Rom::new(data).trailing_data()?, None);
+
+    let rom = Rom::extract(&signed)?;
+    assert_eq!(rom.config().trailing_data.as_deref(), Some(Path::new("trailing_data.bin")));
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+    assert_eq!(fs::read(extract_path.join("trailing_data.bin"))?, signature);
+
+    let mut rom = Rom::load(extract_path.join("config.yaml"), Default::default())?;
+    let layout = rom.plan_layout()?;
+    let trailing_data = layout.trailing_data.unwrap();
+    assert_eq!((trailing_data.offset, trailing_data.size), (official_size as u32, signature.len() as u32));
+    let rebuilt = rom.build(None)?;
+    assert_eq!(rebuilt.data(), signed.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_pre_arm9_data() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-pre-arm9-data");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let start = build.header()?.version().used_size();
+    let end = build.header()?.arm9.offset as usize;
+
+    // Uniform padding between the header and ARM9 program is not extracted
+    assert_eq!(build.pre_arm9()?, None);
+    let rom = Rom::extract(&build)?;
+    assert!(rom.config().pre_arm9.is_none());
+    let padded_path = root.join("padded");
+    rom.save(&padded_path, None)?;
+    assert!(!padded_path.join("pre_arm9.bin").exists());
+    let rebuilt = Rom::load(padded_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.data(), build.data());
+
+    // Data in the unused part of the header, like a flashcart loader might leave
+    let pre_arm9 = (0..end - start).map(|i| (i % 0xfb) as u8).collect::<Vec<_>>();
+    let mut data = build.data().to_vec();
+    data[start..end].copy_from_slice(&pre_arm9);
+    let original = raw::Rom::new(data);
+    assert_eq!(original.pre_arm9()?, Some(pre_arm9.as_slice()));
+
+    let rom = Rom::extract(&original)?;
+    assert_eq!(rom.config().pre_arm9.as_deref(), Some(Path::new("pre_arm9.bin")));
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+    assert_eq!(fs::read(extract_path.join("pre_arm9.bin"))?, pre_arm9);
+
+    let mut rom = Rom::load(extract_path.join("config.yaml"), Default::default())?;
+    let layout = rom.plan_layout()?;
+    let section = layout.pre_arm9.unwrap();
+    assert_eq!((section.offset, section.end()), (start as u32, end as u32));
+    let rebuilt = rom.build(None)?;
+    assert_eq!(rebuilt.data(), original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
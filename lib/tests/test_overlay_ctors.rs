@@ -0,0 +1,86 @@
+use ds_rom::rom::{raw::OverlayFlags, CtorEntry, CtorLayout, Overlay, OverlayCtorError, OverlayInfo};
+
+const BASE_ADDRESS: u32 = 0x2100000;
+
+/// Creates an overlay with 0x10 bytes of code followed by a .ctor section with the given words.
+fn overlay_with_ctors(ctors: &[u32]) -> Overlay<'static> {
+    let mut data = vec![0xaa; 0x10];
+    data.extend(ctors.iter().flat_map(|word| word.to_le_bytes()));
+    let ctor_start = BASE_ADDRESS + 0x10;
+    let info = OverlayInfo {
+        id: 0,
+        base_address: BASE_ADDRESS,
+        code_size: data.len() as u32,
+        bss_size: 0,
+        ctor_start,
+        ctor_end: ctor_start + ctors.len() as u32 * 4,
+        file_id: 0,
+        compressed: false,
+        flags: OverlayFlags::new(),
+    };
+    Overlay::new(data, info, false)
+}
+
+#[test]
+fn test_ctor_terminated() -> Result<(), OverlayCtorError> {
+    let overlay = overlay_with_ctors(&[0x2100101, 0x2100200, 0, 0x2100301]);
+    assert_eq!(overlay.ctor_layout()?, CtorLayout::Terminated);
+    assert_eq!(overlay.ctor_functions(None)?, [CtorEntry { address: 0x2100100, thumb: true }, CtorEntry {
+        address: 0x2100200,
+        thumb: false
+    }]);
+
+    // Without a terminator, the list ends with the section
+    let overlay = overlay_with_ctors(&[0x2100200, 0x2100301]);
+    assert_eq!(overlay.ctor_functions(None)?.len(), 2);
+
+    let overlay = overlay_with_ctors(&[]);
+    assert!(overlay.ctor_functions(None)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_ctor_count_prefixed() -> Result<(), OverlayCtorError> {
+    let overlay = overlay_with_ctors(&[3, 0x2100200, 0x2100301, 0x2100401, 0]);
+    assert_eq!(overlay.ctor_layout()?, CtorLayout::CountPrefixed);
+    let ctors = overlay.ctor_functions(None)?;
+    assert_eq!(ctors.iter().map(|ctor| ctor.pointer()).collect::<Vec<_>>(), [0x2100200, 0x2100301, 0x2100401]);
+    assert_eq!(ctors.iter().map(|ctor| ctor.thumb).collect::<Vec<_>>(), [false, true, true]);
+
+    // Forcing the other layout reads the count as a pointer, which is why the layout is detected by default
+    assert_eq!(overlay.ctor_functions(Some(CtorLayout::Terminated))?.len(), 4);
+
+    // A count which doesn't fit is not a count
+    let overlay = overlay_with_ctors(&[3, 0x2100200]);
+    assert_eq!(overlay.ctor_layout()?, CtorLayout::Terminated);
+    assert!(matches!(
+        overlay.ctor_functions(Some(CtorLayout::CountPrefixed)),
+        Err(OverlayCtorError::CtorCountTooLarge { count: 3, available: 1, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_ctor_errors() {
+    let overlay = overlay_with_ctors(&[0x2100200]);
+    let info = OverlayInfo { compressed: true, ..overlay.info().clone() };
+    let overlay = Overlay::new(overlay.full_data().to_vec(), info, true);
+    assert!(matches!(overlay.ctor_functions(None), Err(OverlayCtorError::CtorCompressed { .. })));
+
+    let overlay = overlay_with_ctors(&[0x2100200]);
+    let mut info = overlay.info().clone();
+    info.ctor_end = BASE_ADDRESS + 0x100;
+    let overlay = Overlay::new(overlay.full_data().to_vec(), info.clone(), false);
+    assert!(matches!(overlay.ctor_functions(None), Err(OverlayCtorError::CtorOutOfBounds { .. })));
+
+    info.ctor_start = BASE_ADDRESS + 2;
+    info.ctor_end = BASE_ADDRESS + 0x12;
+    let overlay = Overlay::new(overlay.full_data().to_vec(), info, false);
+    assert!(matches!(overlay.ctor_functions(None), Err(OverlayCtorError::CtorMisaligned { .. })));
+}
+
+#[test]
+fn test_ctor_entry_display() {
+    assert_eq!(CtorEntry::new(0x2100101).to_string(), "0x02100100 (thumb)");
+    assert_eq!(CtorEntry::new(0x2100100).to_string(), "0x02100100 (arm)");
+}
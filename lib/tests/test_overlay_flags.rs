@@ -0,0 +1,71 @@
+use ds_rom::rom::{
+    raw::{self, OverlayFlags},
+    Overlay, OverlayInfo,
+};
+
+#[test]
+fn test_overlay_flags_display() {
+    assert_eq!(OverlayFlags::new().to_string(), "none");
+    assert_eq!(OverlayFlags::new().with_size(0x1234).with_is_compressed(true).to_string(), "compressed (size 0x1234)");
+    assert_eq!(OverlayFlags::new().with_is_signed(true).to_string(), "signed");
+    assert_eq!(
+        OverlayFlags::new().with_size(0x1234).with_is_compressed(true).with_is_signed(true).to_string(),
+        "compressed (size 0x1234), signed"
+    );
+    assert_eq!(OverlayFlags::new().with_size(0x20).to_string(), "size 0x20");
+    assert_eq!(OverlayFlags::new().with_is_signed(true).with_reserved(0x3f).to_string(), "signed, reserved 0x3f");
+}
+
+#[test]
+fn test_overlay_flags_bits() {
+    let flags = OverlayFlags::from(0xfe123456);
+    assert_eq!(flags.size(), 0x123456);
+    assert!(!flags.is_compressed());
+    assert!(flags.is_signed());
+    assert_eq!(flags.reserved(), 0x3f);
+}
+
+#[test]
+fn test_overlay_flags_serde() {
+    let flags = OverlayFlags::new().with_size(0x1234).with_is_compressed(true).with_reserved(0x2a);
+    let yaml = serde_yml::to_string(&flags).unwrap();
+    assert_eq!(yaml.trim(), "'0xa9001234'");
+
+    let parsed: OverlayFlags = serde_yml::from_str(&yaml).unwrap();
+    assert_eq!(parsed.into_bits(), flags.into_bits());
+
+    let parsed: OverlayFlags = serde_yml::from_str("'03000000'").unwrap();
+    assert!(parsed.is_compressed());
+    assert!(parsed.is_signed());
+}
+
+#[test]
+fn test_overlay_info_compressed_flag() {
+    let raw = raw::Overlay {
+        id: 0,
+        base_addr: 0x2100000,
+        code_size: 0x20,
+        bss_size: 0,
+        ctor_start: 0,
+        ctor_end: 0,
+        file_id: 0,
+        flags: OverlayFlags::new().with_size(0x18).with_is_compressed(true).with_is_signed(true),
+    };
+    let info = OverlayInfo::new(&raw);
+    assert!(info.compressed);
+    assert!(!info.flags.is_compressed());
+    assert_eq!(info.flags.size(), 0x18);
+    assert!(info.flags.is_signed());
+
+    // A compressed flag in the config is ignored in favor of `compressed`
+    let yaml = serde_yml::to_string(&info).unwrap().replace("compressed: true", "compressed: false");
+    assert!(yaml.contains("'0x02000018'"), "{yaml}");
+    let yaml = yaml.replace("'0x02000018'", "'0x03000018'");
+    let info: OverlayInfo = serde_yml::from_str(&yaml).unwrap();
+    assert!(!info.compressed);
+    assert!(!info.flags.is_compressed());
+    assert!(info.flags.is_signed());
+    let overlay = Overlay::new(vec![0u8; 0x20], info, false);
+    assert!(!overlay.build().flags.is_compressed());
+    assert_eq!(overlay.build().flags.size(), 0);
+}
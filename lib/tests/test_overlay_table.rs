@@ -0,0 +1,148 @@
+use ds_rom::{
+    crypto::hmac_sha1::HmacSha1,
+    rom::{
+        raw::{self, FileAlloc, OverlayFlags},
+        Overlay, OverlayInfo, OverlayTable, OverlayTableError,
+    },
+};
+
+fn make_overlay(id: u32) -> Overlay<'static> {
+    let info = OverlayInfo {
+        id,
+        base_address: 0x2100000,
+        code_size: 0x20,
+        bss_size: 0x10,
+        ctor_start: 0x2100010,
+        ctor_end: 0x2100014,
+        file_id: id,
+        compressed: false,
+        flags: OverlayFlags::new().with_is_signed(true),
+    };
+    Overlay::new(vec![0u8; 0x20], info, false)
+}
+
+#[test]
+fn test_overlay_table_round_trip() {
+    let overlays = [make_overlay(0), make_overlay(1)];
+    let table = OverlayTable::from_overlays(&overlays);
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.as_raw_bytes().len(), 2 * size_of::<raw::Overlay>());
+
+    // Copy to an odd offset to make sure parsing doesn't require alignment
+    let mut bytes = vec![0u8];
+    bytes.extend_from_slice(table.as_raw_bytes());
+    let parsed = OverlayTable::from_raw_bytes(&bytes[1..]).unwrap();
+    assert_eq!(parsed.as_raw_bytes(), table.as_raw_bytes());
+    assert_eq!(parsed.overlays()[1].id, 1);
+    assert!(parsed.overlays()[1].flags.is_signed());
+    assert!(parsed.validate(&overlays).is_ok());
+}
+
+#[test]
+fn test_overlay_table_invalid() {
+    let table = OverlayTable::from_overlays(&[make_overlay(0)]);
+    let bytes = table.as_raw_bytes();
+    assert!(matches!(OverlayTable::from_raw_bytes(&bytes[..bytes.len() - 1]), Err(OverlayTableError::RawOverlay { .. })));
+    assert!(matches!(
+        table.validate(&[make_overlay(0), make_overlay(1)]),
+        Err(OverlayTableError::CountMismatch { expected: 2, actual: 1, .. })
+    ));
+}
+
+#[test]
+fn test_overlay_signed_flag_preserved() {
+    let overlay = make_overlay(0);
+    assert!(overlay.is_signed());
+    assert!(overlay.build().flags.is_signed());
+
+    let mut info = overlay.info().clone();
+    info.flags = OverlayFlags::new();
+    assert!(!Overlay::new(vec![0u8; 0x20], info, false).is_signed());
+}
+
+#[test]
+fn test_overlay_file_name() {
+    assert_eq!(Overlay::default_file_name(12, 99), "ov012.bin");
+    assert_eq!(Overlay::default_file_name(12, 1000), "ov0012.bin");
+    assert_eq!(Overlay::default_file_name(1000, 1000), "ov1000.bin");
+
+    let mut overlay = make_overlay(12);
+    assert_eq!(overlay.file_name(), None);
+    overlay.set_file_name(Some("ov012_battle.bin".to_string()));
+    assert_eq!(overlay.to_owned().file_name(), Some("ov012_battle.bin"));
+}
+
+#[test]
+fn test_overlay_table_display_with_fat() {
+    let mut compressed = make_overlay(1).build();
+    compressed.flags = OverlayFlags::new().with_size(0x18).with_is_compressed(true);
+    let mut missing = make_overlay(2).build();
+    missing.file_id = 5;
+    let table = OverlayTable::new(vec![make_overlay(0).build(), compressed, missing]);
+    let fat = [FileAlloc { start: 0x1000, end: 0x1020 }, FileAlloc { start: 0x1200, end: 0x1220 }];
+
+    let display = table.display_with_fat(&fat, 0).to_string();
+    let overlays = display.split("Overlay ").skip(1).collect::<Vec<_>>();
+    assert_eq!(overlays.len(), 3);
+    assert!(overlays[0].starts_with("0:\n  ID ............... : 0\n"), "{display}");
+    assert!(overlays[0].ends_with("  FAT entry ........ : 0x1000..0x1020\n  File length ...... : 0x20 (matches code size)\n"));
+    assert!(overlays[1].contains("  Flags ............ : 0x01000018 (compressed (size 0x18))\n"), "{display}");
+    assert!(overlays[1].ends_with(
+        "  FAT entry ........ : 0x1200..0x1220\n  File length ...... : 0x20 (!) differs from compressed size 0x18\n  \
+         Compressed ratio . : 75.0% of 0x20\n"
+    ));
+    assert!(overlays[2].ends_with("  FAT entry ........ : (!) file ID is outside the FAT of 2 entries\n"), "{display}");
+}
+
+#[test]
+fn test_overlay_table_signature() {
+    let hmac = HmacSha1::new(b"overlay table key");
+    let table = OverlayTable::from_overlays(&[make_overlay(0), make_overlay(1)]);
+    let signature = table.compute_signature(&hmac);
+    assert_eq!(signature, hmac.compute(table.as_raw_bytes()));
+    assert!(table.verify_signature(&hmac, &signature));
+    assert!(!table.verify_signature(&HmacSha1::new(b"another key"), &signature));
+
+    let mut entries = table.overlays().to_vec();
+    entries[1].bss_size += 4;
+    assert!(!OverlayTable::new(entries).verify_signature(&hmac, &signature));
+}
+
+#[test]
+fn test_overlay_table_check_signature() {
+    let hmac = HmacSha1::new(b"overlay table key");
+    let table = OverlayTable::from_overlays(&[make_overlay(0), make_overlay(1)]);
+    let signature = table.compute_signature(&hmac);
+    assert!(table.check_signature(&hmac, &signature).is_ok());
+
+    let error = table.check_signature(&hmac, &[0; 20]).unwrap_err();
+    let OverlayTableError::SignatureMismatch { expected, actual, .. } = error else {
+        panic!("expected a signature mismatch, got {error}");
+    };
+    assert_eq!(expected, signature.iter().map(|b| format!("{b:02x}")).collect::<String>());
+    assert_eq!(actual, "00".repeat(20));
+}
+
+#[test]
+fn test_overlay_signature() {
+    let hmac = HmacSha1::new(b"overlay key");
+    let overlay = make_overlay(0);
+    let signature = overlay.compute_signature(&hmac);
+    assert_eq!(signature, hmac.compute(overlay.full_data()));
+    assert!(overlay.verify_signature(&hmac, &signature));
+    assert!(!make_overlay(1).verify_signature(&HmacSha1::new(b"another key"), &signature));
+}
+
+#[test]
+fn test_overlay_table_edit() {
+    let mut table = OverlayTable::from_overlays(&[make_overlay(0), make_overlay(1)]);
+    table.push(make_overlay(2).build());
+    table.insert(0, make_overlay(7).build());
+    assert_eq!(table.overlays().iter().map(|overlay| overlay.id).collect::<Vec<_>>(), [7, 0, 1, 2]);
+
+    assert_eq!(table.remove(1).map(|overlay| overlay.file_id), Some(1));
+    assert!(table.remove(1).is_none());
+    table.renumber();
+    assert_eq!(table.overlays().iter().map(|overlay| overlay.id).collect::<Vec<_>>(), [0, 1, 2]);
+    assert_eq!(table.overlays().iter().map(|overlay| overlay.file_id).collect::<Vec<_>>(), [7, 0, 2]);
+}
@@ -0,0 +1,337 @@
+mod common;
+
+use std::{fs, mem::offset_of, num::NonZeroUsize};
+
+use anyhow::Result;
+use ds_rom::{
+    crypto::hmac_sha1::HmacSha1,
+    rom::{
+        raw::{self, OverlayFlags},
+        Arm9BuildConfig, Arm9FooterValue, Arm9OverlaySignatures, BuildInfo, Overlay, OverlayEditError, OverlayInfo,
+        OverlayTable, Processor, Rom, RomLoadOptions, RomResignError, RomSaveOptions,
+    },
+};
+
+use crate::common::{assert_crcs, assert_fat_consistent, generate_project, OVERLAYS};
+
+#[test]
+fn test_overlay_table_inside_arm9() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-ovt-inside-arm9");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let ovt_bytes = bytemuck::cast_slice::<_, u8>(build.arm9_overlay_table()?).to_vec();
+
+    // Store a copy of the table inside the ARM9 program, past the part which is left uncompressed. It's followed by noise,
+    // so that the table ends up in the compressed part instead of the identical prefix.
+    const OVT_OFFSET: usize = 0x6000;
+    let mut arm9 = fs::read(root.join("arm9/arm9.bin"))?;
+    arm9.resize(0x8000, 0);
+    arm9[OVT_OFFSET..OVT_OFFSET + ovt_bytes.len()].copy_from_slice(&ovt_bytes);
+    let mut state = 1u32;
+    for byte in &mut arm9[OVT_OFFSET + ovt_bytes.len()..] {
+        state = state.wrapping_mul(1103515245).wrapping_add(12345);
+        *byte = (state >> 16) as u8;
+    }
+    fs::write(root.join("arm9/arm9.bin"), &arm9)?;
+    let mut arm9_config: Arm9BuildConfig = serde_yml::from_str(&fs::read_to_string(root.join("arm9/arm9.yaml"))?)?;
+    arm9_config.compressed = true;
+    arm9_config.build_info = BuildInfo { bss_start: 0x2008000, bss_end: 0x2009000, ..arm9_config.build_info };
+    fs::write(root.join("arm9/arm9.yaml"), serde_yml::to_string(&arm9_config)?)?;
+
+    let mut data = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?.data().to_vec();
+    let arm9_offset = raw::Rom::new(data.as_slice()).header()?.arm9.offset;
+    let ovt_offset = arm9_offset + OVT_OFFSET as u32;
+    let field = offset_of!(raw::Header, arm9_overlays);
+    data[field..field + 4].copy_from_slice(&ovt_offset.to_le_bytes());
+    assert_ne!(&data[ovt_offset as usize..ovt_offset as usize + ovt_bytes.len()], ovt_bytes);
+    let rom = raw::Rom::new(data);
+
+    let arm9 = rom.arm9()?;
+    assert!(arm9.is_compressed()?);
+    let Err(error) = rom.arm9_overlay_table() else { panic!("expected an error for the table inside the ARM9 program") };
+    assert!(matches!(error, raw::RawOverlayError::InsideCompressedArm9 { offset, .. } if offset == ovt_offset), "{error}");
+
+    // Tables outside the ARM9 program are read from the ROM as usual
+    assert_eq!(bytemuck::cast_slice::<_, u8>(build.arm9_overlay_table_with(&build.arm9()?)?), ovt_bytes);
+
+    let mut decompressed_arm9 = arm9.clone();
+    decompressed_arm9.decompress()?;
+    assert_eq!(bytemuck::cast_slice::<_, u8>(rom.arm9_overlay_table_with(&decompressed_arm9)?), ovt_bytes);
+
+    let extracted = Rom::extract(&rom)?;
+    assert_eq!(extracted.arm9_overlays().len(), 1);
+    assert_eq!(extracted.arm9_overlays()[0].full_data(), &[0x33; 0x20]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_insert_remove_overlay() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-insert-remove-overlay");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+    let first_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&first_build)?;
+    let c_id = rom.files().find_path("c.bin").unwrap();
+
+    // No FAT entry is free, so the files are shifted to make room for the new overlay
+    let info = OverlayInfo {
+        id: 0,
+        base_address: 0x2100000,
+        code_size: 0x10,
+        bss_size: 0x20,
+        ctor_start: 0,
+        ctor_end: 0,
+        file_id: 0,
+        compressed: false,
+        flags: OverlayFlags::new(),
+    };
+    let new_contents = vec![0x77; 0x10];
+    assert_eq!(rom.push_overlay(Processor::Arm9, Overlay::new(new_contents.clone(), info.clone(), false))?, 1);
+    assert_eq!(rom.arm9_overlays()[1].file_id(), 1);
+    assert_eq!(rom.files().num_reserved(), 2);
+    assert_eq!(rom.files().find_path("c.bin"), Some(c_id + 1));
+    rom.save(&extract_path, None)?;
+
+    let second_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_crcs(&second_build)?;
+    let mut second = Rom::extract(&second_build)?;
+    assert_fat_consistent(&second_build, second.files())?;
+    let ovt = second_build.arm9_overlay_table()?;
+    assert_eq!(ovt.iter().map(|overlay| (overlay.id, overlay.file_id)).collect::<Vec<_>>(), [(0, 0), (1, 1)]);
+    let fat = second_build.fat()?;
+    assert_eq!(fat[1].into_file(second_build.data()), new_contents);
+    assert_eq!(second.arm9_overlays()[1].full_data(), new_contents);
+    assert_eq!(second.files().file(c_id + 1).contents(), first_build.fat()?[c_id as usize].into_file(first_build.data()));
+
+    assert!(matches!(
+        second.insert_overlay(Processor::Arm9, 3, Overlay::new(vec![], info.clone(), false), true),
+        Err(OverlayEditError::IndexOutOfRange { index: 3, len: 2, .. })
+    ));
+    assert!(matches!(
+        second.insert_overlay(Processor::Arm9, 0, Overlay::new(vec![], info.clone(), false), false),
+        Err(OverlayEditError::DuplicateId { id: 0, .. })
+    ));
+    assert!(matches!(second.remove_overlay(Processor::Arm9, 2, true), Err(OverlayEditError::OverlayNotFound { id: 2, .. })));
+
+    // Removing the first overlay leaves its FAT entry empty, and renumbers the other
+    let removed = second.remove_overlay(Processor::Arm9, 0, true)?;
+    assert_eq!(removed.file_id(), 0);
+    assert_eq!((second.arm9_overlays()[0].id(), second.arm9_overlays()[0].file_id()), (0, 1));
+    second.save(&extract_path, None)?;
+    let third_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    let mut third = Rom::extract(&third_build)?;
+    assert_fat_consistent(&third_build, third.files())?;
+    let ovt = third_build.arm9_overlay_table()?;
+    assert_eq!(ovt.iter().map(|overlay| (overlay.id, overlay.file_id)).collect::<Vec<_>>(), [(0, 1)]);
+    let fat = third_build.fat()?;
+    assert_eq!(fat[0].start, fat[0].end);
+    assert_eq!(fat[1].into_file(third_build.data()), new_contents);
+    assert_eq!(third.files().find_path("c.bin"), Some(c_id + 1));
+
+    // The free FAT entry is reused by the next overlay
+    third.insert_overlay(Processor::Arm9, 0, Overlay::new(vec![0x88; 0x10], info, false), true)?;
+    assert_eq!(third.arm9_overlays().iter().map(|overlay| overlay.file_id()).collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(third.files().num_reserved(), 2);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_placeholder_overlay() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-placeholder-overlay");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+    let first_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&first_build)?;
+
+    // A trailing entry with every field besides the ID set to zero, like the padding some linkers add
+    let info = OverlayInfo {
+        id: 0,
+        base_address: 0,
+        code_size: 0,
+        bss_size: 0,
+        ctor_start: 0,
+        ctor_end: 0,
+        file_id: 0,
+        compressed: false,
+        flags: OverlayFlags::new(),
+    };
+    assert_eq!(rom.push_overlay(Processor::Arm9, Overlay::placeholder(info))?, 1);
+    assert_eq!(rom.files().num_reserved(), 1);
+    let second_build = rom.build(None)?;
+    let ovt = second_build.arm9_overlay_table()?;
+    assert!(!ovt[0].is_placeholder());
+    assert!(ovt[1].is_placeholder());
+    assert_eq!(ovt[1].id, 1);
+    let fat = second_build.fat()?;
+    assert_eq!(fat[0].into_file(second_build.data()), first_build.fat()?[0].into_file(first_build.data()));
+
+    let second = Rom::extract(&second_build)?;
+    assert_eq!(second.arm9_overlays().len(), 2);
+    assert!(second.arm9_overlays()[1].is_placeholder());
+    assert!(second.arm9_overlays()[1].full_data().is_empty());
+    second.save(&extract_path, None)?;
+    let overlays_config = fs::read_to_string(extract_path.join("arm9_overlays/overlays.yaml"))?;
+    assert!(overlays_config.contains("placeholder: true"));
+    let mut overlay_files = fs::read_dir(extract_path.join("arm9_overlays"))?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    overlay_files.sort();
+    assert_eq!(overlay_files, ["ov000.bin", "overlays.yaml"]);
+
+    let third_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(third_build.data(), second_build.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_overlay_signatures_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-overlay-signatures-round-trip");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let overlays = OVERLAYS.replace("file_name: ov000.bin", "flags: '0x02000000', file_name: ov000.bin");
+    fs::write(root.join("arm9_overlays/overlays.yaml"), overlays)?;
+    // The signature table is in the ITCM, which is placed after the 0x1000 bytes of main code
+    let overlay_signatures =
+        Arm9OverlaySignatures { footer_value: Arm9FooterValue::RawOffset, autoload: Some(0x1ff8000), offset: 0x8 };
+    let arm9_config_path = root.join("arm9/arm9.yaml");
+    let mut arm9_config: Arm9BuildConfig = serde_yml::from_str(&fs::read_to_string(&arm9_config_path)?)?;
+    arm9_config.offsets.overlay_signatures = Some(overlay_signatures);
+    fs::write(&arm9_config_path, serde_yml::to_string(&arm9_config)?)?;
+
+    let built = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(built.arm9_footer()?.reserved, 0x1008);
+
+    let extracted = Rom::extract(&built)?;
+    let config = extracted.arm9_build_config()?;
+    assert_eq!(config.offsets.overlay_signatures, Some(overlay_signatures));
+    assert_eq!(config.footer.reserved, 0);
+
+    let rebuilt = extracted.build(None)?;
+    assert_eq!(rebuilt.data(), built.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_resign_arm9_overlays() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-resign-arm9-overlays");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let overlays = OVERLAYS.replace("file_name: ov000.bin", "flags: '0x02000000', file_name: ov000.bin");
+    fs::write(root.join("arm9_overlays/overlays.yaml"), overlays)?;
+    // The HMAC key is in the uncompressed part of the ARM9 program, and the signature table in the compressed part after
+    // the first 0x4000 bytes
+    let key = [0x5a; 0x40];
+    let mut arm9 = fs::read(root.join("arm9/arm9.bin"))?;
+    arm9.resize(0x5000, 0);
+    arm9[0x200..0x240].copy_from_slice(&key);
+    fs::write(root.join("arm9/arm9.bin"), arm9)?;
+    let arm9_config_path = root.join("arm9/arm9.yaml");
+    let mut arm9_config: Arm9BuildConfig = serde_yml::from_str(&fs::read_to_string(&arm9_config_path)?)?;
+    arm9_config.offsets.overlay_signatures =
+        Some(Arm9OverlaySignatures { footer_value: Arm9FooterValue::RawOffset, autoload: None, offset: 0x4800 });
+    arm9_config.compressed = true;
+    arm9_config.build_info.bss_start = 0x2005000;
+    arm9_config.build_info.bss_end = 0x2006000;
+    fs::write(&arm9_config_path, serde_yml::to_string(&arm9_config)?)?;
+
+    // Returns the overlay signature table of a built ROM
+    let signature_slots = |rom: &raw::Rom| -> Result<Vec<[u8; 0x14]>> {
+        let mut arm9 = Rom::extract(rom)?.arm9().to_owned();
+        assert!(arm9.is_compressed()?);
+        arm9.decompress()?;
+        Ok(arm9.overlay_signature_slots(2)?.unwrap().to_vec())
+    };
+
+    let built = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&built)?;
+    assert_eq!(rom.arm9_overlay_hmac_sha1_key(&[])?, None);
+    let hmac = HmacSha1::new(&key);
+    assert!(matches!(rom.resign_arm9_overlays(&hmac, &[5], &[]), Err(RomResignError::ResignOverlayNotFound { id: 5, .. })));
+    assert_eq!(rom.resign_arm9_overlays(&hmac, &[], &[])?, [0]);
+    let table_signature = OverlayTable::from_overlays(rom.arm9_overlays()).compute_signature(&hmac);
+    let resigned = rom.build(None)?;
+    let overlay_range = resigned.fat()?[0].range();
+    let slots = signature_slots(&resigned)?;
+    assert_eq!(slots, [hmac.compute(&resigned.data()[overlay_range.clone()]), table_signature]);
+    assert_eq!(Rom::extract(&resigned)?.arm9_overlay_hmac_sha1_key(&[])?, Some(key));
+
+    // Edit the overlay in place, then sign it again with the key found in the ARM9 program
+    let mut edited = resigned.data().to_vec();
+    edited[overlay_range.start] ^= 0xff;
+    let edited = raw::Rom::new(edited);
+    let mut rom = Rom::extract(&edited)?;
+    let key = rom.arm9_overlay_hmac_sha1_key(&[])?.unwrap();
+    assert_eq!(rom.resign_arm9_overlays(&HmacSha1::new(&key), &[0], &[])?, [0]);
+    let output = rom.build(None)?;
+    assert_eq!(output.data()[overlay_range.clone()], edited.data()[overlay_range.clone()]);
+    let slots = signature_slots(&output)?;
+    assert_eq!(slots, [hmac.compute(&edited.data()[overlay_range]), table_signature]);
+    assert!(Rom::extract(&output)?.arm9_overlays()[0].verify_signature(&hmac, &slots[0]));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_thread_count_does_not_change_output() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-thread-count");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let project_path = root.join("project");
+    generate_project(&project_path)?;
+    let mut overlays = String::new();
+    for id in 0..6u32 {
+        overlays += &format!(
+            "- {{ id: {id}, base_address: 0x2100000, code_size: 0x400, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: \
+             {id}, compressed: true, file_name: ov{id:03}.bin }}\n"
+        );
+        let data = (0..0x400u32).map(|i| ((i / 8) * (id + 1)) as u8).collect::<Vec<_>>();
+        fs::write(project_path.join(format!("arm9_overlays/ov{id:03}.bin")), data)?;
+    }
+    fs::write(project_path.join("arm9_overlays/overlays.yaml"), overlays)?;
+
+    // Overlays are compressed when loading and decompressed when saving, on as many threads as requested
+    let mut builds = vec![];
+    for threads in [1, 4] {
+        let concurrency = NonZeroUsize::new(threads);
+        let rom = Rom::load(project_path.join("config.yaml"), RomLoadOptions { concurrency, ..Default::default() })?;
+        assert!(rom.arm9_overlays().iter().all(|overlay| overlay.is_compressed()));
+        let build = rom.build(None)?;
+
+        let extract_path = root.join(format!("extract_{threads}"));
+        Rom::extract(&build)?.save_with_options(&extract_path, RomSaveOptions { concurrency, ..Default::default() })?;
+        for id in 0..6 {
+            let file_name = format!("arm9_overlays/ov{id:03}.bin");
+            assert_eq!(fs::read(extract_path.join(&file_name))?, fs::read(project_path.join(&file_name))?);
+        }
+        builds.push(build.data().to_vec());
+    }
+    assert!(builds[0] == builds[1], "building with 1 and 4 threads gave different ROMs");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use ds_rom::rom::{
+    raw::{self, OverlayFlags},
+    Arm7, Arm7Offsets, FileSource, Overlay, OverlayInfo,
+};
+
+fn arm7_offsets() -> Arm7Offsets {
+    Arm7Offsets { base_address: 0x2380000, entry_function: 0x2380000, build_info: 0, autoload_callback: 0 }
+}
+
+#[test]
+fn test_into_owned_outlives_buffer() {
+    let arm7 = {
+        let buffer = vec![1u8, 2, 3, 4];
+        let arm7 = Arm7::new(buffer.as_slice(), arm7_offsets());
+        assert_eq!(arm7.source(), FileSource::Borrowed);
+        arm7.into_owned()
+    };
+    assert_eq!(arm7.source(), FileSource::Owned);
+    assert_eq!(arm7.full_data(), [1, 2, 3, 4]);
+
+    let raw_rom = {
+        let buffer = vec![5u8; 0x10];
+        raw::Rom::new(buffer.as_slice()).to_owned()
+    };
+    assert_eq!(raw_rom.data(), [5; 0x10]);
+}
+
+#[test]
+fn test_into_owned_moves_owned_data() {
+    let data = vec![0u8; 0x100];
+    let ptr = data.as_ptr();
+    let arm7 = Arm7::new(data, arm7_offsets()).into_owned();
+    assert_eq!(arm7.full_data().as_ptr(), ptr);
+
+    let data = vec![0u8; 0x100];
+    let ptr = data.as_ptr();
+    let info = OverlayInfo {
+        id: 3,
+        base_address: 0x2100000,
+        code_size: 0x100,
+        bss_size: 0,
+        ctor_start: 0,
+        ctor_end: 0,
+        file_id: 3,
+        compressed: false,
+        flags: OverlayFlags::new(),
+    };
+    let overlay = Overlay::new(data, info, false).into_owned();
+    assert_eq!(overlay.full_data().as_ptr(), ptr);
+    assert_eq!(overlay.id(), 3);
+
+    let copy = overlay.to_owned();
+    assert_ne!(copy.full_data().as_ptr(), ptr);
+    assert_eq!(copy.full_data(), overlay.full_data());
+
+    let data = vec![0u8; 0x10];
+    let ptr = data.as_ptr();
+    let raw_rom = raw::Rom::new(data).into_owned();
+    assert_eq!(raw_rom.data().as_ptr(), ptr);
+}
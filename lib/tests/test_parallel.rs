@@ -0,0 +1,135 @@
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use ds_rom::parallel::ThreadPool;
+
+fn pool(concurrency: usize) -> ThreadPool {
+    ThreadPool::new(NonZeroUsize::new(concurrency)).unwrap()
+}
+
+fn max_active_threads(concurrency: usize) -> usize {
+    let active = AtomicUsize::new(0);
+    let max_active = AtomicUsize::new(0);
+    let mut items = vec![0u32; 8];
+    pool(concurrency)
+        .try_for_each(&mut items, |_| {
+            let now_active = active.fetch_add(1, Ordering::SeqCst) + 1;
+            max_active.fetch_max(now_active, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            active.fetch_sub(1, Ordering::SeqCst);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    max_active.load(Ordering::SeqCst)
+}
+
+#[test]
+fn test_concurrency_limit() {
+    assert_eq!(max_active_threads(1), 1);
+    assert!(max_active_threads(2) <= 2);
+    assert!(max_active_threads(4) <= 4);
+}
+
+#[test]
+fn test_single_thread_stays_on_caller() {
+    let caller = thread::current().id();
+    let threads = Mutex::new(vec![]);
+    let mut items = vec![0u32; 4];
+    pool(1)
+        .try_for_each(&mut items, |_| {
+            threads.lock().unwrap().push(thread::current().id());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    assert!(threads.into_inner().unwrap().iter().all(|id| *id == caller));
+}
+
+#[test]
+fn test_same_output_for_any_concurrency() {
+    let expected = (0..100u32).map(|i| i * 3 + 1).collect::<Vec<_>>();
+    for concurrency in [1, 2, 3, 8, 200] {
+        let mut items = (0..100u32).collect::<Vec<_>>();
+        pool(concurrency)
+            .try_for_each(&mut items, |item| {
+                *item = *item * 3 + 1;
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        assert_eq!(items, expected, "concurrency {concurrency}");
+    }
+}
+
+#[test]
+fn test_first_error_in_item_order() {
+    for concurrency in [1, 4] {
+        let mut items = (0..16u32).collect::<Vec<_>>();
+        let result = pool(concurrency).try_for_each(&mut items, |item| match *item {
+            5 | 12 => Err(*item),
+            _ => Ok(()),
+        });
+        assert_eq!(result, Err(5), "concurrency {concurrency}");
+    }
+}
+
+#[test]
+fn test_slow_item_does_not_hold_up_others() {
+    let threads = Mutex::new(vec![None; 8]);
+    let mut items = (0..8usize).collect::<Vec<_>>();
+    pool(2)
+        .try_for_each(&mut items, |item| {
+            if *item == 0 {
+                thread::sleep(Duration::from_millis(100));
+            }
+            threads.lock().unwrap()[*item] = Some(thread::current().id());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    // While one thread is busy with the slow first item, the other one processes all of the remaining items
+    let threads = threads.into_inner().unwrap();
+    assert!(threads[1..].iter().all(|id| *id != threads[0]));
+}
+
+#[test]
+fn test_pool_reused_across_stages() {
+    let caller = thread::current().id();
+    let pool = pool(2);
+    let threads = Mutex::new(HashSet::new());
+    for _ in 0..3 {
+        let mut items = vec![0u32; 8];
+        pool.try_for_each(&mut items, |_| {
+            threads.lock().unwrap().insert(thread::current().id());
+            thread::sleep(Duration::from_millis(5));
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    }
+    // Every stage runs on the same two worker threads instead of spawning new ones
+    let threads = threads.into_inner().unwrap();
+    assert!(threads.len() <= 2);
+    assert!(!threads.contains(&caller));
+}
+
+#[test]
+fn test_stop_taking_items_after_error() {
+    let processed = AtomicUsize::new(0);
+    let mut items = (0..64u32).collect::<Vec<_>>();
+    let result = pool(2).try_for_each(&mut items, |item| {
+        processed.fetch_add(1, Ordering::SeqCst);
+        if *item == 0 {
+            return Err(*item);
+        }
+        thread::sleep(Duration::from_millis(20));
+        Ok(())
+    });
+    assert_eq!(result, Err(0));
+    // The other thread finishes the item it took alongside the failing one, but takes no more after that
+    assert!(processed.load(Ordering::SeqCst) < 8, "{} items were processed", processed.load(Ordering::SeqCst));
+}
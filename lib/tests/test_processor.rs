@@ -0,0 +1,16 @@
+use ds_rom::rom::Processor;
+
+#[test]
+fn test_processor_names() {
+    assert_eq!(Processor::Arm9.to_string(), "ARM9");
+    assert_eq!(Processor::Arm7.to_string(), "ARM7");
+    assert_eq!(Processor::Arm9.as_str(), "arm9");
+    assert_eq!(Processor::Arm7.as_str(), "arm7");
+    assert_eq!(format!("{} overlay {}", Processor::Arm7, 3), "ARM7 overlay 3");
+}
+
+#[test]
+fn test_processor_serde() {
+    assert_eq!(serde_yml::to_string(&Processor::Arm9).unwrap().trim(), "arm9");
+    assert_eq!(serde_yml::from_str::<Processor>("arm7").unwrap(), Processor::Arm7);
+}
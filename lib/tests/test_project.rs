@@ -0,0 +1,64 @@
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::rom::project::{self, ProjectIssue};
+
+const CONFIG: &str = "
+padding_value: 255
+header: header.yaml
+header_logo: header_logo.png
+arm9_bin: arm9/arm9.bin
+arm9_config: arm9/arm9.yaml
+arm7_bin: arm7/arm7.bin
+arm7_config: arm7/arm7.yaml
+itcm: { bin: arm9/itcm.bin, config: arm9/itcm.yaml }
+dtcm: { bin: arm9/dtcm.bin, config: arm9/dtcm.yaml }
+arm9_overlays: arm9_overlays/overlays.yaml
+arm7_overlays: null
+banner: banner/banner.yaml
+files_dir: files/
+path_order: path_order.txt
+";
+
+const OVERLAYS: &str = "
+- { id: 0, base_address: 0x2100000, code_size: 4, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: 0, compressed: false, file_name: ov000.bin }
+- { id: 1, base_address: 0x2100000, code_size: 4, bss_size: 0, ctor_start: 0, ctor_end: 0, file_id: 1, compressed: false, file_name: ov001.bin }
+";
+
+#[test]
+fn test_project_check_and_fix() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-project");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    fs::create_dir_all(root.join("arm9_overlays"))?;
+    fs::create_dir_all(root.join("files"))?;
+    fs::write(root.join("config.yaml"), CONFIG)?;
+    fs::write(root.join("arm9_overlays/overlays.yaml"), OVERLAYS)?;
+    fs::write(root.join("arm9_overlays/ov000.bin"), [0; 4])?;
+    fs::write(root.join("arm9_overlays/ov002.bin"), [0; 4])?;
+    fs::write(root.join("files/a.bin"), [0])?;
+    fs::write(root.join("path_order.txt"), "/a.bin\n/missing.bin\n")?;
+
+    let config_path = root.join("config.yaml");
+    let issues = project::check(&config_path);
+    let messages = issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>();
+
+    assert!(issues.iter().any(|issue| matches!(issue, ProjectIssue::MissingFile { path, .. } if path.ends_with("arm9.bin"))));
+    assert!(issues.iter().any(|issue| matches!(issue, ProjectIssue::MissingOverlayFile { id: 1, .. })));
+    assert!(issues
+        .iter()
+        .any(|issue| matches!(issue, ProjectIssue::UnlistedOverlayFile { path, .. } if path.ends_with("ov002.bin"))));
+    assert!(issues.iter().any(|issue| matches!(issue, ProjectIssue::UnresolvedPathOrder { line: 2, .. })));
+    assert!(!issues.iter().any(|issue| matches!(issue, ProjectIssue::UnresolvedPathOrder { line: 1, .. })));
+    assert!(messages.iter().any(|message| message == "path_order.txt:2: path '/missing.bin' does not exist"), "{messages:?}");
+
+    assert_eq!(project::fix(&config_path, &issues)?, 2);
+    assert_eq!(fs::read_to_string(root.join("path_order.txt"))?, "/a.bin\n");
+
+    let issues = project::check(&config_path);
+    assert!(!issues.iter().any(|issue| issue.is_fixable()));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::rom::{Rom, Schema, SchemaKind, SchemaType};
+
+use crate::common::generate_project;
+
+fn assert_matches_schema(value: &serde_yml::Value, schema: &Schema, path: &str) {
+    use serde_yml::Value;
+
+    if !schema.any_of.is_empty() {
+        let matches = schema.any_of.iter().any(|option| match (option.kind, value) {
+            (Some(SchemaType::Null), Value::Null) => true,
+            (Some(SchemaType::Null), _) | (_, Value::Null) => false,
+            _ => {
+                assert_matches_schema(value, option, path);
+                true
+            }
+        });
+        assert!(matches, "{path}: {value:?} matches none of the schemas");
+        return;
+    }
+    match (schema.kind, value) {
+        (Some(SchemaType::Object), Value::Mapping(mapping)) => {
+            for name in &schema.required {
+                assert!(mapping.contains_key(name.as_str()), "{path}: missing required property {name}");
+            }
+            for (key, value) in mapping {
+                let name = key.as_str().unwrap();
+                let property = schema.get(name).unwrap_or_else(|| panic!("{path}: property {name} is not in the schema"));
+                assert_matches_schema(value, property, &format!("{path}.{name}"));
+            }
+        }
+        (Some(SchemaType::Array), Value::Sequence(items)) => {
+            let item_schema = schema.items.as_deref().unwrap();
+            for (index, item) in items.iter().enumerate() {
+                assert_matches_schema(item, item_schema, &format!("{path}[{index}]"));
+            }
+            if let Some(max_items) = schema.max_items {
+                assert!(items.len() <= max_items, "{path}: more than {max_items} items");
+            }
+        }
+        (Some(SchemaType::String), Value::String(_))
+        | (Some(SchemaType::Integer), Value::Number(_))
+        | (Some(SchemaType::Boolean), Value::Bool(_)) => {}
+        (kind, value) => panic!("{path}: expected {kind:?}, found {value:?}"),
+    }
+}
+
+#[test]
+fn test_schema_matches_saved_project() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-schema-matches-saved-project");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+
+    let build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    Rom::extract(&build)?.save(&extract_path, None)?;
+
+    for (kind, file) in [
+        (SchemaKind::Config, "config.yaml"),
+        (SchemaKind::Arm9, "arm9/arm9.yaml"),
+        (SchemaKind::Overlays, "arm9_overlays/overlays.yaml"),
+        (SchemaKind::Banner, "banner/banner.yaml"),
+        (SchemaKind::Header, "header.yaml"),
+    ] {
+        for project in [&original_path, &extract_path] {
+            let path = project.join(file);
+            let value: serde_yml::Value = serde_yml::from_str(&fs::read_to_string(&path)?)?;
+            assert_matches_schema(&value, &kind.schema(), &path.display().to_string());
+        }
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
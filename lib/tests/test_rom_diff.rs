@@ -0,0 +1,110 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::rom::{Rom, RomDiff, RomDiffSectionKind};
+
+use crate::common::generate_project;
+
+#[test]
+fn test_rom_diff() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-rom-diff");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    let diff = RomDiff::compute(&original, &original, 16)?;
+    assert!(diff.identical);
+    assert_eq!(diff.differing().count(), 0);
+    assert!(diff.sections.iter().any(|section| section.name == "data/b.bin"));
+    assert!(diff.sections.iter().any(|section| section.name == "ARM9 overlay 0"));
+
+    // Same size, so nothing else moves
+    let mut b = vec![0xbb; 0x30];
+    b[0x4..0x6].fill(0);
+    b[0x20] = 0;
+    fs::write(root.join("files/data/b.bin"), &b)?;
+    let other = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    let diff = RomDiff::compute(&original, &other, 16)?;
+    assert!(!diff.identical && !diff.truncated);
+    let differing: Vec<_> = diff.differing().collect();
+    assert_eq!(differing.len(), 1);
+    assert_eq!(differing[0].name, "data/b.bin");
+    assert_eq!(differing[0].mismatches, [0x4..0x6, 0x20..0x21]);
+    assert!(differing[0].sha1.is_some());
+    assert!(diff.sections.iter().filter(|section| section.matches).all(|section| section.sha1.is_none()));
+    let text = diff.to_string();
+    assert!(text.contains("\nFAT: matches"), "{text}");
+    assert!(text.contains("\ndata/b.bin: differs, SHA-1 "), "{text}");
+    assert!(text.contains("\n  0x4..0x6\n  0x20..0x21"), "{text}");
+
+    let diff = RomDiff::compute(&original, &other, 1)?;
+    assert!(diff.truncated);
+    assert_eq!(diff.differing().next().unwrap().mismatches, [0x4..0x6]);
+
+    // Files are matched by path even if they move
+    fs::write(root.join("files/data/b.bin"), [0xbb; 0x40])?;
+    let other = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let diff = RomDiff::compute(&original, &other, 16)?;
+    let names: Vec<_> = diff.differing().map(|section| section.name.as_str()).collect();
+    assert_eq!(names, ["FAT", "data/b.bin"]);
+    assert!(diff.to_string().contains("\ndata/b.bin: differs, size 0x30 -> 0x40"));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_rom_comparison() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-rom-comparison");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    let comparison = Rom::compare(&original, &original)?;
+    assert!(comparison.identical());
+    assert!(comparison.header_fields.is_empty());
+    assert!(comparison.display_brief().to_string().starts_with("ROMs are identical"));
+
+    // Grows b.bin, which moves c.bin and changes the FAT and ROM size
+    fs::write(root.join("files/data/b.bin"), [0xbb; 0x400])?;
+    let other = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let comparison = Rom::compare(&original, &other)?;
+    assert!(!comparison.identical());
+
+    let field_names: Vec<_> = comparison.header_fields.iter().map(|field| field.name.as_str()).collect();
+    assert!(field_names.contains(&"rom_size_ds"), "{field_names:?}");
+    assert!(field_names.contains(&"header_crc"), "{field_names:?}");
+    let rom_size = comparison.header_fields.iter().find(|field| field.name == "rom_size_ds").unwrap();
+    assert_eq!((rom_size.offset, rom_size.size), (0x80, 4));
+    assert!(rom_size.values.is_some());
+
+    let b = comparison.diff.sections.iter().find(|section| section.name == "data/b.bin").unwrap();
+    assert_eq!(b.kind, RomDiffSectionKind::File);
+    assert_eq!(b.first_mismatch, Some(0x30));
+    let fat = comparison.diff.sections.iter().find(|section| section.name == "FAT").unwrap();
+    assert_eq!(fat.kind, RomDiffSectionKind::Section);
+    assert!(fat.first_mismatch.is_some());
+    assert!(comparison.diff.sections.iter().any(|section| section.kind == RomDiffSectionKind::Overlay && section.matches));
+
+    let brief = comparison.display_brief().to_string();
+    assert!(brief.contains("\nARM9 program: matches"), "{brief}");
+    assert!(brief.contains("\nFAT: differs at "), "{brief}");
+    assert!(brief.contains("\nFiles: 2 of 3 match\n  data/b.bin: differs at 0x30"), "{brief}");
+    let text = comparison.to_string();
+    assert!(text.contains("\nHeader fields:\n  "), "{text}");
+
+    let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&comparison)?)?;
+    assert_eq!(json["identical"], false);
+    assert!(json["header_fields"].as_array().unwrap().iter().any(|field| field["name"] == "rom_size_ds"));
+    assert!(json["sections"].as_array().unwrap().iter().any(|section| section["kind"] == "overlay"));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,531 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use ds_rom::{
+    compress::lz10::Lz10,
+    crypto::sha1::Sha1,
+    io::FileError,
+    rom::{
+        raw::{self, FileAlloc, Language},
+        AliasedFile, AliasedSection, CompressionFormat, FileEditError, FilePathError, FileSource, FileSystem,
+        PreservationLevel, Rom, RomBuildError, RomExtractOptions, RomLoadOptions, RomPathOrderError, RomSaveOptions,
+        TransparentCompression,
+    },
+};
+
+use crate::common::{assert_crcs, assert_fat_consistent, generate_project, CONFIG};
+
+#[test]
+fn test_aliased_file() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-aliased-file");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+
+    // Point the FAT entry of c.bin at the banner, like some games do
+    let build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    let c_id = Rom::extract(&build)?.files().find_path("c.bin").unwrap();
+    let header = build.header()?;
+    let banner_size = build.banner()?.unwrap().full_data().len() as u32;
+    let banner_alloc = FileAlloc { start: header.banner_offset, end: header.banner_offset + banner_size };
+    let entry = header.file_allocs.offset as usize + c_id as usize * size_of::<FileAlloc>();
+    let mut data = build.data().to_vec();
+    data[entry..entry + size_of::<FileAlloc>()].copy_from_slice(bytemuck::bytes_of(&banner_alloc));
+    let aliased_build = raw::Rom::new(data);
+
+    let first = Rom::extract(&aliased_build)?;
+    let aliased_file = AliasedFile { path: "c.bin".to_string(), section: AliasedSection::Banner, size: banner_size };
+    assert_eq!(first.aliased_files(), [aliased_file]);
+    first.save(&extract_path, None)?;
+    assert!(!extract_path.join("files/c.bin").exists());
+    assert!(extract_path.join("aliased_files.yaml").exists());
+    let issues = ds_rom::rom::project::check(&extract_path.join("config.yaml"));
+    assert!(issues.is_empty(), "{}", issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>().join("\n"));
+
+    let banner_path = extract_path.join("banner/banner.yaml");
+    let mut banner: serde_yml::Value = serde_yml::from_str(&fs::read_to_string(&banner_path)?)?;
+    banner["title"]["english"] = "Modified title".into();
+    fs::write(&banner_path, serde_yml::to_string(&banner)?)?;
+
+    // The rebuilt FAT entry follows the new banner instead of keeping a stale copy
+    let second_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    let second = Rom::extract(&second_build)?;
+    assert_eq!(second.files().find_path("c.bin"), Some(c_id));
+    assert_eq!(second.files().file(c_id).contents(), second_build.banner()?.unwrap().full_data());
+    assert_eq!(second.aliased_files().len(), 1);
+    let title = second_build.banner()?.unwrap().title(Language::English).unwrap().to_string();
+    assert!(title.starts_with("Modified title"), "{title}");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_transparent_compression() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-transparent-compression");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    let original_path = root.join("original");
+    let extract_path = root.join("extract");
+    generate_project(&original_path)?;
+
+    // c.bin is compressed the same way ds-rom compresses it, b.bin is stored as literals which ds-rom won't reproduce
+    let c_contents = b"abcabcabcabcabcabc".repeat(4);
+    let c_compressed = Lz10 {}.compress(&c_contents);
+    fs::write(original_path.join("files/c.bin"), &c_compressed)?;
+    let b_contents = b"literal literal literal".to_vec();
+    let mut b_compressed = vec![0x10, b_contents.len() as u8, 0, 0];
+    for chunk in b_contents.chunks(8) {
+        b_compressed.push(0);
+        b_compressed.extend_from_slice(chunk);
+    }
+    fs::write(original_path.join("files/data/b.bin"), &b_compressed)?;
+
+    let first_build = Rom::load(original_path.join("config.yaml"), Default::default())?.build(None)?;
+    let first = Rom::extract(&first_build)?;
+    let transparent_compression =
+        vec![TransparentCompression { glob: "*.bin".to_string(), format: CompressionFormat::Lz10 }, TransparentCompression {
+            glob: "data/a.bin".to_string(),
+            format: CompressionFormat::Lz10,
+        }];
+    first.save_with_options(&extract_path, RomSaveOptions { transparent_compression, ..Default::default() })?;
+
+    // a.bin is not compressed, so it's saved as-is
+    assert_eq!(fs::read(extract_path.join("files/data/a.bin"))?, [0xaa; 0x10]);
+    assert_eq!(fs::read(extract_path.join("files/c.bin"))?, c_contents);
+    assert_eq!(fs::read(extract_path.join("files/data/b.bin"))?, b_contents);
+    assert!(!extract_path.join("compressed_originals/c.bin").exists());
+    assert_eq!(fs::read(extract_path.join("compressed_originals/data/b.bin"))?, b_compressed);
+
+    // Hashes are saved as hexadecimal strings, but lists of bytes from older versions are still accepted
+    let records_path = extract_path.join("compressed_files.yaml");
+    let records = fs::read_to_string(&records_path)?;
+    let b_sha1 = Sha1::digest(&b_compressed);
+    let b_sha1_hex = b_sha1.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+    let b_record = serde_yml::from_str::<serde_yml::Value>(&records)?[1].clone();
+    assert_eq!(b_record["sha1"].as_str(), Some(b_sha1_hex.as_str()), "{records}");
+    let old_records = records
+        .lines()
+        .map(|line| if line.contains(&b_sha1_hex) { format!("  sha1: {b_sha1:?}") } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n");
+    fs::write(&records_path, old_records)?;
+    let loaded = Rom::load(extract_path.join("config.yaml"), Default::default())?;
+    assert_eq!(loaded.files().file(loaded.files().find_path("data/b.bin").unwrap()).contents(), b_compressed);
+    fs::write(&records_path, records)?;
+
+    // Unmodified files are rebuilt byte for byte
+    let second_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    let second = Rom::extract(&second_build)?;
+    let contents = |rom: &Rom, path: &str| rom.files().file(rom.files().find_path(path).unwrap()).contents().to_vec();
+    assert_eq!(contents(&second, "c.bin"), *c_compressed);
+    assert_eq!(contents(&second, "data/b.bin"), b_compressed);
+    assert_eq!(contents(&second, "data/a.bin"), [0xaa; 0x10]);
+
+    // Modified files are compressed again
+    fs::write(extract_path.join("files/data/b.bin"), b"modified")?;
+    let third_build = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    let third = Rom::extract(&third_build)?;
+    assert_eq!(*Lz10 {}.decompress(&contents(&third, "data/b.bin"))?, *b"modified");
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_empty_fnt() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-empty-fnt");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::remove_dir_all(root.join("files"))?;
+    fs::create_dir(root.join("files"))?;
+    fs::write(root.join("path_order.txt"), "")?;
+    let mut original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    // Code-only carts have a zero-sized FNT, leaving only padding where the root directory would be
+    let header = original.header_mut()?;
+    let fnt = header.file_names;
+    header.file_names.size = 0;
+    header.update_crcs();
+    let mut data = original.data().to_vec();
+    data[fnt.offset as usize..(fnt.offset + fnt.size) as usize].fill(0xff);
+    let original = raw::Rom::new(data);
+    assert_eq!(original.fnt()?.subtables.len(), 0);
+
+    let rom = Rom::extract(&original)?;
+    assert!(rom.files().is_empty());
+    assert!(rom.config().empty_fnt);
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.header()?.file_names.size, 0);
+    assert!(rebuilt.data() == original.data());
+
+    // Adding a file brings back the root directory
+    fs::write(extract_path.join("files/new.bin"), [1, 2, 3])?;
+    fs::write(extract_path.join("path_order.txt"), "/new.bin\n")?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert!(rebuilt.header()?.file_names.size > 0);
+    assert!(rebuilt.fnt()?.subtables.len() == 1);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_unsorted_fnt_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-unsorted-fnt-round-trip");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    // Directories before files and names in reverse, unlike the default FNT order
+    fs::write(root.join("fnt_order.txt"), "/data\n/data/b.bin\n/data/a.bin\n/c.bin\n")?;
+    fs::write(root.join("config.yaml"), format!("{CONFIG}fnt_order: fnt_order.txt\n"))?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let original_fnt = original.fnt()?.subtables[0].iter().map(|file| file.name.to_string()).collect::<Vec<_>>();
+    assert_eq!(original_fnt, ["data", "c.bin"]);
+
+    let rom = Rom::extract(&original)?;
+    assert!(rom.config().fnt_order.is_some());
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    let header = rebuilt.header()?;
+    let fnt_range = header.file_names.offset as usize..(header.file_names.offset + header.file_names.size) as usize;
+    assert_eq!(rebuilt.data()[fnt_range.clone()], original.data()[fnt_range]);
+    assert_eq!(rebuilt.data(), original.data());
+
+    // Without the FNT order, the default sorting is used
+    let config_path = extract_path.join("config.yaml");
+    let config = fs::read_to_string(&config_path)?;
+    let config = config.lines().filter(|line| !line.starts_with("fnt_order:")).collect::<Vec<_>>();
+    fs::write(&config_path, config.join("\n"))?;
+    let sorted = Rom::load(&config_path, Default::default())?.build(None)?;
+    let sorted_fnt = sorted.fnt()?.subtables[0].iter().map(|file| file.name.to_string()).collect::<Vec<_>>();
+    assert_eq!(sorted_fnt, ["c.bin", "data"]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_empty_file_order_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-empty-file-order-round-trip");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&build)?;
+    let files = rom.files_mut();
+    files.add_file("data/d.bin", vec![0xdd; 0x18])?;
+    // An empty file shares its offset with the next file, so extracting must not place it after that file
+    files.add_file("data/empty.bin", vec![])?;
+    let file_order = ["/c.bin", "/data/b.bin", "/data/empty.bin", "/data/a.bin", "/data/d.bin"];
+    let file_order = file_order.iter().map(|path| path.to_string()).collect::<Vec<_>>();
+    rom.set_file_order(Some(file_order.clone()))?;
+    let original = rom.build(None)?;
+
+    let rom = Rom::extract_with_options(&original, RomExtractOptions::preservation(PreservationLevel::Standard))?;
+    assert!(rom.file_order().is_none());
+    assert_eq!(rom.path_order(), file_order);
+    let extract_path = root.join("extract");
+    rom.save(&extract_path, None)?;
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert!(rebuilt.data() == original.data(), "{}", Rom::compare(&original, &rebuilt)?);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_files() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-duplicate-files");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::write(root.join("files/c.bin"), [0xaa; 0x10])?;
+
+    let rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let groups = rom.files().duplicate_report()?;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].paths, ["/c.bin", "/data/a.bin"]);
+    assert_eq!(groups[0].size, 0x10);
+    assert_eq!(groups[0].wasted_bytes(), 0x10);
+    let a_id = rom.files().find_path("data/a.bin").unwrap();
+    let c_id = rom.files().find_path("c.bin").unwrap();
+
+    let separate = rom.build(None)?;
+    let fat = separate.fat()?;
+    assert_ne!(fat[a_id as usize].range(), fat[c_id as usize].range());
+
+    fs::write(root.join("config.yaml"), format!("{CONFIG}dedup_identical_files: true\n"))?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let layout = rom.plan_layout()?;
+    assert_eq!(layout.shared_files, [(c_id, a_id)]);
+    assert_eq!(layout.shared_bytes(), 0x10);
+    assert!(!layout.file_order.contains(&c_id));
+
+    let shared = rom.build(None)?;
+    assert_crcs(&shared)?;
+    let fat = shared.fat()?;
+    assert_eq!(fat[a_id as usize].range(), fat[c_id as usize].range());
+    assert!(shared.header()?.rom_size_ds < separate.header()?.rom_size_ds);
+    let files = ds_rom::rom::FileSystem::parse(&shared.fnt()?, fat, &shared)?;
+    assert_eq!(files.file(c_id).contents(), [0xaa; 0x10]);
+    assert_eq!(files.duplicate_report()?, groups);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_edit_file_system() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-edit-file-system");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&original)?;
+    let num_files = rom.files().file_count();
+
+    let files = rom.files_mut();
+    let aa_id = files.add_file("data/aa.bin", vec![0x11; 0x8])?;
+    assert_eq!(files.find_path("data/aa.bin"), Some(aa_id));
+    assert_eq!(files.find_path("data/a.bin"), Some(aa_id - 1));
+    assert_eq!(files.find_path("data/b.bin"), Some(aa_id + 1));
+    let new_dir = files.add_dir("/new")?;
+    assert_eq!(files.find_path("new"), Some(new_dir));
+    files.add_file("new/x.bin", vec![0x22; 0x20])?;
+    files.add_file("0.bin", vec![0x33; 0x4])?;
+    let removed = files.remove_file("data/b.bin")?;
+    assert_eq!(removed.name(), "b.bin");
+    files.replace_file_contents("c.bin", vec![0x44; 0x30])?;
+    assert_eq!(files.file_count(), num_files + 2);
+    assert_eq!(files.max_file_id() as usize, files.num_reserved() + files.file_count() - 1);
+
+    assert!(matches!(files.add_file("c.bin", vec![]), Err(FileEditError::PathExists { .. })));
+    assert!(matches!(
+        files.add_file("missing/d.bin", vec![]),
+        Err(FileEditError::FilePath { source: FilePathError::PathNotFound { .. } })
+    ));
+    assert!(matches!(files.add_file("c.bin/d.bin", vec![]), Err(FileEditError::ParentIsFile { .. })));
+    assert!(matches!(files.add_dir("data/"), Err(FileEditError::EmptyName { .. })));
+    assert!(matches!(files.remove_file("data"), Err(FileEditError::PathIsDir { .. })));
+    assert!(matches!(
+        files.remove_file("data/b.bin"),
+        Err(FileEditError::FilePath { source: FilePathError::PathNotFound { .. } })
+    ));
+
+    let rebuilt = rom.build(None)?;
+    assert_crcs(&rebuilt)?;
+    let files = FileSystem::parse(&rebuilt.fnt()?, rebuilt.fat()?, &rebuilt)?;
+    assert_fat_consistent(&rebuilt, &files)?;
+    assert_eq!(files.file_count(), num_files + 2);
+    for (path, contents) in [
+        ("0.bin", vec![0x33; 0x4]),
+        ("c.bin", vec![0x44; 0x30]),
+        ("data/a.bin", vec![0xaa; 0x10]),
+        ("data/aa.bin", vec![0x11; 0x8]),
+        ("new/x.bin", vec![0x22; 0x20]),
+    ] {
+        let id = files.find_path(path).unwrap_or_else(|| panic!("{path} is missing"));
+        assert_eq!(files.file(id).contents(), contents, "{path}");
+    }
+    assert!(files.find_path("data/b.bin").is_none());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_fnt_names() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-duplicate-fnt-names");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let build = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    // Rename data/b.bin to a.bin in the FNT, so the data directory has two files with the same name
+    let fnt = build.header()?.file_names;
+    let mut data = build.data().to_vec();
+    let fnt_data = &mut data[fnt.offset as usize..(fnt.offset + fnt.size) as usize];
+    let name_offset = fnt_data.windows(5).position(|window| window == b"b.bin").unwrap();
+    fnt_data[name_offset] = b'a';
+    let original = raw::Rom::new(data);
+
+    let rom = Rom::extract(&original)?;
+    let files = rom.files();
+    assert_eq!(files.find_path("data/a.bin"), None);
+    let error = files.resolve_path("data/a.bin").unwrap_err();
+    let FilePathError::AmbiguousPath { path, ids, .. } = &error else { panic!("{error}") };
+    assert_eq!(path, "data/a.bin");
+    assert_eq!(ids.len(), 2);
+    assert_eq!(ids.iter().map(|&id| files.file(id).contents()[0]).collect::<Vec<_>>(), [0xaa, 0xbb]);
+    assert!(matches!(files.resolve_path("data/b.bin"), Err(FilePathError::PathNotFound { .. })));
+    assert!(files.find_path("c.bin").is_some());
+    assert!(matches!(
+        files.to_owned().remove_file("data/a.bin"),
+        Err(FileEditError::FilePath { source: FilePathError::AmbiguousPath { .. } })
+    ));
+
+    // Files are still built by ID, so the ROM round-trips
+    assert_eq!(rom.build(None)?.data(), original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_lazy_files() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-lazy-files");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::write(root.join("files/c.bin"), [0xaa; 0x10])?;
+    fs::write(root.join("config.yaml"), format!("{CONFIG}dedup_identical_files: true\n"))?;
+    let lazy_options = RomLoadOptions { lazy_files: true, ..Default::default() };
+
+    let mut eager = Rom::load(root.join("config.yaml"), Default::default())?;
+    let mut lazy = Rom::load(root.join("config.yaml"), lazy_options.clone())?;
+    let a_id = lazy.files().find_path("data/a.bin").unwrap();
+    assert_eq!(lazy.files().file(a_id).source(), FileSource::OnDisk);
+    assert_eq!(lazy.files().file(a_id).size(), 0x10);
+    assert_eq!(lazy.files().owned_bytes(), 0);
+    assert_eq!(lazy.files().total_content_size(), eager.files().total_content_size());
+    assert_eq!(lazy.plan_layout()?.shared_files, eager.plan_layout()?.shared_files);
+    assert_eq!(lazy.files().duplicate_report()?, eager.files().duplicate_report()?);
+    assert_eq!(lazy.file_contents("data/a.bin")?.as_deref(), Some([0xaa; 0x10].as_slice()));
+    assert_eq!(lazy.build(None)?.data(), eager.build(None)?.data());
+
+    // Contents can be loaded up front to modify them like an eagerly loaded ROM
+    let mut lazy = Rom::load(root.join("config.yaml"), lazy_options.clone())?;
+    lazy.files_mut().load_contents()?;
+    assert_eq!(lazy.files().file(a_id).source(), FileSource::Owned);
+    assert_eq!(lazy.files().file(a_id).contents(), [0xaa; 0x10]);
+
+    // The layout is planned from the sizes at load time, so files can't change size before building
+    let lazy = Rom::load(root.join("config.yaml"), lazy_options)?;
+    fs::write(root.join("files/data/a.bin"), [0xaa; 0x20])?;
+    let result = lazy.build(None).err();
+    assert!(
+        matches!(
+            result,
+            Some(RomBuildError::File { source: FileError::FileSizeChanged { expected: 0x10, actual: 0x20, .. } })
+        ),
+        "{result:?}"
+    );
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_shift_jis_round_trip() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-shift-jis-round-trip");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::create_dir_all(root.join("files/データ"))?;
+    fs::write(root.join("files/データ/テスト.bin"), [0xdd; 0x4])?;
+    fs::write(root.join("files/ｶﾅ.bin"), [0xee; 0x4])?;
+    fs::write(root.join("path_order.txt"), "/data/a.bin\n/data/b.bin\n/c.bin\n/データ/テスト.bin\n/ｶﾅ.bin\n")?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+
+    let fnt = original.fnt()?;
+    let raw_names = fnt.subtables[0].iter().map(|file| file.raw_name.to_vec()).collect::<Vec<_>>();
+    assert!(raw_names.contains(&b"\xb6\xc5.bin".to_vec()), "{raw_names:02x?}");
+    let files = ds_rom::rom::FileSystem::parse(&fnt, original.fat()?, &original)?;
+    let id = files.find_path("データ/テスト.bin").unwrap();
+    assert_eq!(files.file(id).contents(), [0xdd; 0x4]);
+
+    let extract_path = root.join("extract");
+    Rom::extract(&original)?.save(&extract_path, None)?;
+    assert!(extract_path.join("files/ｶﾅ.bin").is_file());
+    let rebuilt = Rom::load(extract_path.join("config.yaml"), Default::default())?.build(None)?;
+    assert_eq!(rebuilt.data(), original.data());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_rom_file_accessors() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-rom-file-accessors");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    fs::create_dir_all(root.join("files/データ"))?;
+    fs::write(root.join("files/データ/テスト.bin"), [0xdd; 0x4])?;
+    fs::write(root.join("files/ｶﾅ.bin"), [0xee; 0x4])?;
+    fs::write(root.join("path_order.txt"), "/data/a.bin\n/data/b.bin\n/c.bin\n/データ/テスト.bin\n/ｶﾅ.bin\n")?;
+    let original = Rom::load(root.join("config.yaml"), Default::default())?.build(None)?;
+    let mut rom = Rom::extract(&original)?;
+
+    assert_eq!(rom.file_contents("データ/テスト.bin")?.as_deref(), Some([0xdd; 0x4].as_slice()));
+    assert_eq!(rom.file_contents("/ｶﾅ.bin")?.as_deref(), Some([0xee; 0x4].as_slice()));
+    assert_eq!(rom.file_contents("データ")?, None);
+    assert_eq!(rom.file_contents("missing.bin")?, None);
+
+    let paths = rom.iter_files().map(|(path, _)| path).collect::<Vec<_>>();
+    assert_eq!(paths, ["c.bin", "ｶﾅ.bin", "data/a.bin", "data/b.bin", "データ/テスト.bin"]);
+    assert!(paths.contains(&"データ/テスト.bin".to_string()));
+    for (path, file) in rom.iter_files() {
+        assert_eq!(rom.files().find_path(&path), Some(file.id()));
+    }
+
+    let id = rom.set_file_contents("/データ/テスト.bin", vec![0x77; 0x8])?;
+    assert_eq!(rom.files().file(id).contents(), [0x77; 0x8]);
+    assert!(matches!(rom.set_file_contents("データ", vec![]), Err(FileEditError::PathIsDir { .. })));
+    let rebuilt = Rom::extract(&rom.build(None)?)?.file_contents("データ/テスト.bin")?.map(|contents| contents.to_vec());
+    assert_eq!(rebuilt, Some(vec![0x77; 0x8]));
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn test_set_path_order_unresolved() -> Result<()> {
+    let root = std::env::temp_dir().join("ds-rom-test-set-path-order");
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    generate_project(&root)?;
+    let mut rom = Rom::load(root.join("config.yaml"), Default::default())?;
+    let original_order = rom.path_order().to_vec();
+
+    // A path which never existed
+    let error = rom.set_path_order(vec!["/c.bin".into(), "/unknown.bin".into(), "/data".into()]).unwrap_err();
+    assert!(matches!(&error, RomPathOrderError::UnresolvedPaths { paths, .. } if paths == &["/unknown.bin"]));
+    assert_eq!(rom.path_order(), original_order);
+
+    // A path which was removed from the file system, and one inside a missing directory
+    rom.files_mut().remove_file("data/a.bin")?;
+    let error = rom.set_path_order(vec!["/data/a.bin".into(), "/data/b.bin".into(), "/missing/c.bin".into()]).unwrap_err();
+    assert!(
+        matches!(&error, RomPathOrderError::UnresolvedPaths { paths, .. } if paths == &["/data/a.bin", "/missing/c.bin"]),
+        "{error}"
+    );
+    assert_eq!(rom.path_order(), original_order);
+
+    rom.set_path_order(vec!["/data/b.bin".into(), "/c.bin".into()])?;
+    assert_eq!(rom.path_order(), ["/data/b.bin", "/c.bin"]);
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use ds_rom::rom::{SchemaKind, SchemaType};
+
+#[test]
+fn test_schema_golden() -> Result<()> {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    for kind in SchemaKind::ALL {
+        let file_name = format!("schema_{kind}.json");
+        let expected = fs::read_to_string(golden.join(&file_name))?;
+        assert_eq!(kind.schema().to_json(), expected, "{file_name} is outdated, regenerate it with `dsrom schema {kind}`");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_schema_required_properties_exist() {
+    for kind in SchemaKind::ALL {
+        let schema = kind.schema();
+        let object = match schema.kind {
+            Some(SchemaType::Array) => schema.items.as_deref().unwrap(),
+            _ => &schema,
+        };
+        assert_eq!(object.kind, Some(SchemaType::Object), "{kind}");
+        for name in &object.required {
+            assert!(object.get(name).is_some(), "{kind}: required property {name} is not described");
+        }
+    }
+}
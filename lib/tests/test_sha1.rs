@@ -0,0 +1,41 @@
+use ds_rom::crypto::{hmac_sha1::HmacSha1, sha1::Sha1};
+
+fn hex(hash: [u8; 20]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_sha1() {
+    assert_eq!(hex(Sha1::digest(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(hex(Sha1::digest(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        hex(Sha1::digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+
+    let mut sha1 = Sha1::new();
+    for _ in 0..1000 {
+        sha1.update(&[b'a'; 1000]);
+    }
+    assert_eq!(hex(sha1.finish()), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+}
+
+#[test]
+fn test_hmac_sha1() {
+    // Test cases from RFC 2202
+    assert_eq!(hex(HmacSha1::new(&[0x0b; 20]).compute(b"Hi There")), "b617318655057264e28bc0b6fb378c8ef146be00");
+    assert_eq!(
+        hex(HmacSha1::new(b"Jefe").compute(b"what do ya want for nothing?")),
+        "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+    );
+    assert_eq!(hex(HmacSha1::new(&[0xaa; 20]).compute(&[0xdd; 50])), "125d7342b9ac11cd91a39af48aa17b4f63f175d3");
+    assert_eq!(
+        hex(HmacSha1::new(&[0xaa; 80]).compute(b"Test Using Larger Than Block-Size Key - Hash Key First")),
+        "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+    );
+
+    let hmac = HmacSha1::new(b"Jefe");
+    let signature = hmac.compute(b"what do ya want for nothing?");
+    assert!(hmac.verify(b"what do ya want for nothing?", &signature));
+    assert!(!hmac.verify(b"what do ya want for something?", &signature));
+}
@@ -0,0 +1,61 @@
+use std::cmp::Ordering::{self, *};
+
+use ds_rom::rom::sort::{fnt_name_cmp, rom_name_cmp};
+
+#[test]
+fn test_fnt_name_cmp() {
+    let cases: &[(&str, &str, Ordering)] = &[
+        // ASCII is case-insensitive
+        ("abc.bin", "ABC.bin", Equal),
+        ("a.bin", "B.bin", Less),
+        ("Z.bin", "a.bin", Greater),
+        // Uppercase letters are lowercased before comparing, so they sort after '_'
+        ("_x.bin", "A.bin", Less),
+        ("a.bin", "a.bin.bak", Less),
+        // Multibyte Shift-JIS characters sort after ASCII
+        ("ア", "z", Greater),
+        // Hiragana (82 xx) sorts before katakana (83 xx)
+        ("あ", "ア", Less),
+        // Half-width katakana are single bytes above ASCII but below multibyte lead bytes
+        ("ｱ", "z", Greater),
+        ("ｱ", "あ", Greater),
+        // The second byte of コ (83 52) is lowercased to 72, so it sorts like ビ (83 72)
+        ("コ", "ビ", Equal),
+        ("コ", "ゴ", Less),
+        // ッ (83 62) would sort after コ (83 52) without the quirk
+        ("コ", "ッ", Greater),
+        ("コ", "サ", Less),
+    ];
+    for (a, b, expected) in cases {
+        assert_eq!(fnt_name_cmp(a, b, false, false), *expected, "{a} vs {b}");
+        assert_eq!(fnt_name_cmp(b, a, true, true), expected.reverse(), "{b} vs {a}");
+    }
+}
+
+#[test]
+fn test_fnt_name_cmp_files_first() {
+    // Names which only differ by the directory bit in the FNT
+    assert_eq!(fnt_name_cmp("data", "data", false, true), Less);
+    assert_eq!(fnt_name_cmp("data", "data", true, false), Greater);
+    assert_eq!(fnt_name_cmp("data", "data", true, true), Equal);
+    // Files come before directories regardless of name
+    assert_eq!(fnt_name_cmp("z.bin", "a", false, true), Less);
+    assert_eq!(fnt_name_cmp("a", "z.bin", true, false), Greater);
+}
+
+#[test]
+fn test_rom_name_cmp() {
+    let cases: &[(&str, &str, Ordering)] = &[
+        // UTF-8 order is case-sensitive
+        ("abc.bin", "ABC.bin", Greater),
+        ("a.bin", "B.bin", Greater),
+        ("_x.bin", "A.bin", Greater),
+        ("a.bin", "a.bin", Equal),
+        // Multibyte UTF-8 sorts after ASCII, and UTF-8 doesn't have the lowercasing quirk
+        ("ア", "z", Greater),
+        ("コ", "ビ", Less),
+    ];
+    for (a, b, expected) in cases {
+        assert_eq!(rom_name_cmp(a, b), *expected, "{a} vs {b}");
+    }
+}
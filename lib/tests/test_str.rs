@@ -0,0 +1,50 @@
+use ds_rom::str::{AsciiArray, AsciiArrayError, Unicode16Array, Unicode16Error};
+
+#[test]
+fn test_ascii_array_not_ascii() {
+    let Err(error) = "POKéMON".parse::<AsciiArray<12>>() else { panic!("expected an error") };
+    assert!(matches!(error, AsciiArrayError::NotAscii { character: 'é', index: 3, .. }));
+
+    let message = error.to_string();
+    assert!(message.contains("'POKéMON'"), "{message}");
+    assert!(message.contains("'é'"), "{message}");
+    assert!(message.contains("byte 3"), "{message}");
+}
+
+#[test]
+fn test_ascii_array_too_long() {
+    let Err(error) = "ABCDE".parse::<AsciiArray<4>>() else { panic!("expected an error") };
+    assert!(matches!(error, AsciiArrayError::TooLong { length: 5, max_length: 4, .. }));
+
+    let message = error.to_string();
+    assert!(message.contains("'ABCDE'"), "{message}");
+    assert!(message.contains("5 characters"), "{message}");
+    assert!(message.contains("maximum length is 4"), "{message}");
+}
+
+#[test]
+fn test_ascii_array_max_length() {
+    let Ok(array) = "ABCD".parse::<AsciiArray<4>>() else { panic!("expected a valid array") };
+    assert_eq!(array.0, *b"ABCD");
+}
+
+#[test]
+fn test_unicode16_array_too_long() {
+    let Err(error) = "ab😀c".parse::<Unicode16Array<5>>() else { panic!("expected an error") };
+    assert!(matches!(error, Unicode16Error::TooLong { length: 5, max_length: 4, .. }));
+
+    let message = error.to_string();
+    assert!(message.contains("'ab😀c'"), "{message}");
+    assert!(message.contains("5 UTF-16 code units"), "{message}");
+    assert!(message.contains("maximum length is 4"), "{message}");
+
+    // The last code unit is reserved for the null terminator
+    let result = "ab😀".parse::<Unicode16Array<4>>();
+    assert!(matches!(result, Err(Unicode16Error::TooLong { length: 4, max_length: 3, .. })));
+}
+
+#[test]
+fn test_unicode16_array_max_length() {
+    let Ok(array) = "ab😀".parse::<Unicode16Array<5>>() else { panic!("expected a valid array") };
+    assert_eq!(array.0, [0x61, 0x62, 0xd83d, 0xde00, 0]);
+}